@@ -53,6 +53,9 @@ pub enum Commands {
         /// Port to bind to
         #[arg(long, default_value = "3040")]
         port: u16,
+        /// Serve MCP Streamable HTTP on /mcp instead of WebSocket
+        #[arg(long)]
+        http: bool,
     },
     /// Show status
     Status,
@@ -126,8 +129,8 @@ pub enum Commands {
         #[arg(long, conflicts_with_all = ["args", "target", "source", "destination", "new_name", "name", "kind", "scope", "update_comments", "update_markdown_prose", "update_all"])]
         input_file: Option<String>,
 
-        /// Output format (pretty or compact)
-        #[arg(long, default_value = "pretty", value_parser = ["pretty", "compact"])]
+        /// Output format (pretty, compact, or diff to preview a dry run as a unified diff)
+        #[arg(long, default_value = "pretty", value_parser = ["pretty", "compact", "diff"])]
         format: String,
 
         // === Common flags across refactoring tools ===
@@ -180,19 +183,34 @@ pub enum Commands {
         #[arg(long)]
         plan_out: Option<String>,
     },
-    /// Apply a saved refactor plan (JSON) without re-planning
+    /// Apply a saved refactor plan (JSON) or a unified diff without re-planning
     ///
     /// Examples:
     ///   mill apply-plan plan.json
-    ///   mill apply-plan -   # read plan from stdin
+    ///   mill apply-plan changes.patch
+    ///   mill apply-plan -   # read plan or patch from stdin
     ApplyPlan {
-        /// Plan file path (use "-" for stdin)
+        /// Plan or patch file path (use "-" for stdin)
         input: String,
 
         /// Output format (pretty or compact)
         #[arg(long, default_value = "pretty", value_parser = ["pretty", "compact"])]
         format: String,
     },
+    /// Show the audit log of applied write operations
+    ///
+    /// Examples:
+    ///   mill audit --since 24h
+    ///   mill audit --since 2026-10-01 --format json
+    Audit {
+        /// Only show records since a timestamp, a date (YYYY-MM-DD) or an age (e.g. 30m, 12h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Output format (table or json lines)
+        #[arg(long, default_value = "table", value_parser = ["table", "json"])]
+        format: String,
+    },
     /// List all public MCP tools (excludes internal tools)
     Tools {
         /// Output format (table, json, or names-only)
@@ -314,7 +332,11 @@ pub async fn run() {
             crate::run_stdio_mode().await;
             // Lock is automatically released when _lock_guard is dropped
         }
-        Commands::Serve { daemon: _, port } => {
+        Commands::Serve {
+            daemon: _,
+            port,
+            http,
+        } => {
             // Acquire exclusive lock on PID file (prevents multiple instances)
             let _lock_guard = match acquire_pid_lock() {
                 Ok(guard) => guard,
@@ -326,7 +348,11 @@ pub async fn run() {
                 }
             };
 
-            crate::run_websocket_server_with_port(port).await;
+            if http {
+                crate::run_http_server_with_port(port).await;
+            } else {
+                crate::run_websocket_server_with_port(port).await;
+            }
             // Lock is automatically released when _lock_guard is dropped
         }
         Commands::Status => {
@@ -396,6 +422,9 @@ pub async fn run() {
         Commands::ApplyPlan { input, format } => {
             handle_apply_plan_command(&input, &format).await;
         }
+        Commands::Audit { since, format } => {
+            handle_audit_command(since.as_deref(), &format).await;
+        }
         Commands::Tools { format } => {
             handle_tools_command(&format).await;
         }
@@ -1244,7 +1273,7 @@ async fn handle_tool_command(
     use std::io::{self, Read};
 
     // Build arguments from either JSON, file, stdin, or flags
-    let arguments: serde_json::Value =
+    let mut arguments: serde_json::Value =
         if let Some(file_path) = input_file {
             // Read JSON from file
             let json = match std::fs::read_to_string(file_path) {
@@ -1356,6 +1385,18 @@ async fn handle_tool_command(
             }
         };
 
+    // A diff can only be shown for a preview, so ask the tool to render one
+    if format == "diff" {
+        if let Some(args) = arguments.as_object_mut() {
+            let options = args
+                .entry("options")
+                .or_insert_with(|| serde_json::json!({}));
+            if let Some(options) = options.as_object_mut() {
+                options.insert("previewFormat".to_string(), serde_json::json!("diff"));
+            }
+        }
+    }
+
     // Construct MCP request message
     use mill_foundation::core::model::mcp::{McpMessage, McpRequest};
     let params = serde_json::json!({
@@ -1513,7 +1554,7 @@ async fn handle_apply_plan_command(input: &str, format: &str) {
         let mut stdin_content = String::new();
         if let Err(e) = io::stdin().read_to_string(&mut stdin_content) {
             let error = MillError::InvalidRequest {
-                message: format!("Failed to read plan from stdin: {}", e),
+                message: format!("Failed to read plan or patch from stdin: {}", e),
                 parameter: Some("input".to_string()),
             };
             output_error(&error, format);
@@ -1525,7 +1566,7 @@ async fn handle_apply_plan_command(input: &str, format: &str) {
             Ok(content) => content,
             Err(e) => {
                 let error = MillError::InvalidRequest {
-                    message: format!("Failed to read plan file '{}': {}", input, e),
+                    message: format!("Failed to read plan or patch file '{}': {}", input, e),
                     parameter: Some("input".to_string()),
                 };
                 output_error(&error, format);
@@ -1534,6 +1575,17 @@ async fn handle_apply_plan_command(input: &str, format: &str) {
         }
     };
 
+    // Unified diffs are sent as a patch; anything else must be a plan
+    let is_patch = input.ends_with(".patch")
+        || input.ends_with(".diff")
+        || json.starts_with("diff --git ")
+        || json.starts_with("--- ");
+    let json = if is_patch {
+        serde_json::json!({ "patch": json }).to_string()
+    } else {
+        json
+    };
+
     handle_tool_command(
         "apply_plan",
         Some(&json),
@@ -1554,6 +1606,76 @@ async fn handle_apply_plan_command(input: &str, format: &str) {
     .await;
 }
 
+/// Handle the audit command - print recorded write operations
+async fn handle_audit_command(since: Option<&str>, format: &str) {
+    use mill_client::formatting::Formatter;
+    use mill_server::services::audit::{parse_since, AuditLog, AuditOutcome};
+    let fmt = Formatter::new();
+
+    let since = match since
+        .map(|value| parse_since(value, chrono::Utc::now()))
+        .transpose()
+    {
+        Ok(since) => since,
+        Err(e) => {
+            eprintln!("{}", fmt.error(&e.to_string()));
+            process::exit(1);
+        }
+    };
+
+    let config = AppConfig::load().unwrap_or_default();
+    let project_root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let audit_log = AuditLog::new(&project_root, &config.audit);
+    let records = match audit_log.read(since).await {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("{}", fmt.error(&e.to_string()));
+            process::exit(1);
+        }
+    };
+
+    match format {
+        "json" => {
+            for record in &records {
+                println!("{}", serde_json::to_string(record).unwrap());
+            }
+        }
+        _ => {
+            if records.is_empty() {
+                println!(
+                    "{}",
+                    fmt.info(&format!(
+                        "No audit records in {}",
+                        audit_log.path().display()
+                    ))
+                );
+                return;
+            }
+
+            let headers = vec!["Time", "User", "Tool", "Outcome", "Files"];
+            let rows: Vec<Vec<String>> = records
+                .iter()
+                .map(|record| {
+                    let outcome = match record.outcome {
+                        AuditOutcome::Success => "success".to_string(),
+                        AuditOutcome::Error => {
+                            format!("error: {}", record.error.as_deref().unwrap_or("unknown"))
+                        }
+                    };
+                    vec![
+                        record.timestamp.clone(),
+                        record.user_id.clone().unwrap_or_else(|| "-".to_string()),
+                        record.tool.clone(),
+                        outcome,
+                        record.files.join(", "),
+                    ]
+                })
+                .collect();
+            println!("{}", fmt.table(&headers, &rows));
+        }
+    }
+}
+
 /// Handle daemon commands (Unix only)
 #[cfg(unix)]
 async fn handle_daemon_command(command: DaemonCommands) {
@@ -2072,6 +2194,15 @@ fn output_result(result: &serde_json::Value, format: &str) {
         }
     }
 
+    // A previewed diff is printed as is, so it can be saved as a patch
+    if format == "diff" {
+        let content = result.get("content").unwrap_or(result);
+        if let Some(diff) = content.pointer("/changes/diff").and_then(|d| d.as_str()) {
+            print!("{}", diff);
+            return;
+        }
+    }
+
     // Always output full JSON (for programmatic use and non-plans)
     let output = match format {
         "compact" => serde_json::to_string(result).unwrap_or_else(|_| "{}".to_string()),
@@ -13,9 +13,7 @@ use axum::{
 use mill_server::handlers::plugin_dispatcher::PluginDispatcher;
 use mill_server::workspaces::WorkspaceManager;
 use mill_transport::SessionInfo;
-use serde_json::json;
 use std::sync::Arc;
-use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tracing::{debug, error, info};
 
 fn warn_if_fuse_enabled() {
@@ -37,7 +35,9 @@ async fn main() {
 /// Runs the application in stdio mode.
 ///
 /// This mode is used when the application is run from the command line. It
-/// reads messages from stdin and writes responses to stdout.
+/// reads messages from stdin and writes responses to stdout, using
+/// newline-delimited JSON or the `---FRAME---` framing, whichever the client
+/// speaks.
 pub async fn run_stdio_mode() {
     debug!("Initializing stdio mode MCP server");
     debug!(
@@ -53,129 +53,10 @@ pub async fn run_stdio_mode() {
             return;
         }
     };
-    let session_info = SessionInfo::default();
     debug!("Plugin dispatcher initialized successfully");
 
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    let mut reader = BufReader::new(stdin);
-
-    debug!("Starting stdio message loop");
-    loop {
-        let mut line = String::new();
-        match reader.read_line(&mut line).await {
-            Ok(0) => {
-                debug!("EOF received, exiting");
-                break; // EOF
-            }
-            Ok(_) => {
-                let trimmed = line.trim();
-
-                // Skip frame delimiter used by test harness
-                if trimmed == "---FRAME---" {
-                    debug!("Skipping frame delimiter");
-                    continue;
-                }
-
-                // Skip empty lines
-                if trimmed.is_empty() {
-                    continue;
-                }
-
-                debug!(message = %trimmed, "Received message");
-
-                // First parse as raw JSON to extract ID for error responses
-                let raw_message: serde_json::Value = match serde_json::from_str(trimmed) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        error!(error = %e, line = %trimmed, "Failed to parse JSON");
-                        continue;
-                    }
-                };
-                let request_id = raw_message.get("id").cloned();
-
-                // Then parse as proper MCP message
-                match serde_json::from_value(raw_message.clone()) {
-                    Ok(mcp_message) => {
-                        debug!("Parsed MCP message, dispatching");
-
-                        match dispatcher.dispatch(mcp_message, &session_info).await {
-                            Ok(response) => {
-                                let response_json = match serde_json::to_string(&response) {
-                                    Ok(json) => json,
-                                    Err(e) => {
-                                        error!(error = %e, "Failed to serialize response");
-                                        continue;
-                                    }
-                                };
-                                debug!(response = %response_json, "Sending response");
-                                if let Err(e) = stdout.write_all(response_json.as_bytes()).await {
-                                    error!(error = %e, "Error writing to stdout");
-                                    break;
-                                }
-                                if let Err(e) = stdout.write_all(b"\n").await {
-                                    error!(error = %e, "Error writing newline");
-                                    break;
-                                }
-                                // Send frame delimiter for test harness compatibility
-                                if let Err(e) = stdout.write_all(b"---FRAME---\n").await {
-                                    error!(error = %e, "Error writing frame delimiter");
-                                    break;
-                                }
-                                if let Err(e) = stdout.flush().await {
-                                    error!(error = %e, "Error flushing stdout");
-                                    break;
-                                }
-                            }
-                            Err(e) => {
-                                error!(error = %e, "Error dispatching message");
-
-                                // Send JSON-RPC error response back to client
-                                let error_response = json!({
-                                    "jsonrpc": "2.0",
-                                    "id": request_id,
-                                    "error": {
-                                        "code": -32603,
-                                        "message": format!("{}", e)
-                                    }
-                                });
-
-                                if let Ok(response_json) = serde_json::to_string(&error_response) {
-                                    let _ = stdout.write_all(response_json.as_bytes()).await;
-                                    let _ = stdout.write_all(b"\n").await;
-                                    let _ = stdout.write_all(b"---FRAME---\n").await;
-                                    let _ = stdout.flush().await;
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!(error = %e, line = %trimmed, "Failed to parse MCP message");
-
-                        // Send JSON-RPC error response for parse errors
-                        let error_response = json!({
-                            "jsonrpc": "2.0",
-                            "id": request_id,
-                            "error": {
-                                "code": -32700,
-                                "message": format!("Parse error: {}", e)
-                            }
-                        });
-
-                        if let Ok(response_json) = serde_json::to_string(&error_response) {
-                            let _ = stdout.write_all(response_json.as_bytes()).await;
-                            let _ = stdout.write_all(b"\n").await;
-                            let _ = stdout.write_all(b"---FRAME---\n").await;
-                            let _ = stdout.flush().await;
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                error!(error = %e, "Error reading from stdin");
-                break;
-            }
-        }
+    if let Err(e) = mill_transport::start_stdio_server(dispatcher).await {
+        error!(error = %e, "Stdio server failed");
     }
     debug!("Stdio mode exiting");
 }
@@ -353,6 +234,36 @@ pub async fn run_websocket_server_with_port(port: u16) {
     }
 }
 
+/// Runs the MCP Streamable HTTP server on the given port.
+///
+/// TLS and authentication come from `server.tls` and `server.auth` in the
+/// configuration, as for the WebSocket server.
+pub async fn run_http_server_with_port(port: u16) {
+    // Load configuration
+    let mut config = match mill_config::config::AppConfig::load() {
+        Ok(c) => c,
+        Err(e) => {
+            error!(error = %e, "Failed to load configuration");
+            return;
+        }
+    };
+    config.server.port = port;
+
+    // Initialize dispatcher via factory
+    let dispatcher = match dispatcher_factory::create_initialized_dispatcher().await {
+        Ok(d) => d,
+        Err(e) => {
+            error!(error = %e, "Failed to initialize dispatcher");
+            return;
+        }
+    };
+
+    if let Err(e) = mill_transport::start_http_server(Arc::new(config), dispatcher).await {
+        error!(error = %e, "HTTP server failed");
+        eprintln!("❌ ERROR: {}", e);
+    }
+}
+
 async fn ws_handler(
     ws: WebSocketUpgrade,
     State(dispatcher): State<Arc<PluginDispatcher>>,
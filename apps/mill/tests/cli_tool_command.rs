@@ -28,7 +28,10 @@ fn mill_cmd() -> Command {
         );
     }
 
-    Command::new(binary_path)
+    let mut cmd = Command::new(binary_path);
+    // Keep the audit log out of the source tree
+    cmd.env("TYPEMILL__AUDIT__ENABLED", "false");
+    cmd
 }
 
 #[test]
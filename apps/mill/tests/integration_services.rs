@@ -156,6 +156,7 @@ async fn create_test_app_state(project_root: PathBuf) -> Arc<AppState> {
     let plugin_manager = Arc::new(PluginManager::new());
     let workflow_executor = mill_server::services::workflow_executor::DefaultWorkflowExecutor::new(
         plugin_manager.clone(),
+        file_service.clone(),
     );
     let workspace_manager = Arc::new(WorkspaceManager::new());
 
@@ -164,6 +165,9 @@ async fn create_test_app_state(project_root: PathBuf) -> Arc<AppState> {
         file_service,
         planner,
         workflow_executor,
+        symbol_index: Arc::new(mill_handlers::handlers::workspace::SymbolIndex::new(
+            project_root.clone(),
+        )),
         project_root,
         lock_manager,
         operation_queue,
@@ -171,6 +175,7 @@ async fn create_test_app_state(project_root: PathBuf) -> Arc<AppState> {
         workspace_manager,
         language_plugins: mill_handlers::LanguagePluginRegistry::from_registry(plugin_registry),
         lsp_mode: mill_config::config::LspMode::Discover,
+        audit_log: None,
     })
 }
 
@@ -1,7 +1,13 @@
 //! AST caching system for performance optimization
+//!
+//! Entries are kept in memory and, when `cache.persistent` is set, also in a
+//! content-addressed on-disk store (see [`crate::persistent_cache`]) so that
+//! restarts do not re-parse unchanged files.
 
+use crate::persistent_cache::{parser_namespace, ContentKey, EntryKind, PersistentStore};
 use dashmap::DashMap;
 use mill_foundation::protocol::{CacheStats, ImportGraph};
+use mill_plugin_api::{LanguagePlugin, PluginResult, Symbol};
 use std::path::PathBuf;
 use std::time::SystemTime;
 use tracing::{debug, trace};
@@ -37,6 +43,8 @@ pub struct CacheSettings {
     pub ttl_seconds: u64,
     /// Maximum total size in bytes (approximate)
     pub max_size_bytes: u64,
+    /// Directory of the persistent store (`None` keeps the cache in memory only)
+    pub persistent_dir: Option<PathBuf>,
 }
 
 impl CacheSettings {
@@ -78,8 +86,25 @@ impl CacheSettings {
             max_entries,
             ttl_seconds,
             max_size_bytes,
+            persistent_dir: None,
         }
     }
+
+    /// Create cache settings from the `cache` section of the app config
+    ///
+    /// With `persistent: true`, parse results are stored under `cacheDir`,
+    /// defaulting to `$TYPEMILL_CACHE_DIR` or `~/.typemill/cache`.
+    pub fn from_cache_config(config: &mill_config::config::CacheConfig) -> Self {
+        let mut settings =
+            Self::from_config(config.enabled, config.ttl_seconds, config.max_size_bytes);
+        if config.persistent {
+            settings.persistent_dir = config
+                .cache_dir
+                .clone()
+                .or_else(crate::persistent_cache::default_cache_dir);
+        }
+        settings
+    }
 }
 
 impl Default for CacheSettings {
@@ -92,6 +117,7 @@ impl Default for CacheSettings {
             max_entries: 10000,
             ttl_seconds: 3600,                 // 1 hour
             max_size_bytes: 256 * 1024 * 1024, // 256 MB
+            persistent_dir: None,
         }
     }
 }
@@ -105,6 +131,10 @@ pub struct AstCache {
     stats: DashMap<String, u64>,
     /// Cache configuration
     settings: CacheSettings,
+    /// Parsed symbols by parser namespace and content hash
+    symbols: DashMap<ContentKey, Vec<Symbol>>,
+    /// On-disk store, when persistence is enabled
+    persistent: Option<PersistentStore>,
 }
 
 impl AstCache {
@@ -115,10 +145,18 @@ impl AstCache {
 
     /// Create a new AST cache with custom settings
     pub fn with_settings(settings: CacheSettings) -> Self {
+        let persistent = settings
+            .persistent_dir
+            .as_deref()
+            .filter(|_| settings.enabled)
+            .map(PersistentStore::new);
+
         let cache = Self {
             cache: DashMap::new(),
             stats: DashMap::new(),
             settings: settings.clone(),
+            symbols: DashMap::new(),
+            persistent,
         };
 
         // Initialize statistics counters
@@ -127,11 +165,13 @@ impl AstCache {
         cache.stats.insert("invalidations".to_string(), 0);
         cache.stats.insert("inserts".to_string(), 0);
         cache.stats.insert("evictions".to_string(), 0);
+        cache.stats.insert("persistent_hits".to_string(), 0);
 
         debug!(
             enabled = settings.enabled,
             max_entries = settings.max_entries,
             ttl_seconds = settings.ttl_seconds,
+            persistent = cache.persistent.is_some(),
             "AstCache initialized"
         );
         cache
@@ -147,6 +187,114 @@ impl AstCache {
         &self.settings
     }
 
+    /// Check if parse results are persisted to disk
+    pub fn is_persistent(&self) -> bool {
+        self.persistent.is_some()
+    }
+
+    /// Namespace of a plugin's parse results in the persistent store
+    pub fn plugin_namespace(plugin: &dyn LanguagePlugin) -> String {
+        parser_namespace(plugin.metadata().name, plugin.parser_version())
+    }
+
+    /// Get an import graph persisted for this file content
+    pub async fn get_persisted(&self, key: &ContentKey) -> Option<ImportGraph> {
+        let graph = self
+            .persistent
+            .as_ref()?
+            .load(key, EntryKind::ImportGraph)
+            .await?;
+        self.increment_stat("persistent_hits");
+        Some(graph)
+    }
+
+    /// Persist an import graph for this file content (best-effort)
+    pub async fn persist(&self, key: &ContentKey, import_graph: &ImportGraph) {
+        if let Some(store) = &self.persistent {
+            if let Err(e) = store.store(key, EntryKind::ImportGraph, import_graph).await {
+                debug!(error = %e, "Failed to persist import graph");
+            }
+        }
+    }
+
+    /// Get the symbols of `source`, parsing it with `plugin` on a cache miss
+    ///
+    /// Symbols are keyed by content hash, so renamed or copied files share
+    /// one entry, and a changed file never sees stale symbols.
+    pub async fn symbols(
+        &self,
+        plugin: &dyn LanguagePlugin,
+        source: &str,
+    ) -> PluginResult<Vec<Symbol>> {
+        if !self.settings.enabled {
+            return Ok(plugin.parse(source).await?.symbols);
+        }
+
+        let key = ContentKey::for_source(&Self::plugin_namespace(plugin), source);
+        if let Some(symbols) = self.symbols.get(&key) {
+            self.increment_stat("hits");
+            return Ok(symbols.clone());
+        }
+
+        if let Some(store) = &self.persistent {
+            if let Some(symbols) = store.load::<Vec<Symbol>>(&key, EntryKind::Symbols).await {
+                self.increment_stat("persistent_hits");
+                self.insert_symbols(key, symbols.clone());
+                return Ok(symbols);
+            }
+        }
+
+        self.increment_stat("misses");
+        let symbols = plugin.parse(source).await?.symbols;
+        if let Some(store) = &self.persistent {
+            if let Err(e) = store.store(&key, EntryKind::Symbols, &symbols).await {
+                debug!(error = %e, "Failed to persist symbols");
+            }
+        }
+        self.insert_symbols(key, symbols.clone());
+        Ok(symbols)
+    }
+
+    fn insert_symbols(&self, key: ContentKey, symbols: Vec<Symbol>) {
+        if self.symbols.len() >= self.settings.max_entries {
+            let evict_count = (self.settings.max_entries / 10).max(1);
+            let evicted: Vec<ContentKey> = self
+                .symbols
+                .iter()
+                .take(evict_count)
+                .map(|entry| entry.key().clone())
+                .collect();
+            for key in evicted {
+                self.symbols.remove(&key);
+                self.increment_stat("evictions");
+            }
+        }
+        self.symbols.insert(key, symbols);
+        self.increment_stat("inserts");
+    }
+
+    /// Remove persisted entries of older parser versions of `plugins`
+    pub fn prune_persistent(&self, plugins: &[std::sync::Arc<dyn LanguagePlugin>]) -> usize {
+        let Some(store) = &self.persistent else {
+            return 0;
+        };
+        let mut namespaces: Vec<String> = plugins
+            .iter()
+            .map(|plugin| Self::plugin_namespace(plugin.as_ref()))
+            .collect();
+        namespaces.push(crate::parser::fallback_namespace());
+        let removed = store.prune_stale(&namespaces);
+        if removed > 0 {
+            debug!(removed, "Pruned stale persistent cache namespaces");
+        }
+        removed
+    }
+
+    /// Number of lookups answered by the persistent store
+    pub fn persistent_hits(&self) -> u64 {
+        self.get_stat("persistent_hits")
+    }
+
     /// Get a cached import graph if it exists and is still valid
     pub async fn get(&self, file_path: &PathBuf) -> Option<ImportGraph> {
         // Check if cache is enabled
@@ -294,8 +442,9 @@ impl AstCache {
 
     /// Clear all cached entries
     pub fn clear(&self) {
-        let count = self.cache.len();
+        let count = self.cache.len() + self.symbols.len();
         self.cache.clear();
+        self.symbols.clear();
         debug!("Cleared {} cached entries", count);
     }
 
@@ -508,4 +657,69 @@ mod tests {
         assert_eq!(stats.misses, 0);
         assert_eq!(stats.hit_ratio(), 100.0);
     }
+
+    fn persistent_settings(dir: &std::path::Path) -> CacheSettings {
+        CacheSettings {
+            enabled: true,
+            persistent_dir: Some(dir.to_path_buf()),
+            ..CacheSettings::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_persistent_symbols_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugin = mill_lang_rust::RustPlugin::default();
+        let source = "pub fn alpha() {}\npub struct Beta;\n";
+
+        let cache = AstCache::with_settings(persistent_settings(dir.path()));
+        let parsed = cache.symbols(&plugin, source).await.unwrap();
+        assert!(parsed.iter().any(|symbol| symbol.name == "alpha"));
+        assert_eq!(cache.stats().misses, 1);
+
+        // Same process: served from memory
+        assert_eq!(cache.symbols(&plugin, source).await.unwrap(), parsed);
+        assert_eq!(cache.stats().hits, 1);
+
+        // Restart: served from disk without re-parsing
+        let restarted = AstCache::with_settings(persistent_settings(dir.path()));
+        assert_eq!(restarted.symbols(&plugin, source).await.unwrap(), parsed);
+        assert_eq!(restarted.persistent_hits(), 1);
+        assert_eq!(restarted.stats().misses, 0);
+
+        // Changed content is a different entry
+        let changed = restarted
+            .symbols(&plugin, "pub fn gamma() {}\n")
+            .await
+            .unwrap();
+        assert!(changed.iter().all(|symbol| symbol.name != "alpha"));
+        assert_eq!(restarted.stats().misses, 1);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_import_graph() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = ContentKey::for_file("rust@1", std::path::Path::new("src/lib.rs"), "mod a;");
+        let import_graph = ImportGraph {
+            source_file: "src/lib.rs".to_string(),
+            imports: vec![],
+            importers: vec![],
+            metadata: mill_foundation::protocol::ImportGraphMetadata {
+                language: "rust".to_string(),
+                parsed_at: chrono::Utc::now(),
+                parser_version: "1".to_string(),
+                circular_dependencies: vec![],
+                external_dependencies: vec!["serde".to_string()],
+            },
+        };
+
+        let memory_only = AstCache::new();
+        memory_only.persist(&key, &import_graph).await;
+        assert!(memory_only.get_persisted(&key).await.is_none());
+
+        let cache = AstCache::with_settings(persistent_settings(dir.path()));
+        cache.persist(&key, &import_graph).await;
+        let restarted = AstCache::with_settings(persistent_settings(dir.path()));
+        assert_eq!(restarted.get_persisted(&key).await, Some(import_graph));
+    }
 }
@@ -12,6 +12,7 @@ pub mod error;
 pub mod import_updater;
 pub mod package_extractor; // Now language-agnostic using capability-based dispatch
 pub mod parser;
+pub mod persistent_cache;
 pub mod refactoring;
 pub mod transformer;
 
@@ -20,6 +21,7 @@ pub use analyzer::plan_refactor;
 
 // Cache
 pub use cache::{AstCache, CacheKey, CacheSettings, CachedEntry};
+pub use persistent_cache::{ContentKey, EntryKind, PersistentStore};
 
 // Error types
 pub use error::{AstError, AstResult};
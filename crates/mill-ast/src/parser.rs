@@ -7,6 +7,15 @@ use petgraph::graph::NodeIndex;
 use petgraph::{Direction, Graph};
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
+
+/// Version of the fallback import parser in this module
+pub const PARSER_VERSION: &str = "0.3.0-swc";
+
+/// Persistent cache namespace of the fallback import parser
+pub fn fallback_namespace() -> String {
+    crate::persistent_cache::parser_namespace("mill-ast", PARSER_VERSION)
+}
+
 /// Build import graph for a source file
 pub fn build_import_graph(source: &str, path: &Path) -> AstResult<ImportGraph> {
     // Note: Only Rust and TypeScript supported after language reduction
@@ -54,7 +63,7 @@ pub fn build_import_graph(source: &str, path: &Path) -> AstResult<ImportGraph> {
         metadata: ImportGraphMetadata {
             language: language.to_string(),
             parsed_at: chrono::Utc::now(),
-            parser_version: "0.3.0-swc".to_string(),
+            parser_version: PARSER_VERSION.to_string(),
             circular_dependencies: Vec::new(),
             external_dependencies,
         },
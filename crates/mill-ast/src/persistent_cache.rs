@@ -0,0 +1,304 @@
+//! Content-addressed on-disk store for parse results
+//!
+//! Parsed symbols and import graphs are stored under
+//! `<cache_dir>/ast/v<FORMAT_VERSION>/<namespace>/<hh>/<hash>.<kind>.json`.
+//! The namespace names the parser (plugin name and parser version) and the
+//! hash is the SHA-256 of the parsed source, so entries stay valid across
+//! restarts for as long as neither the file nor the parser changes. A parser
+//! version bump moves lookups to a new namespace; the old one is pruned.
+
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{debug, trace};
+
+/// On-disk layout version, bumped when the entry format changes
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Kind of persisted parse result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// `Vec<Symbol>` from `LanguagePlugin::parse`
+    Symbols,
+    /// `ImportGraph` from `LanguagePlugin::analyze_detailed_imports`
+    ImportGraph,
+}
+
+impl EntryKind {
+    fn extension(self) -> &'static str {
+        match self {
+            EntryKind::Symbols => "symbols.json",
+            EntryKind::ImportGraph => "imports.json",
+        }
+    }
+}
+
+/// Key of a persisted entry: parser namespace and content hash
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContentKey {
+    /// Parser namespace, `<parser>@<version>`
+    pub namespace: String,
+    /// Hex SHA-256 of the hashed input
+    pub hash: String,
+}
+
+impl ContentKey {
+    /// Key for results that depend only on the source text (symbols)
+    pub fn for_source(namespace: &str, source: &str) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(source.as_bytes());
+        Self {
+            namespace: namespace.to_string(),
+            hash: format!("{:x}", hasher.finalize()),
+        }
+    }
+
+    /// Key for results that also depend on the file path (import graphs
+    /// record their source file and resolve relative imports against it)
+    pub fn for_file(namespace: &str, path: &Path, source: &str) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update([0u8]);
+        hasher.update(source.as_bytes());
+        Self {
+            namespace: namespace.to_string(),
+            hash: format!("{:x}", hasher.finalize()),
+        }
+    }
+}
+
+/// Namespace for a parser name and version, safe to use as a directory name
+pub fn parser_namespace(parser: &str, version: &str) -> String {
+    format!("{}@{}", sanitize(parser), sanitize(version))
+}
+
+fn sanitize(part: &str) -> String {
+    part.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Default cache directory: `$TYPEMILL_CACHE_DIR`, else `~/.typemill/cache`
+pub fn default_cache_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("TYPEMILL_CACHE_DIR") {
+        Some(PathBuf::from(dir))
+    } else if let Ok(home) = std::env::var("HOME") {
+        Some(PathBuf::from(home).join(".typemill").join("cache"))
+    } else {
+        None
+    }
+}
+
+/// Content-addressed store of parse results under a cache directory
+#[derive(Debug)]
+pub struct PersistentStore {
+    /// `<cache_dir>/ast`
+    base: PathBuf,
+    /// `<cache_dir>/ast/v<FORMAT_VERSION>`
+    root: PathBuf,
+    /// Distinguishes temporary files of concurrent writes
+    write_counter: AtomicU64,
+}
+
+impl PersistentStore {
+    /// Create a store under `cache_dir` (created lazily on first write)
+    pub fn new(cache_dir: &Path) -> Self {
+        let base = cache_dir.join("ast");
+        let root = base.join(format!("v{}", FORMAT_VERSION));
+        Self {
+            base,
+            root,
+            write_counter: AtomicU64::new(0),
+        }
+    }
+
+    /// Directory holding the entries of the current format version
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn entry_path(&self, key: &ContentKey, kind: EntryKind) -> PathBuf {
+        let shard = key.hash.get(..2).unwrap_or("00");
+        self.root.join(&key.namespace).join(shard).join(format!(
+            "{}.{}",
+            key.hash,
+            kind.extension()
+        ))
+    }
+
+    /// Load an entry; unreadable or corrupt entries count as missing
+    pub async fn load<T: DeserializeOwned>(&self, key: &ContentKey, kind: EntryKind) -> Option<T> {
+        let path = self.entry_path(key, kind);
+        let data = tokio::fs::read(&path).await.ok()?;
+        match serde_json::from_slice(&data) {
+            Ok(value) => {
+                trace!(path = %path.display(), "Persistent cache hit");
+                Some(value)
+            }
+            Err(e) => {
+                debug!(path = %path.display(), error = %e, "Discarding corrupt persistent cache entry");
+                let _ = tokio::fs::remove_file(&path).await;
+                None
+            }
+        }
+    }
+
+    /// Store an entry, replacing any previous one atomically
+    pub async fn store<T: Serialize>(
+        &self,
+        key: &ContentKey,
+        kind: EntryKind,
+        value: &T,
+    ) -> std::io::Result<()> {
+        let path = self.entry_path(key, kind);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let data = serde_json::to_vec(value)?;
+        let tmp_path = path.with_extension(format!(
+            "tmp.{}.{}",
+            std::process::id(),
+            self.write_counter.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&tmp_path, data).await?;
+        if let Err(e) = tokio::fs::rename(&tmp_path, &path).await {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(e);
+        }
+        trace!(path = %path.display(), "Persisted parse result");
+        Ok(())
+    }
+
+    /// Remove entries no current parser can read
+    ///
+    /// Drops other format versions, and namespaces of the parsers in
+    /// `current_namespaces` whose version differs. Namespaces of parsers not
+    /// listed are kept, since another build may still use them. Returns the
+    /// number of directories removed.
+    pub fn prune_stale(&self, current_namespaces: &[String]) -> usize {
+        let mut removed = 0;
+        let current_version = format!("v{}", FORMAT_VERSION);
+
+        for dir in subdirectories(&self.base) {
+            if dir.file_name().and_then(|n| n.to_str()) != Some(current_version.as_str())
+                && std::fs::remove_dir_all(&dir).is_ok()
+            {
+                removed += 1;
+            }
+        }
+
+        let parser_of = |namespace: &str| namespace.split('@').next().map(str::to_string);
+        let current_parsers: Vec<String> = current_namespaces
+            .iter()
+            .filter_map(|ns| parser_of(ns))
+            .collect();
+
+        for dir in subdirectories(&self.root) {
+            let Some(namespace) = dir.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let is_stale = !current_namespaces.iter().any(|ns| ns == namespace)
+                && parser_of(namespace).is_some_and(|parser| current_parsers.contains(&parser));
+            if is_stale && std::fs::remove_dir_all(&dir).is_ok() {
+                debug!(namespace = %namespace, "Pruned stale persistent cache namespace");
+                removed += 1;
+            }
+        }
+
+        removed
+    }
+}
+
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_store_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PersistentStore::new(dir.path());
+        let namespace = parser_namespace("TypeScript", "0.1.0-plugin");
+        let key = ContentKey::for_source(&namespace, "export const a = 1;");
+
+        assert!(store
+            .load::<Vec<String>>(&key, EntryKind::Symbols)
+            .await
+            .is_none());
+        store
+            .store(&key, EntryKind::Symbols, &vec!["a".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            store.load::<Vec<String>>(&key, EntryKind::Symbols).await,
+            Some(vec!["a".to_string()])
+        );
+        // Kinds are stored separately
+        assert!(store
+            .load::<Vec<String>>(&key, EntryKind::ImportGraph)
+            .await
+            .is_none());
+
+        // A new store over the same directory sees the entry (warm restart)
+        let reopened = PersistentStore::new(dir.path());
+        assert!(reopened
+            .load::<Vec<String>>(&key, EntryKind::Symbols)
+            .await
+            .is_some());
+    }
+
+    #[test]
+    fn test_keys() {
+        let namespace = parser_namespace("Rust", "1");
+        assert_eq!(namespace, "rust@1");
+        assert_eq!(
+            ContentKey::for_source(&namespace, "fn a() {}"),
+            ContentKey::for_source(&namespace, "fn a() {}")
+        );
+        assert_ne!(
+            ContentKey::for_source(&namespace, "fn a() {}"),
+            ContentKey::for_source(&parser_namespace("Rust", "2"), "fn a() {}")
+        );
+        assert_ne!(
+            ContentKey::for_file(&namespace, Path::new("a.rs"), "fn a() {}"),
+            ContentKey::for_file(&namespace, Path::new("b.rs"), "fn a() {}")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prune_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PersistentStore::new(dir.path());
+        for namespace in ["rust@1", "rust@2", "python@1"] {
+            let key = ContentKey::for_source(namespace, "x");
+            store.store(&key, EntryKind::Symbols, &1).await.unwrap();
+        }
+        std::fs::create_dir_all(dir.path().join("ast").join("v0")).unwrap();
+
+        let removed = store.prune_stale(&["rust@2".to_string()]);
+
+        assert_eq!(removed, 2);
+        assert!(!dir.path().join("ast/v0").exists());
+        assert!(!store.root().join("rust@1").exists());
+        assert!(store.root().join("rust@2").exists());
+        assert!(store.root().join("python@1").exists());
+    }
+}
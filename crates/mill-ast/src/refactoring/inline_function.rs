@@ -0,0 +1,60 @@
+use crate::error::{AstError, AstResult};
+use mill_foundation::protocol::EditPlan;
+use tracing::debug;
+
+/// Generate edit plan for inlining a function
+///
+/// Inlining is implemented by language plugins only: LSP servers either lack
+/// an inline-function code action or only offer it for the call under the
+/// cursor, without the `inline_all` semantics.
+pub async fn plan_inline_function(
+    source: &str,
+    line: u32,
+    character: u32,
+    inline_all: bool,
+    file_path: &str,
+    language_plugins: Option<&mill_plugin_api::PluginDiscovery>,
+) -> AstResult<EditPlan> {
+    if let Some(plugins) = language_plugins {
+        if let Some(provider) = plugins.refactoring_provider_for_file(file_path) {
+            if provider.supports_inline_function() {
+                debug!(
+                    file_path = %file_path,
+                    line,
+                    character,
+                    inline_all,
+                    "Using language plugin for inline function"
+                );
+                return provider
+                    .plan_inline_function(source, line, character, inline_all, file_path)
+                    .await
+                    .map_err(|e| AstError::analysis(e.to_string()));
+            }
+        }
+    }
+
+    Err(AstError::analysis(format!(
+        "Inline function not supported for: {}",
+        file_path
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_plan_inline_function_without_plugins() {
+        let result = plan_inline_function(
+            "fn f() -> u8 { 1 }\nfn g() -> u8 { f() }",
+            1,
+            15,
+            false,
+            "/src/lib.rs",
+            None,
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -11,8 +11,11 @@ pub use mill_lang_common::{
 pub mod common;
 pub mod extract_function;
 pub mod extract_variable;
+pub mod inline_function;
 pub mod inline_variable;
 pub mod move_symbol;
+pub mod reorder;
+pub mod transform;
 
 /// Trait for LSP refactoring service
 ///
@@ -0,0 +1,62 @@
+use crate::error::{AstError, AstResult};
+use mill_foundation::protocol::EditPlan;
+use tracing::debug;
+
+pub use mill_lang_common::refactoring::reorder::{reorder_call_arguments, CallSiteRewrite};
+
+/// Generate edit plan for reordering parameters, fields, variants or imports
+///
+/// Reordering is implemented by language plugins only; LSP servers do not
+/// expose a portable code action for it. Call sites of reordered parameters
+/// are rewritten by the caller using [`reorder_call_arguments`].
+pub async fn plan_reorder(
+    source: &str,
+    kind: &str,
+    line: u32,
+    character: u32,
+    new_order: &[String],
+    file_path: &str,
+    language_plugins: Option<&mill_plugin_api::PluginDiscovery>,
+) -> AstResult<EditPlan> {
+    if let Some(plugins) = language_plugins {
+        if let Some(provider) = plugins.refactoring_provider_for_file(file_path) {
+            if provider.supports_reorder(kind) {
+                debug!(
+                    file_path = %file_path,
+                    kind = %kind,
+                    "Using language plugin for reorder"
+                );
+                return provider
+                    .plan_reorder(source, kind, line, character, new_order, file_path)
+                    .await
+                    .map_err(|e| AstError::analysis(e.to_string()));
+            }
+        }
+    }
+
+    Err(AstError::analysis(format!(
+        "Reorder '{}' not supported for: {}",
+        kind, file_path
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_plan_reorder_without_plugins() {
+        let result = plan_reorder(
+            "fn f(a: u8, b: u8) {}",
+            "parameters",
+            0,
+            3,
+            &["b".to_string(), "a".to_string()],
+            "/src/lib.rs",
+            None,
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use super::{CodeRange, LspRefactoringService};
+use crate::error::{AstError, AstResult};
+use mill_foundation::protocol::EditPlan;
+use tracing::debug;
+
+/// Map a transform kind to the LSP code action kinds that may provide it
+fn lsp_code_action_kinds(kind: &str) -> Vec<String> {
+    match kind {
+        "to_async" => vec!["refactor.rewrite.function.async".to_string()],
+        "function_to_arrow" | "arrow_to_function" => {
+            vec!["refactor.rewrite.arrow".to_string()]
+        }
+        _ => vec!["refactor.rewrite".to_string()],
+    }
+}
+
+/// LSP-based code transformation
+async fn lsp_transform(
+    lsp_service: &dyn LspRefactoringService,
+    file_path: &str,
+    kind: &str,
+    line: u32,
+    character: u32,
+) -> AstResult<EditPlan> {
+    debug!(
+        file_path = %file_path,
+        kind = %kind,
+        line = line,
+        character = character,
+        "Requesting LSP transform refactoring"
+    );
+
+    let range = CodeRange {
+        start_line: line,
+        start_col: character,
+        end_line: line,
+        end_col: character + 1,
+    };
+
+    let kinds = lsp_code_action_kinds(kind);
+    let actions = lsp_service
+        .get_code_actions(file_path, &range, Some(kinds.clone()))
+        .await?;
+
+    let action = actions
+        .as_array()
+        .and_then(|arr| {
+            arr.iter().find(|a| {
+                a.get("kind")
+                    .and_then(|k| k.as_str())
+                    .map(|k| kinds.iter().any(|wanted| k.starts_with(wanted.as_str())))
+                    .unwrap_or(false)
+            })
+        })
+        .ok_or_else(|| AstError::analysis(format!("LSP server returned no '{}' actions", kind)))?;
+
+    let workspace_edit = action
+        .get("edit")
+        .ok_or_else(|| AstError::analysis("Code action missing edit field".to_string()))?;
+
+    mill_foundation::protocol::EditPlan::from_lsp_workspace_edit(workspace_edit, file_path, kind)
+        .map_err(|e| AstError::analysis(format!("Failed to convert LSP edit: {}", e)))
+}
+
+/// Generate edit plan for a code transformation
+///
+/// This function implements a plugin-first approach:
+/// 1. If the language plugin supports the transform kind, use it
+/// 2. If LSP service is provided, try LSP rewrite code actions
+pub async fn plan_transform(
+    source: &str,
+    kind: &str,
+    line: u32,
+    character: u32,
+    file_path: &str,
+    lsp_service: Option<&dyn LspRefactoringService>,
+    language_plugins: Option<&mill_plugin_api::PluginDiscovery>,
+) -> AstResult<EditPlan> {
+    // Keep the most specific plugin error so callers see why the transform was refused
+    let mut plugin_error = None;
+
+    if let Some(plugins) = language_plugins {
+        if let Some(provider) = plugins.refactoring_provider_for_file(file_path) {
+            if provider.supports_transform(kind) {
+                debug!(
+                    file_path = %file_path,
+                    kind = %kind,
+                    "Using language plugin for transform"
+                );
+                match provider
+                    .plan_transform(source, kind, line, character, file_path)
+                    .await
+                {
+                    Ok(plan) => return Ok(plan),
+                    Err(e) => {
+                        debug!(
+                            error = ?e,
+                            file_path = %file_path,
+                            "Language plugin transform failed, trying LSP fallback"
+                        );
+                        plugin_error = Some(e.to_string());
+                    }
+                }
+            } else if !provider.supported_transforms().is_empty() {
+                plugin_error = Some(format!(
+                    "Transform '{}' is not supported for {} (supported: {})",
+                    kind,
+                    file_path,
+                    provider.supported_transforms().join(", ")
+                ));
+            }
+        }
+    }
+
+    if let Some(lsp) = lsp_service {
+        match lsp_transform(lsp, file_path, kind, line, character).await {
+            Ok(plan) => return Ok(plan),
+            Err(e) => {
+                debug!(
+                    error = %e,
+                    file_path = %file_path,
+                    "LSP transform also failed"
+                );
+            }
+        }
+    }
+
+    match plugin_error {
+        Some(message) => Err(AstError::analysis(message)),
+        None => Err(AstError::analysis(format!(
+            "Transform '{}' not supported for: {}. Neither language plugin nor LSP implementation succeeded.",
+            kind, file_path
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    struct MockLspRefactoringService {
+        actions: Vec<serde_json::Value>,
+    }
+
+    #[async_trait]
+    impl LspRefactoringService for MockLspRefactoringService {
+        async fn get_code_actions(
+            &self,
+            _file_path: &str,
+            _range: &CodeRange,
+            _kinds: Option<Vec<String>>,
+        ) -> AstResult<serde_json::Value> {
+            Ok(serde_json::Value::Array(self.actions.clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan_transform_with_lsp_fallback() {
+        let action = json!({
+            "title": "Convert to async function",
+            "kind": "refactor.rewrite.function.async",
+            "edit": {
+                "changes": {
+                    "file:///src/app.ts": [
+                        {
+                            "range": {
+                                "start": { "line": 0, "character": 0 },
+                                "end": { "line": 0, "character": 0 }
+                            },
+                            "newText": "async "
+                        }
+                    ]
+                }
+            }
+        });
+
+        let mock_service = MockLspRefactoringService {
+            actions: vec![action],
+        };
+
+        let plan = plan_transform(
+            "function load() {}",
+            "to_async",
+            0,
+            0,
+            "/src/app.ts",
+            Some(&mock_service),
+            None,
+        )
+        .await
+        .expect("LSP fallback should produce a plan");
+
+        assert_eq!(plan.edits.len(), 1);
+        assert_eq!(plan.edits[0].new_text, "async ");
+    }
+
+    #[tokio::test]
+    async fn test_plan_transform_names_supported_kinds() {
+        let mut plugins = mill_plugin_api::PluginDiscovery::new();
+        plugins.register(std::sync::Arc::new(mill_lang_rust::RustPlugin::default()));
+
+        let error = plan_transform(
+            "fn load() {}",
+            "function_to_arrow",
+            0,
+            0,
+            "src/lib.rs",
+            None,
+            Some(&plugins),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("'function_to_arrow' is not supported for src/lib.rs (supported: to_async, if_else_to_match)"));
+    }
+
+    #[tokio::test]
+    async fn test_plan_transform_without_providers() {
+        let result = plan_transform(
+            "function load() {}",
+            "to_async",
+            0,
+            0,
+            "/src/app.ts",
+            None,
+            None,
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -23,6 +23,62 @@ pub struct Claims {
     pub project_id: Option<String>,
     /// User ID (custom claim for multi-tenancy)
     pub user_id: Option<String>,
+    /// What the token may do (custom claim); unrestricted when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<TokenScopes>,
+}
+
+/// Access granted to a token
+///
+/// Each field restricts one aspect of access; a missing field leaves that
+/// aspect unrestricted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TokenScopes {
+    /// Granted permissions (`write` implies `read`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Vec<Permission>>,
+    /// Names of the tools the token may call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<String>>,
+    /// Glob patterns, relative to the project root, of the paths the token may touch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paths: Option<Vec<String>>,
+}
+
+/// Kind of access a tool call needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    /// Read code and preview changes
+    Read,
+    /// Modify files
+    Write,
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Permission::Read => write!(f, "read"),
+            Permission::Write => write!(f, "write"),
+        }
+    }
+}
+
+impl TokenScopes {
+    /// Whether the token grants `permission`
+    pub fn grants(&self, permission: Permission) -> bool {
+        self.permissions.as_ref().is_none_or(|granted| {
+            granted.contains(&permission)
+                || (permission == Permission::Read && granted.contains(&Permission::Write))
+        })
+    }
+
+    /// Whether the token may call `tool`
+    pub fn allows_tool(&self, tool: &str) -> bool {
+        self.tools
+            .as_ref()
+            .is_none_or(|tools| tools.iter().any(|allowed| allowed == tool))
+    }
 }
 
 /// Validate a JWT token and return true if valid
@@ -31,6 +87,15 @@ pub fn validate_token(
     secret: &str,
     auth_config: &mill_config::config::AuthConfig,
 ) -> Result<bool, MillError> {
+    decode_token(token, secret, auth_config).map(|_| true)
+}
+
+/// Validate a JWT token and return its claims
+pub fn decode_token(
+    token: &str,
+    secret: &str,
+    auth_config: &mill_config::config::AuthConfig,
+) -> Result<Claims, MillError> {
     let key = DecodingKey::from_secret(secret.as_ref());
     let mut validation = Validation::default();
 
@@ -46,7 +111,7 @@ pub fn validate_token(
     }
 
     decode::<Claims>(token, &key, &validation)
-        .map(|_| true)
+        .map(|token_data| token_data.claims)
         .map_err(|e| MillError::permission_denied(e.to_string()))
 }
 
@@ -101,6 +166,7 @@ pub fn generate_token(
     audience: &str,
     project_id: Option<String>,
     user_id: Option<String>,
+    scopes: Option<TokenScopes>,
 ) -> Result<String, MillError> {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -115,6 +181,7 @@ pub fn generate_token(
         aud: Some(audience.to_string()),
         project_id,
         user_id,
+        scopes,
     };
 
     let header = Header::default();
@@ -138,6 +205,9 @@ mod tests {
             jwt_audience: "codeflow-clients".to_string(),
             validate_audience,
             jwt_audience_override: None,
+            jwt_public_key_path: None,
+            jwt_algorithm: None,
+            jwks_path: None,
         }
     }
 
@@ -155,6 +225,7 @@ mod tests {
             aud: Some("codeflow-clients".to_string()),
             project_id,
             user_id: None,
+            scopes: None,
         };
 
         let header = Header::default();
@@ -214,4 +285,38 @@ mod tests {
                 .expect("Test token should be valid")
         );
     }
+
+    #[test]
+    fn test_generated_token_carries_scopes() {
+        let auth_config = create_test_auth_config(true);
+        let scopes = TokenScopes {
+            permissions: Some(vec![Permission::Read]),
+            tools: Some(vec!["inspect_code".to_string()]),
+            paths: Some(vec!["src/**".to_string()]),
+        };
+        let token = generate_token(
+            "test_secret",
+            3600,
+            "mill",
+            "codeflow-clients",
+            None,
+            None,
+            Some(scopes.clone()),
+        )
+        .unwrap();
+
+        let claims = decode_token(&token, "test_secret", &auth_config).unwrap();
+        assert_eq!(claims.scopes.as_ref(), Some(&scopes));
+        assert!(scopes.grants(Permission::Read));
+        assert!(!scopes.grants(Permission::Write));
+        assert!(scopes.allows_tool("inspect_code"));
+        assert!(!scopes.allows_tool("prune"));
+
+        let write = TokenScopes {
+            permissions: Some(vec![Permission::Write]),
+            ..TokenScopes::default()
+        };
+        assert!(write.grants(Permission::Read));
+        assert!(TokenScopes::default().grants(Permission::Write));
+    }
 }
@@ -1,5 +1,10 @@
 //! Authentication module
 
 pub mod jwt;
+pub mod keys;
 
-pub use jwt::{generate_token, validate_token, validate_token_with_project, Claims};
+pub use jwt::{
+    decode_token, generate_token, validate_token, validate_token_with_project, Claims, Permission,
+    TokenScopes,
+};
+pub use keys::verify_token;
@@ -4,7 +4,7 @@ use mill_foundation::errors::{MillError, MillResult};
 use mill_foundation::validation::ValidationConfig;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Main application configuration
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -23,6 +23,12 @@ pub struct AppConfig {
     /// Git integration configuration
     #[serde(default)]
     pub git: GitConfig,
+    /// Audit log configuration
+    #[serde(default)]
+    pub audit: AuditConfig,
+    /// Operation journal configuration
+    #[serde(default)]
+    pub journal: JournalConfig,
     /// Validation configuration
     #[serde(default)]
     pub validation: ValidationConfig,
@@ -53,6 +59,10 @@ pub struct ServerConfig {
     pub tls: Option<TlsConfig>,
     /// Authentication configuration
     pub auth: Option<AuthConfig>,
+    /// Browser origins (e.g. `https://app.example.com`) that may call the
+    /// HTTP transport, in addition to loopback origins
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
 }
 
 /// TLS configuration
@@ -70,6 +80,8 @@ pub struct TlsConfig {
 #[serde(rename_all = "camelCase")]
 pub struct AuthConfig {
     /// JWT secret for signing tokens
+    /// Not needed when tokens are verified with a public key or JWKS file
+    #[serde(default)]
     pub jwt_secret: String,
     /// JWT expiry in seconds
     pub jwt_expiry_seconds: u64,
@@ -84,6 +96,32 @@ pub struct AuthConfig {
     /// If None, uses jwt_audience field
     #[serde(skip_serializing_if = "Option::is_none")]
     pub jwt_audience_override: Option<String>,
+    /// PEM public key verifying tokens signed by an external issuer
+    /// If set, tokens signed with `jwt_secret` are rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwt_public_key_path: Option<PathBuf>,
+    /// Signing algorithm of the `jwt_public_key_path` key (default: RS256)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwt_algorithm: Option<JwtAlgorithm>,
+    /// JWKS file holding the issuer's public keys, selected by the token's `kid`
+    /// If set, tokens signed with `jwt_secret` are rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwks_path: Option<PathBuf>,
+}
+
+/// Signing algorithm of a JWT public key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum JwtAlgorithm {
+    /// RSA PKCS#1 v1.5 with SHA-256
+    #[default]
+    #[serde(rename = "RS256")]
+    Rs256,
+    /// ECDSA on P-256 with SHA-256
+    #[serde(rename = "ES256")]
+    Es256,
+    /// Ed25519
+    #[serde(rename = "EdDSA")]
+    EdDsa,
 }
 
 /// LSP server configuration
@@ -99,6 +137,14 @@ pub struct LspConfig {
     pub default_timeout_ms: u64,
     /// Enable LSP server preloading
     pub enable_preload: bool,
+    /// Maximum running instances of one LSP server, one per project root.
+    /// Beyond this, the least recently used idle instance is shut down.
+    #[serde(default = "default_max_instances_per_server")]
+    pub max_instances_per_server: usize,
+}
+
+fn default_max_instances_per_server() -> usize {
+    4
 }
 
 /// LSP usage mode
@@ -135,6 +181,63 @@ pub struct LspServerConfig {
     /// These are sent in the initialize request's initializationOptions field
     #[serde(skip_serializing_if = "Option::is_none")]
     pub initialization_options: Option<serde_json::Value>,
+    /// Name used to attribute merged results to this server (optional)
+    /// Defaults to the command's program name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Requests this server answers when several servers share an extension (optional)
+    /// If None, the server takes every request for its extensions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<LspRole>>,
+    /// Settings answered to the server's `workspace/configuration` requests (optional)
+    /// Sections are looked up by path, so `rust-analyzer.cargo.features` may be
+    /// written nested or as a dotted key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<serde_json::Value>,
+}
+
+impl LspServerConfig {
+    /// Name shown next to results from this server
+    pub fn display_name(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        self.command
+            .first()
+            .and_then(|program| Path::new(program).file_name())
+            .map(|program| program.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "lsp".to_string())
+    }
+
+    /// Whether this server answers requests of `role`
+    pub fn has_role(&self, role: LspRole) -> bool {
+        self.roles
+            .as_ref()
+            .is_none_or(|roles| roles.contains(&role))
+    }
+}
+
+/// Kind of request an LSP server answers
+///
+/// Lets cooperating servers split a language, e.g. one for navigation and
+/// another for diagnostics and code actions.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum LspRole {
+    /// Definitions, references, implementations, symbols and call hierarchy
+    Navigation,
+    /// Hover information
+    Hover,
+    /// Completion and signature help
+    Completion,
+    /// Diagnostics
+    Diagnostics,
+    /// Code actions and quick fixes
+    CodeActions,
+    /// Document and range formatting
+    Formatting,
+    /// Symbol and file renames
+    Rename,
 }
 
 /// Plugin selection configuration for multi-tiered priority system
@@ -280,6 +383,30 @@ pub struct GitConfig {
     pub operations: Vec<String>,
 }
 
+/// Audit log configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuditConfig {
+    /// Record every tool call that modifies files
+    pub enabled: bool,
+    /// Path to the audit log, relative to the project root
+    pub path: PathBuf,
+    /// Rotate the audit log when it would grow beyond this size in bytes
+    pub max_size_bytes: u64,
+    /// Number of rotated audit logs to retain
+    pub max_files: usize,
+}
+
+/// Operation journal configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JournalConfig {
+    /// Record applied edit plans so they can be undone
+    pub enabled: bool,
+    /// Operations whose files add up to more bytes than this are not journaled
+    pub max_operation_bytes: u64,
+}
+
 impl Default for ServerConfig {
     fn default() -> Self {
         Self {
@@ -289,6 +416,7 @@ impl Default for ServerConfig {
             timeout_ms: 30000,
             tls: None,
             auth: None,
+            allowed_origins: Vec::new(),
         }
     }
 }
@@ -311,6 +439,9 @@ impl Default for LspConfig {
                     root_dir: None,
                     restart_interval: Some(10),
                     initialization_options: None,
+                    name: None,
+                    roles: None,
+                    settings: None,
                 },
                 LspServerConfig {
                     extensions: vec!["py".to_string()],
@@ -318,6 +449,9 @@ impl Default for LspConfig {
                     root_dir: None,
                     restart_interval: Some(5),
                     initialization_options: None,
+                    name: None,
+                    roles: None,
+                    settings: None,
                 },
                 LspServerConfig {
                     extensions: vec!["go".to_string()],
@@ -325,6 +459,9 @@ impl Default for LspConfig {
                     root_dir: None,
                     restart_interval: Some(10),
                     initialization_options: None,
+                    name: None,
+                    roles: None,
+                    settings: None,
                 },
                 LspServerConfig {
                     extensions: vec!["rs".to_string()],
@@ -332,11 +469,15 @@ impl Default for LspConfig {
                     root_dir: None,
                     restart_interval: Some(15),
                     initialization_options: None,
+                    name: None,
+                    roles: None,
+                    settings: None,
                 },
             ],
             mode: LspMode::Discover,
             default_timeout_ms: 5000,
             enable_preload: true,
+            max_instances_per_server: default_max_instances_per_server(),
         }
     }
 }
@@ -373,6 +514,26 @@ impl Default for GitConfig {
     }
 }
 
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: PathBuf::from(".typemill/audit.jsonl"),
+            max_size_bytes: 10 * 1024 * 1024, // 10 MB
+            max_files: 5,
+        }
+    }
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_operation_bytes: 64 * 1024 * 1024, // 64 MB
+        }
+    }
+}
+
 impl ServerConfig {
     /// Check if host is a loopback address
     ///
@@ -402,6 +563,15 @@ impl AppConfig {
         Ok(())
     }
 
+    /// TOML configuration file `load` reads, if any: the first of
+    /// `mill.toml` and `.typemill/config.toml` that exists
+    pub fn config_file_path() -> Option<PathBuf> {
+        ["mill.toml", ".typemill/config.toml"]
+            .into_iter()
+            .map(PathBuf::from)
+            .find(|path| path.exists())
+    }
+
     /// Load configuration from environment and config files
     ///
     /// Configuration is loaded in the following priority order (highest to lowest):
@@ -432,18 +602,12 @@ impl AppConfig {
         let figment = Figment::from(figment::providers::Serialized::defaults(default_value));
 
         // 2. Load mill.toml if it exists (base configuration)
-        let toml_paths = ["mill.toml", ".typemill/config.toml"];
-
         let mut figment_with_toml = figment;
         let mut toml_found = false;
-        for toml_path in &toml_paths {
-            let path = std::path::Path::new(toml_path);
-            if path.exists() {
-                tracing::info!(path = %toml_path, "Loading TOML configuration");
-                figment_with_toml = figment_with_toml.merge(Toml::file(path));
-                toml_found = true;
-                break; // Use first found TOML file
-            }
+        if let Some(path) = Self::config_file_path() {
+            tracing::info!(path = %path.display(), "Loading TOML configuration");
+            figment_with_toml = figment_with_toml.merge(Toml::file(&path));
+            toml_found = true;
         }
 
         // 3. If TOML was found and environment profile is not "default", merge environment profile
@@ -507,6 +671,22 @@ impl AppConfig {
             return Err(MillError::config("Server timeout cannot be 0"));
         }
 
+        if let Some(auth) = &self.server.auth {
+            if auth.jwt_public_key_path.is_some() && auth.jwks_path.is_some() {
+                return Err(MillError::config(
+                    "Auth config cannot set both jwtPublicKeyPath and jwksPath",
+                ));
+            }
+            if auth.jwt_secret.is_empty()
+                && auth.jwt_public_key_path.is_none()
+                && auth.jwks_path.is_none()
+            {
+                return Err(MillError::config(
+                    "Auth config needs jwtSecret, jwtPublicKeyPath or jwksPath",
+                ));
+            }
+        }
+
         // Validate LSP config
         if self.lsp.mode != LspMode::Off {
             if self.lsp.servers.is_empty() {
@@ -524,6 +704,11 @@ impl AppConfig {
                 if server.command.is_empty() {
                     return Err(MillError::config("LSP server command cannot be empty"));
                 }
+                if server.roles.as_ref().is_some_and(|roles| roles.is_empty()) {
+                    return Err(MillError::config(
+                        "LSP server roles cannot be empty; omit roles to serve every request",
+                    ));
+                }
             }
         }
 
@@ -546,6 +731,11 @@ impl AppConfig {
             ));
         }
 
+        // Validate audit config: rotating with no rotated logs would delete the log
+        if self.audit.max_files == 0 {
+            return Err(MillError::config("Audit maxFiles must be at least 1"));
+        }
+
         Ok(())
     }
 
@@ -571,6 +761,9 @@ impl AppConfig {
             root_dir: None,
             restart_interval: Some(10),
             initialization_options: None,
+            name: None,
+            roles: None,
+            settings: None,
         };
         self.lsp.servers.push(new_server);
 
@@ -692,6 +885,16 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_validate_rejects_audit_without_rotated_logs() {
+        let mut config = AppConfig::default();
+        assert!(config.validate().is_ok());
+
+        config.audit.max_files = 0;
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("maxFiles"));
+    }
+
     #[test]
     fn test_get_server_for_extension() {
         let config = AppConfig::default();
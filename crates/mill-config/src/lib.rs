@@ -9,9 +9,9 @@ pub mod refactor_config;
 
 // Re-export commonly used types at the crate root for convenience
 pub use config::{
-    AppConfig, AuthConfig, CacheConfig, ExternalMcpConfig, ExternalMcpServerConfig,
-    ExternalPluginConfig, FileLoggingConfig, FuseConfig, GitConfig, LanguagePluginsConfig,
-    LogFormat, LoggingConfig, LspConfig, LspMode, LspServerConfig, PluginSelectionConfig,
-    ServerConfig, TlsConfig,
+    AppConfig, AuditConfig, AuthConfig, CacheConfig, ExternalMcpConfig, ExternalMcpServerConfig,
+    ExternalPluginConfig, FileLoggingConfig, FuseConfig, GitConfig, JwtAlgorithm,
+    LanguagePluginsConfig, LogFormat, LoggingConfig, LspConfig, LspMode, LspRole, LspServerConfig,
+    PluginSelectionConfig, ServerConfig, TlsConfig,
 };
 pub use refactor_config::{RefactorConfig, RefactorDefaults, RefactorPreset};
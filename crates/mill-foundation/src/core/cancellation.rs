@@ -0,0 +1,98 @@
+//! Cancelling requests without leaving changes half-applied
+//!
+//! A transport runs each request inside [`Cancellation::scope`] and, when the
+//! client cancels it, calls [`Cancellation::cancel`] before aborting the
+//! request's task. Services call [`begin_apply`] before writing any file; from
+//! then on the request can no longer be cancelled, so its writes, journal
+//! entry and audit record all land.
+//!
+//! Like the progress reporter, the cancellation state is task-local: work
+//! spawned onto other tasks must be scoped as well.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+const RUNNING: u8 = 0;
+const APPLYING: u8 = 1;
+const CANCELLED: u8 = 2;
+
+/// Whether a request may still be cancelled
+#[derive(Clone, Default)]
+pub struct Cancellation {
+    state: Arc<AtomicU8>,
+}
+
+tokio::task_local! {
+    static CURRENT: Cancellation;
+}
+
+impl Cancellation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `future` as the request this cancellation controls
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// Cancel the request unless it has begun applying changes
+    ///
+    /// Returns whether the request is cancelled; only then may its task be
+    /// aborted. A request applying changes runs to completion.
+    pub fn cancel(&self) -> bool {
+        match self
+            .state
+            .compare_exchange(RUNNING, CANCELLED, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => true,
+            Err(state) => state == CANCELLED,
+        }
+    }
+
+    /// Mark the request as applying changes
+    ///
+    /// Returns false if it was cancelled first, in which case nothing may be
+    /// written.
+    pub fn begin_apply(&self) -> bool {
+        match self
+            .state
+            .compare_exchange(RUNNING, APPLYING, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => true,
+            Err(state) => state == APPLYING,
+        }
+    }
+}
+
+/// Mark the current request, if any, as applying changes
+///
+/// Returns false if the request was cancelled, so the caller must not write.
+pub fn begin_apply() -> bool {
+    CURRENT.try_with(Cancellation::begin_apply).unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_applying_requests_cannot_be_cancelled() {
+        let applying = Cancellation::new();
+        applying
+            .clone()
+            .scope(async { assert!(begin_apply()) })
+            .await;
+        assert!(!applying.cancel());
+
+        let cancelled = Cancellation::new();
+        assert!(cancelled.cancel());
+        cancelled
+            .clone()
+            .scope(async { assert!(!begin_apply()) })
+            .await;
+
+        assert!(begin_apply());
+    }
+}
@@ -3,9 +3,13 @@
 //! This crate provides the foundational types and utilities used across
 //! the entire Codeflow Buddy Rust implementation.
 
+pub mod cancellation;
 pub mod dry_run;
+pub mod progress;
 pub mod rename_scope;
+pub mod tool_effects;
 pub mod utils;
+pub mod write_scope;
 
 pub use dry_run::{execute_with_dry_run, DryRunnable};
 
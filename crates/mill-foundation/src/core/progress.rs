@@ -0,0 +1,98 @@
+//! Progress reporting for long-running operations
+//!
+//! Services call [`report`] as they work through files without knowing who is
+//! listening. A caller that wants the updates (the MCP dispatcher, when a tool
+//! call carries `_meta.progressToken`) runs the operation inside
+//! [`ProgressReporter::scope`]; outside a scope, reports are no-ops.
+//!
+//! The reporter is task-local, so work spawned onto other tasks does not
+//! report unless it is scoped as well. Report from the task that collects
+//! results instead.
+
+use std::future::Future;
+use std::sync::Arc;
+
+/// One progress update within the current phase of an operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressUpdate {
+    /// Units of work finished in this phase
+    pub done: u64,
+    /// Units of work in this phase, if known
+    pub total: Option<u64>,
+    /// What is being worked on
+    pub message: String,
+}
+
+type ProgressSink = Arc<dyn Fn(ProgressUpdate) + Send + Sync>;
+
+/// Receives progress updates for the operation it is scoped to
+#[derive(Clone)]
+pub struct ProgressReporter {
+    sink: ProgressSink,
+}
+
+tokio::task_local! {
+    static CURRENT: ProgressReporter;
+}
+
+impl ProgressReporter {
+    pub fn new(sink: impl Fn(ProgressUpdate) + Send + Sync + 'static) -> Self {
+        Self {
+            sink: Arc::new(sink),
+        }
+    }
+
+    /// Run `future` with this reporter receiving its progress updates
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// The reporter of the current scope, if any
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    pub fn report(&self, done: u64, total: Option<u64>, message: impl Into<String>) {
+        (self.sink)(ProgressUpdate {
+            done,
+            total,
+            message: message.into(),
+        });
+    }
+}
+
+/// Report progress to the current scope's reporter, if any
+pub fn report(done: u64, total: Option<u64>, message: impl Into<String>) {
+    let _ = CURRENT.try_with(|reporter| reporter.report(done, total, message));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_reports_reach_scoped_reporter_only() {
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let sink = updates.clone();
+        let reporter = ProgressReporter::new(move |update| sink.lock().unwrap().push(update));
+
+        report(1, Some(2), "outside");
+        reporter
+            .scope(async {
+                report(1, Some(2), "a.rs");
+                assert!(ProgressReporter::current().is_some());
+            })
+            .await;
+        report(2, Some(2), "outside");
+
+        assert_eq!(
+            *updates.lock().unwrap(),
+            vec![ProgressUpdate {
+                done: 1,
+                total: Some(2),
+                message: "a.rs".to_string(),
+            }]
+        );
+    }
+}
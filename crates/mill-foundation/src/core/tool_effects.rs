@@ -0,0 +1,67 @@
+//! Which tool calls may modify files
+//!
+//! Token authorization and the workflow executor both need to tell reads from
+//! writes; they share these lists so that a tool counts as read-only in both.
+
+use serde_json::Value;
+
+/// Tools that never modify files
+pub const READ_ONLY_TOOLS: &[&str] = &[
+    "inspect_code",
+    "search_code",
+    "read_file",
+    "list_files",
+    "cache_status",
+    "get_completions",
+    "get_signature_help",
+    "health_check",
+    "get_lsp_progress",
+    "notify_file_opened",
+    "notify_file_saved",
+    "notify_file_closed",
+];
+
+/// Tools that return their changes as a plan when `options.dryRun` is true
+/// (it defaults to true), and only modify files when it is false
+pub const PLANNING_TOOLS: &[&str] = &["rename_all", "relocate", "prune", "refactor", "workspace"];
+
+/// File tools that only preview their change when `dryRun` is true (it
+/// defaults to false)
+pub const PREVIEWING_FILE_TOOLS: &[&str] =
+    &["rename_file", "create_file", "delete_file", "write_file"];
+
+/// `workspace` actions that never modify files
+pub const READ_ONLY_WORKSPACE_ACTIONS: &[&str] = &["verify_project", "list_operations"];
+
+/// Whether a call to `tool` with these arguments never modifies files,
+/// whatever its `options.dryRun`
+pub fn is_read_only(tool: &str, args: &Value) -> bool {
+    if READ_ONLY_TOOLS.contains(&tool) {
+        return true;
+    }
+    if tool != "workspace" {
+        return false;
+    }
+
+    let action = args.get("action").and_then(Value::as_str);
+    let members_action = args.pointer("/params/action").and_then(Value::as_str);
+    action.is_some_and(|action| READ_ONLY_WORKSPACE_ACTIONS.contains(&action))
+        || (action == Some("update_members") && members_action == Some("list"))
+}
+
+/// Whether `tool` can preview its changes as a plan
+pub fn is_planning_tool(tool: &str) -> bool {
+    PLANNING_TOOLS.contains(&tool)
+}
+
+/// Whether a call to `tool` with these arguments only previews its changes
+pub fn is_preview(tool: &str, args: &Value) -> bool {
+    if is_planning_tool(tool) {
+        return args
+            .pointer("/options/dryRun")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+    }
+    PREVIEWING_FILE_TOOLS.contains(&tool)
+        && args.get("dryRun").and_then(Value::as_bool).unwrap_or(false)
+}
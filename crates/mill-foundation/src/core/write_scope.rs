@@ -0,0 +1,75 @@
+//! Limits on which files an operation may write
+//!
+//! A caller restricted to part of the project (the MCP dispatcher, when the
+//! token carries path scopes) runs the operation inside [`WriteScope::scope`].
+//! Services check every file a change would touch with [`permits`] before
+//! writing any of them; outside a scope, every file is permitted.
+//!
+//! Like the progress reporter, the scope is task-local: work spawned onto
+//! other tasks must be scoped as well.
+
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+
+type PathFilter = Arc<dyn Fn(&Path) -> bool + Send + Sync>;
+
+/// The files the current operation may write
+#[derive(Clone)]
+pub struct WriteScope {
+    filter: PathFilter,
+}
+
+tokio::task_local! {
+    static CURRENT: WriteScope;
+}
+
+impl WriteScope {
+    /// A scope permitting the absolute paths for which `filter` returns true
+    pub fn new(filter: impl Fn(&Path) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            filter: Arc::new(filter),
+        }
+    }
+
+    /// Run `future` with writes limited to this scope
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// The scope of the current task, if any
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    pub fn permits(&self, path: &Path) -> bool {
+        (self.filter)(path)
+    }
+}
+
+/// Whether the current scope, if any, permits writing the absolute `path`
+pub fn permits(path: &Path) -> bool {
+    CURRENT
+        .try_with(|scope| scope.permits(path))
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_only_scoped_writes_are_limited() {
+        let scope = WriteScope::new(|path| path.starts_with("/project/src"));
+
+        assert!(permits(Path::new("/project/lib/a.rs")));
+        scope
+            .scope(async {
+                assert!(permits(Path::new("/project/src/a.rs")));
+                assert!(!permits(Path::new("/project/lib/a.rs")));
+                assert!(WriteScope::current().is_some());
+            })
+            .await;
+        assert!(WriteScope::current().is_none());
+    }
+}
@@ -50,11 +50,18 @@ impl ErrorResponse {
 
 impl From<MillError> for ErrorResponse {
     fn from(err: MillError) -> Self {
+        let details = match &err {
+            MillError::PermissionDenied {
+                required_permission: Some(required_permission),
+                ..
+            } => Some(serde_json::json!({ "requiredPermission": required_permission })),
+            _ => None, // Can be enhanced later
+        };
         ErrorResponse {
             code: err.error_code().to_string(),
             message: err.to_string(),
             category: err.category().to_string(),
-            details: None,    // Can be enhanced later
+            details,
             suggestion: None, // Can be enhanced later
         }
     }
@@ -16,4 +16,4 @@ pub use mcp::{
     MCP_PROTOCOL_VERSION,
 };
 // Re-export workflow
-pub use workflow::{Intent, Step, Workflow, WorkflowMetadata};
+pub use workflow::{Intent, OnError, Step, Workflow, WorkflowMetadata};
@@ -30,6 +30,10 @@ pub struct Workflow {
     pub steps: Vec<Step>,
     /// Metadata about this workflow's complexity and characteristics.
     pub metadata: WorkflowMetadata,
+    /// Whether the plans of all write steps are applied together, atomically,
+    /// once every step has run. Write steps only produce plans until then.
+    #[serde(default)]
+    pub transactional: bool,
 }
 
 /// Represents a single, atomic action within a Workflow.
@@ -46,4 +50,33 @@ pub struct Step {
     /// This is for future interactive workflow support.
     #[serde(default)]
     pub requires_confirmation: Option<bool>,
+    /// Condition on earlier step results; the step is skipped when it is false.
+    ///
+    /// Either a placeholder such as `"$steps.0.locations"`, true when the value
+    /// exists and is not `null`, `false`, `0` or empty (prefix `!` to negate),
+    /// or an object `{ "value": ..., "equals": ... }` / `{ "value": ..., "notEquals": ... }`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<Value>,
+    /// Placeholder resolving to an array; the step runs once per element.
+    ///
+    /// `$item` and `$item.{path}` in `params` and `when` refer to the current
+    /// element, and the step's result is the array of per-element results.
+    #[serde(default, rename = "forEach", skip_serializing_if = "Option::is_none")]
+    pub for_each: Option<String>,
+    /// What to do when the step fails.
+    #[serde(default, rename = "onError")]
+    pub on_error: OnError,
+}
+
+/// Policy for a failing workflow step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OnError {
+    /// Halt the workflow with an error.
+    #[default]
+    Fail,
+    /// Record the error as the step's result and carry on.
+    Continue,
+    /// Retry the step up to the given number of times, then fail.
+    Retry(u32),
 }
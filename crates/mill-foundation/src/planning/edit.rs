@@ -207,6 +207,19 @@ pub struct EditLocation {
     pub end_column: u32,
 }
 
+impl EditLocation {
+    /// Location spanning all of `content`, for edits replacing a whole file
+    pub fn whole_file(content: &str) -> Self {
+        let last_line = content.rsplit('\n').next().unwrap_or_default();
+        Self {
+            start_line: 0,
+            start_column: 0,
+            end_line: content.matches('\n').count() as u32,
+            end_column: last_line.chars().count() as u32,
+        }
+    }
+}
+
 /// Dependency update information
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -102,6 +102,13 @@ pub trait LanguagePluginRegistry: Send + Sync {
     /// Get a plugin that can handle the given manifest file (e.g., Cargo.toml, package.json)
     fn get_plugin_for_manifest(&self, file_path: &Path) -> Option<&dyn LanguagePlugin>;
 
+    /// Get a plugin whose workspace support edits the given workspace manifest
+    /// (its package manifest, or an additional one such as pnpm-workspace.yaml)
+    fn get_plugin_for_workspace_manifest(&self, file_path: &Path) -> Option<&dyn LanguagePlugin> {
+        self.get_plugin_for_manifest(file_path)
+            .filter(|plugin| plugin.workspace_support().is_some())
+    }
+
     /// Access to the inner registry for builders (used by dependency analysis)
     fn inner(&self) -> &dyn std::any::Any;
 }
@@ -115,6 +122,20 @@ pub trait LspAdapter: Send + Sync + LspImportFinder {
         file_extension: &str,
     ) -> Result<Arc<LspClient>, MillError>;
 
+    /// Get or create the LSP client serving the project root that contains `file_path`
+    ///
+    /// Defaults to the client for the file's extension.
+    async fn get_or_create_client_for_file(
+        &self,
+        file_path: &Path,
+    ) -> Result<Arc<LspClient>, MillError> {
+        let extension = file_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        self.get_or_create_client(extension).await
+    }
+
     /// Access to the inner adapter for downcasting
     fn as_any(&self) -> &dyn std::any::Any;
 
@@ -0,0 +1,143 @@
+//! Audit records of write tool calls
+//!
+//! Once a tool call that modifies files finishes, successfully or not, it is
+//! appended to the [`AuditLog`](mill_services::services::AuditLog). Read-only
+//! tools, previews (dry runs) and calls that no handler accepted are not
+//! recorded.
+
+use super::authorization::required_permission;
+use mill_auth::Permission;
+use mill_foundation::core::model::mcp::ToolCall;
+use mill_foundation::errors::MillResult as ServerResult;
+use mill_services::services::{AuditOutcome, AuditRecord};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// The audit record of a finished tool call, or `None` if it did not write
+pub(crate) fn audit_record(
+    tool_call: &ToolCall,
+    user_id: Option<&str>,
+    result: &ServerResult<Value>,
+    duration: Duration,
+) -> Option<AuditRecord> {
+    if required_permission(tool_call) != Permission::Write {
+        return None;
+    }
+
+    let mut record = AuditRecord {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        user_id: user_id.map(str::to_string),
+        tool: tool_call.name.clone(),
+        arguments: tool_call.arguments.clone().unwrap_or(Value::Null),
+        checksums: BTreeMap::new(),
+        files: Vec::new(),
+        outcome: AuditOutcome::Success,
+        error: None,
+        duration_ms: duration.as_millis() as u64,
+    };
+
+    match result {
+        Ok(value) => {
+            let content = value.get("content").unwrap_or(value);
+            match content.get("status").and_then(Value::as_str) {
+                // The handler only previewed the change
+                Some("preview") => return None,
+                Some("error") => {
+                    record.outcome = AuditOutcome::Error;
+                    record.error = content
+                        .get("summary")
+                        .and_then(Value::as_str)
+                        .map(str::to_string);
+                }
+                _ => {}
+            }
+            if let Some(files) = content.get("filesChanged").and_then(Value::as_array) {
+                record.files = files
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect();
+            }
+            if let Some(checksums) = content
+                .pointer("/changes/checksums")
+                .and_then(Value::as_object)
+            {
+                record.checksums = checksums
+                    .iter()
+                    .filter_map(|(path, checksum)| {
+                        Some((path.clone(), checksum.as_str()?.to_string()))
+                    })
+                    .collect();
+            }
+        }
+        Err(e) => {
+            record.outcome = AuditOutcome::Error;
+            record.error = Some(e.to_string());
+        }
+    }
+
+    Some(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mill_foundation::errors::MillError;
+    use serde_json::json;
+
+    fn call(name: &str, arguments: Value) -> ToolCall {
+        ToolCall {
+            name: name.to_string(),
+            arguments: Some(arguments),
+        }
+    }
+
+    #[test]
+    fn test_only_applied_writes_are_recorded() {
+        let duration = Duration::from_millis(5);
+        let applied = json!({
+            "content": {
+                "status": "success",
+                "summary": "Rename completed successfully: 2 file(s) modified",
+                "filesChanged": ["src/a.ts", "src/b.ts"],
+                "diagnostics": [],
+                "changes": { "success": true, "checksums": { "src/a.ts": "1f2e" } }
+            }
+        });
+        let execute = call(
+            "rename_all",
+            json!({ "target": { "kind": "symbol" }, "options": { "dryRun": false } }),
+        );
+
+        let record = audit_record(&execute, Some("alice"), &Ok(applied), duration).unwrap();
+        assert_eq!(record.user_id.as_deref(), Some("alice"));
+        assert_eq!(record.tool, "rename_all");
+        assert_eq!(record.arguments["options"]["dryRun"], false);
+        assert_eq!(record.files, ["src/a.ts", "src/b.ts"]);
+        assert_eq!(record.checksums["src/a.ts"], "1f2e");
+        assert_eq!(record.outcome, AuditOutcome::Success);
+
+        let failed = audit_record(
+            &execute,
+            None,
+            &Err(MillError::not_found("src/a.ts")),
+            duration,
+        )
+        .unwrap();
+        assert_eq!(failed.outcome, AuditOutcome::Error);
+        assert!(failed.error.unwrap().contains("src/a.ts"));
+
+        // Dry runs, previews and read-only tools are not audited
+        let preview = call("rename_all", json!({ "target": { "kind": "symbol" } }));
+        assert!(audit_record(&preview, None, &Ok(json!({})), duration).is_none());
+        let previewed = json!({ "content": { "status": "preview" } });
+        assert!(audit_record(&execute, None, &Ok(previewed), duration).is_none());
+        let write_preview = call("write_file", json!({ "filePath": "a.ts", "dryRun": true }));
+        assert!(audit_record(&write_preview, None, &Ok(json!({})), duration).is_none());
+        let write = call("write_file", json!({ "filePath": "a.ts", "content": "" }));
+        assert!(audit_record(&write, None, &Ok(json!({})), duration).is_some());
+        let read = call("inspect_code", json!({}));
+        assert!(audit_record(&read, None, &Ok(json!({})), duration).is_none());
+    }
+}
@@ -0,0 +1,612 @@
+//! Token scope enforcement for tool calls
+//!
+//! A token may restrict its holder to reading, to a list of tools, or to
+//! paths matching glob patterns (see [`TokenScopes`]). Every tool call is
+//! checked against the caller's scopes before it is dispatched, and calls
+//! outside them are refused with a `permission_denied` error. Writes are
+//! checked again against the files the resulting plan touches (see
+//! [`write_scope`]), and previews against the files their plan shows (see
+//! [`ReadScope::check_preview`]).
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use mill_auth::{Permission, TokenScopes};
+use mill_foundation::core::model::mcp::ToolCall;
+use mill_foundation::core::tool_effects;
+use mill_foundation::core::write_scope::WriteScope;
+use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+
+/// Tools that report on TypeMill itself rather than on project files
+const PROJECT_INDEPENDENT_TOOLS: &[&str] = &["health_check", "cache_status", "get_lsp_progress"];
+
+/// Check a tool call against the caller's token scopes
+pub fn authorize_tool_call(
+    scopes: &TokenScopes,
+    tool_call: &ToolCall,
+    project_root: &Path,
+) -> ServerResult<()> {
+    let tool = tool_call.name.as_str();
+    if !scopes.allows_tool(tool) {
+        return Err(denied(
+            format!("token may not call tool '{}'", tool),
+            format!("tool:{}", tool),
+        ));
+    }
+
+    let permission = required_permission(tool_call);
+    if !scopes.grants(permission) {
+        return Err(denied(
+            format!("'{}' needs {} permission", tool, permission),
+            permission.to_string(),
+        ));
+    }
+
+    let Some(allowed) = allowed_paths(scopes)? else {
+        return Ok(());
+    };
+
+    let paths = referenced_paths(tool_call).map_err(|field| {
+        denied(
+            format!("'{}' does not take a path in '{}'", tool, field),
+            format!("path:{}", field),
+        )
+    })?;
+    // Without a path the call may read or change the whole project
+    if paths.is_empty() && !PROJECT_INDEPENDENT_TOOLS.contains(&tool) {
+        return Err(denied(
+            format!(
+                "'{}' must name the paths it reads or changes when the token is restricted to paths",
+                tool
+            ),
+            "path",
+        ));
+    }
+    for path in paths {
+        let permitted = project_relative(&path, project_root)
+            .is_some_and(|relative| allowed.is_match(relative));
+        if !permitted {
+            return Err(denied(
+                format!("token may not access '{}'", path),
+                format!("path:{}", path),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// What a caller may read through `resources/*` requests
+pub struct ReadScope {
+    /// Paths the caller may read, or `None` for the whole project
+    allowed: Option<GlobSet>,
+    roots: [PathBuf; 2],
+}
+
+impl ReadScope {
+    /// The read scope of a caller with `scopes` (`None` when unauthenticated)
+    ///
+    /// Fails if the token does not grant read permission.
+    pub fn new(scopes: Option<&TokenScopes>, project_root: &Path) -> ServerResult<Self> {
+        let allowed = match scopes {
+            Some(scopes) if !scopes.grants(Permission::Read) => {
+                return Err(denied(
+                    "resources need read permission".to_string(),
+                    Permission::Read.to_string(),
+                ));
+            }
+            Some(scopes) => allowed_paths(scopes)?,
+            None => None,
+        };
+        Ok(Self {
+            allowed,
+            roots: project_roots(project_root),
+        })
+    }
+
+    /// Whether the caller may read a project-relative `path`
+    pub fn permits(&self, path: &str) -> bool {
+        match &self.allowed {
+            Some(allowed) => self.roots.iter().any(|root| {
+                project_relative(path, root).is_some_and(|relative| allowed.is_match(relative))
+            }),
+            None => true,
+        }
+    }
+
+    /// Whether the caller may read views of the whole project
+    pub fn permits_project(&self) -> bool {
+        self.allowed.is_none()
+    }
+
+    /// Refuse reading `path` unless the caller may
+    pub fn check(&self, path: &str) -> ServerResult<()> {
+        if self.permits(path) {
+            Ok(())
+        } else {
+            Err(denied(
+                format!("token may not access '{}'", path),
+                format!("path:{}", path),
+            ))
+        }
+    }
+
+    /// Refuse a preview whose plan shows files the caller may not read
+    ///
+    /// A plan also shows the references it would update, which may lie
+    /// outside the paths the call named.
+    pub fn check_preview(&self, result: &Value) -> ServerResult<()> {
+        if self.allowed.is_none() {
+            return Ok(());
+        }
+        preview_paths(result)
+            .iter()
+            .try_for_each(|path| self.check(path))
+    }
+}
+
+/// Files shown by a preview result: its `filesChanged` and the files its plan
+/// edits, creates or deletes
+fn preview_paths(result: &Value) -> Vec<String> {
+    let Some(content) = result
+        .get("content")
+        .filter(|c| c.get("status").and_then(Value::as_str) == Some("preview"))
+    else {
+        return Vec::new();
+    };
+    let mut paths: Vec<String> = content
+        .get("filesChanged")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect();
+    if let Some(changes) = content.get("changes") {
+        collect_plan_paths(changes, &mut paths);
+    }
+    paths.retain(|path| !path.is_empty());
+    paths
+}
+
+/// Collect the files a serialized plan names, as `filePath`/`sourceFile`
+/// fields, `fileChecksums` keys or `file://` URIs
+fn collect_plan_paths(value: &Value, paths: &mut Vec<String>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                paths.extend(file_uri_path(key));
+                match (key.as_str(), value) {
+                    ("filePath" | "sourceFile", Value::String(path)) => paths.push(path.clone()),
+                    ("fileChecksums", Value::Object(files)) => paths.extend(files.keys().cloned()),
+                    _ => collect_plan_paths(value, paths),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_plan_paths(item, paths);
+            }
+        }
+        Value::String(text) => paths.extend(file_uri_path(text)),
+        _ => {}
+    }
+}
+
+fn file_uri_path(text: &str) -> Option<String> {
+    if !text.starts_with("file://") {
+        return None;
+    }
+    let path = url::Url::parse(text).ok()?.to_file_path().ok()?;
+    Some(path.to_string_lossy().into_owned())
+}
+
+/// The files a call under `scopes` may write, if the token limits its paths
+///
+/// The arguments only name a call's targets, so this scope also covers the
+/// references a plan updates elsewhere in the project.
+pub fn write_scope(scopes: &TokenScopes, project_root: &Path) -> ServerResult<Option<WriteScope>> {
+    let Some(allowed) = allowed_paths(scopes)? else {
+        return Ok(None);
+    };
+    let roots = project_roots(project_root);
+    Ok(Some(WriteScope::new(move |path| {
+        roots.iter().any(|root| {
+            project_relative(&path.to_string_lossy(), root)
+                .is_some_and(|relative| allowed.is_match(relative))
+        })
+    })))
+}
+
+/// The project root as given and as resolved through symlinks, which is how
+/// services name the files they plan to change
+fn project_roots(project_root: &Path) -> [PathBuf; 2] {
+    let canonical_root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    [project_root.to_path_buf(), canonical_root]
+}
+
+fn allowed_paths(scopes: &TokenScopes) -> ServerResult<Option<GlobSet>> {
+    let Some(patterns) = &scopes.paths else {
+        return Ok(None);
+    };
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| {
+            denied(
+                format!("token has an invalid path pattern '{}': {}", pattern, e),
+                format!("path:{}", pattern),
+            )
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| denied(format!("token path patterns are invalid: {}", e), "path"))
+}
+
+/// The permission a tool call needs
+///
+/// Unknown tools are assumed to write.
+pub(crate) fn required_permission(tool_call: &ToolCall) -> Permission {
+    let tool = tool_call.name.as_str();
+    let args = tool_call.arguments.as_ref().unwrap_or(&Value::Null);
+    if tool_effects::is_read_only(tool, args) || tool_effects::is_preview(tool, args) {
+        Permission::Read
+    } else {
+        Permission::Write
+    }
+}
+
+/// Argument fields that name the files each tool reads or changes
+///
+/// Fields are JSON pointers into the arguments, with `*` standing for every
+/// element of an array. A `newName` field only names a path when its target
+/// is a file or directory (see [`referenced_paths`]).
+fn target_fields(tool: &str) -> &'static [&'static str] {
+    match tool {
+        "inspect_code" | "read_file" | "write_file" | "create_file" | "delete_file"
+        | "get_completions" | "get_signature_help" | "notify_file_opened" | "notify_file_saved"
+        | "notify_file_closed" => &["/filePath"],
+        "search_code" => &["/workspacePath", "/path"],
+        "list_files" => &["/directory"],
+        "rename_file" | "rename_directory" => &["/old_path", "/new_path"],
+        "edit_file" => &["/path"],
+        "insert_after_symbol" => &["/file_path"],
+        "rename_all" => &["/target/filePath", "/targets/*/filePath"],
+        "relocate" => &["/target/filePath", "/destination"],
+        "prune" => &["/target/filePath"],
+        "refactor" => &["/params/filePath", "/params/destination"],
+        "workspace" => &[
+            "/params/path",
+            "/params/filePath",
+            "/params/workspaceManifest",
+        ],
+        _ => &[],
+    }
+}
+
+/// Paths named in a tool call's target fields
+///
+/// Fails with the offending field if any other argument looks like a path
+/// (a key ending in `path`, `destination` or `workspaceManifest`), so a
+/// call cannot satisfy the path scopes with a field the tool ignores.
+fn referenced_paths(tool_call: &ToolCall) -> Result<Vec<String>, String> {
+    let mut paths = Vec::new();
+    let Some(args) = &tool_call.arguments else {
+        return Ok(paths);
+    };
+    collect_paths(
+        args,
+        String::new(),
+        target_fields(&tool_call.name),
+        &mut paths,
+    )?;
+
+    // A file or directory rename names the new path in `newName`, next to
+    // the target's kind (batch targets) or to the `target` object
+    if tool_call.name == "rename_all" {
+        let renames_path = |target: &Value| {
+            matches!(
+                target.get("kind").and_then(Value::as_str),
+                Some("file" | "directory")
+            )
+        };
+        if args.get("target").is_some_and(renames_path) {
+            paths.extend(
+                args.get("newName")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            );
+        }
+        let targets = args.get("targets").and_then(Value::as_array);
+        for target in targets.into_iter().flatten().filter(|t| renames_path(t)) {
+            paths.extend(
+                target
+                    .get("newName")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            );
+        }
+    }
+    Ok(paths)
+}
+
+fn collect_paths(
+    value: &Value,
+    pointer: String,
+    fields: &[&str],
+    paths: &mut Vec<String>,
+) -> Result<(), String> {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let field = format!("{}/{}", pointer, key);
+                if fields.contains(&field.as_str()) {
+                    match value {
+                        Value::String(path) => paths.push(path.clone()),
+                        Value::Null => {}
+                        _ => return Err(field),
+                    }
+                    continue;
+                }
+                let key = key.to_ascii_lowercase();
+                if key.ends_with("path") || key == "destination" || key == "workspacemanifest" {
+                    return Err(field);
+                }
+                collect_paths(value, field, fields, paths)?;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_paths(item, format!("{}/*", pointer), fields, paths)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// A path relative to the project root, or `None` if it lies outside it
+fn project_relative(path: &str, project_root: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in project_root.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+        .strip_prefix(project_root)
+        .ok()
+        .map(Path::to_path_buf)
+}
+
+fn denied(operation: String, required_permission: impl Into<String>) -> ServerError {
+    ServerError::PermissionDenied {
+        operation,
+        required_permission: Some(required_permission.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(name: &str, arguments: Value) -> ToolCall {
+        ToolCall {
+            name: name.to_string(),
+            arguments: Some(arguments),
+        }
+    }
+
+    #[test]
+    fn test_read_only_token_may_preview_but_not_write() {
+        let root = Path::new("/project");
+        let scopes = TokenScopes {
+            permissions: Some(vec![Permission::Read]),
+            ..TokenScopes::default()
+        };
+
+        let preview = call("prune", json!({ "target": { "filePath": "src/a.ts" } }));
+        assert!(authorize_tool_call(&scopes, &preview, root).is_ok());
+
+        let execute = call(
+            "prune",
+            json!({ "target": { "filePath": "src/a.ts" }, "options": { "dryRun": false } }),
+        );
+        let error = authorize_tool_call(&scopes, &execute, root).unwrap_err();
+        assert!(matches!(
+            error,
+            ServerError::PermissionDenied { ref required_permission, .. }
+                if required_permission.as_deref() == Some("write")
+        ));
+        assert!(authorize_tool_call(&scopes, &call("write_file", json!({})), root).is_err());
+        assert!(authorize_tool_call(&scopes, &call("inspect_code", json!({})), root).is_ok());
+    }
+
+    #[test]
+    fn test_tool_allowlist() {
+        let root = Path::new("/project");
+        let scopes = TokenScopes {
+            tools: Some(vec!["inspect_code".to_string()]),
+            ..TokenScopes::default()
+        };
+
+        assert!(authorize_tool_call(&scopes, &call("inspect_code", json!({})), root).is_ok());
+        assert!(authorize_tool_call(&scopes, &call("search_code", json!({})), root).is_err());
+    }
+
+    #[test]
+    fn test_path_restrictions() {
+        let root = Path::new("/project");
+        let scopes = TokenScopes {
+            paths: Some(vec!["src/**".to_string()]),
+            ..TokenScopes::default()
+        };
+        let rename = |file: &str, new_name: &str| {
+            call(
+                "rename_all",
+                json!({
+                    "target": { "kind": "file", "filePath": file },
+                    "newName": new_name,
+                    "options": { "dryRun": false }
+                }),
+            )
+        };
+
+        assert!(authorize_tool_call(&scopes, &rename("src/a.ts", "src/b.ts"), root).is_ok());
+        assert!(
+            authorize_tool_call(&scopes, &rename("/project/src/a.ts", "src/b.ts"), root).is_ok()
+        );
+        assert!(authorize_tool_call(&scopes, &rename("src/a.ts", "lib/b.ts"), root).is_err());
+        assert!(
+            authorize_tool_call(&scopes, &rename("src/../lib/a.ts", "src/b.ts"), root).is_err()
+        );
+        assert!(authorize_tool_call(&scopes, &rename("/etc/passwd", "src/b.ts"), root).is_err());
+
+        // Project-wide writes cannot be confined to the allowed paths
+        let find_replace = call(
+            "workspace",
+            json!({
+                "action": "find_replace",
+                "params": { "pattern": "a", "replacement": "b" },
+                "options": { "dryRun": false }
+            }),
+        );
+        assert!(authorize_tool_call(&scopes, &find_replace, root).is_err());
+
+        // Only the tool's own target fields count as the paths it changes
+        let disguised = call(
+            "workspace",
+            json!({
+                "action": "find_replace",
+                "params": { "pattern": "a", "replacement": "b" },
+                "options": { "dryRun": false, "fooPath": "src/x.ts" }
+            }),
+        );
+        assert!(authorize_tool_call(&scopes, &disguised, root).is_err());
+        let relocate = call(
+            "relocate",
+            json!({
+                "target": { "kind": "file", "filePath": "src/a.ts" },
+                "destination": "src/b.ts",
+                "options": { "dryRun": false }
+            }),
+        );
+        assert!(authorize_tool_call(&scopes, &relocate, root).is_ok());
+    }
+
+    #[test]
+    fn test_path_restricted_reads_must_name_paths() {
+        let root = Path::new("/project");
+        let scopes = TokenScopes {
+            paths: Some(vec!["src/**".to_string()]),
+            ..TokenScopes::default()
+        };
+
+        let search = |args| authorize_tool_call(&scopes, &call("search_code", args), root);
+        assert!(search(json!({ "query": "secret" })).is_err());
+        assert!(search(json!({ "query": "secret", "path": "src/app" })).is_ok());
+        assert!(search(json!({ "query": "secret", "path": "lib" })).is_err());
+
+        // A dry run reads as much of the project as the change would touch
+        let find_replace_preview = call(
+            "workspace",
+            json!({
+                "action": "find_replace",
+                "params": { "pattern": "a", "replacement": "b" }
+            }),
+        );
+        assert!(authorize_tool_call(&scopes, &find_replace_preview, root).is_err());
+
+        assert!(authorize_tool_call(&scopes, &call("health_check", json!({})), root).is_ok());
+    }
+
+    #[test]
+    fn test_preview_limited_to_readable_files() {
+        let root = Path::new("/project");
+        let scopes = TokenScopes {
+            paths: Some(vec!["src/**".to_string()]),
+            ..TokenScopes::default()
+        };
+        let scope = ReadScope::new(Some(&scopes), root).unwrap();
+        let preview = |changes: Value| {
+            json!({
+                "content": {
+                    "status": "preview",
+                    "filesChanged": ["src/a.ts"],
+                    "changes": changes
+                }
+            })
+        };
+
+        let inside = preview(json!({
+            "planType": "RenamePlan",
+            "edits": { "changes": { "file:///project/src/b.ts": [] } },
+            "fileChecksums": { "/project/src/a.ts": "abc" }
+        }));
+        assert!(scope.check_preview(&inside).is_ok());
+
+        let uri = preview(json!({
+            "edits": { "documentChanges": [{ "textDocument": { "uri": "file:///project/lib/b.ts" } }] }
+        }));
+        assert!(scope.check_preview(&uri).is_err());
+        let checksum = preview(json!({ "fileChecksums": { "/project/lib/b.ts": "abc" } }));
+        assert!(scope.check_preview(&checksum).is_err());
+        let edit_plan = preview(json!({ "sourceFile": "", "edits": [{ "filePath": "lib/b.ts" }] }));
+        assert!(scope.check_preview(&edit_plan).is_err());
+
+        // Applied results were checked against the write scope instead
+        let applied = json!({ "content": { "status": "success", "filesChanged": ["lib/b.ts"] } });
+        assert!(scope.check_preview(&applied).is_ok());
+        let unrestricted = ReadScope::new(None, root).unwrap();
+        assert!(unrestricted.check_preview(&checksum).is_ok());
+    }
+
+    #[test]
+    fn test_read_scope() {
+        let root = Path::new("/project");
+        let scopes = TokenScopes {
+            paths: Some(vec!["src/**".to_string()]),
+            ..TokenScopes::default()
+        };
+
+        let scope = ReadScope::new(Some(&scopes), root).unwrap();
+        assert!(scope.permits("src/a.rs"));
+        assert!(!scope.permits("src/../lib/a.rs"));
+        assert!(!scope.permits_project());
+        assert!(ReadScope::new(None, root).unwrap().permits_project());
+
+        let no_permissions = TokenScopes {
+            permissions: Some(Vec::new()),
+            ..TokenScopes::default()
+        };
+        assert!(ReadScope::new(Some(&no_permissions), root).is_err());
+    }
+
+    #[test]
+    fn test_write_scope_covers_plan_files() {
+        let root = Path::new("/project");
+        let scopes = TokenScopes {
+            paths: Some(vec!["src/**".to_string()]),
+            ..TokenScopes::default()
+        };
+
+        let scope = write_scope(&scopes, root).unwrap().unwrap();
+        assert!(scope.permits(Path::new("/project/src/a.rs")));
+        assert!(!scope.permits(Path::new("/project/lib/a.rs")));
+        assert!(!scope.permits(Path::new("/elsewhere/src/a.rs")));
+        assert!(write_scope(&TokenScopes::default(), root)
+            .unwrap()
+            .is_none());
+    }
+}
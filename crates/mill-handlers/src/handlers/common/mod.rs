@@ -3,15 +3,18 @@
 //! This module provides shared functionality used by rename, move, and other
 //! refactoring operations to avoid code duplication.
 
+use crate::handlers::tool_definitions::WriteResponse;
 use crate::handlers::tools::extensions::get_concrete_app_state;
 use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
 use mill_foundation::protocol::RefactorPlan;
 use mill_handler_api::ToolHandlerContext;
-use mill_services::services::{ExecutionOptions, ExecutionResult, PlanExecutor};
+use mill_services::services::{render_plan_diff, ExecutionOptions, ExecutionResult, PlanExecutor};
 
 pub mod checksums;
+pub mod qualified_name;
 
 pub use checksums::calculate_checksum;
+pub use qualified_name::{resolve_symbol_name, resolve_symbol_target};
 pub(crate) use checksums::{
     calculate_checksums_for_directory_rename, calculate_checksums_for_edits,
 };
@@ -107,6 +110,31 @@ pub async fn execute_refactor_plan(
         .await
 }
 
+/// How a dry run presents its plan (`options.previewFormat`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewFormat {
+    /// The serialized plan, which `apply_plan` accepts
+    #[default]
+    Plan,
+    /// A unified diff, which `apply_plan` accepts as `patch`
+    Diff,
+}
+
+/// Replace a dry-run response's serialized plan with a unified diff if asked to
+pub async fn apply_preview_format(
+    context: &ToolHandlerContext,
+    plan: &RefactorPlan,
+    format: PreviewFormat,
+    response: &mut WriteResponse,
+) -> ServerResult<()> {
+    if format == PreviewFormat::Diff {
+        let diff = render_plan_diff(plan, &context.app_state.project_root).await?;
+        response.changes = Some(serde_json::json!({ "diff": diff }));
+    }
+    Ok(())
+}
+
 /// Estimate impact based on number of affected files
 pub fn estimate_impact(affected_files: usize) -> String {
     if affected_files <= 3 {
@@ -0,0 +1,681 @@
+//! Name-based symbol targets
+//!
+//! Write tools accept a `symbolName` instead of a 0-based `line`/`character`
+//! for symbol targets. The name may be qualified in any of these forms:
+//!
+//! - `src/app.ts#UserService.login`: a file, then containers within it
+//! - `pkg.module:Class.method`: a Python module, then containers within it
+//! - `crate::services::FileService::read_file` or `UserService.login`:
+//!   qualifiers matched against the symbol's containers, innermost first,
+//!   and then against the directories and stem of its file
+//!
+//! Symbols come from the file's document symbols, or from the language
+//! plugin's parse when no language server answers. Without a file, the
+//! workspace symbol index finds the files declaring the name. A name that
+//! matches several symbols is refused with the list of candidates.
+//!
+//! The file a name resolves to is checked against the token's path scopes,
+//! since the call's arguments need not name it.
+
+use super::lsp_mode;
+use crate::handlers::tools::extensions::get_concrete_app_state;
+use mill_config::config::LspMode;
+use mill_foundation::core::write_scope;
+use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
+use mill_handler_api::ToolHandlerContext;
+use mill_plugin_api::Symbol;
+use mill_plugin_system::PluginRequest;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+/// File stems that stand for their directory's module
+const MODULE_INDEX_STEMS: &[&str] = &["mod", "lib", "main", "index", "__init__"];
+
+/// Leading Rust path segments that name no module
+const RELATIVE_SEGMENTS: &[&str] = &["crate", "self", "super", "$crate"];
+
+/// A parsed `symbolName`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QualifiedName {
+    /// File named before `#`
+    pub file: Option<String>,
+    /// Python module named before `:`
+    pub module: Vec<String>,
+    /// Module and container names before the symbol's own name
+    pub qualifiers: Vec<String>,
+    pub name: String,
+}
+
+impl QualifiedName {
+    pub fn parse(selector: &str) -> ServerResult<Self> {
+        let invalid = || {
+            ServerError::invalid_request(format!(
+                "Invalid symbolName '{}': expected a name such as UserService.login, \
+                 src/app.ts#UserService.login, pkg.module:Class.method or crate::module::Type::method",
+                selector
+            ))
+        };
+
+        let (file, rest) = match selector.trim().split_once('#') {
+            Some((file, rest)) if !file.trim().is_empty() => (Some(file.trim().to_string()), rest),
+            Some(_) => return Err(invalid()),
+            None => (None, selector.trim()),
+        };
+        let (module, rest) = match rest.split_once(':') {
+            Some((module, path)) if file.is_none() && !rest.contains("::") => {
+                (split_segments(module), path)
+            }
+            _ => (Vec::new(), rest),
+        };
+        let mut qualifiers = split_segments(rest);
+        if qualifiers.iter().chain(&module).any(String::is_empty) {
+            return Err(invalid());
+        }
+        let name = qualifiers.pop().ok_or_else(invalid)?;
+        let relative = qualifiers
+            .iter()
+            .take_while(|q| RELATIVE_SEGMENTS.contains(&q.as_str()))
+            .count();
+        qualifiers.drain(..relative);
+
+        Ok(Self {
+            file,
+            module,
+            qualifiers,
+            name,
+        })
+    }
+
+    /// Whether a candidate is the symbol this name selects
+    fn matches(&self, candidate: &Candidate, root: &Path) -> bool {
+        if candidate.name != self.name {
+            return false;
+        }
+        let components = module_components(&candidate.path, root);
+        if !self.module.is_empty() {
+            let module: Vec<String> = self.module.iter().map(|m| normalize(m)).collect();
+            if !components.ends_with(&module) {
+                return false;
+            }
+        }
+
+        // Qualifiers name the innermost containers first, then the file's modules
+        let mut containers = candidate.containers.iter().rev();
+        let mut remaining = self.qualifiers.as_slice();
+        while let Some((last, rest)) = remaining.split_last() {
+            if !containers.any(|container| container == last) {
+                break;
+            }
+            remaining = rest;
+        }
+        let mut components = components.iter();
+        remaining.iter().all(|qualifier| {
+            let qualifier = normalize(qualifier);
+            components.any(|component| *component == qualifier)
+        })
+    }
+}
+
+/// A symbol declared in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Candidate {
+    name: String,
+    path: PathBuf,
+    /// Names of the enclosing symbols, outermost first
+    containers: Vec<String>,
+    /// 0-based position of the symbol (of its name, for document symbols)
+    line: u32,
+    character: u32,
+}
+
+impl Candidate {
+    /// A `symbolName` for the candidate, with its position
+    fn describe(&self, root: &Path) -> String {
+        let path = self.path.strip_prefix(root).unwrap_or(&self.path);
+        let mut qualified = self.containers.clone();
+        qualified.push(self.name.clone());
+        format!(
+            "{}#{} (line {}, character {})",
+            path.display(),
+            qualified.join("."),
+            self.line,
+            self.character
+        )
+    }
+}
+
+/// A symbol resolved from its `symbolName`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedSymbol {
+    pub file_path: PathBuf,
+    /// 0-based position of the symbol's name
+    pub line: u32,
+    pub character: u32,
+}
+
+/// Resolve a `symbolName` to the position of the symbol's name
+///
+/// `file_path`, when given, restricts the lookup to that file.
+pub async fn resolve_symbol_name(
+    context: &ToolHandlerContext,
+    symbol_name: &str,
+    file_path: Option<&str>,
+) -> ServerResult<ResolvedSymbol> {
+    let name = QualifiedName::parse(symbol_name)?;
+    let file_service = &context.app_state.file_service;
+    let checked = |file: &str| file_service.to_absolute_path_checked(Path::new(file));
+    let given = file_path.map(checked).transpose()?;
+    let named = name.file.as_deref().map(checked).transpose()?;
+    let file = match (given, named) {
+        (Some(given), Some(named)) if given != named => {
+            return Err(ServerError::invalid_request(format!(
+                "symbolName '{}' names a different file than filePath '{}'",
+                symbol_name,
+                file_path.unwrap_or_default()
+            )));
+        }
+        (given, named) => given.or(named),
+    };
+
+    // Checked paths are canonical, while the symbol index uses the project root as given
+    let project_root = context.app_state.project_root.as_path();
+    let canonical_root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    let (candidates, root) = match &file {
+        Some(file) => {
+            authorize_file(file, &canonical_root, symbol_name)?;
+            (
+                file_candidates(context, file).await?,
+                canonical_root.as_path(),
+            )
+        }
+        None => {
+            let mut candidates = workspace_candidates(context, &name.name).await?;
+            candidates.retain(|candidate| write_scope::permits(&candidate.path));
+            (candidates, project_root)
+        }
+    };
+    let candidate = select_candidate(&name, symbol_name, candidates, file.as_deref(), root)?;
+    authorize_file(&candidate.path, root, symbol_name)?;
+
+    let content = tokio::fs::read_to_string(&candidate.path)
+        .await
+        .map_err(|e| ServerError::not_found(format!("{}: {}", candidate.path.display(), e)))?;
+    let (line, character) = name_position(
+        &content,
+        candidate.line,
+        candidate.character,
+        &candidate.name,
+    );
+    debug!(
+        symbol = %symbol_name,
+        file = %candidate.path.display(),
+        line,
+        character,
+        "Resolved symbol name"
+    );
+    Ok(ResolvedSymbol {
+        file_path: candidate.path,
+        line,
+        character,
+    })
+}
+
+/// Fill in a symbol target's position, and its file if omitted, from its `symbolName`
+///
+/// Without a `symbolName` the target must name its file.
+pub async fn resolve_symbol_target(
+    context: &ToolHandlerContext,
+    symbol_name: Option<&str>,
+    file_path: &mut String,
+    line: &mut Option<u32>,
+    character: &mut Option<u32>,
+) -> ServerResult<()> {
+    let Some(symbol_name) = symbol_name else {
+        if file_path.is_empty() {
+            return Err(ServerError::invalid_request(
+                "filePath is required unless symbolName is given",
+            ));
+        }
+        return Ok(());
+    };
+
+    let given = (!file_path.is_empty()).then_some(file_path.as_str());
+    let resolved = resolve_symbol_name(context, symbol_name, given).await?;
+    if file_path.is_empty() {
+        *file_path = resolved.file_path.to_string_lossy().into_owned();
+    }
+    *line = Some(resolved.line);
+    *character = Some(resolved.character);
+    Ok(())
+}
+
+/// Refuse a symbol declared in a file outside the token's path scopes
+fn authorize_file(path: &Path, root: &Path, symbol_name: &str) -> ServerResult<()> {
+    if write_scope::permits(path) {
+        return Ok(());
+    }
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    Err(ServerError::PermissionDenied {
+        operation: format!(
+            "symbol '{}' is in '{}', outside the token's path scopes",
+            symbol_name,
+            relative.display()
+        ),
+        required_permission: Some(format!("path:{}", relative.display())),
+    })
+}
+
+/// The one candidate `name` selects
+fn select_candidate(
+    name: &QualifiedName,
+    symbol_name: &str,
+    candidates: Vec<Candidate>,
+    file: Option<&Path>,
+    root: &Path,
+) -> ServerResult<Candidate> {
+    let mut matches: Vec<Candidate> = candidates
+        .into_iter()
+        .filter(|candidate| name.matches(candidate, root))
+        .collect();
+    matches.sort_by(|a, b| (&a.path, a.line, a.character).cmp(&(&b.path, b.line, b.character)));
+    matches.dedup_by(|a, b| a.path == b.path && a.line == b.line && a.character == b.character);
+
+    match matches.len() {
+        0 => Err(ServerError::invalid_request(match file {
+            Some(file) => format!(
+                "Symbol '{}' not found in {}",
+                symbol_name,
+                file.strip_prefix(root).unwrap_or(file).display()
+            ),
+            None => format!("Symbol '{}' not found in the workspace", symbol_name),
+        })),
+        1 => Ok(matches.remove(0)),
+        _ => {
+            let listed: Vec<String> = matches
+                .iter()
+                .map(|candidate| format!("  {}", candidate.describe(root)))
+                .collect();
+            Err(ServerError::invalid_request(format!(
+                "Symbol '{}' is ambiguous, qualify it further or give filePath. Candidates:\n{}",
+                symbol_name,
+                listed.join("\n")
+            )))
+        }
+    }
+}
+
+/// Symbols declared in a file: its document symbols, or the language plugin's parse
+async fn file_candidates(
+    context: &ToolHandlerContext,
+    path: &Path,
+) -> ServerResult<Vec<Candidate>> {
+    if lsp_mode(context) != LspMode::Off {
+        let request = PluginRequest::new("get_document_symbols".to_string(), path.to_path_buf());
+        match context.plugin_manager.handle_request(request).await {
+            Ok(response) => {
+                let data = response.data.unwrap_or(Value::Null);
+                // LspAdapterPlugin wraps the symbols in {"symbols": [...]}
+                let symbols = data.get("symbols").unwrap_or(&data);
+                if let Some(symbols) = symbols.as_array().filter(|s| !s.is_empty()) {
+                    let mut candidates = Vec::new();
+                    document_symbol_candidates(symbols, path, &[], &mut candidates);
+                    return Ok(candidates);
+                }
+            }
+            Err(e) => debug!(
+                error = %e,
+                file = %path.display(),
+                "No document symbols, parsing the file instead"
+            ),
+        }
+    }
+
+    let concrete_state = get_concrete_app_state(&context.app_state)?;
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let Some(plugin) = concrete_state
+        .language_plugins
+        .all_plugins()
+        .iter()
+        .find(|p| p.handles_extension(extension))
+    else {
+        return Ok(Vec::new());
+    };
+    let source = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| ServerError::not_found(format!("{}: {}", path.display(), e)))?;
+    let symbols = concrete_state
+        .file_service
+        .ast_cache()
+        .symbols(plugin.as_ref(), &source)
+        .await
+        .map_err(|e| ServerError::internal(format!("Failed to parse {}: {}", path.display(), e)))?;
+    Ok(parsed_symbol_candidates(&symbols, path))
+}
+
+/// Symbols named `name` in the files the workspace symbol index finds it in
+async fn workspace_candidates(
+    context: &ToolHandlerContext,
+    name: &str,
+) -> ServerResult<Vec<Candidate>> {
+    let concrete_state = get_concrete_app_state(&context.app_state)?;
+    let index = &concrete_state.symbol_index;
+    index
+        .refresh(
+            concrete_state.language_plugins.all_plugins(),
+            concrete_state.file_service.ast_cache(),
+        )
+        .await
+        .map_err(|e| ServerError::internal(format!("Symbol index: {}", e)))?;
+
+    let files: BTreeSet<PathBuf> = index
+        .search(name, None, index.root(), None)
+        .await
+        .into_iter()
+        .filter(|matched| matched.symbol.name == name)
+        .map(|matched| matched.symbol.path)
+        .collect();
+    let mut candidates = Vec::new();
+    for file in files {
+        candidates.extend(file_candidates(context, &file).await?);
+    }
+    Ok(candidates)
+}
+
+/// Candidates from `textDocument/documentSymbol` results
+///
+/// Handles both nested `DocumentSymbol`s and flat `SymbolInformation`s.
+fn document_symbol_candidates(
+    symbols: &[Value],
+    path: &Path,
+    containers: &[String],
+    candidates: &mut Vec<Candidate>,
+) {
+    for symbol in symbols {
+        let Some(name) = symbol.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let symbol_containers = match symbol.get("containerName").and_then(Value::as_str) {
+            Some(container) if !container.is_empty() => split_segments(&impl_target(container)),
+            _ => containers.to_vec(),
+        };
+        let start = symbol
+            .pointer("/selectionRange/start")
+            .or_else(|| symbol.pointer("/range/start"))
+            .or_else(|| symbol.pointer("/location/range/start"));
+        let line = start.and_then(|s| s.get("line")).and_then(Value::as_u64);
+        let character = start
+            .and_then(|s| s.get("character"))
+            .and_then(Value::as_u64);
+        if let (Some(line), Some(character)) = (line, character) {
+            candidates.push(Candidate {
+                name: name.to_string(),
+                path: path.to_path_buf(),
+                containers: symbol_containers.clone(),
+                line: line as u32,
+                character: character as u32,
+            });
+        }
+
+        if let Some(children) = symbol.get("children").and_then(Value::as_array) {
+            let mut nested = symbol_containers;
+            nested.extend(split_segments(&impl_target(name)).pop());
+            document_symbol_candidates(children, path, &nested, candidates);
+        }
+    }
+}
+
+/// Candidates from a language plugin's parse, nested by their ranges
+fn parsed_symbol_candidates(symbols: &[Symbol], path: &Path) -> Vec<Candidate> {
+    let span = |symbol: &Symbol| {
+        let start = (symbol.location.line, symbol.location.column);
+        let end = symbol
+            .end_location
+            .as_ref()
+            .map_or(start, |end| (end.line, end.column));
+        (start, end)
+    };
+
+    symbols
+        .iter()
+        .map(|symbol| {
+            let (start, end) = span(symbol);
+            let mut enclosing: Vec<_> = symbols
+                .iter()
+                .map(|other| (span(other), other))
+                .filter(|((other_start, other_end), _)| {
+                    *other_start <= start
+                        && end <= *other_end
+                        && (*other_start, *other_end) != (start, end)
+                })
+                .collect();
+            enclosing.sort_by_key(|((other_start, _), _)| *other_start);
+            Candidate {
+                name: symbol.name.clone(),
+                path: path.to_path_buf(),
+                containers: enclosing
+                    .into_iter()
+                    .map(|(_, other)| other.name.clone())
+                    .collect(),
+                line: symbol.location.line as u32,
+                character: symbol.location.column as u32,
+            }
+        })
+        .collect()
+}
+
+/// Position of the first whole-word `name` at or after a symbol's start
+///
+/// Parsers report where a declaration starts (`pub fn`, decorators), but
+/// rename and inline need the position of the name itself.
+fn name_position(content: &str, line: u32, character: u32, name: &str) -> (u32, u32) {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    for (index, text) in content.lines().enumerate().skip(line as usize) {
+        let skip = if index == line as usize {
+            character as usize
+        } else {
+            0
+        };
+        let chars: Vec<char> = text.chars().collect();
+        let name_chars: Vec<char> = name.chars().collect();
+        for start in skip..chars.len() {
+            let end = start + name_chars.len();
+            let whole_word = chars.get(start..end) == Some(name_chars.as_slice())
+                && (start == 0 || !is_ident(chars[start - 1]))
+                && chars.get(end).is_none_or(|c| !is_ident(*c));
+            if whole_word {
+                return (index as u32, start as u32);
+            }
+        }
+    }
+    (line, character)
+}
+
+/// The type an `impl` block is for (`impl<T> Display for Foo<T>` is `Foo`)
+///
+/// Other names are returned as is.
+fn impl_target(name: &str) -> String {
+    let name = name.trim();
+    if !(name.starts_with("impl ") || name.starts_with("impl<") || name.starts_with("unsafe impl"))
+    {
+        return name.to_string();
+    }
+    let mut depth = 0usize;
+    let mut plain = String::new();
+    for c in name.chars() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            c if depth == 0 => plain.push(c),
+            _ => {}
+        }
+    }
+    plain
+        .split_whitespace()
+        .last()
+        .unwrap_or(name)
+        .trim_start_matches(['&', '*'])
+        .to_string()
+}
+
+/// Segments of a `::` or `.` separated path
+fn split_segments(path: &str) -> Vec<String> {
+    let separator = if path.contains("::") { "::" } else { "." };
+    path.split(separator)
+        .map(|segment| segment.trim().to_string())
+        .collect()
+}
+
+/// Directories and stem of a file relative to the project root, normalized
+fn module_components(path: &Path, root: &Path) -> Vec<String> {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let mut components: Vec<String> = relative
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(normalize(&name.to_string_lossy())),
+            _ => None,
+        })
+        .collect();
+    if let Some(stem) = relative.file_stem().map(|s| s.to_string_lossy()) {
+        if !MODULE_INDEX_STEMS.contains(&stem.as_ref()) {
+            components.push(normalize(&stem));
+        }
+    }
+    components
+}
+
+/// Compare names across conventions: `FileService`, `file_service`, `file-service`
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn candidate(path: &str, containers: &[&str], name: &str, line: u32) -> Candidate {
+        Candidate {
+            name: name.to_string(),
+            path: Path::new("/work").join(path),
+            containers: containers.iter().map(|c| c.to_string()).collect(),
+            line,
+            character: 4,
+        }
+    }
+
+    #[test]
+    fn test_parse_qualified_names() {
+        let parsed = QualifiedName::parse("crate::services::FileService::read_file").unwrap();
+        assert_eq!(parsed.qualifiers, ["services", "FileService"]);
+        assert_eq!(parsed.name, "read_file");
+
+        let parsed = QualifiedName::parse("src/app.ts#UserService.login").unwrap();
+        assert_eq!(parsed.file.as_deref(), Some("src/app.ts"));
+        assert_eq!(parsed.qualifiers, ["UserService"]);
+
+        let parsed = QualifiedName::parse("pkg.module:Class.method").unwrap();
+        assert_eq!(parsed.module, ["pkg", "module"]);
+        assert_eq!(parsed.qualifiers, ["Class"]);
+        assert_eq!(parsed.name, "method");
+
+        assert_eq!(QualifiedName::parse("main").unwrap().name, "main");
+        assert!(QualifiedName::parse("#login").is_err());
+        assert!(QualifiedName::parse("UserService.").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_files_outside_path_scopes_are_refused() {
+        let root = Path::new("/work");
+        let scope = write_scope::WriteScope::new(|path| path.starts_with("/work/src"));
+        scope
+            .scope(async {
+                assert!(authorize_file(&root.join("src/a.rs"), root, "Foo").is_ok());
+                let err = authorize_file(&root.join("lib/x.rs"), root, "lib/x.rs#Foo").unwrap_err();
+                assert!(matches!(
+                    err,
+                    ServerError::PermissionDenied { required_permission: Some(ref path), .. }
+                        if path == "path:lib/x.rs"
+                ));
+            })
+            .await;
+        assert!(authorize_file(&root.join("lib/x.rs"), root, "Foo").is_ok());
+    }
+
+    #[test]
+    fn test_select_candidate() {
+        let root = Path::new("/work");
+        let candidates = vec![
+            candidate(
+                "src/services/file_service.rs",
+                &["FileService"],
+                "read_file",
+                10,
+            ),
+            candidate("src/services/cache.rs", &["Cache"], "read_file", 20),
+            candidate("src/legacy.rs", &[], "read_file", 30),
+            candidate("pkg/module/__init__.py", &["Class"], "method", 5),
+            candidate("tests/module.py", &["Class"], "method", 5),
+        ];
+        let select = |selector: &str| {
+            let name = QualifiedName::parse(selector).unwrap();
+            select_candidate(&name, selector, candidates.clone(), None, root)
+        };
+
+        let found = select("crate::services::FileService::read_file").unwrap();
+        assert_eq!(found.line, 10);
+        // Without containers, qualifiers fall back to the file's modules
+        assert_eq!(select("legacy::read_file").unwrap().line, 30);
+        assert_eq!(
+            select("pkg.module:Class.method").unwrap().path,
+            root.join("pkg/module/__init__.py")
+        );
+
+        let error = select("read_file").unwrap_err().to_string();
+        assert!(error.contains("ambiguous"), "{}", error);
+        assert!(error.contains("src/services/cache.rs#Cache.read_file (line 20, character 4)"));
+        assert!(error.contains("src/legacy.rs#read_file"));
+        assert!(select("Other::read_file")
+            .unwrap_err()
+            .to_string()
+            .contains("not found"));
+    }
+
+    #[test]
+    fn test_document_symbols_and_name_position() {
+        let symbols = json!([{
+            "name": "impl<T> Display for Wrapper<T>",
+            "range": { "start": { "line": 2, "character": 0 }, "end": { "line": 8, "character": 1 } },
+            "selectionRange": { "start": { "line": 2, "character": 21 }, "end": { "line": 2, "character": 28 } },
+            "children": [{
+                "name": "fmt",
+                "range": { "start": { "line": 3, "character": 4 }, "end": { "line": 7, "character": 5 } },
+                "selectionRange": { "start": { "line": 3, "character": 7 }, "end": { "line": 3, "character": 10 } }
+            }]
+        }]);
+        let mut candidates = Vec::new();
+        document_symbol_candidates(
+            symbols.as_array().unwrap(),
+            Path::new("/work/src/lib.rs"),
+            &[],
+            &mut candidates,
+        );
+        assert_eq!(candidates[1].containers, ["Wrapper"]);
+        assert_eq!((candidates[1].line, candidates[1].character), (3, 7));
+
+        let content = "/// Docs\n#[inline]\npub fn format_all() {}\npub fn format() {}\n";
+        assert_eq!(name_position(content, 0, 0, "format"), (3, 7));
+        assert_eq!(name_position(content, 0, 0, "missing"), (0, 0));
+    }
+}
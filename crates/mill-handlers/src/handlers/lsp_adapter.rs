@@ -4,14 +4,17 @@
 //! and its hard-coded mappings, enabling dynamic LSP server configuration.
 
 use async_trait::async_trait;
+use mill_config::config::LspRole;
 use mill_plugin_system::LspService;
 use mill_services::services::reference_updater::LspImportFinder;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::{debug, warn};
+use std::time::Instant;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, info, warn};
 
 /// Information about an LSP progress task
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,17 +33,64 @@ pub struct LspProgressInfo {
     pub percentage: Option<u32>,
 }
 
+impl From<mill_lsp::progress::ProgressState> for LspProgressInfo {
+    fn from(state: mill_lsp::progress::ProgressState) -> Self {
+        match state {
+            mill_lsp::progress::ProgressState::InProgress {
+                title,
+                message,
+                percentage,
+            } => LspProgressInfo {
+                status: "in_progress".to_string(),
+                title: Some(title),
+                message,
+                percentage,
+            },
+            mill_lsp::progress::ProgressState::Completed { message } => LspProgressInfo {
+                status: "completed".to_string(),
+                title: None,
+                message,
+                percentage: Some(100),
+            },
+            mill_lsp::progress::ProgressState::Failed { reason } => LspProgressInfo {
+                status: "failed".to_string(),
+                title: None,
+                message: Some(reason),
+                percentage: None,
+            },
+        }
+    }
+}
+
+/// How often the configuration file is checked for changes
+const CONFIG_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// LSP client key: the server's index in `LspConfig.servers` and the project root it serves
+type ClientKey = (usize, PathBuf);
+
+/// A running LSP client and when it was last handed out
+struct ClientEntry {
+    client: Arc<mill_lsp::lsp_system::LspClient>,
+    last_used: Instant,
+}
+
 /// Direct LSP adapter that bypasses the old LSP manager and its hard-coded mappings
 #[derive(Clone)]
 pub struct DirectLspAdapter {
-    /// LSP clients by extension
-    lsp_clients: Arc<Mutex<HashMap<String, Arc<mill_lsp::lsp_system::LspClient>>>>,
+    /// LSP clients by server and project root
+    lsp_clients: Arc<Mutex<HashMap<ClientKey, ClientEntry>>>,
     /// LSP configuration
     config: mill_config::config::LspConfig,
+    /// Current settings of each configured server, updated on configuration reload
+    settings: Arc<Mutex<Vec<Option<Value>>>>,
     /// Supported file extensions
     extensions: Vec<String>,
     /// Adapter name
     name: String,
+    /// URIs whose cached diagnostics changed, across all clients
+    diagnostics_updates: broadcast::Sender<String>,
+    /// `$/progress` updates (token, state) from all clients
+    progress_updates: broadcast::Sender<(String, LspProgressInfo)>,
 }
 
 impl DirectLspAdapter {
@@ -49,67 +99,151 @@ impl DirectLspAdapter {
         extensions: Vec<String>,
         name: String,
     ) -> Self {
+        let (diagnostics_updates, _) = broadcast::channel(256);
+        let (progress_updates, _) = broadcast::channel(256);
+        let settings = config
+            .servers
+            .iter()
+            .map(|server| server.settings.clone())
+            .collect();
         Self {
             lsp_clients: Arc::new(Mutex::new(HashMap::new())),
             config,
+            settings: Arc::new(Mutex::new(settings)),
             extensions,
             name,
+            diagnostics_updates,
+            progress_updates,
         }
     }
 
     /// Get or create an LSP client for the given extension
+    ///
+    /// The client is the extension's primary server, serving its configured
+    /// root (or the current directory). Use `get_or_create_client_for_file`
+    /// to route a file to the instance for its own project root.
     pub async fn get_or_create_client(
         &self,
         extension: &str,
     ) -> Result<Arc<mill_lsp::lsp_system::LspClient>, String> {
-        // Find server config for this extension and derive a stable cache key.
-        let server_config = self
-            .config
+        self.client_for(self.primary_server(extension)?, None).await
+    }
+
+    /// Get or create the LSP client for the project root containing `file_path`
+    ///
+    /// Nested projects (a `tsconfig.json`, Cargo workspace or `pyproject.toml`
+    /// below the configured root) get their own server instance.
+    pub async fn get_or_create_client_for_file(
+        &self,
+        file_path: &Path,
+    ) -> Result<Arc<mill_lsp::lsp_system::LspClient>, String> {
+        let extension = file_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .ok_or_else(|| format!("Could not get extension from path: {}", file_path.display()))?;
+        self.client_for(self.primary_server(extension)?, Some(file_path))
+            .await
+    }
+
+    /// Indexes of the servers configured for an extension, in configuration order
+    fn servers_for(&self, extension: &str) -> Vec<usize> {
+        self.config
             .servers
             .iter()
-            .find(|server| server.extensions.contains(&extension.to_string()))
-            .ok_or_else(|| format!("No LSP server configured for extension: {}", extension))?
-            .clone();
+            .enumerate()
+            .filter(|(_, server)| server.extensions.iter().any(|e| e == extension))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Server taking requests for an extension that no role covers
+    ///
+    /// This is the first server without a role filter, or else the first
+    /// server configured for the extension.
+    fn primary_server(&self, extension: &str) -> Result<usize, String> {
+        let servers = self.servers_for(extension);
+        servers
+            .iter()
+            .copied()
+            .find(|&server| self.config.servers[server].roles.is_none())
+            .or_else(|| servers.first().copied())
+            .ok_or_else(|| format!("No LSP server configured for extension: {}", extension))
+    }
+
+    /// Servers answering `method` for an extension, in configuration order
+    ///
+    /// Methods outside every role go to the primary server only.
+    fn servers_for_method(&self, extension: &str, method: &str) -> Result<Vec<usize>, String> {
+        let Some(role) = method_role(method) else {
+            return Ok(vec![self.primary_server(extension)?]);
+        };
+        let servers: Vec<usize> = self
+            .servers_for(extension)
+            .into_iter()
+            .filter(|&server| self.config.servers[server].has_role(role))
+            .collect();
+        if servers.is_empty() {
+            return Err(format!(
+                "No LSP server with role {:?} configured for extension: {}",
+                role, extension
+            ));
+        }
+        Ok(servers)
+    }
+
+    /// Label of a server in progress reports and logs
+    ///
+    /// The primary server of its first extension is labelled with that
+    /// extension; any other server with its display name.
+    fn server_label(&self, server: usize) -> String {
+        let server_config = &self.config.servers[server];
+        match server_config.extensions.first() {
+            Some(extension) if self.primary_server(extension) == Ok(server) => extension.clone(),
+            _ => server_config.display_name(),
+        }
+    }
 
-        let cache_key = server_config
-            .extensions
-            .first()
-            .cloned()
-            .unwrap_or_else(|| extension.to_string());
+    /// Configured root of a server, or the current directory
+    fn default_root(server_config: &mill_config::config::LspServerConfig) -> PathBuf {
+        match &server_config.root_dir {
+            Some(root_dir) => crate::handlers::lsp_roots::absolute(root_dir),
+            None => std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        }
+    }
+
+    async fn client_for(
+        &self,
+        server: usize,
+        file_path: Option<&Path>,
+    ) -> Result<Arc<mill_lsp::lsp_system::LspClient>, String> {
+        let mut server_config = self.config.servers[server].clone();
+        let label = self.server_label(server);
+        let root = file_path
+            .and_then(|file| {
+                crate::handlers::lsp_roots::detect_project_root(file, &server_config.extensions)
+            })
+            .unwrap_or_else(|| Self::default_root(&server_config));
+        let cache_key = (server, root.clone());
 
         // Check if a client already exists and is alive
         let mut clients = self.lsp_clients.lock().await;
-        if let Some(client) = clients.get(&cache_key) {
-            if client.is_alive().await {
-                debug!(extension, cache_key = %cache_key, "Reusing existing, live LSP client");
-                return Ok(client.clone());
+        if let Some(entry) = clients.get_mut(&cache_key) {
+            if entry.client.is_alive().await {
+                debug!(server = %label, root = %root.display(), "Reusing existing, live LSP client");
+                entry.last_used = Instant::now();
+                return Ok(entry.client.clone());
             } else {
                 // PHASE 2: Dead client found - extract it for cleanup
                 warn!(
-                    extension,
-                    cache_key = %cache_key,
+                    server = %label,
+                    root = %root.display(),
                     "Found dead LSP client in cache, removing it before creating a new one."
                 );
                 let dead_client = clients.remove(&cache_key);
 
                 // Cleanup dead client immediately to prevent zombie processes
                 if let Some(dead_client) = dead_client {
-                    let ext = cache_key.clone();
-                    tokio::spawn(async move {
-                        // Force shutdown (kill + wait) to prevent zombies
-                        if let Err(e) = dead_client.force_shutdown().await {
-                            warn!(
-                                extension = %ext,
-                                error = %e,
-                                "Failed to force shutdown dead LSP client"
-                            );
-                        } else {
-                            debug!(
-                                extension = %ext,
-                                "Force shutdown of dead LSP client completed"
-                            );
-                        }
-                    });
+                    Self::shutdown_in_background(label.clone(), root.clone(), dead_client.client);
                 }
                 // Proceed to create a new client below
             }
@@ -117,70 +251,347 @@ impl DirectLspAdapter {
         // Drop the lock before the potentially long operation of creating a new client
         drop(clients);
 
-        // Create new LSP client
+        // Create new LSP client for this project root
+        server_config.root_dir = Some(root.clone());
+        server_config.settings = self.settings.lock().await[server].clone();
         let client = mill_lsp::lsp_system::LspClient::new(server_config)
             .await
             .map_err(|e| format!("Failed to create LSP client: {}", e))?;
 
         let client = Arc::new(client);
 
-        // Store the client
-        {
-            let mut clients = self.lsp_clients.lock().await;
-            clients.insert(cache_key, client.clone());
+        // Store the client, unless a concurrent caller got there first
+        let mut clients = self.lsp_clients.lock().await;
+        if let Some(existing) = clients.get_mut(&cache_key) {
+            existing.last_used = Instant::now();
+            let existing = existing.client.clone();
+            Self::shutdown_in_background(label, root, client);
+            return Ok(existing);
         }
+        self.forward_diagnostics_updates(&client);
+        self.forward_progress_updates(&client);
+        clients.insert(
+            cache_key.clone(),
+            ClientEntry {
+                client: client.clone(),
+                last_used: Instant::now(),
+            },
+        );
+        self.evict_idle_clients(&mut clients, &cache_key);
 
         Ok(client)
     }
 
+    /// Shut down least recently used idle instances of `kept`'s server
+    /// beyond `max_instances_per_server`
+    ///
+    /// An instance is idle when no caller holds its client. Busy instances
+    /// are never evicted, so the limit can be exceeded while they are in use.
+    fn evict_idle_clients(&self, clients: &mut HashMap<ClientKey, ClientEntry>, kept: &ClientKey) {
+        let max_instances = self.config.max_instances_per_server.max(1);
+        loop {
+            let instances = clients.keys().filter(|key| key.0 == kept.0).count();
+            if instances <= max_instances {
+                return;
+            }
+            let Some(evicted) = clients
+                .iter()
+                .filter(|(key, entry)| {
+                    key.0 == kept.0 && *key != kept && Arc::strong_count(&entry.client) == 1
+                })
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                return;
+            };
+            if let Some(entry) = clients.remove(&evicted) {
+                let label = self.server_label(evicted.0);
+                debug!(
+                    server = %label,
+                    root = %evicted.1.display(),
+                    "Evicting least recently used idle LSP client"
+                );
+                Self::shutdown_in_background(label, evicted.1, entry.client);
+            }
+        }
+    }
+
+    /// Force shutdown (kill + wait) a client without blocking the caller
+    fn shutdown_in_background(
+        label: String,
+        root: PathBuf,
+        client: Arc<mill_lsp::lsp_system::LspClient>,
+    ) {
+        tokio::spawn(async move {
+            // Force shutdown (kill + wait) to prevent zombies
+            if let Err(e) = client.force_shutdown().await {
+                warn!(
+                    server = %label,
+                    root = %root.display(),
+                    error = %e,
+                    "Failed to force shutdown LSP client"
+                );
+            } else {
+                debug!(
+                    server = %label,
+                    root = %root.display(),
+                    "Force shutdown of LSP client completed"
+                );
+            }
+        });
+    }
+
+    /// Running clients of the servers answering `method` for `extension`,
+    /// one per server and project root
+    ///
+    /// Starts a server's client for its configured root when none is running.
+    async fn server_clients(
+        &self,
+        extension: &str,
+        method: &str,
+    ) -> Result<Vec<Arc<mill_lsp::lsp_system::LspClient>>, String> {
+        let mut clients = Vec::new();
+        for server in self.servers_for_method(extension, method)? {
+            let running: Vec<_> = self
+                .lsp_clients
+                .lock()
+                .await
+                .iter()
+                .filter(|(key, _)| key.0 == server)
+                .map(|(_, entry)| entry.client.clone())
+                .collect();
+            let mut alive = Vec::new();
+            for client in running {
+                if client.is_alive().await {
+                    alive.push(client);
+                }
+            }
+            if alive.is_empty() {
+                alive.push(self.client_for(server, None).await?);
+            }
+            clients.extend(alive);
+        }
+        Ok(clients)
+    }
+
+    /// Apply the settings of a reloaded configuration to the running servers
+    ///
+    /// Servers are matched by command and extensions. Every running instance
+    /// of a server whose settings changed receives
+    /// `workspace/didChangeConfiguration`. Returns how many servers changed.
+    pub async fn reload_settings(&self, reloaded: &mill_config::config::LspConfig) -> usize {
+        let mut changed = Vec::new();
+        {
+            let mut settings = self.settings.lock().await;
+            for (server, server_config) in self.config.servers.iter().enumerate() {
+                let Some(reloaded) = reloaded.servers.iter().find(|candidate| {
+                    candidate.command == server_config.command
+                        && candidate.extensions == server_config.extensions
+                }) else {
+                    continue;
+                };
+                if settings[server] != reloaded.settings {
+                    settings[server] = reloaded.settings.clone();
+                    changed.push((server, reloaded.settings.clone()));
+                }
+            }
+        }
+
+        for (server, server_settings) in &changed {
+            let clients: Vec<_> = self
+                .lsp_clients
+                .lock()
+                .await
+                .iter()
+                .filter(|(key, _)| key.0 == *server)
+                .map(|(_, entry)| entry.client.clone())
+                .collect();
+            for client in clients {
+                if let Err(e) = client.update_settings(server_settings.clone()).await {
+                    warn!(
+                        server = %self.server_label(*server),
+                        error = %e,
+                        "Failed to send updated settings to LSP server"
+                    );
+                }
+            }
+        }
+        changed.len()
+    }
+
+    /// Reload server settings whenever the configuration file changes
+    ///
+    /// Polls the file's modification time; the task ends once the adapter is dropped.
+    pub fn watch_config_file(self: &Arc<Self>) {
+        let Some(path) = mill_config::config::AppConfig::config_file_path() else {
+            debug!("No configuration file to watch for LSP settings");
+            return;
+        };
+        let adapter = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+            let mut last_modified = modified(&path);
+            let mut interval = tokio::time::interval(CONFIG_POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                if adapter.strong_count() == 0 {
+                    break;
+                }
+                let current = modified(&path);
+                if current == last_modified {
+                    continue;
+                }
+                last_modified = current;
+                let Some(adapter) = adapter.upgrade() else {
+                    break;
+                };
+                match mill_config::config::AppConfig::load() {
+                    Ok(config) => {
+                        let changed = adapter.reload_settings(&config.lsp).await;
+                        info!(
+                            path = %path.display(),
+                            changed_servers = changed,
+                            "Reloaded LSP settings from configuration file"
+                        );
+                    }
+                    Err(e) => warn!(
+                        path = %path.display(),
+                        error = %e,
+                        "Failed to reload configuration, keeping current LSP settings"
+                    ),
+                }
+            }
+        });
+    }
+
+    /// Project roots with a running LSP client, by server label
+    pub async fn active_roots(&self) -> Vec<(String, PathBuf)> {
+        let keys: Vec<ClientKey> = self.lsp_clients.lock().await.keys().cloned().collect();
+        let mut roots: Vec<_> = keys
+            .into_iter()
+            .map(|(server, root)| (self.server_label(server), root))
+            .collect();
+        roots.sort();
+        roots
+    }
+
+    /// Subscribe to diagnostics changes reported by any LSP server
+    ///
+    /// Receives the `file://` URI of each file whose cached diagnostics changed.
+    pub fn subscribe_diagnostics(&self) -> broadcast::Receiver<String> {
+        self.diagnostics_updates.subscribe()
+    }
+
+    /// Cached push-model diagnostics for a file from the running LSP servers
+    ///
+    /// Does not start a server; returns an empty list when no server has
+    /// published diagnostics for the file.
+    pub async fn cached_diagnostics(&self, uri: &str) -> Vec<lsp_types::Diagnostic> {
+        let Ok(uri) = uri.parse::<lsp_types::Uri>() else {
+            return Vec::new();
+        };
+        let clients: Vec<_> = self
+            .lsp_clients
+            .lock()
+            .await
+            .values()
+            .map(|entry| entry.client.clone())
+            .collect();
+        let mut diagnostics = Vec::new();
+        for client in clients {
+            if let Some(cached) = client.get_cached_diagnostics(&uri).await {
+                diagnostics.extend(cached);
+            }
+        }
+        diagnostics
+    }
+
+    /// All cached push-model diagnostics from the running LSP servers, by URI
+    pub async fn all_cached_diagnostics(&self) -> HashMap<String, Vec<lsp_types::Diagnostic>> {
+        let clients: Vec<_> = self
+            .lsp_clients
+            .lock()
+            .await
+            .values()
+            .map(|entry| entry.client.clone())
+            .collect();
+        let mut all: HashMap<String, Vec<lsp_types::Diagnostic>> = HashMap::new();
+        for client in clients {
+            for (uri, diagnostics) in client.get_all_cached_diagnostics().await {
+                all.entry(uri.as_str().to_string())
+                    .or_default()
+                    .extend(diagnostics);
+            }
+        }
+        all
+    }
+
+    /// Relay a client's diagnostics updates to the adapter-wide channel
+    fn forward_diagnostics_updates(&self, client: &mill_lsp::lsp_system::LspClient) {
+        let mut updates = client.subscribe_diagnostics();
+        let sender = self.diagnostics_updates.clone();
+        tokio::spawn(async move {
+            loop {
+                match updates.recv().await {
+                    Ok(uri) => {
+                        let _ = sender.send(uri.as_str().to_string());
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        debug!(skipped, "Diagnostics update listener lagged behind");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Subscribe to `$/progress` updates (token, state) from any LSP server
+    pub fn subscribe_progress(&self) -> broadcast::Receiver<(String, LspProgressInfo)> {
+        self.progress_updates.subscribe()
+    }
+
+    /// Relay a client's `$/progress` updates to the adapter-wide channel
+    fn forward_progress_updates(&self, client: &mill_lsp::lsp_system::LspClient) {
+        let mut updates = client.subscribe_progress();
+        let sender = self.progress_updates.clone();
+        tokio::spawn(async move {
+            loop {
+                match updates.recv().await {
+                    Ok((token, state)) => {
+                        let _ = sender.send((token.to_string(), state.into()));
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        debug!(skipped, "Progress update listener lagged behind");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
     /// Get progress from all active LSP clients
     ///
-    /// Returns a map of extension -> list of (token, state) pairs for all active progress tasks.
+    /// Returns a map of server label -> list of (token, state) pairs for all active progress tasks.
+    /// Instances for nested project roots are keyed `label:root`.
     /// Useful for monitoring LSP server warmup/indexing progress.
-    pub async fn get_all_lsp_progress(
-        &self,
-    ) -> HashMap<String, Vec<(String, LspProgressInfo)>> {
+    pub async fn get_all_lsp_progress(&self) -> HashMap<String, Vec<(String, LspProgressInfo)>> {
         let clients = self.lsp_clients.lock().await;
         let mut result = HashMap::new();
 
-        for (extension, client) in clients.iter() {
-            let progress_list: Vec<(String, LspProgressInfo)> = client
+        for ((server, root), entry) in clients.iter() {
+            let mut label = self.server_label(*server);
+            if Self::default_root(&self.config.servers[*server]) != *root {
+                label = format!("{}:{}", label, root.display());
+            }
+            let progress_list: Vec<(String, LspProgressInfo)> = entry
+                .client
                 .get_active_progress()
                 .into_iter()
-                .map(|(token, state)| {
-                    let token_str = token.to_string();
-                    let info = match state {
-                        mill_lsp::progress::ProgressState::InProgress {
-                            title,
-                            message,
-                            percentage,
-                        } => LspProgressInfo {
-                            status: "in_progress".to_string(),
-                            title: Some(title),
-                            message,
-                            percentage,
-                        },
-                        mill_lsp::progress::ProgressState::Completed { message } => {
-                            LspProgressInfo {
-                                status: "completed".to_string(),
-                                title: None,
-                                message,
-                                percentage: Some(100),
-                            }
-                        }
-                        mill_lsp::progress::ProgressState::Failed { reason } => LspProgressInfo {
-                            status: "failed".to_string(),
-                            title: None,
-                            message: Some(reason),
-                            percentage: None,
-                        },
-                    };
-                    (token_str, info)
-                })
+                .map(|(token, state)| (token.to_string(), state.into()))
                 .collect();
 
             if !progress_list.is_empty() {
-                result.insert(extension.clone(), progress_list);
+                result.insert(label, progress_list);
             }
         }
 
@@ -223,240 +634,259 @@ impl DirectLspAdapter {
                 }
             }
 
-            // Get or create client for this extension
-            match self.get_or_create_client(extension).await {
-                Ok(client) => {
-                    let client_key = client.config().command.join(" ");
-                    if !seen_clients.insert(client_key.clone()) {
-                        debug!(
-                            extension = %extension,
-                            client_key = %client_key,
-                            "Skipping duplicate workspace/symbol query for shared LSP server"
-                        );
-                        continue;
-                    }
-                    // Check if the server supports workspace symbols
-                    if !client.supports_workspace_symbols().await {
-                        debug!(
-                            extension = %extension,
-                            "LSP server does not support workspace/symbol, skipping"
-                        );
-                        continue;
-                    }
-
-                    // For rust-analyzer, check if workspace indexing notifications are sent:
-                    // 1. Try event-driven wait for progress notifications (500ms timeout)
-                    // 2. If no progress notification arrives, assume indexing is instant or not needed
-                    // This handles both cases: servers that send $/progress and those that complete instantly
-                    if extension == "rs" {
-                        debug!(
-                            extension = %extension,
-                            "Checking for rust-analyzer workspace indexing progress"
-                        );
+            if all_symbols.len() >= MAX_WORKSPACE_SYMBOLS {
+                break;
+            }
 
-                        let token = mill_lsp::progress::ProgressToken::String(
-                            "rustAnalyzer/Indexing".to_string(),
+            // Query every running instance of this extension's server (one per project root)
+            match self.server_clients(extension, "workspace/symbol").await {
+                Ok(clients) => {
+                    for client in clients {
+                        let client_key = format!(
+                            "{} @ {}",
+                            client.config().command.join(" "),
+                            client
+                                .config()
+                                .root_dir
+                                .as_deref()
+                                .unwrap_or(Path::new("."))
+                                .display()
                         );
-
-                        // Check if indexing is already completed
-                        if client.is_progress_completed(&token) {
+                        if !seen_clients.insert(client_key.clone()) {
                             debug!(
                                 extension = %extension,
-                                "rust-analyzer indexing already complete"
+                                client_key = %client_key,
+                                "Skipping duplicate workspace/symbol query for shared LSP server"
                             );
-                        } else {
-                            // Wait briefly (500ms) to see if indexing progress notification arrives
-                            // rust-analyzer doesn't send progress for small projects that index instantly
-                            match client
-                                .wait_for_indexing(std::time::Duration::from_millis(500))
-                                .await
-                            {
-                                Ok(()) => {
-                                    debug!(
-                                        extension = %extension,
-                                        "rust-analyzer indexing complete via progress notification"
-                                    );
-                                }
-                                Err(_) => {
-                                    // No progress notification - indexing either instant or not happening
-                                    debug!(
-                                        extension = %extension,
-                                        "No progress notification in 500ms - indexing complete or not needed"
-                                    );
-                                }
-                            }
+                            continue;
+                        }
+                        // Check if the server supports workspace symbols
+                        if !client.supports_workspace_symbols().await {
+                            debug!(
+                                extension = %extension,
+                                "LSP server does not support workspace/symbol, skipping"
+                            );
+                            continue;
                         }
-                    }
 
-                    // For TypeScript, warm up the server by opening a file first
-                    // TypeScript LSP needs project context before workspace/symbol works
-                    if extension == "ts"
-                        || extension == "tsx"
-                        || extension == "js"
-                        || extension == "jsx"
-                    {
-                        debug!(
-                            extension = %extension,
-                            "TypeScript LSP requires warmup - opening a file to establish project context"
-                        );
+                        // For rust-analyzer, check if workspace indexing notifications are sent:
+                        // 1. Try event-driven wait for progress notifications (500ms timeout)
+                        // 2. If no progress notification arrives, assume indexing is instant or not needed
+                        // This handles both cases: servers that send $/progress and those that complete instantly
+                        if extension == "rs" {
+                            debug!(
+                                extension = %extension,
+                                "Checking for rust-analyzer workspace indexing progress"
+                            );
 
-                        // Try to find and open a representative file to establish project context
-                        if let Some(root_dir) = client.config().root_dir.as_ref() {
-                            let mut warmup_file = None;
-
-                            // Prefer opening a source file to establish a TS project context.
-                            let extensions_to_try = ["ts", "tsx", "js", "jsx"];
-                            for ext in &extensions_to_try {
-                                // Try to find any file with this extension in the workspace
-                                if let Ok(mut entries) = tokio::fs::read_dir(root_dir).await {
-                                    while let Ok(Some(entry)) = entries.next_entry().await {
-                                        let path = entry.path();
-                                        // Note: is_file() on DirEntry is cheap (doesn't stat again on most OSs)
-                                        // but path.is_file() might stat. entry.file_type() is async in tokio.
-                                        let is_file = match entry.file_type().await {
-                                            Ok(ft) => ft.is_file(),
-                                            Err(_) => false,
-                                        };
-
-                                        if is_file
-                                            && path.extension().and_then(|e| e.to_str())
-                                                == Some(ext)
-                                        {
-                                            warmup_file = Some(path);
-                                            break;
-                                        }
+                            let token = mill_lsp::progress::ProgressToken::String(
+                                "rustAnalyzer/Indexing".to_string(),
+                            );
+
+                            // Check if indexing is already completed
+                            if client.is_progress_completed(&token) {
+                                debug!(
+                                    extension = %extension,
+                                    "rust-analyzer indexing already complete"
+                                );
+                            } else {
+                                // Wait briefly (500ms) to see if indexing progress notification arrives
+                                // rust-analyzer doesn't send progress for small projects that index instantly
+                                match client
+                                    .wait_for_indexing(std::time::Duration::from_millis(500))
+                                    .await
+                                {
+                                    Ok(()) => {
+                                        debug!(
+                                            extension = %extension,
+                                            "rust-analyzer indexing complete via progress notification"
+                                        );
+                                    }
+                                    Err(_) => {
+                                        // No progress notification - indexing either instant or not happening
+                                        debug!(
+                                            extension = %extension,
+                                            "No progress notification in 500ms - indexing complete or not needed"
+                                        );
                                     }
-                                }
-                                if warmup_file.is_some() {
-                                    break;
                                 }
                             }
+                        }
 
-                            // If still not found, try src directory
-                            if warmup_file.is_none() {
-                                let src_dir = root_dir.join("src");
-                                let src_exists =
-                                    tokio::fs::try_exists(&src_dir).await.unwrap_or(false);
-                                let is_dir = if src_exists {
-                                    tokio::fs::metadata(&src_dir)
-                                        .await
-                                        .map(|m| m.is_dir())
-                                        .unwrap_or(false)
-                                } else {
-                                    false
-                                };
+                        // For TypeScript, warm up the server by opening a file first
+                        // TypeScript LSP needs project context before workspace/symbol works
+                        if extension == "ts"
+                            || extension == "tsx"
+                            || extension == "js"
+                            || extension == "jsx"
+                        {
+                            debug!(
+                                extension = %extension,
+                                "TypeScript LSP requires warmup - opening a file to establish project context"
+                            );
 
-                                if is_dir {
-                                    if let Ok(mut entries) = tokio::fs::read_dir(&src_dir).await {
+                            // Try to find and open a representative file to establish project context
+                            if let Some(root_dir) = client.config().root_dir.as_ref() {
+                                let mut warmup_file = None;
+
+                                // Prefer opening a source file to establish a TS project context.
+                                let extensions_to_try = ["ts", "tsx", "js", "jsx"];
+                                for ext in &extensions_to_try {
+                                    // Try to find any file with this extension in the workspace
+                                    if let Ok(mut entries) = tokio::fs::read_dir(root_dir).await {
                                         while let Ok(Some(entry)) = entries.next_entry().await {
                                             let path = entry.path();
+                                            // Note: is_file() on DirEntry is cheap (doesn't stat again on most OSs)
+                                            // but path.is_file() might stat. entry.file_type() is async in tokio.
                                             let is_file = match entry.file_type().await {
                                                 Ok(ft) => ft.is_file(),
                                                 Err(_) => false,
                                             };
 
-                                            if is_file {
-                                                if let Some(ext) =
-                                                    path.extension().and_then(|e| e.to_str())
-                                                {
-                                                    if extensions_to_try.contains(&ext) {
-                                                        warmup_file = Some(path);
-                                                        break;
+                                            if is_file
+                                                && path.extension().and_then(|e| e.to_str())
+                                                    == Some(ext)
+                                            {
+                                                warmup_file = Some(path);
+                                                break;
+                                            }
+                                        }
+                                    }
+                                    if warmup_file.is_some() {
+                                        break;
+                                    }
+                                }
+
+                                // If still not found, try src directory
+                                if warmup_file.is_none() {
+                                    let src_dir = root_dir.join("src");
+                                    let src_exists =
+                                        tokio::fs::try_exists(&src_dir).await.unwrap_or(false);
+                                    let is_dir = if src_exists {
+                                        tokio::fs::metadata(&src_dir)
+                                            .await
+                                            .map(|m| m.is_dir())
+                                            .unwrap_or(false)
+                                    } else {
+                                        false
+                                    };
+
+                                    if is_dir {
+                                        if let Ok(mut entries) = tokio::fs::read_dir(&src_dir).await
+                                        {
+                                            while let Ok(Some(entry)) = entries.next_entry().await {
+                                                let path = entry.path();
+                                                let is_file = match entry.file_type().await {
+                                                    Ok(ft) => ft.is_file(),
+                                                    Err(_) => false,
+                                                };
+
+                                                if is_file {
+                                                    if let Some(ext) =
+                                                        path.extension().and_then(|e| e.to_str())
+                                                    {
+                                                        if extensions_to_try.contains(&ext) {
+                                                            warmup_file = Some(path);
+                                                            break;
+                                                        }
                                                     }
                                                 }
                                             }
                                         }
                                     }
                                 }
-                            }
 
-                            // Final fallback: open tsconfig.json if no source file found.
-                            if warmup_file.is_none() {
-                                let tsconfig = root_dir.join("tsconfig.json");
-                                if tsconfig.exists() && tsconfig.is_file() {
-                                    warmup_file = Some(tsconfig);
+                                // Final fallback: open tsconfig.json if no source file found.
+                                if warmup_file.is_none() {
+                                    let tsconfig = root_dir.join("tsconfig.json");
+                                    if tsconfig.exists() && tsconfig.is_file() {
+                                        warmup_file = Some(tsconfig);
+                                    }
                                 }
-                            }
 
-                            // Open the warmup file if found
-                            if let Some(path) = warmup_file {
-                                debug!(
-                                    extension = %extension,
-                                    warmup_file = %path.display(),
-                                    "Opening file to warm up TypeScript LSP"
-                                );
-                                if let Err(e) = client.notify_file_opened(&path).await {
-                                    warn!(
+                                // Open the warmup file if found
+                                if let Some(path) = warmup_file {
+                                    debug!(
                                         extension = %extension,
                                         warmup_file = %path.display(),
-                                        error = %e,
-                                        "Failed to open warmup file for TypeScript LSP"
+                                        "Opening file to warm up TypeScript LSP"
                                     );
+                                    if let Err(e) = client.notify_file_opened(&path).await {
+                                        warn!(
+                                            extension = %extension,
+                                            warmup_file = %path.display(),
+                                            error = %e,
+                                            "Failed to open warmup file for TypeScript LSP"
+                                        );
+                                    } else {
+                                        // Allow the server a short window to register the project context.
+                                        tokio::time::sleep(std::time::Duration::from_millis(200))
+                                            .await;
+                                    }
                                 } else {
-                                    // Allow the server a short window to register the project context.
-                                    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                                    debug!(
+                                        extension = %extension,
+                                        "No suitable warmup file found for TypeScript LSP"
+                                    );
                                 }
-                            } else {
-                                debug!(
-                                    extension = %extension,
-                                    "No suitable warmup file found for TypeScript LSP"
-                                );
                             }
                         }
-                    }
 
-                    // Send workspace/symbol request to this server
-                    match client
-                        .send_request("workspace/symbol", params.clone())
-                        .await
-                    {
-                        Ok(response) => {
-                            // Extract symbols from response - consume the response to avoid cloning
-                            if let Value::Array(symbols) = response {
-                                debug!(
-                                    extension = %extension,
-                                    symbol_count = symbols.len(),
-                                    "Got workspace symbols from LSP server"
-                                );
+                        // Send workspace/symbol request to this server
+                        match client
+                            .send_request("workspace/symbol", params.clone())
+                            .await
+                        {
+                            Ok(response) => {
+                                // Extract symbols from response - consume the response to avoid cloning
+                                if let Value::Array(symbols) = response {
+                                    debug!(
+                                        extension = %extension,
+                                        symbol_count = symbols.len(),
+                                        "Got workspace symbols from LSP server"
+                                    );
 
-                                // Filter by kind if requested (optimization)
-                                if let Some(target_kind) = kind_filter {
-                                    for symbol in symbols {
-                                        if let Some(kind_num) =
-                                            symbol.get("kind").and_then(|k| k.as_u64())
-                                        {
-                                            if let Some(sym_kind) =
-                                                mill_plugin_api::SymbolKind::from_lsp_kind(kind_num)
+                                    // Filter by kind if requested (optimization)
+                                    if let Some(target_kind) = kind_filter {
+                                        for symbol in symbols {
+                                            if let Some(kind_num) =
+                                                symbol.get("kind").and_then(|k| k.as_u64())
                                             {
-                                                if sym_kind == target_kind {
-                                                    all_symbols.push(symbol);
+                                                if let Some(sym_kind) =
+                                                    mill_plugin_api::SymbolKind::from_lsp_kind(
+                                                        kind_num,
+                                                    )
+                                                {
+                                                    if sym_kind == target_kind {
+                                                        all_symbols.push(symbol);
+                                                    }
                                                 }
                                             }
                                         }
+                                    } else {
+                                        all_symbols.extend(symbols);
                                     }
-                                } else {
-                                    all_symbols.extend(symbols);
-                                }
 
-                                queried_servers.push(extension.clone());
+                                    queried_servers.push(extension.clone());
 
-                                // Prevent unbounded symbol collection
-                                if all_symbols.len() >= MAX_WORKSPACE_SYMBOLS {
-                                    debug!(
-                                        symbol_count = all_symbols.len(),
-                                        "Reached maximum workspace symbol limit, stopping collection"
-                                    );
-                                    break;
+                                    // Prevent unbounded symbol collection
+                                    if all_symbols.len() >= MAX_WORKSPACE_SYMBOLS {
+                                        debug!(
+                                            symbol_count = all_symbols.len(),
+                                            "Reached maximum workspace symbol limit, stopping collection"
+                                        );
+                                        break;
+                                    }
                                 }
                             }
-                        }
-                        Err(e) => {
-                            // Log error but continue with other servers
-                            warn!(
-                                extension = %extension,
-                                error = %e,
-                                "Failed to get workspace symbols from LSP server"
-                            );
+                            Err(e) => {
+                                // Log error but continue with other servers
+                                warn!(
+                                    extension = %extension,
+                                    error = %e,
+                                    "Failed to get workspace symbols from LSP server"
+                                );
+                            }
                         }
                     }
                 }
@@ -502,23 +932,22 @@ impl DirectLspAdapter {
         let mut errors = Vec::new();
 
         // Drain all clients and shutdown
-        for (extension, client) in clients_map.drain() {
+        for ((server, _root), entry) in clients_map.drain() {
+            let client = entry.client;
             let strong_count = Arc::strong_count(&client);
+            let label = self.server_label(server);
 
             // Force shutdown (kill + wait) to prevent zombies
             if let Err(e) = client.force_shutdown().await {
                 warn!(
-                    extension = %extension,
+                    server = %label,
                     error = %e,
                     "Failed to force shutdown LSP client during adapter shutdown"
                 );
-                errors.push(format!(
-                    "Failed to force shutdown {} client: {}",
-                    extension, e
-                ));
+                errors.push(format!("Failed to force shutdown {} client: {}", label, e));
             } else {
                 debug!(
-                    extension = %extension,
+                    server = %label,
                     arc_strong_count = strong_count,
                     "Force shutdown LSP client completed during adapter shutdown"
                 );
@@ -532,28 +961,20 @@ impl DirectLspAdapter {
         }
     }
 
-    /// Extract file extension from LSP params
-    fn extract_extension_from_params(&self, params: &Value, method: &str) -> Option<String> {
+    /// Extract the file path from `textDocument.uri` in LSP params
+    fn extract_file_path_from_params(&self, params: &Value, method: &str) -> Option<PathBuf> {
         // For workspace-level operations, no longer needed since we handle them specially
-        match method {
-            "workspace/symbol" => {
-                // This path should not be reached anymore - handled in request() method
-                warn!("extract_extension_from_params called for workspace/symbol - should be handled specially");
-                None
-            }
-            _ => {
-                // For file-specific operations, extract from textDocument.uri
-                if let Some(uri) = params.get("textDocument")?.get("uri")?.as_str() {
-                    if uri.starts_with("file://") {
-                        let path = uri.trim_start_matches("file://");
-                        return std::path::Path::new(path)
-                            .extension()?
-                            .to_str()
-                            .map(|s| s.to_string());
-                    }
-                }
-                None
-            }
+        if method == "workspace/symbol" {
+            // This path should not be reached anymore - handled in request() method
+            warn!("extract_file_path_from_params called for workspace/symbol - should be handled specially");
+            return None;
+        }
+        // For file-specific operations, extract from textDocument.uri
+        let uri = params.get("textDocument")?.get("uri")?.as_str()?;
+        if uri.starts_with("file://") {
+            Self::uri_to_path(uri)
+        } else {
+            None
         }
     }
 
@@ -575,8 +996,9 @@ impl DirectLspAdapter {
             .and_then(|e| e.to_str())
             .ok_or_else(|| format!("Could not get extension from path: {}", old_path.display()))?;
 
-        // Get or create LSP client for this extension
-        let client = self.get_or_create_client(extension).await?;
+        // Get or create the rename server's client for the file's project root
+        let server = self.servers_for_method(extension, "workspace/willRenameFiles")?[0];
+        let client = self.client_for(server, Some(old_path)).await?;
 
         // Check if the server supports willRenameFiles
         // TypeScript LSP supports this via fileOperations.willRename capability
@@ -681,6 +1103,116 @@ impl DirectLspAdapter {
         files.into_iter().collect()
     }
 
+    /// Send a file request to one client
+    async fn send_to_client(
+        client: &mill_lsp::lsp_system::LspClient,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, String> {
+        // Check capabilities before sending requests that may not be supported
+        if method == "textDocument/diagnostic" && !client.supports_diagnostic_pull().await {
+            // Fall back to cached diagnostics from publishDiagnostics notifications
+            debug!("LSP server doesn't support pull-model diagnostics, using cached diagnostics");
+
+            // Extract URI from params
+            let uri = params
+                .get("textDocument")
+                .and_then(|td| td.get("uri"))
+                .and_then(|u| u.as_str())
+                .ok_or_else(|| {
+                    "Missing textDocument.uri in textDocument/diagnostic params".to_string()
+                })?;
+
+            // Parse URI string into lsp_types::Uri
+            let uri_parsed = uri
+                .parse::<lsp_types::Uri>()
+                .map_err(|e| format!("Failed to parse URI '{}': {}", uri, e))?;
+
+            // Get cached diagnostics for this file
+            if let Some(diagnostics) = client.get_cached_diagnostics(&uri_parsed).await {
+                debug!(
+                    uri = %uri,
+                    diagnostic_count = diagnostics.len(),
+                    "Returning cached diagnostics"
+                );
+
+                // Return diagnostics in LSP pull-model format
+                return Ok(json!({
+                    "items": diagnostics
+                }));
+            } else {
+                // No cached diagnostics - return empty set to avoid hard failure
+                debug!(
+                    uri = %uri,
+                    "No cached diagnostics available; returning empty diagnostics"
+                );
+                return Ok(json!({
+                    "items": []
+                }));
+            }
+        }
+
+        // Send LSP method DIRECTLY to client (bypassing old manager and its hard-coded mappings!)
+        client
+            .send_request(method, params)
+            .await
+            .map_err(|e| format!("LSP request failed: {}", e))
+    }
+
+    /// Send a file request to several servers and merge their answers
+    ///
+    /// Servers that fail are skipped; the request fails only when all of them do.
+    async fn request_merged(
+        &self,
+        servers: &[usize],
+        file_path: &Path,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, String> {
+        let mut requests = Vec::new();
+        for &server in servers {
+            let params = params.clone();
+            requests.push(async move {
+                let name = self.config.servers[server].display_name();
+                let response = match self.client_for(server, Some(file_path)).await {
+                    Ok(client) => Self::send_to_client(&client, method, params).await,
+                    Err(e) => Err(e),
+                };
+                (name, response)
+            });
+        }
+
+        let mut responses = Vec::new();
+        let mut errors = Vec::new();
+        for (name, response) in futures::future::join_all(requests).await {
+            match response {
+                Ok(value) => responses.push((name, value)),
+                Err(e) => {
+                    warn!(
+                        server = %name,
+                        method,
+                        error = %e,
+                        "LSP server failed, merging answers from the others"
+                    );
+                    errors.push(format!("{}: {}", name, e));
+                }
+            }
+        }
+        if responses.is_empty() {
+            return Err(format!(
+                "LSP request failed on every server: {}",
+                errors.join("; ")
+            ));
+        }
+
+        debug!(
+            method,
+            servers = responses.len(),
+            "Merged LSP answers from multiple servers"
+        );
+        Ok(merge_responses(method, responses))
+    }
+
     /// Convert a file:// URI to a PathBuf
     fn uri_to_path(uri: &str) -> Option<std::path::PathBuf> {
         if !uri.starts_with("file://") {
@@ -695,6 +1227,118 @@ impl DirectLspAdapter {
     }
 }
 
+/// Role covering an LSP method, if any
+fn method_role(method: &str) -> Option<LspRole> {
+    match method {
+        "textDocument/definition"
+        | "textDocument/declaration"
+        | "textDocument/references"
+        | "textDocument/implementation"
+        | "textDocument/typeDefinition"
+        | "textDocument/documentSymbol"
+        | "textDocument/prepareCallHierarchy"
+        | "callHierarchy/incomingCalls"
+        | "callHierarchy/outgoingCalls"
+        | "workspace/symbol" => Some(LspRole::Navigation),
+        "textDocument/hover" => Some(LspRole::Hover),
+        "textDocument/completion" | "textDocument/signatureHelp" => Some(LspRole::Completion),
+        "textDocument/diagnostic" => Some(LspRole::Diagnostics),
+        "textDocument/codeAction" => Some(LspRole::CodeActions),
+        "textDocument/formatting" | "textDocument/rangeFormatting" => Some(LspRole::Formatting),
+        "textDocument/rename" | "textDocument/prepareRename" | "workspace/willRenameFiles" => {
+            Some(LspRole::Rename)
+        }
+        _ => None,
+    }
+}
+
+/// Whether answers to `method` from several servers can be merged
+///
+/// Other methods go to the first server with the method's role.
+fn merges_responses(method: &str) -> bool {
+    matches!(
+        method,
+        "textDocument/definition"
+            | "textDocument/declaration"
+            | "textDocument/references"
+            | "textDocument/implementation"
+            | "textDocument/typeDefinition"
+            | "textDocument/hover"
+            | "textDocument/diagnostic"
+            | "textDocument/codeAction"
+    )
+}
+
+/// Merge answers from several servers, tagging each result with the
+/// `server` that produced it
+///
+/// Hover takes the first non-empty answer in configuration order. Lists are
+/// concatenated, dropping results an earlier server already returned.
+fn merge_responses(method: &str, responses: Vec<(String, Value)>) -> Value {
+    match method {
+        "textDocument/hover" => responses
+            .into_iter()
+            .find(|(_, hover)| !hover.is_null())
+            .map(|(server, mut hover)| {
+                tag_server(&mut hover, &server);
+                hover
+            })
+            .unwrap_or(Value::Null),
+        "textDocument/diagnostic" => {
+            let reports = responses.into_iter().map(|(server, report)| {
+                let items = match report {
+                    Value::Object(mut report) => report.remove("items").unwrap_or(Value::Null),
+                    other => other,
+                };
+                (server, items)
+            });
+            let items = merge_lists(reports, |d| json!([d.get("range"), d.get("message")]));
+            json!({ "kind": "full", "items": items })
+        }
+        "textDocument/codeAction" => Value::Array(merge_lists(responses, |action| {
+            json!([action.get("title"), action.get("kind")])
+        })),
+        _ => Value::Array(merge_lists(responses, |location| {
+            json!([
+                location.get("uri").or_else(|| location.get("targetUri")),
+                location
+                    .get("range")
+                    .or_else(|| location.get("targetSelectionRange"))
+            ])
+        })),
+    }
+}
+
+/// Concatenate list answers, keeping the first result for each `key`
+fn merge_lists(
+    responses: impl IntoIterator<Item = (String, Value)>,
+    key: impl Fn(&Value) -> Value,
+) -> Vec<Value> {
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+    for (server, response) in responses {
+        let items = match response {
+            Value::Array(items) => items,
+            Value::Null => continue,
+            single => vec![single],
+        };
+        for mut item in items {
+            if seen.insert(key(&item).to_string()) {
+                tag_server(&mut item, &server);
+                merged.push(item);
+            }
+        }
+    }
+    merged
+}
+
+/// Record which server produced a result
+fn tag_server(result: &mut Value, server: &str) {
+    if let Value::Object(fields) = result {
+        fields.insert("server".to_string(), json!(server));
+    }
+}
+
 #[async_trait]
 impl LspImportFinder for DirectLspAdapter {
     /// Find all files that import/reference the given file path
@@ -712,7 +1356,10 @@ impl LspImportFinder for DirectLspAdapter {
         // We use a path that preserves the extension but changes the name,
         // which triggers the LSP to compute all import updates needed.
         let hypothetical_new_path = if let Some(parent) = file_path.parent() {
-            let stem = file_path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+            let stem = file_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("file");
             let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
             if ext.is_empty() {
                 parent.join(format!("{}_renamed", stem))
@@ -831,70 +1478,38 @@ impl LspService for DirectLspAdapter {
             return self.query_all_servers_for_workspace_symbols(params).await;
         }
 
-        // Extract extension from params for file-specific operations
-        let extension = self
-            .extract_extension_from_params(&params, method)
+        // Extract the file from params for file-specific operations
+        let file_path = self
+            .extract_file_path_from_params(&params, method)
             .ok_or_else(|| {
                 format!(
-                    "Could not extract file extension from params for method '{}'",
+                    "Could not extract file path from params for method '{}'",
                     method
                 )
             })?;
-
-        // Get appropriate LSP client
-        let client = self.get_or_create_client(&extension).await?;
-
-        // Check capabilities before sending requests that may not be supported
-        if method == "textDocument/diagnostic" && !client.supports_diagnostic_pull().await {
-            // Fall back to cached diagnostics from publishDiagnostics notifications
-            debug!(
-                extension = %extension,
-                "LSP server doesn't support pull-model diagnostics, using cached diagnostics"
-            );
-
-            // Extract URI from params
-            let uri = params
-                .get("textDocument")
-                .and_then(|td| td.get("uri"))
-                .and_then(|u| u.as_str())
-                .ok_or_else(|| {
-                    "Missing textDocument.uri in textDocument/diagnostic params".to_string()
-                })?;
-
-            // Parse URI string into lsp_types::Uri
-            let uri_parsed = uri
-                .parse::<lsp_types::Uri>()
-                .map_err(|e| format!("Failed to parse URI '{}': {}", uri, e))?;
-
-            // Get cached diagnostics for this file
-            if let Some(diagnostics) = client.get_cached_diagnostics(&uri_parsed).await {
-                debug!(
-                    uri = %uri,
-                    diagnostic_count = diagnostics.len(),
-                    "Returning cached diagnostics"
-                );
-
-                // Return diagnostics in LSP pull-model format
-                return Ok(json!({
-                    "items": diagnostics
-                }));
-            } else {
-                // No cached diagnostics - return empty set to avoid hard failure
-                debug!(
-                    uri = %uri,
-                    "No cached diagnostics available; returning empty diagnostics"
-                );
-                return Ok(json!({
-                    "items": []
-                }));
-            }
+        let extension = file_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .ok_or_else(|| {
+                format!(
+                    "Could not extract file extension from params for method '{}'",
+                    method
+                )
+            })?
+            .to_string();
+
+        // Servers answering this method; several are queried only when
+        // their answers can be merged
+        let servers = self.servers_for_method(&extension, method)?;
+        if servers.len() > 1 && merges_responses(method) {
+            return self
+                .request_merged(&servers, &file_path, method, params)
+                .await;
         }
 
-        // Send LSP method DIRECTLY to client (bypassing old manager and its hard-coded mappings!)
-        client
-            .send_request(method, params)
-            .await
-            .map_err(|e| format!("LSP request failed: {}", e))
+        // Get the LSP client for the file's project root
+        let client = self.client_for(servers[0], Some(&file_path)).await?;
+        Self::send_to_client(&client, method, params).await
     }
 
     fn supports_extension(&self, extension: &str) -> bool {
@@ -934,20 +1549,21 @@ impl Drop for DirectLspAdapter {
                 );
 
                 // Drain all clients and attempt shutdown
-                for (extension, client) in clients_map.drain() {
+                for ((server, _root), entry) in clients_map.drain() {
+                    let client = entry.client;
                     let strong_count = Arc::strong_count(&client);
 
                     // Force shutdown (kill + wait) to prevent zombies
                     if let Err(e) = client.force_shutdown().await {
                         tracing::warn!(
-                            extension = %extension,
+                            server,
                             error = %e,
                             arc_strong_count = strong_count,
                             "Failed to force shutdown LSP client from DirectLspAdapter drop"
                         );
                     } else {
                         tracing::debug!(
-                            extension = %extension,
+                            server,
                             arc_strong_count = strong_count,
                             "Force shutdown LSP client completed from DirectLspAdapter drop"
                         );
@@ -971,6 +1587,15 @@ impl mill_handler_api::LspAdapter for DirectLspAdapter {
             .map_err(mill_foundation::errors::MillError::lsp)
     }
 
+    async fn get_or_create_client_for_file(
+        &self,
+        file_path: &Path,
+    ) -> Result<Arc<mill_lsp::lsp_system::LspClient>, mill_foundation::errors::MillError> {
+        self.get_or_create_client_for_file(file_path)
+            .await
+            .map_err(mill_foundation::errors::MillError::lsp)
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -979,3 +1604,309 @@ impl mill_handler_api::LspAdapter for DirectLspAdapter {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal LSP server answering hover requests with its working directory
+    /// and last pushed settings, and diagnostic pulls with one diagnostic named
+    /// after its first argument
+    const FAKE_SERVER: &str = r#"
+import json, os, sys
+
+NAME = sys.argv[1] if len(sys.argv) > 1 else "fake"
+SETTINGS = None
+
+def read():
+    headers = {}
+    while True:
+        line = sys.stdin.buffer.readline()
+        if not line:
+            sys.exit(0)
+        line = line.decode().strip()
+        if not line:
+            break
+        key, value = line.split(":", 1)
+        headers[key.lower()] = value.strip()
+    return json.loads(sys.stdin.buffer.read(int(headers["content-length"])))
+
+def send(message):
+    body = json.dumps(message).encode()
+    sys.stdout.buffer.write(b"Content-Length: %d\r\n\r\n" % len(body) + body)
+    sys.stdout.buffer.flush()
+
+while True:
+    message = read()
+    method = message.get("method")
+    if method == "exit":
+        sys.exit(0)
+    if method == "workspace/didChangeConfiguration":
+        SETTINGS = message["params"]["settings"]
+    if "id" not in message or method is None:
+        continue
+    result = None
+    if method == "initialize":
+        result = {"capabilities": {"hoverProvider": True, "diagnosticProvider": {}}}
+    elif method == "textDocument/hover":
+        result = {"contents": os.getcwd(), "settings": SETTINGS}
+    elif method == "textDocument/diagnostic":
+        span = {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 1}}
+        result = {"kind": "full", "items": [{"range": span, "message": NAME}]}
+    send({"jsonrpc": "2.0", "id": message["id"], "result": result})
+"#;
+
+    async fn hover_root(adapter: &DirectLspAdapter, file: &Path) -> PathBuf {
+        let response = adapter
+            .request(
+                "textDocument/hover",
+                json!({
+                    "textDocument": { "uri": format!("file://{}", file.display()) },
+                    "position": { "line": 0, "character": 0 }
+                }),
+            )
+            .await
+            .unwrap();
+        PathBuf::from(response["contents"].as_str().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_clients_per_project_root_with_lru_eviction() {
+        if std::process::Command::new("python3")
+            .arg("--version")
+            .output()
+            .is_err()
+        {
+            eprintln!("python3 not available, skipping");
+            return;
+        }
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let server = root.join("fake_lsp.py");
+        std::fs::write(&server, FAKE_SERVER).unwrap();
+        std::fs::write(root.join("tsconfig.json"), "{}").unwrap();
+        for project in ["apps/web", "apps/admin"] {
+            std::fs::create_dir_all(root.join(project).join("src")).unwrap();
+            std::fs::write(root.join(project).join("tsconfig.json"), "{}").unwrap();
+        }
+
+        let config = mill_config::config::LspConfig {
+            servers: vec![mill_config::config::LspServerConfig {
+                extensions: vec!["ts".to_string()],
+                command: vec!["python3".to_string(), server.display().to_string()],
+                root_dir: Some(root.clone()),
+                restart_interval: None,
+                initialization_options: None,
+                name: None,
+                roles: None,
+                settings: None,
+            }],
+            max_instances_per_server: 2,
+            ..Default::default()
+        };
+        let adapter = DirectLspAdapter::new(config, vec!["ts".to_string()], "test".to_string());
+
+        let web = root.join("apps/web/src/main.ts");
+        assert_eq!(hover_root(&adapter, &web).await, root.join("apps/web"));
+        assert_eq!(
+            hover_root(&adapter, &root.join("tools.ts")).await,
+            root.clone()
+        );
+        // Reusing an instance refreshes it in the LRU order
+        assert_eq!(hover_root(&adapter, &web).await, root.join("apps/web"));
+        assert_eq!(adapter.active_roots().await.len(), 2);
+
+        // A third project evicts the least recently used idle instance
+        let admin = root.join("apps/admin/src/main.ts");
+        assert_eq!(hover_root(&adapter, &admin).await, root.join("apps/admin"));
+        let roots: Vec<PathBuf> = adapter
+            .active_roots()
+            .await
+            .into_iter()
+            .map(|(_, root)| root)
+            .collect();
+        assert_eq!(roots, vec![root.join("apps/admin"), root.join("apps/web")]);
+
+        adapter.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reload_settings_notifies_running_servers() {
+        if std::process::Command::new("python3")
+            .arg("--version")
+            .output()
+            .is_err()
+        {
+            eprintln!("python3 not available, skipping");
+            return;
+        }
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let script = root.join("fake_lsp.py");
+        std::fs::write(&script, FAKE_SERVER).unwrap();
+        let file = root.join("main.py");
+        std::fs::write(&file, "x = 1\n").unwrap();
+
+        let config = |settings: Value| mill_config::config::LspConfig {
+            servers: vec![mill_config::config::LspServerConfig {
+                extensions: vec!["py".to_string()],
+                command: vec!["python3".to_string(), script.display().to_string()],
+                root_dir: Some(root.clone()),
+                restart_interval: None,
+                initialization_options: None,
+                name: None,
+                roles: None,
+                settings: Some(settings),
+            }],
+            ..Default::default()
+        };
+        let adapter = DirectLspAdapter::new(
+            config(json!({ "python.venvPath": ".venv" })),
+            vec!["py".to_string()],
+            "test".to_string(),
+        );
+        let hover_settings = || async {
+            adapter
+                .request(
+                    "textDocument/hover",
+                    json!({
+                        "textDocument": { "uri": format!("file://{}", file.display()) },
+                        "position": { "line": 0, "character": 0 }
+                    }),
+                )
+                .await
+                .unwrap()["settings"]
+                .clone()
+        };
+
+        // Configured settings are pushed once the server is initialized
+        assert_eq!(
+            hover_settings().await,
+            json!({ "python.venvPath": ".venv" })
+        );
+
+        let reloaded = config(json!({ "python.venvPath": "env" }));
+        assert_eq!(adapter.reload_settings(&reloaded).await, 1);
+        assert_eq!(hover_settings().await, json!({ "python.venvPath": "env" }));
+        assert_eq!(adapter.reload_settings(&reloaded).await, 0);
+
+        adapter.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_merge_responses() {
+        let span =
+            json!({ "start": { "line": 1, "character": 0 }, "end": { "line": 1, "character": 3 } });
+        let merged = merge_responses(
+            "textDocument/references",
+            vec![
+                (
+                    "pyright".to_string(),
+                    json!([{ "uri": "file:///a.py", "range": span }]),
+                ),
+                (
+                    "ruff".to_string(),
+                    json!([
+                        { "uri": "file:///a.py", "range": span },
+                        { "uri": "file:///b.py", "range": span }
+                    ]),
+                ),
+            ],
+        );
+        assert_eq!(
+            merged,
+            json!([
+                { "uri": "file:///a.py", "range": span, "server": "pyright" },
+                { "uri": "file:///b.py", "range": span, "server": "ruff" }
+            ])
+        );
+
+        let hover = merge_responses(
+            "textDocument/hover",
+            vec![
+                ("pyright".to_string(), Value::Null),
+                ("ruff".to_string(), json!({ "contents": "x" })),
+            ],
+        );
+        assert_eq!(hover, json!({ "contents": "x", "server": "ruff" }));
+    }
+
+    #[tokio::test]
+    async fn test_cooperating_servers_by_role() {
+        if std::process::Command::new("python3")
+            .arg("--version")
+            .output()
+            .is_err()
+        {
+            eprintln!("python3 not available, skipping");
+            return;
+        }
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let script = root.join("fake_lsp.py");
+        std::fs::write(&script, FAKE_SERVER).unwrap();
+        let file = root.join("main.py");
+        std::fs::write(&file, "x = 1\n").unwrap();
+
+        let server = |name: &str, roles: Vec<LspRole>| mill_config::config::LspServerConfig {
+            extensions: vec!["py".to_string()],
+            command: vec![
+                "python3".to_string(),
+                script.display().to_string(),
+                name.to_string(),
+            ],
+            root_dir: Some(root.clone()),
+            restart_interval: None,
+            initialization_options: None,
+            name: Some(name.to_string()),
+            roles: Some(roles),
+            settings: None,
+        };
+        let config = mill_config::config::LspConfig {
+            servers: vec![
+                server("nav", vec![LspRole::Hover, LspRole::Diagnostics]),
+                server("lint", vec![LspRole::Diagnostics]),
+            ],
+            ..Default::default()
+        };
+        let adapter = DirectLspAdapter::new(config, vec!["py".to_string()], "test".to_string());
+        let text_document = json!({ "uri": format!("file://{}", file.display()) });
+
+        // Diagnostics fan out to both servers and carry their provenance
+        let report = adapter
+            .request(
+                "textDocument/diagnostic",
+                json!({ "textDocument": text_document }),
+            )
+            .await
+            .unwrap();
+        let sources: Vec<(&str, &str)> = report["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| {
+                (
+                    d["message"].as_str().unwrap(),
+                    d["server"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(sources, vec![("nav", "nav"), ("lint", "lint")]);
+
+        // Hover has a single server, whose answer passes through untagged
+        let hover = hover_root(&adapter, &file).await;
+        assert_eq!(hover, root);
+
+        // No server takes formatting
+        let error = adapter
+            .request(
+                "textDocument/formatting",
+                json!({ "textDocument": text_document, "options": {} }),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.contains("No LSP server with role"), "{}", error);
+
+        adapter.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,171 @@
+//! Project root detection for LSP servers
+//!
+//! In a monorepo each nested project needs its own language server instance
+//! rooted at the project, not at the repository. The root of a file is found
+//! by walking up from it to the nearest project marker:
+//!
+//! - TypeScript/JavaScript: `tsconfig.json` or `jsconfig.json`
+//! - Rust: the Cargo workspace containing the nearest `Cargo.toml`
+//! - Python: `pyproject.toml`
+
+use std::path::{Path, PathBuf};
+
+const TYPESCRIPT_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mts", "cts", "mjs", "cjs"];
+const TYPESCRIPT_MARKERS: &[&str] = &["tsconfig.json", "jsconfig.json"];
+
+/// Project root of `file` for a server handling `extensions`
+///
+/// Returns `None` when the server's language has no known marker or no
+/// marker exists above the file; callers then use the configured root.
+pub fn detect_project_root(file: &Path, extensions: &[String]) -> Option<PathBuf> {
+    let file = absolute(file);
+    let start = file.parent()?;
+    let serves = |candidates: &[&str]| extensions.iter().any(|e| candidates.contains(&e.as_str()));
+
+    if serves(&["rs"]) {
+        cargo_workspace_root(start)
+    } else if serves(TYPESCRIPT_EXTENSIONS) {
+        nearest_with(start, TYPESCRIPT_MARKERS)
+    } else if serves(&["py", "pyi"]) {
+        nearest_with(start, &["pyproject.toml"])
+    } else {
+        None
+    }
+}
+
+/// Make `path` absolute against the current directory, without touching the filesystem
+pub fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+}
+
+/// Nearest ancestor of `start` (inclusive) containing one of `markers`
+fn nearest_with(start: &Path, markers: &[&str]) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| markers.iter().any(|marker| dir.join(marker).is_file()))
+        .map(Path::to_path_buf)
+}
+
+/// Root of the Cargo workspace owning the nearest package above `start`
+///
+/// Like Cargo, this is the nearest ancestor manifest with a `[workspace]`
+/// table, unless that workspace excludes the package; a package outside any
+/// workspace is its own root.
+fn cargo_workspace_root(start: &Path) -> Option<PathBuf> {
+    let package = nearest_with(start, &["Cargo.toml"])?;
+    for dir in package.ancestors() {
+        let Ok(content) = std::fs::read_to_string(dir.join("Cargo.toml")) else {
+            continue;
+        };
+        let Ok(manifest) = content.parse::<toml::Table>() else {
+            continue;
+        };
+        let Some(workspace) = manifest.get("workspace").and_then(|w| w.as_table()) else {
+            continue;
+        };
+        let relative = package.strip_prefix(dir).unwrap_or(&package);
+        let excluded = workspace
+            .get("exclude")
+            .and_then(|e| e.as_array())
+            .is_some_and(|exclude| {
+                exclude
+                    .iter()
+                    .filter_map(|e| e.as_str())
+                    .any(|e| relative.starts_with(e.trim_start_matches("./")))
+            });
+        return Some(if excluded { package } else { dir.to_path_buf() });
+    }
+    Some(package)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extensions(list: &[&str]) -> Vec<String> {
+        list.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_typescript_nearest_tsconfig() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("apps/web/src")).unwrap();
+        std::fs::create_dir_all(root.join("tools")).unwrap();
+        std::fs::write(root.join("tsconfig.json"), "{}").unwrap();
+        std::fs::write(root.join("apps/web/tsconfig.json"), "{}").unwrap();
+
+        let ts = extensions(&["ts", "tsx", "js", "jsx"]);
+        assert_eq!(
+            detect_project_root(&root.join("apps/web/src/main.ts"), &ts),
+            Some(root.join("apps/web"))
+        );
+        assert_eq!(
+            detect_project_root(&root.join("tools/build.ts"), &ts),
+            Some(root.to_path_buf())
+        );
+        assert_eq!(
+            detect_project_root(&root.join("apps/web/src/main.go"), &extensions(&["go"])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rust_workspace_root() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("crates/core/src")).unwrap();
+        std::fs::create_dir_all(root.join("fixtures/sample/src")).unwrap();
+        std::fs::create_dir_all(root.join("examples/demo/src")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"fixtures\"]\n",
+        )
+        .unwrap();
+        for package in ["crates/core", "fixtures/sample"] {
+            std::fs::write(
+                root.join(package).join("Cargo.toml"),
+                "[package]\nname = \"p\"\n",
+            )
+            .unwrap();
+        }
+        std::fs::write(
+            root.join("examples/demo/Cargo.toml"),
+            "[package]\nname = \"demo\"\n\n[workspace]\n",
+        )
+        .unwrap();
+
+        let rs = extensions(&["rs"]);
+        assert_eq!(
+            detect_project_root(&root.join("crates/core/src/lib.rs"), &rs),
+            Some(root.to_path_buf())
+        );
+        assert_eq!(
+            detect_project_root(&root.join("fixtures/sample/src/lib.rs"), &rs),
+            Some(root.join("fixtures/sample"))
+        );
+        assert_eq!(
+            detect_project_root(&root.join("examples/demo/src/main.rs"), &rs),
+            Some(root.join("examples/demo"))
+        );
+    }
+
+    #[test]
+    fn test_python_pyproject() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("services/api/app")).unwrap();
+        std::fs::write(root.join("services/api/pyproject.toml"), "[project]\n").unwrap();
+
+        assert_eq!(
+            detect_project_root(&root.join("services/api/app/main.py"), &extensions(&["py"])),
+            Some(root.join("services/api"))
+        );
+    }
+}
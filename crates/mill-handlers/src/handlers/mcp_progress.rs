@@ -0,0 +1,209 @@
+//! MCP progress notifications
+//!
+//! When a `tools/call` carries `_meta.progressToken`, the dispatcher runs the
+//! tool inside a [`ProgressReporter`] that turns progress reports from the
+//! services (reference scanning, applying edits) and `$/progress` updates from
+//! language servers (e.g. rust-analyzer indexing) into `notifications/progress`.
+
+use super::lsp_adapter::DirectLspAdapter;
+use mill_foundation::core::model::mcp::McpNotification;
+use mill_foundation::core::progress::{ProgressReporter, ProgressUpdate};
+use mill_transport::NotificationSender;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Method of the MCP progress notification
+pub const PROGRESS_NOTIFICATION: &str = "notifications/progress";
+
+/// Minimum time between notifications, except for the end of a phase
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The progress token of a `tools/call`, if the client asked for progress
+pub fn progress_token(params: &Value) -> Option<Value> {
+    params
+        .get("_meta")?
+        .get("progressToken")
+        .filter(|token| token.is_string() || token.is_number())
+        .cloned()
+}
+
+/// A reporter sending `notifications/progress` for `token`
+pub fn reporter(token: Value, notifications: NotificationSender) -> ProgressReporter {
+    let sequence = Mutex::new(ProgressSequence::default());
+    ProgressReporter::new(move |update| {
+        let next = sequence
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .next(&update, Instant::now());
+        let Some((progress, total)) = next else {
+            return;
+        };
+        let mut params = json!({
+            "progressToken": token,
+            "progress": progress,
+            "message": update.message,
+        });
+        if let Some(total) = total {
+            params["total"] = json!(total);
+        }
+        let _ = notifications.send(McpNotification {
+            jsonrpc: "2.0".to_string(),
+            method: PROGRESS_NOTIFICATION.to_string(),
+            params: Some(params),
+        });
+    })
+}
+
+/// Forwards language server progress while alive
+///
+/// Dropping it stops the forwarding, so it ends with the tool call even when
+/// the call is cancelled.
+pub struct LspProgressForwarder(JoinHandle<()>);
+
+impl Drop for LspProgressForwarder {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Forward language server `$/progress` updates to `reporter` until the
+/// returned forwarder is dropped
+pub fn forward_lsp_progress(
+    adapter: &DirectLspAdapter,
+    reporter: ProgressReporter,
+) -> LspProgressForwarder {
+    let mut updates = adapter.subscribe_progress();
+    LspProgressForwarder(tokio::spawn(async move {
+        loop {
+            let (token, info) = match updates.recv().await {
+                Ok(update) => update,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let label = info.title.unwrap_or(token);
+            let message = match info.message {
+                Some(message) => format!("{}: {}", label, message),
+                None => label,
+            };
+            match info.percentage {
+                Some(percentage) => reporter.report(percentage.into(), Some(100), message),
+                None => reporter.report(0, None, message),
+            }
+        }
+    }))
+}
+
+/// Turns per-phase updates into a strictly increasing MCP progress value
+///
+/// Services count from zero for each phase (scanning, then applying), while
+/// MCP requires `progress` to increase with every notification, so phases are
+/// laid end to end.
+#[derive(Debug, Default)]
+struct ProgressSequence {
+    offset: u64,
+    last_done: u64,
+    last_total: Option<u64>,
+    last_sent: Option<(u64, Instant)>,
+}
+
+impl ProgressSequence {
+    /// `(progress, total)` to send for `update`, or `None` to skip it
+    fn next(&mut self, update: &ProgressUpdate, now: Instant) -> Option<(u64, Option<u64>)> {
+        if update.done < self.last_done {
+            self.offset += self.last_total.unwrap_or(0).max(self.last_done);
+        }
+        self.last_done = update.done;
+        self.last_total = update.total;
+
+        let progress = self.offset + update.done;
+        let phase_finished = update.total == Some(update.done);
+        if let Some((sent, at)) = self.last_sent {
+            if progress <= sent || (!phase_finished && now.duration_since(at) < MIN_INTERVAL) {
+                return None;
+            }
+        }
+        self.last_sent = Some((progress, now));
+        Some((progress, update.total.map(|total| self.offset + total)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(done: u64, total: u64) -> ProgressUpdate {
+        ProgressUpdate {
+            done,
+            total: Some(total),
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_phases_increase_and_updates_are_throttled() {
+        let mut sequence = ProgressSequence::default();
+        let start = Instant::now();
+        let later = |ms| start + Duration::from_millis(ms);
+
+        assert_eq!(sequence.next(&update(1, 4), start), Some((1, Some(4))));
+        // Too soon after the previous notification
+        assert_eq!(sequence.next(&update(2, 4), later(10)), None);
+        assert_eq!(sequence.next(&update(3, 4), later(200)), Some((3, Some(4))));
+        // The end of a phase is always sent
+        assert_eq!(sequence.next(&update(4, 4), later(210)), Some((4, Some(4))));
+
+        // A new phase continues after the previous one
+        assert_eq!(sequence.next(&update(0, 2), later(400)), None);
+        assert_eq!(sequence.next(&update(1, 2), later(600)), Some((5, Some(6))));
+        assert_eq!(sequence.next(&update(2, 2), later(610)), Some((6, Some(6))));
+    }
+
+    #[tokio::test]
+    async fn test_reporter_sends_progress_notifications() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let reporter = reporter(json!("tok-1"), tx);
+
+        reporter
+            .scope(async {
+                mill_foundation::core::progress::report(2, Some(5), "Scanning src/lib.rs");
+            })
+            .await;
+
+        let notification = rx.try_recv().unwrap();
+        assert_eq!(notification.method, PROGRESS_NOTIFICATION);
+        assert_eq!(
+            notification.params.unwrap(),
+            json!({
+                "progressToken": "tok-1",
+                "progress": 2,
+                "total": 5,
+                "message": "Scanning src/lib.rs",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forwarding_stops_when_dropped() {
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let forwarder = LspProgressForwarder(tokio::spawn(async move {
+            let _tx = tx;
+            std::future::pending::<()>().await;
+        }));
+
+        drop(forwarder);
+        // The task is aborted, dropping its end of the channel
+        assert!(rx.await.is_err());
+    }
+
+    #[test]
+    fn test_progress_token() {
+        assert_eq!(
+            progress_token(&json!({ "name": "x", "_meta": { "progressToken": 7 } })),
+            Some(json!(7))
+        );
+        assert_eq!(progress_token(&json!({ "name": "x" })), None);
+    }
+}
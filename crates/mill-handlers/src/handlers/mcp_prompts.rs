@@ -0,0 +1,419 @@
+//! MCP prompts
+//!
+//! Serves `prompts/list` and `prompts/get`. Prompts are refactoring playbooks
+//! written as TOML files; a few are built in and projects can add their own,
+//! or override a built-in by reusing its name, in `.typemill/prompts/*.toml`.
+//!
+//! A prompt's `template` is text with `{{name}}` placeholders (`{{name|fallback}}`
+//! when the value may be missing). Placeholders refer to prompt arguments or to
+//! `[[context]]` entries, which are filled with project data before rendering:
+//! the result of a read-only tool such as `search_code`, or a `mill://` resource.
+//!
+//! ```toml
+//! name = "review_symbol"
+//! description = "Review how a symbol is used"
+//! template = """
+//! Review `{{symbol}}`. It is referenced here:
+//! {{matches}}
+//! """
+//!
+//! [[arguments]]
+//! name = "symbol"
+//! required = true
+//!
+//! [[context]]
+//! name = "matches"
+//! tool = "search_code"
+//! arguments = { query = "{{symbol}}" }
+//! ```
+
+use super::mcp_resources::RESOURCE_SCHEME;
+use mill_foundation::core::model::mcp::ToolCall;
+use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Directory, relative to the project root, holding project prompts
+pub const PROMPTS_DIR: &str = ".typemill/prompts";
+
+/// Tools a prompt may run to gather context; none of them modify the project
+const CONTEXT_TOOLS: &[&str] = &["search_code", "inspect_code"];
+
+/// Longest context value embedded in a prompt, in characters
+const MAX_CONTEXT_CHARS: usize = 8000;
+
+const BUILTIN_PROMPTS: &[&str] = &[
+    include_str!("prompt_templates/safe_rename_public_api.toml"),
+    include_str!("prompt_templates/split_large_module.toml"),
+    include_str!("prompt_templates/extract_crate.toml"),
+];
+
+/// A prompt argument supplied by the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptArgument {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Project data gathered before rendering a prompt
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptContext {
+    /// Placeholder the data is rendered into
+    pub name: String,
+    /// Read-only tool to call
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Tool arguments; string values may contain placeholders
+    #[serde(default)]
+    pub arguments: Option<Value>,
+    /// `mill://` resource to read; may contain placeholders
+    #[serde(default)]
+    pub resource: Option<String>,
+    /// Arguments that must be provided for this context to be gathered
+    #[serde(default)]
+    pub requires: Vec<String>,
+}
+
+/// Where a context value comes from, with placeholders filled in
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContextSource {
+    Tool(ToolCall),
+    Resource(String),
+}
+
+/// A parameterized prompt
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptDefinition {
+    /// Defaults to the file name without extension
+    #[serde(default)]
+    pub name: String,
+    pub description: String,
+    pub template: String,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgument>,
+    #[serde(default)]
+    pub context: Vec<PromptContext>,
+}
+
+impl PromptDefinition {
+    /// Parse and validate a prompt file
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let prompt: Self = toml::from_str(text).map_err(|e| e.to_string())?;
+        for context in &prompt.context {
+            match (&context.tool, &context.resource) {
+                (Some(tool), None) if CONTEXT_TOOLS.contains(&tool.as_str()) => {}
+                (Some(tool), None) => {
+                    return Err(format!(
+                        "context '{}' uses tool '{}'; only read-only tools ({}) are allowed",
+                        context.name,
+                        tool,
+                        CONTEXT_TOOLS.join(", ")
+                    ))
+                }
+                (None, Some(uri)) if uri.starts_with(RESOURCE_SCHEME) => {}
+                _ => {
+                    return Err(format!(
+                        "context '{}' needs either a `tool` or a `resource` ({} URI)",
+                        context.name, RESOURCE_SCHEME
+                    ))
+                }
+            }
+        }
+        Ok(prompt)
+    }
+
+    /// Check the client's arguments and return them by name
+    pub fn bind_arguments(
+        &self,
+        provided: Option<&Value>,
+    ) -> ServerResult<HashMap<String, String>> {
+        let mut values = HashMap::new();
+        if let Some(provided) = provided.and_then(Value::as_object) {
+            for (name, value) in provided {
+                if !self.arguments.iter().any(|a| a.name == *name) {
+                    return Err(ServerError::invalid_request(format!(
+                        "Prompt '{}' has no argument '{}'",
+                        self.name, name
+                    )));
+                }
+                let text = match value {
+                    Value::String(text) => text.clone(),
+                    Value::Null => continue,
+                    other => other.to_string(),
+                };
+                values.insert(name.clone(), text);
+            }
+        }
+        for argument in self.arguments.iter().filter(|a| a.required) {
+            if values.get(&argument.name).is_none_or(|v| v.is_empty()) {
+                return Err(ServerError::invalid_request(format!(
+                    "Missing required argument '{}' for prompt '{}'",
+                    argument.name, self.name
+                )));
+            }
+        }
+        Ok(values)
+    }
+
+    /// Context entries to gather; `None` when a required argument is missing
+    pub fn context_sources(
+        &self,
+        arguments: &HashMap<String, String>,
+    ) -> Vec<(String, Option<ContextSource>)> {
+        self.context
+            .iter()
+            .map(|context| {
+                let available = context
+                    .requires
+                    .iter()
+                    .all(|name| arguments.get(name).is_some_and(|v| !v.is_empty()));
+                let source = available.then(|| match (&context.tool, &context.resource) {
+                    (Some(tool), _) => ContextSource::Tool(ToolCall {
+                        name: tool.clone(),
+                        arguments: context
+                            .arguments
+                            .as_ref()
+                            .map(|args| fill_value(args, arguments)),
+                    }),
+                    (None, Some(uri)) => ContextSource::Resource(render_template(uri, arguments)),
+                    (None, None) => unreachable!("validated by PromptDefinition::parse"),
+                });
+                (context.name.clone(), source)
+            })
+            .collect()
+    }
+
+    /// Render the template with argument and context values
+    pub fn render(&self, values: &HashMap<String, String>) -> String {
+        render_template(&self.template, values)
+    }
+
+    /// Entry for `prompts/list`
+    pub fn to_list_entry(&self) -> Value {
+        json!({
+            "name": self.name,
+            "description": self.description,
+            "arguments": self.arguments,
+        })
+    }
+}
+
+/// Replace `{{name}}` and `{{name|fallback}}` placeholders
+pub fn render_template(template: &str, values: &HashMap<String, String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}").map(|e| start + 2 + e) else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let placeholder = &rest[start + 2..end];
+        let (name, fallback) = match placeholder.split_once('|') {
+            Some((name, fallback)) => (name.trim(), fallback),
+            None => (placeholder.trim(), ""),
+        };
+        match values.get(name).filter(|v| !v.is_empty()) {
+            Some(value) => output.push_str(value),
+            None => output.push_str(fallback),
+        }
+        rest = &rest[end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Fill placeholders in every string of a JSON value
+fn fill_value(value: &Value, values: &HashMap<String, String>) -> Value {
+    match value {
+        Value::String(text) => Value::String(render_template(text, values)),
+        Value::Array(items) => Value::Array(items.iter().map(|v| fill_value(v, values)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), fill_value(v, values)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Text for a context value, shortened to `MAX_CONTEXT_CHARS`
+pub fn format_context(value: &Value) -> String {
+    let value = value.get("content").unwrap_or(value);
+    let text = match value {
+        Value::String(text) => text.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    };
+    truncate(text)
+}
+
+pub(crate) fn truncate(text: String) -> String {
+    match text.char_indices().nth(MAX_CONTEXT_CHARS) {
+        Some((cut, _)) => format!("{}\n... (truncated)", &text[..cut]),
+        None => text,
+    }
+}
+
+/// Loads built-in and project prompts
+pub struct PromptManager {
+    project_root: PathBuf,
+}
+
+impl PromptManager {
+    pub fn new(project_root: PathBuf) -> Self {
+        Self { project_root }
+    }
+
+    /// All prompts by name; project prompts replace built-ins of the same name
+    ///
+    /// Project prompts are read on every call so edits apply without a restart.
+    pub fn load(&self) -> BTreeMap<String, PromptDefinition> {
+        let mut prompts = BTreeMap::new();
+        for text in BUILTIN_PROMPTS {
+            match PromptDefinition::parse(text) {
+                Ok(prompt) => {
+                    prompts.insert(prompt.name.clone(), prompt);
+                }
+                Err(e) => warn!(error = %e, "Invalid built-in prompt"),
+            }
+        }
+        for prompt in load_project_prompts(&self.project_root.join(PROMPTS_DIR)) {
+            prompts.insert(prompt.name.clone(), prompt);
+        }
+        prompts
+    }
+
+    /// Handle `prompts/list`
+    pub fn list(&self) -> Value {
+        let prompts: Vec<Value> = self.load().values().map(|p| p.to_list_entry()).collect();
+        json!({ "prompts": prompts })
+    }
+
+    pub fn find(&self, name: &str) -> ServerResult<PromptDefinition> {
+        self.load()
+            .remove(name)
+            .ok_or_else(|| ServerError::not_found(format!("Unknown prompt: {}", name)))
+    }
+}
+
+fn load_project_prompts(dir: &Path) -> Vec<PromptDefinition> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "toml"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| PromptDefinition::parse(&text));
+            match parsed {
+                Ok(mut prompt) => {
+                    if prompt.name.is_empty() {
+                        prompt.name = path.file_stem()?.to_string_lossy().into_owned();
+                    }
+                    Some(prompt)
+                }
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Skipping invalid project prompt");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_builtin_prompts_parse() {
+        let prompts = PromptManager::new(PathBuf::from("/nonexistent")).load();
+        let names: Vec<&str> = prompts.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            vec![
+                "extract_crate",
+                "safe_rename_public_api",
+                "split_large_module"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_template_with_fallbacks() {
+        let rendered = render_template(
+            "Rename {{ symbol }} in {{dir|the project}}{{missing}}. {{unclosed",
+            &values(&[("symbol", "Foo")]),
+        );
+        assert_eq!(rendered, "Rename Foo in the project. {{unclosed");
+    }
+
+    #[test]
+    fn test_arguments_and_context_sources() {
+        let prompts = PromptManager::new(PathBuf::from("/nonexistent")).load();
+        let prompt = &prompts["safe_rename_public_api"];
+
+        let err = prompt
+            .bind_arguments(Some(&json!({ "symbol": "Foo" })))
+            .unwrap_err();
+        assert!(err.to_string().contains("new_name"));
+
+        let args = prompt
+            .bind_arguments(Some(&json!({ "symbol": "Foo", "new_name": "Bar" })))
+            .unwrap();
+        let sources = prompt.context_sources(&args);
+        assert_eq!(
+            sources[0].1,
+            Some(ContextSource::Tool(ToolCall {
+                name: "search_code".to_string(),
+                arguments: Some(json!({ "query": "Foo", "limit": 50 })),
+            }))
+        );
+        // inspect_code needs file_path, which was not given
+        assert_eq!(sources[1], ("references".to_string(), None));
+    }
+
+    #[test]
+    fn test_project_prompts_override_and_reject_write_tools() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let prompts_dir = dir.path().join(PROMPTS_DIR);
+        std::fs::create_dir_all(&prompts_dir).unwrap();
+        std::fs::write(
+            prompts_dir.join("extract_crate.toml"),
+            "description = \"Team version\"\ntemplate = \"Use our checklist for {{module_path}}\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            prompts_dir.join("danger.toml"),
+            "description = \"x\"\ntemplate = \"x\"\n[[context]]\nname = \"r\"\ntool = \"rename_all\"\n",
+        )
+        .unwrap();
+
+        let prompts = PromptManager::new(dir.path().to_path_buf()).load();
+        assert_eq!(prompts["extract_crate"].description, "Team version");
+        assert!(!prompts.contains_key("danger"));
+    }
+}
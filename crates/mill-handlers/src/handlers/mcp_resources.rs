@@ -0,0 +1,512 @@
+//! MCP resources
+//!
+//! Exposes read-only views of the project through `resources/*` requests:
+//!
+//! - `mill://diagnostics/{path}` - diagnostics cached from LSP `publishDiagnostics`
+//! - `mill://symbols/{path}` - symbol outline from the file's language plugin
+//! - `mill://project/structure` - directories, languages and manifests of the project
+//! - `mill://plans/{id}` - plans returned by dry-run tool calls
+//!
+//! Paths are relative to the project root, and callers only see the files
+//! their token's path scopes allow. Plans are only visible to the session
+//! and user whose dry run produced them. Clients subscribed to a diagnostics
+//! resource receive `notifications/resources/updated` whenever the LSP server
+//! publishes different diagnostics for that file.
+
+use super::authorization::ReadScope;
+use super::lsp_adapter::DirectLspAdapter;
+use super::plugin_dispatcher::AppState;
+use mill_foundation::core::model::mcp::{McpNotification, McpResource};
+use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
+use mill_transport::{NotificationSender, SessionInfo};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// URI scheme of all resources served by mill
+pub const RESOURCE_SCHEME: &str = "mill://";
+
+/// Number of dry-run plans kept for `mill://plans/{id}`
+const MAX_STORED_PLANS: usize = 50;
+
+/// Directory depth reported in `mill://project/structure`
+const STRUCTURE_DEPTH: usize = 2;
+
+/// Manifest files listed in `mill://project/structure`
+const MANIFEST_FILES: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+];
+
+/// A parsed `mill://` URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceUri {
+    Diagnostics(String),
+    Symbols(String),
+    ProjectStructure,
+    Plan(String),
+}
+
+impl ResourceUri {
+    /// Parse a `mill://` URI; paths may be percent-encoded
+    pub fn parse(uri: &str) -> ServerResult<Self> {
+        let rest = uri.strip_prefix(RESOURCE_SCHEME).ok_or_else(|| {
+            ServerError::invalid_request(format!(
+                "Unsupported resource URI '{}': expected {}...",
+                uri, RESOURCE_SCHEME
+            ))
+        })?;
+        let decode = |value: &str| -> ServerResult<String> {
+            let decoded = urlencoding::decode(value)
+                .map_err(|e| ServerError::invalid_request(format!("Invalid resource URI: {}", e)))?
+                .into_owned();
+            if decoded.is_empty() {
+                return Err(ServerError::invalid_request(format!(
+                    "Resource URI '{}' is missing a path",
+                    uri
+                )));
+            }
+            Ok(decoded)
+        };
+        match rest.split_once('/') {
+            Some(("diagnostics", path)) => Ok(Self::Diagnostics(decode(path)?)),
+            Some(("symbols", path)) => Ok(Self::Symbols(decode(path)?)),
+            Some(("plans", id)) => Ok(Self::Plan(decode(id)?)),
+            Some(("project", "structure")) => Ok(Self::ProjectStructure),
+            _ => Err(ServerError::not_found(format!("Unknown resource: {}", uri))),
+        }
+    }
+}
+
+/// A dry-run plan kept for `mill://plans/{id}`
+struct StoredPlan {
+    id: String,
+    /// The user whose dry run produced the plan
+    user_id: Option<String>,
+    /// The session the dry run was made in
+    session_id: Option<String>,
+    /// Files the plan would change
+    files: Vec<String>,
+    plan: Value,
+}
+
+impl StoredPlan {
+    /// Whether the plan belongs to the caller's session
+    fn owned_by(&self, session_info: &SessionInfo) -> bool {
+        self.user_id == session_info.user_id && self.session_id == session_info.session_id
+    }
+}
+
+/// Serves `resources/*` requests and tracks subscriptions
+pub struct ResourceManager {
+    app_state: Arc<AppState>,
+    lsp_adapter: Arc<Mutex<Option<Arc<DirectLspAdapter>>>>,
+    /// Most recent dry-run plans, oldest first
+    plans: Mutex<VecDeque<StoredPlan>>,
+    /// Subscribers by resource URI
+    subscriptions: Mutex<HashMap<String, Vec<NotificationSender>>>,
+}
+
+impl ResourceManager {
+    pub fn new(
+        app_state: Arc<AppState>,
+        lsp_adapter: Arc<Mutex<Option<Arc<DirectLspAdapter>>>>,
+    ) -> Self {
+        Self {
+            app_state,
+            lsp_adapter,
+            plans: Mutex::new(VecDeque::new()),
+            subscriptions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Handle `resources/list`, listing the resources `scope` may read
+    pub async fn list(&self, session_info: &SessionInfo, scope: &ReadScope) -> ServerResult<Value> {
+        let mut resources = Vec::new();
+        if scope.permits_project() {
+            resources.push(McpResource {
+                uri: format!("{}project/structure", RESOURCE_SCHEME),
+                name: Some("Project structure".to_string()),
+                description: Some(
+                    "Directories, languages and manifests of the project".to_string(),
+                ),
+                mime_type: Some("application/json".to_string()),
+            });
+        }
+
+        let adapter = self.lsp_adapter.lock().await.clone();
+        if let Some(adapter) = adapter {
+            let mut files: Vec<String> = adapter
+                .all_cached_diagnostics()
+                .await
+                .into_iter()
+                .filter(|(_, diagnostics)| !diagnostics.is_empty())
+                .filter_map(|(uri, _)| self.relative_path_for_uri(&uri))
+                .filter(|path| scope.permits(path))
+                .collect();
+            files.sort();
+            resources.extend(files.into_iter().map(|path| McpResource {
+                uri: format!("{}diagnostics/{}", RESOURCE_SCHEME, path),
+                name: Some(format!("Diagnostics: {}", path)),
+                description: None,
+                mime_type: Some("application/json".to_string()),
+            }));
+        }
+
+        let plans = self.plans.lock().await;
+        let readable = plans.iter().rev().filter(|stored| {
+            stored.owned_by(session_info) && stored.files.iter().all(|file| scope.permits(file))
+        });
+        resources.extend(readable.map(|stored| McpResource {
+            uri: format!("{}plans/{}", RESOURCE_SCHEME, stored.id),
+            name: Some(format!(
+                "Plan {} ({})",
+                stored.id,
+                stored
+                    .plan
+                    .get("planType")
+                    .and_then(Value::as_str)
+                    .unwrap_or("plan")
+            )),
+            description: None,
+            mime_type: Some("application/json".to_string()),
+        }));
+
+        Ok(json!({ "resources": resources }))
+    }
+
+    /// Handle `resources/templates/list`
+    pub fn list_templates(&self) -> Value {
+        json!({
+            "resourceTemplates": [
+                {
+                    "uriTemplate": format!("{}diagnostics/{{path}}", RESOURCE_SCHEME),
+                    "name": "File diagnostics",
+                    "description": "Diagnostics published by the LSP server for a project-relative file path. Subscribe to be notified when they change.",
+                    "mimeType": "application/json"
+                },
+                {
+                    "uriTemplate": format!("{}symbols/{{path}}", RESOURCE_SCHEME),
+                    "name": "File symbols",
+                    "description": "Outline of the symbols declared in a project-relative file path",
+                    "mimeType": "application/json"
+                },
+                {
+                    "uriTemplate": format!("{}plans/{{id}}", RESOURCE_SCHEME),
+                    "name": "Refactoring plan",
+                    "description": "A plan returned by a dry-run tool call, by its planId",
+                    "mimeType": "application/json"
+                }
+            ]
+        })
+    }
+
+    /// Handle `resources/read`, refusing resources `scope` may not read
+    pub async fn read(
+        &self,
+        params: Option<Value>,
+        session_info: &SessionInfo,
+        scope: &ReadScope,
+    ) -> ServerResult<Value> {
+        let uri = Self::uri_param(params)?;
+        let contents = match ResourceUri::parse(&uri)? {
+            ResourceUri::Diagnostics(path) => {
+                scope.check(&path)?;
+                self.read_diagnostics(&path).await?
+            }
+            ResourceUri::Symbols(path) => {
+                scope.check(&path)?;
+                self.read_symbols(&path).await?
+            }
+            ResourceUri::ProjectStructure => {
+                if !scope.permits_project() {
+                    return Err(ServerError::PermissionDenied {
+                        operation: "token may not read the whole project's structure".to_string(),
+                        required_permission: Some("path:**".to_string()),
+                    });
+                }
+                self.read_structure().await?
+            }
+            ResourceUri::Plan(id) => {
+                let plans = self.plans.lock().await;
+                // Other sessions' plans are reported as missing
+                let stored = plans
+                    .iter()
+                    .find(|stored| stored.id == id && stored.owned_by(session_info))
+                    .ok_or_else(|| {
+                        ServerError::not_found(format!(
+                            "No plan with id '{}' (only the last {} plans are kept)",
+                            id, MAX_STORED_PLANS
+                        ))
+                    })?;
+                for file in &stored.files {
+                    scope.check(file)?;
+                }
+                stored.plan.clone()
+            }
+        };
+
+        let text = serde_json::to_string_pretty(&contents)
+            .map_err(|e| ServerError::internal(format!("Failed to serialize resource: {}", e)))?;
+        Ok(json!({
+            "contents": [{
+                "uri": uri,
+                "mimeType": "application/json",
+                "text": text
+            }]
+        }))
+    }
+
+    /// Handle `resources/subscribe`
+    pub async fn subscribe(
+        &self,
+        params: Option<Value>,
+        session_info: &SessionInfo,
+        scope: &ReadScope,
+    ) -> ServerResult<Value> {
+        let uri = Self::uri_param(params)?;
+        match ResourceUri::parse(&uri)? {
+            ResourceUri::Diagnostics(path) | ResourceUri::Symbols(path) => scope.check(&path)?,
+            _ => {}
+        }
+        let sender = session_info.notifications.clone().ok_or_else(|| {
+            ServerError::not_supported(
+                "This transport cannot deliver resource update notifications",
+            )
+        })?;
+
+        let mut subscriptions = self.subscriptions.lock().await;
+        let subscribers = subscriptions.entry(uri).or_default();
+        if !subscribers.iter().any(|s| s.same_channel(&sender)) {
+            subscribers.push(sender);
+        }
+        Ok(json!({}))
+    }
+
+    /// Handle `resources/unsubscribe`
+    pub async fn unsubscribe(
+        &self,
+        params: Option<Value>,
+        session_info: &SessionInfo,
+    ) -> ServerResult<Value> {
+        let uri = Self::uri_param(params)?;
+        let mut subscriptions = self.subscriptions.lock().await;
+        if let (Some(subscribers), Some(sender)) =
+            (subscriptions.get_mut(&uri), &session_info.notifications)
+        {
+            subscribers.retain(|s| !s.same_channel(sender));
+            if subscribers.is_empty() {
+                subscriptions.remove(&uri);
+            }
+        }
+        Ok(json!({}))
+    }
+
+    /// Notify subscribers that the diagnostics of a `file://` URI changed
+    pub async fn diagnostics_changed(&self, file_uri: &str) {
+        let Some(path) = self.relative_path_for_uri(file_uri) else {
+            return;
+        };
+        let uri = format!("{}diagnostics/{}", RESOURCE_SCHEME, path);
+        let mut subscriptions = self.subscriptions.lock().await;
+        let Some(subscribers) = subscriptions.get_mut(&uri) else {
+            return;
+        };
+
+        let notification = McpNotification {
+            jsonrpc: "2.0".to_string(),
+            method: "notifications/resources/updated".to_string(),
+            params: Some(json!({ "uri": uri })),
+        };
+        // Drop subscribers whose session has gone away
+        subscribers.retain(|sender| sender.send(notification.clone()).is_ok());
+        debug!(uri = %uri, subscribers = subscribers.len(), "Sent resource update notification");
+        if subscribers.is_empty() {
+            subscriptions.remove(&uri);
+        }
+    }
+
+    /// Keep a dry-run plan from a tool result and add its `planId` to the result
+    ///
+    /// Only the session that made the dry run can read the plan.
+    pub async fn record_plan(&self, result: &mut Value, session_info: &SessionInfo) {
+        let Some(content) = result.get_mut("content").and_then(Value::as_object_mut) else {
+            return;
+        };
+        let is_preview = content.get("status").and_then(Value::as_str) == Some("preview");
+        let Some(plan) = content
+            .get("changes")
+            .filter(|c| is_preview && c.is_object())
+        else {
+            return;
+        };
+
+        let files = content
+            .get("filesChanged")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect();
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut plans = self.plans.lock().await;
+        plans.push_back(StoredPlan {
+            id: id.clone(),
+            user_id: session_info.user_id.clone(),
+            session_id: session_info.session_id.clone(),
+            files,
+            plan: plan.clone(),
+        });
+        while plans.len() > MAX_STORED_PLANS {
+            plans.pop_front();
+        }
+        content.insert("planId".to_string(), Value::String(id));
+    }
+
+    fn uri_param(params: Option<Value>) -> ServerResult<String> {
+        params
+            .as_ref()
+            .and_then(|p| p.get("uri"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| ServerError::invalid_request("Missing 'uri' parameter"))
+    }
+
+    /// Resolve a project-relative path, refusing paths outside the project
+    fn resolve(&self, path: &str) -> ServerResult<PathBuf> {
+        self.app_state
+            .file_service
+            .to_absolute_path_checked(Path::new(path))
+    }
+
+    fn relative_path_for_uri(&self, file_uri: &str) -> Option<String> {
+        let path = url::Url::parse(file_uri).ok()?.to_file_path().ok()?;
+        let root = &self.app_state.project_root;
+        let relative = match path.strip_prefix(root) {
+            Ok(relative) => relative.to_path_buf(),
+            // LSP servers may report canonical paths (e.g. with symlinks resolved)
+            Err(_) => path
+                .strip_prefix(root.canonicalize().ok()?)
+                .ok()?
+                .to_path_buf(),
+        };
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    async fn read_diagnostics(&self, path: &str) -> ServerResult<Value> {
+        let absolute = self.resolve(path)?;
+        let file_uri = url::Url::from_file_path(&absolute)
+            .map_err(|_| ServerError::invalid_request(format!("Invalid file path: {}", path)))?;
+        let adapter = self.lsp_adapter.lock().await.clone();
+        let diagnostics = match adapter {
+            Some(adapter) => adapter.cached_diagnostics(file_uri.as_str()).await,
+            None => Vec::new(),
+        };
+        Ok(json!({
+            "path": path,
+            "diagnostics": diagnostics,
+        }))
+    }
+
+    async fn read_symbols(&self, path: &str) -> ServerResult<Value> {
+        let absolute = self.resolve(path)?;
+        let extension = absolute
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        let plugin = self
+            .app_state
+            .language_plugins
+            .get_plugin(extension)
+            .ok_or_else(|| {
+                ServerError::not_supported(format!("No language plugin handles '{}'", path))
+            })?;
+        let source = self.app_state.file_service.read_file(&absolute).await?;
+        let symbols = self
+            .app_state
+            .file_service
+            .ast_cache()
+            .symbols(plugin, &source)
+            .await
+            .map_err(|e| ServerError::internal(format!("Failed to parse {}: {}", path, e)))?;
+        Ok(json!({
+            "path": path,
+            "language": plugin.metadata().name,
+            "symbols": symbols,
+        }))
+    }
+
+    async fn read_structure(&self) -> ServerResult<Value> {
+        let root = self.app_state.project_root.clone();
+        let extensions = self.app_state.language_plugins.supported_extensions();
+        tokio::task::spawn_blocking(move || project_structure(&root, &extensions))
+            .await
+            .map_err(|e| ServerError::internal(format!("Failed to scan project: {}", e)))
+    }
+}
+
+/// Summarize the project: file counts per directory (up to `STRUCTURE_DEPTH`),
+/// per language extension, and the manifests found
+fn project_structure(root: &Path, extensions: &[String]) -> Value {
+    let mut directories: BTreeMap<String, usize> = BTreeMap::new();
+    let mut languages: BTreeMap<String, usize> = BTreeMap::new();
+    let mut manifests = Vec::new();
+    let mut total_files = 0;
+
+    for entry in ignore::WalkBuilder::new(root).build().flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        total_files += 1;
+
+        let components: Vec<String> = relative
+            .parent()
+            .into_iter()
+            .flat_map(|p| p.components())
+            .take(STRUCTURE_DEPTH)
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let directory = if components.is_empty() {
+            ".".to_string()
+        } else {
+            components.join("/")
+        };
+        *directories.entry(directory).or_default() += 1;
+
+        if let Some(extension) = relative.extension().and_then(|e| e.to_str()) {
+            if extensions.iter().any(|e| e == extension) {
+                *languages.entry(extension.to_string()).or_default() += 1;
+            }
+        }
+        let file_name = relative.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if MANIFEST_FILES.contains(&file_name) {
+            manifests.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    manifests.sort();
+
+    json!({
+        "root": root.display().to_string(),
+        "totalFiles": total_files,
+        "directories": directories
+            .into_iter()
+            .map(|(path, files)| json!({ "path": path, "files": files }))
+            .collect::<Vec<_>>(),
+        "languages": languages,
+        "manifests": manifests,
+    })
+}
@@ -1,13 +1,21 @@
 //! MCP tool handlers module
 
+pub mod audit;
+pub mod authorization;
 pub mod common;
 pub mod file_operation_handler;
 pub mod lsp_adapter;
+pub mod lsp_roots;
 pub mod macros;
+pub mod mcp_progress;
+pub mod mcp_prompts;
+pub mod mcp_resources;
 pub mod plugin_dispatcher;
 pub mod prune_ops;
 pub mod refactor_extract;
 pub mod refactor_inline;
+pub mod refactor_reorder;
+pub mod refactor_transform;
 #[path = "relocate_ops/mod.rs"]
 pub mod relocate_ops;
 pub mod rename_ops;
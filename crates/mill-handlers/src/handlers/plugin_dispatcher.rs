@@ -10,7 +10,9 @@
 
 use crate::register_handlers_with_logging;
 use async_trait::async_trait;
-use mill_foundation::core::model::mcp::{McpMessage, McpRequest, McpResponse, ToolCall};
+use mill_foundation::core::model::mcp::{
+    McpMessage, McpPromptsCapability, McpRequest, McpResourcesCapability, McpResponse, ToolCall,
+};
 use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
 use mill_foundation::protocol::AstService;
 use mill_plugin_system::{LspAdapterPlugin, PluginManager};
@@ -24,7 +26,13 @@ use std::time::Instant;
 use tokio::sync::{Mutex, OnceCell};
 use tracing::{debug, error, info, instrument, warn};
 
+use super::audit::audit_record;
+use super::authorization::{self, authorize_tool_call, ReadScope};
 use super::lsp_adapter::DirectLspAdapter;
+use super::mcp_progress;
+use super::mcp_prompts::{format_context, truncate, ContextSource, PromptManager};
+use super::mcp_resources::ResourceManager;
+use super::workspace::SymbolIndex;
 
 /// Application state containing services
 #[derive(Clone)]
@@ -37,6 +45,8 @@ pub struct AppState {
     pub planner: Arc<dyn Planner>,
     /// Workflow executor for running planned workflows
     pub workflow_executor: Arc<dyn WorkflowExecutor>,
+    /// Built-in symbol index of the project root
+    pub symbol_index: Arc<SymbolIndex>,
     /// Project root directory
     pub project_root: std::path::PathBuf,
     /// Lock manager for file-level locking
@@ -51,6 +61,8 @@ pub struct AppState {
     pub language_plugins: crate::LanguagePluginRegistry,
     /// LSP usage mode
     pub lsp_mode: mill_config::config::LspMode,
+    /// Audit log of write operations, if enabled
+    pub audit_log: Option<Arc<mill_services::services::AuditLog>>,
 }
 
 impl AppState {
@@ -216,6 +228,14 @@ impl mill_handler_api::LanguagePluginRegistry for LanguagePluginRegistryWrapper
         self.0.get_plugin_for_manifest(filename)
     }
 
+    fn get_plugin_for_workspace_manifest(
+        &self,
+        file_path: &std::path::Path,
+    ) -> Option<&dyn mill_plugin_api::LanguagePlugin> {
+        let filename = file_path.file_name()?.to_str()?;
+        self.0.get_plugin_for_workspace_manifest(filename)
+    }
+
     fn inner(&self) -> &dyn std::any::Any {
         self.0.inner.as_ref() as &dyn std::any::Any
     }
@@ -231,6 +251,10 @@ pub struct PluginDispatcher {
     lsp_adapter: Arc<Mutex<Option<Arc<DirectLspAdapter>>>>,
     /// Tool handler registry for automatic routing (public for testing)
     pub tool_registry: Arc<Mutex<super::tool_registry::ToolRegistry>>,
+    /// MCP resources and their subscriptions
+    resources: Arc<ResourceManager>,
+    /// MCP prompts (refactoring playbooks)
+    prompts: PromptManager,
     /// Initialization flag
     initialized: OnceCell<()>,
 }
@@ -238,10 +262,13 @@ pub struct PluginDispatcher {
 impl PluginDispatcher {
     /// Creates a new instance of the `PluginDispatcher`.
     pub fn new(app_state: Arc<AppState>, plugin_manager: Arc<PluginManager>) -> Self {
+        let lsp_adapter = Arc::new(Mutex::new(None));
         Self {
             plugin_manager,
+            resources: Arc::new(ResourceManager::new(app_state.clone(), lsp_adapter.clone())),
+            prompts: PromptManager::new(app_state.project_root.clone()),
             app_state,
-            lsp_adapter: Arc::new(Mutex::new(None)),
+            lsp_adapter,
             tool_registry: Arc::new(Mutex::new(super::tool_registry::ToolRegistry::new())),
             initialized: OnceCell::new(),
         }
@@ -295,6 +322,25 @@ impl PluginDispatcher {
                     debug!("Stored unified LSP adapter for all tool handlers");
                 }
 
+                // Send changed server settings when the configuration file is edited
+                unified_lsp_adapter.watch_config_file();
+
+                // Push diagnostics changes to resource subscribers
+                let mut diagnostics_updates = unified_lsp_adapter.subscribe_diagnostics();
+                let resources = self.resources.clone();
+                tokio::spawn(async move {
+                    use tokio::sync::broadcast::error::RecvError;
+                    loop {
+                        match diagnostics_updates.recv().await {
+                            Ok(uri) => resources.diagnostics_changed(&uri).await,
+                            Err(RecvError::Lagged(skipped)) => {
+                                debug!(skipped, "Resource notifier lagged behind diagnostics updates");
+                            }
+                            Err(RecvError::Closed) => break,
+                        }
+                    }
+                });
+
                 for server_config in &lsp_config.servers {
                     if server_config.extensions.is_empty() {
                         warn!(command = ?server_config.command, "LSP server config has no extensions, skipping");
@@ -416,6 +462,30 @@ impl PluginDispatcher {
             "initialized" | "notifications/initialized" => self.handle_initialized().await?,
             "tools/list" => self.handle_list_tools().await?,
             "tools/call" => self.handle_tool_call(request.params, session_info).await?,
+            "resources/list" => {
+                let scope = self.read_scope(session_info)?;
+                self.resources.list(session_info, &scope).await?
+            }
+            "resources/templates/list" => self.resources.list_templates(),
+            "resources/read" => {
+                let scope = self.read_scope(session_info)?;
+                self.resources
+                    .read(request.params, session_info, &scope)
+                    .await?
+            }
+            "resources/subscribe" => {
+                let scope = self.read_scope(session_info)?;
+                self.resources
+                    .subscribe(request.params, session_info, &scope)
+                    .await?
+            }
+            "resources/unsubscribe" => {
+                self.resources
+                    .unsubscribe(request.params, session_info)
+                    .await?
+            }
+            "prompts/list" => self.prompts.list(),
+            "prompts/get" => self.handle_get_prompt(request.params, session_info).await?,
             _ => {
                 return Err(ServerError::not_supported(format!(
                     "Unknown method: {}",
@@ -432,6 +502,11 @@ impl PluginDispatcher {
         }))
     }
 
+    /// What the caller may read through `resources/*`, per its token scopes
+    fn read_scope(&self, session_info: &mill_transport::SessionInfo) -> ServerResult<ReadScope> {
+        ReadScope::new(session_info.scopes.as_ref(), &self.app_state.project_root)
+    }
+
     /// Handle tools/list request - returns only the Magnificent Seven public tools
     #[instrument(skip(self))]
     async fn handle_list_tools(&self) -> ServerResult<Value> {
@@ -451,11 +526,44 @@ impl PluginDispatcher {
 
         let params = params.ok_or_else(|| ServerError::invalid_request("Missing params"))?;
 
+        // Report progress only if the client asked for it and can receive notifications
+        let progress_reporter = mcp_progress::progress_token(&params)
+            .zip(session_info.notifications.clone())
+            .map(|(token, notifications)| mcp_progress::reporter(token, notifications));
+
         let tool_call: ToolCall = serde_json::from_value(params)
             .map_err(|e| ServerError::invalid_request(format!("Invalid tool call: {}", e)))?;
 
         let tool_name = tool_call.name.clone();
 
+        // Enforce the caller's token scopes before anything runs
+        let mut write_scope = None;
+        let mut read_scope = None;
+        if let Some(scopes) = &session_info.scopes {
+            let project_root = &self.app_state.project_root;
+            let authorized = authorize_tool_call(scopes, &tool_call, project_root).and_then(|()| {
+                Ok((
+                    authorization::write_scope(scopes, project_root)?,
+                    ReadScope::new(Some(scopes), project_root)?,
+                ))
+            });
+            match authorized {
+                Ok((write, read)) => {
+                    write_scope = write;
+                    read_scope = Some(read);
+                }
+                Err(e) => {
+                    warn!(
+                        tool_name = %tool_name,
+                        user_id = ?session_info.user_id,
+                        error = %e,
+                        "Tool call denied by token scopes"
+                    );
+                    return Err(e);
+                }
+            }
+        }
+
         // Create concrete context first
         let concrete_context = super::tools::ToolHandlerContext {
             user_id: session_info.user_id.clone(),
@@ -467,12 +575,39 @@ impl PluginDispatcher {
         // Convert to trait-based context for handler compatibility
         let api_context = concrete_context.to_api_context().await;
 
-        let result = self
-            .tool_registry
-            .lock()
-            .await
-            .handle_tool(tool_call, &api_context)
-            .await;
+        // Only hold the registry lock for the lookup so tool calls run concurrently
+        let handler = self.tool_registry.lock().await.handler(&tool_name);
+        // Calls that never reached a handler changed nothing and are not audited
+        let dispatched = handler.is_ok();
+        let call = async {
+            let handler = handler?;
+            let call = handler.handle_tool_call(&api_context, &tool_call);
+            // Plans that update files outside the token's paths are refused
+            match write_scope {
+                Some(scope) => scope.scope(call).await,
+                None => call.await,
+            }
+        };
+        let mut result = match progress_reporter {
+            Some(reporter) => {
+                // Forwarding stops when this is dropped, even if the call is cancelled
+                let _lsp_progress =
+                    self.lsp_adapter.lock().await.as_ref().map(|adapter| {
+                        mcp_progress::forward_lsp_progress(adapter, reporter.clone())
+                    });
+                reporter.scope(call).await
+            }
+            None => call.await,
+        };
+        // Previews must not show files outside the token's paths either
+        if let (Ok(value), Some(scope)) = (&result, &read_scope) {
+            if let Err(e) = scope.check_preview(value) {
+                result = Err(e);
+            }
+        }
+        if let Ok(value) = &mut result {
+            self.resources.record_plan(value, session_info).await;
+        }
 
         let duration = start_time.elapsed();
         match &result {
@@ -495,9 +630,76 @@ impl PluginDispatcher {
             }
         }
 
+        // Record who applied which change
+        if let Some(audit_log) = self.app_state.audit_log.as_ref().filter(|_| dispatched) {
+            let user_id = session_info.user_id.as_deref();
+            if let Some(record) = audit_record(&tool_call, user_id, &result, duration) {
+                if let Err(e) = audit_log.record(&record).await {
+                    error!(tool_name = %tool_name, error = %e, "Failed to write audit record");
+                }
+            }
+        }
+
         result
     }
 
+    /// Handle prompts/get: gather the prompt's context and render it
+    #[instrument(skip(self, params, session_info))]
+    async fn handle_get_prompt(
+        &self,
+        params: Option<Value>,
+        session_info: &mill_transport::SessionInfo,
+    ) -> ServerResult<Value> {
+        let params = params.ok_or_else(|| ServerError::invalid_request("Missing params"))?;
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ServerError::invalid_request("Missing 'name' parameter"))?;
+
+        let prompt = self.prompts.find(name)?;
+        let mut values = prompt.bind_arguments(params.get("arguments"))?;
+
+        for (context_name, source) in prompt.context_sources(&values) {
+            let text = match source {
+                None => "(not gathered: required arguments were not provided)".to_string(),
+                Some(ContextSource::Tool(call)) => {
+                    let call = serde_json::to_value(call)
+                        .map_err(|e| ServerError::internal(e.to_string()))?;
+                    match self.handle_tool_call(Some(call), session_info).await {
+                        Ok(result) => format_context(&result),
+                        Err(e) => format!("(unavailable: {})", e),
+                    }
+                }
+                Some(ContextSource::Resource(uri)) => {
+                    let read = async {
+                        let scope = self.read_scope(session_info)?;
+                        self.resources
+                            .read(Some(json!({ "uri": uri })), session_info, &scope)
+                            .await
+                    };
+                    match read.await {
+                        Ok(result) => truncate(
+                            result["contents"][0]["text"]
+                                .as_str()
+                                .unwrap_or_default()
+                                .to_string(),
+                        ),
+                        Err(e) => format!("(unavailable: {})", e),
+                    }
+                }
+            };
+            values.insert(context_name, text);
+        }
+
+        Ok(json!({
+            "description": prompt.description,
+            "messages": [{
+                "role": "user",
+                "content": { "type": "text", "text": prompt.render(&values) }
+            }]
+        }))
+    }
+
     /// Handle MCP initialize request
     async fn handle_initialize(&self, _params: Option<Value>) -> ServerResult<Value> {
         debug!("Handling MCP initialize request");
@@ -506,7 +708,14 @@ impl PluginDispatcher {
         Ok(json!({
             "protocolVersion": "2025-06-18",
             "capabilities": {
-                "tools": {}
+                "tools": {},
+                "resources": McpResourcesCapability {
+                    subscribe: Some(true),
+                    list_changed: Some(false),
+                },
+                "prompts": McpPromptsCapability {
+                    list_changed: Some(false),
+                }
             },
             "serverInfo": {
                 "name": "mill",
@@ -599,7 +808,11 @@ impl McpDispatcher for PluginDispatcher {
     ) -> mill_foundation::errors::MillResult<McpMessage> {
         self.dispatch(message, session_info)
             .await
-            .map_err(|e| mill_foundation::errors::MillError::internal(e.to_string()))
+            .map_err(|e| match e {
+                // Denials stay structured so clients can tell them apart
+                mill_foundation::errors::MillError::PermissionDenied { .. } => e,
+                e => mill_foundation::errors::MillError::internal(e.to_string()),
+            })
     }
 }
 
@@ -610,7 +823,9 @@ pub async fn create_test_dispatcher() -> PluginDispatcher {
 
     let cache_settings = mill_ast::CacheSettings::default();
     let plugin_manager = Arc::new(PluginManager::new());
-    let config = mill_config::AppConfig::default();
+    let mut config = mill_config::AppConfig::default();
+    // Tests that check the audit log give it a path of their own
+    config.audit.enabled = false;
 
     // Build plugin registry for tests
     let plugin_registry =
@@ -632,6 +847,7 @@ pub async fn create_test_dispatcher() -> PluginDispatcher {
         file_service: services.file_service,
         planner: services.planner,
         workflow_executor: services.workflow_executor,
+        symbol_index: Arc::new(SymbolIndex::new(project_root.clone())),
         project_root,
         lock_manager: services.lock_manager,
         operation_queue: services.operation_queue,
@@ -639,6 +855,7 @@ pub async fn create_test_dispatcher() -> PluginDispatcher {
         workspace_manager,
         language_plugins: crate::LanguagePluginRegistry::from_registry(plugin_registry),
         lsp_mode: config.lsp.mode,
+        audit_log: services.audit_log,
     });
 
     PluginDispatcher::new(app_state, plugin_manager)
@@ -680,6 +897,7 @@ mod tests {
         let workflow_executor =
             mill_services::services::workflow_executor::DefaultWorkflowExecutor::new(
                 plugin_manager,
+                file_service.clone(),
             );
         let workspace_manager = Arc::new(WorkspaceManager::new());
 
@@ -688,6 +906,7 @@ mod tests {
             file_service,
             planner,
             workflow_executor,
+            symbol_index: Arc::new(SymbolIndex::new(project_root.clone())),
             project_root,
             lock_manager,
             operation_queue,
@@ -695,6 +914,7 @@ mod tests {
             workspace_manager,
             language_plugins,
             lsp_mode: config.lsp.mode,
+            audit_log: None,
         })
     }
 
@@ -736,4 +956,384 @@ mod tests {
             panic!("Expected Response message");
         }
     }
+
+    async fn request(
+        dispatcher: &PluginDispatcher,
+        method: &str,
+        params: Value,
+        session_info: &mill_transport::SessionInfo,
+    ) -> Value {
+        let request = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: method.to_string(),
+            params: Some(params),
+        };
+        match dispatcher
+            .dispatch(McpMessage::Request(request), session_info)
+            .await
+            .unwrap()
+        {
+            McpMessage::Response(resp) => resp.result.unwrap(),
+            _ => panic!("Expected Response message"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_denied_outside_token_scopes() {
+        let app_state = create_test_app_state().await;
+        let dispatcher = PluginDispatcher::new(app_state, Arc::new(PluginManager::new()));
+        let session_info = mill_transport::SessionInfo {
+            scopes: Some(mill_auth::TokenScopes {
+                permissions: Some(vec![mill_auth::Permission::Read]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let request = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "tools/call".to_string(),
+            params: Some(json!({
+                "name": "prune",
+                "arguments": {
+                    "target": { "kind": "file", "filePath": "src/main.rs" },
+                    "options": { "dryRun": false }
+                }
+            })),
+        };
+
+        let error = dispatcher
+            .dispatch(McpMessage::Request(request), &session_info)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ServerError::PermissionDenied { ref required_permission, .. }
+                if required_permission.as_deref() == Some("write")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_search_code_rejects_workspace_outside_project() {
+        let app_state = create_test_app_state().await;
+        let symbol_index = app_state.symbol_index.clone();
+        let dispatcher = PluginDispatcher::new(app_state, Arc::new(PluginManager::new()));
+        let request = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "tools/call".to_string(),
+            params: Some(json!({
+                "name": "search_code",
+                "arguments": { "query": "main", "workspacePath": "/" }
+            })),
+        };
+
+        let error = dispatcher
+            .dispatch(
+                McpMessage::Request(request),
+                &mill_transport::SessionInfo::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("escapes project root"),
+            "{}",
+            error
+        );
+        assert_eq!(symbol_index.file_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_write_tool_calls_are_audited() {
+        let project = TempDir::new().unwrap();
+        let audit_config = mill_config::config::AuditConfig::default();
+        let audit_log = Arc::new(mill_services::services::AuditLog::new(
+            project.path(),
+            &audit_config,
+        ));
+        let mut app_state = Arc::try_unwrap(create_test_app_state().await).ok().unwrap();
+        app_state.audit_log = Some(audit_log.clone());
+        let dispatcher = PluginDispatcher::new(Arc::new(app_state), Arc::new(PluginManager::new()));
+        let session_info = mill_transport::SessionInfo {
+            user_id: Some("alice".to_string()),
+            ..Default::default()
+        };
+        let call = |dry_run: bool| {
+            McpMessage::Request(McpRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(json!(1)),
+                method: "tools/call".to_string(),
+                params: Some(json!({
+                    "name": "prune",
+                    "arguments": {
+                        "target": { "kind": "file", "filePath": "missing.rs" },
+                        "options": { "dryRun": dry_run }
+                    }
+                })),
+            })
+        };
+
+        let _ = dispatcher.dispatch(call(true), &session_info).await;
+        assert!(audit_log.read(None).await.unwrap().is_empty());
+
+        let unknown = McpMessage::Request(McpRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "tools/call".to_string(),
+            params: Some(json!({ "name": "nonexistent_tool", "arguments": {} })),
+        });
+        let _ = dispatcher.dispatch(unknown, &session_info).await;
+        assert!(audit_log.read(None).await.unwrap().is_empty());
+
+        let _ = dispatcher.dispatch(call(false), &session_info).await;
+        let records = audit_log.read(None).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].user_id.as_deref(), Some("alice"));
+        assert_eq!(records[0].tool, "prune");
+        assert_eq!(records[0].arguments["target"]["filePath"], "missing.rs");
+    }
+
+    /// A serialized rename plan, as write tools return it in dry-run mode
+    fn rename_plan() -> Value {
+        use mill_foundation::planning::{PlanMetadata, PlanSummary, RefactorPlan, RenamePlan};
+        serde_json::to_value(RefactorPlan::RenamePlan(RenamePlan {
+            edits: lsp_types::WorkspaceEdit::default(),
+            summary: PlanSummary {
+                affected_files: 0,
+                created_files: 0,
+                deleted_files: 0,
+            },
+            warnings: vec![],
+            metadata: PlanMetadata {
+                plan_version: "1.0".to_string(),
+                kind: "rename".to_string(),
+                language: "rust".to_string(),
+                estimated_impact: "low".to_string(),
+                created_at: "2025-01-01T00:00:00Z".to_string(),
+            },
+            file_checksums: std::collections::HashMap::new(),
+            is_consolidation: false,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_resources_plans_and_templates() {
+        let app_state = create_test_app_state().await;
+        let dispatcher = PluginDispatcher::new(app_state, Arc::new(PluginManager::new()));
+        let session_info = mill_transport::SessionInfo::default();
+
+        let templates = request(
+            &dispatcher,
+            "resources/templates/list",
+            json!({}),
+            &session_info,
+        )
+        .await;
+        assert_eq!(templates["resourceTemplates"].as_array().unwrap().len(), 3);
+
+        let mut result = json!({
+            "content": { "status": "preview", "changes": rename_plan() }
+        });
+        dispatcher
+            .resources
+            .record_plan(&mut result, &session_info)
+            .await;
+        let id = result["content"]["planId"].as_str().unwrap().to_string();
+
+        let listed = request(&dispatcher, "resources/list", json!({}), &session_info).await;
+        let uri = format!("mill://plans/{}", id);
+        let listed = listed["resources"].as_array().unwrap();
+        let resource = listed.iter().find(|r| r["uri"] == uri.as_str()).unwrap();
+        assert_eq!(resource["name"], format!("Plan {} (renamePlan)", id));
+
+        let read = request(
+            &dispatcher,
+            "resources/read",
+            json!({ "uri": uri }),
+            &session_info,
+        )
+        .await;
+        let plan: Value =
+            serde_json::from_str(read["contents"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(plan["planType"], "renamePlan");
+
+        // Other sessions and users neither see nor read the plan
+        let others = [
+            mill_transport::SessionInfo {
+                session_id: Some("other-session".to_string()),
+                ..Default::default()
+            },
+            mill_transport::SessionInfo {
+                user_id: Some("mallory".to_string()),
+                ..Default::default()
+            },
+        ];
+        for other in &others {
+            let listed = request(&dispatcher, "resources/list", json!({}), other).await;
+            assert!(!listed["resources"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|r| r["uri"] == uri.as_str()));
+            let read = McpMessage::Request(McpRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(json!(1)),
+                method: "resources/read".to_string(),
+                params: Some(json!({ "uri": uri })),
+            });
+            let error = dispatcher.dispatch(read, other).await;
+            assert!(matches!(error, Err(ServerError::NotFound { .. })));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resources_respect_token_scopes() {
+        let app_state = create_test_app_state().await;
+        let dispatcher = PluginDispatcher::new(app_state, Arc::new(PluginManager::new()));
+        let session_info = mill_transport::SessionInfo {
+            scopes: Some(mill_auth::TokenScopes {
+                paths: Some(vec!["src/**".to_string()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let read = |uri: &str| {
+            McpMessage::Request(McpRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(json!(1)),
+                method: "resources/read".to_string(),
+                params: Some(json!({ "uri": uri })),
+            })
+        };
+
+        for uri in ["mill://symbols/lib/x.rs", "mill://project/structure"] {
+            let error = dispatcher.dispatch(read(uri), &session_info).await;
+            assert!(matches!(error, Err(ServerError::PermissionDenied { .. })));
+        }
+
+        let mut result = json!({
+            "content": {
+                "status": "preview",
+                "filesChanged": ["src/a.rs", "lib/x.rs"],
+                "changes": rename_plan()
+            }
+        });
+        dispatcher
+            .resources
+            .record_plan(&mut result, &session_info)
+            .await;
+        let uri = format!(
+            "mill://plans/{}",
+            result["content"]["planId"].as_str().unwrap()
+        );
+        let listed = request(&dispatcher, "resources/list", json!({}), &session_info).await;
+        assert_eq!(listed["resources"], json!([]));
+        let error = dispatcher.dispatch(read(&uri), &session_info).await;
+        assert!(matches!(error, Err(ServerError::PermissionDenied { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_resource_subscription_notified_on_diagnostics_change() {
+        let app_state = create_test_app_state().await;
+        let file_uri = url::Url::from_file_path(app_state.project_root.join("src/main.rs"))
+            .unwrap()
+            .to_string();
+        let dispatcher = PluginDispatcher::new(app_state, Arc::new(PluginManager::new()));
+
+        // Without a notification channel, subscribing is refused
+        let subscribe = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "resources/subscribe".to_string(),
+            params: Some(json!({ "uri": "mill://diagnostics/src/main.rs" })),
+        };
+        assert!(dispatcher
+            .dispatch(
+                McpMessage::Request(subscribe),
+                &mill_transport::SessionInfo::default()
+            )
+            .await
+            .is_err());
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let session_info = mill_transport::SessionInfo {
+            notifications: Some(tx),
+            ..Default::default()
+        };
+        request(
+            &dispatcher,
+            "resources/subscribe",
+            json!({ "uri": "mill://diagnostics/src/main.rs" }),
+            &session_info,
+        )
+        .await;
+
+        dispatcher.resources.diagnostics_changed(&file_uri).await;
+        let notification = rx.try_recv().unwrap();
+        assert_eq!(notification.method, "notifications/resources/updated");
+        assert_eq!(
+            notification.params.unwrap()["uri"],
+            "mill://diagnostics/src/main.rs"
+        );
+
+        request(
+            &dispatcher,
+            "resources/unsubscribe",
+            json!({ "uri": "mill://diagnostics/src/main.rs" }),
+            &session_info,
+        )
+        .await;
+        dispatcher.resources.diagnostics_changed(&file_uri).await;
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_prompts_list_and_get_project_prompt() {
+        let app_state = create_test_app_state().await;
+        let prompts_dir = app_state
+            .project_root
+            .join(crate::handlers::mcp_prompts::PROMPTS_DIR);
+        std::fs::create_dir_all(&prompts_dir).unwrap();
+        std::fs::write(app_state.project_root.join("Cargo.toml"), "[package]\n").unwrap();
+        std::fs::write(
+            prompts_dir.join("audit_layout.toml"),
+            r#"
+description = "Audit the project layout"
+template = "Check {{area|everything}} against:\n{{structure}}"
+
+[[arguments]]
+name = "area"
+
+[[context]]
+name = "structure"
+resource = "mill://project/structure"
+"#,
+        )
+        .unwrap();
+        let dispatcher = PluginDispatcher::new(app_state, Arc::new(PluginManager::new()));
+        let session_info = mill_transport::SessionInfo::default();
+
+        let listed = request(&dispatcher, "prompts/list", json!({}), &session_info).await;
+        let names: Vec<&str> = listed["prompts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"audit_layout"));
+        assert!(names.contains(&"safe_rename_public_api"));
+
+        let prompt = request(
+            &dispatcher,
+            "prompts/get",
+            json!({ "name": "audit_layout", "arguments": { "area": "the manifests" } }),
+            &session_info,
+        )
+        .await;
+        let text = prompt["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.starts_with("Check the manifests against:"));
+        assert!(text.contains("Cargo.toml"));
+    }
 }
@@ -8,6 +8,8 @@
 //! - File deletion (with reference cleanup)
 //! - Directory deletion (with reference cleanup)
 
+use crate::handlers::common::qualified_name::QualifiedName;
+use crate::handlers::common::{apply_preview_format, resolve_symbol_target, PreviewFormat};
 use crate::handlers::prune_ops::{
     PruneOptions, PrunePlanParams, PrunePlanner, PruneSelector, PruneTarget,
 };
@@ -55,7 +57,12 @@ struct PruneParams {
 #[serde(rename_all = "camelCase")]
 struct PruneTargetInput {
     kind: String,
+    /// Optional for symbols named by `symbol_name`
+    #[serde(default)]
     file_path: String,
+    /// Qualified symbol name, instead of `line` and `character`
+    #[serde(default)]
+    symbol_name: Option<String>,
     #[serde(default)]
     line: Option<u32>,
     #[serde(default)]
@@ -74,6 +81,8 @@ struct PruneOptionsInput {
     force: Option<bool>,
     #[serde(default)]
     remove_tests: Option<bool>,
+    #[serde(default)]
+    preview_format: PreviewFormat,
 }
 
 impl Default for PruneOptionsInput {
@@ -83,6 +92,7 @@ impl Default for PruneOptionsInput {
             cleanup_imports: Some(true),
             force: None,
             remove_tests: None,
+            preview_format: PreviewFormat::Plan,
         }
     }
 }
@@ -110,10 +120,24 @@ impl ToolHandler for PruneHandler {
             .as_ref()
             .ok_or_else(|| ServerError::invalid_request("Missing arguments for prune"))?;
 
-        let params: PruneParams = PruneParams::deserialize(args).map_err(|e| {
+        let mut params: PruneParams = PruneParams::deserialize(args).map_err(|e| {
             ServerError::invalid_request(format!("Invalid prune parameters: {}", e))
         })?;
 
+        let target = &mut params.target;
+        let symbol_name = target
+            .symbol_name
+            .as_deref()
+            .filter(|_| target.kind == "symbol");
+        resolve_symbol_target(
+            context,
+            symbol_name,
+            &mut target.file_path,
+            &mut target.line,
+            &mut target.character,
+        )
+        .await?;
+
         debug!(
             kind = %params.target.kind,
             file_path = %params.target.file_path,
@@ -126,7 +150,7 @@ impl ToolHandler for PruneHandler {
             && (params.target.line.is_none() || params.target.character.is_none())
         {
             return Err(ServerError::invalid_request(
-                "Symbol deletion requires line and character parameters",
+                "Symbol deletion requires symbolName, or line and character parameters",
             ));
         }
 
@@ -139,7 +163,13 @@ impl ToolHandler for PruneHandler {
                     (Some(line), Some(character)) => Some(PruneSelector {
                         line,
                         character,
-                        symbol_name: None,
+                        // Lets imports of the symbol be cleaned up
+                        symbol_name: params
+                            .target
+                            .symbol_name
+                            .as_deref()
+                            .and_then(|name| QualifiedName::parse(name).ok())
+                            .map(|name| name.name),
                     }),
                     _ => None,
                 },
@@ -182,7 +212,7 @@ impl ToolHandler for PruneHandler {
 
         // Handle dry run vs execution
         if params.options.dry_run {
-            self.build_preview_response(&plan, &params)
+            self.build_preview_response(context, &plan, &params).await
         } else {
             self.execute_and_build_response(context, refactor_plan, &params)
                 .await
@@ -192,8 +222,9 @@ impl ToolHandler for PruneHandler {
 
 impl PruneHandler {
     /// Build preview response from DeletePlan
-    fn build_preview_response(
+    async fn build_preview_response(
         &self,
+        context: &mill_handler_api::ToolHandlerContext,
         plan: &mill_foundation::planning::DeletePlan,
         params: &PruneParams,
     ) -> ServerResult<Value> {
@@ -238,11 +269,19 @@ impl PruneHandler {
             })
             .collect();
 
-        let plan_json = serde_json::to_value(RefactorPlan::DeletePlan(plan.clone()))
+        let refactor_plan = RefactorPlan::DeletePlan(plan.clone());
+        let plan_json = serde_json::to_value(&refactor_plan)
             .map_err(|e| ServerError::internal(format!("Failed to serialize plan: {}", e)))?;
 
         let mut response = WriteResponse::preview(summary, files_list, plan_json);
         response.diagnostics = diagnostics;
+        apply_preview_format(
+            context,
+            &refactor_plan,
+            params.options.preview_format,
+            &mut response,
+        )
+        .await?;
 
         serde_json::to_value(&response)
             .map(|v| serde_json::json!({ "content": v }))
@@ -148,22 +148,26 @@ impl PrunePlanner {
 
         let mut changes: HashMap<Uri, Vec<TextEdit>> = HashMap::new();
 
-        // Get extension to find LSP client
-        let extension = def_file_path
+        // The extension selects the LSP server
+        if def_file_path
             .extension()
             .and_then(|ext| ext.to_str())
-            .ok_or_else(|| {
-                ServerError::invalid_request(format!(
-                    "File has no extension: {}",
-                    def_file_path.display()
-                ))
-            })?;
+            .is_none()
+        {
+            return Err(ServerError::invalid_request(format!(
+                "File has no extension: {}",
+                def_file_path.display()
+            )));
+        }
 
         // Get LSP client
         let client_opt = {
             let adapter = context.lsp_adapter.lock().await;
             if let Some(adapter) = adapter.as_ref() {
-                adapter.get_or_create_client(extension).await.ok()
+                adapter
+                    .get_or_create_client_for_file(def_file_path)
+                    .await
+                    .ok()
             } else {
                 None
             }
@@ -7,26 +7,34 @@
     clippy::manual_clamp
 )]
 
-//! Refactor operation handler - unified handler for extract, inline, and transform operations
+//! Refactor operation handler - unified handler for extract, inline, transform, and reorder operations
 //!
 //! This handler implements the `refactor` tool which dispatches to internal
-//! extract/inline planners based on the action type. The transform
-//! action is reserved for future use.
+//! extract/inline/transform/reorder planners based on the action type.
 //!
 //! ## Supported Actions
 //!
 //! - **extract**: Extract functions, variables, constants, or modules
 //! - **inline**: Inline variables, functions, or constants
-//! - **transform**: Code transformations (placeholder for future implementation)
+//! - **transform**: Code transformations (to_async, function_to_arrow, arrow_to_function,
+//!   if_else_to_match, if_else_to_switch, callback_to_promise)
+//! - **reorder**: Reorder parameters (updating call sites), fields, enum variants, or imports
 //!
 //! ## Response Format
 //!
 //! All responses use the WriteResponse envelope from tool_definitions.rs:
 //! - `dryRun: true` (default) - Returns preview with status="preview"
 //! - `dryRun: false` - Executes changes and returns status="success" or "error"
+//! - `previewFormat: "diff"` - The preview's `changes` hold a unified diff instead of the plan
+//!
+//! Inline, transform and reorder accept a qualified `symbolName` instead of
+//! `line` and `character`.
 
+use crate::handlers::common::{apply_preview_format, resolve_symbol_target, PreviewFormat};
 use crate::handlers::refactor_extract::RefactorExtractPlanner;
 use crate::handlers::refactor_inline::RefactorInlinePlanner;
+use crate::handlers::refactor_reorder::RefactorReorderPlanner;
+use crate::handlers::refactor_transform::RefactorTransformPlanner;
 use crate::handlers::tool_definitions::{
     Diagnostic, DiagnosticSeverity, WriteResponse, WriteStatus,
 };
@@ -44,6 +52,8 @@ use tracing::{debug, info};
 pub struct RefactorHandler {
     extract_planner: RefactorExtractPlanner,
     inline_planner: RefactorInlinePlanner,
+    transform_planner: RefactorTransformPlanner,
+    reorder_planner: RefactorReorderPlanner,
 }
 
 impl RefactorHandler {
@@ -51,6 +61,8 @@ impl RefactorHandler {
         Self {
             extract_planner: RefactorExtractPlanner::new(),
             inline_planner: RefactorInlinePlanner::new(),
+            transform_planner: RefactorTransformPlanner::new(),
+            reorder_planner: RefactorReorderPlanner::new(),
         }
     }
 
@@ -63,10 +75,25 @@ impl RefactorHandler {
         let args = tool_call.arguments.clone().unwrap_or(json!({}));
 
         // Deserialize parameters
-        let params: RefactorParams = serde_json::from_value(args).map_err(|e| {
+        let mut params: RefactorParams = serde_json::from_value(args).map_err(|e| {
             ServerError::invalid_request(format!("Invalid refactor parameters: {}", e))
         })?;
 
+        // Extract works on a range, so only the other actions locate symbols by name
+        let symbol_name = params
+            .params
+            .symbol_name
+            .as_deref()
+            .filter(|_| params.action != "extract");
+        resolve_symbol_target(
+            context,
+            symbol_name,
+            &mut params.params.file_path,
+            &mut params.params.line,
+            &mut params.params.character,
+        )
+        .await?;
+
         debug!(
             action = %params.action,
             kind = %params.params.kind,
@@ -78,8 +105,9 @@ impl RefactorHandler {
             "extract" => self.handle_extract(context, &params).await,
             "inline" => self.handle_inline(context, &params).await,
             "transform" => self.handle_transform(context, &params).await,
+            "reorder" => self.handle_reorder(context, &params).await,
             _ => Err(ServerError::invalid_request(format!(
-                "Unsupported refactor action: '{}'. Must be one of: extract, inline, transform",
+                "Unsupported refactor action: '{}'. Must be one of: extract, inline, transform, reorder",
                 params.action
             ))),
         }
@@ -143,7 +171,14 @@ impl RefactorHandler {
         let refactor_plan = mill_foundation::protocol::RefactorPlan::ExtractPlan(plan);
 
         if params.options.dry_run {
-            let response = self.parse_plan_response(&refactor_plan, "extract")?;
+            let mut response = self.parse_plan_response(&refactor_plan, "extract")?;
+            apply_preview_format(
+                context,
+                &refactor_plan,
+                params.options.preview_format,
+                &mut response,
+            )
+            .await?;
             Ok(json!({ "content": response }))
         } else {
             let result =
@@ -199,7 +234,14 @@ impl RefactorHandler {
         let refactor_plan = mill_foundation::protocol::RefactorPlan::InlinePlan(plan);
 
         if params.options.dry_run {
-            let response = self.parse_plan_response(&refactor_plan, "inline")?;
+            let mut response = self.parse_plan_response(&refactor_plan, "inline")?;
+            apply_preview_format(
+                context,
+                &refactor_plan,
+                params.options.preview_format,
+                &mut response,
+            )
+            .await?;
             Ok(json!({ "content": response }))
         } else {
             let result =
@@ -209,30 +251,124 @@ impl RefactorHandler {
         }
     }
 
-    /// Handle transform action (placeholder for future implementation)
+    /// Handle transform action using transform planner
     async fn handle_transform(
         &self,
-        _context: &mill_handler_api::ToolHandlerContext,
+        context: &mill_handler_api::ToolHandlerContext,
         params: &RefactorParams,
     ) -> ServerResult<Value> {
+        // Validate transform-specific requirements
+        let line = params.params.line.ok_or_else(|| {
+            ServerError::invalid_request("Transform action requires 'line' parameter")
+        })?;
+        let character = params.params.character.unwrap_or(0);
+
+        // Resolve relative paths to absolute using workspace root
+        let file_path =
+            resolve_file_path(&context.app_state.project_root, &params.params.file_path);
+
+        let transform_params = crate::handlers::refactor_transform::TransformPlanParams {
+            kind: params.params.kind.clone(),
+            target: crate::handlers::refactor_transform::TransformTarget {
+                file_path,
+                position: lsp_types::Position { line, character },
+            },
+        };
+
         info!(
             operation = "transform",
             kind = %params.params.kind,
-            "Transform action not yet implemented"
+            dry_run = params.options.dry_run,
+            "Building transform plan"
         );
 
-        // Return a WriteResponse with not_supported error
-        let response = WriteResponse::error(
-            "Transform action is not yet implemented".to_string(),
-            vec![Diagnostic {
-                severity: DiagnosticSeverity::Error,
-                message: "The 'transform' action is reserved for future use. Use 'extract' or 'inline' for current refactoring needs.".to_string(),
-                file_path: Some(params.params.file_path.clone()),
-                line: None,
-            }],
+        let plan = self
+            .transform_planner
+            .build_transform_plan(context, &transform_params)
+            .await?;
+
+        let refactor_plan = mill_foundation::protocol::RefactorPlan::TransformPlan(plan);
+
+        if params.options.dry_run {
+            let mut response = self.parse_plan_response(&refactor_plan, "transform")?;
+            apply_preview_format(
+                context,
+                &refactor_plan,
+                params.options.preview_format,
+                &mut response,
+            )
+            .await?;
+            Ok(json!({ "content": response }))
+        } else {
+            let result =
+                crate::handlers::common::execute_refactor_plan(context, refactor_plan).await?;
+            let response = self.parse_execution_response(&result, "transform")?;
+            Ok(json!({ "content": response }))
+        }
+    }
+
+    /// Handle reorder action using reorder planner
+    async fn handle_reorder(
+        &self,
+        context: &mill_handler_api::ToolHandlerContext,
+        params: &RefactorParams,
+    ) -> ServerResult<Value> {
+        // Validate reorder-specific requirements
+        let line = params.params.line.ok_or_else(|| {
+            ServerError::invalid_request("Reorder action requires 'line' parameter")
+        })?;
+        let character = params.params.character.unwrap_or(0);
+        let new_order = params.params.order.clone().unwrap_or_default();
+        if new_order.is_empty() && params.params.kind != "imports" {
+            return Err(ServerError::invalid_request(
+                "Reorder action requires 'order' parameter (only imports can be sorted without it)",
+            ));
+        }
+
+        // Resolve relative paths to absolute using workspace root
+        let file_path =
+            resolve_file_path(&context.app_state.project_root, &params.params.file_path);
+
+        let reorder_params = crate::handlers::refactor_reorder::ReorderPlanParams {
+            kind: params.params.kind.clone(),
+            target: crate::handlers::refactor_reorder::ReorderTarget {
+                file_path,
+                position: lsp_types::Position { line, character },
+            },
+            new_order,
+            dry_run: params.options.dry_run,
+        };
+
+        info!(
+            operation = "reorder",
+            kind = %params.params.kind,
+            dry_run = params.options.dry_run,
+            "Building reorder plan"
         );
 
-        Ok(json!({ "content": response }))
+        let plan = self
+            .reorder_planner
+            .build_reorder_plan(context, &reorder_params)
+            .await?;
+
+        let refactor_plan = mill_foundation::protocol::RefactorPlan::ReorderPlan(plan);
+
+        if params.options.dry_run {
+            let mut response = self.parse_plan_response(&refactor_plan, "reorder")?;
+            apply_preview_format(
+                context,
+                &refactor_plan,
+                params.options.preview_format,
+                &mut response,
+            )
+            .await?;
+            Ok(json!({ "content": response }))
+        } else {
+            let result =
+                crate::handlers::common::execute_refactor_plan(context, refactor_plan).await?;
+            let response = self.parse_execution_response(&result, "reorder")?;
+            Ok(json!({ "content": response }))
+        }
     }
 
     /// Parse RefactorPlan response and convert to WriteResponse
@@ -384,7 +520,7 @@ impl ToolHandler for RefactorHandler {
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct RefactorParams {
-    /// The refactoring action: extract, inline, or transform
+    /// The refactoring action: extract, inline, transform, or reorder
     action: String,
     /// Action-specific parameters
     params: RefactorActionParams,
@@ -398,15 +534,19 @@ struct RefactorParams {
 struct RefactorActionParams {
     /// Kind of element to refactor (function, variable, constant, module)
     kind: String,
-    /// Source file path
+    /// Source file path (optional when `symbol_name` is given)
+    #[serde(default)]
     file_path: String,
+    /// Qualified symbol name, instead of `line` and `character` (not for extract)
+    #[serde(default)]
+    symbol_name: Option<String>,
     /// Code range (for extract)
     #[serde(default)]
     range: Option<RefactorRange>,
-    /// Line number (0-based, for inline)
+    /// Line number (0-based, for inline, transform, and reorder)
     #[serde(default)]
     line: Option<u32>,
-    /// Character offset (0-based, for inline)
+    /// Character offset (0-based, for inline, transform, and reorder)
     #[serde(default)]
     character: Option<u32>,
     /// Name for extracted element (for extract)
@@ -415,6 +555,9 @@ struct RefactorActionParams {
     /// Destination path (for extract module)
     #[serde(default)]
     destination: Option<String>,
+    /// Item names in their new order (for reorder)
+    #[serde(default)]
+    order: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -439,6 +582,9 @@ struct RefactorOptions {
     /// Inline all usages vs current only (inline action only)
     #[serde(default)]
     inline_all: Option<bool>,
+    /// How a dry run presents the plan: "plan" (default) or "diff"
+    #[serde(default)]
+    preview_format: PreviewFormat,
 }
 
 impl Default for RefactorOptions {
@@ -446,6 +592,7 @@ impl Default for RefactorOptions {
         Self {
             dry_run: true,
             inline_all: None,
+            preview_format: PreviewFormat::Plan,
         }
     }
 }
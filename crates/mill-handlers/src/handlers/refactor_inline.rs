@@ -14,7 +14,7 @@
 
 use lsp_types::{Position, Range, WorkspaceEdit};
 use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
-use mill_foundation::protocol::{EditPlan, InlinePlan, PlanMetadata, PlanSummary};
+use mill_foundation::protocol::{EditPlan, InlinePlan, PlanMetadata, PlanSummary, PlanWarning};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
@@ -127,8 +127,6 @@ impl RefactorInlinePlanner {
         target: &InlineTarget,
         options: &InlineOptions,
     ) -> ServerResult<InlinePlan> {
-        // Function inlining uses similar logic to variable inlining
-        // Language plugins can provide more specialized implementations (AST-only approach)
         let file_path = Path::new(&target.file_path);
         let file_content = context
             .app_state
@@ -137,8 +135,6 @@ impl RefactorInlinePlanner {
             .await
             .map_err(|e| ServerError::internal(format!("Failed to read file: {}", e)))?;
 
-        // Try AST-based inline for functions
-
         // Get PluginDiscovery from language_plugins by downcasting
         let plugin_discovery = context
             .app_state
@@ -147,16 +143,17 @@ impl RefactorInlinePlanner {
             .downcast_ref::<mill_plugin_api::PluginDiscovery>()
             .ok_or_else(|| ServerError::internal("Failed to downcast to PluginDiscovery"))?;
 
-        let edit_plan = mill_ast::refactoring::inline_variable::plan_inline_variable(
+        // The plugin substitutes arguments into the body at each call site
+        let edit_plan = mill_ast::refactoring::inline_function::plan_inline_function(
             &file_content,
             target.position.line,
             target.position.character,
+            options.inline_all.unwrap_or(false),
             &target.file_path,
-            None,                   // No LSP service - use AST-only approach
-            Some(plugin_discovery), // Pass plugin registry
+            Some(plugin_discovery),
         )
         .await
-        .map_err(|e| ServerError::internal(format!("Inline function failed: {}", e)))?;
+        .map_err(|e| ServerError::invalid_request(format!("Inline function failed: {}", e)))?;
 
         self.convert_edit_plan_to_inline_plan(
             edit_plan,
@@ -248,8 +245,8 @@ impl RefactorInlinePlanner {
             deleted_files,
         };
 
-        // Generate warnings if needed
-        let warnings = Vec::new();
+        // Definitions kept despite inlineAll are reported by the plugin as notes
+        let warnings = definition_kept_warnings(&edit_plan.metadata.intent_arguments);
 
         // Generate metadata
         let language = crate::handlers::common::detect_language(file_path);
@@ -349,6 +346,24 @@ impl RefactorInlinePlanner {
     }
 }
 
+fn definition_kept_warnings(intent_arguments: &serde_json::Value) -> Vec<PlanWarning> {
+    intent_arguments
+        .get("notes")
+        .and_then(|notes| notes.as_array())
+        .map(|notes| {
+            notes
+                .iter()
+                .filter_map(|note| note.as_str())
+                .map(|note| PlanWarning {
+                    code: "DEFINITION_KEPT".to_string(),
+                    message: note.to_string(),
+                    candidates: None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 impl Default for RefactorInlinePlanner {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,509 @@
+//! Reorder planning service for refactor operations
+//!
+//! Reorders function parameters, struct/class fields, enum variants and import
+//! blocks. Language plugins rewrite the declaration; for parameters the call
+//! sites found through LSP `textDocument/references` are rewritten here so the
+//! arguments follow the new parameter order.
+
+use lsp_types::{Location, Position, Range, WorkspaceEdit};
+use mill_ast::refactoring::reorder::{reorder_call_arguments, CallSiteRewrite};
+use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
+use mill_foundation::protocol::{EditPlan, PlanMetadata, PlanSummary, PlanWarning, ReorderPlan};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::{debug, error, warn};
+use url::Url;
+
+use crate::handlers::common::{lsp_uri_from_file_path, should_use_lsp_for_refactor};
+
+/// Reorder kinds accepted by the `refactor` tool
+pub(crate) const REORDER_KINDS: &[&str] = &["parameters", "fields", "variants", "imports"];
+
+pub struct RefactorReorderPlanner;
+
+impl RefactorReorderPlanner {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Build reorder plan from validated parameters
+    #[allow(clippy::mutable_key_type)]
+    pub(crate) async fn build_reorder_plan(
+        &self,
+        context: &mill_handler_api::ToolHandlerContext,
+        params: &ReorderPlanParams,
+    ) -> ServerResult<ReorderPlan> {
+        debug!(
+            kind = %params.kind,
+            file_path = %params.target.file_path,
+            line = params.target.position.line,
+            new_order = ?params.new_order,
+            "Planning reorder operation"
+        );
+
+        if !REORDER_KINDS.contains(&params.kind.as_str()) {
+            return Err(ServerError::invalid_request(format!(
+                "Unsupported reorder kind: {}. Must be one of: {}",
+                params.kind,
+                REORDER_KINDS.join(", ")
+            )));
+        }
+
+        let file_path = Path::new(&params.target.file_path);
+        let file_content = context
+            .app_state
+            .file_service
+            .read_file(file_path)
+            .await
+            .map_err(|e| ServerError::internal(format!("Failed to read file: {}", e)))?;
+
+        // Get PluginDiscovery from language_plugins by downcasting
+        let plugin_discovery = context
+            .app_state
+            .language_plugins
+            .inner()
+            .downcast_ref::<mill_plugin_api::PluginDiscovery>()
+            .ok_or_else(|| ServerError::internal("Failed to downcast to PluginDiscovery"))?;
+
+        let edit_plan = mill_ast::refactoring::reorder::plan_reorder(
+            &file_content,
+            &params.kind,
+            params.target.position.line,
+            params.target.position.character,
+            &params.new_order,
+            &params.target.file_path,
+            Some(plugin_discovery),
+        )
+        .await
+        .map_err(|e| ServerError::invalid_request(format!("Reorder failed: {}", e)))?;
+
+        let mut warnings = semantic_warnings(&edit_plan.metadata.intent_arguments);
+        let mut changes = self.convert_to_changes(&edit_plan)?;
+
+        if params.kind == "parameters" {
+            self.update_call_sites(
+                context,
+                &edit_plan,
+                file_path,
+                params.dry_run,
+                &mut changes,
+                &mut warnings,
+            )
+            .await?;
+        }
+
+        self.build_plan(context, changes, warnings, &params.kind, file_path)
+            .await
+    }
+
+    /// Rewrite the arguments of every call to the reordered function
+    ///
+    /// When the calls cannot be looked up a preview only warns, while an
+    /// applied reorder fails instead of leaving the calls in the old order.
+    #[allow(clippy::mutable_key_type)]
+    async fn update_call_sites(
+        &self,
+        context: &mill_handler_api::ToolHandlerContext,
+        edit_plan: &EditPlan,
+        file_path: &Path,
+        dry_run: bool,
+        changes: &mut HashMap<lsp_types::Uri, Vec<lsp_types::TextEdit>>,
+        warnings: &mut Vec<PlanWarning>,
+    ) -> ServerResult<()> {
+        let args = &edit_plan.metadata.intent_arguments;
+        let permutation: Vec<usize> = args
+            .get("permutation")
+            .and_then(|p| serde_json::from_value(p.clone()).ok())
+            .ok_or_else(|| ServerError::internal("Reorder plan is missing the permutation"))?;
+        let (Some(name_line), Some(name_character)) = (
+            args.get("name_line").and_then(|v| v.as_u64()),
+            args.get("name_character").and_then(|v| v.as_u64()),
+        ) else {
+            return Err(ServerError::internal(
+                "Reorder plan is missing the function name position",
+            ));
+        };
+        let function_name = args
+            .get("function_name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("function");
+
+        // Nothing to rewrite when the order did not change
+        if edit_plan.edits.is_empty() {
+            return Ok(());
+        }
+
+        let name_position = Position {
+            line: name_line as u32,
+            character: name_character as u32,
+        };
+        let references = match self
+            .find_references(context, file_path, name_position)
+            .await
+        {
+            Ok(references) => references,
+            Err(reason) if !dry_run => {
+                return Err(ServerError::lsp(format!(
+                    "Cannot update calls to '{}' ({}); parameters were not reordered",
+                    function_name, reason
+                )));
+            }
+            Err(reason) => {
+                warnings.push(PlanWarning {
+                    code: "CALL_SITES_NOT_UPDATED".to_string(),
+                    message: format!(
+                        "Calls to '{}' were not updated ({}); reorder their arguments manually",
+                        function_name, reason
+                    ),
+                    candidates: None,
+                });
+                return Ok(());
+            }
+        };
+
+        // Group references by file, keeping each position once
+        let mut by_file: HashMap<PathBuf, Vec<Position>> = HashMap::new();
+        for location in references {
+            let path = Url::parse(location.uri.as_str())
+                .ok()
+                .and_then(|url| url.to_file_path().ok());
+            let Some(path) = path else {
+                warn!(uri = %location.uri.as_str(), "Skipping reference with non-file URI");
+                continue;
+            };
+            if path == file_path && location.range.start == name_position {
+                continue;
+            }
+            let positions = by_file.entry(path).or_default();
+            if !positions.contains(&location.range.start) {
+                positions.push(location.range.start);
+            }
+        }
+
+        let mut updated = 0;
+        for (path, positions) in by_file {
+            let content = match context.app_state.file_service.read_file(&path).await {
+                Ok(content) => content,
+                Err(e) => {
+                    warnings.push(call_site_skipped(
+                        &path,
+                        None,
+                        &format!("failed to read file: {}", e),
+                    ));
+                    continue;
+                }
+            };
+
+            let mut file_edits = Vec::new();
+            for position in positions {
+                match reorder_call_arguments(
+                    &content,
+                    position.line,
+                    position.character,
+                    &permutation,
+                ) {
+                    CallSiteRewrite::Rewritten(edit) => file_edits.push(lsp_types::TextEdit {
+                        range: Range {
+                            start: Position {
+                                line: edit.location.start_line,
+                                character: edit.location.start_column,
+                            },
+                            end: Position {
+                                line: edit.location.end_line,
+                                character: edit.location.end_column,
+                            },
+                        },
+                        new_text: edit.new_text,
+                    }),
+                    CallSiteRewrite::Unchanged | CallSiteRewrite::NotACall => {}
+                    CallSiteRewrite::Skipped(reason) => {
+                        warnings.push(call_site_skipped(&path, Some(position.line), &reason))
+                    }
+                }
+            }
+
+            if !file_edits.is_empty() {
+                updated += file_edits.len();
+                let uri = lsp_uri_from_file_path(&path)
+                    .map_err(|e| ServerError::internal(format!("Invalid file path: {}", e)))?;
+                changes.entry(uri).or_default().extend(file_edits);
+            }
+        }
+
+        debug!(
+            function = %function_name,
+            call_sites = updated,
+            "Rewrote call sites for reordered parameters"
+        );
+
+        Ok(())
+    }
+
+    /// Find references to the function through the LSP server for its file type
+    ///
+    /// Returns the reason as an error when no LSP server can answer.
+    async fn find_references(
+        &self,
+        context: &mill_handler_api::ToolHandlerContext,
+        file_path: &Path,
+        position: Position,
+    ) -> Result<Vec<Location>, String> {
+        if !should_use_lsp_for_refactor(context) {
+            return Err("LSP is disabled".to_string());
+        }
+
+        let extension = file_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .ok_or_else(|| "file has no extension".to_string())?;
+
+        let client = {
+            let adapter = context.lsp_adapter.lock().await;
+            match adapter.as_ref() {
+                Some(adapter) => adapter
+                    .get_or_create_client_for_file(file_path)
+                    .await
+                    .map_err(|e| format!("no LSP server for .{} files: {}", extension, e))?,
+                None => return Err("no LSP adapter available".to_string()),
+            }
+        };
+
+        let uri = lsp_uri_from_file_path(file_path).map_err(|e| e.to_string())?;
+        let params = lsp_types::ReferenceParams {
+            text_document_position: lsp_types::TextDocumentPositionParams {
+                text_document: lsp_types::TextDocumentIdentifier { uri },
+                position,
+            },
+            context: lsp_types::ReferenceContext {
+                include_declaration: false,
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        let response = client
+            .send_request(
+                "textDocument/references",
+                serde_json::to_value(params).map_err(|e| e.to_string())?,
+            )
+            .await
+            .map_err(|e| format!("reference lookup failed: {}", e))?;
+
+        // A null result means there are no references
+        if response.is_null() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_value(response)
+            .map_err(|e| format!("invalid reference lookup response: {}", e))
+    }
+
+    /// Convert EditPlan edits to LSP text edits grouped by file
+    #[allow(clippy::mutable_key_type)]
+    fn convert_to_changes(
+        &self,
+        edit_plan: &EditPlan,
+    ) -> ServerResult<HashMap<lsp_types::Uri, Vec<lsp_types::TextEdit>>> {
+        let mut changes: HashMap<lsp_types::Uri, Vec<lsp_types::TextEdit>> = HashMap::new();
+
+        for edit in &edit_plan.edits {
+            let file_path = edit.file_path.as_ref().unwrap_or(&edit_plan.source_file);
+
+            let uri = lsp_uri_from_file_path(Path::new(file_path))
+                .map_err(|e| ServerError::internal(format!("Invalid file path: {}", e)))?;
+
+            let lsp_edit = lsp_types::TextEdit {
+                range: Range {
+                    start: Position {
+                        line: edit.location.start_line,
+                        character: edit.location.start_column,
+                    },
+                    end: Position {
+                        line: edit.location.end_line,
+                        character: edit.location.end_column,
+                    },
+                },
+                new_text: edit.new_text.clone(),
+            };
+
+            changes.entry(uri).or_default().push(lsp_edit);
+        }
+
+        Ok(changes)
+    }
+
+    /// Assemble the ReorderPlan with summary, metadata and checksums
+    #[allow(clippy::mutable_key_type)]
+    async fn build_plan(
+        &self,
+        context: &mill_handler_api::ToolHandlerContext,
+        changes: HashMap<lsp_types::Uri, Vec<lsp_types::TextEdit>>,
+        warnings: Vec<PlanWarning>,
+        kind: &str,
+        file_path: &Path,
+    ) -> ServerResult<ReorderPlan> {
+        let mut affected_files = HashSet::new();
+        affected_files.insert(file_path.to_string_lossy().to_string());
+        for uri in changes.keys() {
+            if let Some(path) = Url::parse(uri.as_str())
+                .ok()
+                .and_then(|url| url.to_file_path().ok())
+            {
+                affected_files.insert(path.to_string_lossy().to_string());
+            }
+        }
+
+        // Reorders rewrite code in place and never create or delete files
+        let summary = PlanSummary {
+            affected_files: affected_files.len(),
+            created_files: 0,
+            deleted_files: 0,
+        };
+
+        let language = crate::handlers::common::detect_language(&file_path.to_string_lossy());
+        let estimated_impact = if affected_files.len() <= 1 {
+            "low"
+        } else if affected_files.len() <= 3 {
+            "medium"
+        } else {
+            "high"
+        };
+
+        let metadata = PlanMetadata {
+            plan_version: "1.0".to_string(),
+            kind: kind.to_string(),
+            language: language.to_string(),
+            estimated_impact: estimated_impact.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        let file_checksums = self
+            .generate_file_checksums(context, &affected_files)
+            .await?;
+
+        Ok(ReorderPlan {
+            edits: WorkspaceEdit {
+                changes: Some(changes),
+                document_changes: None,
+                change_annotations: None,
+            },
+            summary,
+            warnings,
+            metadata,
+            file_checksums,
+        })
+    }
+
+    /// Generate SHA-256 checksums for all affected files
+    async fn generate_file_checksums(
+        &self,
+        context: &mill_handler_api::ToolHandlerContext,
+        file_paths: &HashSet<String>,
+    ) -> ServerResult<HashMap<String, String>> {
+        use crate::handlers::common::calculate_checksum;
+
+        let mut checksums = HashMap::new();
+
+        for file_path in file_paths {
+            let path = Path::new(file_path);
+            match context.app_state.file_service.read_file(path).await {
+                Ok(content) => {
+                    checksums.insert(file_path.clone(), calculate_checksum(&content));
+                }
+                Err(e) => {
+                    error!(
+                        file_path = %file_path,
+                        error = %e,
+                        "Failed to read file for checksum"
+                    );
+                }
+            }
+        }
+
+        Ok(checksums)
+    }
+}
+
+impl Default for RefactorReorderPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Warnings for reorders that change behaviour (layout, discriminants, init order)
+fn semantic_warnings(intent_arguments: &serde_json::Value) -> Vec<PlanWarning> {
+    intent_arguments
+        .get("notes")
+        .and_then(|notes| notes.as_array())
+        .map(|notes| {
+            notes
+                .iter()
+                .filter_map(|note| note.as_str())
+                .map(|note| PlanWarning {
+                    code: "REORDER_SEMANTICS".to_string(),
+                    message: note.to_string(),
+                    candidates: None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn call_site_skipped(path: &Path, line: Option<u32>, reason: &str) -> PlanWarning {
+    let location = match line {
+        Some(line) => format!("{}:{}", path.display(), line + 1),
+        None => path.display().to_string(),
+    };
+    PlanWarning {
+        code: "CALL_SITE_SKIPPED".to_string(),
+        message: format!("Call at {} was not updated: {}", location, reason),
+        candidates: None,
+    }
+}
+
+// Parameter structures
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReorderPlanParams {
+    pub(crate) kind: String,
+    pub(crate) target: ReorderTarget,
+    /// Item names in their new order (empty sorts an import block)
+    #[serde(default)]
+    pub(crate) new_order: Vec<String>,
+    /// Preview mode, where call sites that cannot be updated only warn
+    #[serde(default = "crate::default_true")]
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReorderTarget {
+    #[serde(alias = "file_path")]
+    pub(crate) file_path: String,
+    pub(crate) position: Position, // lsp_types::Position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_semantic_warnings_from_notes() {
+        let args = serde_json::json!({
+            "permutation": [1, 0],
+            "notes": ["'Point' is #[repr(C)]; reordering fields changes its memory layout"]
+        });
+
+        let warnings = semantic_warnings(&args);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "REORDER_SEMANTICS");
+    }
+
+    #[test]
+    fn test_call_site_skipped_message() {
+        let warning = call_site_skipped(Path::new("/src/main.rs"), Some(4), "spread argument");
+        assert_eq!(warning.code, "CALL_SITE_SKIPPED");
+        assert!(warning.message.contains("/src/main.rs:5"));
+    }
+}
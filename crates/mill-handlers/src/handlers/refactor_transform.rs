@@ -0,0 +1,269 @@
+//! Transform planning service for refactor operations
+//!
+//! Supports code transformations such as making a function async, converting
+//! between function declarations and arrow functions, turning if/else chains
+//! into `match`/`switch`, and converting callback APIs to promises. The actual
+//! rewrites are provided by language plugins through `RefactoringProvider`.
+
+use lsp_types::{Position, Range, WorkspaceEdit};
+use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
+use mill_foundation::protocol::{EditPlan, PlanMetadata, PlanSummary, PlanWarning, TransformPlan};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tracing::{debug, error};
+
+use crate::handlers::common::lsp_uri_from_file_path;
+
+/// Transform kinds accepted by the `refactor` tool
+pub(crate) const TRANSFORM_KINDS: &[&str] = &[
+    "to_async",
+    "function_to_arrow",
+    "arrow_to_function",
+    "if_else_to_match",
+    "if_else_to_switch",
+    "callback_to_promise",
+];
+
+pub struct RefactorTransformPlanner;
+
+impl RefactorTransformPlanner {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Build transform plan from validated parameters
+    pub(crate) async fn build_transform_plan(
+        &self,
+        context: &mill_handler_api::ToolHandlerContext,
+        params: &TransformPlanParams,
+    ) -> ServerResult<TransformPlan> {
+        debug!(
+            kind = %params.kind,
+            file_path = %params.target.file_path,
+            line = params.target.position.line,
+            character = params.target.position.character,
+            "Planning transform operation"
+        );
+
+        if !TRANSFORM_KINDS.contains(&params.kind.as_str()) {
+            return Err(ServerError::invalid_request(format!(
+                "Unsupported transform kind: {}. Must be one of: {}",
+                params.kind,
+                TRANSFORM_KINDS.join(", ")
+            )));
+        }
+
+        let file_path = Path::new(&params.target.file_path);
+        let file_content = context
+            .app_state
+            .file_service
+            .read_file(file_path)
+            .await
+            .map_err(|e| ServerError::internal(format!("Failed to read file: {}", e)))?;
+
+        // Get PluginDiscovery from language_plugins by downcasting
+        let plugin_discovery = context
+            .app_state
+            .language_plugins
+            .inner()
+            .downcast_ref::<mill_plugin_api::PluginDiscovery>()
+            .ok_or_else(|| ServerError::internal("Failed to downcast to PluginDiscovery"))?;
+
+        let edit_plan = mill_ast::refactoring::transform::plan_transform(
+            &file_content,
+            &params.kind,
+            params.target.position.line,
+            params.target.position.character,
+            &params.target.file_path,
+            None,                   // No LSP service - use AST-only approach
+            Some(plugin_discovery), // Pass plugin registry
+        )
+        .await
+        .map_err(|e| ServerError::invalid_request(format!("Transform failed: {}", e)))?;
+
+        self.convert_edit_plan_to_transform_plan(
+            edit_plan,
+            &params.target.file_path,
+            &params.kind,
+            context,
+        )
+        .await
+    }
+
+    /// Convert EditPlan (from AST) to TransformPlan (protocol type)
+    async fn convert_edit_plan_to_transform_plan(
+        &self,
+        edit_plan: EditPlan,
+        file_path: &str,
+        kind: &str,
+        context: &mill_handler_api::ToolHandlerContext,
+    ) -> ServerResult<TransformPlan> {
+        let workspace_edit = self.convert_to_workspace_edit(&edit_plan)?;
+
+        let mut affected_files = HashSet::new();
+        affected_files.insert(file_path.to_string());
+        for edit in &edit_plan.edits {
+            if let Some(ref path) = edit.file_path {
+                affected_files.insert(path.clone());
+            }
+        }
+
+        // Transforms rewrite code in place and never create or delete files
+        let summary = PlanSummary {
+            affected_files: affected_files.len(),
+            created_files: 0,
+            deleted_files: 0,
+        };
+
+        let warnings = transform_warnings(kind);
+
+        let language = crate::handlers::common::detect_language(file_path);
+        let estimated_impact = if affected_files.len() <= 1 {
+            "low"
+        } else if affected_files.len() <= 3 {
+            "medium"
+        } else {
+            "high"
+        };
+
+        let metadata = PlanMetadata {
+            plan_version: "1.0".to_string(),
+            kind: kind.to_string(),
+            language: language.to_string(),
+            estimated_impact: estimated_impact.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        let file_checksums = self
+            .generate_file_checksums(context, &affected_files)
+            .await?;
+
+        Ok(TransformPlan {
+            edits: workspace_edit,
+            summary,
+            warnings,
+            metadata,
+            file_checksums,
+        })
+    }
+
+    /// Convert EditPlan edits to LSP WorkspaceEdit
+    #[allow(clippy::mutable_key_type)]
+    fn convert_to_workspace_edit(&self, edit_plan: &EditPlan) -> ServerResult<WorkspaceEdit> {
+        let mut changes: HashMap<lsp_types::Uri, Vec<lsp_types::TextEdit>> = HashMap::new();
+
+        for edit in &edit_plan.edits {
+            let file_path = edit.file_path.as_ref().unwrap_or(&edit_plan.source_file);
+
+            let uri = lsp_uri_from_file_path(Path::new(file_path))
+                .map_err(|e| ServerError::internal(format!("Invalid file path: {}", e)))?;
+
+            let lsp_edit = lsp_types::TextEdit {
+                range: Range {
+                    start: Position {
+                        line: edit.location.start_line,
+                        character: edit.location.start_column,
+                    },
+                    end: Position {
+                        line: edit.location.end_line,
+                        character: edit.location.end_column,
+                    },
+                },
+                new_text: edit.new_text.clone(),
+            };
+
+            changes.entry(uri).or_default().push(lsp_edit);
+        }
+
+        Ok(WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+            change_annotations: None,
+        })
+    }
+
+    /// Generate SHA-256 checksums for all affected files
+    async fn generate_file_checksums(
+        &self,
+        context: &mill_handler_api::ToolHandlerContext,
+        file_paths: &HashSet<String>,
+    ) -> ServerResult<HashMap<String, String>> {
+        use crate::handlers::common::calculate_checksum;
+
+        let mut checksums = HashMap::new();
+
+        for file_path in file_paths {
+            let path = Path::new(file_path);
+            match context.app_state.file_service.read_file(path).await {
+                Ok(content) => {
+                    checksums.insert(file_path.clone(), calculate_checksum(&content));
+                }
+                Err(e) => {
+                    error!(
+                        file_path = %file_path,
+                        error = %e,
+                        "Failed to read file for checksum"
+                    );
+                }
+            }
+        }
+
+        Ok(checksums)
+    }
+}
+
+impl Default for RefactorTransformPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Warnings for transforms that change a function's calling convention
+///
+/// Only the target function is rewritten; call sites are left untouched.
+fn transform_warnings(kind: &str) -> Vec<PlanWarning> {
+    let message = match kind {
+        "to_async" => "Callers are not updated and now receive a future/promise; add `await` where the result is used",
+        "callback_to_promise" => "Callers are not updated; replace callback arguments with `.then()` or `await`",
+        _ => return Vec::new(),
+    };
+
+    vec![PlanWarning {
+        code: "CALLERS_NOT_UPDATED".to_string(),
+        message: message.to_string(),
+        candidates: None,
+    }]
+}
+
+// Parameter structures
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct TransformPlanParams {
+    pub(crate) kind: String,
+    pub(crate) target: TransformTarget,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TransformTarget {
+    #[serde(alias = "file_path")]
+    pub(crate) file_path: String,
+    pub(crate) position: Position, // lsp_types::Position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transform_warnings() {
+        assert_eq!(
+            transform_warnings("to_async")[0].code,
+            "CALLERS_NOT_UPDATED"
+        );
+        assert_eq!(transform_warnings("callback_to_promise").len(), 1);
+        assert!(transform_warnings("function_to_arrow").is_empty());
+        assert!(transform_warnings("if_else_to_match").is_empty());
+    }
+}
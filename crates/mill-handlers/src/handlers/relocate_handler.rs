@@ -10,16 +10,18 @@
 //!   "target": {
 //!     "kind": "symbol" | "file" | "directory",
 //!     "filePath": "path/to/file",
-//!     "line": 10,        // Required for symbol moves
-//!     "character": 5     // Required for symbol moves
+//!     "line": 10,        // Required for symbol moves,
+//!     "character": 5     // unless "symbolName" is given
 //!   },
 //!   "destination": "path/to/destination",
 //!   "options": {
-//!     "dryRun": true  // Default: true (preview mode)
+//!     "dryRun": true,  // Default: true (preview mode)
+//!     "previewFormat": "plan"  // Or "diff" for a unified diff preview
 //!   }
 //! }
 //! ```
 
+use crate::handlers::common::{apply_preview_format, resolve_symbol_target, PreviewFormat};
 use crate::handlers::relocate_ops::{directory_move, file_move, symbol_move};
 use crate::handlers::tool_definitions::{Diagnostic, DiagnosticSeverity, WriteResponse};
 use crate::handlers::tools::ToolHandler;
@@ -63,7 +65,12 @@ struct RelocateParams {
 #[serde(rename_all = "camelCase")]
 struct RelocateTarget {
     kind: String,
+    /// Optional for symbols named by `symbol_name`
+    #[serde(default)]
     file_path: String,
+    /// Qualified symbol name, instead of `line` and `character`
+    #[serde(default)]
+    symbol_name: Option<String>,
     #[serde(default)]
     line: Option<u32>,
     #[serde(default)]
@@ -76,11 +83,16 @@ struct RelocateTarget {
 struct RelocateOptions {
     #[serde(default = "crate::default_true")]
     dry_run: bool,
+    #[serde(default)]
+    preview_format: PreviewFormat,
 }
 
 impl Default for RelocateOptions {
     fn default() -> Self {
-        Self { dry_run: true }
+        Self {
+            dry_run: true,
+            preview_format: PreviewFormat::Plan,
+        }
     }
 }
 
@@ -109,10 +121,24 @@ impl ToolHandler for RelocateHandler {
             .clone()
             .ok_or_else(|| ServerError::invalid_request("Missing arguments for relocate"))?;
 
-        let params: RelocateParams = serde_json::from_value(args).map_err(|e| {
+        let mut params: RelocateParams = serde_json::from_value(args).map_err(|e| {
             ServerError::invalid_request(format!("Invalid relocate parameters: {}", e))
         })?;
 
+        let target = &mut params.target;
+        let symbol_name = target
+            .symbol_name
+            .as_deref()
+            .filter(|_| target.kind == "symbol");
+        resolve_symbol_target(
+            context,
+            symbol_name,
+            &mut target.file_path,
+            &mut target.line,
+            &mut target.character,
+        )
+        .await?;
+
         info!(
             operation_id = %operation_id,
             kind = %params.target.kind,
@@ -172,7 +198,8 @@ impl ToolHandler for RelocateHandler {
 
         // Handle dry run vs execution
         if params.options.dry_run {
-            self.build_preview_response(&plan, &params, &operation_id)
+            self.build_preview_response(context, &plan, &params, &operation_id)
+                .await
         } else {
             self.execute_and_build_response(context, plan, &params, &operation_id)
                 .await
@@ -182,8 +209,9 @@ impl ToolHandler for RelocateHandler {
 
 impl RelocateHandler {
     /// Build preview response from plan
-    fn build_preview_response(
+    async fn build_preview_response(
         &self,
+        context: &mill_handler_api::ToolHandlerContext,
         plan: &RefactorPlan,
         params: &RelocateParams,
         operation_id: &str,
@@ -218,6 +246,7 @@ impl RelocateHandler {
         for warning in warnings {
             response = response.with_warning(warning);
         }
+        apply_preview_format(context, plan, params.options.preview_format, &mut response).await?;
 
         serde_json::to_value(&response)
             .map(|v| serde_json::json!({ "content": v }))
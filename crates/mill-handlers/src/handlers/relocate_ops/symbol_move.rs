@@ -72,21 +72,7 @@ pub async fn plan_symbol_move(
             Ok(plan)
         }
         Err(e) => {
-            // Check if the edit was applied directly via workspace/applyEdit
-            // This happens with TypeScript LSP and other servers that use the
-            // workspace/applyEdit flow for refactorings
-            let error_msg = format!("{}", e);
-            if error_msg.contains("LSP_APPLIED_VIA_WORKSPACE_EDIT") {
-                info!(
-                    operation_id = %operation_id,
-                    "LSP applied edit directly via workspace/applyEdit - returning success"
-                );
-                // The files have already been modified by the workspace/applyEdit handler
-                // Return a success marker error that the caller can interpret
-                return Err(e);
-            }
-
-            // LSP failed for a different reason, try AST fallback
+            // LSP failed, try AST fallback
             warn!(
                 operation_id = %operation_id,
                 error = %e,
@@ -129,20 +115,23 @@ async fn try_lsp_symbol_move(
         "Getting or creating LSP client for extension"
     );
 
-    // Get or create LSP client for this extension
-    let client = adapter.get_or_create_client(extension).await.map_err(|e| {
-        error!(
-            operation_id = %operation_id,
-            error = %e,
-            extension = %extension,
-            function = "try_lsp_symbol_move",
-            "No LSP server configured for extension"
-        );
-        ServerError::not_supported(format!(
-            "No LSP server configured for extension {}: {}",
-            extension, e
-        ))
-    })?;
+    // Get or create the LSP client for the file's project root
+    let client = adapter
+        .get_or_create_client_for_file(Path::new(target_path))
+        .await
+        .map_err(|e| {
+            error!(
+                operation_id = %operation_id,
+                error = %e,
+                extension = %extension,
+                function = "try_lsp_symbol_move",
+                "No LSP server configured for extension"
+            );
+            ServerError::not_supported(format!(
+                "No LSP server configured for extension {}: {}",
+                extension, e
+            ))
+        })?;
 
     // Convert source path to absolute and create file URI
     let path = Path::new(target_path);
@@ -340,7 +329,11 @@ async fn try_lsp_symbol_move(
             ServerError::internal(format!("Failed to serialize ExecuteCommandParams: {}", e))
         })?;
 
-        // Send the command and try to interpret the result as a WorkspaceEdit
+        // Servers such as TypeScript apply the refactoring by sending
+        // workspace/applyEdit while the command runs. Capture those edits into
+        // this plan so they are previewed in dry-run and applied with the rest
+        // of the plan, instead of being written behind Mill's back.
+        let capture = client.capture_edits().await;
         let result_value = client
             .send_request("workspace/executeCommand", params_value)
             .await
@@ -354,42 +347,42 @@ async fn try_lsp_symbol_move(
                 );
                 ServerError::internal(format!("executeCommand failed: {}", e))
             })?;
+        let captured_edit = capture.finish()?;
 
         debug!(
             operation_id = %operation_id,
             response = ?result_value,
+            captured_edit = captured_edit.is_some(),
             "Received response from workspace/executeCommand"
         );
 
-        // Handle two cases:
-        // 1. The server returns a WorkspaceEdit directly
-        // 2. The server returns null (edit was applied via workspace/applyEdit request)
-        if result_value.is_null() {
-            // When TypeScript LSP (and others) apply refactorings, they send workspace/applyEdit
-            // to the client, then return null. Since we now handle workspace/applyEdit,
-            // the edit has already been applied. Return success with empty WorkspaceEdit
-            // since we can't reconstruct what was applied.
+        // The command may return a WorkspaceEdit, send one via
+        // workspace/applyEdit, or both
+        let returned_edit = match serde_json::from_value::<lsp_types::WorkspaceEdit>(result_value) {
+            Ok(edit) => Some(edit),
+            Err(_) if captured_edit.is_some() => None,
+            Err(e) => {
+                error!(
+                    operation_id = %operation_id,
+                    error = %e,
+                    function = "try_lsp_symbol_move",
+                    "Failed to parse WorkspaceEdit from executeCommand result"
+                );
+                return Err(ServerError::internal(format!(
+                    "Failed to parse WorkspaceEdit: {}",
+                    e
+                )));
+            }
+        };
+        if captured_edit.is_some() {
             info!(
                 operation_id = %operation_id,
-                "workspace/executeCommand returned null - edit was likely applied via workspace/applyEdit"
+                "Added edits sent via workspace/applyEdit to the move plan"
             );
-
-            // Return a marker indicating the edit was applied server-side
-            // The caller should check if this is dryRun and handle accordingly
-            return Err(ServerError::internal(
-                "LSP_APPLIED_VIA_WORKSPACE_EDIT: The LSP server applied the edit directly via workspace/applyEdit. The refactoring has been completed."
-            ));
         }
 
-        serde_json::from_value(result_value).map_err(|e| {
-            error!(
-                operation_id = %operation_id,
-                error = %e,
-                function = "try_lsp_symbol_move",
-                "Failed to parse WorkspaceEdit from executeCommand result"
-            );
-            ServerError::internal(format!("Failed to parse WorkspaceEdit: {}", e))
-        })?
+        mill_lsp::lsp_system::merge_workspace_edits(returned_edit.into_iter().chain(captured_edit))?
+            .ok_or_else(|| ServerError::not_supported("Move command produced no edits"))?
     } else {
         // No actionable information found
         error!(
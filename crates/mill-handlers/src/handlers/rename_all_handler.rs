@@ -6,6 +6,7 @@
 //! This handler uses the rename planning service and exposes it through the
 //! Magnificent Seven API with the WriteResponse envelope.
 
+use super::common::{apply_preview_format, resolve_symbol_target, PreviewFormat};
 use super::rename_ops::{RenameOptions, RenameService, RenameTarget, SymbolSelector};
 use super::tool_definitions::WriteResponse;
 use crate::handlers::tools::ToolHandler;
@@ -63,7 +64,12 @@ struct RenameAllTarget {
     /// Kind of target: "symbol", "file", or "directory"
     kind: String,
     /// Path to the file/directory, or file containing the symbol
+    /// (optional for symbols named by `symbol_name`)
+    #[serde(default)]
     file_path: String,
+    /// Qualified symbol name, instead of `line` and `character`
+    #[serde(default)]
+    symbol_name: Option<String>,
     /// New name (required for batch mode, optional for single mode)
     #[serde(default)]
     new_name: Option<String>,
@@ -91,6 +97,9 @@ struct RenameAllOptions {
     /// When None, auto-detects based on path patterns (moving crate into another crate's src/).
     #[serde(default)]
     consolidate: Option<bool>,
+    /// How a dry run presents the plan: "plan" (default) or "diff"
+    #[serde(default)]
+    preview_format: PreviewFormat,
 }
 
 impl Default for RenameAllOptions {
@@ -99,6 +108,7 @@ impl Default for RenameAllOptions {
             dry_run: true, // Safe default - preview mode
             scope: None,
             consolidate: None,
+            preview_format: PreviewFormat::Plan,
         }
     }
 }
@@ -109,10 +119,14 @@ impl RenameAllHandler {
         // For symbol renames, position is required
         let selector = if target.kind == "symbol" {
             let line = target.line.ok_or_else(|| {
-                ServerError::invalid_request("line is required for symbol rename")
+                ServerError::invalid_request(
+                    "line is required for symbol rename unless symbolName is given",
+                )
             })?;
             let character = target.character.ok_or_else(|| {
-                ServerError::invalid_request("character is required for symbol rename")
+                ServerError::invalid_request(
+                    "character is required for symbol rename unless symbolName is given",
+                )
             })?;
 
             Some(SymbolSelector {
@@ -327,10 +341,27 @@ impl ToolHandler for RenameAllHandler {
             .as_ref()
             .ok_or_else(|| ServerError::invalid_request("Missing arguments for rename_all"))?;
 
-        let params: RenameAllParams = RenameAllParams::deserialize(args).map_err(|e| {
+        let mut params: RenameAllParams = RenameAllParams::deserialize(args).map_err(|e| {
             ServerError::invalid_request(format!("Invalid rename_all parameters: {}", e))
         })?;
 
+        // Locate symbols given by name
+        let targets = params.targets.iter_mut().flatten();
+        for target in params.target.iter_mut().chain(targets) {
+            let symbol_name = target
+                .symbol_name
+                .as_deref()
+                .filter(|_| target.kind == "symbol");
+            resolve_symbol_target(
+                context,
+                symbol_name,
+                &mut target.file_path,
+                &mut target.line,
+                &mut target.character,
+            )
+            .await?;
+        }
+
         let rename_options = Self::convert_to_rename_options(&params.options);
 
         // Determine mode: Batch or Single
@@ -425,7 +456,15 @@ impl ToolHandler for RenameAllHandler {
             );
 
             // Preview mode - return plan
-            Self::convert_plan_to_write_response(&refactor_plan)?
+            let mut response = Self::convert_plan_to_write_response(&refactor_plan)?;
+            apply_preview_format(
+                context,
+                &refactor_plan,
+                params.options.preview_format,
+                &mut response,
+            )
+            .await?;
+            response
         } else {
             // Execution mode - execute the plan
             info!(
@@ -469,6 +508,7 @@ mod tests {
         let target = RenameAllTarget {
             kind: "file".to_string(),
             file_path: "src/main.rs".to_string(),
+            symbol_name: None,
             new_name: None,
             line: None,
             character: None,
@@ -485,6 +525,7 @@ mod tests {
         let target = RenameAllTarget {
             kind: "symbol".to_string(),
             file_path: "src/lib.rs".to_string(),
+            symbol_name: None,
             new_name: None,
             line: Some(10),
             character: Some(5),
@@ -504,6 +545,7 @@ mod tests {
         let target = RenameAllTarget {
             kind: "symbol".to_string(),
             file_path: "src/lib.rs".to_string(),
+            symbol_name: None,
             new_name: None,
             line: None,
             character: Some(5),
@@ -49,13 +49,16 @@ impl RenameService {
             .as_ref()
             .ok_or_else(|| ServerError::internal("LSP adapter not initialized"))?;
 
-        // Get or create LSP client for this extension
-        let client = adapter.get_or_create_client(extension).await.map_err(|e| {
-            ServerError::not_supported(format!(
-                "No LSP server configured for extension {}: {}",
-                extension, e
-            ))
-        })?;
+        // Get or create the LSP client for the file's project root
+        let client = adapter
+            .get_or_create_client_for_file(path)
+            .await
+            .map_err(|e| {
+                ServerError::not_supported(format!(
+                    "No LSP server configured for extension {}: {}",
+                    extension, e
+                ))
+            })?;
 
         // Convert path to absolute and create file URI
         let abs_path = tokio::fs::canonicalize(path)
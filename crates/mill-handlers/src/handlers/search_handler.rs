@@ -3,18 +3,22 @@
 //! Handles: search_code
 //!
 //! Implements workspace-wide symbol search with filtering and pagination.
-//! Reuses the existing workspace symbol search functionality via the plugin system
-//! but exposes it through a more specialized API for code search.
+//! Results come from the built-in symbol index (see `workspace::symbol_index`),
+//! merged with `workspace/symbol` results from language servers when LSP is
+//! enabled, so search works with `lsp.mode = "off"` and while servers index.
 
+use super::common::lsp_mode;
+use super::tools::extensions::get_concrete_app_state;
 use super::tools::ToolHandler;
+use super::workspace::{fuzzy_score, PathFilter, ScoredSymbol};
 use async_trait::async_trait;
 use mill_foundation::core::model::mcp::ToolCall;
 use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
 use mill_plugin_api::SymbolKind;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, warn};
@@ -31,6 +35,8 @@ struct SearchCodeRequest {
     kind: Option<String>,
     #[serde(default)]
     workspace_path: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
     #[serde(default = "default_limit")]
     limit: usize,
     #[serde(default)]
@@ -68,6 +74,99 @@ impl SearchHandler {
         }
     }
 
+    /// Refresh the workspace's symbol index and query it
+    ///
+    /// Returns no matches when the server's language plugins are unavailable.
+    async fn search_symbol_index(
+        &self,
+        context: &mill_handler_api::ToolHandlerContext,
+        query: &str,
+        workspace_path: &Path,
+        kind_filter: Option<SymbolKind>,
+        path_filter: Option<&PathFilter>,
+    ) -> (Vec<ScoredSymbol>, Option<String>) {
+        let Ok(concrete_state) = get_concrete_app_state(&context.app_state) else {
+            return (Vec::new(), None);
+        };
+        let index = &concrete_state.symbol_index;
+        let warning = index
+            .refresh(
+                concrete_state.language_plugins.all_plugins(),
+                concrete_state.file_service.ast_cache(),
+            )
+            .await
+            .err()
+            .map(|e| format!("symbol index: {}", e));
+        let matches = index
+            .search(query, kind_filter, workspace_path, path_filter)
+            .await;
+        (matches, warning)
+    }
+
+    /// Merge symbol index matches with LSP results, best matches first
+    ///
+    /// An LSP result replaces the index match for the same name, file and
+    /// line. LSP results outside `path_filter` are dropped.
+    fn merge_symbol_results(
+        query: &str,
+        workspace_path: &Path,
+        lsp_symbols: Vec<Value>,
+        index_matches: Vec<ScoredSymbol>,
+        path_filter: Option<&PathFilter>,
+    ) -> Vec<Value> {
+        let mut seen: HashSet<(PathBuf, u64, String)> = HashSet::new();
+        let mut ranked: Vec<(i64, Value)> = Vec::new();
+
+        for symbol in lsp_symbols {
+            let name = symbol
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let location = Self::lsp_symbol_location(&symbol);
+            if let Some(filter) = path_filter {
+                match &location {
+                    Some((path, _)) if filter.matches(workspace_path, path) => {}
+                    _ => continue,
+                }
+            }
+            // The server matched it with its own rules, so keep non-fuzzy matches too
+            let score = fuzzy_score(query, &name).unwrap_or(0);
+            if let Some((path, line)) = location {
+                seen.insert((path, line, name));
+            }
+            ranked.push((score, symbol));
+        }
+
+        for matched in index_matches {
+            let symbol = &matched.symbol;
+            let key = (
+                symbol.path.clone(),
+                symbol.location.line as u64,
+                symbol.name.clone(),
+            );
+            if !seen.contains(&key) {
+                ranked.push((matched.score, symbol.to_symbol_information()));
+            }
+        }
+
+        // Stable: on equal scores LSP results stay ahead of index matches
+        ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        ranked.into_iter().map(|(_, symbol)| symbol).collect()
+    }
+
+    /// File path and start line of an LSP `SymbolInformation`
+    fn lsp_symbol_location(symbol: &Value) -> Option<(PathBuf, u64)> {
+        let location = symbol.get("location")?;
+        let uri = location.get("uri")?.as_str()?;
+        let path = url::Url::parse(uri).ok()?.to_file_path().ok()?;
+        let line = location
+            .pointer("/range/start/line")
+            .and_then(|l| l.as_u64())
+            .unwrap_or(0);
+        Some((path, line))
+    }
+
     /// Parse symbol kind from string
     fn parse_symbol_kind(kind_str: &str) -> Option<SymbolKind> {
         match kind_str.to_lowercase().as_str() {
@@ -420,11 +519,25 @@ impl SearchHandler {
             ));
         }
 
-        // Get workspace path
-        let workspace_path = request
-            .workspace_path
-            .map(PathBuf::from)
-            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+        // Get workspace path, which must lie inside the project
+        let project_root = &context.app_state.project_root;
+        let workspace_path = match request.workspace_path {
+            Some(path) => {
+                let checked = context
+                    .app_state
+                    .file_service
+                    .to_absolute_path_checked(Path::new(&path))?;
+                // Spelled from the project root, like the indexed files
+                let canonical_root = project_root
+                    .canonicalize()
+                    .unwrap_or_else(|_| project_root.clone());
+                match checked.strip_prefix(&canonical_root) {
+                    Ok(relative) => project_root.join(relative),
+                    Err(_) => checked,
+                }
+            }
+            None => project_root.clone(),
+        };
 
         // Parse kind filter if provided
         let kind_filter = if let Some(kind_str) = &request.kind {
@@ -441,31 +554,78 @@ impl SearchHandler {
             None
         };
 
+        let path_filter = request
+            .path
+            .as_deref()
+            .map(PathFilter::new)
+            .transpose()
+            .map_err(ServerError::invalid_request)?;
+
         debug!(
             query = %request.query,
             kind = ?kind_filter,
+            path = ?request.path,
             limit = request.limit,
             offset = request.offset,
             "search_code: Parsed request"
         );
 
-        // Perform workspace search with pagination pushed down
-        let (paginated_symbols, total, processing_time, warnings) = self
-            .search_workspace_symbols(
+        let start_time = std::time::Instant::now();
+        let use_lsp = lsp_mode(context) != mill_config::config::LspMode::Off;
+        let lsp_search = async {
+            if !use_lsp {
+                return Ok((Vec::new(), None));
+            }
+            // Pagination happens after merging with the symbol index
+            self.search_workspace_symbols(
                 &context.plugin_manager,
                 &request.query,
-                workspace_path,
+                workspace_path.clone(),
                 kind_filter,
-                request.limit,
-                request.offset,
+                usize::MAX,
+                0,
             )
-            .await?;
+            .await
+            .map(|(symbols, _, _, warnings)| (symbols, warnings))
+        };
+        let index_search = self.search_symbol_index(
+            context,
+            &request.query,
+            &workspace_path,
+            kind_filter,
+            path_filter.as_ref(),
+        );
+        let (lsp_result, (index_matches, index_warning)) = tokio::join!(lsp_search, index_search);
+        let (lsp_symbols, lsp_warnings) = lsp_result?;
 
         debug!(
-            total_symbols = total,
-            paginated_count = paginated_symbols.len(),
-            "search_code: Got symbols from workspace search"
+            lsp_symbols = lsp_symbols.len(),
+            index_symbols = index_matches.len(),
+            "search_code: Got symbols from LSP and symbol index"
+        );
+
+        let merged = Self::merge_symbol_results(
+            &request.query,
+            &workspace_path,
+            lsp_symbols,
+            index_matches,
+            path_filter.as_ref(),
         );
+        let total = merged.len();
+        let paginated_symbols: Vec<Value> = merged
+            .into_iter()
+            .skip(request.offset)
+            .take(request.limit)
+            .collect();
+        let processing_time = start_time.elapsed().as_millis() as u64;
+
+        let mut warnings = lsp_warnings.unwrap_or_default();
+        warnings.extend(index_warning);
+        let warnings = if warnings.is_empty() {
+            None
+        } else {
+            Some(warnings)
+        };
 
         // Build response
         let response = SearchCodeResponse {
@@ -732,6 +892,63 @@ mod tests {
             SymbolKind::Function
         ));
     }
+
+    #[test]
+    fn test_merge_symbol_results() {
+        use crate::handlers::workspace::symbol_index::IndexedSymbol;
+        use mill_plugin_api::SourceLocation;
+
+        let workspace = Path::new("/work");
+        let indexed = |name: &str, file: &str, line: usize| ScoredSymbol {
+            score: fuzzy_score("config", name).unwrap(),
+            symbol: IndexedSymbol {
+                name: name.to_string(),
+                kind: SymbolKind::Function,
+                path: workspace.join(file),
+                location: SourceLocation { line, column: 0 },
+                end_location: None,
+            },
+        };
+        let lsp = vec![
+            json!({
+                "name": "load_config",
+                "kind": 12,
+                "containerName": "app",
+                "location": {
+                    "uri": "file:///work/src/app.rs",
+                    "range": {"start": {"line": 3, "character": 7}, "end": {"line": 3, "character": 18}}
+                }
+            }),
+            json!({
+                "name": "ConfigError",
+                "kind": 10,
+                "location": {"uri": "file:///work/tests/errors.rs", "range": {"start": {"line": 0, "character": 0}}}
+            }),
+        ];
+        let index = vec![
+            indexed("Config", "src/config.rs", 1),
+            indexed("load_config", "src/app.rs", 3),
+        ];
+
+        let merged =
+            SearchHandler::merge_symbol_results("config", workspace, lsp.clone(), index, None);
+        let names: Vec<&str> = merged.iter().map(|s| s["name"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["Config", "ConfigError", "load_config"]);
+        // The duplicate keeps the LSP result
+        assert_eq!(merged[2]["containerName"], "app");
+        assert_eq!(merged[0]["location"]["uri"], "file:///work/src/config.rs");
+
+        let src_only = PathFilter::new("src").unwrap();
+        let merged = SearchHandler::merge_symbol_results(
+            "config",
+            workspace,
+            lsp,
+            vec![indexed("Config", "src/config.rs", 1)],
+            Some(&src_only),
+        );
+        let names: Vec<&str> = merged.iter().map(|s| s["name"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["Config", "load_config"]);
+    }
 }
 
 #[cfg(test)]
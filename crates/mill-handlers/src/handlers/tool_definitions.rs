@@ -11,7 +11,7 @@
 //! 3. `rename_all` - Rename symbols, files, or directories with reference updates
 //! 4. `relocate` - Move symbols, files, or directories
 //! 5. `prune` - Delete symbols, files, or directories with cleanup
-//! 6. `refactor` - Extract, inline, and transform operations
+//! 6. `refactor` - Extract, inline, transform, and reorder operations
 //! 7. `workspace` - Package management, dependency extraction, find/replace
 
 use serde_json::{json, Value};
@@ -111,7 +111,7 @@ pub fn inspect_code_schema() -> Value {
 pub fn search_code_schema() -> Value {
     json!({
         "name": "search_code",
-        "description": "Search for symbols across the workspace. Supports fuzzy matching and filtering by kind.",
+        "description": "Search for symbols across the workspace. Supports fuzzy matching and filtering by kind and path. Uses the built-in symbol index, merged with language server results when LSP is enabled.",
         "inputSchema": {
             "type": "object",
             "properties": {
@@ -126,7 +126,11 @@ pub fn search_code_schema() -> Value {
                 },
                 "workspacePath": {
                     "type": "string",
-                    "description": "Root path to search within (defaults to project root)"
+                    "description": "Path inside the project to search within (defaults to project root)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Only return symbols from files under this path or matching this glob, relative to workspacePath (e.g. 'src/handlers', '**/*.ts')"
                 },
                 "limit": {
                     "type": "integer",
@@ -167,14 +171,22 @@ pub fn rename_all_schema() -> Value {
                         },
                         "line": {
                             "type": "integer",
-                            "description": "0-based line number (required for symbol rename)"
+                            "description": "0-based line number (required for symbol rename unless symbolName is given)"
                         },
                         "character": {
                             "type": "integer",
-                            "description": "0-based character offset (required for symbol rename)"
+                            "description": "0-based character offset (required for symbol rename unless symbolName is given)"
+                        },
+                        "symbolName": {
+                            "type": "string",
+                            "description": "Alternative to line/character for symbols: a qualified name such as UserService.login, src/app.ts#UserService.login, pkg.module:Class.method or crate::module::Type::method. filePath, if given, restricts the lookup to that file"
                         }
                     },
-                    "required": ["kind", "filePath"]
+                    "required": ["kind"],
+                    "anyOf": [
+                        { "required": ["filePath"] },
+                        { "required": ["symbolName"] }
+                    ]
                 },
                 "newName": {
                     "type": "string",
@@ -188,6 +200,12 @@ pub fn rename_all_schema() -> Value {
                             "default": true,
                             "description": "Preview changes without applying (default: true for safety)"
                         },
+                        "previewFormat": {
+                            "type": "string",
+                            "enum": ["plan", "diff"],
+                            "default": "plan",
+                            "description": "How a dry run is previewed: the plan, or a unified diff of the changes"
+                        },
                         "scope": {
                             "type": "string",
                             "enum": ["code", "standard", "comments", "everything"],
@@ -225,14 +243,22 @@ pub fn relocate_schema() -> Value {
                         },
                         "line": {
                             "type": "integer",
-                            "description": "0-based line number (required for symbol move)"
+                            "description": "0-based line number (required for symbol move unless symbolName is given)"
                         },
                         "character": {
                             "type": "integer",
-                            "description": "0-based character offset (required for symbol move)"
+                            "description": "0-based character offset (required for symbol move unless symbolName is given)"
+                        },
+                        "symbolName": {
+                            "type": "string",
+                            "description": "Alternative to line/character for symbols: a qualified name such as UserService.login, src/app.ts#UserService.login, pkg.module:Class.method or crate::module::Type::method. filePath, if given, restricts the lookup to that file"
                         }
                     },
-                    "required": ["kind", "filePath"]
+                    "required": ["kind"],
+                    "anyOf": [
+                        { "required": ["filePath"] },
+                        { "required": ["symbolName"] }
+                    ]
                 },
                 "destination": {
                     "type": "string",
@@ -245,6 +271,12 @@ pub fn relocate_schema() -> Value {
                             "type": "boolean",
                             "default": true,
                             "description": "Preview changes without applying (default: true for safety)"
+                        },
+                        "previewFormat": {
+                            "type": "string",
+                            "enum": ["plan", "diff"],
+                            "default": "plan",
+                            "description": "How a dry run is previewed: the plan, or a unified diff of the changes"
                         }
                     }
                 }
@@ -277,14 +309,22 @@ pub fn prune_schema() -> Value {
                         },
                         "line": {
                             "type": "integer",
-                            "description": "0-based line number (required for symbol delete)"
+                            "description": "0-based line number (required for symbol delete unless symbolName is given)"
                         },
                         "character": {
                             "type": "integer",
-                            "description": "0-based character offset (required for symbol delete)"
+                            "description": "0-based character offset (required for symbol delete unless symbolName is given)"
+                        },
+                        "symbolName": {
+                            "type": "string",
+                            "description": "Alternative to line/character for symbols: a qualified name such as UserService.login, src/app.ts#UserService.login, pkg.module:Class.method or crate::module::Type::method. filePath, if given, restricts the lookup to that file"
                         }
                     },
-                    "required": ["kind", "filePath"]
+                    "required": ["kind"],
+                    "anyOf": [
+                        { "required": ["filePath"] },
+                        { "required": ["symbolName"] }
+                    ]
                 },
                 "options": {
                     "type": "object",
@@ -294,6 +334,12 @@ pub fn prune_schema() -> Value {
                             "default": true,
                             "description": "Preview changes without applying (default: true for safety)"
                         },
+                        "previewFormat": {
+                            "type": "string",
+                            "enum": ["plan", "diff"],
+                            "default": "plan",
+                            "description": "How a dry run is previewed: the plan, or a unified diff of the changes"
+                        },
                         "cleanupImports": {
                             "type": "boolean",
                             "default": true,
@@ -317,17 +363,17 @@ pub fn prune_schema() -> Value {
     })
 }
 
-/// Schema for `refactor` - extract, inline, and transform operations
+/// Schema for `refactor` - extract, inline, transform, and reorder operations
 pub fn refactor_schema() -> Value {
     json!({
         "name": "refactor",
-        "description": "Perform code refactoring operations: extract (function, variable, constant, module), inline (variable, function, constant), or transform.",
+        "description": "Perform code refactoring operations: extract (function, variable, constant, module), inline (variable, function, constant), or transform (to_async, function_to_arrow, arrow_to_function, if_else_to_match, if_else_to_switch, callback_to_promise), or reorder (parameters with call sites, fields, variants, imports).",
         "inputSchema": {
             "type": "object",
             "properties": {
                 "action": {
                     "type": "string",
-                    "enum": ["extract", "inline", "transform"],
+                    "enum": ["extract", "inline", "transform", "reorder"],
                     "description": "The refactoring action to perform"
                 },
                 "params": {
@@ -336,8 +382,13 @@ pub fn refactor_schema() -> Value {
                     "properties": {
                         "kind": {
                             "type": "string",
-                            "enum": ["function", "variable", "constant", "module"],
-                            "description": "For extract/inline: the kind of code element"
+                            "enum": [
+                                "function", "variable", "constant", "module",
+                                "to_async", "function_to_arrow", "arrow_to_function",
+                                "if_else_to_match", "if_else_to_switch", "callback_to_promise",
+                                "parameters", "fields", "variants", "imports"
+                            ],
+                            "description": "For extract/inline: the kind of code element. For transform: the transformation to apply. For reorder: what to reorder"
                         },
                         "filePath": {
                             "type": "string",
@@ -356,11 +407,11 @@ pub fn refactor_schema() -> Value {
                         },
                         "line": {
                             "type": "integer",
-                            "description": "0-based line number (for inline action)"
+                            "description": "0-based line number (for inline, transform, and reorder actions)"
                         },
                         "character": {
                             "type": "integer",
-                            "description": "0-based character offset (for inline action)"
+                            "description": "0-based character offset (for inline, transform, and reorder actions)"
                         },
                         "name": {
                             "type": "string",
@@ -369,9 +420,22 @@ pub fn refactor_schema() -> Value {
                         "destination": {
                             "type": "string",
                             "description": "Destination file path (for extract module)"
+                        },
+                        "order": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Item names in their new order (for reorder action; omit for imports to sort alphabetically)"
+                        },
+                        "symbolName": {
+                            "type": "string",
+                            "description": "Alternative to line/character (not for extract): a qualified name such as UserService.login, src/app.ts#UserService.login, pkg.module:Class.method or crate::module::Type::method. filePath, if given, restricts the lookup to that file"
                         }
                     },
-                    "required": ["kind", "filePath"]
+                    "required": ["kind"],
+                    "anyOf": [
+                        { "required": ["filePath"] },
+                        { "required": ["symbolName"] }
+                    ]
                 },
                 "options": {
                     "type": "object",
@@ -380,6 +444,12 @@ pub fn refactor_schema() -> Value {
                             "type": "boolean",
                             "default": true,
                             "description": "Preview changes without applying (default: true for safety)"
+                        },
+                        "previewFormat": {
+                            "type": "string",
+                            "enum": ["plan", "diff"],
+                            "default": "plan",
+                            "description": "How a dry run is previewed: the plan, or a unified diff of the changes"
                         }
                     }
                 }
@@ -393,13 +463,13 @@ pub fn refactor_schema() -> Value {
 pub fn workspace_schema() -> Value {
     json!({
         "name": "workspace",
-        "description": "Workspace-level operations: create packages, extract dependencies, find/replace, update members, or verify project health.",
+        "description": "Workspace-level operations: create packages, extract dependencies, find/replace, update members, verify project health, or list, undo and redo applied operations.",
         "inputSchema": {
             "type": "object",
             "properties": {
                 "action": {
                     "type": "string",
-                    "enum": ["create_package", "extract_dependencies", "find_replace", "update_members", "verify_project"],
+                    "enum": ["create_package", "extract_dependencies", "find_replace", "update_members", "verify_project", "list_operations", "undo", "redo"],
                     "description": "The workspace action to perform"
                 },
                 "params": {
@@ -438,7 +508,7 @@ pub fn workspace_schema() -> Value {
                         },
                         "mode": {
                             "type": "string",
-                            "enum": ["literal", "regex", "case_preserving"],
+                            "enum": ["literal", "regex", "case_preserving", "structural"],
                             "default": "literal",
                             "description": "For find_replace: matching mode"
                         },
@@ -453,12 +523,16 @@ pub fn workspace_schema() -> Value {
                         },
                         "workspaceManifest": {
                             "type": "string",
-                            "description": "For update_members: path to workspace Cargo.toml"
+                            "description": "For update_members: path to the workspace manifest (Cargo.toml, package.json, pnpm-workspace.yaml or pyproject.toml)"
                         },
                         "members": {
                             "type": "array",
                             "items": { "type": "string" },
-                            "description": "For update_members: member paths to add/remove"
+                            "description": "For update_members: member paths or glob patterns to add/remove"
+                        },
+                        "operationId": {
+                            "type": "integer",
+                            "description": "For undo/redo: the journaled operation (default: latest applied for undo, earliest undone for redo)"
                         }
                     }
                 },
@@ -469,6 +543,11 @@ pub fn workspace_schema() -> Value {
                             "type": "boolean",
                             "default": true,
                             "description": "Preview changes without applying (default: true for safety)"
+                        },
+                        "merge": {
+                            "type": "boolean",
+                            "default": false,
+                            "description": "For undo/redo: merge with changes made to the files since, instead of refusing"
                         }
                     }
                 }
@@ -68,14 +68,22 @@ impl ToolRegistry {
         tool_call: ToolCall,
         context: &mill_handler_api::ToolHandlerContext,
     ) -> ServerResult<Value> {
-        if let Some(handler) = self.handlers.get(&tool_call.name) {
-            handler.handle_tool_call(context, &tool_call).await
-        } else {
-            Err(ServerError::not_supported(format!(
+        self.handler(&tool_call.name)?
+            .handle_tool_call(context, &tool_call)
+            .await
+    }
+
+    /// Look up the handler for a tool
+    ///
+    /// Callers holding the registry behind a lock should clone the handler out
+    /// and release the lock before running it, so tool calls can run concurrently.
+    pub fn handler(&self, tool_name: &str) -> ServerResult<Arc<dyn ToolHandler>> {
+        self.handlers.get(tool_name).cloned().ok_or_else(|| {
+            ServerError::not_supported(format!(
                 "Unknown tool: '{}'. Available tools: inspect_code, search_code, rename_all, relocate, prune, refactor, workspace",
-                tool_call.name
-            )))
-        }
+                tool_name
+            ))
+        })
     }
 
     /// Check if a tool is registered
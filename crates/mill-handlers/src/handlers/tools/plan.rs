@@ -1,12 +1,13 @@
 //! Plan tools handler
 //!
-//! Handles: apply_plan
+//! Handles: apply_plan, which applies a saved refactor plan or a unified diff
 
 use super::ToolHandler;
 use async_trait::async_trait;
 use mill_foundation::core::model::mcp::ToolCall;
 use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
 use mill_foundation::protocol::RefactorPlan;
+use mill_services::services::planning::diff::plan_from_patch;
 use mill_services::services::planning::executor::{ExecutionOptions, PlanExecutor};
 use serde::Deserialize;
 use serde_json::Value;
@@ -29,6 +30,8 @@ impl Default for PlanToolsHandler {
 #[serde(rename_all = "camelCase")]
 struct ApplyPlanParams {
     plan: Option<RefactorPlan>,
+    /// A unified diff to apply instead of a plan
+    patch: Option<String>,
     options: Option<ExecutionOptions>,
 }
 
@@ -55,10 +58,14 @@ impl ToolHandler for PlanToolsHandler {
             .clone()
             .unwrap_or(serde_json::Value::Null);
 
+        let concrete_state = super::extensions::get_concrete_app_state(&context.app_state)?;
         let (plan_value, options) = if let Ok(params) =
             serde_json::from_value::<ApplyPlanParams>(args.clone())
         {
-            if let Some(plan) = params.plan {
+            if let Some(patch) = params.patch {
+                let plan = plan_from_patch(&patch, &concrete_state.file_service).await?;
+                (serde_json::to_value(plan).unwrap_or(Value::Null), params.options)
+            } else if let Some(plan) = params.plan {
                 (serde_json::to_value(plan).unwrap_or(Value::Null), params.options)
             } else {
                 (args, params.options)
@@ -74,7 +81,6 @@ impl ToolHandler for PlanToolsHandler {
             ))
         })?;
 
-        let concrete_state = super::extensions::get_concrete_app_state(&context.app_state)?;
         let executor = PlanExecutor::new(concrete_state.file_service.clone());
         let result = executor.execute_plan(plan, options.unwrap_or_default()).await?;
 
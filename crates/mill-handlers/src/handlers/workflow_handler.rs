@@ -97,8 +97,15 @@ impl WorkflowHandler {
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
+        // Transactional mode may be requested per call or set by the recipe
+        let transactional = args.get("transactional").and_then(|v| v.as_bool());
+
         match concrete_state.planner.plan_for_intent(&intent) {
-            Ok(workflow) => {
+            Ok(mut workflow) => {
+                if let Some(transactional) = transactional {
+                    workflow.transactional = transactional;
+                }
+
                 info!(
                     intent = %intent.name,
                     workflow_name = %workflow.name,
@@ -106,6 +113,7 @@ impl WorkflowHandler {
                     complexity = workflow.metadata.complexity,
                     execute = execute,
                     dry_run = dry_run,
+                    transactional = workflow.transactional,
                     "Successfully planned workflow"
                 );
 
@@ -3,17 +3,20 @@
 //! This module provides a comprehensive find-replace tool that supports:
 //! - Literal string matching with optional whole-word boundaries
 //! - Regex pattern matching with capture group expansion
+//! - Structural (syntax-aware) pattern matching with metavariables
 //! - Case-preserving replacements
 //! - Configurable file scope (include/exclude patterns)
 //! - Dry-run mode for safe previewing
 
-use crate::handlers::workspace::{case_preserving, literal_matcher, regex_matcher};
+use crate::handlers::workspace::{
+    case_preserving, literal_matcher, regex_matcher, structural_matcher,
+};
 use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
 use mill_foundation::protocol::{EditLocation, EditPlan, EditPlanMetadata, EditType, TextEdit};
 use regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use tracing::{debug, error, info};
 
@@ -24,10 +27,10 @@ pub struct FindReplaceParams {
     /// Pattern to search for (literal or regex)
     pub pattern: String,
 
-    /// Replacement text (may contain $1, $2 for regex mode)
+    /// Replacement text (may contain $1, $2 for regex mode, $NAME for structural mode)
     pub replacement: String,
 
-    /// Search mode: "literal" or "regex"
+    /// Search mode: "literal", "regex" or "structural"
     #[serde(default = "default_mode")]
     pub mode: SearchMode,
 
@@ -54,6 +57,8 @@ pub struct FindReplaceParams {
 pub enum SearchMode {
     Literal,
     Regex,
+    /// Code pattern with `$NAME` metavariables, matched on syntax nodes
+    Structural,
 }
 
 fn default_mode() -> SearchMode {
@@ -213,15 +218,29 @@ pub async fn handle_find_replace(
     };
 
     // 1. Discover files matching scope
-    let files = discover_files(workspace_root, &scope_config).await?;
+    let mut files = discover_files(workspace_root, &scope_config).await?;
     debug!(files_count = files.len(), "Discovered files to search");
 
+    // Structural patterns are compiled per language; only files of languages
+    // with structural search support are searched
+    let structural_patterns = if params.mode == SearchMode::Structural {
+        let patterns = compile_structural_patterns(&files, &params, context).await?;
+        files.retain(|file| {
+            file.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| patterns.contains_key(ext))
+        });
+        patterns
+    } else {
+        HashMap::new()
+    };
+
     // 2. Process each file
     let mut all_edits: Vec<FileEdits> = Vec::new();
     let mut total_matches = 0;
 
     for file_path in files {
-        match process_file(&file_path, &params, context).await {
+        match process_file(&file_path, &params, &structural_patterns, context).await {
             Ok(file_edits) => {
                 if !file_edits.edits.is_empty() {
                     total_matches += file_edits.edits.len();
@@ -354,10 +373,63 @@ async fn discover_files(
     Ok(files)
 }
 
+/// Compile the structural pattern for each file extension that supports it
+///
+/// Languages whose tokenizer rejects the pattern are skipped; it is an error
+/// if no language accepts it.
+async fn compile_structural_patterns(
+    files: &[PathBuf],
+    params: &FindReplaceParams,
+    context: &mill_handler_api::ToolHandlerContext,
+) -> Result<HashMap<String, structural_matcher::StructuralPattern>, ServerError> {
+    let encoded = structural_matcher::encode_metavariables(&params.pattern);
+    let extensions: HashSet<&str> = files
+        .iter()
+        .filter_map(|file| file.extension().and_then(|ext| ext.to_str()))
+        .collect();
+
+    let mut patterns = HashMap::new();
+    let mut last_error = None;
+    for extension in extensions {
+        let Some(support) = context
+            .app_state
+            .language_plugins
+            .get_plugin(extension)
+            .and_then(|plugin| plugin.structural_search_support())
+        else {
+            continue;
+        };
+        let compiled = match support.tokenize(&encoded).await {
+            Ok(tokens) => structural_matcher::StructuralPattern::new(&encoded, &tokens)
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(format!("Invalid structural pattern: {}", e)),
+        };
+        match compiled {
+            Ok(pattern) => {
+                structural_matcher::validate_replacement(&pattern, &params.replacement)
+                    .map_err(|e| ServerError::invalid_request(e.to_string()))?;
+                patterns.insert(extension.to_string(), pattern);
+            }
+            Err(e) => {
+                debug!(extension, error = %e, "Structural pattern rejected for language");
+                last_error = Some(e);
+            }
+        }
+    }
+
+    if patterns.is_empty() {
+        if let Some(e) = last_error {
+            return Err(ServerError::invalid_request(e));
+        }
+    }
+    Ok(patterns)
+}
+
 /// Process a single file and find all matches
 async fn process_file(
     file_path: &Path,
     params: &FindReplaceParams,
+    structural_patterns: &HashMap<String, structural_matcher::StructuralPattern>,
     context: &mill_handler_api::ToolHandlerContext,
 ) -> Result<FileEdits, ServerError> {
     // Read file content
@@ -381,6 +453,34 @@ async fn process_file(
                     .map_err(|e| ServerError::invalid_request(format!("Regex error: {}", e)))?;
             convert_regex_matches_to_edits(matches)?
         }
+        SearchMode::Structural => {
+            let extension = file_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or_default();
+            let support = context
+                .app_state
+                .language_plugins
+                .get_plugin(extension)
+                .and_then(|plugin| plugin.structural_search_support());
+            match (structural_patterns.get(extension), support) {
+                // Skip parsing files that lack a word of the pattern
+                (Some(pattern), Some(support)) if pattern.words().all(|w| content.contains(w)) => {
+                    let index = support
+                        .syntax_index(&content, file_path)
+                        .await
+                        .map_err(|e| ServerError::parse(e.to_string()))?;
+                    let matches = structural_matcher::find_structural_matches(
+                        &content,
+                        &index,
+                        pattern,
+                        &params.replacement,
+                    );
+                    convert_structural_matches_to_edits(matches)
+                }
+                _ => Vec::new(),
+            }
+        }
     };
 
     Ok(FileEdits {
@@ -453,6 +553,32 @@ fn convert_regex_matches_to_edits(
         .collect()
 }
 
+/// Convert structural matches to TextEdit objects
+fn convert_structural_matches_to_edits(
+    matches: Vec<structural_matcher::StructuralMatch>,
+) -> Vec<TextEdit> {
+    matches
+        .into_iter()
+        .map(|m| TextEdit {
+            file_path: None, // Will be set later
+            edit_type: EditType::Replace,
+            location: EditLocation {
+                start_line: m.line - 1, // Convert from 1-indexed to 0-indexed
+                start_column: m.column,
+                end_line: m.end_line - 1,
+                end_column: m.end_column,
+            },
+            description: format!(
+                "Replace '{}' with '{}' (structural)",
+                m.matched_text, m.replacement_text
+            ),
+            original_text: m.matched_text,
+            new_text: m.replacement_text,
+            priority: 0,
+        })
+        .collect()
+}
+
 /// Create an EditPlan from all file edits
 fn create_edit_plan(all_edits: Vec<FileEdits>, params: &FindReplaceParams) -> EditPlan {
     let total_files = all_edits.len();
@@ -481,6 +607,7 @@ fn create_edit_plan(all_edits: Vec<FileEdits>, params: &FindReplaceParams) -> Ed
                 "mode": match params.mode {
                     SearchMode::Literal => "literal",
                     SearchMode::Regex => "regex",
+                    SearchMode::Structural => "structural",
                 }
             }),
             created_at: chrono::Utc::now(),
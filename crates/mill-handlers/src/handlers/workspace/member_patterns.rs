@@ -0,0 +1,135 @@
+//! Workspace member patterns as declared in workspace manifests
+//!
+//! Members are directories relative to the workspace manifest, either literal
+//! paths (`crates/core`) or globs (`packages/*`, `libs/**`). A leading `!`
+//! excludes matching directories (npm, pnpm). `*` does not cross `/`.
+
+use globset::{GlobBuilder, GlobMatcher};
+use std::path::Path;
+
+/// A compiled member pattern
+pub struct MemberPattern {
+    /// Pattern as written in the manifest
+    pub pattern: String,
+    /// Pattern starts with `!`
+    pub negated: bool,
+    /// Pattern contains glob metacharacters
+    pub is_glob: bool,
+    matcher: GlobMatcher,
+}
+
+/// All member patterns of a workspace manifest
+#[derive(Default)]
+pub struct MemberPatterns {
+    pub patterns: Vec<MemberPattern>,
+    /// Patterns that failed to compile, with the error
+    pub invalid: Vec<(String, String)>,
+}
+
+impl MemberPatterns {
+    pub fn new(members: &[String]) -> Self {
+        let mut patterns = MemberPatterns::default();
+        for member in members {
+            let (body, negated) = match member.strip_prefix('!') {
+                Some(body) => (body, true),
+                None => (member.as_str(), false),
+            };
+            let body = normalize_member(body);
+            match GlobBuilder::new(&body).literal_separator(true).build() {
+                Ok(glob) => patterns.patterns.push(MemberPattern {
+                    pattern: member.clone(),
+                    negated,
+                    is_glob: body.contains(['*', '?', '[', '{']),
+                    matcher: glob.compile_matcher(),
+                }),
+                Err(e) => patterns.invalid.push((member.clone(), e.to_string())),
+            }
+        }
+        patterns
+    }
+
+    /// Patterns that add members
+    pub fn includes(&self) -> impl Iterator<Item = &MemberPattern> {
+        self.patterns.iter().filter(|p| !p.negated)
+    }
+
+    /// The include pattern declaring `dir`, unless an exclude pattern drops it
+    pub fn declaring(&self, dir: &str) -> Option<&MemberPattern> {
+        let dir = normalize_member(dir);
+        if self
+            .patterns
+            .iter()
+            .any(|p| p.negated && p.matcher.is_match(&dir))
+        {
+            return None;
+        }
+        self.includes().find(|p| p.matcher.is_match(&dir))
+    }
+}
+
+impl MemberPattern {
+    pub fn is_match(&self, dir: &str) -> bool {
+        self.matcher.is_match(normalize_member(dir))
+    }
+}
+
+/// Normalize a member path: forward slashes, no leading `./`, no trailing `/`
+pub fn normalize_member(member: &str) -> String {
+    let member = member.replace('\\', "/");
+    let member = member.trim_start_matches("./").trim_end_matches('/');
+    member.to_string()
+}
+
+/// Package directories under `workspace_dir` declared by `patterns`
+///
+/// A package directory is one containing `package_manifest`. Returned paths
+/// are relative to `workspace_dir` and sorted.
+pub fn resolve_members(
+    workspace_dir: &Path,
+    patterns: &MemberPatterns,
+    package_manifest: &str,
+) -> Vec<String> {
+    let mut members: Vec<String> = super::verify_project::walk_project(workspace_dir)
+        .into_iter()
+        .filter(|file| file.file_name().and_then(|n| n.to_str()) == Some(package_manifest))
+        .filter_map(|file| {
+            let dir = file.parent()?.strip_prefix(workspace_dir).ok()?;
+            let dir = dir.to_string_lossy().replace('\\', "/");
+            (!dir.is_empty() && patterns.declaring(&dir).is_some()).then_some(dir)
+        })
+        .collect();
+    members.sort();
+    members
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(members: &[&str]) -> MemberPatterns {
+        MemberPatterns::new(&members.iter().map(|m| m.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_declaring() {
+        let patterns = patterns(&["packages/*", "./apps/web/", "!packages/legacy", "libs/**"]);
+        assert_eq!(
+            patterns
+                .declaring("packages/ui")
+                .map(|p| p.pattern.as_str()),
+            Some("packages/*")
+        );
+        assert!(patterns.declaring("packages/ui/nested").is_none());
+        assert!(patterns.declaring("packages/legacy").is_none());
+        assert!(patterns.declaring("apps/web").is_some());
+        assert!(patterns.declaring("libs/a/b").is_some());
+        assert!(patterns.declaring("tools/cli").is_none());
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let patterns = patterns(&["packages/[", "apps/*"]);
+        assert_eq!(patterns.invalid.len(), 1);
+        assert_eq!(patterns.includes().count(), 1);
+    }
+}
@@ -1,15 +1,27 @@
 //! Workspace-level operations module
 //!
-//! Contains utilities for workspace-wide operations like find/replace.
+//! Contains utilities for workspace-wide operations like find/replace,
+//! workspace member management, project verification and the built-in
+//! symbol index.
 
 pub mod case_preserving;
 pub mod find_replace_handler;
 pub mod literal_matcher;
+pub mod member_patterns;
 pub mod regex_matcher;
+pub mod structural_matcher;
+pub mod symbol_index;
+pub mod verify_project;
 
 pub use case_preserving::{
     apply_case_style, detect_case_style, replace_preserving_case, split_into_words, CaseStyle,
 };
 pub use find_replace_handler::handle_find_replace;
 pub use literal_matcher::{find_literal_matches, Match};
+pub use member_patterns::{resolve_members, MemberPattern, MemberPatterns};
 pub use regex_matcher::{find_regex_matches, RegexError, RegexMatch};
+pub use structural_matcher::{
+    find_structural_matches, StructuralError, StructuralMatch, StructuralPattern,
+};
+pub use symbol_index::{fuzzy_score, PathFilter, ScoredSymbol, SymbolIndex};
+pub use verify_project::{verify_project, ProjectReport};
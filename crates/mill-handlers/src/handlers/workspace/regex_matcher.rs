@@ -265,7 +265,7 @@ fn validate_replacement_template(template: &str) -> Result<(), RegexError> {
 /// Convert byte offset to (line, column) position
 ///
 /// Line numbers are 1-based, column numbers are 0-based
-fn byte_offset_to_position(content: &str, byte_offset: usize) -> (u32, u32) {
+pub(crate) fn byte_offset_to_position(content: &str, byte_offset: usize) -> (u32, u32) {
     let mut line = 1;
     let mut column = 0;
 
@@ -0,0 +1,446 @@
+//! Structural matching engine for workspace find_replace action
+//!
+//! Matches code patterns on the token stream and syntax nodes of a file, as
+//! provided by the language plugin's `StructuralSearchSupport`. Patterns are
+//! code with metavariables (`$X`, `$ARGS`): literal tokens must match exactly,
+//! ignoring whitespace and comments; a metavariable matches a single syntax
+//! node, and every occurrence of the same metavariable must match the same
+//! code. A match is only reported if it is itself a complete syntax node.
+
+use super::regex_matcher::byte_offset_to_position;
+use mill_plugin_api::SyntaxIndex;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+
+/// Identifier prefix metavariables are encoded with before tokenizing a pattern
+pub const METAVARIABLE_PREFIX: &str = "__mill_meta_";
+
+/// A single structural match with position and replacement information
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructuralMatch {
+    /// Start byte offset in the content
+    pub start_byte: usize,
+    /// End byte offset in the content
+    pub end_byte: usize,
+    /// The matched text
+    pub matched_text: String,
+    /// The replacement text with metavariables substituted
+    pub replacement_text: String,
+    /// Code bound to each metavariable
+    pub bindings: BTreeMap<String, String>,
+    /// Start line number (1-based)
+    pub line: u32,
+    /// Start column number (0-based)
+    pub column: u32,
+    /// End line number (1-based)
+    pub end_line: u32,
+    /// End column number (0-based, exclusive)
+    pub end_column: u32,
+}
+
+/// Errors in a structural pattern or replacement
+#[derive(Debug, Clone)]
+pub enum StructuralError {
+    /// Pattern that cannot be matched
+    InvalidPattern(String),
+    /// Replacement referring to unknown metavariables
+    InvalidReplacement(String),
+}
+
+impl fmt::Display for StructuralError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StructuralError::InvalidPattern(msg) => {
+                write!(f, "Invalid structural pattern: {}", msg)
+            }
+            StructuralError::InvalidReplacement(msg) => {
+                write!(f, "Invalid structural replacement: {}", msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StructuralError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatternToken {
+    Text(String),
+    Metavariable(String),
+}
+
+/// A pattern compiled against one language's tokenizer
+#[derive(Debug, Clone)]
+pub struct StructuralPattern {
+    tokens: Vec<PatternToken>,
+}
+
+impl StructuralPattern {
+    /// Build a pattern from the output of [`encode_metavariables`] and its tokens
+    pub fn new(encoded: &str, tokens: &[Range<usize>]) -> Result<Self, StructuralError> {
+        let tokens: Vec<PatternToken> = tokens
+            .iter()
+            .map(|range| {
+                let text = &encoded[range.clone()];
+                match text.strip_prefix(METAVARIABLE_PREFIX) {
+                    Some(name) => PatternToken::Metavariable(name.to_string()),
+                    None => PatternToken::Text(text.to_string()),
+                }
+            })
+            .collect();
+
+        if !tokens
+            .iter()
+            .any(|token| matches!(token, PatternToken::Text(_)))
+        {
+            return Err(StructuralError::InvalidPattern(
+                "pattern must contain code besides metavariables".to_string(),
+            ));
+        }
+        Ok(Self { tokens })
+    }
+
+    /// Names of the metavariables in the pattern
+    pub fn metavariables(&self) -> BTreeSet<&str> {
+        self.tokens
+            .iter()
+            .filter_map(|token| match token {
+                PatternToken::Metavariable(name) => Some(name.as_str()),
+                PatternToken::Text(_) => None,
+            })
+            .collect()
+    }
+
+    /// Identifiers and keywords of the pattern; a file lacking one cannot match
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.tokens.iter().filter_map(|token| match token {
+            PatternToken::Text(text) if text.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+                Some(text.as_str())
+            }
+            _ => None,
+        })
+    }
+}
+
+/// Replace each `$NAME` metavariable with an identifier, so the pattern tokenizes as code
+///
+/// `$$` is a literal `$`.
+pub fn encode_metavariables(pattern: &str) -> String {
+    substitute(pattern, |name| format!("{}{}", METAVARIABLE_PREFIX, name))
+}
+
+/// Check that `replacement` only uses metavariables bound by `pattern`
+pub fn validate_replacement(
+    pattern: &StructuralPattern,
+    replacement: &str,
+) -> Result<(), StructuralError> {
+    let bound = pattern.metavariables();
+    let mut unknown = BTreeSet::new();
+    substitute(replacement, |name| {
+        if !bound.contains(name) {
+            unknown.insert(format!("${}", name));
+        }
+        String::new()
+    });
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(StructuralError::InvalidReplacement(format!(
+            "{} not bound by the pattern",
+            unknown.into_iter().collect::<Vec<_>>().join(", ")
+        )))
+    }
+}
+
+/// Find all non-overlapping structural matches of `pattern` in content
+///
+/// `index` is the syntax index of `content`. Matches are searched left to
+/// right; code inside a match is not searched again.
+pub fn find_structural_matches(
+    content: &str,
+    index: &SyntaxIndex,
+    pattern: &StructuralPattern,
+    replacement: &str,
+) -> Vec<StructuralMatch> {
+    let matcher = Matcher::new(content, index, pattern);
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start < matcher.tokens.len() {
+        let mut bindings = HashMap::new();
+        match matcher.match_at(0, start, start, &mut bindings) {
+            Some(end) => {
+                matches.push(matcher.to_match(start, end, &bindings, replacement));
+                start = end.max(start + 1);
+            }
+            None => start += 1,
+        }
+    }
+    matches
+}
+
+/// Apply `replace` to each metavariable name in `text`, turning `$$` into `$`
+fn substitute(text: &str, mut replace: impl FnMut(&str) -> String) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(dollar) = rest.find('$') {
+        result.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        if let Some(escaped) = after.strip_prefix('$') {
+            result.push('$');
+            rest = escaped;
+            continue;
+        }
+        let name_len = after
+            .char_indices()
+            .take_while(|&(i, c)| {
+                c.is_ascii_uppercase() || c == '_' || (i > 0 && c.is_ascii_digit())
+            })
+            .count();
+        if name_len == 0 {
+            result.push('$');
+        } else {
+            result.push_str(&replace(&after[..name_len]));
+        }
+        rest = &after[name_len..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Token-level view of a file for matching one pattern
+struct Matcher<'a> {
+    content: &'a str,
+    tokens: &'a [Range<usize>],
+    pattern: &'a [PatternToken],
+    /// For each start token, the end tokens (exclusive) of nodes starting there, longest first
+    nodes_by_start: HashMap<usize, Vec<usize>>,
+    nodes: HashSet<(usize, usize)>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(content: &'a str, index: &'a SyntaxIndex, pattern: &'a StructuralPattern) -> Self {
+        let token_starting_at: HashMap<usize, usize> = index
+            .tokens
+            .iter()
+            .enumerate()
+            .map(|(i, range)| (range.start, i))
+            .collect();
+        let token_ending_at: HashMap<usize, usize> = index
+            .tokens
+            .iter()
+            .enumerate()
+            .map(|(i, range)| (range.end, i))
+            .collect();
+
+        // Nodes as token ranges; nodes not aligned with tokens cannot be matched
+        let nodes: HashSet<(usize, usize)> = index
+            .nodes
+            .iter()
+            .filter_map(|range| {
+                let start = *token_starting_at.get(&range.start)?;
+                let last = *token_ending_at.get(&range.end)?;
+                (start <= last).then_some((start, last + 1))
+            })
+            .collect();
+
+        let mut nodes_by_start: HashMap<usize, Vec<usize>> = HashMap::new();
+        for &(start, end) in &nodes {
+            nodes_by_start.entry(start).or_default().push(end);
+        }
+        for ends in nodes_by_start.values_mut() {
+            ends.sort_unstable_by(|a, b| b.cmp(a));
+        }
+
+        Self {
+            content,
+            tokens: &index.tokens,
+            pattern: &pattern.tokens,
+            nodes_by_start,
+            nodes,
+        }
+    }
+
+    fn text(&self, token: usize) -> &'a str {
+        &self.content[self.tokens[token].clone()]
+    }
+
+    /// Match pattern tokens from `pattern_pos` at token `pos`, returning the end token of the match
+    fn match_at(
+        &self,
+        pattern_pos: usize,
+        pos: usize,
+        start: usize,
+        bindings: &mut HashMap<&'a str, (usize, usize)>,
+    ) -> Option<usize> {
+        let Some(token) = self.pattern.get(pattern_pos) else {
+            return self.nodes.contains(&(start, pos)).then_some(pos);
+        };
+        match token {
+            PatternToken::Text(text) => {
+                if pos < self.tokens.len() && self.text(pos) == text {
+                    self.match_at(pattern_pos + 1, pos + 1, start, bindings)
+                } else {
+                    None
+                }
+            }
+            PatternToken::Metavariable(name) => {
+                if let Some(&(bound_start, bound_end)) = bindings.get(name.as_str()) {
+                    let len = bound_end - bound_start;
+                    let same = pos + len <= self.tokens.len()
+                        && (0..len).all(|i| self.text(bound_start + i) == self.text(pos + i));
+                    return if same {
+                        self.match_at(pattern_pos + 1, pos + len, start, bindings)
+                    } else {
+                        None
+                    };
+                }
+                for end in self.candidates(pos) {
+                    bindings.insert(name.as_str(), (pos, end));
+                    if let Some(matched_end) = self.match_at(pattern_pos + 1, end, start, bindings)
+                    {
+                        return Some(matched_end);
+                    }
+                    bindings.remove(name.as_str());
+                }
+                None
+            }
+        }
+    }
+
+    /// End tokens a metavariable at `pos` may extend to, longest first
+    ///
+    /// Nodes starting at `pos`, plus the token itself if it is a name or
+    /// literal (names of declarations are not always nodes of their own).
+    fn candidates(&self, pos: usize) -> Vec<usize> {
+        let mut ends = self.nodes_by_start.get(&pos).cloned().unwrap_or_default();
+        if pos < self.tokens.len() && !ends.contains(&(pos + 1)) {
+            let is_word = self
+                .text(pos)
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '"' || c == '\'');
+            if is_word {
+                ends.push(pos + 1);
+            }
+        }
+        ends
+    }
+
+    fn to_match(
+        &self,
+        start: usize,
+        end: usize,
+        bindings: &HashMap<&'a str, (usize, usize)>,
+        replacement: &str,
+    ) -> StructuralMatch {
+        let span =
+            |(first, end): (usize, usize)| self.tokens[first].start..self.tokens[end - 1].end;
+        let bindings: BTreeMap<String, String> = bindings
+            .iter()
+            .map(|(name, &tokens)| (name.to_string(), self.content[span(tokens)].to_string()))
+            .collect();
+        let replacement_text = substitute(replacement, |name| {
+            bindings.get(name).cloned().unwrap_or_default()
+        });
+
+        let bytes = span((start, end));
+        let (line, column) = byte_offset_to_position(self.content, bytes.start);
+        let (end_line, end_column) = byte_offset_to_position(self.content, bytes.end);
+        StructuralMatch {
+            start_byte: bytes.start,
+            end_byte: bytes.end,
+            matched_text: self.content[bytes].to_string(),
+            replacement_text,
+            bindings,
+            line,
+            column,
+            end_line,
+            end_column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whitespace-separated tokens; nodes given as substrings
+    fn index(content: &str, nodes: &[&str]) -> SyntaxIndex {
+        let mut tokens = Vec::new();
+        let mut start = None;
+        for (i, c) in content.char_indices().chain([(content.len(), ' ')]) {
+            match (c.is_whitespace(), start) {
+                (true, Some(s)) => {
+                    tokens.push(s..i);
+                    start = None;
+                }
+                (false, None) => start = Some(i),
+                _ => {}
+            }
+        }
+        let nodes = nodes
+            .iter()
+            .flat_map(|node| {
+                content
+                    .match_indices(node)
+                    .map(|(i, m)| i..i + m.len())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        SyntaxIndex { tokens, nodes }
+    }
+
+    fn pattern(pattern: &str) -> StructuralPattern {
+        let encoded = encode_metavariables(pattern);
+        let tokens = index(&encoded, &[]).tokens;
+        StructuralPattern::new(&encoded, &tokens).unwrap()
+    }
+
+    #[test]
+    fn test_metavariable_binds_node() {
+        let content = "let v = a . b . unwrap ( ) ;";
+        let index = index(content, &["a . b . unwrap ( )", "a . b", "a"]);
+        let matches = find_structural_matches(content, &index, &pattern("$X . unwrap ( )"), "$X ?");
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].matched_text, "a . b . unwrap ( )");
+        assert_eq!(matches[0].bindings["X"], "a . b");
+        assert_eq!(matches[0].replacement_text, "a . b ?");
+        assert_eq!((matches[0].line, matches[0].column), (1, 8));
+        assert_eq!((matches[0].end_line, matches[0].end_column), (1, 26));
+    }
+
+    #[test]
+    fn test_repeated_metavariable_must_match_same_code() {
+        let content = "x == x ; x == y ;";
+        let index = index(content, &["x == x", "x == y", "x", "y"]);
+        let matches = find_structural_matches(content, &index, &pattern("$A == $A"), "true");
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].start_byte, 0);
+    }
+
+    #[test]
+    fn test_match_must_be_a_node() {
+        // `b + c` is not a node of `a * b + c`
+        let content = "a * b + c";
+        let index = index(content, &["a * b + c", "a * b", "a", "b", "c"]);
+        let matches = find_structural_matches(content, &index, &pattern("$X + c"), "");
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].bindings["X"], "a * b");
+    }
+
+    #[test]
+    fn test_pattern_validation() {
+        let encoded = encode_metavariables("$X");
+        let tokens = index(&encoded, &[]).tokens;
+        assert!(StructuralPattern::new(&encoded, &tokens).is_err());
+
+        let p = pattern("foo ( $X )");
+        assert!(validate_replacement(&p, "bar($X)").is_ok());
+        assert!(validate_replacement(&p, "bar($Y)").is_err());
+        assert!(validate_replacement(&p, "cost: $$5").is_ok());
+        assert_eq!(p.words().collect::<Vec<_>>(), vec!["foo"]);
+    }
+}
@@ -0,0 +1,446 @@
+//! Built-in workspace symbol index
+//!
+//! Symbols come from `LanguagePlugin::parse` through the AST cache, so
+//! persisted parses are reused across restarts. Each refresh re-parses only
+//! the files whose size or modification time changed and drops deleted files.
+//! Queries are fuzzy-matched against symbol names and can be filtered by kind
+//! and by path, without any language server.
+
+use super::verify_project::walk_project;
+use futures::stream::{self, StreamExt};
+use globset::{Glob, GlobMatcher};
+use mill_ast::AstCache;
+use mill_plugin_api::{LanguagePlugin, SourceLocation, SymbolKind};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::RwLock;
+use tracing::debug;
+
+/// Files parsed concurrently during a refresh
+const PARSE_CONCURRENCY: usize = 16;
+
+/// A symbol declared in a workspace file
+#[derive(Debug, Clone)]
+pub struct IndexedSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub path: PathBuf,
+    /// 0-based start position
+    pub location: SourceLocation,
+    /// 0-based end position, when the parser reports one
+    pub end_location: Option<SourceLocation>,
+}
+
+impl IndexedSymbol {
+    /// LSP `SymbolInformation` shape, matching `workspace/symbol` results
+    pub fn to_symbol_information(&self) -> Value {
+        let end = self.end_location.as_ref().unwrap_or(&self.location);
+        let uri = url::Url::from_file_path(&self.path)
+            .map(|url| url.to_string())
+            .unwrap_or_else(|_| format!("file://{}", self.path.display()));
+        json!({
+            "name": self.name,
+            "kind": self.kind.to_lsp_kind(),
+            "location": {
+                "uri": uri,
+                "range": {
+                    "start": { "line": self.location.line, "character": self.location.column },
+                    "end": { "line": end.line, "character": end.column }
+                }
+            }
+        })
+    }
+}
+
+/// A query match with its fuzzy score (higher is better)
+#[derive(Debug, Clone)]
+pub struct ScoredSymbol {
+    pub score: i64,
+    pub symbol: IndexedSymbol,
+}
+
+/// Filter on a file's path relative to the index root
+///
+/// Patterns with glob metacharacters are matched as globs (`src/**/*.rs`),
+/// anything else as a directory or file prefix (`crates/core`).
+pub enum PathFilter {
+    Glob(GlobMatcher),
+    Prefix(String),
+}
+
+impl PathFilter {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let pattern = pattern
+            .replace('\\', "/")
+            .trim_start_matches("./")
+            .trim_end_matches('/')
+            .to_string();
+        if pattern.contains(['*', '?', '[', '{']) {
+            Glob::new(&pattern)
+                .map(|glob| PathFilter::Glob(glob.compile_matcher()))
+                .map_err(|e| format!("Invalid path filter '{}': {}", pattern, e))
+        } else {
+            Ok(PathFilter::Prefix(pattern))
+        }
+    }
+
+    /// Whether `path` (absolute, or relative to `root`) passes the filter
+    pub fn matches(&self, root: &Path, path: &Path) -> bool {
+        let relative = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        match self {
+            PathFilter::Glob(matcher) => matcher.is_match(&relative),
+            PathFilter::Prefix(prefix) => {
+                prefix.is_empty()
+                    || relative == *prefix
+                    || relative
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            }
+        }
+    }
+}
+
+/// Files indexed and removed by one refresh
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshStats {
+    pub parsed: usize,
+    pub removed: usize,
+}
+
+struct IndexedFile {
+    modified: Option<SystemTime>,
+    len: u64,
+    symbols: Vec<IndexedSymbol>,
+}
+
+/// Symbol index of one workspace root
+pub struct SymbolIndex {
+    root: PathBuf,
+    files: RwLock<HashMap<PathBuf, IndexedFile>>,
+}
+
+impl SymbolIndex {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            files: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Number of indexed files
+    pub async fn file_count(&self) -> usize {
+        self.files.read().await.len()
+    }
+
+    /// Bring the index up to date with the files on disk
+    ///
+    /// Files no plugin handles are skipped. A file that fails to read or
+    /// parse is indexed without symbols until it changes again.
+    pub async fn refresh(
+        &self,
+        plugins: &[Arc<dyn LanguagePlugin>],
+        cache: &AstCache,
+    ) -> Result<RefreshStats, String> {
+        let walk_root = self.root.clone();
+        let files = tokio::task::spawn_blocking(move || walk_project(&walk_root))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?;
+
+        let mut candidates = Vec::new();
+        {
+            let indexed = self.files.read().await;
+            for file in files {
+                let extension = file
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or_default();
+                let Some(plugin) = plugins.iter().find(|p| p.handles_extension(extension)) else {
+                    continue;
+                };
+                let Ok(metadata) = tokio::fs::metadata(&file).await else {
+                    continue;
+                };
+                let modified = metadata.modified().ok();
+                let unchanged = indexed.get(&file).is_some_and(|entry| {
+                    entry.len == metadata.len() && entry.modified == modified && modified.is_some()
+                });
+                candidates.push((file, plugin.clone(), modified, metadata.len(), unchanged));
+            }
+        }
+
+        let present: HashSet<PathBuf> = candidates.iter().map(|c| c.0.clone()).collect();
+        // Futures are built in a plain loop: a closure here makes the
+        // handler future fail the `Send` bound of `ToolHandler`
+        let mut parses = Vec::new();
+        for (file, plugin, modified, len, unchanged) in candidates {
+            if unchanged {
+                continue;
+            }
+            parses.push(async move {
+                let symbols = parse_file(&file, plugin.as_ref(), cache).await;
+                (
+                    file,
+                    IndexedFile {
+                        modified,
+                        len,
+                        symbols,
+                    },
+                )
+            });
+        }
+        let parsed: Vec<(PathBuf, IndexedFile)> = stream::iter(parses)
+            .buffer_unordered(PARSE_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut indexed = self.files.write().await;
+        let before = indexed.len();
+        indexed.retain(|file, _| present.contains(file));
+        let stats = RefreshStats {
+            parsed: parsed.len(),
+            removed: before - indexed.len(),
+        };
+        indexed.extend(parsed);
+        if stats != RefreshStats::default() {
+            debug!(
+                root = %self.root.display(),
+                parsed = stats.parsed,
+                removed = stats.removed,
+                files = indexed.len(),
+                "Refreshed symbol index"
+            );
+        }
+        Ok(stats)
+    }
+
+    /// Symbols matching `query` in files under `scope`, best matches first
+    ///
+    /// `path` is matched against file paths relative to `scope`.
+    pub async fn search(
+        &self,
+        query: &str,
+        kind: Option<SymbolKind>,
+        scope: &Path,
+        path: Option<&PathFilter>,
+    ) -> Vec<ScoredSymbol> {
+        let indexed = self.files.read().await;
+        let mut matches: Vec<ScoredSymbol> = indexed
+            .iter()
+            .filter(|(file, _)| file.starts_with(scope))
+            .filter(|(file, _)| path.is_none_or(|filter| filter.matches(scope, file)))
+            .flat_map(|(_, entry)| entry.symbols.iter())
+            .filter(|symbol| kind.is_none_or(|kind| symbol.kind == kind))
+            .filter_map(|symbol| {
+                fuzzy_score(query, &symbol.name).map(|score| ScoredSymbol {
+                    score,
+                    symbol: symbol.clone(),
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.symbol.name.len().cmp(&b.symbol.name.len()))
+                .then_with(|| a.symbol.path.cmp(&b.symbol.path))
+                .then_with(|| a.symbol.location.line.cmp(&b.symbol.location.line))
+        });
+        matches
+    }
+}
+
+async fn parse_file(
+    path: &Path,
+    plugin: &dyn LanguagePlugin,
+    cache: &AstCache,
+) -> Vec<IndexedSymbol> {
+    let source = match tokio::fs::read_to_string(path).await {
+        Ok(source) => source,
+        Err(e) => {
+            debug!(file = %path.display(), error = %e, "Skipping unreadable file");
+            return Vec::new();
+        }
+    };
+    match cache.symbols(plugin, &source).await {
+        Ok(symbols) => symbols
+            .into_iter()
+            .map(|symbol| IndexedSymbol {
+                name: symbol.name,
+                kind: symbol.kind,
+                path: path.to_path_buf(),
+                location: symbol.location,
+                end_location: symbol.end_location,
+            })
+            .collect(),
+        Err(e) => {
+            debug!(file = %path.display(), error = %e, "Failed to parse file for symbol index");
+            Vec::new()
+        }
+    }
+}
+
+/// Fuzzy match score of `name` against `query`, or `None` if it does not match
+///
+/// Exact, prefix and substring matches (case-insensitive) rank above
+/// subsequence matches; subsequence matches that hit word starts
+/// (`gUN` for `getUserName`, `g_u_n` for `get_user_name`) rank higher.
+pub fn fuzzy_score(query: &str, name: &str) -> Option<i64> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let query_lower = query.to_lowercase();
+    let name_lower = name.to_lowercase();
+
+    if name == query {
+        return Some(1100);
+    }
+    if name_lower == query_lower {
+        return Some(1000);
+    }
+    if name_lower.starts_with(&query_lower) {
+        return Some(800);
+    }
+    if let Some(position) = name_lower.find(&query_lower) {
+        let at_boundary = is_word_start(name, position);
+        return Some(if at_boundary { 700 } else { 600 } - position.min(100) as i64);
+    }
+
+    let name_chars: Vec<(usize, char)> = name.char_indices().collect();
+    let mut score = 100i64;
+    let mut next = 0;
+    let mut previous_match: Option<usize> = None;
+    for query_char in query_lower.chars() {
+        let found = name_chars[next..]
+            .iter()
+            .position(|(_, c)| c.to_lowercase().eq(std::iter::once(query_char)))?;
+        let index = next + found;
+        if is_word_start(name, name_chars[index].0) {
+            score += 10;
+        }
+        match previous_match {
+            Some(previous) if previous + 1 == index => score += 5,
+            Some(previous) => score -= (index - previous - 1).min(10) as i64,
+            None => score -= index.min(10) as i64,
+        }
+        previous_match = Some(index);
+        next = index + 1;
+    }
+    Some(score.clamp(1, 499))
+}
+
+/// Whether the character at byte `index` starts a word of an identifier
+fn is_word_start(name: &str, index: usize) -> bool {
+    let Some(current) = name[index..].chars().next() else {
+        return false;
+    };
+    let Some(previous) = name[..index].chars().next_back() else {
+        return true;
+    };
+    !previous.is_alphanumeric() || (current.is_uppercase() && previous.is_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score_ranking() {
+        let exact = fuzzy_score("Config", "Config").unwrap();
+        let prefix = fuzzy_score("Config", "ConfigLoader").unwrap();
+        let word = fuzzy_score("config", "load_config").unwrap();
+        let substring = fuzzy_score("onfig", "Config").unwrap();
+        let camel = fuzzy_score("gUN", "getUserName").unwrap();
+        let scattered = fuzzy_score("gun", "igniting").unwrap_or(0);
+
+        assert!(exact > prefix);
+        assert!(prefix > word);
+        assert!(word > substring);
+        assert!(substring > camel);
+        assert!(camel > scattered);
+        assert_eq!(fuzzy_score("xyz", "getUserName"), None);
+    }
+
+    #[test]
+    fn test_path_filter() {
+        let root = Path::new("/work");
+        let prefix = PathFilter::new("./src/handlers/").unwrap();
+        assert!(prefix.matches(root, Path::new("/work/src/handlers/search.rs")));
+        assert!(!prefix.matches(root, Path::new("/work/src/handlers_old/search.rs")));
+
+        let glob = PathFilter::new("**/*.ts").unwrap();
+        assert!(glob.matches(root, Path::new("/work/packages/ui/index.ts")));
+        assert!(!glob.matches(root, Path::new("/work/src/main.rs")));
+
+        assert!(PathFilter::new("src/[").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_is_incremental() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn load_config() {}\n").unwrap();
+        std::fs::write(root.join("src/util.rs"), "pub struct ConfigLoader;\n").unwrap();
+        std::fs::write(root.join("config.bin"), [0u8, 1, 2]).unwrap();
+
+        let plugins = mill_test_support::harness::get_test_registry().all();
+        let cache = AstCache::new();
+        let index = SymbolIndex::new(root);
+
+        let stats = index.refresh(plugins, &cache).await.unwrap();
+        assert_eq!(
+            stats,
+            RefreshStats {
+                parsed: 2,
+                removed: 0
+            }
+        );
+        let stats = index.refresh(plugins, &cache).await.unwrap();
+        assert_eq!(stats, RefreshStats::default());
+
+        let names: Vec<String> = index
+            .search("config", None, root, None)
+            .await
+            .into_iter()
+            .map(|m| m.symbol.name)
+            .collect();
+        assert_eq!(names, vec!["ConfigLoader", "load_config"]);
+
+        let structs = index
+            .search("config", Some(SymbolKind::Struct), root, None)
+            .await;
+        assert_eq!(structs.len(), 1);
+        let in_lib = PathFilter::new("src/lib.rs").unwrap();
+        let in_lib = index.search("config", None, root, Some(&in_lib)).await;
+        assert_eq!(in_lib[0].symbol.name, "load_config");
+
+        std::fs::remove_file(root.join("src/util.rs")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn read_config_file() {}\n").unwrap();
+        let stats = index.refresh(plugins, &cache).await.unwrap();
+        assert_eq!(
+            stats,
+            RefreshStats {
+                parsed: 1,
+                removed: 1
+            }
+        );
+        let names: Vec<String> = index
+            .search("config", None, root, None)
+            .await
+            .into_iter()
+            .map(|m| m.symbol.name)
+            .collect();
+        assert_eq!(names, vec!["read_config_file"]);
+    }
+}
@@ -0,0 +1,783 @@
+//! Project health check for the workspace verify_project action
+//!
+//! Inspects the codebase rather than the server:
+//! - Relative and path-alias imports that do not resolve to a file
+//! - Workspace members declared in manifests but missing on disk, or
+//!   packages on disk that no workspace manifest declares
+//! - Path dependencies pointing at missing packages
+//! - Import cycles between files
+//! - Diagnostics cached from running language servers
+//!
+//! Every finding is reported as a structured [`Diagnostic`].
+
+use super::member_patterns::{normalize_member, MemberPatterns};
+use crate::handlers::lsp_adapter::DirectLspAdapter;
+use crate::handlers::tool_definitions::{Diagnostic, DiagnosticSeverity};
+use crate::handlers::tools::extensions::get_concrete_app_state;
+use mill_foundation::errors::MillResult as ServerResult;
+use mill_plugin_api::{DependencySource, LanguagePlugin};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Directories never searched for source files or packages
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    ".git",
+    "dist",
+    "build",
+    ".typemill",
+    "__pycache__",
+    ".venv",
+    "venv",
+];
+
+/// Result of verifying a project
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectReport {
+    /// Findings, errors first
+    #[serde(skip)]
+    pub diagnostics: Vec<Diagnostic>,
+    pub files_checked: usize,
+    pub manifests_checked: usize,
+    pub unresolved_imports: usize,
+    pub missing_members: usize,
+    pub undeclared_members: usize,
+    pub broken_path_dependencies: usize,
+    pub import_cycles: usize,
+    pub lsp_errors: usize,
+    pub lsp_warnings: usize,
+}
+
+impl ProjectReport {
+    /// Number of error findings
+    pub fn errors(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| matches!(d.severity, DiagnosticSeverity::Error))
+            .count()
+    }
+
+    /// Number of warning findings
+    pub fn warnings(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| matches!(d.severity, DiagnosticSeverity::Warning))
+            .count()
+    }
+
+    fn push(
+        &mut self,
+        severity: DiagnosticSeverity,
+        message: String,
+        file_path: Option<String>,
+        line: Option<u32>,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            message,
+            file_path,
+            line,
+        });
+    }
+}
+
+/// Verify the project at the context's project root
+pub async fn verify_project(
+    context: &mill_handler_api::ToolHandlerContext,
+) -> ServerResult<ProjectReport> {
+    let concrete_state = get_concrete_app_state(&context.app_state)?;
+    let plugins = concrete_state.language_plugins.all_plugins();
+    let root = context.app_state.project_root.clone();
+
+    let walk_root = root.clone();
+    let files = tokio::task::spawn_blocking(move || walk_project(&walk_root))
+        .await
+        .map_err(|e| {
+            mill_foundation::errors::MillError::internal(format!("Task join error: {}", e))
+        })?;
+
+    let mut report = ProjectReport::default();
+    let mut sources = Vec::new();
+    let mut manifests: Vec<(PathBuf, &Arc<dyn LanguagePlugin>)> = Vec::new();
+    for file in &files {
+        let file_name = file
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let extension = file
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        for plugin in plugins {
+            if plugin.handles_manifest(file_name) {
+                manifests.push((file.clone(), plugin));
+            }
+            if plugin.handles_extension(extension) {
+                sources.push((file.clone(), plugin));
+            }
+        }
+    }
+
+    // Imports may name files of other languages (`.ts` modules from `.svelte` files)
+    let all_extensions: Vec<&str> = plugins
+        .iter()
+        .flat_map(|plugin| plugin.metadata().extensions.iter().copied())
+        .collect();
+    let graph = check_imports(context, &root, &sources, &all_extensions, &mut report).await;
+    check_cycles(&root, &graph, &mut report);
+    check_workspace_members(&root, plugins, &manifests, &mut report).await;
+    check_path_dependencies(&root, &manifests, &mut report).await;
+    roll_up_lsp_diagnostics(context, &root, &mut report).await;
+
+    report.files_checked = sources.len();
+    report.manifests_checked = manifests.len();
+    report
+        .diagnostics
+        .sort_by_key(|d| severity_rank(&d.severity));
+    Ok(report)
+}
+
+fn severity_rank(severity: &DiagnosticSeverity) -> u8 {
+    match severity {
+        DiagnosticSeverity::Error => 0,
+        DiagnosticSeverity::Warning => 1,
+        DiagnosticSeverity::Info => 2,
+        DiagnosticSeverity::Hint => 3,
+    }
+}
+
+/// All files of the project, honouring `.gitignore` and skipping build output
+pub(crate) fn walk_project(root: &Path) -> Vec<PathBuf> {
+    ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .git_ignore(true)
+        .filter_entry(|entry| {
+            !entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| SKIPPED_DIRS.contains(&name))
+        })
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+// ============================================================================
+// Imports
+// ============================================================================
+
+/// Check that file imports resolve, returning the file import graph
+async fn check_imports(
+    context: &mill_handler_api::ToolHandlerContext,
+    root: &Path,
+    sources: &[(PathBuf, &Arc<dyn LanguagePlugin>)],
+    all_extensions: &[&str],
+    report: &mut ProjectReport,
+) -> BTreeMap<PathBuf, BTreeSet<PathBuf>> {
+    let mut graph: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+    for (file, plugin) in sources {
+        let Some(parser) = plugin.import_parser() else {
+            continue;
+        };
+        let content = match context.app_state.file_service.read_file(file).await {
+            Ok(content) => content,
+            Err(e) => {
+                debug!(file = %file.display(), error = %e, "Skipping unreadable file");
+                continue;
+            }
+        };
+
+        let own_extensions = plugin.metadata().extensions;
+        let extensions: Vec<&str> = own_extensions
+            .iter()
+            .chain(
+                all_extensions
+                    .iter()
+                    .filter(|ext| !own_extensions.contains(ext)),
+            )
+            .copied()
+            .collect();
+        for specifier in parser.parse_imports(&content) {
+            let target = if is_generated_module(&specifier) {
+                None
+            } else if is_relative_specifier(&specifier) {
+                file.parent().map(|dir| dir.join(&specifier))
+            } else if let Some(resolved) = plugin
+                .path_alias_resolver()
+                .filter(|resolver| resolver.is_potential_alias(&specifier))
+                .and_then(|resolver| resolver.resolve_alias(&specifier, file, root))
+            {
+                let resolved_path = Path::new(&resolved);
+                Some(if resolved_path.is_absolute() {
+                    resolved_path.to_path_buf()
+                } else if is_relative_specifier(&resolved) {
+                    file.parent().unwrap_or(root).join(resolved_path)
+                } else {
+                    root.join(resolved_path)
+                })
+            } else if specifier.starts_with('.') {
+                // Python relative module (`.sibling`, `..pkg.module`)
+                python_relative_module(file, &specifier)
+            } else {
+                // Package or module path; resolved by the language toolchain
+                None
+            };
+            let Some(target) = target else {
+                continue;
+            };
+
+            match resolve_file(&normalize(&target), &extensions) {
+                Some(resolved) => {
+                    graph.entry(file.clone()).or_default().insert(resolved);
+                }
+                None => {
+                    report.unresolved_imports += 1;
+                    let line = content
+                        .lines()
+                        .position(|line| line.contains(specifier.as_str()))
+                        .map(|index| index as u32 + 1);
+                    report.push(
+                        DiagnosticSeverity::Error,
+                        format!("Unresolved import '{}'", specifier),
+                        Some(relative(root, file)),
+                        line,
+                    );
+                }
+            }
+        }
+    }
+    graph
+}
+
+fn is_relative_specifier(specifier: &str) -> bool {
+    specifier.starts_with("./") || specifier.starts_with("../")
+}
+
+/// SvelteKit's generated route types (`./$types`), which live outside the source tree
+fn is_generated_module(specifier: &str) -> bool {
+    specifier.rsplit('/').next() == Some("$types")
+}
+
+/// Path of a Python relative module, without extension
+fn python_relative_module(file: &Path, specifier: &str) -> Option<PathBuf> {
+    let module = specifier.trim_start_matches('.');
+    let levels = specifier.len() - module.len();
+    let mut dir = file.parent()?.to_path_buf();
+    for _ in 1..levels {
+        dir = dir.parent()?.to_path_buf();
+    }
+    Some(if module.is_empty() {
+        dir
+    } else {
+        dir.join(module.replace('.', "/"))
+    })
+}
+
+/// Resolve `..` and `.` components without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// The file an import target refers to, trying the language's extensions and index files
+///
+/// A directory without an index file still resolves (to itself) since
+/// packages and namespace modules can be imported by directory.
+fn resolve_file(target: &Path, extensions: &[&str]) -> Option<PathBuf> {
+    if target.is_file() {
+        return Some(target.to_path_buf());
+    }
+    let with_extension = |base: &Path, ext: &str| {
+        let mut path = base.as_os_str().to_owned();
+        path.push(".");
+        path.push(ext);
+        PathBuf::from(path)
+    };
+    if let Some(found) = extensions
+        .iter()
+        .map(|ext| with_extension(target, ext))
+        .find(|candidate| candidate.is_file())
+    {
+        return Some(found);
+    }
+    // ES module imports name the emitted `.js` file of a `.ts` source
+    if let Some(stem) = target
+        .extension()
+        .and_then(|e| e.to_str())
+        .filter(|e| matches!(*e, "js" | "jsx" | "mjs" | "cjs"))
+        .map(|_| target.with_extension(""))
+    {
+        if let Some(found) = extensions
+            .iter()
+            .map(|ext| with_extension(&stem, ext))
+            .find(|candidate| candidate.is_file())
+        {
+            return Some(found);
+        }
+    }
+    if target.is_dir() {
+        let index = ["index", "__init__"].iter().find_map(|name| {
+            extensions
+                .iter()
+                .map(|ext| with_extension(&target.join(name), ext))
+                .find(|candidate| candidate.is_file())
+        });
+        return Some(index.unwrap_or_else(|| target.to_path_buf()));
+    }
+    None
+}
+
+// ============================================================================
+// Import cycles
+// ============================================================================
+
+/// Report each group of files that import each other in a cycle
+fn check_cycles(
+    root: &Path,
+    graph: &BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    report: &mut ProjectReport,
+) {
+    for cycle in find_cycles(graph) {
+        report.import_cycles += 1;
+        let mut chain: Vec<String> = cycle.iter().map(|file| relative(root, file)).collect();
+        chain.push(chain[0].clone());
+        report.push(
+            DiagnosticSeverity::Warning,
+            format!("Import cycle: {}", chain.join(" -> ")),
+            Some(relative(root, &cycle[0])),
+            None,
+        );
+    }
+}
+
+/// One cycle through each strongly connected component of the graph
+///
+/// Uses Tarjan's algorithm; each returned cycle starts at the component's
+/// smallest file.
+fn find_cycles(graph: &BTreeMap<PathBuf, BTreeSet<PathBuf>>) -> Vec<Vec<PathBuf>> {
+    struct Tarjan<'a> {
+        graph: &'a BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+        index: HashMap<&'a Path, usize>,
+        low: HashMap<&'a Path, usize>,
+        stack: Vec<&'a Path>,
+        on_stack: BTreeSet<&'a Path>,
+        components: Vec<Vec<&'a Path>>,
+    }
+
+    impl<'a> Tarjan<'a> {
+        fn visit(&mut self, node: &'a Path) {
+            let index = self.index.len();
+            self.index.insert(node, index);
+            self.low.insert(node, index);
+            self.stack.push(node);
+            self.on_stack.insert(node);
+
+            for next in self.graph.get(node).into_iter().flatten() {
+                let next = next.as_path();
+                if !self.index.contains_key(next) {
+                    self.visit(next);
+                    let low = self.low[node].min(self.low[next]);
+                    self.low.insert(node, low);
+                } else if self.on_stack.contains(next) {
+                    let low = self.low[node].min(self.index[next]);
+                    self.low.insert(node, low);
+                }
+            }
+
+            if self.low[node] == self.index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack.remove(member);
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        graph,
+        index: HashMap::new(),
+        low: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: BTreeSet::new(),
+        components: Vec::new(),
+    };
+    for node in graph.keys() {
+        if !tarjan.index.contains_key(node.as_path()) {
+            tarjan.visit(node);
+        }
+    }
+
+    let mut cycles: Vec<Vec<PathBuf>> = tarjan
+        .components
+        .into_iter()
+        .filter_map(|component| {
+            let members: BTreeSet<&Path> = component.into_iter().collect();
+            let start = *members.first()?;
+            let is_self_import = graph.get(start).is_some_and(|next| next.contains(start));
+            if members.len() == 1 && !is_self_import {
+                return None;
+            }
+            cycle_through(graph, start, &members)
+        })
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+/// A shortest path from `start` back to itself within `members`
+fn cycle_through(
+    graph: &BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    start: &Path,
+    members: &BTreeSet<&Path>,
+) -> Option<Vec<PathBuf>> {
+    let mut previous: HashMap<&Path, &Path> = HashMap::new();
+    let mut queue = std::collections::VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for next in graph.get(node).into_iter().flatten() {
+            let next = next.as_path();
+            if next == start {
+                let mut cycle = vec![node.to_path_buf()];
+                let mut current = node;
+                while current != start {
+                    current = previous[current];
+                    cycle.push(current.to_path_buf());
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if members.contains(next) && !previous.contains_key(next) {
+                previous.insert(next, node);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+// ============================================================================
+// Workspace members and path dependencies
+// ============================================================================
+
+/// Compare the members declared in root workspace manifests with the packages on disk
+async fn check_workspace_members(
+    root: &Path,
+    plugins: &[Arc<dyn LanguagePlugin>],
+    manifests: &[(PathBuf, &Arc<dyn LanguagePlugin>)],
+    report: &mut ProjectReport,
+) {
+    for plugin in plugins {
+        let Some(workspace_support) = plugin.workspace_support() else {
+            continue;
+        };
+        let manifest_name = plugin.metadata().manifest_filename;
+        let package_manifest = root.join(manifest_name);
+
+        // The first workspace manifest found declares the members
+        let mut workspace = None;
+        for file_name in workspace_support
+            .additional_workspace_manifests()
+            .iter()
+            .chain(std::iter::once(&manifest_name))
+        {
+            let path = root.join(file_name);
+            if let Ok(content) = tokio::fs::read_to_string(&path).await {
+                if workspace_support.is_workspace_manifest(&content) {
+                    workspace = Some((path, content));
+                    break;
+                }
+            }
+        }
+        let Some((workspace_manifest, content)) = workspace else {
+            continue;
+        };
+        let patterns = MemberPatterns::new(&workspace_support.list_workspace_members(&content));
+        let manifest_path = relative(root, &workspace_manifest);
+
+        // Package directories of this language, relative to the root
+        let mut packages: Vec<String> = Vec::new();
+        let mut nested_workspaces: Vec<String> = Vec::new();
+        for (path, owner) in manifests {
+            if !Arc::ptr_eq(owner, plugin) || path == &package_manifest {
+                continue;
+            }
+            let Some(dir) = path.parent() else {
+                continue;
+            };
+            let is_nested_workspace = tokio::fs::read_to_string(path)
+                .await
+                .is_ok_and(|content| workspace_support.is_workspace_manifest(&content));
+            if is_nested_workspace {
+                nested_workspaces.push(relative(root, dir));
+            } else {
+                packages.push(relative(root, dir));
+            }
+        }
+        // Packages of nested workspaces belong to those workspaces
+        packages.retain(|dir| {
+            !nested_workspaces
+                .iter()
+                .any(|nested| Path::new(dir).starts_with(nested))
+        });
+
+        for (member, error) in &patterns.invalid {
+            report.missing_members += 1;
+            report.push(
+                DiagnosticSeverity::Error,
+                format!("Invalid workspace member pattern '{}': {}", member, error),
+                Some(manifest_path.clone()),
+                None,
+            );
+        }
+
+        for pattern in patterns.includes() {
+            let exists = if pattern.is_glob {
+                packages.iter().any(|dir| pattern.is_match(dir))
+            } else {
+                root.join(normalize_member(&pattern.pattern))
+                    .join(manifest_name)
+                    .is_file()
+            };
+            if !exists {
+                report.missing_members += 1;
+                let message = if pattern.is_glob {
+                    format!(
+                        "Workspace member pattern '{}' matches no {} package",
+                        pattern.pattern, manifest_name
+                    )
+                } else {
+                    format!(
+                        "Workspace member '{}' does not exist (no {} found)",
+                        pattern.pattern, manifest_name
+                    )
+                };
+                report.push(
+                    DiagnosticSeverity::Error,
+                    message,
+                    Some(manifest_path.clone()),
+                    None,
+                );
+            }
+        }
+
+        for dir in &packages {
+            if patterns.declaring(dir).is_none() {
+                report.undeclared_members += 1;
+                report.push(
+                    DiagnosticSeverity::Warning,
+                    format!(
+                        "Package '{}' is not a member of the workspace declared in {}",
+                        dir, manifest_path
+                    ),
+                    Some(format!("{}/{}", dir, manifest_name)),
+                    None,
+                );
+            }
+        }
+    }
+}
+
+/// Check that local path dependencies point at existing packages
+async fn check_path_dependencies(
+    root: &Path,
+    manifests: &[(PathBuf, &Arc<dyn LanguagePlugin>)],
+    report: &mut ProjectReport,
+) {
+    for (manifest, plugin) in manifests {
+        let data = match plugin.analyze_manifest(manifest).await {
+            Ok(data) => data,
+            Err(e) => {
+                warn!(manifest = %manifest.display(), error = %e, "Failed to analyze manifest");
+                continue;
+            }
+        };
+        let manifest_dir = manifest.parent().unwrap_or(root);
+        for dependency in data.dependencies.iter().chain(&data.dev_dependencies) {
+            let DependencySource::Path(path) = &dependency.source else {
+                continue;
+            };
+            if path.starts_with("http://") || path.starts_with("https://") {
+                continue;
+            }
+            let target = manifest_dir.join(path.strip_prefix("file:").unwrap_or(path));
+            let exists = if target.is_dir() {
+                target.join(plugin.metadata().manifest_filename).is_file()
+            } else {
+                // Archives and single-file packages
+                target.is_file()
+            };
+            if !exists {
+                report.broken_path_dependencies += 1;
+                report.push(
+                    DiagnosticSeverity::Error,
+                    format!(
+                        "Path dependency '{}' points at '{}', which is not a package",
+                        dependency.name, path
+                    ),
+                    Some(relative(root, manifest)),
+                    None,
+                );
+            }
+        }
+    }
+}
+
+// ============================================================================
+// LSP diagnostics
+// ============================================================================
+
+/// Summarize diagnostics the running language servers have published, per file
+///
+/// Does not start servers: only files that were opened during the session
+/// have diagnostics.
+async fn roll_up_lsp_diagnostics(
+    context: &mill_handler_api::ToolHandlerContext,
+    root: &Path,
+    report: &mut ProjectReport,
+) {
+    let adapter = context.lsp_adapter.lock().await.clone();
+    let Some(adapter) = adapter else {
+        return;
+    };
+    let Some(direct_adapter) = adapter.as_any().downcast_ref::<DirectLspAdapter>() else {
+        return;
+    };
+
+    let mut by_file: Vec<_> = direct_adapter
+        .all_cached_diagnostics()
+        .await
+        .into_iter()
+        .collect();
+    by_file.sort_by(|a, b| a.0.cmp(&b.0));
+    for (uri, diagnostics) in by_file {
+        let is_error = |d: &&lsp_types::Diagnostic| {
+            d.severity
+                .is_none_or(|s| s == lsp_types::DiagnosticSeverity::ERROR)
+        };
+        let errors = diagnostics.iter().filter(is_error).count();
+        let warnings = diagnostics
+            .iter()
+            .filter(|d| d.severity == Some(lsp_types::DiagnosticSeverity::WARNING))
+            .count();
+        if errors + warnings == 0 {
+            continue;
+        }
+        report.lsp_errors += errors;
+        report.lsp_warnings += warnings;
+
+        let first = diagnostics
+            .iter()
+            .find(is_error)
+            .or_else(|| diagnostics.first());
+        let file_path = url::Url::parse(&uri)
+            .ok()
+            .and_then(|url| url.to_file_path().ok())
+            .map(|path| relative(root, &path))
+            .unwrap_or(uri);
+        report.push(
+            if errors > 0 {
+                DiagnosticSeverity::Error
+            } else {
+                DiagnosticSeverity::Warning
+            },
+            format!(
+                "Language server reports {} error(s) and {} warning(s){}",
+                errors,
+                warnings,
+                first
+                    .map(|d| format!(", first: {}", d.message))
+                    .unwrap_or_default()
+            ),
+            Some(file_path),
+            first.map(|d| d.range.start.line + 1),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &str)]) -> BTreeMap<PathBuf, BTreeSet<PathBuf>> {
+        let mut graph: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+        for (from, to) in edges {
+            graph
+                .entry(PathBuf::from(from))
+                .or_default()
+                .insert(PathBuf::from(to));
+        }
+        graph
+    }
+
+    #[test]
+    fn test_find_cycles() {
+        let graph = graph(&[
+            ("a", "b"),
+            ("b", "c"),
+            ("c", "a"),
+            ("c", "d"),
+            ("d", "e"),
+            ("f", "f"),
+        ]);
+        let cycles = find_cycles(&graph);
+        assert_eq!(
+            cycles,
+            vec![
+                vec![PathBuf::from("a"), PathBuf::from("b"), PathBuf::from("c")],
+                vec![PathBuf::from("f")],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_python_relative_module() {
+        let file = Path::new("/p/pkg/sub/mod.py");
+        assert_eq!(
+            python_relative_module(file, ".sibling"),
+            Some(PathBuf::from("/p/pkg/sub/sibling"))
+        );
+        assert_eq!(
+            python_relative_module(file, "..other.mod"),
+            Some(PathBuf::from("/p/pkg/other/mod"))
+        );
+        assert_eq!(
+            python_relative_module(file, "."),
+            Some(PathBuf::from("/p/pkg/sub"))
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(Path::new("/p/src/./a/../b/c")),
+            PathBuf::from("/p/src/b/c")
+        );
+    }
+}
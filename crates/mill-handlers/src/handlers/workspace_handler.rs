@@ -7,12 +7,15 @@
 //! - create_package -> WorkspaceCreateService logic
 //! - extract_dependencies -> WorkspaceExtractService logic
 //! - find_replace -> find_replace service
-//! - verify_project -> health_check style verification
+//! - update_members -> workspace member lists via language plugin workspace support
+//! - verify_project -> project health check (imports, members, dependencies, cycles)
+//! - list_operations / undo / redo -> operation journal of applied edit plans
 
 use super::tools::{extensions::get_concrete_app_state, ToolHandler};
 use async_trait::async_trait;
 use mill_foundation::core::model::mcp::ToolCall;
 use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
+use mill_foundation::protocol::{EditLocation, EditPlan, EditPlanMetadata, EditType, TextEdit};
 use serde_json::{json, Value};
 use tracing::{debug, info};
 
@@ -67,8 +70,10 @@ impl ToolHandler for WorkspaceHandler {
             "find_replace" => self.handle_find_replace(context, args).await,
             "verify_project" => self.handle_verify_project(context).await,
             "update_members" => self.handle_update_members(context, args).await,
+            "list_operations" => self.handle_list_operations(context).await,
+            "undo" | "redo" => self.handle_undo_redo(context, action, args).await,
             _ => Err(ServerError::invalid_request(format!(
-                "Unknown workspace action: {}. Valid actions: create_package, extract_dependencies, find_replace, verify_project, update_members",
+                "Unknown workspace action: {}. Valid actions: create_package, extract_dependencies, find_replace, verify_project, update_members, list_operations, undo, redo",
                 action
             ))),
         }
@@ -234,14 +239,17 @@ impl WorkspaceHandler {
         self.convert_find_replace_response(result, &options).await
     }
 
-    /// Handle verify_project action - similar to health_check
+    /// Handle verify_project action - check imports, workspace members, path
+    /// dependencies, import cycles and language server diagnostics
     async fn handle_verify_project(
         &self,
         context: &mill_handler_api::ToolHandlerContext,
     ) -> ServerResult<Value> {
         info!("Handling workspace verify_project action");
 
-        let _concrete_state = get_concrete_app_state(&context.app_state)?;
+        let report = crate::handlers::workspace::verify_project(context).await?;
+        let errors = report.errors();
+        let warnings = report.warnings();
 
         // Get plugin count from plugin manager
         let plugin_count = context
@@ -249,51 +257,42 @@ impl WorkspaceHandler {
             .get_all_tool_definitions()
             .await
             .len();
-
-        // Get detailed metrics and statistics
-        let metrics = context.plugin_manager.get_metrics().await;
         let stats = context.plugin_manager.get_registry_statistics().await;
 
-        // Calculate success rate
-        let success_rate = if metrics.total_requests > 0 {
-            (metrics.successful_requests as f64 / metrics.total_requests as f64) * 100.0
-        } else {
-            100.0
-        };
-
         // Determine overall status
-        let status = if success_rate >= 95.0 {
-            WriteStatus::Success
-        } else if success_rate >= 75.0 {
+        let status = if errors > 0 {
+            WriteStatus::Error
+        } else if warnings > 0 {
             WriteStatus::Preview // Using Preview as "warning"
         } else {
-            WriteStatus::Error
+            WriteStatus::Success
         };
 
-        let summary = format!(
-            "Project verified: {} plugins loaded, {:.1}% success rate",
-            plugin_count, success_rate
-        );
+        let summary = if errors + warnings == 0 {
+            format!(
+                "Project verified: {} files and {} manifests checked, no problems found",
+                report.files_checked, report.manifests_checked
+            )
+        } else {
+            format!(
+                "Project verified: {} files and {} manifests checked, {} errors, {} warnings",
+                report.files_checked, report.manifests_checked, errors, warnings
+            )
+        };
 
         let response = WriteResponse {
             status,
             summary,
             files_changed: vec![],
-            diagnostics: vec![],
+            diagnostics: report.diagnostics.clone(),
             changes: Some(json!({
+                "checks": report,
                 "plugins": {
                     "loaded": plugin_count,
                     "total_plugins": stats.total_plugins,
                     "supported_extensions": stats.supported_extensions,
                     "supported_methods": stats.supported_methods,
                 },
-                "metrics": {
-                    "total_requests": metrics.total_requests,
-                    "successful_requests": metrics.successful_requests,
-                    "failed_requests": metrics.failed_requests,
-                    "success_rate": format!("{:.2}%", success_rate),
-                    "average_processing_time_ms": metrics.average_processing_time_ms,
-                }
             })),
         };
 
@@ -563,13 +562,17 @@ impl WorkspaceHandler {
     }
 
     /// Handle update_members action - add/remove/list workspace members
+    ///
+    /// Manifest edits go through the workspace support of the language plugin
+    /// owning the manifest (Cargo.toml, package.json, pnpm-workspace.yaml,
+    /// pyproject.toml), which preserves the manifest's formatting.
     async fn handle_update_members(
         &self,
         context: &mill_handler_api::ToolHandlerContext,
         args: &Value,
     ) -> ServerResult<Value> {
+        use crate::handlers::workspace::{resolve_members, MemberPatterns};
         use std::path::Path;
-        use toml_edit::{DocumentMut, Item};
 
         debug!("Handling workspace update_members action");
 
@@ -602,6 +605,13 @@ impl WorkspaceHandler {
                 ServerError::invalid_request("Missing 'action' in params (add/remove/list)")
             })?;
 
+        if !matches!(sub_action, "add" | "remove" | "list") {
+            return Err(ServerError::invalid_request(format!(
+                "Invalid update_members action: {}. Valid: add, remove, list",
+                sub_action
+            )));
+        }
+
         // Get workspace manifest path
         let manifest_path = params
             .get("workspaceManifest")
@@ -618,156 +628,153 @@ impl WorkspaceHandler {
             })
             .ok_or_else(|| ServerError::invalid_request("Missing 'workspaceManifest' path"))?;
 
-        // Extract members argument early to move into closure
         let members_arg: Vec<String> = params
             .get("members")
             .and_then(|v| v.as_array())
             .map(|arr| {
                 arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .filter_map(|v| v.as_str().map(|s| s.replace('\\', "/")))
                     .collect()
             })
             .unwrap_or_default();
 
-        let sub_action = sub_action.to_string();
-        let sub_action_clone = sub_action.clone();
+        let manifest_file = Path::new(&manifest_path);
+        let manifest_name = manifest_file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&manifest_path)
+            .to_string();
+
+        let plugin = context
+            .app_state
+            .language_plugins
+            .get_plugin_for_workspace_manifest(manifest_file)
+            .ok_or_else(|| {
+                ServerError::invalid_request(format!(
+                    "No language plugin supports workspace manifest '{}'",
+                    manifest_name
+                ))
+            })?;
+        let workspace_support = plugin.workspace_support().ok_or_else(|| {
+            ServerError::invalid_request(format!(
+                "Plugin '{}' has no workspace support",
+                plugin.metadata().name
+            ))
+        })?;
 
-        // Read Cargo.toml asynchronously
-        let cargo_content = tokio::fs::read_to_string(&manifest_path).await.map_err(|e| {
+        let original_content = tokio::fs::read_to_string(&manifest_path).await.map_err(|e| {
             ServerError::invalid_request(format!(
                 "Failed to read workspace manifest '{}': {}",
                 manifest_path, e
             ))
         })?;
 
-        let (members_before, members_after, changes_made, workspace_updated, new_content) =
-            tokio::task::spawn_blocking(move || {
-                let mut doc = cargo_content.parse::<DocumentMut>().map_err(|e| {
-                    ServerError::invalid_request(format!(
-                        "Failed to parse workspace manifest: {}",
-                        e
-                    ))
-                })?;
-
-                // Check if workspace section exists
-                let has_workspace_section = doc.get("workspace").is_some();
-
-                if !has_workspace_section && !create_if_missing {
-                    return Err(ServerError::invalid_request(
-                        "Cargo.toml does not contain a [workspace] section. Use createIfMissing: true to create it."
-                    ));
-                }
-
-                // Create workspace section if needed and allowed
-                if !has_workspace_section && create_if_missing {
-                    let mut workspace_table = toml_edit::Table::new();
-                    workspace_table.insert(
-                        "members",
-                        Item::Value(toml_edit::Value::Array(toml_edit::Array::new())),
-                    );
-                    doc.insert("workspace", Item::Table(workspace_table));
-                }
-
-                // Get current members
-                let members_before: Vec<String> = doc
-                    .get("workspace")
-                    .and_then(|w| w.get("members"))
-                    .and_then(|m| m.as_array())
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                            .collect()
-                    })
-                    .unwrap_or_default();
-
-                match sub_action_clone.as_str() {
-                    "add" => {
-                        let new_members: Vec<String> = members_arg
-                            .iter()
-                            .map(|s| s.replace('\\', "/"))
-                            .collect();
-
-                        let mut members_after = members_before.clone();
-                        // Use HashSet for O(1) lookup
-                        let mut members_set: std::collections::HashSet<_> = members_before.iter().collect();
-
-                        let mut added = 0;
-                        for member in &new_members {
-                            if !members_set.contains(member) {
-                                members_after.push(member.clone());
-                                members_set.insert(member);
-                                added += 1;
-                            }
-                        }
-
-                        let content = if added > 0 && !dry_run {
-                            // Update the document
-                            let members_array = members_after
-                                .iter()
-                                .map(|s| toml_edit::Value::from(s.as_str()))
-                                .collect::<toml_edit::Array>();
+        if !workspace_support.is_workspace_manifest(&original_content) && !create_if_missing {
+            return Err(ServerError::invalid_request(format!(
+                "{} does not declare a workspace. Use createIfMissing: true to create it.",
+                manifest_name
+            )));
+        }
 
-                            if let Some(workspace) = doc.get_mut("workspace") {
-                                workspace["members"] =
-                                    Item::Value(toml_edit::Value::Array(members_array));
-                            }
-                            Some(doc.to_string())
-                        } else {
-                            None
-                        };
+        let members_before = workspace_support.list_workspace_members(&original_content);
+        let patterns = MemberPatterns::new(&members_before);
+        let mut diagnostics = Vec::new();
+        let mut content = original_content.clone();
+        let mut changes_made = 0;
 
-                        Ok((members_before, members_after, added, added > 0, content))
+        match sub_action {
+            "add" => {
+                for member in &members_arg {
+                    if workspace_support
+                        .list_workspace_members(&content)
+                        .contains(member)
+                    {
+                        continue;
                     }
-                    "remove" => {
-                        // Use HashSet for O(1) lookup
-                        let remove_members: std::collections::HashSet<_> = members_arg.iter().collect();
-
-                        let members_after: Vec<String> = members_before
-                            .iter()
-                            .filter(|m| !remove_members.contains(m))
-                            .cloned()
-                            .collect();
-
-                        let removed = members_before.len() - members_after.len();
-
-                        let content = if removed > 0 && !dry_run {
-                            // Update the document
-                            let members_array = members_after
-                                .iter()
-                                .map(|s| toml_edit::Value::from(s.as_str()))
-                                .collect::<toml_edit::Array>();
-
-                            if let Some(workspace) = doc.get_mut("workspace") {
-                                workspace["members"] =
-                                    Item::Value(toml_edit::Value::Array(members_array));
-                            }
-                            Some(doc.to_string())
-                        } else {
-                            None
-                        };
-
-                        Ok((members_before, members_after, removed, removed > 0, content))
+                    if let Some(pattern) = patterns.declaring(member).filter(|p| p.is_glob) {
+                        diagnostics.push(Diagnostic {
+                            severity: DiagnosticSeverity::Info,
+                            message: format!(
+                                "'{}' is already a member through pattern '{}'",
+                                member, pattern.pattern
+                            ),
+                            file_path: Some(manifest_path.clone()),
+                            line: None,
+                        });
+                        continue;
+                    }
+                    let updated = workspace_support.add_workspace_member(&content, member);
+                    if updated == content {
+                        diagnostics.push(Diagnostic {
+                            severity: DiagnosticSeverity::Warning,
+                            message: format!("Could not add '{}' to {}", member, manifest_name),
+                            file_path: Some(manifest_path.clone()),
+                            line: None,
+                        });
+                        continue;
                     }
-                    "list" => Ok((members_before.clone(), members_before, 0, false, None)),
-                    _ => Err(ServerError::invalid_request(format!(
-                        "Invalid update_members action: {}. Valid: add, remove, list",
-                        sub_action_clone
-                    ))),
+                    content = updated;
+                    changes_made += 1;
                 }
-            })
-            .await
-            .map_err(|e| ServerError::internal(format!("Task join error: {}", e)))??;
+            }
+            "remove" => {
+                for member in &members_arg {
+                    if workspace_support
+                        .list_workspace_members(&content)
+                        .contains(member)
+                    {
+                        let updated = workspace_support.remove_workspace_member(&content, member);
+                        if updated != content {
+                            content = updated;
+                            changes_made += 1;
+                        }
+                    } else if let Some(pattern) = patterns.declaring(member) {
+                        diagnostics.push(Diagnostic {
+                            severity: DiagnosticSeverity::Warning,
+                            message: format!(
+                                "'{}' is a member through pattern '{}' and was not removed; narrow or exclude the pattern instead",
+                                member, pattern.pattern
+                            ),
+                            file_path: Some(manifest_path.clone()),
+                            line: None,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
 
-        if let Some(content) = new_content {
-            tokio::fs::write(&manifest_path, content).await.map_err(|e| {
-                ServerError::invalid_request(format!(
-                    "Failed to write workspace manifest: {}",
-                    e
-                ))
-            })?;
+        let members_after = workspace_support.list_workspace_members(&content);
+        let workspace_updated = content != original_content;
+
+        if workspace_updated && !dry_run {
+            // Written as an edit plan so the write is scope-checked, not
+            // cancelled once started, and journaled for undo
+            let plan = manifest_plan(&manifest_path, &original_content, &content, sub_action);
+            context
+                .app_state
+                .file_service
+                .apply_edit_plan(&plan)
+                .await?;
         }
 
-        let summary = match sub_action.as_str() {
+        let resolved_members = if sub_action == "list" {
+            let workspace_dir = manifest_file
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .to_path_buf();
+            let package_manifest = plugin.metadata().manifest_filename;
+            let resolved = tokio::task::spawn_blocking(move || {
+                resolve_members(&workspace_dir, &patterns, package_manifest)
+            })
+            .await
+            .map_err(|e| ServerError::internal(format!("Task join error: {}", e)))?;
+            Some(resolved)
+        } else {
+            None
+        };
+
+        let summary = match sub_action {
             "add" => {
                 if dry_run {
                     format!("Preview: Would add {} members", changes_made)
@@ -782,15 +789,14 @@ impl WorkspaceHandler {
                     format!("Removed {} members from workspace", changes_made)
                 }
             }
-            "list" => format!("Workspace has {} members", members_before.len()),
-            _ => "Unknown operation".to_string(),
+            _ => format!("Workspace has {} members", members_before.len()),
         };
 
-        let response = json!({
+        let mut response = json!({
             "status": if dry_run { "preview" } else { "success" },
             "summary": summary,
             "filesChanged": if workspace_updated && !dry_run { vec![manifest_path] } else { vec![] as Vec<String> },
-            "diagnostics": [],
+            "diagnostics": diagnostics,
             "result": {
                 "action": sub_action,
                 "membersBefore": members_before,
@@ -800,9 +806,147 @@ impl WorkspaceHandler {
                 "dryRun": dry_run
             }
         });
+        if let Some(resolved) = resolved_members {
+            response["result"]["resolvedMembers"] = json!(resolved);
+        }
 
         Ok(response)
     }
+
+    /// Handle list_operations action - list journaled edit plans, newest first
+    async fn handle_list_operations(
+        &self,
+        context: &mill_handler_api::ToolHandlerContext,
+    ) -> ServerResult<Value> {
+        debug!("Handling workspace list_operations action");
+
+        let concrete_state = get_concrete_app_state(&context.app_state)?;
+        let operations = concrete_state.file_service.list_operations().await?;
+
+        let response = WriteResponse {
+            status: WriteStatus::Success,
+            summary: format!("{} operations in journal", operations.len()),
+            files_changed: vec![],
+            diagnostics: vec![],
+            changes: Some(json!({ "operations": operations })),
+        };
+
+        Ok(serde_json::to_value(response)?)
+    }
+
+    /// Handle undo/redo actions - replay a journaled operation backwards or forwards
+    async fn handle_undo_redo(
+        &self,
+        context: &mill_handler_api::ToolHandlerContext,
+        action: &str,
+        args: &Value,
+    ) -> ServerResult<Value> {
+        debug!(action = %action, "Handling workspace undo/redo action");
+
+        let operation_id = match args.get("params").and_then(|p| p.get("operationId")) {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(value.as_u64().ok_or_else(|| {
+                ServerError::invalid_request("'operationId' must be a positive integer")
+            })?),
+        };
+        let options = args.get("options");
+        let dry_run = options
+            .and_then(|o| o.get("dryRun"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let merge = options
+            .and_then(|o| o.get("merge"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let concrete_state = get_concrete_app_state(&context.app_state)?;
+        let file_service = &concrete_state.file_service;
+        let outcome = if action == "undo" {
+            file_service
+                .undo_operation(operation_id, merge, dry_run)
+                .await?
+        } else {
+            file_service
+                .redo_operation(operation_id, merge, dry_run)
+                .await?
+        };
+
+        let files_changed: Vec<String> = outcome
+            .restored_files
+            .iter()
+            .chain(&outcome.merged_files)
+            .chain(&outcome.removed_files)
+            .cloned()
+            .collect();
+        let summary = format!(
+            "{} operation {} ({}): {} files{}",
+            if dry_run {
+                format!("Preview: would {}", action)
+            } else if action == "undo" {
+                "Undid".to_string()
+            } else {
+                "Redid".to_string()
+            },
+            outcome.entry.id,
+            outcome.entry.intent,
+            files_changed.len(),
+            if outcome.merged_files.is_empty() {
+                String::new()
+            } else {
+                format!(", {} merged with later changes", outcome.merged_files.len())
+            }
+        );
+        let diagnostics = outcome
+            .merged_files
+            .iter()
+            .map(|path| Diagnostic {
+                severity: DiagnosticSeverity::Warning,
+                message: "File changed after the operation; later changes were merged".to_string(),
+                file_path: Some(path.clone()),
+                line: None,
+            })
+            .collect();
+
+        let response = WriteResponse {
+            status: if dry_run {
+                WriteStatus::Preview
+            } else {
+                WriteStatus::Success
+            },
+            summary,
+            files_changed: if dry_run { vec![] } else { files_changed },
+            diagnostics,
+            changes: Some(serde_json::to_value(&outcome)?),
+        };
+
+        Ok(serde_json::to_value(response)?)
+    }
+}
+
+/// Edit plan replacing the whole of a workspace manifest with `content`
+fn manifest_plan(manifest_path: &str, original: &str, content: &str, action: &str) -> EditPlan {
+    EditPlan {
+        source_file: manifest_path.to_string(),
+        edits: vec![TextEdit {
+            file_path: Some(manifest_path.to_string()),
+            edit_type: EditType::Replace,
+            location: EditLocation::whole_file(original),
+            original_text: original.to_string(),
+            new_text: content.to_string(),
+            priority: 0,
+            description: format!("Update workspace members in {}", manifest_path),
+        }],
+        dependency_updates: Vec::new(),
+        validations: Vec::new(),
+        metadata: EditPlanMetadata {
+            intent_name: "update_members".to_string(),
+            intent_arguments: json!({ "action": action, "workspaceManifest": manifest_path }),
+            created_at: chrono::Utc::now(),
+            complexity: 1,
+            impact_areas: vec!["workspace".to_string()],
+            consolidation: None,
+        },
+    }
 }
 
 #[cfg(test)]
@@ -818,7 +962,10 @@ mod tests {
     use mill_plugin_api::{LanguagePlugin, ScanScope};
 
     // Mock implementations
-    struct DummyFileService;
+    #[derive(Default)]
+    struct DummyFileService {
+        applied: std::sync::Mutex<Vec<EditPlan>>,
+    }
     #[async_trait]
     impl FileService for DummyFileService {
         async fn read_file(&self, _: &Path) -> Result<String, MillError> { Ok("".to_string()) }
@@ -830,7 +977,8 @@ mod tests {
         async fn rename_directory_with_imports(&self, _: &Path, _: &Path, _: bool, _: Option<ScanScope>, _: bool) -> Result<DryRunnable<Value>, MillError> { Ok(DryRunnable::new(false, Value::Null)) }
         async fn list_files_with_pattern(&self, _: &Path, _: bool, _: Option<&str>) -> Result<Vec<String>, MillError> { Ok(vec![]) }
         fn to_absolute_path_checked(&self, p: &Path) -> Result<PathBuf, MillError> { Ok(p.to_path_buf()) }
-        async fn apply_edit_plan(&self, _: &EditPlan) -> Result<mill_foundation::protocol::EditPlanResult, MillError> {
+        async fn apply_edit_plan(&self, plan: &EditPlan) -> Result<mill_foundation::protocol::EditPlanResult, MillError> {
+            self.applied.lock().unwrap().push(plan.clone());
             Ok(mill_foundation::protocol::EditPlanResult {
                 success: true,
                 modified_files: vec![],
@@ -851,7 +999,14 @@ mod tests {
     impl LanguagePluginRegistry for DummyPluginRegistry {
         fn get_plugin(&self, _: &str) -> Option<&dyn LanguagePlugin> { None }
         fn supported_extensions(&self) -> Vec<String> { vec![] }
-        fn get_plugin_for_manifest(&self, _: &Path) -> Option<&dyn LanguagePlugin> { None }
+        fn get_plugin_for_manifest(&self, path: &Path) -> Option<&dyn LanguagePlugin> {
+            let name = path.file_name()?.to_str()?;
+            mill_test_support::harness::get_test_registry()
+                .all()
+                .iter()
+                .find(|plugin| plugin.handles_manifest(name))
+                .map(|plugin| plugin.as_ref())
+        }
         fn inner(&self) -> &dyn std::any::Any { self }
     }
 
@@ -884,7 +1039,7 @@ members = [
         let handler = WorkspaceHandler::new();
 
         let app_state = Arc::new(mill_handler_api::AppState {
-            file_service: Arc::new(DummyFileService),
+            file_service: Arc::new(DummyFileService::default()),
             language_plugins: Arc::new(DummyPluginRegistry),
             project_root: temp_dir.path().to_path_buf(),
             extensions: None,
@@ -940,8 +1095,9 @@ members = []
 
         let handler = WorkspaceHandler::new();
 
+        let file_service = Arc::new(DummyFileService::default());
         let app_state = Arc::new(mill_handler_api::AppState {
-            file_service: Arc::new(DummyFileService),
+            file_service: file_service.clone(),
             language_plugins: Arc::new(DummyPluginRegistry),
             project_root: temp_dir.path().to_path_buf(),
             extensions: None,
@@ -979,9 +1135,17 @@ members = []
         // Verify response
         assert_eq!(result["status"], "success");
 
-        // Verify file content
-        let new_content = tokio::fs::read_to_string(&cargo_toml_path).await.unwrap();
-        assert!(new_content.contains("\"new_member\""));
+        // The manifest is written through the file service as one edit plan
+        let applied = file_service.applied.lock().unwrap().clone();
+        assert_eq!(applied.len(), 1);
+        let edit = &applied[0].edits[0];
+        assert_eq!(edit.file_path.as_deref(), cargo_toml_path.to_str());
+        assert_eq!(edit.original_text, cargo_content);
+        assert!(edit.new_text.contains("\"new_member\""));
+        assert_eq!(
+            tokio::fs::read_to_string(&cargo_toml_path).await.unwrap(),
+            cargo_content
+        );
     }
 
 }
@@ -104,6 +104,22 @@ impl LanguagePluginRegistry {
         debug!(filename = filename, "No plugin found for manifest");
         None
     }
+
+    /// Get a plugin whose workspace support edits a specific workspace manifest
+    pub fn get_plugin_for_workspace_manifest(&self, filename: &str) -> Option<&dyn LanguagePlugin> {
+        self.inner
+            .all()
+            .iter()
+            .find(|plugin| {
+                plugin.workspace_support().is_some_and(|workspace_support| {
+                    plugin.handles_manifest(filename)
+                        || workspace_support
+                            .additional_workspace_manifests()
+                            .contains(&filename)
+                })
+            })
+            .map(|plugin| plugin.as_ref())
+    }
 }
 
 // NOTE: No Default impl - this would bypass dependency injection.
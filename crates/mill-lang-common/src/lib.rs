@@ -126,7 +126,9 @@ pub use import_parsing::{extract_package_name, parse_import_alias};
 pub use location::{
     extract_text_at_location, offset_to_position, position_to_offset, LocationBuilder,
 };
-pub use manifest_common::{JsonWorkspace, TomlWorkspace};
+pub use manifest_common::{
+    push_toml_array_value, remove_toml_array_values, JsonWorkspace, TomlWorkspace,
+};
 pub use parsing::{parse_with_fallback, parse_with_optional_fallback, try_parsers};
 pub use refactoring::{
     edit_plan_builder::EditPlanBuilder, extract_constant_builder::ExtractConstantEditPlanBuilder,
@@ -238,6 +238,93 @@ impl TomlWorkspace {
     }
 }
 
+/// Append a value to a TOML array, laid out like the existing values
+///
+/// In a multi-line array the new value goes on its own line with the
+/// indentation of the last value; `Array::push` would put it on the last
+/// value's line.
+pub fn push_toml_array_value(array: &mut Array, new_value: impl Into<toml_edit::Value>) {
+    let mut new_value = new_value.into();
+    let line_prefix = array
+        .iter()
+        .last()
+        .and_then(|last| last.decor().prefix())
+        .and_then(|prefix| prefix.as_str())
+        .and_then(|prefix| prefix.rfind('\n').map(|i| prefix[i..].to_string()));
+
+    match line_prefix {
+        Some(prefix) => {
+            // A comment after the last value's comma stays on that line
+            let trailing = raw_str(Some(array.trailing())).to_string();
+            let (trailing_head, trailing_rest) = split_first_line(&trailing);
+            new_value
+                .decor_mut()
+                .set_prefix(format!("{}{}", trailing_head, prefix));
+            array.set_trailing(trailing_rest.to_string());
+            new_value.decor_mut().set_suffix("");
+            array.push_formatted(new_value);
+        }
+        None => array.push(new_value),
+    }
+}
+
+/// Remove the values matching `predicate` from a TOML array, keeping its layout
+///
+/// Comments stay on their lines: in a multi-line array the comment after a
+/// value's comma is stored in the next value's decor, so it is handed on
+/// rather than dropped with the removed value.
+///
+/// Returns whether anything was removed.
+pub fn remove_toml_array_values(
+    array: &mut Array,
+    predicate: impl Fn(&toml_edit::Value) -> bool,
+) -> bool {
+    let indices: Vec<usize> = array
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| predicate(v))
+        .map(|(index, _)| index)
+        .collect();
+
+    for &index in indices.iter().rev() {
+        let removed = array.remove(index);
+        let removed_prefix = raw_str(removed.decor().prefix()).to_string();
+        let (removed_head, _) = split_first_line(&removed_prefix);
+
+        if let Some(next) = array.get_mut(index) {
+            let next_prefix = raw_str(next.decor().prefix()).to_string();
+            let new_prefix = if removed_prefix.contains('\n') {
+                match next_prefix.find('\n') {
+                    Some(i) => format!("{}{}", removed_head, &next_prefix[i..]),
+                    None => next_prefix,
+                }
+            } else if index == 0 {
+                removed_prefix
+            } else {
+                next_prefix
+            };
+            next.decor_mut().set_prefix(new_prefix);
+        } else if !removed_head.trim().is_empty() {
+            let trailing = raw_str(Some(array.trailing())).to_string();
+            let (_, trailing_rest) = split_first_line(&trailing);
+            array.set_trailing(format!("{}{}", removed_head, trailing_rest));
+        }
+    }
+    !indices.is_empty()
+}
+
+fn raw_str(raw: Option<&toml_edit::RawString>) -> &str {
+    raw.and_then(|raw| raw.as_str()).unwrap_or_default()
+}
+
+/// Split decor text at its first newline: (rest of the current line, remainder)
+fn split_first_line(text: &str) -> (&str, &str) {
+    match text.find('\n') {
+        Some(i) => text.split_at(i),
+        None => (text, ""),
+    }
+}
+
 /// JSON-based workspace utilities (for TypeScript/JavaScript package.json)
 pub struct JsonWorkspace;
 
@@ -327,3 +414,60 @@ impl JsonWorkspace {
             .map_err(|e| MillError::parse(format!("Failed to serialize JSON: {}", e)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members_array(content: &str) -> (DocumentMut, &'static str) {
+        (content.parse::<DocumentMut>().unwrap(), "members")
+    }
+
+    #[test]
+    fn test_push_toml_array_value_multiline() {
+        let (mut doc, key) = members_array("members = [\n    \"a\",\n    \"b\", # last\n]\n");
+        push_toml_array_value(doc[key].as_array_mut().unwrap(), "c");
+        assert_eq!(
+            doc.to_string(),
+            "members = [\n    \"a\",\n    \"b\", # last\n    \"c\",\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_push_toml_array_value_inline() {
+        let (mut doc, key) = members_array("members = [\"a\"]\n");
+        push_toml_array_value(doc[key].as_array_mut().unwrap(), "b");
+        assert_eq!(doc.to_string(), "members = [\"a\", \"b\"]\n");
+    }
+
+    #[test]
+    fn test_remove_toml_array_values() {
+        let remove = |content: &str, member: &str| {
+            let (mut doc, key) = members_array(content);
+            let removed = remove_toml_array_values(doc[key].as_array_mut().unwrap(), |v| {
+                v.as_str() == Some(member)
+            });
+            (removed, doc.to_string())
+        };
+
+        assert_eq!(
+            remove("members = [\"a\", \"b\"]\n", "a"),
+            (true, "members = [\"b\"]\n".to_string())
+        );
+        assert_eq!(
+            remove("members = [\n  \"a\", # first\n  \"b\",\n]\n", "b"),
+            (true, "members = [\n  \"a\", # first\n]\n".to_string())
+        );
+        assert_eq!(
+            remove(
+                "members = [\n  \"a\", # first\n  \"b\", # second\n  \"c\",\n]\n",
+                "b"
+            ),
+            (
+                true,
+                "members = [\n  \"a\", # first\n  \"c\",\n]\n".to_string()
+            )
+        );
+        assert!(!remove("members = [\"a\"]\n", "x").0);
+    }
+}
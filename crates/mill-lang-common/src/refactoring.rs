@@ -5,6 +5,8 @@
 
 pub mod edit_plan_builder;
 pub mod extract_constant_builder;
+pub mod file_edits;
+pub mod reorder;
 
 use mill_foundation::protocol::EditLocation;
 use serde::{Deserialize, Serialize};
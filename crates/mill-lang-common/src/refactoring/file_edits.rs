@@ -0,0 +1,241 @@
+//! Byte-offset edit collection for multi-edit refactorings.
+//!
+//! Symbol moves touch the same file in several places (removing an item,
+//! adding imports, rewriting paths). [`FileEdits`] collects those changes as
+//! byte ranges and converts them into [`TextEdit`]s that never overlap or
+//! touch, merging adjacent ranges so the edit transformer applies them as-is.
+
+use crate::location::offset_to_position;
+use mill_foundation::protocol::{EditLocation, EditType, TextEdit};
+
+/// Pending edits against one file's content
+#[derive(Debug, Clone)]
+pub struct FileEdits<'a> {
+    source: &'a str,
+    file_path: Option<String>,
+    edits: Vec<(usize, usize, String)>,
+}
+
+impl<'a> FileEdits<'a> {
+    /// Collect edits for `source`; `file_path` is `None` for the plan's source file
+    pub fn new(source: &'a str, file_path: Option<String>) -> Self {
+        Self {
+            source,
+            file_path,
+            edits: Vec::new(),
+        }
+    }
+
+    /// Replace the byte range `start..end` with `text`
+    pub fn replace(&mut self, start: usize, end: usize, text: impl Into<String>) {
+        self.edits.push((start, end, text.into()));
+    }
+
+    /// Insert `text` at byte offset `offset`
+    pub fn insert(&mut self, offset: usize, text: impl Into<String>) {
+        self.replace(offset, offset, text);
+    }
+
+    /// Delete the byte range `start..end`
+    pub fn delete(&mut self, start: usize, end: usize) {
+        self.replace(start, end, String::new());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Content after applying all edits
+    pub fn apply(&self) -> String {
+        let mut result = String::with_capacity(self.source.len());
+        let mut last = 0;
+        for (start, end, text) in self.merged() {
+            result.push_str(&self.source[last..start]);
+            result.push_str(&text);
+            last = end;
+        }
+        result.push_str(&self.source[last..]);
+        result
+    }
+
+    /// Convert to text edits, merging ranges that overlap or touch
+    pub fn into_text_edits(self, description: &str) -> Vec<TextEdit> {
+        self.merged()
+            .into_iter()
+            .map(|(start, end, text)| {
+                let (start_line, start_column) = offset_to_position(self.source, start);
+                let (end_line, end_column) = offset_to_position(self.source, end);
+                TextEdit {
+                    file_path: self.file_path.clone(),
+                    edit_type: EditType::Replace,
+                    location: EditLocation {
+                        start_line,
+                        start_column,
+                        end_line,
+                        end_column,
+                    },
+                    original_text: self.source[start..end].to_string(),
+                    new_text: text,
+                    priority: 100,
+                    description: description.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    /// Sorted edits with touching or overlapping ranges combined: the ranges
+    /// are unioned and the replacement texts concatenated in order
+    fn merged(&self) -> Vec<(usize, usize, String)> {
+        let mut edits = self.edits.clone();
+        edits.sort_by_key(|(start, end, _)| (*start, *end));
+
+        let mut merged: Vec<(usize, usize, String)> = Vec::new();
+        for (start, end, text) in edits {
+            match merged.last_mut() {
+                Some((_, last_end, last_text)) if start <= *last_end => {
+                    *last_end = (*last_end).max(end);
+                    last_text.push_str(&text);
+                }
+                _ => merged.push((start, end, text)),
+            }
+        }
+        merged
+    }
+}
+
+/// Extend `start..end` to whole lines when only whitespace surrounds it,
+/// including the trailing newline and one following blank line if the item
+/// was also preceded by a blank line or opened a block
+pub fn full_line_range(source: &str, start: usize, end: usize) -> (usize, usize) {
+    let line_start = source[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    if !source[line_start..start].trim().is_empty() {
+        return (start, end);
+    }
+    let start = line_start;
+
+    let line_end = source[end..]
+        .find('\n')
+        .map(|i| end + i + 1)
+        .unwrap_or(source.len());
+    if !source[end..line_end].trim().is_empty() {
+        return (start, end);
+    }
+    let mut end = line_end;
+
+    let before = source[..start].trim_end_matches([' ', '\t']);
+    let preceded_by_blank = before.is_empty()
+        || before.ends_with("\n\n")
+        || before.trim_end().ends_with(['{', '(', '[', ':']);
+    if preceded_by_blank {
+        let next_end = source[end..]
+            .find('\n')
+            .map(|i| end + i + 1)
+            .unwrap_or(source.len());
+        if next_end > end && source[end..next_end].trim().is_empty() {
+            end = next_end;
+        }
+    }
+
+    (start, end)
+}
+
+/// Text of `range` with the replacements that fall inside it applied
+pub fn text_with(
+    source: &str,
+    range: (usize, usize),
+    replacements: &[(usize, usize, String)],
+) -> String {
+    let mut inner: Vec<&(usize, usize, String)> = replacements
+        .iter()
+        .filter(|(start, end, _)| range.0 <= *start && *end <= range.1)
+        .collect();
+    inner.sort_by_key(|(start, end, _)| (*start, *end));
+    let mut text = String::new();
+    let mut last = range.0;
+    for (start, end, replacement) in inner {
+        text.push_str(&source[last..*start]);
+        text.push_str(replacement);
+        last = *end;
+    }
+    text.push_str(&source[last..range.1]);
+    text
+}
+
+/// Leading whitespace of the line containing `offset`
+pub fn line_indent(source: &str, offset: usize) -> &str {
+    let line_start = source[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line = &source[line_start..];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Lines of a block body re-indented under `indent`, without leading or
+/// trailing blank lines
+pub fn reindent(body: &str, indent: &str) -> Vec<String> {
+    let lines: Vec<&str> = body.lines().collect();
+    let first = lines.iter().position(|l| !l.trim().is_empty());
+    let last = lines.iter().rposition(|l| !l.trim().is_empty());
+    let (Some(first), Some(last)) = (first, last) else {
+        return Vec::new();
+    };
+    let lines = &lines[first..=last];
+    let min_indent = lines
+        .iter()
+        .skip(usize::from(first == 0))
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, l)| {
+            if l.trim().is_empty() {
+                String::new()
+            } else if i == 0 && first == 0 {
+                format!("{}{}", indent, l.trim())
+            } else {
+                format!(
+                    "{}{}",
+                    indent,
+                    l[min_indent.min(l.len() - l.trim_start().len())..].trim_end()
+                )
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_touching_edits_are_merged() {
+        let source = "use a;\nfn f() {}\nfn g() {}\n";
+        let mut edits = FileEdits::new(source, None);
+        edits.insert(7, "use b;\n");
+        edits.delete(7, 17);
+
+        assert_eq!(edits.apply(), "use a;\nuse b;\nfn g() {}\n");
+        let text_edits = edits.into_text_edits("move");
+        assert_eq!(text_edits.len(), 1);
+        assert_eq!(text_edits[0].new_text, "use b;\n");
+        assert_eq!(text_edits[0].original_text, "fn f() {}\n");
+    }
+
+    #[test]
+    fn test_full_line_range() {
+        let source = "fn a() {}\n\nfn b() {}\n\nfn c() {}\n";
+        let start = source.find("fn b").unwrap();
+        let (s, e) = full_line_range(source, start, start + 9);
+        assert_eq!(&source[s..e], "fn b() {}\n\n");
+
+        let source = "impl A {\n    fn a() {}\n\n    fn b() {}\n}\n";
+        let start = source.find("fn a").unwrap();
+        let (s, e) = full_line_range(source, start, start + 9);
+        assert_eq!(&source[s..e], "    fn a() {}\n\n");
+
+        let source = "let x = 1; let y = 2;\n";
+        assert_eq!(full_line_range(source, 11, 21), (11, 21));
+        assert_eq!(full_line_range(source, 0, 10), (0, 10));
+    }
+}
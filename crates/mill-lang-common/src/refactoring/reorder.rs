@@ -0,0 +1,404 @@
+//! Language-agnostic helpers for reorder refactorings.
+//!
+//! Plugins locate the items to reorder (parameters, fields, variants, imports)
+//! with their own parsers and hand the byte ranges to [`reorder_items_edit`],
+//! which rearranges the item texts while leaving the separators between them
+//! (commas, newlines, comments) where they were. Call sites of a function whose
+//! parameters were reordered are rewritten with [`reorder_call_arguments`].
+
+use crate::location::{offset_to_position, position_to_offset};
+use mill_foundation::protocol::{EditLocation, EditType, TextEdit};
+
+/// A reorderable item: its name and byte range in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReorderItem {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl ReorderItem {
+    pub fn new(name: impl Into<String>, start: usize, end: usize) -> Self {
+        Self {
+            name: name.into(),
+            start,
+            end,
+        }
+    }
+}
+
+/// Resolve the requested order into a permutation of the current items.
+///
+/// `permutation[i]` is the index in `current` of the item that ends up at
+/// position `i`. Every current item must be named exactly once.
+pub fn resolve_permutation(current: &[String], new_order: &[String]) -> Result<Vec<usize>, String> {
+    if new_order.len() != current.len() {
+        return Err(format!(
+            "New order lists {} items but there are {} ({})",
+            new_order.len(),
+            current.len(),
+            current.join(", ")
+        ));
+    }
+
+    let mut used = vec![false; current.len()];
+    let mut permutation = Vec::with_capacity(current.len());
+    for name in new_order {
+        let index = current
+            .iter()
+            .enumerate()
+            .position(|(i, c)| c == name && !used[i])
+            .ok_or_else(|| {
+                format!(
+                    "'{}' is not one of the items to reorder ({})",
+                    name,
+                    current.join(", ")
+                )
+            })?;
+        used[index] = true;
+        permutation.push(index);
+    }
+
+    Ok(permutation)
+}
+
+/// Build a single replace edit that rearranges `items` according to `permutation`.
+///
+/// Items must be sorted by position and must not overlap. Returns `None` when
+/// the permutation is the identity (nothing to change).
+pub fn reorder_items_edit(
+    source: &str,
+    items: &[ReorderItem],
+    permutation: &[usize],
+    description: &str,
+) -> Option<TextEdit> {
+    if items.is_empty() || permutation.iter().enumerate().all(|(i, p)| i == *p) {
+        return None;
+    }
+
+    let start = items[0].start;
+    let end = items[items.len() - 1].end;
+    let mut new_text = String::with_capacity(end - start);
+    for (position, item) in items.iter().enumerate() {
+        if position > 0 {
+            new_text.push_str(&source[items[position - 1].end..item.start]);
+        }
+        let moved = &items[permutation[position]];
+        new_text.push_str(&source[moved.start..moved.end]);
+    }
+
+    Some(replace_edit(source, start, end, new_text, description))
+}
+
+/// Outcome of rewriting one call site
+#[derive(Debug, Clone)]
+pub enum CallSiteRewrite {
+    /// The arguments were reordered
+    Rewritten(TextEdit),
+    /// The arguments are already in the requested order
+    Unchanged,
+    /// The reference is not followed by an argument list (imports, function values, ...)
+    NotACall,
+    /// The call could not be rewritten safely; the reason is reported to the user
+    Skipped(String),
+}
+
+/// Reorder the arguments of the call whose callee identifier starts at `line`/`character`.
+///
+/// Works on plain text so it can be used for any C-like or Python call syntax:
+/// the identifier is skipped, optional generic arguments (`::<T>` or `<T>`) are
+/// skipped, and the parenthesized argument list is split on top-level commas.
+/// Calls that pass a different number of arguments than the function declares,
+/// or that use spread/keyword arguments, are skipped rather than guessed at.
+pub fn reorder_call_arguments(
+    source: &str,
+    line: u32,
+    character: u32,
+    permutation: &[usize],
+) -> CallSiteRewrite {
+    let Some(offset) = position_to_offset(source, line, character) else {
+        return CallSiteRewrite::Skipped(format!(
+            "Position {}:{} is outside the file",
+            line + 1,
+            character + 1
+        ));
+    };
+
+    let bytes = source.as_bytes();
+    let mut pos = offset;
+    while pos < bytes.len()
+        && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_' || bytes[pos] == b'$')
+    {
+        pos += 1;
+    }
+    pos = skip_whitespace(bytes, pos);
+
+    // Skip explicit generic arguments: `f::<T>(..)` or `f<T>(..)`
+    if source[pos..].starts_with("::<") {
+        pos += 2;
+    }
+    if bytes.get(pos) == Some(&b'<') {
+        match find_closing(bytes, pos, b'<', b'>') {
+            Some(close) => pos = skip_whitespace(bytes, close + 1),
+            None => return CallSiteRewrite::NotACall,
+        }
+    }
+
+    if bytes.get(pos) != Some(&b'(') {
+        return CallSiteRewrite::NotACall;
+    }
+    let Some((args, _)) = split_delimited(source, pos) else {
+        return CallSiteRewrite::Skipped("Unbalanced argument list".to_string());
+    };
+
+    if args.len() != permutation.len() {
+        return CallSiteRewrite::Skipped(format!(
+            "Call passes {} argument(s) but the function takes {}",
+            args.len(),
+            permutation.len()
+        ));
+    }
+    if let Some(arg) = args.iter().find(|a| is_spread_or_keyword(&a.name)) {
+        return CallSiteRewrite::Skipped(format!(
+            "Call uses spread or keyword argument `{}`",
+            arg.name
+        ));
+    }
+
+    match reorder_items_edit(source, &args, permutation, "Reorder call arguments") {
+        Some(edit) => CallSiteRewrite::Rewritten(edit),
+        None => CallSiteRewrite::Unchanged,
+    }
+}
+
+/// Split the comma-separated list inside the bracket at `open_pos`.
+///
+/// Each item is named by its trimmed text. Returns the items and the offset of
+/// the closing bracket, or `None` when the bracket is unbalanced.
+pub fn split_delimited(source: &str, open_pos: usize) -> Option<(Vec<ReorderItem>, usize)> {
+    let bytes = source.as_bytes();
+    let (open, close) = match bytes.get(open_pos)? {
+        b'(' => (b'(', b')'),
+        b'[' => (b'[', b']'),
+        b'{' => (b'{', b'}'),
+        b'<' => (b'<', b'>'),
+        _ => return None,
+    };
+    let close_pos = find_closing(bytes, open_pos, open, close)?;
+    Some((split_top_level(source, open_pos + 1, close_pos), close_pos))
+}
+
+fn replace_edit(
+    source: &str,
+    start: usize,
+    end: usize,
+    new_text: String,
+    description: &str,
+) -> TextEdit {
+    let (start_line, start_column) = offset_to_position(source, start);
+    let (end_line, end_column) = offset_to_position(source, end);
+    TextEdit {
+        file_path: None,
+        edit_type: EditType::Replace,
+        location: EditLocation {
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        },
+        original_text: source[start..end].to_string(),
+        new_text,
+        priority: 100,
+        description: description.to_string(),
+    }
+}
+
+fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+        pos += 1;
+    }
+    pos
+}
+
+/// Find the bracket closing the one at `open_pos`, skipping string literals
+fn find_closing(bytes: &[u8], open_pos: usize, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut pos = open_pos;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'"' | b'\'' | b'`' => pos = skip_string(bytes, pos),
+            b if b == open => depth += 1,
+            b if b == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(pos);
+                }
+            }
+            _ => {}
+        }
+        pos += 1;
+    }
+    None
+}
+
+/// Return the index of the closing quote of the string starting at `pos`
+fn skip_string(bytes: &[u8], pos: usize) -> usize {
+    let quote = bytes[pos];
+    let mut i = pos + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b if b == quote => return i,
+            // Rust lifetimes and char literals share the quote; stop at line end
+            b'\n' if quote == b'\'' => return pos,
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Split `source[start..end]` on top-level commas into trimmed argument ranges
+fn split_top_level(source: &str, start: usize, end: usize) -> Vec<ReorderItem> {
+    let bytes = source.as_bytes();
+    let mut items = Vec::new();
+    let mut depth = 0i32;
+    let mut segment_start = start;
+    let mut pos = start;
+
+    let push = |from: usize, to: usize, items: &mut Vec<ReorderItem>| {
+        let text = &source[from..to];
+        let trimmed = text.trim();
+        if !trimmed.is_empty() {
+            let lead = text.len() - text.trim_start().len();
+            items.push(ReorderItem::new(
+                trimmed,
+                from + lead,
+                from + lead + trimmed.len(),
+            ));
+        }
+    };
+
+    while pos < end {
+        match bytes[pos] {
+            b'"' | b'\'' | b'`' => pos = skip_string(bytes, pos),
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            b',' if depth == 0 => {
+                push(segment_start, pos, &mut items);
+                segment_start = pos + 1;
+            }
+            _ => {}
+        }
+        pos += 1;
+    }
+    push(segment_start, end, &mut items);
+    items
+}
+
+/// Spread (`...xs`, `*args`, `**kwargs`) or keyword (`name=value`) arguments
+fn is_spread_or_keyword(arg: &str) -> bool {
+    if arg.starts_with("...") || arg.starts_with('*') {
+        return true;
+    }
+    let bytes = arg.as_bytes();
+    let ident_len = bytes
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+        .count();
+    let rest = arg[ident_len..].trim_start();
+    ident_len > 0 && rest.starts_with('=') && !rest.starts_with("==") && !rest.starts_with("=>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(source: &str, edit: &TextEdit) -> String {
+        let start =
+            position_to_offset(source, edit.location.start_line, edit.location.start_column)
+                .unwrap();
+        let end =
+            position_to_offset(source, edit.location.end_line, edit.location.end_column).unwrap();
+        let mut result = source.to_string();
+        result.replace_range(start..end, &edit.new_text);
+        result
+    }
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_resolve_permutation() {
+        let current = names(&["a", "b", "c"]);
+        assert_eq!(
+            resolve_permutation(&current, &names(&["c", "a", "b"])).unwrap(),
+            vec![2, 0, 1]
+        );
+        assert!(resolve_permutation(&current, &names(&["a", "b"])).is_err());
+        assert!(resolve_permutation(&current, &names(&["a", "b", "d"])).is_err());
+        assert!(resolve_permutation(&current, &names(&["a", "a", "b"])).is_err());
+    }
+
+    #[test]
+    fn test_reorder_items_keeps_separators() {
+        let source = "fn f(a: i32, /* keep */ b: &str,\n     c: bool) {}";
+        let items = vec![
+            ReorderItem::new("a", 5, 11),
+            ReorderItem::new("b", 24, 31),
+            ReorderItem::new("c", 38, 45),
+        ];
+        let edit = reorder_items_edit(source, &items, &[2, 0, 1], "test").unwrap();
+        assert_eq!(
+            apply(source, &edit),
+            "fn f(c: bool, /* keep */ a: i32,\n     b: &str) {}"
+        );
+        assert!(reorder_items_edit(source, &items, &[0, 1, 2], "test").is_none());
+    }
+
+    #[test]
+    fn test_reorder_call_arguments() {
+        let source = "let x = compute(first(1, 2), \"a, b\", [3, 4]);";
+        match reorder_call_arguments(source, 0, 8, &[2, 1, 0]) {
+            CallSiteRewrite::Rewritten(edit) => assert_eq!(
+                apply(source, &edit),
+                "let x = compute([3, 4], \"a, b\", first(1, 2));"
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reorder_call_arguments_with_turbofish() {
+        let source = "parse::<u8>(a, b)";
+        assert!(matches!(
+            reorder_call_arguments(source, 0, 0, &[1, 0]),
+            CallSiteRewrite::Rewritten(_)
+        ));
+    }
+
+    #[test]
+    fn test_reorder_call_arguments_skips_unsafe_calls() {
+        assert!(matches!(
+            reorder_call_arguments("f(a)", 0, 0, &[1, 0]),
+            CallSiteRewrite::Skipped(_)
+        ));
+        assert!(matches!(
+            reorder_call_arguments("f(a, b=1)", 0, 0, &[1, 0]),
+            CallSiteRewrite::Skipped(_)
+        ));
+        assert!(matches!(
+            reorder_call_arguments("f(...xs, b)", 0, 0, &[1, 0]),
+            CallSiteRewrite::Skipped(_)
+        ));
+        assert!(matches!(
+            reorder_call_arguments("import { f } from './f';", 0, 9, &[1, 0]),
+            CallSiteRewrite::NotACall
+        ));
+        assert!(matches!(
+            reorder_call_arguments("f(a == b, c)", 0, 0, &[1, 0]),
+            CallSiteRewrite::Rewritten(_)
+        ));
+    }
+}
@@ -1,6 +1,8 @@
 import sys
 import ast
+import io
 import json
+import tokenize
 
 def list_functions(source_code):
     """
@@ -24,21 +26,78 @@ def list_functions(source_code):
             },
         }
 
+# Token types that are not code: layout, comments and the end marker
+NON_CODE_TOKENS = {
+    tokenize.COMMENT,
+    tokenize.NL,
+    tokenize.NEWLINE,
+    tokenize.INDENT,
+    tokenize.DEDENT,
+    tokenize.ENDMARKER,
+}
+
+def syntax_index(source_code, with_nodes):
+    """
+    Returns the byte ranges of the code tokens and, if requested, of the AST
+    nodes of Python source code.
+    """
+    lines = io.StringIO(source_code).readlines()
+    line_starts = [0]
+    for line in lines:
+        line_starts.append(line_starts[-1] + len(line.encode("utf-8")))
+
+    def char_offset(row, col):
+        line = lines[row - 1] if row - 1 < len(lines) else ""
+        return line_starts[row - 1] + len(line[:col].encode("utf-8"))
+
+    try:
+        tokens = [
+            [char_offset(*tok.start), char_offset(*tok.end)]
+            for tok in tokenize.generate_tokens(io.StringIO(source_code).readline)
+            if tok.type not in NON_CODE_TOKENS
+        ]
+        nodes = []
+        if with_nodes:
+            for node in ast.walk(ast.parse(source_code)):
+                if getattr(node, "end_col_offset", None) is None:
+                    continue
+                start = line_starts[node.lineno - 1] + node.col_offset
+                end = line_starts[node.end_lineno - 1] + node.end_col_offset
+                if start < end:
+                    nodes.append([start, end])
+        return {"status": "success", "data": {"tokens": tokens, "nodes": nodes}}
+    except (SyntaxError, tokenize.TokenError) as e:
+        return {
+            "status": "error",
+            "error": {
+                "type": type(e).__name__,
+                "message": getattr(e, "msg", str(e)),
+                "lineno": getattr(e, "lineno", None),
+                "offset": getattr(e, "offset", None),
+            },
+        }
+
 if __name__ == "__main__":
     if len(sys.argv) < 2:
         print(json.dumps({"status": "error", "error": {"type": "UsageError", "message": "No command provided."}}), file=sys.stderr)
         sys.exit(1)
 
     command = sys.argv[1]
-    source = sys.stdin.read()
+    # Read undecoded so that offsets match the source as stored (no newline translation)
+    source = sys.stdin.buffer.read().decode("utf-8")
 
     if command == "list-functions":
         result = list_functions(source)
-        if result["status"] == "success":
-            print(json.dumps(result["data"]))
-        else:
-            print(json.dumps(result["error"]), file=sys.stderr)
-            sys.exit(1)
+    elif command in ("tokens", "syntax-index"):
+        result = syntax_index(source, command == "syntax-index")
     else:
+        result = None
+
+    if result is None:
         print(json.dumps({"status": "error", "error": {"type": "UsageError", "message": f"Unknown command: {command}"}}), file=sys.stderr)
         sys.exit(1)
+    elif result["status"] == "success":
+        print(json.dumps(result["data"]))
+    else:
+        print(json.dumps(result["error"]), file=sys.stderr)
+        sys.exit(1)
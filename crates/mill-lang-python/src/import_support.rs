@@ -266,7 +266,7 @@ impl ImportMutationSupport for PythonImportSupport {
 /// - `src/foo/bar.py` -> `foo.bar`
 /// - `foo/bar/__init__.py` -> `foo.bar`
 /// - `example.py` -> `example`
-fn path_to_python_module(path: &Path) -> String {
+pub(crate) fn path_to_python_module(path: &Path) -> String {
     // Get the path without extension
     let path_no_ext = path.with_extension("");
 
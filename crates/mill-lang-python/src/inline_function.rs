@@ -0,0 +1,1693 @@
+//! Inline-function refactoring for Python
+//!
+//! Calls to a function defined in the same file are replaced by its body.
+//! Names, numbers and plain string literals passed as arguments are
+//! substituted for the parameters; anything else is assigned to the parameter
+//! first. Python has no block scope, so names the body introduces that clash
+//! with names at the call site are renamed to `name_function`.
+//!
+//! A final `return` becomes an assignment (or an expression statement), and
+//! early returns ending the branches of a top-level `if` chain turn the rest
+//! of the body into an `else` branch. Calls written as `return f(...)` keep
+//! the body's returns unchanged.
+//!
+//! Like the rest of this plugin, the analysis works on tokens and indentation
+//! so it does not need a Python interpreter.
+
+use crate::symbol_ops::{
+    definition_header, enclosing_body_left_empty, find_block, indentation, is_blank_or_comment,
+    removal_range, SourceLines,
+};
+use mill_foundation::protocol::EditPlan;
+use mill_lang_common::position_to_offset;
+use mill_lang_common::refactoring::edit_plan_builder::EditPlanBuilder;
+use mill_lang_common::refactoring::file_edits::{reindent, text_with, FileEdits};
+use mill_plugin_api::{PluginApiError, PluginResult};
+use std::collections::{HashMap, HashSet};
+
+type Range = (usize, usize);
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Plan inlining the function called (or defined) at the given position
+pub fn plan_inline_function(
+    source: &str,
+    line: u32,
+    character: u32,
+    inline_all: bool,
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let lines = SourceLines::new(source);
+    let cursor = position_to_offset(source, line, character)
+        .ok_or_else(|| PluginApiError::invalid_input("Position is outside of the file"))?;
+    let tokens = code_tokens(source);
+    let definitions = find_definitions(source, &lines, &tokens);
+    let all_calls = find_calls(source, &lines, &tokens);
+
+    // Resolve the function from a call under the cursor, or from its definition
+    let cursor_call = all_calls
+        .iter()
+        .filter(|call| call.range.0 <= cursor && cursor <= call.range.1)
+        .min_by_key(|call| call.range.1 - call.range.0);
+    let definition = match cursor_call {
+        Some(call) => find_definition(&definitions, &call.name)?.ok_or_else(|| {
+            PluginApiError::invalid_input(format!(
+                "'{}' is not a function defined in this file",
+                call.name
+            ))
+        })?,
+        None => {
+            let definition = definitions
+                .iter()
+                .find(|d| d.header_line == line as usize)
+                .ok_or_else(|| {
+                    PluginApiError::invalid_input(format!(
+                        "No function call or definition found at line {}",
+                        line + 1
+                    ))
+                })?;
+            find_definition(&definitions, &definition.name)?;
+            definition
+        }
+    };
+    let name = definition.name.clone();
+    let body = analyze_body(source, &tokens, definition)?;
+
+    let scope_of = |call: &CallSite| enclosing_function(&definitions, call.range.0);
+    let shadowed = |call: &CallSite| {
+        scope_of(call).is_some_and(|scope| {
+            Some(scope.header_line) != definition.parent.map(|(line, _)| line)
+                && scan_names(source, &tokens_in(&tokens, scope.range))
+                    .declared
+                    .contains(&call.name)
+        })
+    };
+    let mut calls: Vec<&CallSite> = match (cursor_call, inline_all) {
+        (Some(call), false) => {
+            if shadowed(call) {
+                return Err(PluginApiError::invalid_input(format!(
+                    "'{}' at this call refers to a local variable, not the function defined in this file",
+                    call.name
+                )));
+            }
+            vec![call]
+        }
+        _ => all_calls
+            .iter()
+            .filter(|call| call.name == name && !shadowed(call))
+            .collect(),
+    };
+    if calls.is_empty() {
+        return Err(PluginApiError::invalid_input(format!(
+            "No calls to '{}' found in this file",
+            name
+        )));
+    }
+
+    // Locals of the enclosing function the body may capture
+    let parent = definition
+        .parent
+        .and_then(|(line, _)| definitions.iter().find(|d| d.header_line == line));
+    let mut captured: Vec<&String> = match parent {
+        Some(parent) => {
+            let names = scan_names(source, &tokens_in(&tokens, parent.range));
+            body.free
+                .iter()
+                .filter(|n| names.declared.contains(*n))
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    captured.sort();
+
+    // Inner calls first, so a call nested in another call's arguments is
+    // inlined into that argument's text
+    calls.sort_by_key(|call| call.range.1 - call.range.0);
+    let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+    for call in &calls {
+        let call_line = lines_before(source, call.range.0) + 1;
+        let inside_parent =
+            parent.is_none_or(|p| p.range.0 <= call.range.0 && call.range.1 <= p.range.1);
+        if let (false, Some(local)) = (inside_parent, captured.first()) {
+            return Err(PluginApiError::invalid_input(format!(
+                "Cannot inline call at line {}: '{}' captures '{}' from its enclosing function",
+                call_line, name, local
+            )));
+        }
+
+        let scope = scope_of(call);
+        let caller_names: HashSet<String> = match scope {
+            Some(scope) => {
+                if parent.map(|p| p.header_line) != Some(scope.header_line) {
+                    let locals = scan_names(source, &tokens_in(&tokens, scope.range)).declared;
+                    if let Some(shadowed) = body.free.iter().filter(|f| locals.contains(*f)).min() {
+                        return Err(PluginApiError::invalid_input(format!(
+                            "Cannot inline call at line {}: '{}' used by '{}' is shadowed by a local variable there",
+                            call_line, shadowed, name
+                        )));
+                    }
+                }
+                scan_names(source, &tokens_in(&tokens, scope.range)).used
+            }
+            None => {
+                let outside: Vec<Token> = tokens
+                    .iter()
+                    .filter(|t| t.start < definition.body.0 || t.start >= definition.body.1)
+                    .copied()
+                    .collect();
+                scan_names(source, &outside).used
+            }
+        };
+
+        let (range, replacement) = inline_call(
+            source,
+            &tokens,
+            definition,
+            &body,
+            call,
+            &replacements,
+            &caller_names,
+        )?;
+        replacements.retain(|(start, end, _)| !(range.0 <= *start && *end <= range.1));
+        replacements.push((range.0, range.1, replacement));
+    }
+
+    let mut edits = FileEdits::new(source, None);
+    for (start, end, text) in replacements {
+        edits.replace(start, end, text);
+    }
+
+    // Remove the definition once nothing else refers to it
+    let mut notes = Vec::new();
+    let mut definition_removed = false;
+    if inline_all {
+        let references = tokens
+            .iter()
+            .enumerate()
+            .filter(|(i, t)| {
+                t.kind == Kind::Name
+                    && t.text(source) == name
+                    && !(*i > 0 && matches!(tokens[i - 1].text(source), "." | "def"))
+            })
+            .count();
+        if definition.parent.is_none() && !name.starts_with('_') {
+            notes.push(format!(
+                "'{}' is a public module-level function and may be imported by other modules; its definition was kept",
+                name
+            ));
+        } else if references > calls.len() {
+            notes.push(format!(
+                "'{}' is still referenced in this file (e.g. passed as a value); its definition was kept",
+                name
+            ));
+        } else {
+            let block = find_block(&lines, definition.header_line)?;
+            let (start, end) = removal_range(&lines, &block);
+            match enclosing_body_left_empty(&lines, &block) {
+                true => edits.replace(start, end, format!("{}pass\n", " ".repeat(block.indent))),
+                false => edits.delete(start, end),
+            }
+            definition_removed = true;
+        }
+    }
+
+    Ok(EditPlanBuilder::new(file_path, "inline_function")
+        .with_edits(edits.into_text_edits(&format!("Inline function '{}'", name)))
+        .with_syntax_validation("Verify Python syntax is valid after inlining")
+        .with_intent_args(serde_json::json!({
+            "function": name,
+            "calls_inlined": calls.len(),
+            "definition_removed": definition_removed,
+            "notes": notes,
+        }))
+        .with_complexity(3)
+        .with_impact_area("function_inlining")
+        .build())
+}
+
+fn lines_before(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count()
+}
+
+// ============================================================================
+// Tokens
+// ============================================================================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Name,
+    Str {
+        formatted: bool,
+    },
+    Number,
+    Op,
+    Comment,
+    /// End of a logical line
+    Newline,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token {
+    kind: Kind,
+    start: usize,
+    end: usize,
+    /// Bracket depth; an opening bracket and its closing bracket share it
+    depth: usize,
+}
+
+impl Token {
+    fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+
+    fn is(&self, source: &str, text: &str) -> bool {
+        matches!(self.kind, Kind::Op | Kind::Name) && self.text(source) == text
+    }
+
+    fn is_identifier(&self, source: &str) -> bool {
+        self.kind == Kind::Name && !KEYWORDS.contains(&self.text(source))
+    }
+}
+
+const OPERATORS: &[&str] = &[
+    "**=", "//=", ">>=", "<<=", "...", "->", ":=", "==", "!=", "<=", ">=", "**", "//", "<<", ">>",
+    "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "@=",
+];
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    let mut continued = false;
+    let mut i = 0;
+    let push = |tokens: &mut Vec<Token>, kind, start, end, depth| {
+        tokens.push(Token {
+            kind,
+            start,
+            end,
+            depth,
+        })
+    };
+    while i < source.len() {
+        let c = source[i..].chars().next().unwrap_or(' ');
+        if c == '\n' {
+            if depth == 0 && !continued {
+                push(&mut tokens, Kind::Newline, i, i, 0);
+            }
+            continued = false;
+            i += 1;
+            continue;
+        }
+        if c == '\\' {
+            continued = true;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += c.len_utf8();
+            continue;
+        }
+        continued = false;
+
+        let start = i;
+        if c == '#' {
+            let end = source[i..]
+                .find('\n')
+                .map(|n| i + n)
+                .unwrap_or(source.len());
+            push(&mut tokens, Kind::Comment, start, end, depth);
+            i = end;
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = i;
+            for ch in source[i..].chars() {
+                if !(ch.is_alphanumeric() || ch == '_') {
+                    break;
+                }
+                end += ch.len_utf8();
+            }
+            let word = &source[i..end];
+            let is_prefix = word.len() <= 2 && word.chars().all(|ch| "rRbBuUfF".contains(ch));
+            if is_prefix && matches!(bytes.get(end), Some(b'"' | b'\'')) {
+                let formatted = word.contains(['f', 'F']);
+                i = string_end(bytes, end);
+                push(&mut tokens, Kind::Str { formatted }, start, i, depth);
+            } else {
+                push(&mut tokens, Kind::Name, start, end, depth);
+                i = end;
+            }
+        } else if c == '"' || c == '\'' {
+            i = string_end(bytes, i);
+            push(&mut tokens, Kind::Str { formatted: false }, start, i, depth);
+        } else if c.is_ascii_digit()
+            || (c == '.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit))
+        {
+            let mut end = i + 1;
+            while end < bytes.len() {
+                let b = bytes[end];
+                let exponent_sign =
+                    matches!(b, b'+' | b'-') && matches!(bytes[end - 1], b'e' | b'E');
+                if !(b.is_ascii_alphanumeric() || b == b'_' || b == b'.' || exponent_sign) {
+                    break;
+                }
+                end += 1;
+            }
+            push(&mut tokens, Kind::Number, start, end, depth);
+            i = end;
+        } else {
+            let len = OPERATORS
+                .iter()
+                .find(|op| source[i..].starts_with(*op))
+                .map(|op| op.len())
+                .unwrap_or(c.len_utf8());
+            match c {
+                '(' | '[' | '{' => {
+                    push(&mut tokens, Kind::Op, start, i + len, depth);
+                    depth += 1;
+                }
+                ')' | ']' | '}' => {
+                    depth = depth.saturating_sub(1);
+                    push(&mut tokens, Kind::Op, start, i + len, depth);
+                }
+                _ => push(&mut tokens, Kind::Op, start, i + len, depth),
+            }
+            i += len;
+        }
+    }
+    push(&mut tokens, Kind::Newline, source.len(), source.len(), 0);
+    tokens
+}
+
+/// Byte offset just past the string literal whose opening quote is at `quote`
+fn string_end(bytes: &[u8], quote: usize) -> usize {
+    let q = bytes[quote];
+    let triple = bytes.get(quote..quote + 3) == Some(&[q, q, q][..]);
+    let mut i = quote + if triple { 3 } else { 1 };
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b if b == q && !triple => return i + 1,
+            b if b == q && bytes.get(i..i + 3) == Some(&[q, q, q][..]) => return i + 3,
+            b'\n' if !triple => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Tokens without comments
+fn code_tokens(source: &str) -> Vec<Token> {
+    tokenize(source)
+        .into_iter()
+        .filter(|t| t.kind != Kind::Comment)
+        .collect()
+}
+
+fn tokens_in(tokens: &[Token], range: Range) -> Vec<Token> {
+    tokens
+        .iter()
+        .filter(|t| range.0 <= t.start && t.end <= range.1)
+        .copied()
+        .collect()
+}
+
+/// Index of the bracket closing the one at `open`
+fn matching_close(tokens: &[Token], open: usize) -> Option<usize> {
+    let depth = tokens[open].depth;
+    (open + 1..tokens.len()).find(|i| tokens[*i].kind == Kind::Op && tokens[*i].depth == depth)
+}
+
+/// Token index ranges of logical lines
+fn logical_lines(tokens: &[Token]) -> Vec<Range> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind == Kind::Newline {
+            if i > start {
+                result.push((start, i));
+            }
+            start = i + 1;
+        }
+    }
+    if start < tokens.len() {
+        result.push((start, tokens.len()));
+    }
+    result
+}
+
+/// Split `open+1..close` at commas directly inside the bracket
+fn split_commas(tokens: &[Token], source: &str, open: usize, close: usize) -> Vec<Range> {
+    let depth = tokens[open].depth + 1;
+    let mut parts = Vec::new();
+    let mut start = open + 1;
+    for (i, token) in tokens.iter().enumerate().take(close).skip(open + 1) {
+        if token.depth == depth && token.is(source, ",") {
+            parts.push((start, i));
+            start = i + 1;
+        }
+    }
+    parts.push((start, close));
+    parts.retain(|(s, e)| e > s);
+    parts
+}
+
+fn span(tokens: &[Token], range: Range) -> Range {
+    (tokens[range.0].start, tokens[range.1 - 1].end)
+}
+
+/// Distinct identifiers in `tokens`
+fn words(source: &str, tokens: &[Token]) -> HashSet<String> {
+    tokens
+        .iter()
+        .filter(|t| t.kind == Kind::Name)
+        .map(|t| t.text(source).to_string())
+        .collect()
+}
+
+// ============================================================================
+// Definitions and call sites
+// ============================================================================
+
+struct Param {
+    name: String,
+    default: Option<Range>,
+    /// `*args` or `**kwargs`
+    variadic: bool,
+}
+
+struct Definition {
+    name: String,
+    header_line: usize,
+    /// Byte range from the `def` keyword to the end of the body
+    range: Range,
+    params: Vec<Param>,
+    /// Byte range of the body, starting at the newline before it unless the
+    /// body follows the colon on the header line
+    body: Range,
+    is_async: bool,
+    decorated: bool,
+    /// Enclosing `def`/`class` header line and kind
+    parent: Option<(usize, &'static str)>,
+}
+
+fn line_of(lines: &SourceLines, offset: usize) -> usize {
+    lines.starts.partition_point(|start| *start <= offset) - 1
+}
+
+fn find_definitions(source: &str, lines: &SourceLines, tokens: &[Token]) -> Vec<Definition> {
+    let mut definitions = Vec::new();
+    for (start, end) in logical_lines(tokens) {
+        let is_async = tokens[start].is(source, "async");
+        let def = start + usize::from(is_async);
+        if def + 2 >= end || !tokens[def].is(source, "def") {
+            continue;
+        }
+        let name = tokens[def + 1].text(source).to_string();
+        let open = def + 2;
+        let Some(close) = matching_close(tokens, open) else {
+            continue;
+        };
+        let params = split_commas(tokens, source, open, close)
+            .into_iter()
+            .filter_map(|(s, e)| {
+                let first = tokens[s].text(source);
+                let variadic = matches!(first, "*" | "**");
+                let name_index = (s..e).find(|i| tokens[*i].kind == Kind::Name)?;
+                let default = (s..e)
+                    .find(|i| tokens[*i].is(source, "="))
+                    .map(|eq| span(tokens, (eq + 1, e)));
+                Some(Param {
+                    name: tokens[name_index].text(source).to_string(),
+                    default,
+                    variadic,
+                })
+            })
+            .collect();
+        let Some(colon) =
+            (close + 1..end).find(|i| tokens[*i].depth == 0 && tokens[*i].is(source, ":"))
+        else {
+            continue;
+        };
+
+        let header_line = line_of(lines, tokens[def].start);
+        let indent = indentation(lines.lines[header_line]);
+        let body_end_line = crate::symbol_ops::block_end(lines, header_line, indent);
+        let inline_body = colon + 1 < end;
+        let body = if inline_body {
+            (tokens[colon + 1].start, tokens[end - 1].end)
+        } else {
+            let colon_line = line_of(lines, tokens[colon].start);
+            let (_, body_end) = lines.byte_range(colon_line + 1, body_end_line);
+            (
+                lines.starts[colon_line + 1] - 1,
+                body_end.max(lines.starts[colon_line + 1] - 1),
+            )
+        };
+        let decorated = header_line > 0 && {
+            let previous = (0..header_line)
+                .rev()
+                .find(|i| !lines.lines[*i].trim().is_empty());
+            previous.is_some_and(|i| lines.lines[i].trim_start().starts_with('@'))
+        };
+        definitions.push(Definition {
+            name,
+            header_line,
+            range: (tokens[start].start, body.1),
+            params,
+            body,
+            is_async,
+            decorated,
+            parent: enclosing_header(lines, header_line, indent),
+        });
+    }
+    definitions
+}
+
+/// Nearest `def`/`class` header above `line` with less indentation
+fn enclosing_header(
+    lines: &SourceLines,
+    line: usize,
+    indent: usize,
+) -> Option<(usize, &'static str)> {
+    let mut current = indent;
+    for i in (0..line).rev() {
+        let text = lines.lines[i];
+        if is_blank_or_comment(text) || current == 0 {
+            continue;
+        }
+        let own = indentation(text);
+        if own < current {
+            if let Some((_, kind)) = definition_header(text) {
+                return Some((i, kind));
+            }
+            current = own;
+        }
+    }
+    None
+}
+
+/// Innermost function whose body contains `offset`
+fn enclosing_function(definitions: &[Definition], offset: usize) -> Option<&Definition> {
+    definitions
+        .iter()
+        .filter(|d| d.body.0 <= offset && offset < d.body.1)
+        .min_by_key(|d| d.body.1 - d.body.0)
+}
+
+fn find_definition<'a>(
+    definitions: &'a [Definition],
+    name: &str,
+) -> PluginResult<Option<&'a Definition>> {
+    let mut matching = definitions.iter().filter(|d| d.name == name);
+    let first = matching.next();
+    if first.is_some() && matching.next().is_some() {
+        return Err(PluginApiError::invalid_input(format!(
+            "'{}' is defined more than once in this file; inlining it is ambiguous",
+            name
+        )));
+    }
+    if let Some(definition) = first {
+        if definition.parent.is_some_and(|(_, kind)| kind == "class") {
+            return Err(PluginApiError::invalid_input(
+                "Methods cannot be inlined; only functions defined with `def` outside a class are supported",
+            ));
+        }
+    }
+    Ok(first)
+}
+
+enum Arg {
+    Positional(Range),
+    Keyword(String, Range),
+    Unpacked,
+}
+
+enum CallContext {
+    /// `f(a)` on its own line
+    Statement(Range),
+    /// `return f(a)`
+    Return(Range),
+    /// `x = f(a)` or `x: T = f(a)`
+    Assignment { range: Range, target: String },
+    /// Anywhere else
+    Expression,
+}
+
+struct CallSite {
+    name: String,
+    range: Range,
+    args: Vec<Arg>,
+    context: CallContext,
+    /// Whether the call sits where an expression never needs parentheses
+    standalone: bool,
+}
+
+fn find_calls(source: &str, lines: &SourceLines, tokens: &[Token]) -> Vec<CallSite> {
+    let mut calls = Vec::new();
+    for (line_start, line_end) in logical_lines(tokens) {
+        let has_semicolon =
+            (line_start..line_end).any(|i| tokens[i].depth == 0 && tokens[i].is(source, ";"));
+        let first_on_line = {
+            let start = tokens[line_start].start;
+            let row = line_of(lines, start);
+            source[lines.starts[row]..start].trim().is_empty()
+        };
+        let statement = span(tokens, (line_start, line_end));
+
+        for k in line_start..line_end.saturating_sub(1) {
+            if !tokens[k].is_identifier(source) || !tokens[k + 1].is(source, "(") {
+                continue;
+            }
+            if k > line_start && matches!(tokens[k - 1].text(source), "." | "def" | "class") {
+                continue;
+            }
+            let Some(close) = matching_close(tokens, k + 1) else {
+                continue;
+            };
+            let args = split_commas(tokens, source, k + 1, close)
+                .into_iter()
+                .map(|(s, e)| {
+                    if matches!(tokens[s].text(source), "*" | "**") {
+                        Arg::Unpacked
+                    } else if e - s > 2
+                        && tokens[s].kind == Kind::Name
+                        && tokens[s + 1].is(source, "=")
+                    {
+                        Arg::Keyword(tokens[s].text(source).to_string(), span(tokens, (s + 2, e)))
+                    } else {
+                        Arg::Positional(span(tokens, (s, e)))
+                    }
+                })
+                .collect();
+
+            let whole_rest = close + 1 == line_end;
+            let simple = first_on_line && !has_semicolon && whole_rest;
+            let context = if simple && k == line_start {
+                CallContext::Statement(statement)
+            } else if simple && k == line_start + 1 && tokens[line_start].is(source, "return") {
+                CallContext::Return(statement)
+            } else if simple
+                && k >= line_start + 2
+                && tokens[k - 1].is(source, "=")
+                && tokens[line_start].is_identifier(source)
+                && (k == line_start + 2 || tokens[line_start + 1].is(source, ":"))
+            {
+                CallContext::Assignment {
+                    range: statement,
+                    target: tokens[line_start].text(source).to_string(),
+                }
+            } else {
+                CallContext::Expression
+            };
+            let before = tokens[k - usize::from(k > 0)].text(source);
+            let after = tokens.get(close + 1).map(|t| t.text(source)).unwrap_or("");
+            let standalone = !matches!(context, CallContext::Expression)
+                || (k > 0
+                    && matches!(before, "(" | "," | "[" | "=")
+                    && matches!(after, ")" | "," | "]"));
+
+            calls.push(CallSite {
+                name: tokens[k].text(source).to_string(),
+                range: (tokens[k].start, tokens[close].end),
+                args,
+                context,
+                standalone,
+            });
+        }
+    }
+    calls
+}
+
+// ============================================================================
+// Names
+// ============================================================================
+
+/// Identifier occurrences in a statement sequence
+#[derive(Default)]
+struct ScopeNames {
+    declared: HashSet<String>,
+    used: HashSet<String>,
+    /// Identifiers referring to variables, including those inside f-strings
+    occurrences: Vec<(String, Range)>,
+    formatted: HashSet<String>,
+    yields: bool,
+    non_local: Option<&'static str>,
+}
+
+fn scan_names(source: &str, tokens: &[Token]) -> ScopeNames {
+    let mut names = ScopeNames::default();
+    for (start, end) in logical_lines(tokens) {
+        let line = &tokens[start..end];
+        let depth = line[0].depth;
+
+        // Assignment targets: before the last `=` (or an augmented operator)
+        let assignment = line
+            .iter()
+            .rposition(|t| {
+                t.depth == depth
+                    && t.kind == Kind::Op
+                    && (t.text(source) == "="
+                        || (t.text(source).ends_with('=')
+                            && !matches!(t.text(source), "==" | "!=" | "<=" | ">=" | ":=")))
+            })
+            .filter(|_| !line[0].is(source, "def") && !line[0].is(source, "lambda"));
+        let annotated = line.len() > 1
+            && line[0].is_identifier(source)
+            && line[1].is(source, ":")
+            && line[1].depth == depth;
+        let mut subscripts: Vec<bool> = Vec::new();
+        let mut target_end = assignment.unwrap_or(0);
+        if annotated {
+            target_end = target_end.max(1);
+        }
+
+        let mut pending: Option<&str> = None;
+        let mut params_until: Option<usize> = None;
+        let mut importing = false;
+        for (j, token) in line.iter().enumerate() {
+            let text = token.text(source);
+            let previous = j.checked_sub(1).map(|p| line[p].text(source)).unwrap_or("");
+            let next = line.get(j + 1).map(|t| t.text(source)).unwrap_or("");
+            if j < target_end && token.kind == Kind::Op {
+                match text {
+                    "(" | "[" | "{" => subscripts.push(
+                        j > 0 && matches!(line[j - 1].kind, Kind::Name | Kind::Str { .. })
+                            || matches!(previous, ")" | "]"),
+                    ),
+                    ")" | "]" | "}" => {
+                        subscripts.pop();
+                    }
+                    _ => {}
+                }
+            }
+            if params_until.is_some_and(|close| close == j) {
+                params_until = None;
+            }
+            match token.kind {
+                Kind::Str { formatted: true } => {
+                    for (name, range) in formatted_names(source, token) {
+                        names.used.insert(name.clone());
+                        names.formatted.insert(name.clone());
+                        names.occurrences.push((name, range));
+                    }
+                }
+                Kind::Name if !token.is_identifier(source) => match text {
+                    "yield" => names.yields = true,
+                    "global" => names.non_local = Some("global"),
+                    "nonlocal" => names.non_local = Some("nonlocal"),
+                    "for" | "lambda" => pending = Some(text),
+                    "in" if pending == Some("for") => pending = None,
+                    "import" => importing = true,
+                    _ => {}
+                },
+                Kind::Name => {
+                    if previous == "." {
+                        continue;
+                    }
+                    let in_params = params_until.is_some();
+                    if token.depth > depth && next == "=" && !in_params && pending != Some("lambda")
+                    {
+                        continue;
+                    }
+                    names.used.insert(text.to_string());
+                    names
+                        .occurrences
+                        .push((text.to_string(), (token.start, token.end)));
+
+                    let is_target = j < target_end
+                        && !subscripts.iter().any(|s| *s)
+                        && !matches!(next, "." | "[" | "(");
+                    let declares = pending.is_some()
+                        || in_params
+                        || is_target
+                        || matches!(previous, "as" | "def" | "class")
+                        || next == ":="
+                        || (importing && matches!(previous, "import" | "," | "("));
+                    if declares {
+                        names.declared.insert(text.to_string());
+                    }
+                    if previous == "def" && next == "(" {
+                        params_until = matching_close(line, j + 1);
+                    }
+                }
+                Kind::Op if text == ":" && pending == Some("lambda") => pending = None,
+                _ => {}
+            }
+        }
+    }
+    names
+}
+
+/// Identifiers referenced in the replacement fields of an f-string
+fn formatted_names(source: &str, token: &Token) -> Vec<(String, Range)> {
+    let text = token.text(source);
+    let bytes = text.as_bytes();
+    let mut names = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'{' && bytes.get(i + 1) == Some(&b'{') {
+            i += 2;
+            continue;
+        }
+        if bytes[i] != b'{' {
+            i += 1;
+            continue;
+        }
+        i += 1;
+        let mut nesting = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            match b {
+                b'(' | b'[' | b'{' => nesting += 1,
+                b')' | b']' if nesting > 0 => nesting -= 1,
+                b'}' if nesting > 0 => nesting -= 1,
+                b'}' => break,
+                b':' | b'!' if nesting == 0 && bytes.get(i + 1) != Some(&b'=') => {
+                    // Format spec or conversion: skip to the closing brace
+                    while i < bytes.len() && bytes[i] != b'}' {
+                        i += 1;
+                    }
+                    break;
+                }
+                b'"' | b'\'' => {
+                    i = string_end(bytes, i);
+                    continue;
+                }
+                _ if b.is_ascii_alphabetic() || b == b'_' => {
+                    let start = i;
+                    while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_')
+                    {
+                        i += 1;
+                    }
+                    let word = &text[start..i];
+                    let attribute = start > 0 && bytes[start - 1] == b'.';
+                    if !attribute && !KEYWORDS.contains(&word) {
+                        let offset = token.start + start;
+                        names.push((word.to_string(), (offset, offset + word.len())));
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    names
+}
+
+// ============================================================================
+// Body analysis
+// ============================================================================
+
+struct BodyAnalysis {
+    names: ScopeNames,
+    /// Parameters that cannot be substituted textually
+    pinned: HashSet<String>,
+    /// Names the body refers to that it does not declare
+    free: HashSet<String>,
+}
+
+fn analyze_body(
+    source: &str,
+    tokens: &[Token],
+    definition: &Definition,
+) -> PluginResult<BodyAnalysis> {
+    let name = &definition.name;
+    let refuse = |reason: &str| {
+        Err(PluginApiError::invalid_input(format!(
+            "Cannot inline '{}': {}",
+            name, reason
+        )))
+    };
+    if definition.is_async {
+        return refuse("async functions cannot be inlined without awaiting their body");
+    }
+    if definition.decorated {
+        return refuse("decorators may change what calling the function does");
+    }
+    if let Some(param) = definition.params.iter().find(|p| p.variadic) {
+        return refuse(&format!(
+            "variadic parameter '{}' is not supported",
+            param.name
+        ));
+    }
+
+    let names = scan_names(source, &tokens_in(tokens, definition.body));
+    if names.used.contains(name) {
+        return refuse("the function is recursive");
+    }
+    if names.yields {
+        return refuse("generator functions cannot be inlined");
+    }
+    if let Some(statement) = names.non_local {
+        return refuse(&format!(
+            "the body uses `{}`, which would refer to the caller's scope",
+            statement
+        ));
+    }
+
+    let params: HashSet<&String> = definition.params.iter().map(|p| &p.name).collect();
+    let pinned = definition
+        .params
+        .iter()
+        .map(|p| p.name.clone())
+        .filter(|p| names.declared.contains(p) || names.formatted.contains(p))
+        .collect();
+    let free = names
+        .used
+        .iter()
+        .filter(|n| !names.declared.contains(*n) && !params.contains(n))
+        .cloned()
+        .collect();
+    Ok(BodyAnalysis {
+        names,
+        pinned,
+        free,
+    })
+}
+
+// ============================================================================
+// Building the inlined code
+// ============================================================================
+
+/// Whether the source range holds a single name, number or plain string
+fn is_trivial(source: &str, range: Range) -> bool {
+    let tokens = code_tokens(&source[range.0..range.1]);
+    let text = &source[range.0..range.1];
+    matches!(
+        tokens.as_slice(),
+        [single, newline] if newline.kind == Kind::Newline
+            && (matches!(single.kind, Kind::Number | Kind::Str { formatted: false })
+                || (single.kind == Kind::Name
+                    && (single.is_identifier(text)
+                        || matches!(single.text(text), "None" | "True" | "False"))))
+    )
+}
+
+/// Whether an expression never needs parentheses: a primary followed by
+/// attribute access, calls and subscripts
+fn is_atomic(text: &str) -> bool {
+    let tokens = code_tokens(text);
+    let tokens = &tokens[..tokens.len() - 1];
+    let Some(first) = tokens.first() else {
+        return false;
+    };
+    let mut i = match first.text(text) {
+        "(" | "[" | "{" => match matching_close(tokens, 0) {
+            Some(close) => close + 1,
+            None => return false,
+        },
+        _ if matches!(first.kind, Kind::Name | Kind::Number | Kind::Str { .. }) => 1,
+        _ => return false,
+    };
+    while i < tokens.len() {
+        match tokens[i].text(text) {
+            "." if i + 1 < tokens.len() && tokens[i + 1].kind == Kind::Name => i += 2,
+            "(" | "[" => match matching_close(tokens, i) {
+                Some(close) => i = close + 1,
+                None => return false,
+            },
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn inline_call(
+    source: &str,
+    tokens: &[Token],
+    definition: &Definition,
+    body: &BodyAnalysis,
+    call: &CallSite,
+    replacements: &[(usize, usize, String)],
+    caller_names: &HashSet<String>,
+) -> PluginResult<(Range, String)> {
+    let name = &definition.name;
+    let call_line = lines_before(source, call.range.0) + 1;
+    let refuse = |reason: String| {
+        Err(PluginApiError::invalid_input(format!(
+            "Cannot inline call at line {}: {}",
+            call_line, reason
+        )))
+    };
+
+    // Match arguments to parameters, keeping the order they are evaluated in
+    let mut values: HashMap<&str, (usize, Range)> = HashMap::new();
+    let mut positional = 0;
+    for (order, arg) in call.args.iter().enumerate() {
+        let (param, range) = match arg {
+            Arg::Unpacked => {
+                return refuse("unpacked arguments cannot be matched to parameters".to_string())
+            }
+            Arg::Positional(range) => {
+                let Some(param) = definition.params.get(positional) else {
+                    return refuse(format!(
+                        "'{}' takes {} arguments but more are passed",
+                        name,
+                        definition.params.len()
+                    ));
+                };
+                positional += 1;
+                (param.name.as_str(), *range)
+            }
+            Arg::Keyword(keyword, range) => {
+                match definition.params.iter().find(|p| p.name == *keyword) {
+                    Some(param) => (param.name.as_str(), *range),
+                    None => {
+                        return refuse(format!("'{}' has no parameter named '{}'", name, keyword))
+                    }
+                }
+            }
+        };
+        if values.insert(param, (order, range)).is_some() {
+            return refuse(format!("parameter '{}' is passed twice", param));
+        }
+    }
+
+    let declared = &body.names.declared;
+    let mut substitutions: HashMap<String, String> = HashMap::new();
+    // (evaluation order, parameter, value, names in the value)
+    let mut bindings: Vec<(usize, String, String, HashSet<String>)> = Vec::new();
+    for param in &definition.params {
+        let (order, value_range, from_call) = match (values.get(param.name.as_str()), param.default)
+        {
+            (Some((order, range)), _) => (*order, *range, true),
+            (None, Some(default)) => (usize::MAX, default, false),
+            (None, None) => {
+                return refuse(format!("no value is passed for parameter '{}'", param.name))
+            }
+        };
+        let value = if from_call {
+            text_with(source, value_range, replacements)
+        } else {
+            source[value_range.0..value_range.1].to_string()
+        };
+        let value_names = words(source, &tokens_in(tokens, value_range));
+        let used = body.names.used.contains(&param.name);
+        let trivial = is_trivial(source, value_range);
+        if trivial && !body.pinned.contains(&param.name) && value_names.is_disjoint(declared) {
+            substitutions.insert(param.name.clone(), value);
+            continue;
+        }
+        if let Some(clash) = value_names.intersection(declared).min() {
+            return refuse(format!(
+                "an argument refers to '{}', which the body of '{}' assigns too",
+                clash, name
+            ));
+        }
+        if !used && trivial {
+            continue;
+        }
+        bindings.push((order, param.name.clone(), value, value_names));
+    }
+    bindings.sort_by_key(|(order, ..)| *order);
+
+    // A `return expr` body can take each argument in place when every bound
+    // parameter is used once, in the order the arguments are evaluated
+    let body_tokens = tokens_in(tokens, definition.body);
+    let expression_body = matches!(
+        logical_lines(&body_tokens).as_slice(),
+        [(start, _)] if body_tokens[*start].is(source, "return")
+    );
+    if expression_body && !bindings.is_empty() {
+        let positions: Option<Vec<usize>> = bindings
+            .iter()
+            .map(|(_, param, ..)| {
+                let mut uses = body.names.occurrences.iter().filter(|(n, _)| n == param);
+                match (uses.next(), uses.next()) {
+                    (Some((_, range)), None) => Some(range.0),
+                    _ => None,
+                }
+            })
+            .collect();
+        if positions.is_some_and(|p| p.windows(2).all(|w| w[0] < w[1])) {
+            for (_, param, value, _) in bindings.drain(..) {
+                let value = if is_atomic(&value) {
+                    value
+                } else {
+                    format!("({})", value)
+                };
+                substitutions.insert(param, value);
+            }
+        }
+    }
+
+    // Rename introduced names that would clobber the caller's
+    let introduced: HashSet<&String> = declared
+        .iter()
+        .chain(bindings.iter().map(|(_, param, ..)| param))
+        .collect();
+    let mut taken: HashSet<String> = caller_names.clone();
+    taken.extend(body.names.used.iter().cloned());
+    let mut renames: HashMap<String, String> = HashMap::new();
+    let mut sorted: Vec<&&String> = introduced.iter().collect();
+    sorted.sort();
+    for original in sorted {
+        if caller_names.contains(*original) {
+            let mut renamed = format!("{}_{}", original, name);
+            let mut counter = 2;
+            while taken.contains(&renamed) {
+                renamed = format!("{}_{}{}", original, name, counter);
+                counter += 1;
+            }
+            taken.insert(renamed.clone());
+            renames.insert((*original).clone(), renamed);
+        }
+    }
+
+    let body_edits: Vec<(usize, usize, String)> = body
+        .names
+        .occurrences
+        .iter()
+        .filter(|(_, range)| definition.body.0 <= range.0 && range.1 <= definition.body.1)
+        .filter_map(|(occurrence, range)| {
+            substitutions
+                .get(occurrence)
+                .or_else(|| renames.get(occurrence))
+                .map(|text| (range.0, range.1, text.clone()))
+        })
+        .collect();
+    let rewritten = text_with(source, definition.body, &body_edits);
+    let body_text = reindent(&rewritten, "").join("\n");
+    let body_text = split_one_line_returns(&body_text);
+    let statements = parse_statements(&body_text);
+    let body_lines: Vec<&str> = body_text.split('\n').collect();
+
+    // A lone `return expr` with nothing to bind is inlined as an expression
+    if bindings.is_empty() {
+        if let [only] = statements.as_slice() {
+            if only.keyword == "return" {
+                let expr = only.returned.clone().unwrap_or_else(|| "None".to_string());
+                let tuple = matches!(call.context, CallContext::Expression)
+                    && code_tokens(&expr)
+                        .iter()
+                        .any(|t| t.depth == 0 && t.is(&expr, ","));
+                let wrap = (!call.standalone || tuple) && !is_atomic(&expr);
+                return Ok((call.range, if wrap { format!("({})", expr) } else { expr }));
+            }
+        }
+    }
+
+    let (statement, result) = match &call.context {
+        CallContext::Statement(range) => (*range, Result::Discard),
+        CallContext::Return(range) => (*range, Result::Return),
+        CallContext::Assignment { range, target } => (*range, Result::Assign(target.clone())),
+        CallContext::Expression => {
+            return refuse(format!(
+                "the body of '{}' has statements, so the call must be a statement, a `return` or an assignment; assign it to a variable first",
+                name
+            ))
+        }
+    };
+
+    let mut output = Vec::new();
+    let binding_names: HashSet<&String> = bindings.iter().map(|(_, p, ..)| p).collect();
+    let binding_name = |param: &String| renames.get(param).unwrap_or(param).clone();
+    let simultaneous = bindings.len() > 1
+        && bindings
+            .iter()
+            .any(|(.., names)| names.iter().any(|n| binding_names.contains(n)));
+    if simultaneous {
+        let targets: Vec<String> = bindings.iter().map(|(_, p, ..)| binding_name(p)).collect();
+        let values: Vec<&str> = bindings.iter().map(|(_, _, v, _)| v.as_str()).collect();
+        output.push(format!("{} = {}", targets.join(", "), values.join(", ")));
+    } else {
+        for (_, param, value, _) in &bindings {
+            if body.names.used.contains(param) {
+                output.push(format!("{} = {}", binding_name(param), value));
+            } else {
+                output.push(value.clone());
+            }
+        }
+    }
+
+    let unit = indent_unit(&body_lines);
+    match &result {
+        Result::Return => {
+            output.extend(body_lines.iter().map(|l| l.to_string()));
+            let ends_with_exit = statements
+                .last()
+                .is_some_and(|s| matches!(s.keyword.as_str(), "return" | "raise"));
+            if !ends_with_exit {
+                output.push("return".to_string());
+            }
+        }
+        _ => {
+            let mut writer = BodyWriter {
+                lines: &body_lines,
+                unit: &unit,
+                result: &result,
+                output: &mut output,
+            };
+            if let Some(first) = statements.first() {
+                writer.copy(0, first.first, "");
+            }
+            if let Err(reason) = writer.write(&statements, "", true) {
+                return refuse(format!("'{}' {}", name, reason));
+            }
+        }
+    }
+
+    let indent = " ".repeat(indentation(
+        &source[source[..statement.0]
+            .rfind('\n')
+            .map(|i| i + 1)
+            .unwrap_or(0)..],
+    ));
+    let text = output
+        .join("\n")
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 || line.trim().is_empty() {
+                line.trim_end().to_string()
+            } else {
+                format!("{}{}", indent, line.trim_end())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.trim().is_empty() {
+        return Ok((statement, "pass".to_string()));
+    }
+    Ok((statement, text))
+}
+
+/// Indentation step used in the body, falling back to four spaces
+fn indent_unit(lines: &[&str]) -> String {
+    lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| indentation(l))
+        .find(|n| *n > 0)
+        .map(|n| " ".repeat(n))
+        .unwrap_or_else(|| "    ".to_string())
+}
+
+/// What happens to the value the body returns
+enum Result {
+    /// The call is a statement: the value is evaluated and dropped
+    Discard,
+    /// `return f(...)`: returns stay as they are
+    Return,
+    /// `x = f(...)`
+    Assign(String),
+}
+
+/// A statement of the (dedented) body with its nested statements
+struct Statement {
+    /// First line
+    first: usize,
+    /// One past the header's last line
+    header_end: usize,
+    /// One past the last line, including nested statements
+    end: usize,
+    indent: usize,
+    keyword: String,
+    /// Text after `return`, for return statements
+    returned: Option<String>,
+    children: Vec<Statement>,
+}
+
+/// Put the `return` of one-line compound statements (`if x: return y`) on
+/// its own line so it can be rewritten
+fn split_one_line_returns(text: &str) -> String {
+    let tokens = code_tokens(text);
+    let mut edits = Vec::new();
+    for (start, end) in logical_lines(&tokens) {
+        let line = &tokens[start..end];
+        let compound = [
+            "if", "elif", "else", "for", "while", "with", "try", "except", "finally",
+        ];
+        if !compound.iter().any(|k| line[0].is(text, k)) {
+            continue;
+        }
+        let Some(colon) = line
+            .iter()
+            .position(|t| t.depth == line[0].depth && t.is(text, ":"))
+        else {
+            continue;
+        };
+        if colon + 1 < line.len() && line[colon + 1..].iter().any(|t| t.is(text, "return")) {
+            let line_start = text[..line[0].start]
+                .rfind('\n')
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            let indent = indentation(&text[line_start..]);
+            let lines: Vec<&str> = text.split('\n').collect();
+            let unit = indent_unit(&lines);
+            edits.push((
+                line[colon].end,
+                line[colon + 1].start,
+                format!("\n{}{}", " ".repeat(indent), unit),
+            ));
+        }
+    }
+    text_with(text, (0, text.len()), &edits)
+}
+
+fn parse_statements(text: &str) -> Vec<Statement> {
+    let tokens = code_tokens(text);
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|s| *s <= offset) - 1;
+    let logical: Vec<(Statement, bool)> = logical_lines(&tokens)
+        .into_iter()
+        .map(|(start, end)| {
+            let first = line_of(tokens[start].start);
+            let last = line_of(
+                tokens[end - 1]
+                    .start
+                    .max(tokens[end - 1].end.saturating_sub(1)),
+            );
+            let keyword = tokens[start].text(text).to_string();
+            let returned = (keyword == "return").then(|| {
+                if end > start + 1 {
+                    text[tokens[start + 1].start..tokens[end - 1].end].to_string()
+                } else {
+                    String::new()
+                }
+            });
+            let compound = tokens[end - 1].is(text, ":");
+            (
+                Statement {
+                    first,
+                    header_end: last + 1,
+                    end: last + 1,
+                    indent: indentation(&text[line_starts[first]..]),
+                    keyword,
+                    returned: returned.filter(|r| !r.is_empty()).or(None),
+                    children: Vec::new(),
+                },
+                compound,
+            )
+        })
+        .collect();
+
+    fn build(
+        logical: &mut std::iter::Peekable<std::vec::IntoIter<(Statement, bool)>>,
+        indent: usize,
+    ) -> Vec<Statement> {
+        let mut statements = Vec::new();
+        while let Some((next, _)) = logical.peek() {
+            if next.indent != indent {
+                break;
+            }
+            let (mut statement, compound) = logical.next().expect("peeked");
+            if compound {
+                if let Some(child_indent) = logical
+                    .peek()
+                    .map(|(s, _)| s.indent)
+                    .filter(|i| *i > indent)
+                {
+                    statement.children = build(logical, child_indent);
+                    if let Some(last) = statement.children.last() {
+                        statement.end = last.end;
+                    }
+                }
+            }
+            statements.push(statement);
+        }
+        statements
+    }
+    let mut iter = logical.into_iter().peekable();
+    build(&mut iter, 0)
+}
+
+fn contains_return(statement: &Statement) -> bool {
+    if matches!(statement.keyword.as_str(), "def" | "class" | "async") {
+        return false;
+    }
+    statement.keyword == "return" || statement.children.iter().any(contains_return)
+}
+
+/// Emits the body with its returns rewritten for the call's context
+struct BodyWriter<'a> {
+    lines: &'a [&'a str],
+    unit: &'a str,
+    result: &'a Result,
+    output: &'a mut Vec<String>,
+}
+
+impl BodyWriter<'_> {
+    fn copy(&mut self, from: usize, to: usize, extra: &str) {
+        for line in &self.lines[from..to] {
+            if line.trim().is_empty() {
+                self.output.push(String::new());
+            } else {
+                self.output.push(format!("{}{}", extra, line));
+            }
+        }
+    }
+
+    /// Lines replacing `return value`; empty when nothing remains to do
+    fn result_lines(&self, value: Option<&str>, indent: &str) -> Vec<String> {
+        match self.result {
+            Result::Assign(target) => vec![format!(
+                "{}{} = {}",
+                indent,
+                target,
+                value.unwrap_or("None")
+            )],
+            Result::Discard => match value {
+                Some(value) if !is_trivial(value, (0, value.len())) => {
+                    vec![format!("{}{}", indent, value)]
+                }
+                _ => Vec::new(),
+            },
+            Result::Return => Vec::new(),
+        }
+    }
+
+    /// Write `statements`, whose end means leaving the function when `last`
+    fn write(
+        &mut self,
+        statements: &[Statement],
+        extra: &str,
+        last: bool,
+    ) -> std::result::Result<(), String> {
+        let mut k = 0;
+        while k < statements.len() {
+            let statement = &statements[k];
+            let indent = format!("{}{}", extra, " ".repeat(statement.indent));
+            if statement.keyword == "return" {
+                let lines = self.result_lines(statement.returned.as_deref(), &indent);
+                self.output.extend(lines);
+                return Ok(());
+            }
+            if statement.keyword == "if" && contains_return(statement) {
+                let chain_end = (k + 1..statements.len())
+                    .find(|j| !matches!(statements[*j].keyword.as_str(), "elif" | "else"))
+                    .unwrap_or(statements.len());
+                return self.write_chain(
+                    &statements[k..chain_end],
+                    &statements[chain_end..],
+                    extra,
+                    last,
+                );
+            }
+            if contains_return(statement) {
+                return Err("returns from inside a loop or a `with`/`try` block, which cannot be rewritten; inline a call written as `return f(...)` instead".to_string());
+            }
+            let next = statements
+                .get(k + 1)
+                .map(|s| s.first)
+                .unwrap_or(statement.end);
+            self.copy(statement.first, next, extra);
+            k += 1;
+        }
+        if last {
+            let indent = format!(
+                "{}{}",
+                extra,
+                statements
+                    .first()
+                    .map(|s| " ".repeat(s.indent))
+                    .unwrap_or_default()
+            );
+            if let Result::Assign(_) = self.result {
+                let lines = self.result_lines(None, &indent);
+                self.output.extend(lines);
+            }
+        }
+        Ok(())
+    }
+
+    /// An `if`/`elif`/`else` chain whose branches end in `return`, followed by `rest`
+    fn write_chain(
+        &mut self,
+        clauses: &[Statement],
+        rest: &[Statement],
+        extra: &str,
+        last: bool,
+    ) -> std::result::Result<(), String> {
+        for clause in clauses {
+            let n = clause.children.len();
+            for (i, child) in clause.children.iter().enumerate() {
+                if contains_return(child) && !(i + 1 == n && child.keyword == "return") {
+                    return Err("returns from a nested block, which cannot be rewritten; inline a call written as `return f(...)` instead".to_string());
+                }
+            }
+        }
+        let all_return = clauses
+            .iter()
+            .all(|c| c.children.last().is_some_and(|s| s.keyword == "return"));
+        let has_else = clauses.last().is_some_and(|c| c.keyword == "else");
+        if !all_return && !rest.is_empty() {
+            return Err("returns from only some branches of an `if` with code after it; inline a call written as `return f(...)` instead".to_string());
+        }
+
+        for clause in clauses {
+            self.copy(clause.first, clause.header_end, extra);
+            let before = self.output.len();
+            let body_indent = clause
+                .children
+                .first()
+                .map(|c| format!("{}{}", extra, " ".repeat(c.indent)))
+                .unwrap_or_default();
+            for (i, child) in clause.children.iter().enumerate() {
+                if child.keyword == "return" {
+                    let lines = self.result_lines(child.returned.as_deref(), &body_indent);
+                    self.output.extend(lines);
+                } else {
+                    let next = clause
+                        .children
+                        .get(i + 1)
+                        .map(|s| s.first)
+                        .unwrap_or(child.end);
+                    self.copy(child.first, next, extra);
+                }
+            }
+            let returns = clause
+                .children
+                .last()
+                .is_some_and(|s| s.keyword == "return");
+            if !returns && last {
+                if let Result::Assign(_) = self.result {
+                    let lines = self.result_lines(None, &body_indent);
+                    self.output.extend(lines);
+                }
+            }
+            if self.output[before..].iter().all(|l| l.trim().is_empty()) {
+                self.output.push(format!("{}pass", body_indent));
+            }
+        }
+
+        if !has_else {
+            let header_indent = format!("{}{}", extra, " ".repeat(clauses[0].indent));
+            let mut nested = BodyWriter {
+                lines: self.lines,
+                unit: self.unit,
+                result: self.result,
+                output: &mut Vec::new(),
+            };
+            let inner_extra = format!("{}{}", extra, self.unit);
+            if rest.is_empty() {
+                if let (Result::Assign(_), true) = (self.result, last) {
+                    let lines =
+                        nested.result_lines(None, &format!("{}{}", header_indent, self.unit));
+                    nested.output.extend(lines);
+                }
+            } else {
+                nested.write(rest, &inner_extra, last)?;
+            }
+            let else_lines = std::mem::take(nested.output);
+            if else_lines.iter().any(|l| !l.trim().is_empty()) {
+                self.output.push(format!("{}else:", header_indent));
+                self.output.extend(else_lines);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mill_foundation::protocol::TextEdit;
+
+    fn apply(source: &str, edits: &[TextEdit]) -> String {
+        let mut result = source.to_string();
+        let mut edits = edits.to_vec();
+        edits.sort_by_key(|e| std::cmp::Reverse((e.location.start_line, e.location.start_column)));
+        for edit in edits {
+            let loc = &edit.location;
+            let start = position_to_offset(&result, loc.start_line, loc.start_column).unwrap();
+            let end = position_to_offset(&result, loc.end_line, loc.end_column).unwrap();
+            result.replace_range(start..end, &edit.new_text);
+        }
+        result
+    }
+
+    fn inline(source: &str, line: u32, character: u32, inline_all: bool) -> String {
+        let plan = plan_inline_function(source, line, character, inline_all, "app.py").unwrap();
+        apply(source, &plan.edits)
+    }
+
+    #[test]
+    fn test_inline_expression_body() {
+        let source = "def double(x):\n    return x * 2\n\n\ndef main():\n    a = 3\n    b = double(a) + 1\n    c = double(a)\n";
+        assert_eq!(
+            inline(source, 6, 8, false),
+            "def double(x):\n    return x * 2\n\n\ndef main():\n    a = 3\n    b = (a * 2) + 1\n    c = double(a)\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_all_binds_arguments_and_removes_private_definition() {
+        let source = "def _area(w, h):\n    total = w * h\n    return total\n\n\ndef main():\n    s = _area(2, h=next_value())\n    print(s)\n";
+        assert_eq!(
+            inline(source, 0, 4, true),
+            "def main():\n    h = next_value()\n    total = 2 * h\n    s = total\n    print(s)\n"
+        );
+    }
+
+    #[test]
+    fn test_early_return_becomes_else_branch() {
+        let source = "def clamp(v):\n    if v < 0:\n        return 0\n    v = min(v, 10)\n    return v\n\n\ndef main():\n    r = clamp(read())\n    use(r)\n";
+        assert_eq!(
+            inline(source, 8, 8, false),
+            "def clamp(v):\n    if v < 0:\n        return 0\n    v = min(v, 10)\n    return v\n\n\ndef main():\n    v = read()\n    if v < 0:\n        r = 0\n    else:\n        v = min(v, 10)\n        r = v\n    use(r)\n"
+        );
+    }
+
+    #[test]
+    fn test_colliding_locals_are_renamed() {
+        let source = "def greet(name):\n    message = f\"Hello {name}\"\n    print(message)\n\n\ndef main(message):\n    greet(user())\n    print(message)\n";
+        assert_eq!(
+            inline(source, 6, 4, false),
+            "def greet(name):\n    message = f\"Hello {name}\"\n    print(message)\n\n\ndef main(message):\n    name = user()\n    message_greet = f\"Hello {name}\"\n    print(message_greet)\n    print(message)\n"
+        );
+    }
+
+    #[test]
+    fn test_return_call_keeps_early_returns() {
+        let source = "def log(msg, level=1):\n    if level > 2:\n        return\n    print(msg)\n\n\ndef main():\n    return log(make())\n";
+        assert_eq!(
+            inline(source, 7, 11, false),
+            "def log(msg, level=1):\n    if level > 2:\n        return\n    print(msg)\n\n\ndef main():\n    msg = make()\n    if 1 > 2:\n        return\n    print(msg)\n    return\n"
+        );
+    }
+
+    #[test]
+    fn test_refuses_recursion_loop_returns_and_methods() {
+        let source = "def fact(n):\n    return 1 if n == 0 else n * fact(n - 1)\n\nfact(3)\n";
+        let err = plan_inline_function(source, 3, 0, false, "app.py").unwrap_err();
+        assert!(err.to_string().contains("recursive"), "{}", err);
+
+        let source = "def first(items):\n    for item in items:\n        if item:\n            return item\n    return None\n\nx = first(values)\n";
+        let err = plan_inline_function(source, 6, 4, false, "app.py").unwrap_err();
+        assert!(err.to_string().contains("loop"), "{}", err);
+
+        let source = "class A:\n    def f(self):\n        return 1\n";
+        let err = plan_inline_function(source, 1, 8, false, "app.py").unwrap_err();
+        assert!(err.to_string().contains("Methods"), "{}", err);
+    }
+}
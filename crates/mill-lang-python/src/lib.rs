@@ -13,14 +13,19 @@
 pub mod consolidation;
 pub mod constants;
 pub mod import_support;
+pub mod inline_function;
 pub mod lsp_installer;
 pub mod manifest;
 pub mod parser;
 pub mod project_factory;
 pub mod refactoring;
 pub mod reference_detector;
+pub mod reorder;
 mod string_literal_support;
+pub mod structural_search;
+pub mod symbol_ops;
 pub mod test_fixtures;
+pub mod transform;
 pub mod workspace_support;
 
 #[cfg(test)]
@@ -166,6 +171,10 @@ impl LanguagePlugin for PythonPlugin {
         parser::analyze_imports(source, file_path)
     }
 
+    fn parser_version(&self) -> &'static str {
+        constants::PARSER_VERSION
+    }
+
     fn test_fixtures(&self) -> Option<mill_plugin_api::LanguageTestFixtures> {
         Some(test_fixtures::python_test_fixtures())
     }
@@ -229,6 +238,7 @@ impl LanguagePlugin for PythonPlugin {
             refactoring_provider: RefactoringProvider,
             import_analyzer: ImportAnalyzer,
             manifest_updater: ManifestUpdater,
+            structural_search_support: StructuralSearchSupport,
         },
         import_support => {
             import_parser: ImportParser,
@@ -343,6 +353,21 @@ impl mill_plugin_api::RefactoringProvider for PythonPlugin {
             .map_err(|e| mill_plugin_api::PluginApiError::internal(e.to_string()))
     }
 
+    fn supports_inline_function(&self) -> bool {
+        true
+    }
+
+    async fn plan_inline_function(
+        &self,
+        source: &str,
+        line: u32,
+        character: u32,
+        inline_all: bool,
+        file_path: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        inline_function::plan_inline_function(source, line, character, inline_all, file_path)
+    }
+
     fn supports_extract_function(&self) -> bool {
         true
     }
@@ -410,6 +435,81 @@ impl mill_plugin_api::RefactoringProvider for PythonPlugin {
         refactoring::plan_extract_constant(source, line, character, constant_name, file_path)
             .map_err(|e| mill_plugin_api::PluginApiError::internal(e.to_string()))
     }
+
+    fn supported_transforms(&self) -> &'static [&'static str] {
+        transform::SUPPORTED_TRANSFORMS
+    }
+
+    async fn plan_transform(
+        &self,
+        source: &str,
+        kind: &str,
+        line: u32,
+        character: u32,
+        file_path: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        transform::plan_transform(source, kind, line, character, file_path)
+    }
+
+    fn supports_reorder(&self, kind: &str) -> bool {
+        reorder::SUPPORTED_REORDERS.contains(&kind)
+    }
+
+    async fn plan_reorder(
+        &self,
+        source: &str,
+        kind: &str,
+        line: u32,
+        character: u32,
+        new_order: &[String],
+        file_path: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        reorder::plan_reorder(source, kind, line, character, new_order, file_path)
+    }
+
+    fn supports_symbol_move(&self) -> bool {
+        true
+    }
+
+    async fn plan_symbol_move(
+        &self,
+        source: &str,
+        symbol_line: u32,
+        symbol_col: u32,
+        file_path: &str,
+        destination: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        symbol_ops::plan_symbol_move(source, symbol_line, symbol_col, file_path, destination).await
+    }
+
+    fn supports_symbol_delete(&self) -> bool {
+        true
+    }
+
+    async fn plan_symbol_delete(
+        &self,
+        source: &str,
+        symbol_line: u32,
+        symbol_col: u32,
+        file_path: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        symbol_ops::plan_symbol_delete(source, symbol_line, symbol_col, file_path)
+    }
+}
+
+#[async_trait]
+impl mill_plugin_api::StructuralSearchSupport for PythonPlugin {
+    async fn tokenize(&self, source: &str) -> PluginResult<Vec<std::ops::Range<usize>>> {
+        structural_search::tokenize(source).await
+    }
+
+    async fn syntax_index(
+        &self,
+        source: &str,
+        _file_path: &Path,
+    ) -> PluginResult<mill_plugin_api::SyntaxIndex> {
+        structural_search::syntax_index(source).await
+    }
 }
 
 impl mill_plugin_api::ImportAnalyzer for PythonPlugin {
@@ -0,0 +1,535 @@
+//! Python reorder refactorings
+//!
+//! Supported reorder kinds:
+//! - `parameters`: parameters of a `def` (a leading `self`/`cls` stays first)
+//! - `fields`: class-level attribute declarations (dataclass fields, etc.)
+//! - `variants`: members of an `Enum` subclass
+//! - `imports`: a contiguous block of `import`/`from ... import` statements
+//!
+//! Like the rest of this plugin, the analysis is indentation based so it works
+//! without a Python interpreter being available.
+
+use mill_foundation::protocol::EditPlan;
+use mill_lang_common::refactoring::edit_plan_builder::EditPlanBuilder;
+use mill_lang_common::refactoring::reorder::{
+    reorder_items_edit, resolve_permutation, split_delimited, ReorderItem,
+};
+use mill_plugin_api::{PluginApiError, PluginResult};
+
+/// Reorder kinds implemented by the Python plugin
+pub const SUPPORTED_REORDERS: &[&str] = &["parameters", "fields", "variants", "imports"];
+
+/// Plan a reorder for Python
+pub fn plan_reorder(
+    source: &str,
+    kind: &str,
+    line: u32,
+    _character: u32,
+    new_order: &[String],
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let lines = SourceLines::new(source);
+    if line as usize >= lines.len() {
+        return Err(PluginApiError::invalid_input("Line number out of bounds"));
+    }
+
+    let target = match kind {
+        "parameters" => find_parameters(&lines, line as usize)?,
+        "fields" => find_class_members(&lines, line as usize, false)?,
+        "variants" => find_class_members(&lines, line as usize, true)?,
+        "imports" => find_imports(&lines, line as usize)?,
+        other => {
+            return Err(PluginApiError::not_supported(format!(
+                "Python reorder '{}' (supported: {})",
+                other,
+                SUPPORTED_REORDERS.join(", ")
+            )))
+        }
+    };
+
+    let current: Vec<String> = target.items.iter().map(|i| i.name.clone()).collect();
+    let permutation = if new_order.is_empty() && kind == "imports" {
+        let mut permutation: Vec<usize> = (0..current.len()).collect();
+        permutation.sort_by(|a, b| current[*a].cmp(&current[*b]));
+        permutation
+    } else {
+        resolve_permutation(&current, new_order).map_err(PluginApiError::invalid_input)?
+    };
+
+    if let Some(defaults) = &target.has_default {
+        // Python rejects a parameter/field without a default after one with a default
+        let mut seen_default = None;
+        for &index in &permutation {
+            if defaults[index] {
+                seen_default = Some(index);
+            } else if let Some(previous) = seen_default {
+                return Err(PluginApiError::invalid_input(format!(
+                    "'{}' has no default value and cannot follow '{}', which has one",
+                    current[index], current[previous]
+                )));
+            }
+        }
+    }
+
+    let edits = reorder_items_edit(
+        source,
+        &target.items,
+        &permutation,
+        &format!("Reorder {} of '{}'", kind, target.owner),
+    )
+    .into_iter()
+    .collect::<Vec<_>>();
+
+    let mut intent_args = serde_json::json!({
+        "kind": kind,
+        "owner": target.owner,
+        "permutation": permutation,
+        "notes": target.notes,
+    });
+    if let Some((name_line, name_character)) = target.name_position {
+        intent_args["function_name"] = serde_json::json!(target.owner);
+        intent_args["name_line"] = serde_json::json!(name_line);
+        intent_args["name_character"] = serde_json::json!(name_character);
+    }
+
+    Ok(EditPlanBuilder::new(file_path, kind)
+        .with_edits(edits)
+        .with_syntax_validation("Verify Python syntax is valid after reorder")
+        .with_intent_args(intent_args)
+        .with_complexity(2)
+        .with_impact_area("reorder")
+        .build())
+}
+
+/// Items found at the target position
+struct ReorderTarget {
+    owner: String,
+    items: Vec<ReorderItem>,
+    /// Whether each item has a default value, when order is constrained by defaults
+    has_default: Option<Vec<bool>>,
+    /// Position of the function name (parameters only)
+    name_position: Option<(u32, u32)>,
+    notes: Vec<String>,
+}
+
+/// Source split into lines with the byte offset of each line start
+struct SourceLines<'a> {
+    source: &'a str,
+    lines: Vec<&'a str>,
+    starts: Vec<usize>,
+}
+
+impl<'a> SourceLines<'a> {
+    fn new(source: &'a str) -> Self {
+        let mut starts = Vec::new();
+        let mut offset = 0;
+        let lines: Vec<&str> = source
+            .split('\n')
+            .map(|l| {
+                starts.push(offset);
+                offset += l.len() + 1;
+                l.strip_suffix('\r').unwrap_or(l)
+            })
+            .collect();
+        Self {
+            source,
+            lines,
+            starts,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Byte range of a line's content, excluding leading indentation and trailing whitespace
+    fn content_range(&self, line: usize) -> (usize, usize) {
+        let text = self.lines[line];
+        let start = self.starts[line] + indentation(text);
+        (start, self.starts[line] + text.trim_end().len())
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_blank_or_comment(line: &str) -> bool {
+    let t = line.trim();
+    t.is_empty() || t.starts_with('#')
+}
+
+/// Locate the header line for the target: the line itself, the header following
+/// decorators, or the nearest enclosing header above it.
+fn find_header(lines: &[&str], line: usize, is_header: fn(&str) -> bool) -> Option<usize> {
+    let trimmed = lines[line].trim_start();
+    if is_header(trimmed) {
+        return Some(line);
+    }
+
+    if trimmed.starts_with('@') {
+        return (line + 1..lines.len()).find(|&i| is_header(lines[i].trim_start()));
+    }
+
+    let mut max_indent = indentation(lines[line]);
+    for i in (0..line).rev() {
+        let text = lines[i];
+        if text.trim().is_empty() {
+            continue;
+        }
+        let indent = indentation(text);
+        if indent < max_indent {
+            if is_header(text.trim_start()) {
+                return Some(i);
+            }
+            max_indent = indent;
+        }
+    }
+    None
+}
+
+fn is_def(line: &str) -> bool {
+    line.starts_with("def ") || line.starts_with("async def ")
+}
+
+fn is_class(line: &str) -> bool {
+    line.starts_with("class ")
+}
+
+/// Identifier at the start of `text`
+fn leading_identifier(text: &str) -> &str {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+// ============================================================================
+// parameters
+// ============================================================================
+
+fn find_parameters(lines: &SourceLines, line: usize) -> PluginResult<ReorderTarget> {
+    let def_line = find_header(&lines.lines, line, is_def).ok_or_else(|| {
+        PluginApiError::invalid_input(format!("No function definition found at line {}", line + 1))
+    })?;
+
+    let text = lines.lines[def_line];
+    let def_offset = text.find("def ").unwrap_or(0) + "def ".len();
+    let name = leading_identifier(text[def_offset..].trim_start()).to_string();
+    let name_column = text[..def_offset].chars().count()
+        + (text[def_offset..].len() - text[def_offset..].trim_start().len());
+
+    let open = lines.starts[def_line]
+        + text
+            .find('(')
+            .ok_or_else(|| PluginApiError::parse(format!("Malformed definition of '{}'", name)))?;
+    let (params, close) = split_delimited(lines.source, open).ok_or_else(|| {
+        PluginApiError::parse(format!("Unbalanced parameter list for '{}'", name))
+    })?;
+    if lines.source[open..close].contains('#') {
+        return Err(PluginApiError::invalid_input(format!(
+            "Parameter list of '{}' contains comments, which cannot be reordered safely",
+            name
+        )));
+    }
+
+    let mut items = Vec::new();
+    let mut has_default = Vec::new();
+    for (index, param) in params.iter().enumerate() {
+        let text = param.name.as_str();
+        if text == "*" || text == "/" || text.starts_with('*') {
+            return Err(PluginApiError::invalid_input(format!(
+                "'{}' uses `*`, `/`, *args or **kwargs; only plain positional parameters can be reordered",
+                name
+            )));
+        }
+        let param_name = leading_identifier(text).to_string();
+        if index == 0 && (param_name == "self" || param_name == "cls") {
+            continue;
+        }
+        has_default.push(text.contains('='));
+        items.push(ReorderItem::new(param_name, param.start, param.end));
+    }
+
+    Ok(ReorderTarget {
+        owner: name,
+        items,
+        has_default: Some(has_default),
+        name_position: Some((def_line as u32, name_column as u32)),
+        notes: vec![
+            "Calls passing arguments by keyword are unaffected; calls mixing keyword, *args or **kwargs arguments are skipped".to_string(),
+        ],
+    })
+}
+
+// ============================================================================
+// fields / variants
+// ============================================================================
+
+fn find_class_members(
+    lines: &SourceLines,
+    line: usize,
+    enum_members: bool,
+) -> PluginResult<ReorderTarget> {
+    let class_line = find_header(&lines.lines, line, is_class).ok_or_else(|| {
+        PluginApiError::invalid_input(format!("No class definition found at line {}", line + 1))
+    })?;
+
+    let header = lines.lines[class_line].trim_start();
+    let owner = leading_identifier(&header["class ".len()..]).to_string();
+    let bases = header.find('(').map(|i| &header[i..]).unwrap_or_default();
+    let is_enum = bases.contains("Enum") || bases.contains("Flag");
+    if enum_members && !is_enum {
+        return Err(PluginApiError::invalid_input(format!(
+            "'{}' is not an Enum subclass",
+            owner
+        )));
+    }
+
+    let mut is_dataclass = false;
+    for i in (0..class_line).rev() {
+        let t = lines.lines[i].trim_start();
+        if !t.starts_with('@') {
+            break;
+        }
+        is_dataclass |= t.contains("dataclass") || t.contains("attr.s") || t.contains("define");
+    }
+
+    let class_indent = indentation(lines.lines[class_line]);
+    let mut body_indent = None;
+    let mut items = Vec::new();
+    let mut has_default = Vec::new();
+
+    for i in class_line + 1..lines.len() {
+        let text = lines.lines[i];
+        if is_blank_or_comment(text) {
+            continue;
+        }
+        let indent = indentation(text);
+        if indent <= class_indent {
+            break;
+        }
+        let body_indent = *body_indent.get_or_insert(indent);
+        if indent != body_indent {
+            continue;
+        }
+
+        let trimmed = text.trim_start();
+        let name = leading_identifier(trimmed);
+        let rest = trimmed[name.len()..].trim_start();
+        let is_member = !name.is_empty()
+            && !matches!(
+                name,
+                "def" | "async" | "class" | "pass" | "return" | "if" | "for"
+            )
+            && (rest.starts_with(':') || (rest.starts_with('=') && !rest.starts_with("==")));
+        if !is_member || (enum_members && name.starts_with('_')) {
+            continue;
+        }
+        if trimmed.ends_with('(')
+            || trimmed.ends_with('[')
+            || trimmed.ends_with('{')
+            || trimmed.ends_with('\\')
+        {
+            return Err(PluginApiError::invalid_input(format!(
+                "'{}' spans multiple lines; only single-line members can be reordered",
+                name
+            )));
+        }
+
+        let (start, end) = lines.content_range(i);
+        has_default.push(rest.contains('='));
+        items.push(ReorderItem::new(name, start, end));
+    }
+
+    if items.is_empty() {
+        return Err(PluginApiError::invalid_input(format!(
+            "'{}' has no {} to reorder",
+            owner,
+            if enum_members { "members" } else { "fields" }
+        )));
+    }
+
+    let mut notes = Vec::new();
+    if enum_members {
+        notes.push(format!(
+            "Members of '{}' using auto() get new values, and iteration order changes",
+            owner
+        ));
+    } else if is_dataclass {
+        notes.push(format!(
+            "'{}' is a dataclass; positional constructor arguments follow field order",
+            owner
+        ));
+    }
+
+    Ok(ReorderTarget {
+        owner,
+        items,
+        has_default: (is_dataclass && !enum_members).then_some(has_default),
+        name_position: None,
+        notes,
+    })
+}
+
+// ============================================================================
+// imports
+// ============================================================================
+
+fn is_import(line: &str) -> bool {
+    line.starts_with("import ") || line.starts_with("from ")
+}
+
+/// Module an import statement refers to
+fn import_name(statement: &str) -> String {
+    statement
+        .trim_start_matches("from ")
+        .trim_start_matches("import ")
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(',')
+        .to_string()
+}
+
+fn find_imports(lines: &SourceLines, line: usize) -> PluginResult<ReorderTarget> {
+    let mut blocks: Vec<Vec<ReorderItem>> = Vec::new();
+    let mut current: Vec<ReorderItem> = Vec::new();
+    let mut block_lines: Vec<(usize, usize)> = Vec::new();
+    let mut current_lines = (0, 0);
+    let mut indent = None;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let text = lines.lines[i];
+        let trimmed = text.trim_start();
+        let same_indent = indent.is_none_or(|n| n == indentation(text));
+
+        if is_import(trimmed) && same_indent {
+            // Parenthesized imports continue until the closing parenthesis
+            let mut last = i;
+            if trimmed.contains('(') && !trimmed.contains(')') {
+                while last + 1 < lines.len() && !lines.lines[last].contains(')') {
+                    last += 1;
+                }
+            } else {
+                while lines.lines[last].trim_end().ends_with('\\') && last + 1 < lines.len() {
+                    last += 1;
+                }
+            }
+            if current.is_empty() {
+                current_lines.0 = i;
+                indent = Some(indentation(text));
+            }
+            current_lines.1 = last;
+            let (start, _) = lines.content_range(i);
+            let (_, end) = lines.content_range(last);
+            current.push(ReorderItem::new(import_name(trimmed), start, end));
+            i = last + 1;
+            continue;
+        }
+
+        // Any other line, including a blank one, ends the current import group
+        if !current.is_empty() {
+            blocks.push(std::mem::take(&mut current));
+            block_lines.push(current_lines);
+        }
+        indent = None;
+        i += 1;
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+        block_lines.push(current_lines);
+    }
+
+    let index = block_lines
+        .iter()
+        .position(|(start, end)| *start <= line && line <= *end)
+        .ok_or_else(|| {
+            PluginApiError::invalid_input(format!("No import block found at line {}", line + 1))
+        })?;
+
+    Ok(ReorderTarget {
+        owner: "import block".to_string(),
+        items: blocks.swap_remove(index),
+        has_default: None,
+        name_position: None,
+        notes: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mill_foundation::protocol::TextEdit;
+    use mill_lang_common::position_to_offset;
+
+    fn apply(source: &str, edits: &[TextEdit]) -> String {
+        let mut result = source.to_string();
+        for edit in edits {
+            let loc = &edit.location;
+            let start = position_to_offset(&result, loc.start_line, loc.start_column).unwrap();
+            let end = position_to_offset(&result, loc.end_line, loc.end_column).unwrap();
+            result.replace_range(start..end, &edit.new_text);
+        }
+        result
+    }
+
+    fn order(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reorder_method_parameters() {
+        let source = "class A:\n    def run(self, a, b=1):\n        pass\n";
+        let plan = plan_reorder(source, "parameters", 2, 0, &order(&["a", "b"]), "a.py").unwrap();
+        assert!(plan.edits.is_empty());
+
+        let source = "class A:\n    def run(self, a, b):\n        pass\n";
+        let plan = plan_reorder(source, "parameters", 1, 0, &order(&["b", "a"]), "a.py").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "class A:\n    def run(self, b, a):\n        pass\n"
+        );
+        assert_eq!(plan.metadata.intent_arguments["name_character"], 8);
+    }
+
+    #[test]
+    fn test_reorder_parameters_rejects_default_before_required() {
+        let source = "def f(a, b=1):\n    pass\n";
+        assert!(plan_reorder(source, "parameters", 0, 0, &order(&["b", "a"]), "a.py").is_err());
+    }
+
+    #[test]
+    fn test_reorder_dataclass_fields() {
+        let source =
+            "@dataclass\nclass P:\n    x: int\n    y: str\n\n    def f(self):\n        pass\n";
+        let plan = plan_reorder(source, "fields", 2, 0, &order(&["y", "x"]), "a.py").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "@dataclass\nclass P:\n    y: str\n    x: int\n\n    def f(self):\n        pass\n"
+        );
+    }
+
+    #[test]
+    fn test_reorder_enum_members() {
+        let source = "class Color(Enum):\n    RED = 1\n    GREEN = 2\n";
+        let plan =
+            plan_reorder(source, "variants", 1, 0, &order(&["GREEN", "RED"]), "a.py").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "class Color(Enum):\n    GREEN = 2\n    RED = 1\n"
+        );
+        assert!(plan_reorder("class A:\n    x = 1\n", "variants", 0, 0, &[], "a.py").is_err());
+    }
+
+    #[test]
+    fn test_reorder_imports_sorts_block() {
+        let source = "import sys\nfrom typing import (\n    Any,\n)\nimport os\n\nimport zlib\n";
+        let plan = plan_reorder(source, "imports", 0, 0, &[], "a.py").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "import os\nimport sys\nfrom typing import (\n    Any,\n)\n\nimport zlib\n"
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! Tokens and syntax nodes for structural search, from Python's `tokenize` and `ast` modules
+//!
+//! This runs the embedded AST tool in a `python3` subprocess. Nodes are every AST node with a source range.
+
+use mill_lang_common::{run_ast_tool_async, SubprocessAstTool};
+use mill_plugin_api::{PluginResult, SyntaxIndex};
+use serde::Deserialize;
+use std::ops::Range;
+
+const AST_TOOL_PY: &str = include_str!("../resources/ast_tool.py");
+
+/// Byte ranges as reported by the AST tool
+#[derive(Deserialize)]
+struct RawSyntaxIndex {
+    tokens: Vec<(usize, usize)>,
+    nodes: Vec<(usize, usize)>,
+}
+
+/// Split Python code into tokens
+pub(crate) async fn tokenize(source: &str) -> PluginResult<Vec<Range<usize>>> {
+    Ok(run(source, "tokens").await?.tokens)
+}
+
+/// Tokenize and parse a Python file
+pub(crate) async fn syntax_index(source: &str) -> PluginResult<SyntaxIndex> {
+    run(source, "syntax-index").await
+}
+
+async fn run(source: &str, command: &str) -> PluginResult<SyntaxIndex> {
+    let tool = SubprocessAstTool::new("python3")
+        .with_embedded_str(AST_TOOL_PY)
+        .with_temp_filename("ast_tool.py")
+        .with_args(vec![command.to_string()]);
+    let raw: RawSyntaxIndex = run_ast_tool_async(tool, source).await?;
+    let to_ranges = |ranges: Vec<(usize, usize)>| {
+        ranges
+            .into_iter()
+            .map(|(start, end)| start..end)
+            .collect::<Vec<_>>()
+    };
+    Ok(SyntaxIndex {
+        tokens: to_ranges(raw.tokens),
+        nodes: to_ranges(raw.nodes),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_syntax_index_byte_offsets() {
+        let source = "s = \"é\"  # note\nx = foo(a.b)\n";
+        let index = syntax_index(source).await.unwrap();
+        let tokens: Vec<&str> = index.tokens.iter().map(|r| &source[r.clone()]).collect();
+        assert_eq!(
+            tokens,
+            vec!["s", "=", "\"é\"", "x", "=", "foo", "(", "a", ".", "b", ")"]
+        );
+        let nodes: Vec<&str> = index.nodes.iter().map(|r| &source[r.clone()]).collect();
+        assert!(nodes.contains(&"foo(a.b)"));
+        assert!(nodes.contains(&"a.b"));
+    }
+}
@@ -0,0 +1,1013 @@
+//! Symbol-level move and delete for Python
+//!
+//! A symbol is a `def`, `async def` or `class` (with its decorators) or a
+//! top-level assignment. Moving a symbol copies the imports its body needs to
+//! the destination module and rewrites `from x import y` statements (and
+//! `x.y` attribute access through `import x`) across the project.
+//!
+//! Like the rest of this plugin, the analysis is indentation based so it works
+//! without a Python interpreter being available.
+
+use crate::import_support::path_to_python_module;
+use mill_foundation::protocol::EditPlan;
+use mill_lang_common::refactoring::edit_plan_builder::EditPlanBuilder;
+use mill_lang_common::refactoring::file_edits::FileEdits;
+use mill_plugin_api::{PluginApiError, PluginResult};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Directories never scanned for callers
+const SKIPPED_DIRS: &[&str] = &[
+    ".git",
+    ".venv",
+    "venv",
+    "__pycache__",
+    ".mypy_cache",
+    ".pytest_cache",
+    "dist",
+    "build",
+    "node_modules",
+    ".tox",
+];
+
+/// Plan moving the top-level symbol at `symbol_line` to the module `destination`
+pub async fn plan_symbol_move(
+    source: &str,
+    symbol_line: u32,
+    _symbol_col: u32,
+    file_path: &str,
+    destination: &str,
+) -> PluginResult<EditPlan> {
+    let source_path = Path::new(file_path);
+    let dest_path = Path::new(destination);
+    if source_path == dest_path {
+        return Err(PluginApiError::invalid_input(
+            "Destination is the file that already contains the symbol",
+        ));
+    }
+
+    let root = find_project_root(source_path);
+    let dest_source = tokio::fs::read_to_string(dest_path).await.ok();
+    let mut callers = BTreeMap::new();
+    for path in find_python_files(&root).await {
+        if path != source_path && path != dest_path {
+            if let Ok(content) = tokio::fs::read_to_string(&path).await {
+                callers.insert(path, content);
+            }
+        }
+    }
+
+    plan_move(
+        source,
+        symbol_line as usize,
+        source_path,
+        dest_path,
+        dest_source.as_deref(),
+        &root,
+        &callers,
+    )
+}
+
+/// Plan deleting the innermost `def`/`class` (or top-level assignment) at `symbol_line`
+pub fn plan_symbol_delete(
+    source: &str,
+    symbol_line: u32,
+    _symbol_col: u32,
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let lines = SourceLines::new(source);
+    let block = find_block(&lines, symbol_line as usize)?;
+
+    let mut edits = FileEdits::new(source, None);
+    let (start, end) = removal_range(&lines, &block);
+    match enclosing_body_left_empty(&lines, &block) {
+        true => edits.replace(start, end, format!("{}pass\n", " ".repeat(block.indent))),
+        false => edits.delete(start, end),
+    }
+
+    Ok(EditPlanBuilder::new(file_path, "delete_symbol")
+        .with_edits(edits.into_text_edits(&format!("Delete symbol '{}'", block.name)))
+        .with_syntax_validation("Verify Python syntax is valid after deletion")
+        .with_intent_args(serde_json::json!({
+            "symbol": block.name,
+        }))
+        .with_complexity(2)
+        .with_impact_area("symbol_deletion")
+        .build())
+}
+
+fn plan_move(
+    source: &str,
+    symbol_line: usize,
+    source_path: &Path,
+    dest_path: &Path,
+    dest_source: Option<&str>,
+    root: &Path,
+    callers: &BTreeMap<PathBuf, String>,
+) -> PluginResult<EditPlan> {
+    let lines = SourceLines::new(source);
+    let block = find_block(&lines, symbol_line)?;
+    if block.indent > 0 {
+        return Err(PluginApiError::invalid_input(format!(
+            "'{}' is not a top-level symbol; only module-level definitions can be moved",
+            block.name
+        )));
+    }
+
+    let file_path = source_path.to_string_lossy().to_string();
+    let destination = dest_path.to_string_lossy().to_string();
+    let source_module = module_name(source_path, root);
+    let dest_module = module_name(dest_path, root);
+
+    debug!(
+        symbol = %block.name,
+        kind = block.kind,
+        from = %source_module,
+        to = %dest_module,
+        "Planning Python symbol move"
+    );
+
+    let dest_lines = dest_source.map(SourceLines::new);
+    let dest_imports = dest_lines
+        .as_ref()
+        .map(|l| parse_imports(l, true))
+        .unwrap_or_default();
+    let mut dest_names: HashSet<String> = dest_lines
+        .as_ref()
+        .map(top_level_definitions)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    if dest_names.contains(&block.name) {
+        return Err(PluginApiError::invalid_input(format!(
+            "{} already defines '{}'",
+            destination, block.name
+        )));
+    }
+    for import in &dest_imports {
+        let module = import.resolved_module(&dest_module, is_package(dest_path));
+        for name in &import.names {
+            // The destination's own import of the moved symbol goes away below
+            if module.as_deref() != Some(source_module.as_str()) || name.name != block.name {
+                dest_names.insert(name.bound(import.module.is_some()).to_string());
+            }
+        }
+    }
+
+    let (block_start, block_end) = lines.byte_range(block.start, block.end);
+    let block_text = source[block_start..block_end].trim_end().to_string();
+    let idents = identifiers(&block_text);
+
+    // Imports the moved code needs, made absolute
+    let mut carried: Vec<String> = Vec::new();
+    for import in parse_imports(&lines, true) {
+        let module = import.resolved_module(&source_module, is_package(source_path));
+        if module.as_deref() == Some("__future__") {
+            continue;
+        }
+        let names: Vec<&ImportedName> = import
+            .names
+            .iter()
+            .filter(|n| {
+                let bound = n.bound(import.module.is_some());
+                n.name == "*" || (idents.contains(bound) && !dest_names.contains(bound))
+            })
+            .collect();
+        if names.is_empty() || module.as_deref() == Some(dest_module.as_str()) {
+            continue;
+        }
+        let statement = match &module {
+            Some(module) => render_from(module, names.iter().copied()),
+            None => render_import(names.iter().copied()),
+        };
+        carried.push(statement);
+    }
+    // Definitions that stay behind in the source module
+    let left_behind: Vec<String> = top_level_definitions(&lines)
+        .into_iter()
+        .filter(|(name, line)| {
+            *line != block.header
+                && *name != block.name
+                && idents.contains(name.as_str())
+                && !dest_names.contains(name)
+        })
+        .map(|(name, _)| name)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if !left_behind.is_empty() {
+        carried.push(format!(
+            "from {} import {}",
+            source_module,
+            left_behind.join(", ")
+        ));
+    }
+    let mut seen = HashSet::new();
+    carried.retain(|statement| seen.insert(statement.clone()));
+
+    let mut edits = Vec::new();
+    let description = format!("Move '{}' to {}", block.name, destination);
+
+    // Source file: remove the block and import the symbol if it is still used
+    let mut source_edits = FileEdits::new(source, None);
+    let (start, end) = removal_range(&lines, &block);
+    source_edits.delete(start, end);
+    let remaining = source_edits.apply();
+    if identifiers(&strip_import_lines(&remaining)).contains(block.name.as_str()) {
+        source_edits.insert(
+            import_insertion_offset(&lines),
+            format!("from {} import {}\n", dest_module, block.name),
+        );
+    }
+    edits.extend(source_edits.into_text_edits(&description));
+
+    // Destination file: add imports and append the block
+    let imports_text: String = carried.iter().map(|s| format!("{}\n", s)).collect();
+    match (dest_source, &dest_lines) {
+        (Some(dest_source), Some(dest_lines)) => {
+            let mut dest_edits = FileEdits::new(dest_source, Some(destination.clone()));
+            for import in &dest_imports {
+                let module = import.resolved_module(&dest_module, is_package(dest_path));
+                if module.as_deref() == Some(source_module.as_str()) {
+                    remove_imported_name(dest_lines, import, &block.name, None, &mut dest_edits);
+                }
+            }
+            if !imports_text.is_empty() {
+                dest_edits.insert(import_insertion_offset(dest_lines), imports_text);
+            }
+            let trailing = dest_source.len() - dest_source.trim_end_matches('\n').len();
+            let separator = if dest_source.trim().is_empty() {
+                String::new()
+            } else {
+                "\n".repeat(3usize.saturating_sub(trailing))
+            };
+            dest_edits.insert(dest_source.len(), format!("{}{}\n", separator, block_text));
+            edits.extend(dest_edits.into_text_edits(&description));
+        }
+        _ => {
+            let mut dest_edits = FileEdits::new("", Some(destination.clone()));
+            let separator = if imports_text.is_empty() { "" } else { "\n\n" };
+            dest_edits.insert(0, format!("{}{}{}\n", imports_text, separator, block_text));
+            edits.extend(dest_edits.into_text_edits(&description));
+        }
+    }
+
+    // Callers: rewrite `from source import name` and `source.name`
+    for (caller, caller_source) in callers {
+        if !caller_source.contains(&block.name) {
+            continue;
+        }
+        let caller_lines = SourceLines::new(caller_source);
+        let caller_module = module_name(caller, root);
+        let mut caller_edits =
+            FileEdits::new(caller_source, Some(caller.to_string_lossy().to_string()));
+        for import in parse_imports(&caller_lines, false) {
+            let module = import.resolved_module(&caller_module, is_package(caller));
+            match &import.module {
+                Some(_) if module.as_deref() == Some(source_module.as_str()) => {
+                    remove_imported_name(
+                        &caller_lines,
+                        &import,
+                        &block.name,
+                        Some(&dest_module),
+                        &mut caller_edits,
+                    );
+                }
+                Some(_) => {}
+                None => rewrite_attribute_access(
+                    &caller_lines,
+                    &import,
+                    &source_module,
+                    &dest_module,
+                    &block.name,
+                    &mut caller_edits,
+                )?,
+            }
+        }
+        if !caller_edits.is_empty() {
+            edits.extend(
+                caller_edits.into_text_edits(&format!("Update imports of '{}'", block.name)),
+            );
+        }
+    }
+
+    Ok(EditPlanBuilder::new(&file_path, "move_symbol")
+        .with_edits(edits)
+        .with_syntax_validation("Verify Python syntax is valid after move")
+        .with_intent_args(serde_json::json!({
+            "symbol": block.name,
+            "kind": block.kind,
+            "destination": destination,
+            "carried_imports": carried,
+        }))
+        .with_complexity(3)
+        .with_impact_area("symbol_move")
+        .build())
+}
+
+// ============================================================================
+// Source model
+// ============================================================================
+
+/// Source split into lines with the byte offset of each line start
+pub(crate) struct SourceLines<'a> {
+    pub(crate) source: &'a str,
+    pub(crate) lines: Vec<&'a str>,
+    pub(crate) starts: Vec<usize>,
+}
+
+impl<'a> SourceLines<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        let mut starts = Vec::new();
+        let mut offset = 0;
+        let lines: Vec<&str> = source
+            .split('\n')
+            .map(|l| {
+                starts.push(offset);
+                offset += l.len() + 1;
+                l.strip_suffix('\r').unwrap_or(l)
+            })
+            .collect();
+        Self {
+            source,
+            lines,
+            starts,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Byte range of lines `start..end`, including the final newline
+    pub(crate) fn byte_range(&self, start: usize, end: usize) -> (usize, usize) {
+        let end = self
+            .starts
+            .get(end)
+            .copied()
+            .unwrap_or(self.source.len())
+            .min(self.source.len());
+        (self.starts[start], end)
+    }
+}
+
+/// A definition spanning whole lines
+pub(crate) struct Block {
+    pub(crate) name: String,
+    pub(crate) kind: &'static str,
+    pub(crate) indent: usize,
+    /// First line, including decorators
+    pub(crate) start: usize,
+    /// The `def`/`class`/assignment line
+    pub(crate) header: usize,
+    /// One past the last line of the body
+    pub(crate) end: usize,
+}
+
+pub(crate) fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+pub(crate) fn is_blank_or_comment(line: &str) -> bool {
+    let t = line.trim();
+    t.is_empty() || t.starts_with('#')
+}
+
+fn is_closing(line: &str) -> bool {
+    line.trim_start().starts_with([')', ']', '}'])
+}
+
+pub(crate) fn definition_header(line: &str) -> Option<(String, &'static str)> {
+    let t = line.trim_start();
+    let (rest, kind) = if let Some(rest) = t.strip_prefix("async def ") {
+        (rest, "function")
+    } else if let Some(rest) = t.strip_prefix("def ") {
+        (rest, "function")
+    } else if let Some(rest) = t.strip_prefix("class ") {
+        (rest, "class")
+    } else {
+        return None;
+    };
+    let name = leading_identifier(rest.trim_start());
+    (!name.is_empty()).then(|| (name.to_string(), kind))
+}
+
+fn assignment_header(line: &str) -> Option<String> {
+    if indentation(line) > 0 {
+        return None;
+    }
+    let name = leading_identifier(line);
+    if name.is_empty() || matches!(name, "import" | "from" | "if" | "for" | "while" | "with") {
+        return None;
+    }
+    let rest = line[name.len()..].trim_start();
+    let is_assignment = (rest.starts_with('=') && !rest.starts_with("=="))
+        || (rest.starts_with(':') && rest.contains('='));
+    is_assignment.then(|| name.to_string())
+}
+
+fn leading_identifier(text: &str) -> &str {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+/// One past the last line belonging to the statement at `header`
+pub(crate) fn block_end(lines: &SourceLines, header: usize, indent: usize) -> usize {
+    let mut end = header + 1;
+    while end < lines.len() {
+        let line = lines.lines[end];
+        if !is_blank_or_comment(line) && indentation(line) <= indent && !is_closing(line) {
+            break;
+        }
+        end += 1;
+    }
+    while end > header + 1 && is_blank_or_comment(lines.lines[end - 1]) {
+        end -= 1;
+    }
+    end
+}
+
+/// Innermost definition containing `line`
+pub(crate) fn find_block(lines: &SourceLines, line: usize) -> PluginResult<Block> {
+    if line >= lines.len() {
+        return Err(PluginApiError::invalid_input("Line number out of bounds"));
+    }
+
+    // A decorator line targets the definition it decorates
+    let mut target = line;
+    while target + 1 < lines.len() && lines.lines[target].trim_start().starts_with('@') {
+        let indent = indentation(lines.lines[target]);
+        target += 1;
+        while target < lines.len()
+            && (indentation(lines.lines[target]) > indent || is_closing(lines.lines[target]))
+        {
+            target += 1;
+        }
+    }
+
+    for header in (0..=target.min(lines.len() - 1)).rev() {
+        let text = lines.lines[header];
+        let (name, kind) = match definition_header(text) {
+            Some(found) => found,
+            None => match assignment_header(text) {
+                Some(name) => (name, "variable"),
+                None => continue,
+            },
+        };
+        let indent = indentation(text);
+        let end = block_end(lines, header, indent);
+        if end <= target {
+            if indent == 0 {
+                break;
+            }
+            continue;
+        }
+
+        let mut start = header;
+        while start > 0 {
+            let previous = lines.lines[start - 1];
+            if previous.trim_start().starts_with('@') && indentation(previous) == indent {
+                start -= 1;
+            } else {
+                break;
+            }
+        }
+        return Ok(Block {
+            name,
+            kind,
+            indent,
+            start,
+            header,
+            end,
+        });
+    }
+
+    Err(PluginApiError::invalid_input(format!(
+        "No function, class or assignment found at line {}",
+        line + 1
+    )))
+}
+
+/// Byte range removing a block together with the blank lines separating it
+pub(crate) fn removal_range(lines: &SourceLines, block: &Block) -> (usize, usize) {
+    let mut start = block.start;
+    let mut end = block.end;
+    let preceded_by_gap = start == 0
+        || lines.lines[start - 1].trim().is_empty()
+        || lines.lines[start - 1].trim_end().ends_with(':');
+    let mut after = end;
+    while after < lines.len() && lines.lines[after].trim().is_empty() {
+        after += 1;
+    }
+    if after >= lines.len()
+        || (after < lines.len() && indentation(lines.lines[after]) < block.indent)
+    {
+        // Last statement of the file or body: drop the blank lines before it instead
+        while start > 0 && lines.lines[start - 1].trim().is_empty() {
+            start -= 1;
+        }
+        if after >= lines.len() {
+            end = lines.len();
+        }
+    } else if preceded_by_gap {
+        end = after;
+    }
+    lines.byte_range(start, end)
+}
+
+/// Whether removing `block` leaves its enclosing `def`/`class` without statements
+pub(crate) fn enclosing_body_left_empty(lines: &SourceLines, block: &Block) -> bool {
+    if block.indent == 0 {
+        return false;
+    }
+    let Some(parent) = (0..block.start).rev().find(|i| {
+        !is_blank_or_comment(lines.lines[*i]) && indentation(lines.lines[*i]) < block.indent
+    }) else {
+        return false;
+    };
+    let parent_end = block_end(lines, parent, indentation(lines.lines[parent]));
+    ((parent + 1)..parent_end)
+        .filter(|i| *i < block.start || *i >= block.end)
+        .filter(|i| !is_blank_or_comment(lines.lines[*i]))
+        .all(|i| {
+            indentation(lines.lines[i]) <= indentation(lines.lines[parent])
+                || is_closing(lines.lines[i])
+        })
+}
+
+/// Top-level definitions with their header line
+fn top_level_definitions(lines: &SourceLines) -> Vec<(String, usize)> {
+    lines
+        .lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| indentation(l) == 0)
+        .filter_map(|(i, l)| {
+            definition_header(l)
+                .map(|(name, _)| name)
+                .or_else(|| assignment_header(l))
+                .map(|name| (name, i))
+        })
+        .collect()
+}
+
+fn identifiers(text: &str) -> HashSet<&str> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|s| !s.is_empty() && !s.starts_with(|c: char| c.is_ascii_digit()))
+        .collect()
+}
+
+fn strip_import_lines(text: &str) -> String {
+    text.lines()
+        .filter(|l| !is_import(l))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ============================================================================
+// Imports
+// ============================================================================
+
+struct ImportedName {
+    name: String,
+    alias: Option<String>,
+}
+
+impl ImportedName {
+    /// Name bound in the importing module (`import a.b` binds `a`)
+    fn bound(&self, from_import: bool) -> &str {
+        match &self.alias {
+            Some(alias) => alias,
+            None if from_import => &self.name,
+            None => self.name.split('.').next().unwrap_or(&self.name),
+        }
+    }
+
+    fn render(&self) -> String {
+        match &self.alias {
+            Some(alias) => format!("{} as {}", self.name, alias),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// An `import` or `from ... import` statement
+struct ImportStatement {
+    start: usize,
+    end: usize,
+    indent: String,
+    /// Module of a `from` import, as written (may be relative)
+    module: Option<String>,
+    names: Vec<ImportedName>,
+}
+
+impl ImportStatement {
+    /// Absolute module of a `from` import
+    fn resolved_module(&self, current_module: &str, current_is_package: bool) -> Option<String> {
+        let module = self.module.as_deref()?;
+        let dots = module.len() - module.trim_start_matches('.').len();
+        if dots == 0 {
+            return Some(module.to_string());
+        }
+        let mut package: Vec<&str> = current_module
+            .split('.')
+            .filter(|s| !s.is_empty())
+            .collect();
+        if !current_is_package {
+            package.pop();
+        }
+        for _ in 1..dots {
+            package.pop()?;
+        }
+        let rest = &module[dots..];
+        if !rest.is_empty() {
+            package.push(rest);
+        }
+        Some(package.join("."))
+    }
+}
+
+fn is_import(line: &str) -> bool {
+    let t = line.trim_start();
+    t.starts_with("import ") || (t.starts_with("from ") && t.contains(" import"))
+}
+
+fn parse_imports(lines: &SourceLines, top_level_only: bool) -> Vec<ImportStatement> {
+    let mut imports = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines.lines[i];
+        if !is_import(line) || (top_level_only && indentation(line) > 0) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let mut text = line.trim().to_string();
+        while (text.contains('(') && !text.contains(')')) || text.ends_with('\\') {
+            i += 1;
+            if i >= lines.len() {
+                break;
+            }
+            text = format!("{} {}", text.trim_end_matches('\\'), lines.lines[i].trim());
+        }
+        i += 1;
+
+        let text = text.split('#').next().unwrap_or("").trim();
+        let (module, list) = match text.strip_prefix("from ") {
+            Some(rest) => match rest.split_once(" import ") {
+                Some((module, list)) => (Some(module.trim().to_string()), list),
+                None => continue,
+            },
+            None => (None, &text["import ".len()..]),
+        };
+        let names = list
+            .trim()
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|entry| match entry.split_once(" as ") {
+                Some((name, alias)) => ImportedName {
+                    name: name.trim().to_string(),
+                    alias: Some(alias.trim().to_string()),
+                },
+                None => ImportedName {
+                    name: entry.to_string(),
+                    alias: None,
+                },
+            })
+            .collect();
+        imports.push(ImportStatement {
+            start,
+            end: i,
+            indent: " ".repeat(indentation(line)),
+            module,
+            names,
+        });
+    }
+    imports
+}
+
+fn render_from<'a>(module: &str, names: impl Iterator<Item = &'a ImportedName>) -> String {
+    let names: Vec<String> = names.map(ImportedName::render).collect();
+    format!("from {} import {}", module, names.join(", "))
+}
+
+fn render_import<'a>(names: impl Iterator<Item = &'a ImportedName>) -> String {
+    let names: Vec<String> = names.map(ImportedName::render).collect();
+    format!("import {}", names.join(", "))
+}
+
+/// Byte offset after the last top-level import, or after the module docstring
+fn import_insertion_offset(lines: &SourceLines) -> usize {
+    if let Some(last) = parse_imports(lines, true).last() {
+        return lines
+            .byte_range(last.end, last.end)
+            .0
+            .min(lines.source.len());
+    }
+    let Some(first) = (0..lines.len()).find(|i| !is_blank_or_comment(lines.lines[*i])) else {
+        return 0;
+    };
+    let text = lines.lines[first].trim_start();
+    for quote in ["\"\"\"", "'''"] {
+        if let Some(rest) = text.strip_prefix(quote) {
+            let closing = if rest.contains(quote) {
+                Some(first)
+            } else {
+                ((first + 1)..lines.len()).find(|i| lines.lines[*i].contains(quote))
+            };
+            if let Some(closing) = closing {
+                return lines
+                    .byte_range(closing + 1, closing + 1)
+                    .0
+                    .min(lines.source.len());
+            }
+        }
+    }
+    0
+}
+
+/// Remove `name` from a `from` import; with `new_module` it is re-imported from there
+fn remove_imported_name(
+    lines: &SourceLines,
+    import: &ImportStatement,
+    name: &str,
+    new_module: Option<&str>,
+    edits: &mut FileEdits,
+) {
+    let Some(module) = &import.module else {
+        return;
+    };
+    let (moved, kept): (Vec<&ImportedName>, Vec<&ImportedName>) =
+        import.names.iter().partition(|n| n.name == name);
+    if moved.is_empty() {
+        return;
+    }
+
+    let mut replacement = String::new();
+    if !kept.is_empty() {
+        replacement.push_str(&format!(
+            "{}{}\n",
+            import.indent,
+            render_from(module, kept.into_iter())
+        ));
+    }
+    if let Some(new_module) = new_module {
+        replacement.push_str(&format!(
+            "{}{}\n",
+            import.indent,
+            render_from(new_module, moved.into_iter())
+        ));
+    }
+    let (start, end) = lines.byte_range(import.start, import.end);
+    let ends_with_newline = lines.source[start..end].ends_with('\n');
+    if !ends_with_newline && replacement.ends_with('\n') {
+        replacement.pop();
+    }
+    edits.replace(start, end, replacement);
+}
+
+/// Rewrite `module.name` through `import module [as alias]` to the new module
+fn rewrite_attribute_access(
+    lines: &SourceLines,
+    import: &ImportStatement,
+    old_module: &str,
+    new_module: &str,
+    name: &str,
+    edits: &mut FileEdits,
+) -> PluginResult<()> {
+    let Some(imported) = import.names.iter().find(|n| n.name == old_module) else {
+        return Ok(());
+    };
+    let qualifier = imported.alias.as_deref().unwrap_or(old_module);
+    let pattern = Regex::new(&format!(
+        r"(^|[^\w.]){}\.{}\b",
+        regex::escape(qualifier),
+        regex::escape(name)
+    ))
+    .map_err(|e| PluginApiError::internal(format!("Invalid attribute pattern: {}", e)))?;
+
+    let mut found = false;
+    for (i, line) in lines.lines.iter().enumerate() {
+        if is_import(line) {
+            continue;
+        }
+        for captures in pattern.captures_iter(line) {
+            let (Some(whole), Some(prefix)) = (captures.get(0), captures.get(1)) else {
+                continue;
+            };
+            let start = lines.starts[i] + prefix.end();
+            edits.replace(
+                start,
+                lines.starts[i] + whole.end(),
+                format!("{}.{}", new_module, name),
+            );
+            found = true;
+        }
+    }
+
+    if found {
+        let (_, end) = lines.byte_range(import.start, import.end);
+        let newline = if lines.source[..end].ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        edits.insert(
+            end,
+            format!("{}{}import {}\n", newline, import.indent, new_module),
+        );
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Project layout
+// ============================================================================
+
+fn find_project_root(file: &Path) -> PathBuf {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| {
+            ["pyproject.toml", "setup.py", "setup.cfg"]
+                .iter()
+                .any(|marker| dir.join(marker).is_file())
+        })
+        .or_else(|| file.parent())
+        .unwrap_or(Path::new("."))
+        .to_path_buf()
+}
+
+fn module_name(file: &Path, root: &Path) -> String {
+    path_to_python_module(file.strip_prefix(root).unwrap_or(file))
+}
+
+fn is_package(file: &Path) -> bool {
+    file.file_stem().is_some_and(|stem| stem == "__init__")
+}
+
+async fn find_python_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut queue = vec![root.to_path_buf()];
+    while let Some(dir) = queue.pop() {
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            match entry.file_type().await {
+                Ok(t)
+                    if t.is_dir()
+                        && !SKIPPED_DIRS.contains(&name.as_str())
+                        && !name.starts_with('.') =>
+                {
+                    queue.push(path)
+                }
+                Ok(t) if t.is_dir() => {}
+                Ok(_) if name.ends_with(".py") => files.push(path),
+                _ => {}
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mill_foundation::protocol::TextEdit;
+    use mill_lang_common::position_to_offset;
+    use std::collections::HashMap;
+
+    fn apply(source: &str, edits: &[&TextEdit]) -> String {
+        let mut result = source.to_string();
+        let mut edits = edits.to_vec();
+        edits.sort_by_key(|e| std::cmp::Reverse((e.location.start_line, e.location.start_column)));
+        for edit in edits {
+            let loc = &edit.location;
+            let start = position_to_offset(&result, loc.start_line, loc.start_column).unwrap();
+            let end = position_to_offset(&result, loc.end_line, loc.end_column).unwrap();
+            result.replace_range(start..end, &edit.new_text);
+        }
+        result
+    }
+
+    fn by_file(plan: &EditPlan) -> HashMap<String, Vec<&TextEdit>> {
+        let mut files: HashMap<String, Vec<&TextEdit>> = HashMap::new();
+        for edit in &plan.edits {
+            let file = edit.file_path.clone().unwrap_or(plan.source_file.clone());
+            files.entry(file).or_default().push(edit);
+        }
+        files
+    }
+
+    #[tokio::test]
+    async fn test_move_function_carries_imports_and_updates_callers() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("pyproject.toml"), "[project]\nname = \"demo\"\n").unwrap();
+        std::fs::create_dir(root.join("app")).unwrap();
+        std::fs::write(root.join("app/__init__.py"), "").unwrap();
+        let utils = "import os\nfrom typing import List, Dict\n\nBASE = \"/tmp\"\n\n\ndef join_all(parts: List[str]) -> str:\n    return os.path.join(BASE, *parts)\n\n\ndef other() -> Dict[str, str]:\n    return {}\n";
+        let main = "from app.utils import join_all, other\nimport app.utils\n\nprint(join_all([\"a\"]), app.utils.join_all([]))\n";
+        std::fs::write(root.join("app/utils.py"), utils).unwrap();
+        std::fs::write(root.join("main.py"), main).unwrap();
+
+        let utils_path = root.join("app/utils.py").to_string_lossy().to_string();
+        let dest = root.join("app/paths.py").to_string_lossy().to_string();
+        let plan = plan_symbol_move(utils, 6, 0, &utils_path, &dest)
+            .await
+            .unwrap();
+        let files = by_file(&plan);
+
+        assert_eq!(
+            apply("", &files[&dest]),
+            "import os\nfrom typing import List\nfrom app.utils import BASE\n\n\ndef join_all(parts: List[str]) -> str:\n    return os.path.join(BASE, *parts)\n"
+        );
+        assert_eq!(
+            apply(utils, &files[&utils_path]),
+            "import os\nfrom typing import List, Dict\n\nBASE = \"/tmp\"\n\n\ndef other() -> Dict[str, str]:\n    return {}\n"
+        );
+        let main_path = root.join("main.py").to_string_lossy().to_string();
+        assert_eq!(
+            apply(main, &files[&main_path]),
+            "from app.utils import other\nfrom app.paths import join_all\nimport app.utils\nimport app.paths\n\nprint(join_all([\"a\"]), app.paths.join_all([]))\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move_class_with_decorator_to_existing_module() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("setup.py"), "").unwrap();
+        let models = "from dataclasses import dataclass\n\n\n@dataclass\nclass User:\n    id: int\n\n\ndef make() -> User:\n    return User(1)\n";
+        let entities = "from models import User\n\n\nclass Team:\n    members: list[User]\n";
+        std::fs::write(root.join("models.py"), models).unwrap();
+        std::fs::write(root.join("entities.py"), entities).unwrap();
+
+        let models_path = root.join("models.py").to_string_lossy().to_string();
+        let dest = root.join("entities.py").to_string_lossy().to_string();
+        let plan = plan_symbol_move(models, 3, 0, &models_path, &dest)
+            .await
+            .unwrap();
+        let files = by_file(&plan);
+
+        assert_eq!(
+            apply(entities, &files[&dest]),
+            "from dataclasses import dataclass\n\n\nclass Team:\n    members: list[User]\n\n\n@dataclass\nclass User:\n    id: int\n"
+        );
+        assert_eq!(
+            apply(models, &files[&models_path]),
+            "from dataclasses import dataclass\nfrom entities import User\n\n\ndef make() -> User:\n    return User(1)\n"
+        );
+    }
+
+    #[test]
+    fn test_delete_function() {
+        let source = "def a():\n    pass\n\n\ndef b():\n    pass\n\n\ndef c():\n    pass\n";
+        let plan = plan_symbol_delete(source, 4, 0, "mod.py").unwrap();
+        let edits: Vec<&TextEdit> = plan.edits.iter().collect();
+        assert_eq!(
+            apply(source, &edits),
+            "def a():\n    pass\n\n\ndef c():\n    pass\n"
+        );
+    }
+
+    #[test]
+    fn test_delete_only_method_leaves_pass() {
+        let source = "class A:\n    def f(self):\n        return 1\n\n\nx = A()\n";
+        let plan = plan_symbol_delete(source, 2, 0, "mod.py").unwrap();
+        let edits: Vec<&TextEdit> = plan.edits.iter().collect();
+        assert_eq!(apply(source, &edits), "class A:\n    pass\n\n\nx = A()\n");
+        assert_eq!(plan.metadata.intent_arguments["symbol"], "f");
+    }
+
+    #[test]
+    fn test_relative_import_resolution() {
+        let import = ImportStatement {
+            start: 0,
+            end: 1,
+            indent: String::new(),
+            module: Some("..core.db".to_string()),
+            names: Vec::new(),
+        };
+        assert_eq!(
+            import.resolved_module("app.api.views", false),
+            Some("app.core.db".to_string())
+        );
+        assert_eq!(
+            import.resolved_module("app.api", true),
+            Some("app.core.db".to_string())
+        );
+    }
+}
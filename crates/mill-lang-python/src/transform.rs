@@ -0,0 +1,422 @@
+//! Python code transformations
+//!
+//! Supported transform kinds:
+//! - `to_async`: turn the `def` at (or enclosing) the target line into `async def`
+//! - `if_else_to_match`: rewrite an `if`/`elif`/`else` equality chain as a
+//!   structural `match` statement (requires Python 3.10+)
+//!
+//! Like the rest of this plugin, the analysis is indentation based so it works
+//! without a Python interpreter being available.
+
+use mill_foundation::protocol::{EditLocation, EditPlan, EditType, TextEdit};
+use mill_lang_common::{refactoring::edit_plan_builder::EditPlanBuilder, IndentationDetector};
+use mill_plugin_api::{PluginApiError, PluginResult};
+
+/// Transform kinds implemented by the Python plugin
+pub const SUPPORTED_TRANSFORMS: &[&str] = &["to_async", "if_else_to_match"];
+
+/// Plan a code transformation for Python
+///
+/// A line holds at most one `def` or `if` header, so the line alone selects
+/// the target and the column is not consulted.
+pub fn plan_transform(
+    source: &str,
+    kind: &str,
+    line: u32,
+    _character: u32,
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let lines: Vec<&str> = source.lines().collect();
+    if line as usize >= lines.len() {
+        return Err(PluginApiError::invalid_input("Line number out of bounds"));
+    }
+
+    match kind {
+        "to_async" => plan_to_async(&lines, line as usize, file_path),
+        "if_else_to_match" => plan_if_else_to_match(source, &lines, line as usize, file_path),
+        other => Err(PluginApiError::not_supported(format!(
+            "Python transform '{}' (supported: {})",
+            other,
+            SUPPORTED_TRANSFORMS.join(", ")
+        ))),
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+// ============================================================================
+// to_async
+// ============================================================================
+
+/// Locate the `def` line for the target: the line itself, the `def` following a
+/// decorator, or the nearest enclosing `def` above it.
+fn find_def_line(lines: &[&str], line: usize) -> Option<usize> {
+    let trimmed = lines[line].trim_start();
+    if trimmed.starts_with("def ") || trimmed.starts_with("async def ") {
+        return Some(line);
+    }
+
+    if trimmed.starts_with('@') {
+        return (line + 1..lines.len()).find(|&i| {
+            let t = lines[i].trim_start();
+            t.starts_with("def ") || t.starts_with("async def ")
+        });
+    }
+
+    let mut max_indent = indentation(lines[line]);
+    for i in (0..line).rev() {
+        let text = lines[i];
+        if text.trim().is_empty() {
+            continue;
+        }
+        let indent = indentation(text);
+        if indent < max_indent {
+            let t = text.trim_start();
+            if t.starts_with("def ") || t.starts_with("async def ") {
+                return Some(i);
+            }
+            max_indent = indent;
+        }
+    }
+    None
+}
+
+fn plan_to_async(lines: &[&str], line: usize, file_path: &str) -> PluginResult<EditPlan> {
+    let def_line = find_def_line(lines, line).ok_or_else(|| {
+        PluginApiError::invalid_input(format!("No function definition found at line {}", line + 1))
+    })?;
+
+    let text = lines[def_line];
+    let trimmed = text.trim_start();
+    let name = trimmed
+        .trim_start_matches("async ")
+        .trim_start_matches("def ")
+        .split('(')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    if trimmed.starts_with("async def ") {
+        return Err(PluginApiError::invalid_input(format!(
+            "Function '{}' is already async",
+            name
+        )));
+    }
+
+    let column = indentation(text) as u32;
+    let edit = TextEdit {
+        file_path: None,
+        edit_type: EditType::Insert,
+        location: EditLocation {
+            start_line: def_line as u32,
+            start_column: column,
+            end_line: def_line as u32,
+            end_column: column,
+        },
+        original_text: String::new(),
+        new_text: "async ".to_string(),
+        priority: 100,
+        description: format!("Make '{}' async", name),
+    };
+
+    Ok(EditPlanBuilder::new(file_path, "to_async")
+        .with_edits(vec![edit])
+        .with_syntax_validation("Verify Python syntax is valid after transform")
+        .with_intent_args(serde_json::json!({
+            "function_name": name,
+            "kind": "to_async",
+        }))
+        .with_complexity(2)
+        .with_impact_area("code_transform")
+        .build())
+}
+
+// ============================================================================
+// if_else_to_match
+// ============================================================================
+
+/// One `if`/`elif`/`else` clause with its body line range (inclusive)
+struct Clause {
+    condition: Option<String>,
+    body_start: usize,
+    body_end: usize,
+}
+
+fn plan_if_else_to_match(
+    source: &str,
+    lines: &[&str],
+    line: usize,
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let header = lines[line];
+    let base_indent = indentation(header);
+    let trimmed = header.trim();
+    if !trimmed.starts_with("if ") || !trimmed.ends_with(':') {
+        return Err(PluginApiError::invalid_input(format!(
+            "Line {} is not the head of an `if` chain",
+            line + 1
+        )));
+    }
+
+    let clauses = collect_clauses(lines, line, base_indent)?;
+    let last_line = clauses.last().map(|c| c.body_end).unwrap_or(line);
+
+    let (indent_char, indent_size) = IndentationDetector::detect(source);
+    let unit = IndentationDetector::indent_string(1, indent_char, indent_size);
+    let base = &header[..base_indent];
+
+    let mut subject: Option<String> = None;
+    let mut output = Vec::new();
+    let mut case_count = 0;
+
+    for clause in &clauses {
+        let pattern = match &clause.condition {
+            Some(condition) => {
+                let mut patterns = Vec::new();
+                for part in split_top_level_or(condition) {
+                    let (lhs, pattern) = parse_comparison(&part)?;
+                    match &subject {
+                        Some(existing) if *existing != lhs => {
+                            return Err(PluginApiError::invalid_input(
+                                "All branches must compare the same expression to be converted to a match",
+                            ));
+                        }
+                        Some(_) => {}
+                        None => subject = Some(lhs),
+                    }
+                    patterns.push(pattern);
+                }
+                patterns.join(" | ")
+            }
+            None => "_".to_string(),
+        };
+
+        case_count += 1;
+        output.push(format!("{}{}case {}:", base, unit, pattern));
+        for body_line in &lines[clause.body_start..=clause.body_end] {
+            if body_line.trim().is_empty() {
+                output.push(String::new());
+            } else {
+                output.push(format!("{}{}", unit, body_line));
+            }
+        }
+    }
+
+    let subject = subject
+        .ok_or_else(|| PluginApiError::invalid_input("Could not determine match subject"))?;
+    output.insert(0, format!("{}match {}:", base, subject));
+
+    let original_text = lines[line..=last_line].join("\n");
+    let edit = TextEdit {
+        file_path: None,
+        edit_type: EditType::Replace,
+        location: EditLocation {
+            start_line: line as u32,
+            start_column: 0,
+            end_line: last_line as u32,
+            end_column: lines[last_line].chars().count() as u32,
+        },
+        original_text,
+        new_text: output.join("\n"),
+        priority: 100,
+        description: "Convert if/elif chain to match".to_string(),
+    };
+
+    Ok(EditPlanBuilder::new(file_path, "if_else_to_match")
+        .with_edits(vec![edit])
+        .with_syntax_validation("Verify Python syntax is valid after transform")
+        .with_intent_args(serde_json::json!({
+            "kind": "if_else_to_match",
+            "cases": case_count,
+        }))
+        .with_complexity(4)
+        .with_impact_area("code_transform")
+        .build())
+}
+
+/// Walk the `if`/`elif`/`else` chain starting at `line`
+fn collect_clauses(lines: &[&str], line: usize, base_indent: usize) -> PluginResult<Vec<Clause>> {
+    let mut clauses = Vec::new();
+    let mut header = line;
+
+    loop {
+        let trimmed = lines[header].trim();
+        let condition = trimmed
+            .strip_prefix("if ")
+            .or_else(|| trimmed.strip_prefix("elif "))
+            .map(|c| c.trim_end_matches(':').trim().to_string());
+
+        // Body: following lines indented deeper than the header (blank lines allowed)
+        let mut body_end = header;
+        let mut i = header + 1;
+        while i < lines.len() {
+            let text = lines[i];
+            if text.trim().is_empty() {
+                i += 1;
+                continue;
+            }
+            if indentation(text) <= base_indent {
+                break;
+            }
+            body_end = i;
+            i += 1;
+        }
+
+        if body_end == header {
+            return Err(PluginApiError::invalid_input(format!(
+                "Clause at line {} has no indented body (single-line clauses are not supported)",
+                header + 1
+            )));
+        }
+
+        let is_else = condition.is_none();
+        clauses.push(Clause {
+            condition,
+            body_start: header + 1,
+            body_end,
+        });
+
+        if is_else || i >= lines.len() || indentation(lines[i]) != base_indent {
+            break;
+        }
+        let next = lines[i].trim();
+        if (next.starts_with("elif ") || next == "else:") && next.ends_with(':') {
+            header = i;
+        } else {
+            break;
+        }
+    }
+
+    Ok(clauses)
+}
+
+/// Split a condition on top-level ` or ` operators (ignoring parentheses and strings)
+fn split_top_level_or(condition: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    let mut current = String::new();
+    let chars: Vec<char> = condition.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match quote {
+            Some(q) => {
+                if c == q && (i == 0 || chars[i - 1] != '\\') {
+                    quote = None;
+                }
+            }
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                ' ' if depth == 0 && chars[i..].starts_with(&[' ', 'o', 'r', ' ']) => {
+                    parts.push(current.trim().to_string());
+                    current.clear();
+                    i += 4;
+                    continue;
+                }
+                _ => {}
+            },
+        }
+        current.push(c);
+        i += 1;
+    }
+    parts.push(current.trim().to_string());
+    parts
+}
+
+/// Parse `subject == pattern`, returning `(subject, pattern)`
+fn parse_comparison(part: &str) -> PluginResult<(String, String)> {
+    let part = part.trim().trim_start_matches('(').trim_end_matches(')');
+    let (lhs, rhs) = part.split_once("==").ok_or_else(|| {
+        PluginApiError::invalid_input(format!(
+            "Condition `{}` is not an equality comparison",
+            part
+        ))
+    })?;
+    let (lhs, rhs) = (lhs.trim().to_string(), rhs.trim().to_string());
+
+    if is_value_pattern(&rhs) {
+        Ok((lhs, rhs))
+    } else if is_value_pattern(&lhs) {
+        Ok((rhs, lhs))
+    } else {
+        Err(PluginApiError::invalid_input(format!(
+            "Comparison `{}` has no literal or dotted constant usable as a case pattern",
+            part
+        )))
+    }
+}
+
+/// Whether an expression is a valid value pattern. Bare names are rejected
+/// because `case name:` is a capture pattern that matches everything.
+fn is_value_pattern(expr: &str) -> bool {
+    let is_literal = expr.starts_with('"')
+        || expr.starts_with('\'')
+        || matches!(expr, "True" | "False" | "None")
+        || expr
+            .trim_start_matches('-')
+            .chars()
+            .next()
+            .map(|c| c.is_ascii_digit())
+            .unwrap_or(false);
+    let is_dotted_name = expr.contains('.')
+        && expr
+            .split('.')
+            .all(|seg| !seg.is_empty() && seg.chars().all(|c| c.is_alphanumeric() || c == '_'));
+    is_literal || is_dotted_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_async_on_def_line() {
+        let source = "def fetch(url):\n    return get(url)\n";
+        let plan = plan_transform(source, "to_async", 0, 0, "app.py").unwrap();
+        assert_eq!(plan.edits[0].new_text, "async ");
+        assert_eq!(plan.edits[0].location.start_column, 0);
+    }
+
+    #[test]
+    fn test_to_async_from_body_of_method() {
+        let source = "class Client:\n    def fetch(self, url):\n        return get(url)\n";
+        let plan = plan_transform(source, "to_async", 2, 8, "app.py").unwrap();
+        assert_eq!(plan.edits[0].location.start_line, 1);
+        assert_eq!(plan.edits[0].location.start_column, 4);
+    }
+
+    #[test]
+    fn test_to_async_rejects_async_def() {
+        let source = "async def fetch(url):\n    return url\n";
+        assert!(plan_transform(source, "to_async", 0, 0, "app.py").is_err());
+    }
+
+    #[test]
+    fn test_if_else_to_match() {
+        let source = "def status(code):\n    if code == 200:\n        return \"ok\"\n    elif code == 404 or code == 410:\n        return \"gone\"\n    else:\n        return \"error\"\n";
+        let plan = plan_transform(source, "if_else_to_match", 1, 4, "app.py").unwrap();
+        let expected = "    match code:\n        case 200:\n            return \"ok\"\n        case 404 | 410:\n            return \"gone\"\n        case _:\n            return \"error\"";
+        assert_eq!(plan.edits[0].new_text, expected);
+        assert_eq!(plan.edits[0].location.end_line, 6);
+    }
+
+    #[test]
+    fn test_if_else_to_match_rejects_capture_pattern() {
+        let source = "if value == other:\n    pass\n";
+        assert!(plan_transform(source, "if_else_to_match", 0, 0, "app.py").is_err());
+    }
+
+    #[test]
+    fn test_if_else_to_match_accepts_dotted_constants() {
+        let source = "if color == Color.RED:\n    stop()\nelif color == Color.GREEN:\n    go()\n";
+        let plan = plan_transform(source, "if_else_to_match", 0, 0, "app.py").unwrap();
+        assert!(plan.edits[0].new_text.contains("case Color.GREEN:"));
+    }
+}
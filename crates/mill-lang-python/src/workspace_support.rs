@@ -1,19 +1,21 @@
-//! Python workspace support for Poetry/PDM/Hatch monorepos
+//! Python workspace support for uv/PDM/Poetry/Hatch monorepos
 //!
 //! Handles workspace operations through pyproject.toml manipulation.
+//! Member lists are edited in place, keeping the manifest's layout and comments.
 
 use async_trait::async_trait;
 use mill_foundation::protocol::ConsolidationMetadata;
+use mill_lang_common::{push_toml_array_value, remove_toml_array_values};
 use mill_plugin_api::WorkspaceSupport;
 use std::path::Path;
-use toml_edit::{value, Array, DocumentMut, Item, Table};
+use toml_edit::{value, Array, DocumentMut, InlineTable, Item, Table, Value};
 use tracing::{debug, info, warn};
 
 /// Python workspace support implementation
 pub struct PythonWorkspaceSupport;
 
 impl PythonWorkspaceSupport {
-    /// Creates a new Python workspace support instance for Poetry/PDM/Hatch monorepos.
+    /// Creates a new Python workspace support instance for uv/PDM/Poetry/Hatch monorepos.
     pub fn new() -> Self {
         Self
     }
@@ -124,45 +126,49 @@ impl WorkspaceSupport for PythonWorkspaceSupport {
 #[derive(Debug, PartialEq)]
 #[allow(clippy::upper_case_acronyms)]
 enum PythonWorkspaceTool {
-    PDM,    // [tool.pdm.workspace.members]
-    Poetry, // [tool.poetry.packages]
-    Hatch,  // [tool.hatch.envs]
+    PDM,             // [tool.pdm.workspace.members]
+    Uv,              // [tool.uv.workspace.members]
+    PoetryWorkspace, // [tool.poetry.workspace.members]
+    Poetry,          // [tool.poetry.packages]
+    Hatch,           // [tool.hatch.envs]
     None,
 }
 
-/// Detect which Python tool is being used
-fn detect_tool(doc: &DocumentMut) -> PythonWorkspaceTool {
-    // Check for PDM workspace
-    if doc
-        .get("tool")
-        .and_then(|t| t.get("pdm"))
-        .and_then(|p| p.get("workspace"))
-        .is_some()
-    {
-        return PythonWorkspaceTool::PDM;
-    }
-
-    // Check for Poetry packages
-    if doc
-        .get("tool")
-        .and_then(|t| t.get("poetry"))
-        .and_then(|p| p.get("packages"))
-        .is_some()
-    {
-        return PythonWorkspaceTool::Poetry;
+impl PythonWorkspaceTool {
+    /// Table holding a `members` array of path/glob patterns
+    fn members_table(&self) -> Option<[&'static str; 3]> {
+        match self {
+            PythonWorkspaceTool::PDM => Some(["tool", "pdm", "workspace"]),
+            PythonWorkspaceTool::Uv => Some(["tool", "uv", "workspace"]),
+            PythonWorkspaceTool::PoetryWorkspace => Some(["tool", "poetry", "workspace"]),
+            _ => None,
+        }
     }
+}
 
-    // Check for Hatch envs (minimal support)
-    if doc
-        .get("tool")
-        .and_then(|t| t.get("hatch"))
-        .and_then(|h| h.get("envs"))
-        .is_some()
-    {
-        return PythonWorkspaceTool::Hatch;
+/// Detect which Python tool is being used
+fn detect_tool(doc: &DocumentMut) -> PythonWorkspaceTool {
+    let tool = |name: &str, key: &str| {
+        doc.get("tool")
+            .and_then(|t| t.get(name))
+            .and_then(|p| p.get(key))
+            .is_some()
+    };
+
+    if tool("pdm", "workspace") {
+        PythonWorkspaceTool::PDM
+    } else if tool("uv", "workspace") {
+        PythonWorkspaceTool::Uv
+    } else if tool("poetry", "workspace") {
+        PythonWorkspaceTool::PoetryWorkspace
+    } else if tool("poetry", "packages") {
+        PythonWorkspaceTool::Poetry
+    } else if tool("hatch", "envs") {
+        // Minimal support
+        PythonWorkspaceTool::Hatch
+    } else {
+        PythonWorkspaceTool::None
     }
-
-    PythonWorkspaceTool::None
 }
 
 /// Check if pyproject.toml is a workspace manifest
@@ -179,8 +185,11 @@ fn add_workspace_member_impl(content: &str, member: &str) -> Result<String, Stri
         .parse::<DocumentMut>()
         .map_err(|e| format!("Failed to parse pyproject.toml: {}", e))?;
 
-    match detect_tool(&doc) {
-        PythonWorkspaceTool::PDM => add_pdm_member(&mut doc, member)?,
+    let tool = detect_tool(&doc);
+    match tool {
+        PythonWorkspaceTool::PDM
+        | PythonWorkspaceTool::Uv
+        | PythonWorkspaceTool::PoetryWorkspace => add_table_member(&mut doc, &tool, member)?,
         PythonWorkspaceTool::Poetry => add_poetry_package(&mut doc, member)?,
         PythonWorkspaceTool::Hatch => {
             warn!("Hatch workspace member addition not fully supported");
@@ -201,8 +210,11 @@ fn remove_workspace_member_impl(content: &str, member: &str) -> Result<String, S
         .parse::<DocumentMut>()
         .map_err(|e| format!("Failed to parse pyproject.toml: {}", e))?;
 
-    match detect_tool(&doc) {
-        PythonWorkspaceTool::PDM => remove_pdm_member(&mut doc, member)?,
+    let tool = detect_tool(&doc);
+    match tool {
+        PythonWorkspaceTool::PDM
+        | PythonWorkspaceTool::Uv
+        | PythonWorkspaceTool::PoetryWorkspace => remove_table_member(&mut doc, &tool, member)?,
         PythonWorkspaceTool::Poetry => remove_poetry_package(&mut doc, member)?,
         PythonWorkspaceTool::Hatch => {
             warn!("Hatch workspace member removal not fully supported");
@@ -223,8 +235,11 @@ fn list_workspace_members_impl(content: &str) -> Result<Vec<String>, String> {
         .parse::<DocumentMut>()
         .map_err(|e| format!("Failed to parse pyproject.toml: {}", e))?;
 
-    match detect_tool(&doc) {
-        PythonWorkspaceTool::PDM => list_pdm_members(&doc),
+    let tool = detect_tool(&doc);
+    match tool {
+        PythonWorkspaceTool::PDM
+        | PythonWorkspaceTool::Uv
+        | PythonWorkspaceTool::PoetryWorkspace => list_table_members(&doc, &tool),
         PythonWorkspaceTool::Poetry => list_poetry_packages(&doc),
         PythonWorkspaceTool::Hatch => list_hatch_envs(&doc),
         PythonWorkspaceTool::None => Ok(Vec::new()),
@@ -257,7 +272,7 @@ fn update_package_name_impl(content: &str, new_name: &str) -> Result<String, Str
 }
 
 // ============================================================================
-// PDM Workspace Operations (simplest - glob patterns like Rust)
+// Member Table Operations (PDM, uv, Poetry workspace: glob patterns like Rust)
 // ============================================================================
 
 /// Create PDM workspace
@@ -293,47 +308,67 @@ fn create_pdm_workspace(doc: &mut DocumentMut, member: &str) -> Result<(), Strin
     Ok(())
 }
 
-/// Add PDM workspace member
-fn add_pdm_member(doc: &mut DocumentMut, member: &str) -> Result<(), String> {
-    let members = doc["tool"]["pdm"]["workspace"]["members"]
+/// The `members` array of a workspace table, created when missing
+fn table_members_mut<'a>(
+    doc: &'a mut DocumentMut,
+    tool: &PythonWorkspaceTool,
+) -> Result<&'a mut Array, String> {
+    let [tool_key, name, table] = tool.members_table().ok_or("No workspace members table")?;
+    let workspace = &mut doc[tool_key][name][table];
+    if workspace.get("members").is_none() {
+        workspace["members"] = value(Array::new());
+    }
+    workspace["members"]
         .as_array_mut()
-        .ok_or("[tool.pdm.workspace.members] is not an array")?;
+        .ok_or_else(|| format!("[{}.{}.{}.members] is not an array", tool_key, name, table))
+}
+
+/// Add workspace member to a `members` array
+fn add_table_member(
+    doc: &mut DocumentMut,
+    tool: &PythonWorkspaceTool,
+    member: &str,
+) -> Result<(), String> {
+    let members = table_members_mut(doc, tool)?;
 
     // Check if already exists
     if members.iter().any(|v| v.as_str() == Some(member)) {
-        debug!(member = %member, "Member already exists in PDM workspace");
+        debug!(member = %member, tool = ?tool, "Member already exists in workspace");
         return Ok(());
     }
 
-    members.push(member);
+    push_toml_array_value(members, member);
     Ok(())
 }
 
-/// Remove PDM workspace member
-fn remove_pdm_member(doc: &mut DocumentMut, member: &str) -> Result<(), String> {
-    let members = doc["tool"]["pdm"]["workspace"]["members"]
-        .as_array_mut()
-        .ok_or("[tool.pdm.workspace.members] is not an array")?;
-
-    let original_len = members.len();
-    members.retain(|v| v.as_str() != Some(member));
+/// Remove workspace member from a `members` array
+fn remove_table_member(
+    doc: &mut DocumentMut,
+    tool: &PythonWorkspaceTool,
+    member: &str,
+) -> Result<(), String> {
+    let members = table_members_mut(doc, tool)?;
 
-    if members.len() == original_len {
-        debug!(member = %member, "Member not found in PDM workspace");
+    if !remove_toml_array_values(members, |v| v.as_str() == Some(member)) {
+        debug!(member = %member, tool = ?tool, "Member not found in workspace");
     }
 
     Ok(())
 }
 
-/// List PDM workspace members
-fn list_pdm_members(doc: &DocumentMut) -> Result<Vec<String>, String> {
+/// List workspace members from a `members` array
+fn list_table_members(
+    doc: &DocumentMut,
+    tool: &PythonWorkspaceTool,
+) -> Result<Vec<String>, String> {
+    let [tool_key, name, table] = tool.members_table().ok_or("No workspace members table")?;
     let members = doc
-        .get("tool")
-        .and_then(|t| t.get("pdm"))
-        .and_then(|p| p.get("workspace"))
+        .get(tool_key)
+        .and_then(|t| t.get(name))
+        .and_then(|p| p.get(table))
         .and_then(|w| w.get("members"))
         .and_then(|m| m.as_array())
-        .ok_or("PDM workspace members not found")?;
+        .ok_or_else(|| format!("[{}.{}.{}.members] not found", tool_key, name, table))?;
 
     Ok(members
         .iter()
@@ -346,61 +381,92 @@ fn list_pdm_members(doc: &DocumentMut) -> Result<Vec<String>, String> {
 // ============================================================================
 
 /// Add Poetry package
+///
+/// `packages` may be an array of tables (`[[tool.poetry.packages]]`) or an
+/// inline array (`packages = [{ include = "pkg", from = "src" }]`).
 fn add_poetry_package(doc: &mut DocumentMut, member: &str) -> Result<(), String> {
-    let packages = doc["tool"]["poetry"]["packages"]
-        .as_array_of_tables_mut()
-        .ok_or("[tool.poetry.packages] is not an array of tables")?;
-
-    // Check if already exists
     let package_name = extract_package_name(member);
-    for pkg in packages.iter() {
-        if let Some(include) = pkg.get("include").and_then(|v| v.as_str()) {
-            if include == package_name {
-                debug!(member = %member, "Package already exists in Poetry workspace");
-                return Ok(());
-            }
-        }
+    if list_poetry_includes(doc)
+        .iter()
+        .any(|include| include == package_name)
+    {
+        debug!(member = %member, "Package already exists in Poetry workspace");
+        return Ok(());
     }
 
-    // Create new package table
-    let mut pkg_table = Table::new();
-    pkg_table["include"] = value(package_name);
-    pkg_table["from"] = value(member);
-
-    packages.push(pkg_table);
+    let packages = &mut doc["tool"]["poetry"]["packages"];
+    if let Some(tables) = packages.as_array_of_tables_mut() {
+        let mut pkg_table = Table::new();
+        pkg_table["include"] = value(package_name);
+        pkg_table["from"] = value(member);
+        tables.push(pkg_table);
+    } else if let Some(array) = packages.as_array_mut() {
+        let mut pkg_table = InlineTable::new();
+        pkg_table.insert("include", package_name.into());
+        pkg_table.insert("from", member.into());
+        push_toml_array_value(array, pkg_table);
+    } else {
+        return Err("[tool.poetry.packages] is not an array".to_string());
+    }
     Ok(())
 }
 
 /// Remove Poetry package
 fn remove_poetry_package(doc: &mut DocumentMut, member: &str) -> Result<(), String> {
-    let packages = doc["tool"]["poetry"]["packages"]
-        .as_array_of_tables_mut()
-        .ok_or("[tool.poetry.packages] is not an array of tables")?;
-
     let package_name = extract_package_name(member);
-    packages.retain(|pkg| {
-        pkg.get("include")
-            .and_then(|v| v.as_str())
-            .map(|inc| inc != package_name)
-            .unwrap_or(true)
-    });
-
+    let packages = &mut doc["tool"]["poetry"]["packages"];
+    if let Some(tables) = packages.as_array_of_tables_mut() {
+        tables.retain(|pkg| {
+            pkg.get("include")
+                .and_then(|v| v.as_str())
+                .map(|inc| inc != package_name)
+                .unwrap_or(true)
+        });
+    } else if let Some(array) = packages.as_array_mut() {
+        remove_toml_array_values(array, |pkg| {
+            inline_package_field(pkg, "include") == Some(package_name)
+        });
+    } else {
+        return Err("[tool.poetry.packages] is not an array".to_string());
+    }
     Ok(())
 }
 
-/// List Poetry packages
+/// List Poetry packages (their `from` directories)
 fn list_poetry_packages(doc: &DocumentMut) -> Result<Vec<String>, String> {
+    poetry_package_fields(doc, "from")
+}
+
+fn list_poetry_includes(doc: &DocumentMut) -> Vec<String> {
+    poetry_package_fields(doc, "include").unwrap_or_default()
+}
+
+fn poetry_package_fields(doc: &DocumentMut, field: &str) -> Result<Vec<String>, String> {
     let packages = doc
         .get("tool")
         .and_then(|t| t.get("poetry"))
         .and_then(|p| p.get("packages"))
-        .and_then(|p| p.as_array_of_tables())
         .ok_or("Poetry packages not found")?;
 
-    Ok(packages
-        .iter()
-        .filter_map(|pkg| pkg.get("from").and_then(|v| v.as_str()).map(String::from))
-        .collect())
+    if let Some(tables) = packages.as_array_of_tables() {
+        Ok(tables
+            .iter()
+            .filter_map(|pkg| pkg.get(field).and_then(|v| v.as_str()).map(String::from))
+            .collect())
+    } else if let Some(array) = packages.as_array() {
+        Ok(array
+            .iter()
+            .filter_map(|pkg| inline_package_field(pkg, field).map(String::from))
+            .collect())
+    } else {
+        Err("[tool.poetry.packages] is not an array".to_string())
+    }
+}
+
+fn inline_package_field<'a>(pkg: &'a Value, field: &str) -> Option<&'a str> {
+    pkg.as_inline_table()
+        .and_then(|table| table.get(field))
+        .and_then(|v| v.as_str())
 }
 
 /// Extract package name from path (e.g., "packages/my-pkg" -> "my_pkg")
@@ -10,7 +10,9 @@ use std::sync::OnceLock;
 pub const DEFAULT_EDITION: &str = "2021";
 
 /// Parser version for import graph metadata
-#[allow(dead_code)] // Future enhancement: Parser versioning
+///
+/// Persisted parse results are keyed by this version, so bump it whenever the
+/// import or symbol output changes.
 pub const PARSER_VERSION: &str = "0.1.0";
 
 /// Regex pattern for extracting Rust test annotations
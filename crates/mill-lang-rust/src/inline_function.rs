@@ -0,0 +1,907 @@
+//! Inline-function refactoring for Rust using syn AST
+//!
+//! Calls to a free function in the same file are replaced by its body.
+//! Arguments that are paths or literals are substituted for the parameters;
+//! anything else is bound with `let` at the top of a block so it is evaluated
+//! once and in the original order. Early `return`s become `break`s out of a
+//! labeled block (`'inline_name: { ... }`).
+
+use crate::reorder::byte_range;
+use mill_foundation::protocol::EditPlan;
+use mill_lang_common::position_to_offset;
+use mill_lang_common::refactoring::edit_plan_builder::EditPlanBuilder;
+use mill_lang_common::refactoring::file_edits::{
+    full_line_range, line_indent, reindent, text_with, FileEdits,
+};
+use mill_plugin_api::{PluginApiError, PluginResult};
+use proc_macro2::{LineColumn, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::collections::{HashMap, HashSet};
+use syn::{spanned::Spanned, visit::Visit, Expr, Stmt};
+
+type ByteRange = (usize, usize);
+
+/// Plan inlining the function called (or defined) at the given position
+pub fn plan_inline_function(
+    source: &str,
+    line: u32,
+    character: u32,
+    inline_all: bool,
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let file = syn::parse_file(source)
+        .map_err(|e| PluginApiError::parse(format!("Failed to parse Rust source: {}", e)))?;
+    let cursor = position_to_offset(source, line, character)
+        .ok_or_else(|| PluginApiError::invalid_input("Position is outside of the file"))?;
+
+    // Resolve the function from a call under the cursor, or from its definition
+    let calls_by_name = |name: Option<&str>| -> PluginResult<Vec<CallSite>> {
+        let mut collector = CallCollector {
+            source,
+            name: name.map(str::to_string),
+            calls: Vec::new(),
+            standalone: HashSet::new(),
+            locals: None,
+            error: None,
+        };
+        collector.visit_file(&file);
+        match collector.error {
+            Some(error) => Err(error),
+            None => Ok(collector.calls),
+        }
+    };
+    let cursor_call = calls_by_name(None)?
+        .into_iter()
+        .filter(|call| call.range.0 <= cursor && cursor <= call.range.1)
+        .min_by_key(|call| call.range.1 - call.range.0);
+
+    let (function, target_call) = match cursor_call {
+        Some(call) => {
+            let function = find_function(&file, &call.name).ok_or_else(|| {
+                PluginApiError::invalid_input(format!(
+                    "'{}' is not a free function defined in this file",
+                    call.name
+                ))
+            })?;
+            (function, Some(call))
+        }
+        None => {
+            let function = file
+                .items
+                .iter()
+                .find_map(|item| match item {
+                    syn::Item::Fn(f) if f.sig.ident.span().start().line == line as usize + 1 => {
+                        Some(f)
+                    }
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    if on_method(&file, line) {
+                        PluginApiError::invalid_input(
+                            "Methods and associated functions cannot be inlined; only free functions are supported",
+                        )
+                    } else {
+                        PluginApiError::invalid_input(format!(
+                            "No function call or definition found at line {}",
+                            line + 1
+                        ))
+                    }
+                })?;
+            (function, None)
+        }
+    };
+    let name = function.sig.ident.to_string();
+    let body = analyze_body(source, function)?;
+
+    let mut calls: Vec<CallSite> = match (&target_call, inline_all) {
+        (Some(call), false) => vec![call.clone()],
+        _ => calls_by_name(Some(&name))?,
+    };
+    if calls.is_empty() {
+        return Err(PluginApiError::invalid_input(format!(
+            "No calls to '{}' found in this file",
+            name
+        )));
+    }
+
+    // Inner calls first, so a call nested in another call's arguments is
+    // inlined into that argument's text
+    calls.sort_by_key(|call| call.range.1 - call.range.0);
+    let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+    for call in &calls {
+        let text = inline_call(source, function, &body, call, &replacements)?;
+        replacements.retain(|(start, end, _)| !(call.range.0 <= *start && *end <= call.range.1));
+        replacements.push((call.range.0, call.range.1, text));
+    }
+
+    let mut edits = FileEdits::new(source, None);
+    for (start, end, text) in replacements {
+        edits.replace(start, end, text);
+    }
+
+    // Remove the definition once nothing else refers to it
+    let mut notes = Vec::new();
+    let mut definition_removed = false;
+    if inline_all {
+        let mut references = 0;
+        count_ident(file.to_token_stream(), &name, &mut references);
+        if !matches!(function.vis, syn::Visibility::Inherited) {
+            notes.push(format!(
+                "'{}' is public and may be used outside this file; its definition was kept",
+                name
+            ));
+        } else if references > calls.len() + 1 {
+            notes.push(format!(
+                "'{}' is still referenced in this file (e.g. passed as a value or used in a macro); its definition was kept",
+                name
+            ));
+        } else {
+            let (start, end) = byte_range(source, function.span())?;
+            let (start, end) = full_line_range(source, start, end);
+            edits.delete(start, end);
+            definition_removed = true;
+        }
+    }
+
+    Ok(EditPlanBuilder::new(file_path, "inline_function")
+        .with_edits(edits.into_text_edits(&format!("Inline function '{}'", name)))
+        .with_syntax_validation("Verify Rust syntax is valid after inlining")
+        .with_intent_args(serde_json::json!({
+            "function": name,
+            "calls_inlined": calls.len(),
+            "definition_removed": definition_removed,
+            "notes": notes,
+        }))
+        .with_complexity(3)
+        .with_impact_area("function_inlining")
+        .build())
+}
+
+fn find_function<'a>(file: &'a syn::File, name: &str) -> Option<&'a syn::ItemFn> {
+    file.items.iter().find_map(|item| match item {
+        syn::Item::Fn(f) if f.sig.ident == name => Some(f),
+        _ => None,
+    })
+}
+
+fn on_method(file: &syn::File, line: u32) -> bool {
+    file.items.iter().any(|item| match item {
+        syn::Item::Impl(imp) => imp.items.iter().any(|member| {
+            matches!(member, syn::ImplItem::Fn(f) if f.sig.ident.span().start().line == line as usize + 1)
+        }),
+        _ => false,
+    })
+}
+
+fn count_ident(tokens: TokenStream, name: &str, count: &mut usize) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) if ident == name => *count += 1,
+            TokenTree::Group(group) => count_ident(group.stream(), name, count),
+            _ => {}
+        }
+    }
+}
+
+fn collect_idents(tokens: TokenStream, out: &mut HashSet<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => {
+                out.insert(ident.to_string());
+            }
+            TokenTree::Group(group) => collect_idents(group.stream(), out),
+            _ => {}
+        }
+    }
+}
+
+fn span_key(span: proc_macro2::Span) -> (LineColumn, LineColumn) {
+    (span.start(), span.end())
+}
+
+// ============================================================================
+// Call sites
+// ============================================================================
+
+#[derive(Clone)]
+struct CallSite {
+    name: String,
+    range: (usize, usize),
+    args: Vec<(usize, usize)>,
+    arg_exprs: Vec<Expr>,
+    /// Whether the call sits where an expression never needs parentheses
+    standalone: bool,
+    /// Variables bound in the enclosing function
+    locals: HashSet<String>,
+}
+
+/// Collects calls of the form `name(args)`, all of them when `name` is `None`
+struct CallCollector<'a> {
+    source: &'a str,
+    name: Option<String>,
+    calls: Vec<CallSite>,
+    standalone: HashSet<(LineColumn, LineColumn)>,
+    locals: Option<HashSet<String>>,
+    error: Option<PluginApiError>,
+}
+
+impl CallCollector<'_> {
+    fn mark_standalone(&mut self, expr: &Expr) {
+        self.standalone.insert(span_key(expr.span()));
+    }
+
+    fn with_locals(&mut self, node: &dyn Fn(&mut BindingCollector), visit: impl FnOnce(&mut Self)) {
+        let mut bindings = BindingCollector::default();
+        node(&mut bindings);
+        let outer = self.locals.replace(bindings.names);
+        visit(self);
+        self.locals = outer;
+    }
+}
+
+impl<'ast> Visit<'ast> for CallCollector<'_> {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.with_locals(&|b| b.visit_item_fn(i), |this| {
+            syn::visit::visit_item_fn(this, i)
+        });
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.with_locals(&|b| b.visit_impl_item_fn(i), |this| {
+            syn::visit::visit_impl_item_fn(this, i)
+        });
+    }
+
+    fn visit_local(&mut self, node: &'ast syn::Local) {
+        if let Some(init) = &node.init {
+            self.mark_standalone(&init.expr);
+        }
+        syn::visit::visit_local(self, node);
+    }
+
+    fn visit_stmt(&mut self, node: &'ast Stmt) {
+        if let Stmt::Expr(expr, _) = node {
+            self.mark_standalone(expr);
+        }
+        syn::visit::visit_stmt(self, node);
+    }
+
+    fn visit_expr(&mut self, node: &'ast Expr) {
+        match node {
+            Expr::MethodCall(call) => call.args.iter().for_each(|a| self.mark_standalone(a)),
+            Expr::Return(ret) => ret.expr.iter().for_each(|e| self.mark_standalone(e)),
+            Expr::Assign(assign) => self.mark_standalone(&assign.right),
+            Expr::Paren(paren) => self.mark_standalone(&paren.expr),
+            Expr::Array(array) => array.elems.iter().for_each(|e| self.mark_standalone(e)),
+            Expr::Tuple(tuple) => tuple.elems.iter().for_each(|e| self.mark_standalone(e)),
+            Expr::Struct(s) => s.fields.iter().for_each(|f| self.mark_standalone(&f.expr)),
+            Expr::Closure(closure) => self.mark_standalone(&closure.body),
+            Expr::Call(call) => call.args.iter().for_each(|a| self.mark_standalone(a)),
+            _ => {}
+        }
+        syn::visit::visit_expr(self, node);
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        if let Some(callee) = single_ident(&node.func) {
+            if self.name.as_deref().is_none_or(|name| name == callee) {
+                let site = (|| -> PluginResult<CallSite> {
+                    Ok(CallSite {
+                        name: callee.clone(),
+                        range: byte_range(self.source, node.span())?,
+                        args: node
+                            .args
+                            .iter()
+                            .map(|a| byte_range(self.source, a.span()))
+                            .collect::<PluginResult<_>>()?,
+                        arg_exprs: node.args.iter().cloned().collect(),
+                        standalone: self.standalone.contains(&span_key(node.span())),
+                        locals: self.locals.clone().unwrap_or_default(),
+                    })
+                })();
+                match site {
+                    Ok(site) => self.calls.push(site),
+                    Err(error) => self.error = Some(error),
+                }
+            }
+        }
+        syn::visit::visit_expr_call(self, node);
+    }
+}
+
+fn single_ident(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) if path.qself.is_none() && path.path.segments.len() == 1 => {
+            Some(path.path.segments[0].ident.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Names bound by patterns (parameters, `let`, closures, `match` arms, ...)
+#[derive(Default)]
+struct BindingCollector {
+    names: HashSet<String>,
+}
+
+impl<'ast> Visit<'ast> for BindingCollector {
+    fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+        self.names.insert(node.ident.to_string());
+        syn::visit::visit_pat_ident(self, node);
+    }
+}
+
+// ============================================================================
+// Body analysis
+// ============================================================================
+
+struct ParamUse {
+    range: (usize, usize),
+    /// `Point { x }` shorthand, which needs `x: arg`
+    shorthand: bool,
+}
+
+struct BodyAnalysis {
+    params: Vec<Param>,
+    uses: HashMap<String, Vec<ParamUse>>,
+    /// Parameters that cannot be substituted textually
+    pinned: HashSet<String>,
+    /// Names declared inside the body
+    declared: HashSet<String>,
+    /// Names the body refers to that it does not declare
+    free: HashSet<String>,
+    /// `return` expressions outside closures: keyword range and statement range when final
+    returns: Vec<(usize, usize)>,
+    /// The final statement when it is `return expr;`: (statement range, expression range)
+    final_return: Option<(ByteRange, Option<ByteRange>)>,
+    /// Byte range of the block contents, without braces
+    inner: (usize, usize),
+    /// Tail expression used as the value of the inlined call
+    tail: Option<Expr>,
+    only_tail: bool,
+}
+
+struct Param {
+    name: Option<String>,
+    pattern: String,
+    ty: String,
+}
+
+struct BodyVisitor<'a> {
+    source: &'a str,
+    fn_name: String,
+    params: HashSet<String>,
+    uses: HashMap<String, Vec<ParamUse>>,
+    pinned: HashSet<String>,
+    declared: HashSet<String>,
+    free: HashSet<String>,
+    returns: Vec<(usize, usize)>,
+    closure_depth: usize,
+    recursive: bool,
+    uses_try: bool,
+    error: Option<PluginApiError>,
+}
+
+impl BodyVisitor<'_> {
+    fn record_use(&mut self, name: &str, span: proc_macro2::Span, shorthand: bool) {
+        match byte_range(self.source, span) {
+            Ok(range) => self
+                .uses
+                .entry(name.to_string())
+                .or_default()
+                .push(ParamUse { range, shorthand }),
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    fn scan_macro_tokens(&mut self, tokens: TokenStream) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => {
+                    let name = ident.to_string();
+                    if name == self.fn_name {
+                        self.recursive = true;
+                    } else if self.params.contains(&name) {
+                        self.record_use(&name, ident.span(), false);
+                    } else {
+                        self.free.insert(name);
+                    }
+                }
+                TokenTree::Literal(literal) => {
+                    // Implicit format captures (`"{x}"`) cannot be rewritten
+                    let text = literal.to_string();
+                    for param in &self.params {
+                        if text.contains(&format!("{{{}}}", param))
+                            || text.contains(&format!("{{{}:", param))
+                        {
+                            self.pinned.insert(param.clone());
+                        }
+                    }
+                }
+                TokenTree::Group(group) => self.scan_macro_tokens(group.stream()),
+                TokenTree::Punct(_) => {}
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for BodyVisitor<'_> {
+    fn visit_item(&mut self, _node: &'ast syn::Item) {
+        // Nested items cannot see the function's parameters or locals
+    }
+
+    fn visit_expr_path(&mut self, node: &'ast syn::ExprPath) {
+        if node.qself.is_none() && node.path.segments.len() == 1 {
+            let name = node.path.segments[0].ident.to_string();
+            if name == self.fn_name {
+                self.recursive = true;
+            } else if self.params.contains(&name) {
+                self.record_use(&name, node.span(), false);
+            } else {
+                self.free.insert(name);
+            }
+        } else if node
+            .path
+            .segments
+            .last()
+            .is_some_and(|s| s.ident == self.fn_name)
+        {
+            self.recursive = true;
+        }
+        syn::visit::visit_expr_path(self, node);
+    }
+
+    fn visit_field_value(&mut self, node: &'ast syn::FieldValue) {
+        if let (None, syn::Member::Named(ident)) = (&node.colon_token, &node.member) {
+            let name = ident.to_string();
+            if self.params.contains(&name) {
+                self.record_use(&name, ident.span(), true);
+                return;
+            }
+        }
+        syn::visit::visit_field_value(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        self.scan_macro_tokens(node.tokens.clone());
+        syn::visit::visit_macro(self, node);
+    }
+
+    fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+        let name = node.ident.to_string();
+        if self.params.contains(&name) {
+            self.pinned.insert(name.clone());
+        }
+        self.declared.insert(name);
+        syn::visit::visit_pat_ident(self, node);
+    }
+
+    fn visit_expr_closure(&mut self, node: &'ast syn::ExprClosure) {
+        self.closure_depth += 1;
+        syn::visit::visit_expr_closure(self, node);
+        self.closure_depth -= 1;
+    }
+
+    fn visit_expr_async(&mut self, node: &'ast syn::ExprAsync) {
+        self.closure_depth += 1;
+        syn::visit::visit_expr_async(self, node);
+        self.closure_depth -= 1;
+    }
+
+    fn visit_expr_return(&mut self, node: &'ast syn::ExprReturn) {
+        if self.closure_depth == 0 {
+            match byte_range(self.source, node.return_token.span) {
+                Ok(range) => self.returns.push(range),
+                Err(error) => self.error = Some(error),
+            }
+        }
+        syn::visit::visit_expr_return(self, node);
+    }
+
+    fn visit_expr_try(&mut self, node: &'ast syn::ExprTry) {
+        if self.closure_depth == 0 {
+            self.uses_try = true;
+        }
+        syn::visit::visit_expr_try(self, node);
+    }
+}
+
+fn analyze_body(source: &str, function: &syn::ItemFn) -> PluginResult<BodyAnalysis> {
+    let name = function.sig.ident.to_string();
+    let refuse = |reason: &str| {
+        Err(PluginApiError::invalid_input(format!(
+            "Cannot inline '{}': {}",
+            name, reason
+        )))
+    };
+    if function.sig.asyncness.is_some() {
+        return refuse("async functions cannot be inlined into synchronous call sites");
+    }
+    if function.sig.unsafety.is_some() {
+        return refuse("the body of an `unsafe fn` would lose its unsafe context");
+    }
+    if function.sig.variadic.is_some() {
+        return refuse("variadic functions are not supported");
+    }
+
+    let mut params = Vec::new();
+    for input in &function.sig.inputs {
+        let syn::FnArg::Typed(pat_type) = input else {
+            return refuse("methods cannot be inlined");
+        };
+        let name = match pat_type.pat.as_ref() {
+            syn::Pat::Ident(ident)
+                if ident.by_ref.is_none()
+                    && ident.mutability.is_none()
+                    && ident.subpat.is_none() =>
+            {
+                Some(ident.ident.to_string())
+            }
+            _ => None,
+        };
+        let (pat_start, pat_end) = byte_range(source, pat_type.pat.span())?;
+        let (ty_start, ty_end) = byte_range(source, pat_type.ty.span())?;
+        params.push(Param {
+            name,
+            pattern: source[pat_start..pat_end].to_string(),
+            ty: source[ty_start..ty_end].to_string(),
+        });
+    }
+
+    let mut visitor = BodyVisitor {
+        source,
+        fn_name: name.clone(),
+        params: params.iter().filter_map(|p| p.name.clone()).collect(),
+        uses: HashMap::new(),
+        pinned: HashSet::new(),
+        declared: HashSet::new(),
+        free: HashSet::new(),
+        returns: Vec::new(),
+        closure_depth: 0,
+        recursive: false,
+        uses_try: false,
+        error: None,
+    };
+    visitor.visit_block(&function.block);
+    if let Some(error) = visitor.error {
+        return Err(error);
+    }
+    if visitor.recursive {
+        return refuse("the function is recursive");
+    }
+    if visitor.uses_try {
+        return refuse("the body uses `?`, which would return from the caller instead");
+    }
+
+    let (block_start, block_end) = byte_range(source, function.block.span())?;
+    let inner = (block_start + 1, block_end - 1);
+
+    let stmts = &function.block.stmts;
+    let (tail, final_return) = match stmts.last() {
+        Some(Stmt::Expr(Expr::Return(ret), semi)) => {
+            let stmt_range = byte_range(source, stmts.last().unwrap().span())?;
+            let stmt_range = match semi {
+                Some(semi) => (stmt_range.0, byte_range(source, semi.span)?.1),
+                None => stmt_range,
+            };
+            let expr_range = ret
+                .expr
+                .as_ref()
+                .map(|e| byte_range(source, e.span()))
+                .transpose()?;
+            (ret.expr.as_deref().cloned(), Some((stmt_range, expr_range)))
+        }
+        Some(Stmt::Expr(expr, None)) => (Some(expr.clone()), None),
+        _ => (None, None),
+    };
+
+    Ok(BodyAnalysis {
+        params,
+        uses: visitor.uses,
+        pinned: visitor.pinned,
+        declared: visitor.declared,
+        free: visitor.free,
+        returns: visitor.returns,
+        final_return,
+        inner,
+        only_tail: stmts.len() == 1 && tail.is_some(),
+        tail,
+    })
+}
+
+// ============================================================================
+// Building the inlined code
+// ============================================================================
+
+fn is_trivial(expr: &Expr) -> bool {
+    matches!(expr, Expr::Lit(_)) || matches!(expr, Expr::Path(p) if p.qself.is_none())
+}
+
+fn is_atomic(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Lit(_)
+            | Expr::Path(_)
+            | Expr::Call(_)
+            | Expr::MethodCall(_)
+            | Expr::Field(_)
+            | Expr::Index(_)
+            | Expr::Paren(_)
+            | Expr::Macro(_)
+            | Expr::Struct(_)
+            | Expr::Tuple(_)
+            | Expr::Array(_)
+            | Expr::Block(_)
+            | Expr::Unsafe(_)
+    )
+}
+
+fn inline_call(
+    source: &str,
+    function: &syn::ItemFn,
+    body: &BodyAnalysis,
+    call: &CallSite,
+    replacements: &[(usize, usize, String)],
+) -> PluginResult<String> {
+    let name = function.sig.ident.to_string();
+    let call_line = position_line(source, call.range.0);
+    if call.args.len() != body.params.len() {
+        return Err(PluginApiError::invalid_input(format!(
+            "Call to '{}' at line {} passes {} arguments, expected {}",
+            name,
+            call_line,
+            call.args.len(),
+            body.params.len()
+        )));
+    }
+    if let Some(shadowed) = body
+        .free
+        .iter()
+        .filter(|free| !body.declared.contains(*free) && call.locals.contains(*free))
+        .min()
+    {
+        return Err(PluginApiError::invalid_input(format!(
+            "Cannot inline call at line {}: '{}' used by '{}' is shadowed by a local variable there",
+            call_line, shadowed, name
+        )));
+    }
+
+    // Decide, per parameter, between substitution and a `let` binding
+    let annotate = function.sig.generics.params.is_empty();
+    let mut substitutions: Vec<(usize, usize, String)> = Vec::new();
+    let mut bindings: Vec<(String, String, String, HashSet<String>)> = Vec::new();
+    for ((param, arg_range), arg_expr) in body.params.iter().zip(&call.args).zip(&call.arg_exprs) {
+        let arg = text_with(source, *arg_range, replacements);
+        let mut arg_idents = HashSet::new();
+        collect_idents(arg_expr.to_token_stream(), &mut arg_idents);
+        let uses = param
+            .name
+            .as_ref()
+            .and_then(|n| body.uses.get(n))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let substitutable = param
+            .name
+            .as_ref()
+            .is_some_and(|n| !body.pinned.contains(n))
+            && is_trivial(arg_expr)
+            && arg_idents.is_disjoint(&body.declared);
+        if substitutable {
+            let param_name = param.name.as_deref().unwrap_or_default();
+            for param_use in uses {
+                let text = if param_use.shorthand && arg != param_name {
+                    format!("{}: {}", param_name, arg)
+                } else if param_use.shorthand {
+                    param_name.to_string()
+                } else {
+                    arg.clone()
+                };
+                substitutions.push((param_use.range.0, param_use.range.1, text));
+            }
+            continue;
+        }
+
+        let pattern = if param.name.is_some() && uses.is_empty() {
+            "_".to_string()
+        } else {
+            param.pattern.clone()
+        };
+        let ty = if annotate && !param.ty.contains("impl ") {
+            param.ty.clone()
+        } else {
+            String::new()
+        };
+        bindings.push((pattern, ty, arg, arg_idents));
+    }
+
+    let label = format!("'inline_{}", name);
+    let early_returns: Vec<(usize, usize)> = body
+        .returns
+        .iter()
+        .filter(|(start, _)| {
+            body.final_return
+                .is_none_or(|((stmt_start, _), _)| *start != stmt_start)
+        })
+        .copied()
+        .collect();
+
+    // Rewrite the body: substitutions, final `return`, early returns
+    let mut body_edits = substitutions;
+    if let Some(((stmt_start, stmt_end), expr)) = body.final_return {
+        match expr {
+            Some((expr_start, expr_end)) => {
+                body_edits.push((stmt_start, expr_start, String::new()));
+                body_edits.push((expr_end, stmt_end, String::new()));
+            }
+            None => body_edits.push((stmt_start, stmt_end, String::new())),
+        }
+    }
+    for (start, end) in &early_returns {
+        body_edits.push((*start, *end, format!("break {}", label)));
+    }
+    let rewritten = text_with(source, body.inner, &body_edits);
+
+    // A single expression with nothing to bind is inlined as an expression
+    if bindings.is_empty() && body.only_tail && early_returns.is_empty() {
+        let text = rewritten.trim().to_string();
+        let atomic = body.tail.as_ref().is_some_and(is_atomic);
+        return Ok(if atomic || call.standalone {
+            text
+        } else {
+            format!("({})", text)
+        });
+    }
+    if bindings.is_empty() && rewritten.trim().is_empty() {
+        return Ok("()".to_string());
+    }
+
+    let indent = line_indent(source, call.range.0);
+    let inner_indent = format!("{}    ", indent);
+    let mut lines = Vec::new();
+    let binding_names: HashSet<String> = bindings
+        .iter()
+        .flat_map(|(pattern, ..)| {
+            let mut names = HashSet::new();
+            if let Ok(tokens) = pattern.parse::<TokenStream>() {
+                collect_idents(tokens, &mut names);
+            }
+            names
+        })
+        .collect();
+    let needs_tuple = bindings.len() > 1
+        && bindings
+            .iter()
+            .any(|(.., idents)| !idents.is_disjoint(&binding_names));
+    if needs_tuple {
+        // Bind simultaneously so an argument cannot see an earlier binding
+        let patterns: Vec<&str> = bindings.iter().map(|(p, ..)| p.as_str()).collect();
+        let args: Vec<&str> = bindings.iter().map(|(_, _, a, _)| a.as_str()).collect();
+        let annotated = bindings.iter().all(|(_, ty, ..)| !ty.is_empty());
+        let ty = if annotated {
+            let types: Vec<&str> = bindings.iter().map(|(_, t, ..)| t.as_str()).collect();
+            format!(": ({})", types.join(", "))
+        } else {
+            String::new()
+        };
+        lines.push(format!(
+            "{}let ({}){} = ({});",
+            inner_indent,
+            patterns.join(", "),
+            ty,
+            args.join(", ")
+        ));
+    } else {
+        for (pattern, ty, arg, _) in &bindings {
+            let ty = if ty.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", ty)
+            };
+            lines.push(format!("{}let {}{} = {};", inner_indent, pattern, ty, arg));
+        }
+    }
+    lines.extend(reindent(&rewritten, &inner_indent));
+
+    let opening = if early_returns.is_empty() {
+        "{".to_string()
+    } else {
+        format!("{}: {{", label)
+    };
+    Ok(format!("{}\n{}\n{}}}", opening, lines.join("\n"), indent))
+}
+
+fn position_line(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mill_foundation::protocol::TextEdit;
+
+    fn apply(source: &str, edits: &[TextEdit]) -> String {
+        let mut result = source.to_string();
+        let mut edits = edits.to_vec();
+        edits.sort_by_key(|e| std::cmp::Reverse((e.location.start_line, e.location.start_column)));
+        for edit in edits {
+            let loc = &edit.location;
+            let start = position_to_offset(&result, loc.start_line, loc.start_column).unwrap();
+            let end = position_to_offset(&result, loc.end_line, loc.end_column).unwrap();
+            result.replace_range(start..end, &edit.new_text);
+        }
+        result
+    }
+
+    fn inline(source: &str, line: u32, character: u32, inline_all: bool) -> String {
+        let plan = plan_inline_function(source, line, character, inline_all, "lib.rs").unwrap();
+        apply(source, &plan.edits)
+    }
+
+    #[test]
+    fn test_inline_expression_body() {
+        let source = "fn double(x: i32) -> i32 {\n    x * 2\n}\n\nfn main() {\n    let a = 3;\n    let b = double(a) + 1;\n    let c = double(a);\n}\n";
+        assert_eq!(
+            inline(source, 6, 12, false),
+            "fn double(x: i32) -> i32 {\n    x * 2\n}\n\nfn main() {\n    let a = 3;\n    let b = (a * 2) + 1;\n    let c = double(a);\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_all_binds_complex_arguments_and_removes_definition() {
+        let source = "fn area(w: u32, h: u32) -> u32 {\n    let total = w * h;\n    total\n}\n\nfn main() {\n    let s = area(2, next());\n    println!(\"{}\", s);\n}\n";
+        assert_eq!(
+            inline(source, 0, 3, true),
+            "fn main() {\n    let s = {\n        let h: u32 = next();\n        let total = 2 * h;\n        total\n    };\n    println!(\"{}\", s);\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_early_return_uses_labeled_block() {
+        let source = "fn clamp(v: i32) -> i32 {\n    if v < 0 {\n        return 0;\n    }\n    v\n}\n\nfn main() {\n    let r = clamp(read());\n}\n";
+        assert_eq!(
+            inline(source, 8, 12, false),
+            "fn clamp(v: i32) -> i32 {\n    if v < 0 {\n        return 0;\n    }\n    v\n}\n\nfn main() {\n    let r = 'inline_clamp: {\n        let v: i32 = read();\n        if v < 0 {\n            break 'inline_clamp 0;\n        }\n        v\n    };\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_swapped_arguments_bind_simultaneously() {
+        let source = "fn sub(a: i32, b: i32) -> i32 {\n    a - b\n}\n\nfn main() {\n    let (a, b) = (1, 2);\n    let r = sub(b + 0, a + 0);\n}\n";
+        let result = inline(source, 6, 12, false);
+        assert!(
+            result.contains("let (a, b): (i32, i32) = (b + 0, a + 0);"),
+            "{}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_refuses_recursion_and_question_mark() {
+        let source = "fn fact(n: u64) -> u64 {\n    if n == 0 { 1 } else { n * fact(n - 1) }\n}\n\nfn main() {\n    fact(3);\n}\n";
+        let err = plan_inline_function(source, 5, 4, false, "lib.rs").unwrap_err();
+        assert!(err.to_string().contains("recursive"), "{}", err);
+
+        let source = "fn parse(s: &str) -> Result<u8, std::num::ParseIntError> {\n    let v = s.parse::<u8>()?;\n    Ok(v)\n}\n\nfn main() {\n    let _ = parse(\"1\");\n}\n";
+        let err = plan_inline_function(source, 6, 12, false, "lib.rs").unwrap_err();
+        assert!(err.to_string().contains('?'), "{}", err);
+    }
+
+    #[test]
+    fn test_public_definition_is_kept() {
+        let source = "pub fn one() -> u8 {\n    1\n}\n\nfn main() {\n    let x = one();\n}\n";
+        let plan = plan_inline_function(source, 5, 12, true, "lib.rs").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "pub fn one() -> u8 {\n    1\n}\n\nfn main() {\n    let x = 1;\n}\n"
+        );
+        assert_eq!(plan.metadata.intent_arguments["definition_removed"], false);
+        assert_eq!(
+            plan.metadata.intent_arguments["notes"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
@@ -6,9 +6,14 @@
 
 mod constants;
 mod manifest;
+pub mod inline_function;
 pub mod parser;
 pub mod refactoring;
+pub mod reorder;
+pub mod structural_search;
+pub mod symbol_ops;
 pub mod test_fixtures;
+pub mod transform;
 pub mod workspace;
 
 // Capability trait implementations
@@ -130,6 +135,10 @@ impl LanguagePlugin for RustPlugin {
         parser::analyze_imports(source, file_path)
     }
 
+    fn parser_version(&self) -> &'static str {
+        constants::PARSER_VERSION
+    }
+
     // Use macro to generate capability delegation methods
     impl_capability_delegations! {
         this => {
@@ -139,6 +148,7 @@ impl LanguagePlugin for RustPlugin {
             manifest_updater: ManifestUpdater,
             module_declaration_support: ModuleDeclarationSupport,
             module_locator: ModuleLocator,
+            structural_search_support: StructuralSearchSupport,
         },
         import_support => {
             import_parser: ImportParser,
@@ -313,6 +323,21 @@ impl mill_plugin_api::RefactoringProvider for RustPlugin {
         )
     }
 
+    fn supports_inline_function(&self) -> bool {
+        true
+    }
+
+    async fn plan_inline_function(
+        &self,
+        source: &str,
+        line: u32,
+        character: u32,
+        inline_all: bool,
+        file_path: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        inline_function::plan_inline_function(source, line, character, inline_all, file_path)
+    }
+
     fn supports_extract_function(&self) -> bool {
         true
     }
@@ -385,6 +410,66 @@ impl mill_plugin_api::RefactoringProvider for RustPlugin {
             mill_plugin_api::PluginApiError::internal(format!("Rust refactoring error: {}", e))
         })
     }
+
+    fn supported_transforms(&self) -> &'static [&'static str] {
+        transform::SUPPORTED_TRANSFORMS
+    }
+
+    async fn plan_transform(
+        &self,
+        source: &str,
+        kind: &str,
+        line: u32,
+        character: u32,
+        file_path: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        transform::plan_transform(source, kind, line, character, file_path)
+    }
+
+    fn supports_reorder(&self, kind: &str) -> bool {
+        reorder::SUPPORTED_REORDERS.contains(&kind)
+    }
+
+    async fn plan_reorder(
+        &self,
+        source: &str,
+        kind: &str,
+        line: u32,
+        character: u32,
+        new_order: &[String],
+        file_path: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        reorder::plan_reorder(source, kind, line, character, new_order, file_path)
+    }
+
+    fn supports_symbol_move(&self) -> bool {
+        true
+    }
+
+    async fn plan_symbol_move(
+        &self,
+        source: &str,
+        symbol_line: u32,
+        symbol_col: u32,
+        file_path: &str,
+        destination: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        symbol_ops::plan_symbol_move(source, symbol_line, symbol_col, file_path, destination).await
+    }
+
+    fn supports_symbol_delete(&self) -> bool {
+        true
+    }
+
+    async fn plan_symbol_delete(
+        &self,
+        source: &str,
+        symbol_line: u32,
+        symbol_col: u32,
+        file_path: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        symbol_ops::plan_symbol_delete(source, symbol_line, symbol_col, file_path)
+    }
 }
 
 impl mill_plugin_api::ImportAnalyzer for RustPlugin {
@@ -443,6 +528,25 @@ impl mill_plugin_api::ManifestUpdater for RustPlugin {
     }
 }
 
+// ============================================================================
+// Structural Search Capability
+// ============================================================================
+
+#[async_trait]
+impl mill_plugin_api::StructuralSearchSupport for RustPlugin {
+    async fn tokenize(&self, source: &str) -> PluginResult<Vec<std::ops::Range<usize>>> {
+        structural_search::tokenize(source)
+    }
+
+    async fn syntax_index(
+        &self,
+        source: &str,
+        _file_path: &Path,
+    ) -> PluginResult<mill_plugin_api::SyntaxIndex> {
+        structural_search::syntax_index(source)
+    }
+}
+
 // ============================================================================
 // Module Locator Capability
 // ============================================================================
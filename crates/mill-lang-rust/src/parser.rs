@@ -2,10 +2,12 @@
 //!
 //! This module provides functionality for parsing Rust source code into ASTs,
 //! extracting symbols, and analyzing imports.
+use crate::constants;
 use mill_foundation::protocol::{ImportGraph, ImportInfo, ImportType, NamedImport};
 use mill_lang_common::ImportGraphBuilder;
 use mill_plugin_api::{PluginApiError, PluginResult, SourceLocation, Symbol, SymbolKind};
 use syn::{spanned::Spanned, visit::Visit, File, Item, ItemUse, UseTree};
+
 /// A visitor that walks the AST and collects function names
 struct FunctionVisitor {
     functions: Vec<String>,
@@ -505,7 +507,7 @@ pub(crate) fn analyze_imports(
         .with_source_file(file_path)
         .with_imports(imports)
         .extract_external_dependencies(is_external_dependency)
-        .with_parser_version("0.1.0-plugin")
+        .with_parser_version(constants::PARSER_VERSION)
         .build())
 }
 /// Check if a module path represents an external dependency
@@ -0,0 +1,523 @@
+//! Rust reorder refactorings using syn AST
+//!
+//! Supported reorder kinds:
+//! - `parameters`: function/method parameters (the `self` receiver stays first)
+//! - `fields`: named struct fields
+//! - `variants`: enum variants
+//! - `imports`: a contiguous block of `use` declarations
+
+use mill_foundation::protocol::EditPlan;
+use mill_lang_common::position_to_offset;
+use mill_lang_common::refactoring::edit_plan_builder::EditPlanBuilder;
+use mill_lang_common::refactoring::reorder::{
+    reorder_items_edit, resolve_permutation, ReorderItem,
+};
+use mill_plugin_api::{PluginApiError, PluginResult};
+use proc_macro2::Span;
+use syn::{spanned::Spanned, visit::Visit};
+
+/// Reorder kinds implemented by the Rust plugin
+pub const SUPPORTED_REORDERS: &[&str] = &["parameters", "fields", "variants", "imports"];
+
+/// Plan a reorder for Rust
+pub fn plan_reorder(
+    source: &str,
+    kind: &str,
+    line: u32,
+    _character: u32,
+    new_order: &[String],
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let file = syn::parse_file(source)
+        .map_err(|e| PluginApiError::parse(format!("Failed to parse Rust source: {}", e)))?;
+
+    let target = match kind {
+        "parameters" => find_parameters(source, &file, line)?,
+        "fields" => find_fields(source, &file, line)?,
+        "variants" => find_variants(source, &file, line)?,
+        "imports" => find_imports(source, &file, line)?,
+        other => {
+            return Err(PluginApiError::not_supported(format!(
+                "Rust reorder '{}' (supported: {})",
+                other,
+                SUPPORTED_REORDERS.join(", ")
+            )))
+        }
+    };
+
+    let current: Vec<String> = target.items.iter().map(|i| i.name.clone()).collect();
+    let permutation = if new_order.is_empty() && kind == "imports" {
+        sorted_permutation(&current)
+    } else {
+        resolve_permutation(&current, new_order).map_err(PluginApiError::invalid_input)?
+    };
+
+    let edits = reorder_items_edit(
+        source,
+        &target.items,
+        &permutation,
+        &format!("Reorder {} of '{}'", kind, target.owner),
+    )
+    .into_iter()
+    .collect::<Vec<_>>();
+
+    let mut intent_args = serde_json::json!({
+        "kind": kind,
+        "owner": target.owner,
+        "permutation": permutation,
+        "notes": target.notes,
+    });
+    if let Some((name_line, name_character)) = target.name_position {
+        intent_args["function_name"] = serde_json::json!(target.owner);
+        intent_args["name_line"] = serde_json::json!(name_line);
+        intent_args["name_character"] = serde_json::json!(name_character);
+    }
+
+    Ok(EditPlanBuilder::new(file_path, kind)
+        .with_edits(edits)
+        .with_syntax_validation("Verify Rust syntax is valid after reorder")
+        .with_intent_args(intent_args)
+        .with_complexity(2)
+        .with_impact_area("reorder")
+        .build())
+}
+
+/// Items found at the target position
+struct ReorderTarget {
+    /// Name of the function, type or module owning the items
+    owner: String,
+    items: Vec<ReorderItem>,
+    /// Position of the function name (parameters only)
+    name_position: Option<(u32, u32)>,
+    notes: Vec<String>,
+}
+
+/// Permutation that sorts the names alphabetically (stable)
+fn sorted_permutation(names: &[String]) -> Vec<usize> {
+    let mut permutation: Vec<usize> = (0..names.len()).collect();
+    permutation.sort_by(|a, b| names[*a].cmp(&names[*b]));
+    permutation
+}
+
+/// Byte range of a span in the source
+pub(crate) fn byte_range(source: &str, span: Span) -> PluginResult<(usize, usize)> {
+    let start = span.start();
+    let end = span.end();
+    let start = position_to_offset(
+        source,
+        start.line.saturating_sub(1) as u32,
+        start.column as u32,
+    );
+    let end = position_to_offset(source, end.line.saturating_sub(1) as u32, end.column as u32);
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end => Ok((start, end)),
+        _ => Err(PluginApiError::internal(
+            "Span is outside of the source text",
+        )),
+    }
+}
+
+fn item_for(source: &str, name: String, span: Span) -> PluginResult<ReorderItem> {
+    let (start, end) = byte_range(source, span)?;
+    Ok(ReorderItem::new(name, start, end))
+}
+
+fn line_range(span: Span) -> (u32, u32) {
+    (
+        span.start().line.saturating_sub(1) as u32,
+        span.end().line.saturating_sub(1) as u32,
+    )
+}
+
+/// Pick the innermost candidate whose line range contains `line`
+fn innermost<T>(candidates: Vec<(Span, T)>, line: u32) -> Option<T> {
+    candidates
+        .into_iter()
+        .filter(|(span, _)| {
+            let (start, end) = line_range(*span);
+            start <= line && line <= end
+        })
+        .min_by_key(|(span, _)| {
+            let (start, end) = line_range(*span);
+            end - start
+        })
+        .map(|(_, value)| value)
+}
+
+fn has_derive(attrs: &[syn::Attribute], names: &[&str]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("derive")
+            && attr
+                .parse_args_with(
+                    syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+                )
+                .map(|paths| {
+                    paths.iter().any(|p| {
+                        p.segments
+                            .last()
+                            .is_some_and(|s| names.contains(&s.ident.to_string().as_str()))
+                    })
+                })
+                .unwrap_or(false)
+    })
+}
+
+fn has_repr(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident("repr"))
+}
+
+// ============================================================================
+// parameters
+// ============================================================================
+
+#[derive(Default)]
+struct SignatureCollector {
+    signatures: Vec<(Span, syn::Signature)>,
+}
+
+impl<'ast> Visit<'ast> for SignatureCollector {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.signatures.push((i.span(), i.sig.clone()));
+        syn::visit::visit_item_fn(self, i);
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.signatures.push((i.span(), i.sig.clone()));
+        syn::visit::visit_impl_item_fn(self, i);
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.signatures.push((i.span(), i.sig.clone()));
+        syn::visit::visit_trait_item_fn(self, i);
+    }
+}
+
+fn find_parameters(source: &str, file: &syn::File, line: u32) -> PluginResult<ReorderTarget> {
+    let mut collector = SignatureCollector::default();
+    collector.visit_file(file);
+
+    let sig = innermost(collector.signatures, line).ok_or_else(|| {
+        PluginApiError::invalid_input(format!("No function found at line {}", line + 1))
+    })?;
+    let owner = sig.ident.to_string();
+
+    let mut items = Vec::new();
+    for input in &sig.inputs {
+        if let syn::FnArg::Typed(pat_type) = input {
+            let name = match pat_type.pat.as_ref() {
+                syn::Pat::Ident(ident) => ident.ident.to_string(),
+                other => quote::ToTokens::to_token_stream(other).to_string(),
+            };
+            items.push(item_for(source, name, pat_type.span())?);
+        }
+    }
+
+    let name_start = sig.ident.span().start();
+    let mut notes = Vec::new();
+    if sig.receiver().is_some() {
+        notes.push(
+            "Call sites using `Type::method(receiver, ..)` syntax pass an extra argument and are skipped"
+                .to_string(),
+        );
+    }
+
+    Ok(ReorderTarget {
+        owner,
+        items,
+        name_position: Some((
+            name_start.line.saturating_sub(1) as u32,
+            name_start.column as u32,
+        )),
+        notes,
+    })
+}
+
+// ============================================================================
+// fields
+// ============================================================================
+
+#[derive(Default)]
+struct StructCollector {
+    structs: Vec<(Span, syn::ItemStruct)>,
+}
+
+impl<'ast> Visit<'ast> for StructCollector {
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        self.structs.push((i.span(), i.clone()));
+        syn::visit::visit_item_struct(self, i);
+    }
+}
+
+fn find_fields(source: &str, file: &syn::File, line: u32) -> PluginResult<ReorderTarget> {
+    let mut collector = StructCollector::default();
+    collector.visit_file(file);
+
+    let item = innermost(collector.structs, line).ok_or_else(|| {
+        PluginApiError::invalid_input(format!("No struct found at line {}", line + 1))
+    })?;
+    let owner = item.ident.to_string();
+
+    let fields = match &item.fields {
+        syn::Fields::Named(named) => &named.named,
+        syn::Fields::Unnamed(_) => {
+            return Err(PluginApiError::invalid_input(format!(
+                "'{}' is a tuple struct; reordering its fields would change every constructor and `.0` access",
+                owner
+            )))
+        }
+        syn::Fields::Unit => {
+            return Err(PluginApiError::invalid_input(format!(
+                "'{}' has no fields",
+                owner
+            )))
+        }
+    };
+
+    let items = fields
+        .iter()
+        .map(|field| {
+            let name = field
+                .ident
+                .as_ref()
+                .map(|i| i.to_string())
+                .unwrap_or_default();
+            item_for(source, name, field.span())
+        })
+        .collect::<PluginResult<Vec<_>>>()?;
+
+    let mut notes = Vec::new();
+    if has_repr(&item.attrs) {
+        notes.push(format!(
+            "'{}' has a #[repr] attribute; reordering fields changes its memory layout",
+            owner
+        ));
+    }
+    if has_derive(&item.attrs, &["PartialOrd", "Ord", "Hash"]) {
+        notes.push(format!(
+            "'{}' derives comparison/hash traits that depend on field order",
+            owner
+        ));
+    }
+
+    Ok(ReorderTarget {
+        owner,
+        items,
+        name_position: None,
+        notes,
+    })
+}
+
+// ============================================================================
+// variants
+// ============================================================================
+
+#[derive(Default)]
+struct EnumCollector {
+    enums: Vec<(Span, syn::ItemEnum)>,
+}
+
+impl<'ast> Visit<'ast> for EnumCollector {
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        self.enums.push((i.span(), i.clone()));
+        syn::visit::visit_item_enum(self, i);
+    }
+}
+
+fn find_variants(source: &str, file: &syn::File, line: u32) -> PluginResult<ReorderTarget> {
+    let mut collector = EnumCollector::default();
+    collector.visit_file(file);
+
+    let item = innermost(collector.enums, line).ok_or_else(|| {
+        PluginApiError::invalid_input(format!("No enum found at line {}", line + 1))
+    })?;
+    let owner = item.ident.to_string();
+
+    let items = item
+        .variants
+        .iter()
+        .map(|variant| item_for(source, variant.ident.to_string(), variant.span()))
+        .collect::<PluginResult<Vec<_>>>()?;
+
+    let mut notes = Vec::new();
+    if item.variants.iter().any(|v| v.discriminant.is_none())
+        && item
+            .variants
+            .iter()
+            .all(|v| matches!(v.fields, syn::Fields::Unit))
+    {
+        notes.push(format!(
+            "'{}' has implicit discriminants; `as` casts will yield different values",
+            owner
+        ));
+    }
+    if has_derive(&item.attrs, &["PartialOrd", "Ord"]) {
+        notes.push(format!(
+            "'{}' derives PartialOrd/Ord, which compare variants by declaration order",
+            owner
+        ));
+    }
+
+    Ok(ReorderTarget {
+        owner,
+        items,
+        name_position: None,
+        notes,
+    })
+}
+
+// ============================================================================
+// imports
+// ============================================================================
+
+/// Split a list of items into runs of consecutive `use` declarations, breaking at blank lines
+fn use_blocks(source: &str, items: &[syn::Item]) -> PluginResult<Vec<Vec<ReorderItem>>> {
+    let mut blocks = Vec::new();
+    let mut current = Vec::new();
+
+    for item in items {
+        match item {
+            syn::Item::Use(item_use) => {
+                let (tree_start, tree_end) = byte_range(source, item_use.tree.span())?;
+                let name = source[tree_start..tree_end]
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let item = item_for(source, name, item_use.span())?;
+                // A blank line starts a new import group
+                let gap_has_blank_line = current.last().is_some_and(|last: &ReorderItem| {
+                    source[last.end..item.start].matches('\n').count() > 1
+                });
+                if gap_has_blank_line {
+                    blocks.push(std::mem::take(&mut current));
+                }
+                current.push(item);
+            }
+            syn::Item::Mod(item_mod) => {
+                if !current.is_empty() {
+                    blocks.push(std::mem::take(&mut current));
+                }
+                if let Some((_, nested)) = &item_mod.content {
+                    blocks.extend(use_blocks(source, nested)?);
+                }
+            }
+            _ => {
+                if !current.is_empty() {
+                    blocks.push(std::mem::take(&mut current));
+                }
+            }
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+
+    Ok(blocks)
+}
+
+fn find_imports(source: &str, file: &syn::File, line: u32) -> PluginResult<ReorderTarget> {
+    let line_start = position_to_offset(source, line, 0).unwrap_or(source.len());
+    let line_end = source[line_start..]
+        .find('\n')
+        .map(|i| line_start + i)
+        .unwrap_or(source.len());
+
+    let items = use_blocks(source, &file.items)?
+        .into_iter()
+        .find(|block| {
+            let first = &block[0];
+            let last = &block[block.len() - 1];
+            first.start <= line_end && line_start <= last.end
+        })
+        .ok_or_else(|| {
+            PluginApiError::invalid_input(format!("No `use` block found at line {}", line + 1))
+        })?;
+
+    Ok(ReorderTarget {
+        owner: "use block".to_string(),
+        items,
+        name_position: None,
+        notes: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mill_foundation::protocol::TextEdit;
+
+    fn apply(source: &str, edits: &[TextEdit]) -> String {
+        let mut result = source.to_string();
+        for edit in edits {
+            let loc = &edit.location;
+            let start = position_to_offset(&result, loc.start_line, loc.start_column).unwrap();
+            let end = position_to_offset(&result, loc.end_line, loc.end_column).unwrap();
+            result.replace_range(start..end, &edit.new_text);
+        }
+        result
+    }
+
+    fn order(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reorder_parameters_keeps_receiver() {
+        let source = "impl S {\n    fn f(&self, a: i32, b: &str) {}\n}\n";
+        let plan = plan_reorder(source, "parameters", 1, 0, &order(&["b", "a"]), "lib.rs").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "impl S {\n    fn f(&self, b: &str, a: i32) {}\n}\n"
+        );
+        let args = &plan.metadata.intent_arguments;
+        assert_eq!(args["function_name"], "f");
+        assert_eq!(args["permutation"], serde_json::json!([1, 0]));
+        assert_eq!(args["name_line"], 1);
+        assert_eq!(args["name_character"], 7);
+    }
+
+    #[test]
+    fn test_reorder_parameters_rejects_unknown_name() {
+        let source = "fn f(a: i32, b: i32) {}\n";
+        assert!(plan_reorder(source, "parameters", 0, 0, &order(&["b", "c"]), "lib.rs").is_err());
+    }
+
+    #[test]
+    fn test_reorder_fields_moves_attributes() {
+        let source = "#[derive(Debug)]\nstruct P {\n    /// X\n    x: i32,\n    #[serde(default)]\n    y: i32,\n}\n";
+        let plan = plan_reorder(source, "fields", 2, 0, &order(&["y", "x"]), "lib.rs").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "#[derive(Debug)]\nstruct P {\n    #[serde(default)]\n    y: i32,\n    /// X\n    x: i32,\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_reorder_variants_notes_discriminants() {
+        let source =
+            "#[derive(PartialOrd, Ord, PartialEq, Eq)]\nenum E {\n    A,\n    B,\n    C,\n}\n";
+        let plan =
+            plan_reorder(source, "variants", 1, 0, &order(&["C", "A", "B"]), "lib.rs").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "#[derive(PartialOrd, Ord, PartialEq, Eq)]\nenum E {\n    C,\n    A,\n    B,\n}\n"
+        );
+        assert_eq!(
+            plan.metadata.intent_arguments["notes"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_reorder_imports_sorts_by_default() {
+        let source =
+            "use std::sync::Arc;\nuse crate::a::{b, c};\nuse anyhow::Result;\n\nfn main() {}\n";
+        let plan = plan_reorder(source, "imports", 0, 0, &[], "lib.rs").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "use anyhow::Result;\nuse crate::a::{b, c};\nuse std::sync::Arc;\n\nfn main() {}\n"
+        );
+    }
+}
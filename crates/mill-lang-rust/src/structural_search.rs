@@ -0,0 +1,163 @@
+//! Tokens and syntax nodes for structural search, from proc-macro2 and syn
+//!
+//! Nodes are expressions, statements, patterns, types and items. Arguments of
+//! macro invocations that parse as comma-separated expressions (`println!`,
+//! `assert_eq!`, `vec!`, ...) are indexed too.
+
+use mill_plugin_api::{PluginApiError, PluginResult, SyntaxIndex};
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use std::ops::Range;
+use std::str::FromStr;
+use syn::punctuated::Punctuated;
+use syn::{spanned::Spanned, visit::Visit, Expr, Token};
+
+/// Split Rust code into tokens
+pub fn tokenize(source: &str) -> PluginResult<Vec<Range<usize>>> {
+    let stream = TokenStream::from_str(source)
+        .map_err(|e| PluginApiError::parse(format!("Failed to tokenize Rust code: {}", e)))?;
+    let mut tokens = Vec::new();
+    collect_tokens(source, stream, &mut tokens);
+    Ok(tokens)
+}
+
+/// Tokenize and parse a Rust file
+pub fn syntax_index(source: &str) -> PluginResult<SyntaxIndex> {
+    let file = syn::parse_file(source)
+        .map_err(|e| PluginApiError::parse(format!("Failed to parse Rust source: {}", e)))?;
+    let mut collector = NodeCollector { nodes: Vec::new() };
+    collector.visit_file(&file);
+    Ok(SyntaxIndex {
+        tokens: tokenize(source)?,
+        nodes: collector.nodes,
+    })
+}
+
+/// Flatten a token stream, keeping only tokens whose span covers their own text
+///
+/// Doc comments are lexed into `#[doc = "..."]` attributes whose tokens all
+/// span the comment; they are not code tokens and are dropped.
+fn collect_tokens(source: &str, stream: TokenStream, tokens: &mut Vec<Range<usize>>) {
+    for tree in stream {
+        match tree {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                let open_range = group.span_open().byte_range();
+                let is_code = !open.is_empty() && source.get(open_range.clone()) == Some(open);
+                if is_code {
+                    tokens.push(open_range);
+                }
+                collect_tokens(source, group.stream(), tokens);
+                let close_range = group.span_close().byte_range();
+                if is_code && source.get(close_range.clone()) == Some(close) {
+                    tokens.push(close_range);
+                }
+            }
+            TokenTree::Ident(ident) => push_if_text(
+                source,
+                tokens,
+                ident.span().byte_range(),
+                &ident.to_string(),
+            ),
+            TokenTree::Punct(punct) => push_if_text(
+                source,
+                tokens,
+                punct.span().byte_range(),
+                punct.as_char().encode_utf8(&mut [0; 4]),
+            ),
+            TokenTree::Literal(literal) => push_if_text(
+                source,
+                tokens,
+                literal.span().byte_range(),
+                &literal.to_string(),
+            ),
+        }
+    }
+}
+
+fn push_if_text(source: &str, tokens: &mut Vec<Range<usize>>, range: Range<usize>, text: &str) {
+    if source.get(range.clone()) == Some(text) {
+        tokens.push(range);
+    }
+}
+
+struct NodeCollector {
+    nodes: Vec<Range<usize>>,
+}
+
+impl NodeCollector {
+    fn push(&mut self, node: &impl Spanned) {
+        let range = node.span().byte_range();
+        if !range.is_empty() {
+            self.nodes.push(range);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for NodeCollector {
+    fn visit_expr(&mut self, node: &'ast Expr) {
+        self.push(node);
+        syn::visit::visit_expr(self, node);
+    }
+
+    fn visit_stmt(&mut self, node: &'ast syn::Stmt) {
+        self.push(node);
+        syn::visit::visit_stmt(self, node);
+    }
+
+    fn visit_pat(&mut self, node: &'ast syn::Pat) {
+        self.push(node);
+        syn::visit::visit_pat(self, node);
+    }
+
+    fn visit_type(&mut self, node: &'ast syn::Type) {
+        self.push(node);
+        syn::visit::visit_type(self, node);
+    }
+
+    fn visit_item(&mut self, node: &'ast syn::Item) {
+        self.push(node);
+        syn::visit::visit_item(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        if let Ok(args) = node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        syn::visit::visit_macro(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(source: &'a str, ranges: &[Range<usize>]) -> Vec<&'a str> {
+        ranges.iter().map(|r| &source[r.clone()]).collect()
+    }
+
+    #[test]
+    fn test_tokens_skip_comments() {
+        let source = "/// doc\nfn f() { a.b(); } // note";
+        let index = syntax_index(source).unwrap();
+        assert_eq!(
+            texts(source, &index.tokens),
+            vec!["fn", "f", "(", ")", "{", "a", ".", "b", "(", ")", ";", "}"]
+        );
+    }
+
+    #[test]
+    fn test_nodes_include_macro_arguments() {
+        let source = "fn f() { println!(\"{}\", x.unwrap()); }";
+        let index = syntax_index(source).unwrap();
+        let nodes = texts(source, &index.nodes);
+        assert!(nodes.contains(&"x.unwrap()"));
+        assert!(nodes.contains(&"x"));
+    }
+}
@@ -0,0 +1,1104 @@
+//! Symbol-level move and delete for Rust using syn AST
+//!
+//! A symbol is a top-level item: fn, struct, enum, union, trait, type alias,
+//! const, static, inline module or impl block. Moving a type carries its
+//! `impl` blocks from the same file. The `use` items the moved code needs are
+//! copied to the destination, the destination module is declared in its parent
+//! when missing, and `use` paths in the rest of the crate are rewritten.
+
+use crate::imports::compute_module_path_from_file;
+use crate::reorder::byte_range;
+use mill_foundation::protocol::EditPlan;
+use mill_lang_common::find_source_files;
+use mill_lang_common::refactoring::edit_plan_builder::EditPlanBuilder;
+use mill_lang_common::refactoring::file_edits::{full_line_range, FileEdits};
+use mill_plugin_api::{PluginApiError, PluginResult};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use syn::spanned::Spanned;
+use tracing::debug;
+
+/// Plan moving the symbol at `symbol_line` to the module file `destination`
+pub async fn plan_symbol_move(
+    source: &str,
+    symbol_line: u32,
+    _symbol_col: u32,
+    file_path: &str,
+    destination: &str,
+) -> PluginResult<EditPlan> {
+    let source_path = Path::new(file_path);
+    let dest_path = Path::new(destination);
+    if source_path == dest_path {
+        return Err(PluginApiError::invalid_input(
+            "Destination is the file that already contains the symbol",
+        ));
+    }
+    let crate_dir = find_crate_dir(source_path).ok_or_else(|| {
+        PluginApiError::invalid_input(format!("{} is not inside a Cargo crate", file_path))
+    })?;
+    let src_root = crate_dir.join("src");
+    if !dest_path.starts_with(&src_root) {
+        return Err(PluginApiError::invalid_input(format!(
+            "Destination must be a module file under {}",
+            src_root.display()
+        )));
+    }
+
+    // syn trees are not Send, so read every module up front and plan synchronously
+    let mut crate_files = BTreeMap::new();
+    for path in find_source_files(&src_root, &["rs"]).await? {
+        if path != source_path {
+            if let Ok(content) = tokio::fs::read_to_string(&path).await {
+                crate_files.insert(path, content);
+            }
+        }
+    }
+
+    plan_move(
+        source,
+        symbol_line,
+        source_path,
+        dest_path,
+        &crate_dir,
+        &crate_files,
+    )
+}
+
+fn plan_move(
+    source: &str,
+    symbol_line: u32,
+    source_path: &Path,
+    dest_path: &Path,
+    crate_dir: &Path,
+    crate_files: &BTreeMap<PathBuf, String>,
+) -> PluginResult<EditPlan> {
+    let file = parse(source)?;
+    let target = find_move_target(source, &file, symbol_line)?;
+    let file_path = source_path.to_string_lossy().to_string();
+    let destination = dest_path.to_string_lossy().to_string();
+    let src_root = crate_dir.join("src");
+
+    let source_module = module_segments(source_path, crate_dir);
+    let dest_module = module_segments(dest_path, crate_dir);
+    let old_path = crate_path(&source_module, &target.name);
+    let new_path = crate_path(&dest_module, &target.name);
+
+    debug!(
+        symbol = %target.name,
+        kind = target.kind,
+        from = %old_path.join("::"),
+        to = %new_path.join("::"),
+        "Planning Rust symbol move"
+    );
+
+    let dest_source = crate_files.get(dest_path).cloned();
+    let dest_file = dest_source.as_deref().map(parse).transpose()?;
+    let dest_names = dest_file
+        .as_ref()
+        .zip(dest_source.as_deref())
+        .map(|(file, src)| defined_and_imported_names(src, file))
+        .transpose()?
+        .unwrap_or_default();
+    if target.kind != "impl" && dest_names.contains(&target.name) {
+        return Err(PluginApiError::invalid_input(format!(
+            "{} already defines or imports '{}'",
+            destination, target.name
+        )));
+    }
+
+    // Text of the moved items, with private items opened up to the crate
+    let ranges = target
+        .items
+        .iter()
+        .map(|i| byte_range(source, file.items[*i].span()))
+        .collect::<PluginResult<Vec<_>>>()?;
+    let moved_text = ranges
+        .iter()
+        .map(|(start, end)| match target.private_vis_offset {
+            Some(offset) if (*start..*end).contains(&offset) => format!(
+                "{}pub(crate) {}",
+                &source[*start..offset],
+                &source[offset..*end]
+            ),
+            _ => source[*start..*end].to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let mut idents = HashSet::new();
+    for i in &target.items {
+        collect_idents(file.items[*i].to_token_stream(), &mut idents);
+    }
+    let imports = carried_imports(
+        source,
+        &file,
+        &target,
+        &idents,
+        &source_module,
+        &dest_module,
+        &dest_names,
+    )?;
+
+    let mut edits = Vec::new();
+    let description = format!("Move '{}' to {}", target.name, destination);
+
+    // Source file: remove the items and import the symbol if it is still used
+    let mut source_edits = FileEdits::new(source, None);
+    for (start, end) in &ranges {
+        let (start, end) = full_line_range(source, *start, *end);
+        source_edits.delete(start, end);
+    }
+    if target.kind != "impl" && still_referenced(&source_edits.apply(), &target.name) {
+        source_edits.insert(
+            import_insertion_offset(source, &file)?,
+            format!("use {};\n", new_path.join("::")),
+        );
+    }
+    rewrite_qualified_paths(
+        source,
+        &file,
+        &old_path,
+        &new_path,
+        &ranges,
+        &mut source_edits,
+    )?;
+
+    // Declare the destination module in its parent when needed
+    if let Some((parent, module_name)) = parent_module_file(&src_root, &dest_module) {
+        if parent == source_path {
+            if !declares_module(&file, &module_name) {
+                source_edits.insert(
+                    module_insertion_offset(source, &file)?,
+                    format!("pub mod {};\n", module_name),
+                );
+            }
+        } else if let Some(parent_source) = crate_files.get(&parent) {
+            let parent_file = parse(parent_source)?;
+            if !declares_module(&parent_file, &module_name) {
+                let mut parent_edits =
+                    FileEdits::new(parent_source, Some(parent.to_string_lossy().to_string()));
+                parent_edits.insert(
+                    module_insertion_offset(parent_source, &parent_file)?,
+                    format!("pub mod {};\n", module_name),
+                );
+                edits.extend(
+                    parent_edits.into_text_edits(&format!("Declare module '{}'", module_name)),
+                );
+            }
+        }
+    }
+    edits.extend(source_edits.into_text_edits(&description));
+
+    // Destination file: add imports and append the moved items
+    let imports_text = imports
+        .iter()
+        .map(|import| format!("{}\n", import))
+        .collect::<String>();
+    match (&dest_source, &dest_file) {
+        (Some(dest_source), Some(dest_file)) => {
+            let mut dest_edits = FileEdits::new(dest_source, Some(destination.to_string()));
+            if !imports_text.is_empty() {
+                dest_edits.insert(
+                    import_insertion_offset(dest_source, dest_file)?,
+                    imports_text,
+                );
+            }
+            // The destination no longer needs to import the symbol from its old module
+            rewrite_use_items(
+                dest_source,
+                dest_file,
+                &dest_module,
+                &old_path,
+                None,
+                &target.name,
+                &mut dest_edits,
+            )?;
+            rewrite_qualified_paths(
+                dest_source,
+                dest_file,
+                &old_path,
+                &new_path,
+                &[],
+                &mut dest_edits,
+            )?;
+            let separator = if dest_source.is_empty() || dest_source.ends_with("\n\n") {
+                ""
+            } else if dest_source.ends_with('\n') {
+                "\n"
+            } else {
+                "\n\n"
+            };
+            dest_edits.insert(dest_source.len(), format!("{}{}\n", separator, moved_text));
+            edits.extend(dest_edits.into_text_edits(&description));
+        }
+        _ => {
+            let mut dest_edits = FileEdits::new("", Some(destination.to_string()));
+            let separator = if imports_text.is_empty() { "" } else { "\n" };
+            dest_edits.insert(0, format!("{}{}{}\n", imports_text, separator, moved_text));
+            edits.extend(dest_edits.into_text_edits(&description));
+        }
+    }
+
+    // Other modules of the crate: rewrite imports and qualified paths
+    if target.kind != "impl" {
+        for (caller, caller_source) in crate_files {
+            if caller == dest_path {
+                continue;
+            }
+            if !caller_source.contains(&target.name) {
+                continue;
+            }
+            let Ok(caller_file) = syn::parse_file(caller_source) else {
+                debug!(file = %caller.display(), "Skipping caller that does not parse");
+                continue;
+            };
+            let caller_module = module_segments(caller, crate_dir);
+            let mut caller_edits =
+                FileEdits::new(caller_source, Some(caller.to_string_lossy().to_string()));
+            rewrite_use_items(
+                caller_source,
+                &caller_file,
+                &caller_module,
+                &old_path,
+                Some(&new_path),
+                &target.name,
+                &mut caller_edits,
+            )?;
+            rewrite_qualified_paths(
+                caller_source,
+                &caller_file,
+                &old_path,
+                &new_path,
+                &[],
+                &mut caller_edits,
+            )?;
+            if !caller_edits.is_empty() {
+                edits.extend(
+                    caller_edits
+                        .into_text_edits(&format!("Update references to '{}'", target.name)),
+                );
+            }
+        }
+    }
+
+    Ok(EditPlanBuilder::new(file_path, "move_symbol")
+        .with_edits(edits)
+        .with_syntax_validation("Verify Rust syntax is valid after move")
+        .with_intent_args(serde_json::json!({
+            "symbol": target.name,
+            "kind": target.kind,
+            "destination": destination,
+            "carried_imports": imports,
+        }))
+        .with_complexity(3)
+        .with_impact_area("symbol_move")
+        .build())
+}
+
+/// Plan deleting the innermost symbol at `symbol_line`
+///
+/// Deleting a type also deletes its `impl` blocks in the same file; a line
+/// inside an impl block or inline module deletes only the member there.
+pub fn plan_symbol_delete(
+    source: &str,
+    symbol_line: u32,
+    _symbol_col: u32,
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let file = parse(source)?;
+    let (name, ranges) = find_deletable(source, &file.items, symbol_line)?;
+
+    let mut edits = FileEdits::new(source, None);
+    for (start, end) in ranges {
+        let (start, end) = full_line_range(source, start, end);
+        edits.delete(start, end);
+    }
+
+    Ok(EditPlanBuilder::new(file_path, "delete_symbol")
+        .with_edits(edits.into_text_edits(&format!("Delete symbol '{}'", name)))
+        .with_syntax_validation("Verify Rust syntax is valid after deletion")
+        .with_intent_args(serde_json::json!({
+            "symbol": name,
+        }))
+        .with_complexity(2)
+        .with_impact_area("symbol_deletion")
+        .build())
+}
+
+/// The items that make up a movable symbol
+struct SymbolTarget {
+    name: String,
+    kind: &'static str,
+    /// Indices into the file's items: the symbol plus the impls of a type
+    items: Vec<usize>,
+    /// Where to insert `pub(crate) ` when a private item leaves its module
+    private_vis_offset: Option<usize>,
+}
+
+fn parse(source: &str) -> PluginResult<syn::File> {
+    syn::parse_file(source)
+        .map_err(|e| PluginApiError::parse(format!("Failed to parse Rust source: {}", e)))
+}
+
+fn line_range(span: proc_macro2::Span) -> (u32, u32) {
+    (
+        span.start().line.saturating_sub(1) as u32,
+        span.end().line.saturating_sub(1) as u32,
+    )
+}
+
+fn contains_line(span: proc_macro2::Span, line: u32) -> bool {
+    let (start, end) = line_range(span);
+    start <= line && line <= end
+}
+
+fn item_name_kind(item: &syn::Item) -> Option<(String, &'static str)> {
+    let named = match item {
+        syn::Item::Fn(i) => (i.sig.ident.to_string(), "function"),
+        syn::Item::Struct(i) => (i.ident.to_string(), "struct"),
+        syn::Item::Enum(i) => (i.ident.to_string(), "enum"),
+        syn::Item::Union(i) => (i.ident.to_string(), "union"),
+        syn::Item::Trait(i) => (i.ident.to_string(), "trait"),
+        syn::Item::Type(i) => (i.ident.to_string(), "type alias"),
+        syn::Item::Const(i) => (i.ident.to_string(), "constant"),
+        syn::Item::Static(i) => (i.ident.to_string(), "static"),
+        syn::Item::Mod(i) if i.content.is_some() => (i.ident.to_string(), "module"),
+        syn::Item::Impl(i) => (self_type_name(&i.self_ty)?, "impl"),
+        _ => return None,
+    };
+    Some(named)
+}
+
+/// Outer attributes and visibility of items that have one
+fn item_attrs_and_vis(item: &syn::Item) -> Option<(&[syn::Attribute], &syn::Visibility)> {
+    match item {
+        syn::Item::Fn(i) => Some((&i.attrs, &i.vis)),
+        syn::Item::Struct(i) => Some((&i.attrs, &i.vis)),
+        syn::Item::Enum(i) => Some((&i.attrs, &i.vis)),
+        syn::Item::Union(i) => Some((&i.attrs, &i.vis)),
+        syn::Item::Trait(i) => Some((&i.attrs, &i.vis)),
+        syn::Item::Type(i) => Some((&i.attrs, &i.vis)),
+        syn::Item::Const(i) => Some((&i.attrs, &i.vis)),
+        syn::Item::Static(i) => Some((&i.attrs, &i.vis)),
+        syn::Item::Mod(i) => Some((&i.attrs, &i.vis)),
+        _ => None,
+    }
+}
+
+fn self_type_name(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+fn find_move_target(source: &str, file: &syn::File, line: u32) -> PluginResult<SymbolTarget> {
+    let (index, item) = file
+        .items
+        .iter()
+        .enumerate()
+        .find(|(_, item)| contains_line(item.span(), line))
+        .ok_or_else(|| {
+            PluginApiError::invalid_input(format!("No item found at line {}", line + 1))
+        })?;
+    let (name, kind) = item_name_kind(item).ok_or_else(|| {
+        PluginApiError::invalid_input(format!(
+            "Item at line {} is not a movable symbol (use, extern crate and macro items are not supported)",
+            line + 1
+        ))
+    })?;
+
+    // Members of impls and inline modules cannot be moved on their own
+    let inner_member = match item {
+        syn::Item::Impl(i) => i.items.iter().any(|m| contains_line(m.span(), line)),
+        syn::Item::Mod(m) => m
+            .content
+            .as_ref()
+            .is_some_and(|(_, items)| items.iter().any(|i| contains_line(i.span(), line))),
+        _ => false,
+    };
+    if inner_member {
+        return Err(PluginApiError::invalid_input(format!(
+            "Line {} is inside {} '{}'; target its first line to move the whole {}",
+            line + 1,
+            kind,
+            name,
+            kind
+        )));
+    }
+
+    let mut items = vec![index];
+    if matches!(kind, "struct" | "enum" | "union" | "type alias") {
+        for (i, other) in file.items.iter().enumerate() {
+            if let syn::Item::Impl(imp) = other {
+                if self_type_name(&imp.self_ty).as_deref() == Some(name.as_str()) {
+                    items.push(i);
+                }
+            }
+        }
+        items.sort_unstable();
+    }
+
+    let private_vis_offset = match item_attrs_and_vis(item) {
+        Some((attrs, syn::Visibility::Inherited)) => {
+            let outer_end = attrs
+                .iter()
+                .filter(|a| matches!(a.style, syn::AttrStyle::Outer))
+                .map(|a| byte_range(source, a.span()).map(|(_, end)| end))
+                .collect::<PluginResult<Vec<_>>>()?
+                .into_iter()
+                .max();
+            let offset = match outer_end {
+                Some(end) => end + (source[end..].len() - source[end..].trim_start().len()),
+                None => byte_range(source, item.span())?.0,
+            };
+            Some(offset)
+        }
+        _ => None,
+    };
+
+    Ok(SymbolTarget {
+        name,
+        kind,
+        items,
+        private_vis_offset,
+    })
+}
+
+/// Innermost deletable symbol at `line` with the byte ranges to remove
+fn find_deletable(
+    source: &str,
+    items: &[syn::Item],
+    line: u32,
+) -> PluginResult<(String, Vec<(usize, usize)>)> {
+    let item = items
+        .iter()
+        .find(|item| contains_line(item.span(), line))
+        .ok_or_else(|| {
+            PluginApiError::invalid_input(format!("No item found at line {}", line + 1))
+        })?;
+
+    match item {
+        syn::Item::Mod(m) => {
+            if let Some((_, nested)) = &m.content {
+                if nested.iter().any(|i| contains_line(i.span(), line)) {
+                    return find_deletable(source, nested, line);
+                }
+            }
+        }
+        syn::Item::Impl(imp) => {
+            if let Some(member) = imp.items.iter().find(|m| contains_line(m.span(), line)) {
+                let name = match member {
+                    syn::ImplItem::Fn(f) => f.sig.ident.to_string(),
+                    syn::ImplItem::Const(c) => c.ident.to_string(),
+                    syn::ImplItem::Type(t) => t.ident.to_string(),
+                    _ => "impl item".to_string(),
+                };
+                return Ok((name, vec![byte_range(source, member.span())?]));
+            }
+        }
+        _ => {}
+    }
+
+    let (name, kind) = item_name_kind(item).ok_or_else(|| {
+        PluginApiError::invalid_input(format!(
+            "Item at line {} is not a deletable symbol",
+            line + 1
+        ))
+    })?;
+    let mut ranges = vec![byte_range(source, item.span())?];
+    if matches!(kind, "struct" | "enum" | "union" | "type alias") {
+        for other in items {
+            if let syn::Item::Impl(imp) = other {
+                if self_type_name(&imp.self_ty).as_deref() == Some(name.as_str()) {
+                    ranges.push(byte_range(source, imp.span())?);
+                }
+            }
+        }
+    }
+    Ok((name, ranges))
+}
+
+fn collect_idents(tokens: TokenStream, out: &mut HashSet<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => {
+                out.insert(ident.to_string());
+            }
+            TokenTree::Group(group) => collect_idents(group.stream(), out),
+            _ => {}
+        }
+    }
+}
+
+fn still_referenced(source: &str, name: &str) -> bool {
+    let mut idents = HashSet::new();
+    if let Ok(tokens) = source.parse::<TokenStream>() {
+        collect_idents(tokens, &mut idents);
+    }
+    idents.contains(name)
+}
+
+// ============================================================================
+// Module paths
+// ============================================================================
+
+fn find_crate_dir(file: &Path) -> Option<PathBuf> {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Module path of a file inside its crate, without the leading `crate`
+fn module_segments(file: &Path, crate_dir: &Path) -> Vec<String> {
+    let root = crate_dir.parent().unwrap_or(crate_dir);
+    compute_module_path_from_file(file, "crate", root)
+        .split("::")
+        .skip(1)
+        .map(str::to_string)
+        .collect()
+}
+
+fn crate_path(module: &[String], name: &str) -> Vec<String> {
+    std::iter::once("crate".to_string())
+        .chain(module.iter().cloned())
+        .chain(std::iter::once(name.to_string()))
+        .collect()
+}
+
+/// Resolve `crate::`, `self::` and `super::` paths to a `crate::` path
+fn absolutize(segments: &[String], module: &[String]) -> Option<Vec<String>> {
+    let first = segments.first()?;
+    match first.as_str() {
+        "crate" => Some(segments.to_vec()),
+        "self" => Some(
+            std::iter::once("crate".to_string())
+                .chain(module.iter().cloned())
+                .chain(segments[1..].iter().cloned())
+                .collect(),
+        ),
+        "super" => {
+            let supers = segments.iter().take_while(|s| *s == "super").count();
+            let kept = module.len().checked_sub(supers)?;
+            Some(
+                std::iter::once("crate".to_string())
+                    .chain(module[..kept].iter().cloned())
+                    .chain(segments[supers..].iter().cloned())
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// The file declaring the destination module and the module's name
+fn parent_module_file(src_root: &Path, dest_module: &[String]) -> Option<(PathBuf, String)> {
+    let (name, parents) = dest_module.split_last()?;
+    let candidates = if parents.is_empty() {
+        vec![src_root.join("lib.rs"), src_root.join("main.rs")]
+    } else {
+        let dir = parents
+            .iter()
+            .fold(src_root.to_path_buf(), |p, s| p.join(s));
+        vec![dir.with_extension("rs"), dir.join("mod.rs")]
+    };
+    candidates
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+        .map(|parent| (parent, name.clone()))
+}
+
+fn declares_module(file: &syn::File, name: &str) -> bool {
+    file.items
+        .iter()
+        .any(|item| matches!(item, syn::Item::Mod(m) if m.ident == name))
+}
+
+// ============================================================================
+// Use items
+// ============================================================================
+
+/// One imported path of a `use` item
+struct UseLeaf {
+    /// Path segments; a trailing `self` is dropped
+    segments: Vec<String>,
+    alias: Option<String>,
+    glob: bool,
+    /// Byte range that removes this leaf from a group with several members
+    group_member: Option<(usize, usize)>,
+}
+
+impl UseLeaf {
+    fn bound_name(&self) -> Option<&str> {
+        if self.glob {
+            return None;
+        }
+        self.alias
+            .as_deref()
+            .or_else(|| self.segments.last().map(String::as_str))
+    }
+
+    fn render(&self, vis: &str, segments: &[String]) -> String {
+        let mut path = segments.join("::");
+        if self.glob {
+            path.push_str("::*");
+        }
+        if let Some(alias) = &self.alias {
+            path.push_str(" as ");
+            path.push_str(alias);
+        }
+        format!("{}use {};", vis, path)
+    }
+}
+
+fn use_leaves(source: &str, tree: &syn::UseTree) -> PluginResult<Vec<UseLeaf>> {
+    let mut leaves = Vec::new();
+    collect_leaves(source, tree, &mut Vec::new(), None, &mut leaves)?;
+    Ok(leaves)
+}
+
+fn collect_leaves(
+    source: &str,
+    tree: &syn::UseTree,
+    prefix: &mut Vec<String>,
+    group_member: Option<(usize, usize)>,
+    out: &mut Vec<UseLeaf>,
+) -> PluginResult<()> {
+    let leaf = |ident: &syn::Ident, alias: Option<String>| {
+        let mut segments = prefix.clone();
+        if ident != "self" {
+            segments.push(ident.to_string());
+        }
+        UseLeaf {
+            segments,
+            alias,
+            glob: false,
+            group_member,
+        }
+    };
+    match tree {
+        syn::UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            collect_leaves(source, &path.tree, prefix, group_member, out)?;
+            prefix.pop();
+        }
+        syn::UseTree::Name(name) => out.push(leaf(&name.ident, None)),
+        syn::UseTree::Rename(rename) => {
+            out.push(leaf(&rename.ident, Some(rename.rename.to_string())))
+        }
+        syn::UseTree::Glob(_) => out.push(UseLeaf {
+            segments: prefix.clone(),
+            alias: None,
+            glob: true,
+            group_member,
+        }),
+        syn::UseTree::Group(group) => {
+            let spans = group
+                .items
+                .iter()
+                .map(|t| byte_range(source, t.span()))
+                .collect::<PluginResult<Vec<_>>>()?;
+            for (i, tree) in group.items.iter().enumerate() {
+                let member = match spans.len() {
+                    0 | 1 => group_member,
+                    len if i + 1 < len => Some((spans[i].0, spans[i + 1].0)),
+                    _ => Some((spans[i - 1].1, spans[i].1)),
+                };
+                collect_leaves(source, tree, prefix, member, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn vis_text(source: &str, vis: &syn::Visibility) -> PluginResult<String> {
+    Ok(match vis {
+        syn::Visibility::Inherited => String::new(),
+        vis => {
+            let (start, end) = byte_range(source, vis.span())?;
+            format!("{} ", &source[start..end])
+        }
+    })
+}
+
+fn defined_and_imported_names(source: &str, file: &syn::File) -> PluginResult<HashSet<String>> {
+    let mut names = HashSet::new();
+    for item in &file.items {
+        match item {
+            syn::Item::Use(u) => {
+                for leaf in use_leaves(source, &u.tree)? {
+                    if let Some(name) = leaf.bound_name() {
+                        names.insert(name.to_string());
+                    }
+                }
+            }
+            syn::Item::Impl(_) => {}
+            item => {
+                if let Some((name, _)) = item_name_kind(item) {
+                    names.insert(name);
+                }
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// `use` items the moved code needs in its new module
+fn carried_imports(
+    source: &str,
+    file: &syn::File,
+    target: &SymbolTarget,
+    idents: &HashSet<String>,
+    source_module: &[String],
+    dest_module: &[String],
+    dest_names: &HashSet<String>,
+) -> PluginResult<Vec<String>> {
+    let mut imports: Vec<String> = Vec::new();
+    let dest_prefix = crate_path(dest_module, "");
+    let dest_prefix = &dest_prefix[..dest_prefix.len() - 1];
+
+    for item in &file.items {
+        let syn::Item::Use(use_item) = item else {
+            continue;
+        };
+        for leaf in use_leaves(source, &use_item.tree)? {
+            let needed = match leaf.bound_name() {
+                Some(name) => idents.contains(name) && !dest_names.contains(name),
+                None => true,
+            };
+            if !needed {
+                continue;
+            }
+            let segments =
+                absolutize(&leaf.segments, source_module).unwrap_or(leaf.segments.clone());
+            // Paths into the destination module itself are already in scope there
+            let parent = &segments[..segments.len().saturating_sub(usize::from(!leaf.glob))];
+            if parent == dest_prefix {
+                continue;
+            }
+            imports.push(leaf.render("", &segments));
+        }
+    }
+
+    // Items that stay behind in the source module
+    for (i, item) in file.items.iter().enumerate() {
+        if target.items.contains(&i) || matches!(item, syn::Item::Impl(_)) {
+            continue;
+        }
+        if let Some((name, _)) = item_name_kind(item) {
+            if name != target.name && idents.contains(&name) && !dest_names.contains(&name) {
+                imports.push(format!(
+                    "use {};",
+                    crate_path(source_module, &name).join("::")
+                ));
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    imports.retain(|import| seen.insert(import.clone()));
+    Ok(imports)
+}
+
+/// Byte offset after the last top-level `use` item, or after inner attributes
+fn import_insertion_offset(source: &str, file: &syn::File) -> PluginResult<usize> {
+    let last_use = file
+        .items
+        .iter()
+        .rfind(|item| matches!(item, syn::Item::Use(_)));
+    let anchor = match last_use {
+        Some(item) => Some(byte_range(source, item.span())?.1),
+        None => match file.attrs.last() {
+            Some(attr) => Some(byte_range(source, attr.span())?.1),
+            None => None,
+        },
+    };
+    Ok(match anchor {
+        Some(end) => source[end..]
+            .find('\n')
+            .map(|i| end + i + 1)
+            .unwrap_or(source.len()),
+        None => 0,
+    })
+}
+
+/// Byte offset after the last `mod` declaration, falling back to the imports
+fn module_insertion_offset(source: &str, file: &syn::File) -> PluginResult<usize> {
+    let last_mod = file
+        .items
+        .iter()
+        .rfind(|item| matches!(item, syn::Item::Mod(m) if m.content.is_none()));
+    match last_mod {
+        Some(item) => {
+            let end = byte_range(source, item.span())?.1;
+            Ok(source[end..]
+                .find('\n')
+                .map(|i| end + i + 1)
+                .unwrap_or(source.len()))
+        }
+        None => import_insertion_offset(source, file),
+    }
+}
+
+/// Rewrite `use` leaves that import `old_path` (or items below it)
+///
+/// With `new_path` the leaf is pointed at the new module; without it the leaf
+/// is removed (the file is the destination and now defines the symbol).
+/// Glob imports of the old module get an explicit import when `name` is used.
+fn rewrite_use_items(
+    source: &str,
+    file: &syn::File,
+    module: &[String],
+    old_path: &[String],
+    new_path: Option<&[String]>,
+    name: &str,
+    edits: &mut FileEdits,
+) -> PluginResult<()> {
+    let old_module = &old_path[..old_path.len() - 1];
+    let mut idents = None;
+
+    for item in &file.items {
+        let syn::Item::Use(use_item) = item else {
+            continue;
+        };
+        let (item_start, item_end) = byte_range(source, use_item.span())?;
+        let line_end = source[item_end..]
+            .find('\n')
+            .map(|i| item_end + i + 1)
+            .unwrap_or(source.len());
+        let vis = vis_text(source, &use_item.vis)?;
+
+        for leaf in use_leaves(source, &use_item.tree)? {
+            let Some(absolute) = absolutize(&leaf.segments, module) else {
+                continue;
+            };
+
+            if leaf.glob {
+                if absolute.as_slice() != old_module {
+                    continue;
+                }
+                let file_idents = idents.get_or_insert_with(|| {
+                    let mut set = HashSet::new();
+                    collect_idents(file.to_token_stream(), &mut set);
+                    set
+                });
+                if let (Some(new_path), true) = (new_path, file_idents.contains(name)) {
+                    edits.insert(line_end, format!("{}use {};\n", vis, new_path.join("::")));
+                }
+                continue;
+            }
+
+            if !absolute.starts_with(old_path) {
+                continue;
+            }
+
+            let replacement = new_path.map(|new_path| {
+                let segments: Vec<String> = new_path
+                    .iter()
+                    .chain(absolute[old_path.len()..].iter())
+                    .cloned()
+                    .collect();
+                leaf.render(&vis, &segments)
+            });
+
+            match (leaf.group_member, replacement) {
+                (Some((start, end)), Some(replacement)) => {
+                    edits.delete(start, end);
+                    edits.insert(line_end, format!("{}\n", replacement));
+                }
+                (Some((start, end)), None) => edits.delete(start, end),
+                (None, Some(replacement)) => edits.replace(item_start, item_end, replacement),
+                (None, None) => {
+                    let (start, end) = full_line_range(source, item_start, item_end);
+                    edits.delete(start, end);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace `crate::old::Name` paths written out in code (outside `use` items
+/// and outside `skip` ranges)
+fn rewrite_qualified_paths(
+    source: &str,
+    file: &syn::File,
+    old_path: &[String],
+    new_path: &[String],
+    skip: &[(usize, usize)],
+    edits: &mut FileEdits,
+) -> PluginResult<()> {
+    let old = old_path.join("::");
+    if !source.contains(&old) {
+        return Ok(());
+    }
+    let pattern = regex::Regex::new(&format!(r"\b{}\b", regex::escape(&old)))
+        .map_err(|e| PluginApiError::internal(format!("Invalid path pattern: {}", e)))?;
+
+    let mut excluded = skip.to_vec();
+    for item in &file.items {
+        if matches!(item, syn::Item::Use(_)) {
+            excluded.push(byte_range(source, item.span())?);
+        }
+    }
+
+    let new = new_path.join("::");
+    for found in pattern.find_iter(source) {
+        let inside = excluded
+            .iter()
+            .any(|(start, end)| *start <= found.start() && found.end() <= *end);
+        if !inside {
+            edits.replace(found.start(), found.end(), new.clone());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mill_foundation::protocol::TextEdit;
+    use mill_lang_common::position_to_offset;
+    use std::collections::HashMap;
+
+    fn apply(source: &str, edits: &[&TextEdit]) -> String {
+        let mut result = source.to_string();
+        let mut edits = edits.to_vec();
+        edits.sort_by_key(|e| std::cmp::Reverse((e.location.start_line, e.location.start_column)));
+        for edit in edits {
+            let loc = &edit.location;
+            let start = position_to_offset(&result, loc.start_line, loc.start_column).unwrap();
+            let end = position_to_offset(&result, loc.end_line, loc.end_column).unwrap();
+            result.replace_range(start..end, &edit.new_text);
+        }
+        result
+    }
+
+    fn by_file(plan: &EditPlan) -> HashMap<String, Vec<&TextEdit>> {
+        let mut files: HashMap<String, Vec<&TextEdit>> = HashMap::new();
+        for edit in &plan.edits {
+            let file = edit.file_path.clone().unwrap_or(plan.source_file.clone());
+            files.entry(file).or_default().push(edit);
+        }
+        files
+    }
+
+    #[tokio::test]
+    async fn test_move_function_to_new_module() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        let lib = "pub mod shapes;\npub mod report;\n";
+        let shapes = "use std::collections::HashMap;\n\npub struct Area(pub f64);\n\nfn total(areas: &HashMap<String, Area>) -> f64 {\n    areas.values().map(|a| a.0).sum()\n}\n\npub fn summary(areas: &HashMap<String, Area>) -> String {\n    format!(\"{}\", total(areas))\n}\n";
+        let report = "use crate::shapes::{summary, Area};\n\npub fn print() {\n    let _ = crate::shapes::total;\n}\n";
+        std::fs::write(root.join("src/lib.rs"), lib).unwrap();
+        std::fs::write(root.join("src/shapes.rs"), shapes).unwrap();
+        std::fs::write(root.join("src/report.rs"), report).unwrap();
+
+        let shapes_path = root.join("src/shapes.rs").to_string_lossy().to_string();
+        let dest = root.join("src/math.rs").to_string_lossy().to_string();
+        let plan = plan_symbol_move(shapes, 4, 0, &shapes_path, &dest)
+            .await
+            .unwrap();
+        let files = by_file(&plan);
+
+        assert_eq!(
+            apply("", &files[&dest]),
+            "use std::collections::HashMap;\nuse crate::shapes::Area;\n\npub(crate) fn total(areas: &HashMap<String, Area>) -> f64 {\n    areas.values().map(|a| a.0).sum()\n}\n"
+        );
+        assert_eq!(
+            apply(shapes, &files[&shapes_path]),
+            "use std::collections::HashMap;\nuse crate::math::total;\n\npub struct Area(pub f64);\n\npub fn summary(areas: &HashMap<String, Area>) -> String {\n    format!(\"{}\", total(areas))\n}\n"
+        );
+        let lib_path = root.join("src/lib.rs").to_string_lossy().to_string();
+        assert_eq!(
+            apply(lib, &files[&lib_path]),
+            "pub mod shapes;\npub mod report;\npub mod math;\n"
+        );
+        let report_path = root.join("src/report.rs").to_string_lossy().to_string();
+        assert_eq!(
+            apply(report, &files[&report_path]),
+            "use crate::shapes::{summary, Area};\n\npub fn print() {\n    let _ = crate::math::total;\n}\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move_struct_carries_impls_and_rewrites_group_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        let models = "pub struct User {\n    pub id: u32,\n}\n\nimpl User {\n    pub fn new(id: u32) -> Self {\n        Self { id }\n    }\n}\n\npub struct Team;\n";
+        let service =
+            "use crate::models::{Team, User};\n\npub fn make() -> User {\n    User::new(1)\n}\n";
+        let entities = "pub struct Account;\n";
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "mod entities;\nmod models;\nmod service;\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/models.rs"), models).unwrap();
+        std::fs::write(root.join("src/service.rs"), service).unwrap();
+        std::fs::write(root.join("src/entities.rs"), entities).unwrap();
+
+        let models_path = root.join("src/models.rs").to_string_lossy().to_string();
+        let dest = root.join("src/entities.rs").to_string_lossy().to_string();
+        let plan = plan_symbol_move(models, 0, 0, &models_path, &dest)
+            .await
+            .unwrap();
+        let files = by_file(&plan);
+
+        assert_eq!(apply(models, &files[&models_path]), "pub struct Team;\n");
+        assert_eq!(
+            apply(entities, &files[&dest]),
+            "pub struct Account;\n\npub struct User {\n    pub id: u32,\n}\n\nimpl User {\n    pub fn new(id: u32) -> Self {\n        Self { id }\n    }\n}\n"
+        );
+        let service_path = root.join("src/service.rs").to_string_lossy().to_string();
+        assert_eq!(
+            apply(service, &files[&service_path]),
+            "use crate::models::{Team};\nuse crate::entities::User;\n\npub fn make() -> User {\n    User::new(1)\n}\n"
+        );
+        let lib_path = root.join("src/lib.rs").to_string_lossy().to_string();
+        assert!(!files.contains_key(&lib_path));
+    }
+
+    #[test]
+    fn test_delete_struct_with_impl() {
+        let source = "pub struct A;\n\nimpl A {\n    fn f(&self) {}\n}\n\npub fn keep() {}\n";
+        let plan = plan_symbol_delete(source, 0, 0, "lib.rs").unwrap();
+        let edits: Vec<&TextEdit> = plan.edits.iter().collect();
+        assert_eq!(apply(source, &edits), "pub fn keep() {}\n");
+    }
+
+    #[test]
+    fn test_delete_impl_method_only() {
+        let source = "pub struct A;\n\nimpl A {\n    fn f(&self) {}\n\n    fn g(&self) {}\n}\n";
+        let plan = plan_symbol_delete(source, 3, 4, "lib.rs").unwrap();
+        let edits: Vec<&TextEdit> = plan.edits.iter().collect();
+        assert_eq!(
+            apply(source, &edits),
+            "pub struct A;\n\nimpl A {\n    fn g(&self) {}\n}\n"
+        );
+        assert_eq!(plan.metadata.intent_arguments["symbol"], "f");
+    }
+
+    #[test]
+    fn test_absolutize() {
+        let module = vec!["a".to_string(), "b".to_string()];
+        let path = |s: &str| s.split("::").map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            absolutize(&path("super::c::X"), &module),
+            Some(path("crate::a::c::X"))
+        );
+        assert_eq!(
+            absolutize(&path("self::X"), &module),
+            Some(path("crate::a::b::X"))
+        );
+        assert_eq!(absolutize(&path("std::fmt"), &module), None);
+    }
+}
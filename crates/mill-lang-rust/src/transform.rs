@@ -0,0 +1,700 @@
+//! Rust code transformations using syn AST
+//!
+//! Supported transform kinds:
+//! - `to_async`: add `async` to the signature of the function at the target position
+//! - `if_else_to_match`: rewrite an `if`/`else if` equality chain as a `match`
+//!
+//! The target is the innermost construct containing the position; when the
+//! column is outside every candidate (e.g. in the indentation), the line alone
+//! selects it.
+
+use mill_foundation::protocol::{EditLocation, EditPlan, EditType, TextEdit};
+use mill_lang_common::{
+    position_to_offset, refactoring::edit_plan_builder::EditPlanBuilder, IndentationDetector,
+    LineExtractor,
+};
+use mill_plugin_api::{PluginApiError, PluginResult};
+use proc_macro2::{LineColumn, Span};
+use quote::ToTokens;
+use syn::{spanned::Spanned, visit::Visit, BinOp, Expr, ExprIf};
+
+/// Transform kinds implemented by the Rust plugin
+pub const SUPPORTED_TRANSFORMS: &[&str] = &["to_async", "if_else_to_match"];
+
+/// Plan a code transformation for Rust
+pub fn plan_transform(
+    source: &str,
+    kind: &str,
+    line: u32,
+    character: u32,
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let file = syn::parse_file(source)
+        .map_err(|e| PluginApiError::parse(format!("Failed to parse Rust source: {}", e)))?;
+
+    match kind {
+        "to_async" => plan_to_async(&file, (line, character), file_path),
+        "if_else_to_match" => plan_if_else_to_match(source, &file, (line, character), file_path),
+        other => Err(PluginApiError::not_supported(format!(
+            "Rust transform '{}' (supported: {})",
+            other,
+            SUPPORTED_TRANSFORMS.join(", ")
+        ))),
+    }
+}
+
+// ============================================================================
+// to_async
+// ============================================================================
+
+/// A function signature found in the file together with the positions it spans
+struct FnCandidate {
+    sig: syn::Signature,
+    start: (u32, u32),
+    end: (u32, u32),
+}
+
+struct FnCollector {
+    candidates: Vec<FnCandidate>,
+}
+
+impl FnCollector {
+    fn push(&mut self, sig: &syn::Signature, span: Span) {
+        self.candidates.push(FnCandidate {
+            sig: sig.clone(),
+            start: to_position(span.start()),
+            end: to_position(span.end()),
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for FnCollector {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.push(&i.sig, i.span());
+        syn::visit::visit_item_fn(self, i);
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.push(&i.sig, i.span());
+        syn::visit::visit_impl_item_fn(self, i);
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.push(&i.sig, i.span());
+        syn::visit::visit_trait_item_fn(self, i);
+    }
+}
+
+fn plan_to_async(file: &syn::File, at: (u32, u32), file_path: &str) -> PluginResult<EditPlan> {
+    let mut collector = FnCollector {
+        candidates: Vec::new(),
+    };
+    collector.visit_file(file);
+
+    // Innermost function containing the position, else the innermost spanning its line
+    let line = at.0;
+    let candidate = collector
+        .candidates
+        .iter()
+        .filter(|c| c.start <= at && at < c.end)
+        .max_by_key(|c| c.start)
+        .or_else(|| {
+            collector
+                .candidates
+                .iter()
+                .filter(|c| c.start.0 <= line && line <= c.end.0)
+                .min_by_key(|c| (c.end.0 - c.start.0, c.start))
+        })
+        .ok_or_else(|| {
+            PluginApiError::invalid_input(format!("No function found at line {}", line + 1))
+        })?;
+
+    let sig = &candidate.sig;
+    let name = sig.ident.to_string();
+
+    if sig.asyncness.is_some() {
+        return Err(PluginApiError::invalid_input(format!(
+            "Function '{}' is already async",
+            name
+        )));
+    }
+    if sig.constness.is_some() {
+        return Err(PluginApiError::invalid_input(format!(
+            "Function '{}' is a `const fn` and cannot be made async",
+            name
+        )));
+    }
+
+    // `async` goes after `const` and before `unsafe`/`extern`/`fn`
+    let anchor = if let Some(unsafety) = &sig.unsafety {
+        unsafety.span
+    } else if let Some(abi) = &sig.abi {
+        abi.extern_token.span
+    } else {
+        sig.fn_token.span
+    };
+    let at = to_position(anchor.start());
+
+    let edit = TextEdit {
+        file_path: None,
+        edit_type: EditType::Insert,
+        location: EditLocation {
+            start_line: at.0,
+            start_column: at.1,
+            end_line: at.0,
+            end_column: at.1,
+        },
+        original_text: String::new(),
+        new_text: "async ".to_string(),
+        priority: 100,
+        description: format!("Make '{}' async", name),
+    };
+
+    Ok(EditPlanBuilder::new(file_path, "to_async")
+        .with_edits(vec![edit])
+        .with_syntax_validation("Verify Rust syntax is valid after transform")
+        .with_intent_args(serde_json::json!({
+            "function_name": name,
+            "kind": "to_async",
+        }))
+        .with_complexity(2)
+        .with_impact_area("code_transform")
+        .build())
+}
+
+// ============================================================================
+// if_else_to_match
+// ============================================================================
+
+/// Collects the `if` expressions starting on the target line, skipping
+/// `else if` links so that a chain is always converted from its head.
+struct IfFinder {
+    target_line: u32,
+    chain_links: Vec<LineColumn>,
+    heads: Vec<ExprIf>,
+}
+
+impl<'ast> Visit<'ast> for IfFinder {
+    fn visit_expr_if(&mut self, node: &'ast ExprIf) {
+        let start = node.if_token.span.start();
+        let is_link = self.chain_links.contains(&start);
+
+        if !is_link && to_position(start).0 == self.target_line {
+            self.heads.push(node.clone());
+        }
+
+        if let Some((_, else_branch)) = &node.else_branch {
+            if let Expr::If(inner) = else_branch.as_ref() {
+                self.chain_links.push(inner.if_token.span.start());
+            }
+        }
+
+        syn::visit::visit_expr_if(self, node);
+    }
+}
+
+fn plan_if_else_to_match(
+    source: &str,
+    file: &syn::File,
+    at: (u32, u32),
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let line = at.0;
+    let mut finder = IfFinder {
+        target_line: line,
+        chain_links: Vec::new(),
+        heads: Vec::new(),
+    };
+    finder.visit_file(file);
+
+    // Innermost chain containing the position, else the first one on the line
+    let head = finder
+        .heads
+        .iter()
+        .filter(|head| {
+            let span = head.span();
+            to_position(span.start()) <= at && at < to_position(span.end())
+        })
+        .max_by_key(|head| to_position(head.span().start()))
+        .or_else(|| finder.heads.first())
+        .ok_or_else(|| {
+            PluginApiError::invalid_input(format!(
+                "No `if` expression starts at line {} (point at the head of the chain)",
+                line + 1
+            ))
+        })?;
+
+    let (indent_char, indent_size) = IndentationDetector::detect(source);
+    let unit = IndentationDetector::indent_string(1, indent_char, indent_size);
+    let base_indent = LineExtractor::get_indentation_str(source, line);
+    let arm_indent = format!("{}{}", base_indent, unit);
+
+    let mut subject = ChainSubject::default();
+    let mut arms = Vec::new();
+    let mut current = head;
+
+    let fallback = loop {
+        let mut patterns = Vec::new();
+        collect_patterns(source, &current.cond, &mut subject, &mut patterns)?;
+        let body = reindent(&span_text(source, current.then_branch.span())?, &unit);
+        arms.push(format!(
+            "{}{} => {}",
+            arm_indent,
+            patterns.join(" | "),
+            body
+        ));
+
+        match &current.else_branch {
+            None => break "{}".to_string(),
+            Some((_, else_branch)) => match else_branch.as_ref() {
+                Expr::If(inner) => current = inner,
+                Expr::Block(block) => {
+                    break reindent(&span_text(source, block.block.span())?, &unit);
+                }
+                _ => {
+                    return Err(PluginApiError::invalid_input(
+                        "Unsupported `else` branch in if/else chain",
+                    ))
+                }
+            },
+        }
+    };
+    arms.push(format!("{}_ => {}", arm_indent, fallback));
+
+    let (_, scrutinee) = subject
+        .scrutinee
+        .ok_or_else(|| PluginApiError::invalid_input("Could not determine match scrutinee"))?;
+    let mut scrutinee_text = span_text(source, scrutinee.span())?;
+
+    // String literal patterns only match a `&str`, so an owned `String` is borrowed
+    if subject.compares_str {
+        match str_binding(file, &scrutinee, head.if_token.span.start()) {
+            StrBinding::Str => {}
+            StrBinding::Owned => scrutinee_text.push_str(".as_str()"),
+            StrBinding::Unknown => {
+                return Err(PluginApiError::invalid_input(format!(
+                    "`{}` is compared with string literals, but it is not a `&str` or `String` \
+                     variable declared with its type in the enclosing function",
+                    scrutinee_text
+                )))
+            }
+        }
+    }
+
+    let new_text = format!(
+        "match {} {{\n{}\n{}}}",
+        scrutinee_text,
+        arms.join("\n"),
+        base_indent
+    );
+
+    let span = head.span();
+    let start = to_position(span.start());
+    let end = to_position(span.end());
+    let original_text = span_text(source, span)?;
+
+    let edit = TextEdit {
+        file_path: None,
+        edit_type: EditType::Replace,
+        location: EditLocation {
+            start_line: start.0,
+            start_column: start.1,
+            end_line: end.0,
+            end_column: end.1,
+        },
+        original_text,
+        new_text,
+        priority: 100,
+        description: "Convert if/else chain to match".to_string(),
+    };
+
+    Ok(EditPlanBuilder::new(file_path, "if_else_to_match")
+        .with_edits(vec![edit])
+        .with_syntax_validation("Verify Rust syntax is valid after transform")
+        .with_intent_args(serde_json::json!({
+            "kind": "if_else_to_match",
+            "arms": arms.len(),
+        }))
+        .with_complexity(4)
+        .with_impact_area("code_transform")
+        .build())
+}
+
+/// What the comparisons of an if/else chain have in common
+#[derive(Default)]
+struct ChainSubject {
+    /// Normalized token string and expression compared in every branch
+    scrutinee: Option<(String, Expr)>,
+    /// Whether any branch compares against a string literal
+    compares_str: bool,
+}
+
+/// Collect match patterns from a condition of the form `x == A || x == B`.
+///
+/// Every comparison in the chain must use the same subject expression.
+fn collect_patterns(
+    source: &str,
+    cond: &Expr,
+    subject: &mut ChainSubject,
+    patterns: &mut Vec<String>,
+) -> PluginResult<()> {
+    match cond {
+        Expr::Paren(paren) => collect_patterns(source, &paren.expr, subject, patterns),
+        Expr::Binary(binary) if matches!(binary.op, BinOp::Or(_)) => {
+            collect_patterns(source, &binary.left, subject, patterns)?;
+            collect_patterns(source, &binary.right, subject, patterns)
+        }
+        Expr::Binary(binary) if matches!(binary.op, BinOp::Eq(_)) => {
+            let (compared, pattern) = if is_pattern_expr(&binary.right) {
+                (&binary.left, &binary.right)
+            } else if is_pattern_expr(&binary.left) {
+                (&binary.right, &binary.left)
+            } else {
+                return Err(PluginApiError::invalid_input(format!(
+                    "Comparison `{}` has no literal or constant side usable as a match pattern",
+                    span_text(source, binary.span())?
+                )));
+            };
+
+            let key = compared.to_token_stream().to_string();
+            match &subject.scrutinee {
+                Some((existing, _)) if *existing != key => {
+                    return Err(PluginApiError::invalid_input(
+                        "All branches must compare the same expression to be converted to a match",
+                    ));
+                }
+                Some(_) => {}
+                None => subject.scrutinee = Some((key, compared.as_ref().clone())),
+            }
+
+            if let Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(_),
+                ..
+            }) = pattern.as_ref()
+            {
+                subject.compares_str = true;
+            }
+            patterns.push(span_text(source, pattern.span())?);
+            Ok(())
+        }
+        _ => Err(PluginApiError::invalid_input(format!(
+            "Condition `{}` is not an equality comparison",
+            span_text(source, cond.span())?
+        ))),
+    }
+}
+
+/// Whether an expression is valid as a match pattern without introducing a binding
+fn is_pattern_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(_) => true,
+        Expr::Unary(unary) => {
+            matches!(unary.op, syn::UnOp::Neg(_)) && matches!(unary.expr.as_ref(), Expr::Lit(_))
+        }
+        // Single lowercase identifiers would become catch-all bindings in a match
+        Expr::Path(path) => {
+            path.qself.is_none()
+                && (path.path.segments.len() > 1
+                    || path
+                        .path
+                        .segments
+                        .first()
+                        .and_then(|s| s.ident.to_string().chars().next())
+                        .map(|c| c.is_uppercase())
+                        .unwrap_or(false))
+        }
+        _ => false,
+    }
+}
+
+/// How a scrutinee compared with string literals is declared
+enum StrBinding {
+    /// `&str`, matched as is
+    Str,
+    /// `String` or a reference to one, matched through `as_str()`
+    Owned,
+    /// Not a local variable, or declared without a type
+    Unknown,
+}
+
+/// Look up the declared type of a variable scrutinee used by the `if` at `at`
+///
+/// Only the parameters and typed `let` bindings of the innermost enclosing
+/// function are considered; there is no type inference.
+fn str_binding(file: &syn::File, scrutinee: &Expr, at: LineColumn) -> StrBinding {
+    let Expr::Path(path) = scrutinee else {
+        return StrBinding::Unknown;
+    };
+    let Some(name) = path.path.get_ident().filter(|_| path.qself.is_none()) else {
+        return StrBinding::Unknown;
+    };
+
+    let mut bodies = BodyCollector {
+        at: to_position(at),
+        innermost: None,
+    };
+    bodies.visit_file(file);
+    let Some((sig, block)) = bodies.innermost else {
+        return StrBinding::Unknown;
+    };
+
+    let mut bindings = BindingFinder {
+        name,
+        at: to_position(at),
+        ty: None,
+    };
+    for input in &sig.inputs {
+        if let syn::FnArg::Typed(typed) = input {
+            bindings.bind(&typed.pat, Some(&typed.ty));
+        }
+    }
+    bindings.visit_block(&block);
+
+    bindings.ty.map_or(StrBinding::Unknown, |ty| str_kind(&ty))
+}
+
+/// Classify a declared type as `&str`, `String` or neither
+fn str_kind(ty: &syn::Type) -> StrBinding {
+    let is_named = |ty: &syn::Type, wanted: &str| match ty {
+        syn::Type::Path(path) => path.qself.is_none() && path.path.is_ident(wanted),
+        _ => false,
+    };
+    match ty {
+        syn::Type::Paren(paren) => str_kind(&paren.elem),
+        syn::Type::Group(group) => str_kind(&group.elem),
+        syn::Type::Reference(reference) if is_named(&reference.elem, "str") => StrBinding::Str,
+        syn::Type::Reference(reference) if is_named(&reference.elem, "String") => StrBinding::Owned,
+        ty if is_named(ty, "String") => StrBinding::Owned,
+        _ => StrBinding::Unknown,
+    }
+}
+
+/// Finds the innermost function body containing a position
+struct BodyCollector {
+    at: (u32, u32),
+    innermost: Option<(syn::Signature, syn::Block)>,
+}
+
+impl BodyCollector {
+    fn consider(&mut self, sig: &syn::Signature, block: &syn::Block) {
+        let span = block.span();
+        if to_position(span.start()) <= self.at && self.at < to_position(span.end()) {
+            // Visiting is outside-in, so later matches are nested deeper
+            self.innermost = Some((sig.clone(), block.clone()));
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for BodyCollector {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.consider(&i.sig, &i.block);
+        syn::visit::visit_item_fn(self, i);
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.consider(&i.sig, &i.block);
+        syn::visit::visit_impl_item_fn(self, i);
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        if let Some(block) = &i.default {
+            self.consider(&i.sig, block);
+        }
+        syn::visit::visit_trait_item_fn(self, i);
+    }
+}
+
+/// Tracks the declared type of the last binding of `name` in scope before `at`
+struct BindingFinder<'a> {
+    name: &'a syn::Ident,
+    at: (u32, u32),
+    ty: Option<syn::Type>,
+}
+
+impl BindingFinder<'_> {
+    /// Record a binding; an untyped one shadows any earlier type
+    fn bind(&mut self, pat: &syn::Pat, ty: Option<&syn::Type>) {
+        match pat {
+            syn::Pat::Ident(ident) if ident.ident == *self.name => self.ty = ty.cloned(),
+            syn::Pat::Type(typed) => self.bind(&typed.pat, Some(&typed.ty)),
+            _ => {}
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for BindingFinder<'_> {
+    fn visit_block(&mut self, block: &'ast syn::Block) {
+        // Bindings in blocks that do not contain the `if` are out of scope
+        let span = block.span();
+        if to_position(span.start()) <= self.at && self.at < to_position(span.end()) {
+            syn::visit::visit_block(self, block);
+        }
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        if to_position(local.span().end()) <= self.at {
+            self.bind(&local.pat, None);
+        }
+    }
+
+    fn visit_item(&mut self, _item: &'ast syn::Item) {}
+}
+
+/// Convert a 1-based proc-macro2 position to 0-based (line, column)
+fn to_position(pos: LineColumn) -> (u32, u32) {
+    (pos.line.saturating_sub(1) as u32, pos.column as u32)
+}
+
+/// Source text covered by a span
+fn span_text(source: &str, span: Span) -> PluginResult<String> {
+    let (start_line, start_col) = to_position(span.start());
+    let (end_line, end_col) = to_position(span.end());
+    let start = position_to_offset(source, start_line, start_col);
+    let end = position_to_offset(source, end_line, end_col);
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end => Ok(source[start..end].to_string()),
+        _ => Err(PluginApiError::internal(
+            "Span is outside of the source text",
+        )),
+    }
+}
+
+/// Indent every line after the first by one extra level
+fn reindent(text: &str, unit: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, l)| {
+            if i == 0 || l.trim().is_empty() {
+                l.to_string()
+            } else {
+                format!("{}{}", unit, l)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply the single edit of a transform plan to the source
+    fn apply(source: &str, plan: &EditPlan) -> String {
+        let loc = &plan.edits[0].location;
+        let start = position_to_offset(source, loc.start_line, loc.start_column).unwrap();
+        let end = position_to_offset(source, loc.end_line, loc.end_column).unwrap();
+        format!(
+            "{}{}{}",
+            &source[..start],
+            plan.edits[0].new_text,
+            &source[end..]
+        )
+    }
+
+    #[test]
+    fn test_to_async_plain_fn() {
+        let source = "pub fn load(path: &str) -> String {\n    path.to_string()\n}\n";
+        let plan = plan_transform(source, "to_async", 1, 0, "lib.rs").unwrap();
+        assert_eq!(
+            apply(source, &plan),
+            "pub async fn load(path: &str) -> String {\n    path.to_string()\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_async_unsafe_method() {
+        let source = "impl Foo {\n    pub unsafe fn run(&self) {}\n}\n";
+        let plan = plan_transform(source, "to_async", 1, 0, "lib.rs").unwrap();
+        assert_eq!(
+            apply(source, &plan),
+            "impl Foo {\n    pub async unsafe fn run(&self) {}\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_async_uses_column() {
+        let source = "fn first() {} fn second() {}\n";
+        let plan = plan_transform(source, "to_async", 0, 16, "lib.rs").unwrap();
+        assert_eq!(apply(source, &plan), "fn first() {} async fn second() {}\n");
+    }
+
+    #[test]
+    fn test_to_async_rejects_async_fn() {
+        let source = "async fn run() {}\n";
+        assert!(plan_transform(source, "to_async", 0, 0, "lib.rs").is_err());
+    }
+
+    #[test]
+    fn test_if_else_to_match() {
+        let source = r#"fn f(code: u32) -> &'static str {
+    if code == 200 {
+        "ok"
+    } else if code == 404 || code == 410 {
+        "gone"
+    } else {
+        "error"
+    }
+}
+"#;
+        let plan = plan_transform(source, "if_else_to_match", 1, 4, "lib.rs").unwrap();
+        let expected = r#"fn f(code: u32) -> &'static str {
+    match code {
+        200 => {
+            "ok"
+        }
+        404 | 410 => {
+            "gone"
+        }
+        _ => {
+            "error"
+        }
+    }
+}
+"#;
+        assert_eq!(apply(source, &plan), expected);
+    }
+
+    #[test]
+    fn test_if_else_to_match_uses_column() {
+        let source = "fn f(a: u32, b: u32) {\n    if a == 1 { if b == 2 { g() } }\n}\n";
+        let plan = plan_transform(source, "if_else_to_match", 1, 17, "lib.rs").unwrap();
+        assert!(plan.edits[0].original_text.starts_with("if b == 2"));
+        assert!(plan.edits[0].new_text.starts_with("match b {"));
+    }
+
+    #[test]
+    fn test_if_else_to_match_borrows_string_scrutinee() {
+        let source = r#"fn f(s: String) -> u32 {
+    if s == "a" {
+        1
+    } else if s == "b" {
+        2
+    } else {
+        3
+    }
+}
+"#;
+        let plan = plan_transform(source, "if_else_to_match", 1, 4, "lib.rs").unwrap();
+        assert!(apply(source, &plan).contains("match s.as_str() {\n        \"a\" => {"));
+
+        let borrowed = source.replace("s: String", "s: &str");
+        let plan = plan_transform(&borrowed, "if_else_to_match", 1, 4, "lib.rs").unwrap();
+        assert!(apply(&borrowed, &plan).contains("match s {"));
+
+        // A later untyped binding hides the declared type
+        let shadowed = source.replace("-> u32 {\n", "-> u32 {\n    let s = s.trim();\n");
+        assert!(plan_transform(&shadowed, "if_else_to_match", 2, 4, "lib.rs").is_err());
+    }
+
+    #[test]
+    fn test_if_else_to_match_rejects_binding_pattern() {
+        let source = "fn f(a: u32, b: u32) {\n    if a == b {\n    }\n}\n";
+        assert!(plan_transform(source, "if_else_to_match", 1, 4, "lib.rs").is_err());
+    }
+
+    #[test]
+    fn test_if_else_to_match_rejects_mixed_subjects() {
+        let source = "fn f(a: u32, b: u32) {\n    if a == 1 {\n    } else if b == 2 {\n    }\n}\n";
+        assert!(plan_transform(source, "if_else_to_match", 1, 4, "lib.rs").is_err());
+    }
+}
@@ -4,6 +4,7 @@
 //! synchronous methods for manipulating Cargo.toml workspace manifests.
 
 use async_trait::async_trait;
+use mill_lang_common::{push_toml_array_value, remove_toml_array_values};
 use mill_plugin_api::workspace_support::WorkspaceSupport;
 use std::path::Path;
 use toml_edit::DocumentMut;
@@ -334,7 +335,7 @@ fn add_workspace_member_impl(content: &str, member: &str) -> Result<String, Stri
     let member_exists = members.iter().any(|v| v.as_str() == Some(member));
 
     if !member_exists {
-        members.push(member);
+        push_toml_array_value(members, member);
         debug!(member = %member, "Added new member to workspace");
     } else {
         debug!(member = %member, "Member already exists in workspace");
@@ -352,10 +353,7 @@ fn remove_workspace_member_impl(content: &str, member: &str) -> Result<String, S
     if let Some(workspace) = doc.get_mut("workspace").and_then(|w| w.as_table_mut()) {
         if let Some(members) = workspace.get_mut("members").and_then(|m| m.as_array_mut()) {
             // Find and remove the member
-            let original_len = members.len();
-            members.retain(|v| v.as_str() != Some(member));
-
-            if members.len() < original_len {
+            if remove_toml_array_values(members, |v| v.as_str() == Some(member)) {
                 debug!(member = %member, "Removed member from workspace");
             } else {
                 debug!(member = %member, "Member not found in workspace");
@@ -10,7 +10,9 @@ use regex::Regex;
 pub const DEFAULT_TS_VERSION: &str = "^5.0.0";
 
 /// Parser version for import graph metadata
-#[allow(dead_code)] // Future enhancement: Parser versioning
+///
+/// Persisted parse results are keyed by this version, so bump it whenever the
+/// import or symbol output changes.
 pub const PARSER_VERSION: &str = "0.1.0";
 
 /// Node runtime command
@@ -0,0 +1,1639 @@
+//! Inline-function refactoring for TypeScript/JavaScript using the SWC AST
+//!
+//! Calls to a function defined in the same file (`function f() {}` or
+//! `const f = (...) => ...`) are replaced by its body. Identifier and literal
+//! arguments are substituted for the parameters; anything else is bound with
+//! `const` so it is evaluated once and in the original order. How `return`
+//! is rewritten depends on where the call sits: `return f(x);` keeps the
+//! returns, `const r = f(x);` assigns the result, and early returns become
+//! `break`s out of a labeled block (`inline_f: { ... }`).
+
+use crate::refactoring::parse_module_with_source_map;
+use crate::reorder::SourceText;
+use mill_foundation::protocol::EditPlan;
+use mill_lang_common::position_to_offset;
+use mill_lang_common::refactoring::edit_plan_builder::EditPlanBuilder;
+use mill_lang_common::refactoring::file_edits::{
+    full_line_range, line_indent, reindent, text_with, FileEdits,
+};
+use mill_plugin_api::{PluginApiError, PluginResult};
+use std::collections::{HashMap, HashSet};
+use swc_common::{Span, Spanned};
+use swc_ecma_ast::*;
+use swc_ecma_visit::{Visit, VisitWith};
+
+type Range = (usize, usize);
+
+/// Plan inlining the function called (or defined) at the given position
+pub fn plan_inline_function(
+    source: &str,
+    line: u32,
+    character: u32,
+    inline_all: bool,
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let (module, cm) = parse_module_with_source_map(source, file_path)?;
+    let text = SourceText { source, cm };
+    let cursor = position_to_offset(source, line, character)
+        .ok_or_else(|| PluginApiError::invalid_input("Position is outside of the file"))?;
+
+    let mut index = ModuleIndex::new(&text);
+    module.visit_with(&mut index);
+
+    // Resolve the function from a call under the cursor, or from its definition
+    let cursor_call = index
+        .calls
+        .iter()
+        .filter(|call| call.range.0 <= cursor && cursor <= call.range.1)
+        .min_by_key(|call| call.range.1 - call.range.0)
+        .cloned();
+    let (definition, target_call) = match cursor_call {
+        Some(call) => {
+            let definition = index.find_definition(&call.name)?.ok_or_else(|| {
+                PluginApiError::invalid_input(format!(
+                    "'{}' is not a function defined in this file",
+                    call.name
+                ))
+            })?;
+            if index.is_shadowed(definition, &call) {
+                return Err(PluginApiError::invalid_input(format!(
+                    "'{}' at this call refers to a local variable, not the function defined in this file",
+                    call.name
+                )));
+            }
+            (definition, Some(call))
+        }
+        None => {
+            let definition = index
+                .definitions
+                .iter()
+                .find(|d| d.name_line == line)
+                .ok_or_else(|| {
+                    if index.method_lines.contains(&line) {
+                        PluginApiError::invalid_input(
+                            "Methods cannot be inlined; only functions declared with `function` or assigned to a `const` are supported",
+                        )
+                    } else {
+                        PluginApiError::invalid_input(format!(
+                            "No function call or definition found at line {}",
+                            line + 1
+                        ))
+                    }
+                })?;
+            index.find_definition(&definition.name)?;
+            (definition, None)
+        }
+    };
+    let name = definition.name.clone();
+    let body = analyze_body(&text, definition)?;
+
+    let mut calls: Vec<CallSite> = match (&target_call, inline_all) {
+        (Some(call), false) => vec![call.clone()],
+        _ => index
+            .calls
+            .iter()
+            .filter(|call| call.name == name && !index.is_shadowed(definition, call))
+            .cloned()
+            .collect(),
+    };
+    if calls.is_empty() {
+        return Err(PluginApiError::invalid_input(format!(
+            "No calls to '{}' found in this file",
+            name
+        )));
+    }
+
+    // Locals of the enclosing functions the body may capture
+    let enclosing: HashSet<String> = definition
+        .scopes
+        .iter()
+        .filter_map(|scope| index.scope_names.get(scope))
+        .flat_map(|names| names.declared.iter().cloned())
+        .collect();
+    let mut captured: Vec<&String> = body.free.intersection(&enclosing).collect();
+    captured.sort();
+
+    // Inner calls first, so a call nested in another call's arguments is
+    // inlined into that argument's text
+    calls.sort_by_key(|call| call.range.1 - call.range.0);
+    let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+    for call in &calls {
+        let call_line = position_line(source, call.range.0);
+        let shared = call.scopes.starts_with(&definition.scopes);
+        if let (false, Some(local)) = (shared, captured.first()) {
+            return Err(PluginApiError::invalid_input(format!(
+                "Cannot inline call at line {}: '{}' captures '{}' from its enclosing function",
+                call_line, name, local
+            )));
+        }
+
+        let prefix = common_prefix(&call.scopes, &definition.scopes);
+        let caller_locals: HashSet<&String> = call.scopes[prefix..]
+            .iter()
+            .filter_map(|scope| index.scope_names.get(scope))
+            .flat_map(|names| names.declared.iter())
+            .collect();
+        if let Some(shadowed) = body.free.iter().filter(|f| caller_locals.contains(f)).min() {
+            return Err(PluginApiError::invalid_input(format!(
+                "Cannot inline call at line {}: '{}' used by '{}' is shadowed by a local variable there",
+                call_line, shadowed, name
+            )));
+        }
+
+        let mut caller_names: HashSet<String> = index.module_names.declared.clone();
+        if call.scopes.is_empty() {
+            caller_names.extend(index.module_names.referenced.iter().cloned());
+        }
+        for names in call.scopes.iter().filter_map(|s| index.scope_names.get(s)) {
+            caller_names.extend(names.declared.iter().cloned());
+            caller_names.extend(names.referenced.iter().cloned());
+        }
+        let crowded = calls
+            .iter()
+            .filter(|other| other.scopes.last() == call.scopes.last())
+            .count()
+            > 1;
+
+        let (range, replacement) = inline_call(
+            &text,
+            definition,
+            &body,
+            call,
+            &replacements,
+            &caller_names,
+            crowded,
+        )?;
+        if let Some(overlap) = replacements.iter().find(|(start, end, _)| {
+            *start < range.1 && range.0 < *end && !(range.0 <= *start && *end <= range.1)
+        }) {
+            return Err(PluginApiError::invalid_input(format!(
+                "Cannot inline call at line {}: it overlaps the inlined call at line {}",
+                call_line,
+                position_line(source, overlap.0)
+            )));
+        }
+        replacements.retain(|(start, end, _)| !(range.0 <= *start && *end <= range.1));
+        replacements.push((range.0, range.1, replacement));
+    }
+
+    let mut edits = FileEdits::new(source, None);
+    for (start, end, text) in replacements {
+        edits.replace(start, end, text);
+    }
+
+    // Remove the definition once nothing else refers to it
+    let mut notes = Vec::new();
+    let mut definition_removed = false;
+    if inline_all {
+        let mut counter = ReferenceCounter {
+            name: name.clone(),
+            count: 0,
+        };
+        module.visit_with(&mut counter);
+        if definition.exported || index.exported_names.contains(&name) {
+            notes.push(format!(
+                "'{}' is exported and may be used by other modules; its definition was kept",
+                name
+            ));
+        } else if counter.count > calls.len() {
+            notes.push(format!(
+                "'{}' is still referenced in this file (e.g. passed as a value); its definition was kept",
+                name
+            ));
+        } else {
+            let (start, end) = range_of(&text, definition.statement);
+            let (start, end) = full_line_range(source, start, end);
+            edits.delete(start, end);
+            definition_removed = true;
+        }
+    }
+
+    Ok(EditPlanBuilder::new(file_path, "inline_function")
+        .with_edits(edits.into_text_edits(&format!("Inline function '{}'", name)))
+        .with_syntax_validation("Verify syntax is valid after inlining")
+        .with_intent_args(serde_json::json!({
+            "function": name,
+            "calls_inlined": calls.len(),
+            "definition_removed": definition_removed,
+            "notes": notes,
+        }))
+        .with_complexity(3)
+        .with_impact_area("function_inlining")
+        .build())
+}
+
+fn range_of(text: &SourceText, span: Span) -> Range {
+    (text.offset(span.lo), text.offset(span.hi))
+}
+
+fn position_line(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+fn common_prefix(a: &[Span], b: &[Span]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+// ============================================================================
+// Module index: definitions, call sites and scopes
+// ============================================================================
+
+/// A function that can be inlined
+struct Definition {
+    name: String,
+    name_line: u32,
+    params: Vec<Pat>,
+    body: Option<DefinitionBody>,
+    is_async: bool,
+    is_generator: bool,
+    generic: bool,
+    return_type: Option<String>,
+    /// Declaration statement removed once every call is inlined
+    statement: Span,
+    exported: bool,
+    /// Enclosing functions, outermost first
+    scopes: Vec<Span>,
+}
+
+enum DefinitionBody {
+    Block(BlockStmt),
+    Expr(Box<Expr>),
+}
+
+#[derive(Clone)]
+struct CallSite {
+    name: String,
+    range: Range,
+    args: Vec<(Range, Expr)>,
+    spread: bool,
+    context: CallContext,
+    /// Whether the call sits where an expression never needs parentheses
+    standalone: bool,
+    /// Whether the call starts a statement or arrow body, where `{` or
+    /// `function` would be misread
+    at_start: bool,
+    /// Enclosing functions, outermost first
+    scopes: Vec<Span>,
+}
+
+#[derive(Clone)]
+enum CallContext {
+    /// `f(a);`
+    Statement { range: Range, in_list: bool },
+    /// `return f(a);`
+    Return { range: Range, in_list: bool },
+    /// `const r = f(a);`
+    Declaration {
+        range: Range,
+        in_list: bool,
+        kind: &'static str,
+        name: String,
+        ty: String,
+    },
+    /// Anywhere else
+    Expression,
+}
+
+/// Names declared and referenced within a node
+#[derive(Default)]
+struct Names {
+    declared: HashSet<String>,
+    referenced: HashSet<String>,
+    /// Skip the contents of nested functions
+    shallow: bool,
+}
+
+impl Visit for Names {
+    fn visit_function(&mut self, node: &Function) {
+        if !self.shallow {
+            node.visit_children_with(self);
+        }
+    }
+
+    fn visit_arrow_expr(&mut self, node: &ArrowExpr) {
+        if !self.shallow {
+            node.visit_children_with(self);
+        }
+    }
+
+    fn visit_binding_ident(&mut self, node: &BindingIdent) {
+        self.declared.insert(node.id.sym.to_string());
+    }
+
+    fn visit_fn_decl(&mut self, node: &FnDecl) {
+        self.declared.insert(node.ident.sym.to_string());
+        node.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, node: &ClassDecl) {
+        self.declared.insert(node.ident.sym.to_string());
+        node.visit_children_with(self);
+    }
+
+    fn visit_expr(&mut self, node: &Expr) {
+        if let Expr::Ident(ident) = node {
+            self.referenced.insert(ident.sym.to_string());
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_prop(&mut self, node: &Prop) {
+        if let Prop::Shorthand(ident) = node {
+            self.referenced.insert(ident.sym.to_string());
+        }
+        node.visit_children_with(self);
+    }
+}
+
+struct ModuleIndex<'a> {
+    text: &'a SourceText<'a>,
+    definitions: Vec<Definition>,
+    calls: Vec<CallSite>,
+    method_lines: HashSet<u32>,
+    exported_names: HashSet<String>,
+    scopes: Vec<Span>,
+    scope_names: HashMap<Span, Names>,
+    module_names: Names,
+    list_statements: HashSet<Span>,
+    contexts: HashMap<Span, CallContext>,
+    standalone: HashSet<Span>,
+    at_start: HashSet<Span>,
+    in_export: bool,
+}
+
+impl<'a> ModuleIndex<'a> {
+    fn new(text: &'a SourceText<'a>) -> Self {
+        Self {
+            text,
+            definitions: Vec::new(),
+            calls: Vec::new(),
+            method_lines: HashSet::new(),
+            exported_names: HashSet::new(),
+            scopes: Vec::new(),
+            scope_names: HashMap::new(),
+            module_names: Names {
+                shallow: true,
+                ..Names::default()
+            },
+            list_statements: HashSet::new(),
+            contexts: HashMap::new(),
+            standalone: HashSet::new(),
+            at_start: HashSet::new(),
+            in_export: false,
+        }
+    }
+
+    fn find_definition(&self, name: &str) -> PluginResult<Option<&Definition>> {
+        let mut matching = self.definitions.iter().filter(|d| d.name == name);
+        let first = matching.next();
+        if first.is_some() && matching.next().is_some() {
+            return Err(PluginApiError::invalid_input(format!(
+                "'{}' is defined more than once in this file; inline from a specific call inside its scope is not supported",
+                name
+            )));
+        }
+        Ok(first)
+    }
+
+    /// Whether `call` resolves to a local binding rather than `definition`
+    fn is_shadowed(&self, definition: &Definition, call: &CallSite) -> bool {
+        let prefix = common_prefix(&call.scopes, &definition.scopes);
+        call.scopes[prefix..].iter().any(|scope| {
+            self.scope_names
+                .get(scope)
+                .is_some_and(|names| names.declared.contains(&call.name))
+        })
+    }
+
+    fn enter_scope(
+        &mut self,
+        span: Span,
+        node: &dyn Fn(&mut Names),
+        visit: impl FnOnce(&mut Self),
+    ) {
+        let mut names = Names::default();
+        node(&mut names);
+        self.scope_names.insert(span, names);
+        self.scopes.push(span);
+        visit(self);
+        self.scopes.pop();
+    }
+
+    fn mark_list(&mut self, stmts: &[Stmt]) {
+        self.list_statements.extend(stmts.iter().map(|s| s.span()));
+    }
+
+    fn add_definition(
+        &mut self,
+        name: &Ident,
+        function: DefinitionShape,
+        statement: Span,
+        exported: bool,
+    ) {
+        self.definitions.push(Definition {
+            name: name.sym.to_string(),
+            name_line: self.text.line(name.span.lo),
+            params: function.params,
+            body: function.body,
+            is_async: function.is_async,
+            is_generator: function.is_generator,
+            generic: function.generic,
+            return_type: function
+                .return_type
+                .map(|ty| self.text.text(ty).to_string()),
+            statement,
+            exported,
+            scopes: self.scopes.clone(),
+        });
+    }
+}
+
+struct DefinitionShape {
+    params: Vec<Pat>,
+    body: Option<DefinitionBody>,
+    is_async: bool,
+    is_generator: bool,
+    generic: bool,
+    return_type: Option<Span>,
+}
+
+impl DefinitionShape {
+    fn function(function: &Function) -> Self {
+        Self {
+            params: function.params.iter().map(|p| p.pat.clone()).collect(),
+            body: function.body.clone().map(DefinitionBody::Block),
+            is_async: function.is_async,
+            is_generator: function.is_generator,
+            generic: function.type_params.is_some(),
+            return_type: function.return_type.as_ref().map(|ty| ty.type_ann.span()),
+        }
+    }
+
+    fn arrow(arrow: &ArrowExpr) -> Self {
+        Self {
+            params: arrow.params.clone(),
+            body: Some(match arrow.body.as_ref() {
+                BlockStmtOrExpr::BlockStmt(block) => DefinitionBody::Block(block.clone()),
+                BlockStmtOrExpr::Expr(expr) => DefinitionBody::Expr(expr.clone()),
+            }),
+            is_async: arrow.is_async,
+            is_generator: arrow.is_generator,
+            generic: arrow.type_params.is_some(),
+            return_type: arrow.return_type.as_ref().map(|ty| ty.type_ann.span()),
+        }
+    }
+}
+
+impl Visit for ModuleIndex<'_> {
+    fn visit_module(&mut self, node: &Module) {
+        node.visit_with(&mut self.module_names);
+        for item in &node.body {
+            if let ModuleItem::Stmt(stmt) = item {
+                self.list_statements.insert(stmt.span());
+            }
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_block_stmt(&mut self, node: &BlockStmt) {
+        self.mark_list(&node.stmts);
+        node.visit_children_with(self);
+    }
+
+    fn visit_switch_case(&mut self, node: &SwitchCase) {
+        self.mark_list(&node.cons);
+        node.visit_children_with(self);
+    }
+
+    fn visit_export_decl(&mut self, node: &ExportDecl) {
+        self.in_export = true;
+        node.visit_children_with(self);
+        self.in_export = false;
+    }
+
+    fn visit_named_export(&mut self, node: &NamedExport) {
+        if node.src.is_none() {
+            for specifier in &node.specifiers {
+                if let ExportSpecifier::Named(ExportNamedSpecifier {
+                    orig: ModuleExportName::Ident(ident),
+                    ..
+                }) = specifier
+                {
+                    self.exported_names.insert(ident.sym.to_string());
+                }
+            }
+        }
+    }
+
+    fn visit_fn_decl(&mut self, node: &FnDecl) {
+        let exported = std::mem::take(&mut self.in_export);
+        self.add_definition(
+            &node.ident,
+            DefinitionShape::function(&node.function),
+            node.span(),
+            exported,
+        );
+        node.visit_children_with(self);
+    }
+
+    fn visit_var_decl(&mut self, node: &VarDecl) {
+        let exported = std::mem::take(&mut self.in_export);
+        if let [VarDeclarator {
+            name: Pat::Ident(binding),
+            init: Some(init),
+            ..
+        }] = node.decls.as_slice()
+        {
+            let shape = match init.as_ref() {
+                Expr::Arrow(arrow) => Some(DefinitionShape::arrow(arrow)),
+                Expr::Fn(function) => Some(DefinitionShape::function(&function.function)),
+                _ => None,
+            };
+            if let Some(shape) = shape {
+                self.add_definition(&binding.id, shape, node.span, exported);
+            }
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, node: &Function) {
+        self.in_export = false;
+        self.enter_scope(node.span, &|names| node.visit_with(names), |this| {
+            node.visit_children_with(this)
+        });
+    }
+
+    fn visit_arrow_expr(&mut self, node: &ArrowExpr) {
+        if let BlockStmtOrExpr::Expr(body) = node.body.as_ref() {
+            self.standalone.insert(body.span());
+            self.at_start.insert(body.span());
+        }
+        self.enter_scope(node.span, &|names| node.visit_with(names), |this| {
+            node.visit_children_with(this)
+        });
+    }
+
+    fn visit_class_method(&mut self, node: &ClassMethod) {
+        self.method_lines.insert(self.text.line(node.key.span().lo));
+        node.visit_children_with(self);
+    }
+
+    fn visit_method_prop(&mut self, node: &MethodProp) {
+        self.method_lines.insert(self.text.line(node.key.span().lo));
+        node.visit_children_with(self);
+    }
+
+    fn visit_stmt(&mut self, node: &Stmt) {
+        let range = range_of(self.text, node.span());
+        let in_list = self.list_statements.contains(&node.span());
+        match node {
+            Stmt::Expr(stmt) => {
+                self.standalone.insert(stmt.expr.span());
+                self.at_start.insert(stmt.expr.span());
+                if let Expr::Call(call) = stmt.expr.as_ref() {
+                    self.contexts
+                        .insert(call.span, CallContext::Statement { range, in_list });
+                }
+            }
+            Stmt::Return(ReturnStmt { arg: Some(arg), .. }) => {
+                self.standalone.insert(arg.span());
+                if let Expr::Call(call) = arg.as_ref() {
+                    self.contexts
+                        .insert(call.span, CallContext::Return { range, in_list });
+                }
+            }
+            Stmt::Decl(Decl::Var(var)) => {
+                if let [VarDeclarator {
+                    name: Pat::Ident(binding),
+                    init: Some(init),
+                    ..
+                }] = var.decls.as_slice()
+                {
+                    if let Expr::Call(call) = init.as_ref() {
+                        let ty = binding
+                            .type_ann
+                            .as_ref()
+                            .map(|ann| format!(": {}", self.text.text(ann.type_ann.span())))
+                            .unwrap_or_default();
+                        let kind = match var.kind {
+                            VarDeclKind::Var => "var",
+                            VarDeclKind::Let => "let",
+                            VarDeclKind::Const => "const",
+                        };
+                        self.contexts.insert(
+                            call.span,
+                            CallContext::Declaration {
+                                range,
+                                in_list,
+                                kind,
+                                name: binding.id.sym.to_string(),
+                                ty,
+                            },
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_var_declarator(&mut self, node: &VarDeclarator) {
+        if let Some(init) = &node.init {
+            self.standalone.insert(init.span());
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_expr(&mut self, node: &Expr) {
+        let args =
+            |args: &[ExprOrSpread]| -> Vec<Span> { args.iter().map(|a| a.expr.span()).collect() };
+        let spans: Vec<Span> = match node {
+            Expr::Assign(assign) => vec![assign.right.span()],
+            Expr::Paren(paren) => vec![paren.expr.span()],
+            Expr::Array(array) => array
+                .elems
+                .iter()
+                .flatten()
+                .map(|e| e.expr.span())
+                .collect(),
+            Expr::Tpl(tpl) => tpl.exprs.iter().map(|e| e.span()).collect(),
+            Expr::Call(call) => args(&call.args),
+            Expr::New(new) => new.args.as_deref().map(args).unwrap_or_default(),
+            Expr::Object(object) => object
+                .props
+                .iter()
+                .filter_map(|prop| match prop {
+                    PropOrSpread::Prop(prop) => match prop.as_ref() {
+                        Prop::KeyValue(kv) => Some(kv.value.span()),
+                        _ => None,
+                    },
+                    PropOrSpread::Spread(_) => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        self.standalone.extend(spans);
+        node.visit_children_with(self);
+    }
+
+    fn visit_call_expr(&mut self, node: &CallExpr) {
+        if let Callee::Expr(callee) = &node.callee {
+            if let Expr::Ident(ident) = callee.as_ref() {
+                self.calls.push(CallSite {
+                    name: ident.sym.to_string(),
+                    range: range_of(self.text, node.span),
+                    args: node
+                        .args
+                        .iter()
+                        .map(|a| (range_of(self.text, a.expr.span()), (*a.expr).clone()))
+                        .collect(),
+                    spread: node.args.iter().any(|a| a.spread.is_some()),
+                    context: self
+                        .contexts
+                        .get(&node.span)
+                        .cloned()
+                        .unwrap_or(CallContext::Expression),
+                    standalone: self.standalone.contains(&node.span),
+                    at_start: self.at_start.contains(&node.span),
+                    scopes: self.scopes.clone(),
+                });
+            }
+        }
+        node.visit_children_with(self);
+    }
+}
+
+/// Counts references to a name outside of calls' callee position included
+struct ReferenceCounter {
+    name: String,
+    count: usize,
+}
+
+impl Visit for ReferenceCounter {
+    fn visit_expr(&mut self, node: &Expr) {
+        if matches!(node, Expr::Ident(ident) if ident.sym == *self.name) {
+            self.count += 1;
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_prop(&mut self, node: &Prop) {
+        if matches!(node, Prop::Shorthand(ident) if ident.sym == *self.name) {
+            self.count += 1;
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_export_named_specifier(&mut self, node: &ExportNamedSpecifier) {
+        if matches!(&node.orig, ModuleExportName::Ident(ident) if ident.sym == *self.name) {
+            self.count += 1;
+        }
+    }
+}
+
+// ============================================================================
+// Body analysis
+// ============================================================================
+
+struct ParamUse {
+    range: Range,
+    /// `{ x }` shorthand, which needs `x: arg`
+    shorthand: bool,
+}
+
+struct ParamInfo {
+    name: Option<String>,
+    /// Binding pattern, including its type annotation when not an identifier
+    pattern: String,
+    ty: Option<String>,
+    /// Default value text and whether it can be substituted
+    default: Option<(String, bool)>,
+    rest: bool,
+}
+
+struct ReturnSite {
+    range: Range,
+    /// Returned expression range and whether it is an identifier or literal
+    arg: Option<(Range, bool)>,
+}
+
+struct BodyAnalysis {
+    params: Vec<ParamInfo>,
+    uses: HashMap<String, Vec<ParamUse>>,
+    /// Parameters that cannot be substituted textually
+    pinned: HashSet<String>,
+    /// Parameters that are reassigned in the body
+    assigned: HashSet<String>,
+    /// Names declared inside the body
+    declared: HashSet<String>,
+    /// Names the body refers to that it does not declare
+    free: HashSet<String>,
+    /// `return`s other than the final statement, outside nested functions
+    early_returns: Vec<ReturnSite>,
+    final_return: Option<ReturnSite>,
+    /// Byte range of the block contents, without braces
+    inner: Option<Range>,
+    /// The returned expression: the final `return`'s or an arrow's body
+    result: Option<(Range, ResultShape)>,
+    /// Whether the body is nothing but the returned expression
+    only_result: bool,
+    /// One level of indentation in the definition
+    indent_unit: String,
+}
+
+#[derive(Clone, Copy)]
+struct ResultShape {
+    atomic: bool,
+    trivial: bool,
+    /// Starts with `{`, `function` or `class`
+    ambiguous_start: bool,
+}
+
+impl ResultShape {
+    fn of(expr: &Expr) -> Self {
+        Self {
+            atomic: is_atomic(expr),
+            trivial: is_trivial(expr),
+            ambiguous_start: matches!(expr, Expr::Object(_) | Expr::Fn(_) | Expr::Class(_)),
+        }
+    }
+}
+
+struct BodyVisitor<'a> {
+    text: &'a SourceText<'a>,
+    fn_name: String,
+    params: HashSet<String>,
+    uses: HashMap<String, Vec<ParamUse>>,
+    pinned: HashSet<String>,
+    assigned: HashSet<String>,
+    declared: HashSet<String>,
+    free: HashSet<String>,
+    returns: Vec<ReturnSite>,
+    /// Depth of nested non-arrow functions, which rebind `this`
+    function_depth: usize,
+    /// Depth of all nested functions, whose `return`s stay put
+    nested_depth: usize,
+    recursive: bool,
+    call_dependent: Option<&'static str>,
+}
+
+impl BodyVisitor<'_> {
+    fn reference(&mut self, ident: &Ident, shorthand: bool) {
+        let name = ident.sym.to_string();
+        if name == self.fn_name {
+            self.recursive = true;
+        } else if self.params.contains(&name) {
+            self.uses.entry(name).or_default().push(ParamUse {
+                range: range_of(self.text, ident.span),
+                shorthand,
+            });
+        } else {
+            if name == "arguments" && self.function_depth == 0 {
+                self.call_dependent = Some("arguments");
+            }
+            self.free.insert(name);
+        }
+    }
+
+    fn assign(&mut self, name: String) {
+        if self.params.contains(&name) {
+            self.pinned.insert(name.clone());
+            self.assigned.insert(name);
+        } else {
+            self.free.insert(name);
+        }
+    }
+}
+
+impl Visit for BodyVisitor<'_> {
+    fn visit_expr(&mut self, node: &Expr) {
+        match node {
+            Expr::Ident(ident) => self.reference(ident, false),
+            Expr::This(_) if self.function_depth == 0 => self.call_dependent = Some("this"),
+            Expr::Update(update) => {
+                if let Expr::Ident(ident) = update.arg.as_ref() {
+                    self.assign(ident.sym.to_string());
+                }
+            }
+            _ => {}
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_super(&mut self, _node: &Super) {
+        if self.function_depth == 0 {
+            self.call_dependent = Some("super");
+        }
+    }
+
+    fn visit_prop(&mut self, node: &Prop) {
+        match node {
+            Prop::Shorthand(ident) => self.reference(ident, true),
+            _ => node.visit_children_with(self),
+        }
+    }
+
+    fn visit_assign_target(&mut self, node: &AssignTarget) {
+        match node {
+            AssignTarget::Simple(SimpleAssignTarget::Ident(binding)) => {
+                self.assign(binding.id.sym.to_string())
+            }
+            _ => node.visit_children_with(self),
+        }
+    }
+
+    fn visit_binding_ident(&mut self, node: &BindingIdent) {
+        let name = node.id.sym.to_string();
+        if self.params.contains(&name) {
+            self.pinned.insert(name.clone());
+        }
+        self.declared.insert(name);
+    }
+
+    fn visit_fn_decl(&mut self, node: &FnDecl) {
+        self.declared.insert(node.ident.sym.to_string());
+        node.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, node: &ClassDecl) {
+        self.declared.insert(node.ident.sym.to_string());
+        node.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, node: &Function) {
+        self.function_depth += 1;
+        self.nested_depth += 1;
+        node.visit_children_with(self);
+        self.function_depth -= 1;
+        self.nested_depth -= 1;
+    }
+
+    fn visit_constructor(&mut self, node: &Constructor) {
+        self.function_depth += 1;
+        self.nested_depth += 1;
+        node.visit_children_with(self);
+        self.function_depth -= 1;
+        self.nested_depth -= 1;
+    }
+
+    fn visit_arrow_expr(&mut self, node: &ArrowExpr) {
+        self.nested_depth += 1;
+        node.visit_children_with(self);
+        self.nested_depth -= 1;
+    }
+
+    fn visit_return_stmt(&mut self, node: &ReturnStmt) {
+        if self.nested_depth == 0 {
+            self.returns.push(return_site(self.text, node));
+        }
+        node.visit_children_with(self);
+    }
+}
+
+fn return_site(text: &SourceText, node: &ReturnStmt) -> ReturnSite {
+    ReturnSite {
+        range: range_of(text, node.span),
+        arg: node
+            .arg
+            .as_ref()
+            .map(|arg| (range_of(text, arg.span()), is_trivial(arg))),
+    }
+}
+
+fn analyze_body(text: &SourceText, definition: &Definition) -> PluginResult<BodyAnalysis> {
+    let name = &definition.name;
+    let refuse = |reason: &str| {
+        Err(PluginApiError::invalid_input(format!(
+            "Cannot inline '{}': {}",
+            name, reason
+        )))
+    };
+    if definition.is_async {
+        return refuse("async functions cannot be inlined without awaiting their body");
+    }
+    if definition.is_generator {
+        return refuse("generator functions cannot be inlined");
+    }
+    let Some(body) = &definition.body else {
+        return refuse("the function has no body");
+    };
+
+    let mut params = Vec::new();
+    for pat in &definition.params {
+        params.push(param_info(text, pat));
+    }
+
+    let mut visitor = BodyVisitor {
+        text,
+        fn_name: name.clone(),
+        params: params.iter().filter_map(|p| p.name.clone()).collect(),
+        uses: HashMap::new(),
+        pinned: HashSet::new(),
+        assigned: HashSet::new(),
+        declared: HashSet::new(),
+        free: HashSet::new(),
+        returns: Vec::new(),
+        function_depth: 0,
+        nested_depth: 0,
+        recursive: false,
+        call_dependent: None,
+    };
+    match body {
+        DefinitionBody::Block(block) => block.visit_with(&mut visitor),
+        DefinitionBody::Expr(expr) => expr.visit_with(&mut visitor),
+    }
+    if visitor.recursive {
+        return refuse("the function is recursive");
+    }
+    if let Some(keyword) = visitor.call_dependent {
+        return refuse(&format!(
+            "the body uses `{}`, which depends on how the function is called",
+            keyword
+        ));
+    }
+    let free = visitor
+        .free
+        .difference(&visitor.declared)
+        .cloned()
+        .collect();
+
+    let (statement_start, _) = range_of(text, definition.statement);
+    let (inner, final_return, result, only_result, first_statement) = match body {
+        DefinitionBody::Block(block) => {
+            let (start, end) = range_of(text, block.span);
+            let (final_return, result) = match block.stmts.last() {
+                Some(Stmt::Return(ret)) => (
+                    Some(return_site(text, ret)),
+                    ret.arg
+                        .as_ref()
+                        .map(|arg| (range_of(text, arg.span()), ResultShape::of(arg))),
+                ),
+                _ => (None, None),
+            };
+            let only_result = block.stmts.len() == 1 && result.is_some();
+            let first = block.stmts.first().map(|s| range_of(text, s.span()).0);
+            (
+                Some((start + 1, end - 1)),
+                final_return,
+                result,
+                only_result,
+                first,
+            )
+        }
+        DefinitionBody::Expr(expr) => (
+            None,
+            None,
+            Some((range_of(text, expr.span()), ResultShape::of(expr))),
+            true,
+            None,
+        ),
+    };
+    let early_returns = visitor
+        .returns
+        .into_iter()
+        .filter(|ret| final_return.as_ref().is_none_or(|f| f.range != ret.range))
+        .collect();
+
+    Ok(BodyAnalysis {
+        params,
+        uses: visitor.uses,
+        pinned: visitor.pinned,
+        assigned: visitor.assigned,
+        declared: visitor.declared,
+        free,
+        early_returns,
+        final_return,
+        inner,
+        result,
+        only_result,
+        indent_unit: indent_unit(text.source, statement_start, first_statement),
+    })
+}
+
+fn param_info(text: &SourceText, pat: &Pat) -> ParamInfo {
+    let type_of = |binding: &BindingIdent| {
+        binding
+            .type_ann
+            .as_ref()
+            .filter(|_| !binding.id.optional)
+            .map(|ann| text.text(ann.type_ann.span()).to_string())
+    };
+    match pat {
+        Pat::Ident(binding) => ParamInfo {
+            name: Some(binding.id.sym.to_string()),
+            pattern: binding.id.sym.to_string(),
+            ty: type_of(binding),
+            default: None,
+            rest: false,
+        },
+        Pat::Assign(assign) => {
+            let default = Some((
+                text.text(assign.right.span()).to_string(),
+                is_trivial(&assign.right),
+            ));
+            match assign.left.as_ref() {
+                Pat::Ident(binding) => ParamInfo {
+                    name: Some(binding.id.sym.to_string()),
+                    pattern: binding.id.sym.to_string(),
+                    ty: type_of(binding),
+                    default,
+                    rest: false,
+                },
+                left => ParamInfo {
+                    name: None,
+                    pattern: text.text(left.span()).to_string(),
+                    ty: None,
+                    default,
+                    rest: false,
+                },
+            }
+        }
+        Pat::Rest(rest) => ParamInfo {
+            name: match rest.arg.as_ref() {
+                Pat::Ident(binding) => Some(binding.id.sym.to_string()),
+                _ => None,
+            },
+            pattern: text.text(rest.arg.span()).to_string(),
+            ty: rest
+                .type_ann
+                .as_ref()
+                .map(|ann| text.text(ann.type_ann.span()).to_string()),
+            default: None,
+            rest: true,
+        },
+        other => ParamInfo {
+            name: None,
+            pattern: text.text(other.span()).to_string(),
+            ty: None,
+            default: None,
+            rest: false,
+        },
+    }
+}
+
+/// Indentation step used by the definition, falling back to two spaces
+fn indent_unit(source: &str, definition_start: usize, first_statement: Option<usize>) -> String {
+    let outer = line_indent(source, definition_start);
+    first_statement
+        .map(|offset| line_indent(source, offset))
+        .filter(|inner| inner.len() > outer.len() && inner.starts_with(outer))
+        .map(|inner| inner[outer.len()..].to_string())
+        .unwrap_or_else(|| "  ".to_string())
+}
+
+// ============================================================================
+// Building the inlined code
+// ============================================================================
+
+fn is_trivial(expr: &Expr) -> bool {
+    match expr {
+        Expr::Ident(_) => true,
+        Expr::Lit(lit) => !matches!(lit, Lit::Regex(_)),
+        _ => false,
+    }
+}
+
+fn is_atomic(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Ident(_)
+            | Expr::Lit(_)
+            | Expr::Call(_)
+            | Expr::Member(_)
+            | Expr::SuperProp(_)
+            | Expr::OptChain(_)
+            | Expr::Paren(_)
+            | Expr::Array(_)
+            | Expr::Object(_)
+            | Expr::Tpl(_)
+            | Expr::This(_)
+    )
+}
+
+/// A parameter that is bound rather than substituted
+struct Binding {
+    /// `None` for an unused parameter whose argument is still evaluated
+    pattern: Option<String>,
+    ty: Option<String>,
+    value: String,
+    mutable: bool,
+    /// Default applied when the argument is `undefined`
+    default: Option<String>,
+    arg_names: HashSet<String>,
+}
+
+fn inline_call(
+    text: &SourceText,
+    definition: &Definition,
+    body: &BodyAnalysis,
+    call: &CallSite,
+    replacements: &[(usize, usize, String)],
+    caller_names: &HashSet<String>,
+    crowded: bool,
+) -> PluginResult<(Range, String)> {
+    let source = text.source;
+    let name = &definition.name;
+    let call_line = position_line(source, call.range.0);
+    let refuse = |reason: String| {
+        Err(PluginApiError::invalid_input(format!(
+            "Cannot inline call at line {}: {}",
+            call_line, reason
+        )))
+    };
+    if call.spread {
+        return refuse("spread arguments cannot be matched to parameters".to_string());
+    }
+    let has_rest = body.params.last().is_some_and(|p| p.rest);
+    if !has_rest && call.args.len() > body.params.len() {
+        return refuse(format!(
+            "'{}' takes {} arguments but {} are passed",
+            name,
+            body.params.len(),
+            call.args.len()
+        ));
+    }
+
+    // Decide, per parameter, between substitution and a binding
+    let mut substitutions: Vec<(usize, usize, String)> = Vec::new();
+    let mut bindings: Vec<Binding> = Vec::new();
+    for (index, param) in body.params.iter().enumerate() {
+        let uses = param
+            .name
+            .as_ref()
+            .and_then(|n| body.uses.get(n))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mutable = param
+            .name
+            .as_ref()
+            .is_some_and(|n| body.assigned.contains(n));
+        let ty = param.ty.clone().filter(|_| !definition.generic);
+
+        if param.rest {
+            let rest_args = call.args.get(index..).unwrap_or_default();
+            let values: Vec<String> = rest_args
+                .iter()
+                .map(|(range, _)| text_with(source, *range, replacements))
+                .collect();
+            bindings.push(Binding {
+                pattern: Some(param.pattern.clone()),
+                ty,
+                value: format!("[{}]", values.join(", ")),
+                mutable,
+                default: None,
+                arg_names: expr_names(rest_args.iter().map(|(_, e)| e)),
+            });
+            continue;
+        }
+
+        let arg = call.args.get(index);
+        let (value, trivial, arg_names) = match (arg, &param.default) {
+            (Some((range, expr)), _) => (
+                text_with(source, *range, replacements),
+                is_trivial(expr),
+                expr_names(std::iter::once(expr)),
+            ),
+            (None, Some((default, trivial))) => (default.clone(), *trivial, HashSet::new()),
+            (None, None) => ("undefined".to_string(), true, HashSet::new()),
+        };
+        // A default only applies when the argument turns out `undefined`
+        let default = match (arg, &param.default) {
+            (Some((_, Expr::Lit(_))), _) | (None, _) => None,
+            (Some(_), default) => default.as_ref().map(|(d, _)| d.clone()),
+        };
+
+        let substitutable = param
+            .name
+            .as_ref()
+            .is_some_and(|n| !body.pinned.contains(n))
+            && trivial
+            && default.is_none()
+            && arg_names.is_disjoint(&body.declared);
+        if substitutable {
+            let param_name = param.name.as_deref().unwrap_or_default();
+            for param_use in uses {
+                let replacement = if param_use.shorthand && value != param_name {
+                    format!("{}: {}", param_name, value)
+                } else {
+                    value.clone()
+                };
+                substitutions.push((param_use.range.0, param_use.range.1, replacement));
+            }
+            continue;
+        }
+        if default.is_some() && param.name.is_none() {
+            return refuse(format!(
+                "destructured parameter '{}' has a default value",
+                param.pattern
+            ));
+        }
+        if let Some(clash) = arg_names.intersection(&body.declared).min() {
+            return refuse(format!(
+                "an argument refers to '{}', which the body of '{}' declares too",
+                clash, name
+            ));
+        }
+
+        let unused = param.name.is_some() && uses.is_empty() && !mutable;
+        if unused && trivial {
+            continue;
+        }
+        bindings.push(Binding {
+            pattern: (!unused).then(|| param.pattern.clone()),
+            ty,
+            value,
+            mutable: mutable || default.is_some(),
+            default,
+            arg_names,
+        });
+    }
+
+    // A single expression with nothing to bind is inlined as an expression
+    if bindings.is_empty() && body.only_result {
+        let (range, shape) = body.result.expect("only_result implies a result");
+        let expr = text_with(source, range, &substitutions);
+        let wrap = (!shape.atomic && !call.standalone) || (shape.ambiguous_start && call.at_start);
+        let expr = if wrap { format!("({})", expr) } else { expr };
+        return Ok((call.range, expr));
+    }
+    let empty = bindings.is_empty()
+        && body.result.is_none()
+        && body
+            .inner
+            .is_none_or(|inner| source[inner.0..inner.1].trim().is_empty());
+
+    let (statement, in_list) = match &call.context {
+        CallContext::Statement { range, in_list }
+        | CallContext::Return { range, in_list }
+        | CallContext::Declaration { range, in_list, .. } => (*range, *in_list),
+        CallContext::Expression if empty => return Ok((call.range, "undefined".to_string())),
+        CallContext::Expression => {
+            return refuse(format!(
+                "the body of '{}' has statements, so the call must be a statement, a `return` or a variable initializer; assign it to a variable first",
+                name
+            ))
+        }
+    };
+    if empty {
+        if let CallContext::Statement { .. } = call.context {
+            return Ok((
+                full_line_range(source, statement.0, statement.1),
+                String::new(),
+            ));
+        }
+    }
+
+    let label = format!("inline_{}", name);
+    let result_name = match &call.context {
+        CallContext::Declaration { name, .. } => Some(name.clone()),
+        _ => None,
+    };
+    let keeps_returns = matches!(call.context, CallContext::Return { .. });
+    let labeled = !keeps_returns && !body.early_returns.is_empty();
+
+    // Names the inlined code introduces into the caller's scope
+    let mut introduced: HashSet<String> = body.declared.clone();
+    introduced.extend(
+        bindings
+            .iter()
+            .filter_map(|b| b.pattern.as_ref())
+            .flat_map(|p| pattern_names(p)),
+    );
+    let isolated = !in_list
+        || labeled
+        || (!introduced.is_empty()
+            && (crowded || introduced.iter().any(|n| caller_names.contains(n))));
+
+    // Rewrite the body: early returns and the final return
+    let mut body_edits: Vec<(usize, usize, String)> = Vec::new();
+    let mut replaced: Vec<Range> = Vec::new();
+    if let Some(final_return) = &body.final_return {
+        body_edits.push((final_return.range.0, final_return.range.1, String::new()));
+        replaced.push(final_return.range);
+    }
+    if !keeps_returns {
+        for ret in &body.early_returns {
+            let exit = format!("break {};", label);
+            match (&ret.arg, &result_name) {
+                (Some((arg, _)), Some(result)) => {
+                    body_edits.push((ret.range.0, arg.0, format!("{{ {} = ", result)));
+                    body_edits.push((arg.1, ret.range.1, format!("; {} }}", exit)));
+                }
+                (Some((arg, false)), None) => {
+                    body_edits.push((ret.range.0, arg.0, "{ ".to_string()));
+                    body_edits.push((arg.1, ret.range.1, format!("; {} }}", exit)));
+                }
+                _ => {
+                    body_edits.push((ret.range.0, ret.range.1, exit));
+                    replaced.push(ret.range);
+                }
+            }
+        }
+    }
+    body_edits.extend(
+        substitutions
+            .iter()
+            .filter(|(start, end, _)| !replaced.iter().any(|r| r.0 <= *start && *end <= r.1))
+            .cloned(),
+    );
+    let result = body.result.map(|(range, shape)| {
+        let expr = text_with(source, range, &substitutions);
+        (expr, shape)
+    });
+
+    let indent = line_indent(source, statement.0).to_string();
+    let inner_indent = if isolated {
+        format!("{}{}", indent, body.indent_unit)
+    } else {
+        indent.clone()
+    };
+
+    let mut lines = binding_lines(&bindings, &inner_indent);
+    if let Some(inner) = body.inner {
+        lines.extend(reindent(
+            &text_with(source, inner, &body_edits),
+            &inner_indent,
+        ));
+    }
+    match (&call.context, &result) {
+        (CallContext::Statement { .. }, Some((expr, shape))) if !shape.trivial => {
+            let expr = if shape.ambiguous_start {
+                format!("({})", expr)
+            } else {
+                expr.clone()
+            };
+            lines.push(format!("{}{};", inner_indent, expr));
+        }
+        (CallContext::Statement { .. }, _) => {}
+        (CallContext::Return { .. }, Some((expr, _))) => {
+            lines.push(format!("{}return {};", inner_indent, expr))
+        }
+        (CallContext::Return { .. }, None) => lines.push(format!("{}return;", inner_indent)),
+        (CallContext::Declaration { kind, name, ty, .. }, result) => {
+            let value = result.as_ref().map(|(expr, _)| expr.as_str());
+            if isolated {
+                if let Some(value) = value {
+                    lines.push(format!("{}{} = {};", inner_indent, name, value));
+                }
+            } else {
+                lines.push(format!(
+                    "{}{} {}{} = {};",
+                    inner_indent,
+                    kind,
+                    name,
+                    ty,
+                    value.unwrap_or("undefined")
+                ));
+            }
+        }
+        (CallContext::Expression, _) => {}
+    }
+
+    let mut output = Vec::new();
+    if isolated {
+        if let CallContext::Declaration { name, ty, .. } = &call.context {
+            // The result variable is assigned inside the block, so it needs
+            // the type the initializer used to provide
+            let ty = match (ty.is_empty(), &definition.return_type) {
+                (true, Some(return_type)) if !definition.generic => format!(": {}", return_type),
+                _ => ty.clone(),
+            };
+            output.push(format!("{}let {}{};", indent, name, ty));
+        }
+        if labeled {
+            output.push(format!("{}{}: {{", indent, label));
+        } else {
+            output.push(format!("{}{{", indent));
+        }
+        output.extend(lines);
+        output.push(format!("{}}}", indent));
+    } else {
+        output = lines;
+    }
+    if output.is_empty() {
+        return Ok((
+            full_line_range(source, statement.0, statement.1),
+            String::new(),
+        ));
+    }
+    let joined = output.join("\n");
+    Ok((statement, joined[indent.len()..].to_string()))
+}
+
+fn binding_lines(bindings: &[Binding], indent: &str) -> Vec<String> {
+    let keyword = |mutable: bool| if mutable { "let" } else { "const" };
+    let names: HashSet<String> = bindings
+        .iter()
+        .filter_map(|b| b.pattern.as_ref())
+        .flat_map(|p| pattern_names(p))
+        .collect();
+    let simultaneous = bindings.len() > 1
+        && bindings.iter().all(|b| b.pattern.is_some())
+        && bindings.iter().any(|b| !b.arg_names.is_disjoint(&names));
+
+    let mut lines = Vec::new();
+    if simultaneous {
+        // Bind together so an argument cannot see an earlier binding
+        let patterns: Vec<&str> = bindings
+            .iter()
+            .filter_map(|b| b.pattern.as_deref())
+            .collect();
+        let values: Vec<&str> = bindings.iter().map(|b| b.value.as_str()).collect();
+        lines.push(format!(
+            "{}{} [{}] = [{}];",
+            indent,
+            keyword(bindings.iter().any(|b| b.mutable)),
+            patterns.join(", "),
+            values.join(", ")
+        ));
+    } else {
+        for binding in bindings {
+            match &binding.pattern {
+                Some(pattern) => {
+                    let ty = match (&binding.ty, &binding.default) {
+                        (Some(ty), None) => format!(": {}", ty),
+                        _ => String::new(),
+                    };
+                    lines.push(format!(
+                        "{}{} {}{} = {};",
+                        indent,
+                        keyword(binding.mutable),
+                        pattern,
+                        ty,
+                        binding.value
+                    ));
+                }
+                None => lines.push(format!("{}{};", indent, binding.value)),
+            }
+        }
+    }
+    for binding in bindings {
+        if let (Some(pattern), Some(default)) = (&binding.pattern, &binding.default) {
+            lines.push(format!(
+                "{}if ({} === undefined) {} = {};",
+                indent, pattern, pattern, default
+            ));
+        }
+    }
+    lines
+}
+
+fn expr_names<'e>(exprs: impl Iterator<Item = &'e Expr>) -> HashSet<String> {
+    let mut names = Names::default();
+    for expr in exprs {
+        expr.visit_with(&mut names);
+    }
+    names.referenced
+}
+
+/// Identifiers in a binding pattern's text
+fn pattern_names(pattern: &str) -> Vec<String> {
+    let pattern = pattern.split(':').next().unwrap_or_default();
+    pattern
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .filter(|word| !word.is_empty() && !word.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mill_foundation::protocol::TextEdit;
+
+    fn apply(source: &str, edits: &[TextEdit]) -> String {
+        let mut result = source.to_string();
+        let mut edits = edits.to_vec();
+        edits.sort_by_key(|e| std::cmp::Reverse((e.location.start_line, e.location.start_column)));
+        for edit in edits {
+            let loc = &edit.location;
+            let start = position_to_offset(&result, loc.start_line, loc.start_column).unwrap();
+            let end = position_to_offset(&result, loc.end_line, loc.end_column).unwrap();
+            result.replace_range(start..end, &edit.new_text);
+        }
+        result
+    }
+
+    fn inline(source: &str, line: u32, character: u32, inline_all: bool) -> String {
+        let plan = plan_inline_function(source, line, character, inline_all, "index.ts").unwrap();
+        apply(source, &plan.edits)
+    }
+
+    #[test]
+    fn test_inline_arrow_expression() {
+        let source = "const double = (x: number) => x * 2;\n\nfunction main() {\n  const a = 3;\n  const b = double(a) + 1;\n  const c = double(a);\n}\n";
+        assert_eq!(
+            inline(source, 4, 12, false),
+            "const double = (x: number) => x * 2;\n\nfunction main() {\n  const a = 3;\n  const b = (a * 2) + 1;\n  const c = double(a);\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_all_binds_complex_arguments_and_removes_definition() {
+        let source = "function area(w: number, h: number): number {\n  const total = w * h;\n  return total;\n}\n\nfunction main() {\n  const s = area(2, next());\n  console.log(s);\n}\n";
+        assert_eq!(
+            inline(source, 0, 9, true),
+            "function main() {\n  const h: number = next();\n  const total = 2 * h;\n  const s = total;\n  console.log(s);\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_early_return_uses_labeled_block() {
+        let source = "function clamp(v: number): number {\n  if (v < 0) {\n    return 0;\n  }\n  return v;\n}\n\nfunction main() {\n  const r = clamp(read());\n  use(r);\n}\n";
+        assert_eq!(
+            inline(source, 8, 12, false),
+            "function clamp(v: number): number {\n  if (v < 0) {\n    return 0;\n  }\n  return v;\n}\n\nfunction main() {\n  let r: number;\n  inline_clamp: {\n    const v: number = read();\n    if (v < 0) {\n      { r = 0; break inline_clamp; }\n    }\n    r = v;\n  }\n  use(r);\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_statement_call_with_swapped_arguments() {
+        let source = "function report(a: string, b: string) {\n  if (!a) return;\n  log(a + b);\n}\n\nfunction main(a: string, b: string) {\n  report(b.trim(), a.trim());\n  done();\n}\n";
+        let result = inline(source, 6, 2, false);
+        assert!(
+            result.contains("  inline_report: {\n    const [a, b] = [b.trim(), a.trim()];\n    if (!a) break inline_report;\n    log(a + b);\n  }\n  done();"),
+            "{}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_return_context_keeps_returns() {
+        let source = "function sign(n: number) {\n  if (n < 0) {\n    return -1;\n  }\n  return 1;\n}\n\nfunction f(x: number) {\n  return sign(x);\n}\n";
+        assert_eq!(
+            inline(source, 8, 9, false),
+            "function sign(n: number) {\n  if (n < 0) {\n    return -1;\n  }\n  return 1;\n}\n\nfunction f(x: number) {\n  if (x < 0) {\n    return -1;\n  }\n  return 1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_refuses_recursion_this_and_nested_expression() {
+        let source = "function fact(n: number): number {\n  return n === 0 ? 1 : n * fact(n - 1);\n}\n\nfact(3);\n";
+        let err = plan_inline_function(source, 4, 0, false, "index.ts").unwrap_err();
+        assert!(err.to_string().contains("recursive"), "{}", err);
+
+        let source = "function name() {\n  return this.name;\n}\n\nname();\n";
+        let err = plan_inline_function(source, 4, 0, false, "index.ts").unwrap_err();
+        assert!(err.to_string().contains("`this`"), "{}", err);
+
+        let source = "function log(m: string) {\n  console.log(m);\n  return m.length;\n}\n\nconst n = 1 + log(read());\n";
+        let err = plan_inline_function(source, 5, 14, false, "index.ts").unwrap_err();
+        assert!(
+            err.to_string().contains("assign it to a variable"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_exported_definition_is_kept() {
+        let source = "export function one() {\n  return 1;\n}\n\nconst x = one();\n";
+        let plan = plan_inline_function(source, 4, 10, true, "index.ts").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "export function one() {\n  return 1;\n}\n\nconst x = 1;\n"
+        );
+        assert_eq!(plan.metadata.intent_arguments["definition_removed"], false);
+    }
+}
@@ -13,6 +13,7 @@ pub mod consolidation;
 mod constants;
 pub mod import_support;
 pub mod imports;
+pub mod inline_function;
 pub mod lsp_installer;
 mod manifest;
 pub mod parser;
@@ -20,9 +21,12 @@ pub mod path_alias_resolver;
 mod project_factory;
 pub mod refactoring;
 pub mod reference_detector;
+pub mod reorder;
 mod regex_patterns; // Re-exports from constants for backward compatibility
 mod string_literal_support;
+pub mod structural_search;
 pub mod test_fixtures;
+pub mod transform;
 mod tsconfig;
 pub mod workspace_support;
 
@@ -101,6 +105,10 @@ impl LanguagePlugin for TypeScriptPlugin {
         parser::analyze_imports(source, file_path)
     }
 
+    fn parser_version(&self) -> &'static str {
+        constants::PARSER_VERSION
+    }
+
     async fn list_functions(&self, source: &str) -> PluginResult<Vec<String>> {
         parser::list_functions(source).await
     }
@@ -112,6 +120,7 @@ impl LanguagePlugin for TypeScriptPlugin {
             refactoring_provider: RefactoringProvider,
             import_analyzer: ImportAnalyzer,
             manifest_updater: ManifestUpdater,
+            structural_search_support: StructuralSearchSupport,
         },
         import_support => {
             import_parser: ImportParser,
@@ -210,6 +219,21 @@ impl mill_plugin_api::RefactoringProvider for TypeScriptPlugin {
         refactoring::plan_inline_variable(source, variable_line, variable_col, file_path)
     }
 
+    fn supports_inline_function(&self) -> bool {
+        true
+    }
+
+    async fn plan_inline_function(
+        &self,
+        source: &str,
+        line: u32,
+        character: u32,
+        inline_all: bool,
+        file_path: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        inline_function::plan_inline_function(source, line, character, inline_all, file_path)
+    }
+
     fn supports_extract_function(&self) -> bool {
         true
     }
@@ -303,6 +327,37 @@ impl mill_plugin_api::RefactoringProvider for TypeScriptPlugin {
     ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
         refactoring::plan_symbol_delete(source, symbol_line, symbol_col, file_path)
     }
+
+    fn supported_transforms(&self) -> &'static [&'static str] {
+        transform::SUPPORTED_TRANSFORMS
+    }
+
+    async fn plan_transform(
+        &self,
+        source: &str,
+        kind: &str,
+        line: u32,
+        character: u32,
+        file_path: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        transform::plan_transform(source, kind, line, character, file_path)
+    }
+
+    fn supports_reorder(&self, kind: &str) -> bool {
+        reorder::SUPPORTED_REORDERS.contains(&kind)
+    }
+
+    async fn plan_reorder(
+        &self,
+        source: &str,
+        kind: &str,
+        line: u32,
+        character: u32,
+        new_order: &[String],
+        file_path: &str,
+    ) -> mill_plugin_api::PluginResult<mill_foundation::protocol::EditPlan> {
+        reorder::plan_reorder(source, kind, line, character, new_order, file_path)
+    }
 }
 
 impl mill_plugin_api::ImportAnalyzer for TypeScriptPlugin {
@@ -344,6 +399,25 @@ impl mill_plugin_api::ManifestUpdater for TypeScriptPlugin {
     }
 }
 
+// ============================================================================
+// Structural Search Capability
+// ============================================================================
+
+#[async_trait]
+impl mill_plugin_api::StructuralSearchSupport for TypeScriptPlugin {
+    async fn tokenize(&self, source: &str) -> PluginResult<Vec<std::ops::Range<usize>>> {
+        Ok(structural_search::tokenize(source, false))
+    }
+
+    async fn syntax_index(
+        &self,
+        source: &str,
+        file_path: &Path,
+    ) -> PluginResult<mill_plugin_api::SyntaxIndex> {
+        structural_search::syntax_index(source, &file_path.to_string_lossy())
+    }
+}
+
 // ============================================================================
 // Plugin-specific helper methods
 // ============================================================================
@@ -1,4 +1,5 @@
 //! TypeScript/JavaScript import parsing and symbol extraction logic.
+use crate::constants;
 use mill_foundation::protocol::{ImportGraph, ImportInfo, ImportType, SourceLocation};
 use mill_lang_common::{
     parse_with_fallback, run_ast_tool, run_ast_tool_async, ImportGraphBuilder, SubprocessAstTool,
@@ -6,6 +7,7 @@ use mill_lang_common::{
 use mill_plugin_api::{PluginApiError, PluginResult, Symbol, SymbolKind};
 use serde::Deserialize;
 use std::path::Path;
+
 /// Analyzes TypeScript/JavaScript source code to produce an import graph.
 /// It attempts to use an AST-based approach first, falling back to regex on failure.
 pub(crate) fn analyze_imports(source: &str, file_path: Option<&Path>) -> PluginResult<ImportGraph> {
@@ -18,7 +20,7 @@ pub(crate) fn analyze_imports(source: &str, file_path: Option<&Path>) -> PluginR
         .with_source_file(file_path)
         .with_imports(imports)
         .extract_external_dependencies(is_external_dependency)
-        .with_parser_version("0.1.0-plugin")
+        .with_parser_version(constants::PARSER_VERSION)
         .build())
 }
 /// TypeScript import information from AST tool
@@ -1304,7 +1304,7 @@ fn parse_module(source: &str, file_path: &str) -> PluginResult<Module> {
     Ok(module)
 }
 
-fn parse_module_with_source_map(
+pub(crate) fn parse_module_with_source_map(
     source: &str,
     file_path: &str,
 ) -> PluginResult<(Module, Lrc<SourceMap>)> {
@@ -0,0 +1,707 @@
+//! TypeScript/JavaScript reorder refactorings using the SWC AST
+//!
+//! Supported reorder kinds:
+//! - `parameters`: parameters of functions, arrows, methods and constructors
+//! - `fields`: class properties, interface and type literal members
+//! - `variants`: enum members
+//! - `imports`: a contiguous block of `import` declarations
+
+use crate::refactoring::parse_module_with_source_map;
+use mill_foundation::protocol::EditPlan;
+use mill_lang_common::offset_to_position;
+use mill_lang_common::refactoring::edit_plan_builder::EditPlanBuilder;
+use mill_lang_common::refactoring::reorder::{
+    reorder_items_edit, resolve_permutation, ReorderItem,
+};
+use mill_plugin_api::{PluginApiError, PluginResult};
+use swc_common::{sync::Lrc, SourceMap, Span, Spanned};
+use swc_ecma_ast::*;
+use swc_ecma_visit::{Visit, VisitWith};
+
+/// Reorder kinds implemented by the TypeScript plugin
+pub const SUPPORTED_REORDERS: &[&str] = &["parameters", "fields", "variants", "imports"];
+
+/// Plan a reorder for TypeScript/JavaScript
+pub fn plan_reorder(
+    source: &str,
+    kind: &str,
+    line: u32,
+    _character: u32,
+    new_order: &[String],
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let (module, cm) = parse_module_with_source_map(source, file_path)?;
+    let text = SourceText { source, cm };
+
+    let target = match kind {
+        "parameters" => find_parameters(&text, &module, line)?,
+        "fields" => find_fields(&text, &module, line)?,
+        "variants" => find_variants(&text, &module, line)?,
+        "imports" => find_imports(&text, &module, line)?,
+        other => {
+            return Err(PluginApiError::not_supported(format!(
+                "TypeScript reorder '{}' (supported: {})",
+                other,
+                SUPPORTED_REORDERS.join(", ")
+            )))
+        }
+    };
+
+    let current: Vec<String> = target.items.iter().map(|i| i.name.clone()).collect();
+    let permutation = if new_order.is_empty() && kind == "imports" {
+        let mut permutation: Vec<usize> = (0..current.len()).collect();
+        permutation.sort_by(|a, b| current[*a].cmp(&current[*b]));
+        permutation
+    } else {
+        resolve_permutation(&current, new_order).map_err(PluginApiError::invalid_input)?
+    };
+
+    if let Some(optional) = &target.optional {
+        // A rest parameter must stay last, and required parameters cannot follow optional ones
+        if let Some(rest) = target.rest_index {
+            if permutation.last() != Some(&rest) {
+                return Err(PluginApiError::invalid_input(format!(
+                    "Rest parameter '{}' must remain last",
+                    current[rest]
+                )));
+            }
+        }
+        let mut seen_optional = None;
+        for &index in &permutation {
+            if Some(index) == target.rest_index {
+                continue;
+            }
+            if optional[index] {
+                seen_optional = Some(index);
+            } else if let Some(previous) = seen_optional {
+                return Err(PluginApiError::invalid_input(format!(
+                    "Required parameter '{}' cannot follow optional parameter '{}'",
+                    current[index], current[previous]
+                )));
+            }
+        }
+    }
+
+    let edits = reorder_items_edit(
+        source,
+        &target.items,
+        &permutation,
+        &format!("Reorder {} of '{}'", kind, target.owner),
+    )
+    .into_iter()
+    .collect::<Vec<_>>();
+
+    let mut intent_args = serde_json::json!({
+        "kind": kind,
+        "owner": target.owner,
+        "permutation": permutation,
+        "notes": target.notes,
+    });
+    if let Some((name_line, name_character)) = target.name_position {
+        intent_args["function_name"] = serde_json::json!(target.owner);
+        intent_args["name_line"] = serde_json::json!(name_line);
+        intent_args["name_character"] = serde_json::json!(name_character);
+    }
+
+    Ok(EditPlanBuilder::new(file_path, kind)
+        .with_edits(edits)
+        .with_syntax_validation("Verify syntax is valid after reorder")
+        .with_intent_args(intent_args)
+        .with_complexity(2)
+        .with_impact_area("reorder")
+        .build())
+}
+
+/// Items found at the target position
+struct ReorderTarget {
+    owner: String,
+    items: Vec<ReorderItem>,
+    /// Whether each parameter is optional (parameters only)
+    optional: Option<Vec<bool>>,
+    rest_index: Option<usize>,
+    /// Position of the function name (parameters only)
+    name_position: Option<(u32, u32)>,
+    notes: Vec<String>,
+}
+
+impl ReorderTarget {
+    fn members(owner: String, items: Vec<ReorderItem>, notes: Vec<String>) -> Self {
+        Self {
+            owner,
+            items,
+            optional: None,
+            rest_index: None,
+            name_position: None,
+            notes,
+        }
+    }
+}
+
+/// Source text plus the SWC source map used to resolve spans into it
+pub(crate) struct SourceText<'a> {
+    pub(crate) source: &'a str,
+    pub(crate) cm: Lrc<SourceMap>,
+}
+
+impl SourceText<'_> {
+    pub(crate) fn offset(&self, pos: swc_common::BytePos) -> usize {
+        self.cm.lookup_byte_offset(pos).pos.0 as usize
+    }
+
+    pub(crate) fn line(&self, pos: swc_common::BytePos) -> u32 {
+        offset_to_position(self.source, self.offset(pos)).0
+    }
+
+    pub(crate) fn contains_line(&self, span: Span, line: u32) -> bool {
+        self.line(span.lo) <= line && line <= self.line(span.hi)
+    }
+
+    pub(crate) fn text(&self, span: Span) -> &str {
+        &self.source[self.offset(span.lo)..self.offset(span.hi)]
+    }
+
+    /// Item for a member span, leaving any trailing `;`/`,` in the separator
+    fn item(&self, name: impl Into<String>, span: Span) -> ReorderItem {
+        let start = self.offset(span.lo);
+        let text = self.source[start..self.offset(span.hi)]
+            .trim_end()
+            .trim_end_matches([';', ','])
+            .trim_end();
+        ReorderItem::new(name, start, start + text.len())
+    }
+}
+
+/// Pick the innermost candidate whose line range contains `line`
+fn innermost<T>(text: &SourceText, candidates: Vec<(Span, T)>, line: u32) -> Option<T> {
+    candidates
+        .into_iter()
+        .filter(|(span, _)| text.contains_line(*span, line))
+        .min_by_key(|(span, _)| span.hi.0 - span.lo.0)
+        .map(|(_, value)| value)
+}
+
+fn prop_name(key: &PropName) -> String {
+    match key {
+        PropName::Ident(ident) => ident.sym.to_string(),
+        PropName::Str(s) => s.value.to_string_lossy().to_string(),
+        PropName::Num(n) => n.value.to_string(),
+        _ => "<computed>".to_string(),
+    }
+}
+
+// ============================================================================
+// parameters
+// ============================================================================
+
+/// A function-like construct with its parameter patterns
+struct FunctionParams {
+    name: String,
+    name_span: Span,
+    params: Vec<(Span, Pat)>,
+}
+
+#[derive(Default)]
+struct ParamsCollector {
+    functions: Vec<(Span, FunctionParams)>,
+    /// Name of the enclosing class, for constructors
+    class_names: Vec<Ident>,
+}
+
+fn function_params(function: &Function) -> Vec<(Span, Pat)> {
+    function
+        .params
+        .iter()
+        .map(|p| (p.span, p.pat.clone()))
+        .collect()
+}
+
+impl Visit for ParamsCollector {
+    fn visit_fn_decl(&mut self, node: &FnDecl) {
+        self.functions.push((
+            node.function.span,
+            FunctionParams {
+                name: node.ident.sym.to_string(),
+                name_span: node.ident.span,
+                params: function_params(&node.function),
+            },
+        ));
+        node.visit_children_with(self);
+    }
+
+    fn visit_var_declarator(&mut self, node: &VarDeclarator) {
+        if let (Pat::Ident(binding), Some(init)) = (&node.name, node.init.as_deref()) {
+            let params = match init {
+                Expr::Arrow(arrow) => {
+                    Some(arrow.params.iter().map(|p| (p.span(), p.clone())).collect())
+                }
+                Expr::Fn(fn_expr) => Some(function_params(&fn_expr.function)),
+                _ => None,
+            };
+            if let Some(params) = params {
+                self.functions.push((
+                    node.span,
+                    FunctionParams {
+                        name: binding.id.sym.to_string(),
+                        name_span: binding.id.span,
+                        params,
+                    },
+                ));
+            }
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, node: &ClassDecl) {
+        self.class_names.push(node.ident.clone());
+        node.visit_children_with(self);
+        self.class_names.pop();
+    }
+
+    fn visit_class_method(&mut self, node: &ClassMethod) {
+        self.functions.push((
+            node.span,
+            FunctionParams {
+                name: prop_name(&node.key),
+                name_span: node.key.span(),
+                params: function_params(&node.function),
+            },
+        ));
+        node.visit_children_with(self);
+    }
+
+    fn visit_method_prop(&mut self, node: &MethodProp) {
+        self.functions.push((
+            Span::new(node.key.span().lo, node.function.span.hi),
+            FunctionParams {
+                name: prop_name(&node.key),
+                name_span: node.key.span(),
+                params: function_params(&node.function),
+            },
+        ));
+        node.visit_children_with(self);
+    }
+
+    fn visit_constructor(&mut self, node: &Constructor) {
+        if let Some(class) = self.class_names.last() {
+            let params = node
+                .params
+                .iter()
+                .map(|p| match p {
+                    ParamOrTsParamProp::Param(param) => (param.span, param.pat.clone()),
+                    ParamOrTsParamProp::TsParamProp(prop) => (
+                        prop.span,
+                        match &prop.param {
+                            TsParamPropParam::Ident(binding) => Pat::Ident(binding.clone()),
+                            TsParamPropParam::Assign(assign) => Pat::Assign(assign.clone()),
+                        },
+                    ),
+                })
+                .collect();
+            self.functions.push((
+                node.span,
+                FunctionParams {
+                    name: class.sym.to_string(),
+                    name_span: class.span,
+                    params,
+                },
+            ));
+        }
+        node.visit_children_with(self);
+    }
+}
+
+/// Name of a parameter pattern, whether it is optional, and whether it is a rest parameter
+fn describe_param(text: &SourceText, pat: &Pat) -> (String, bool, bool) {
+    match pat {
+        Pat::Ident(binding) => (binding.id.sym.to_string(), binding.id.optional, false),
+        Pat::Assign(assign) => (describe_param(text, &assign.left).0, true, false),
+        Pat::Rest(rest) => (describe_param(text, &rest.arg).0, false, true),
+        other => (text.text(other.span()).to_string(), false, false),
+    }
+}
+
+fn find_parameters(text: &SourceText, module: &Module, line: u32) -> PluginResult<ReorderTarget> {
+    let mut collector = ParamsCollector::default();
+    module.visit_with(&mut collector);
+
+    let function = innermost(text, collector.functions, line).ok_or_else(|| {
+        PluginApiError::invalid_input(format!("No function found at line {}", line + 1))
+    })?;
+
+    let mut items = Vec::new();
+    let mut optional = Vec::new();
+    let mut rest_index = None;
+    for (span, pat) in &function.params {
+        let (name, is_optional, is_rest) = describe_param(text, pat);
+        // A TypeScript `this` parameter is not passed by callers
+        if name == "this" {
+            continue;
+        }
+        if is_rest {
+            rest_index = Some(items.len());
+        }
+        optional.push(is_optional);
+        items.push(text.item(name, *span));
+    }
+
+    let name_offset = text.offset(function.name_span.lo);
+    Ok(ReorderTarget {
+        owner: function.name,
+        items,
+        optional: Some(optional),
+        rest_index,
+        name_position: Some(offset_to_position(text.source, name_offset)),
+        notes: vec!["Calls using spread arguments are skipped".to_string()],
+    })
+}
+
+// ============================================================================
+// fields
+// ============================================================================
+
+/// A class, interface or type literal with its named members
+struct FieldOwner {
+    name: String,
+    fields: Vec<(String, Span)>,
+    is_class: bool,
+}
+
+#[derive(Default)]
+struct FieldsCollector {
+    owners: Vec<(Span, FieldOwner)>,
+}
+
+impl FieldsCollector {
+    fn push_class(&mut self, name: String, class: &Class) {
+        let fields = class
+            .body
+            .iter()
+            .filter_map(|member| match member {
+                ClassMember::ClassProp(prop) => Some((prop_name(&prop.key), prop.span)),
+                ClassMember::PrivateProp(prop) => Some((format!("#{}", prop.key.name), prop.span)),
+                _ => None,
+            })
+            .collect();
+        self.owners.push((
+            class.span,
+            FieldOwner {
+                name,
+                fields,
+                is_class: true,
+            },
+        ));
+    }
+
+    fn push_type_members(&mut self, span: Span, name: String, members: &[TsTypeElement]) {
+        let fields = members
+            .iter()
+            .filter_map(|member| match member {
+                TsTypeElement::TsPropertySignature(prop) => {
+                    let name = match prop.key.as_ref() {
+                        Expr::Ident(ident) => ident.sym.to_string(),
+                        Expr::Lit(Lit::Str(s)) => s.value.to_string_lossy().to_string(),
+                        _ => return None,
+                    };
+                    Some((name, prop.span))
+                }
+                _ => None,
+            })
+            .collect();
+        self.owners.push((
+            span,
+            FieldOwner {
+                name,
+                fields,
+                is_class: false,
+            },
+        ));
+    }
+}
+
+impl Visit for FieldsCollector {
+    fn visit_class_decl(&mut self, node: &ClassDecl) {
+        self.push_class(node.ident.sym.to_string(), &node.class);
+        node.visit_children_with(self);
+    }
+
+    fn visit_class_expr(&mut self, node: &ClassExpr) {
+        let name = node
+            .ident
+            .as_ref()
+            .map(|i| i.sym.to_string())
+            .unwrap_or_else(|| "<anonymous class>".to_string());
+        self.push_class(name, &node.class);
+        node.visit_children_with(self);
+    }
+
+    fn visit_ts_interface_decl(&mut self, node: &TsInterfaceDecl) {
+        self.push_type_members(node.span, node.id.sym.to_string(), &node.body.body);
+        node.visit_children_with(self);
+    }
+
+    fn visit_ts_type_alias_decl(&mut self, node: &TsTypeAliasDecl) {
+        if let TsType::TsTypeLit(lit) = node.type_ann.as_ref() {
+            self.push_type_members(node.span, node.id.sym.to_string(), &lit.members);
+        }
+        node.visit_children_with(self);
+    }
+}
+
+fn find_fields(text: &SourceText, module: &Module, line: u32) -> PluginResult<ReorderTarget> {
+    let mut collector = FieldsCollector::default();
+    module.visit_with(&mut collector);
+
+    let FieldOwner {
+        name: owner,
+        fields,
+        is_class,
+    } = innermost(text, collector.owners, line).ok_or_else(|| {
+        PluginApiError::invalid_input(format!(
+            "No class, interface or type literal found at line {}",
+            line + 1
+        ))
+    })?;
+    if fields.is_empty() {
+        return Err(PluginApiError::invalid_input(format!(
+            "'{}' has no fields to reorder",
+            owner
+        )));
+    }
+
+    let items = fields
+        .into_iter()
+        .map(|(name, span)| text.item(name, span))
+        .collect();
+    let notes = if is_class {
+        vec![format!(
+            "Field initializers of '{}' run in declaration order",
+            owner
+        )]
+    } else {
+        Vec::new()
+    };
+
+    Ok(ReorderTarget::members(owner, items, notes))
+}
+
+// ============================================================================
+// variants
+// ============================================================================
+
+#[derive(Default)]
+struct EnumCollector {
+    enums: Vec<(Span, TsEnumDecl)>,
+}
+
+impl Visit for EnumCollector {
+    fn visit_ts_enum_decl(&mut self, node: &TsEnumDecl) {
+        self.enums.push((node.span, node.clone()));
+        node.visit_children_with(self);
+    }
+}
+
+fn find_variants(text: &SourceText, module: &Module, line: u32) -> PluginResult<ReorderTarget> {
+    let mut collector = EnumCollector::default();
+    module.visit_with(&mut collector);
+
+    let decl = innermost(text, collector.enums, line).ok_or_else(|| {
+        PluginApiError::invalid_input(format!("No enum found at line {}", line + 1))
+    })?;
+    let owner = decl.id.sym.to_string();
+
+    let items = decl
+        .members
+        .iter()
+        .map(|member| {
+            let name = match &member.id {
+                TsEnumMemberId::Ident(ident) => ident.sym.to_string(),
+                TsEnumMemberId::Str(s) => s.value.to_string_lossy().to_string(),
+            };
+            text.item(name, member.span)
+        })
+        .collect();
+
+    let mut notes = Vec::new();
+    if decl.members.iter().any(|m| m.init.is_none()) {
+        notes.push(format!(
+            "'{}' has members without initializers; their numeric values will change",
+            owner
+        ));
+    }
+
+    Ok(ReorderTarget::members(owner, items, notes))
+}
+
+// ============================================================================
+// imports
+// ============================================================================
+
+fn find_imports(text: &SourceText, module: &Module, line: u32) -> PluginResult<ReorderTarget> {
+    let mut blocks: Vec<Vec<ReorderItem>> = Vec::new();
+    let mut current: Vec<ReorderItem> = Vec::new();
+
+    for item in &module.body {
+        match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => {
+                let item = text.item(import.src.value.to_string_lossy().to_string(), import.span);
+                // A blank line starts a new import group
+                let gap_has_blank_line = current.last().is_some_and(|last| {
+                    text.source[last.end..item.start].matches('\n').count() > 1
+                });
+                if gap_has_blank_line {
+                    blocks.push(std::mem::take(&mut current));
+                }
+                current.push(item);
+            }
+            _ => {
+                if !current.is_empty() {
+                    blocks.push(std::mem::take(&mut current));
+                }
+            }
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+
+    let items = blocks
+        .into_iter()
+        .find(|block| {
+            let first = offset_to_position(text.source, block[0].start).0;
+            let last = offset_to_position(text.source, block[block.len() - 1].end).0;
+            first <= line && line <= last
+        })
+        .ok_or_else(|| {
+            PluginApiError::invalid_input(format!("No import block found at line {}", line + 1))
+        })?;
+
+    Ok(ReorderTarget::members(
+        "import block".to_string(),
+        items,
+        Vec::new(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mill_foundation::protocol::TextEdit;
+    use mill_lang_common::position_to_offset;
+
+    fn apply(source: &str, edits: &[TextEdit]) -> String {
+        let mut result = source.to_string();
+        for edit in edits {
+            let loc = &edit.location;
+            let start = position_to_offset(&result, loc.start_line, loc.start_column).unwrap();
+            let end = position_to_offset(&result, loc.end_line, loc.end_column).unwrap();
+            result.replace_range(start..end, &edit.new_text);
+        }
+        result
+    }
+
+    fn order(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reorder_function_parameters() {
+        let source = "export function greet(name: string, greeting = 'hi', ...rest: string[]) {}\n";
+        let plan = plan_reorder(
+            source,
+            "parameters",
+            0,
+            0,
+            &order(&["greeting", "name", "rest"]),
+            "a.ts",
+        );
+        assert!(plan.is_err(), "required after optional must be rejected");
+
+        let source =
+            "export function greet(name: string, greeting: string, ...rest: string[]) {}\n";
+        let plan = plan_reorder(
+            source,
+            "parameters",
+            0,
+            0,
+            &order(&["greeting", "name", "rest"]),
+            "a.ts",
+        )
+        .unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "export function greet(greeting: string, name: string, ...rest: string[]) {}\n"
+        );
+        let args = &plan.metadata.intent_arguments;
+        assert_eq!(args["name_line"], 0);
+        assert_eq!(args["name_character"], 16);
+        assert_eq!(args["permutation"], serde_json::json!([1, 0, 2]));
+
+        assert!(plan_reorder(
+            source,
+            "parameters",
+            0,
+            0,
+            &order(&["rest", "name", "greeting"]),
+            "a.ts"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_reorder_constructor_parameters_targets_class_name() {
+        let source = "class Point {\n  constructor(private x: number, y: number) {}\n}\n";
+        let plan = plan_reorder(source, "parameters", 1, 0, &order(&["y", "x"]), "a.ts").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "class Point {\n  constructor(y: number, private x: number) {}\n}\n"
+        );
+        assert_eq!(plan.metadata.intent_arguments["function_name"], "Point");
+        assert_eq!(plan.metadata.intent_arguments["name_character"], 6);
+    }
+
+    #[test]
+    fn test_reorder_interface_fields() {
+        let source = "interface User {\n  id: number;\n  name: string\n}\n";
+        let plan = plan_reorder(source, "fields", 1, 0, &order(&["name", "id"]), "a.ts").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "interface User {\n  name: string;\n  id: number\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_reorder_enum_members() {
+        let source = "enum Color {\n  Red,\n  Green = 'g',\n}\n";
+        let plan =
+            plan_reorder(source, "variants", 0, 0, &order(&["Green", "Red"]), "a.ts").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "enum Color {\n  Green = 'g',\n  Red,\n}\n"
+        );
+        assert_eq!(
+            plan.metadata.intent_arguments["notes"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_reorder_imports_sorts_group() {
+        let source =
+            "import { b } from './b';\nimport React from 'react';\n\nimport { a } from './a';\n";
+        let plan = plan_reorder(source, "imports", 0, 0, &[], "a.ts").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "import { b } from './b';\nimport React from 'react';\n\nimport { a } from './a';\n"
+        );
+
+        let plan =
+            plan_reorder(source, "imports", 1, 0, &order(&["react", "./b"]), "a.ts").unwrap();
+        assert_eq!(
+            apply(source, &plan.edits),
+            "import React from 'react';\nimport { b } from './b';\n\nimport { a } from './a';\n"
+        );
+    }
+}
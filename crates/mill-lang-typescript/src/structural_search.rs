@@ -0,0 +1,111 @@
+//! Tokens and syntax nodes for structural search, from the SWC lexer and parser
+//!
+//! Nodes are expressions, statements, module declarations, patterns and types.
+
+use crate::refactoring::parse_module_with_source_map;
+use mill_plugin_api::{PluginResult, SyntaxIndex};
+use std::ops::Range;
+use swc_common::{sync::Lrc, FileName, SourceMap, Span, Spanned};
+use swc_ecma_ast::*;
+use swc_ecma_parser::{lexer::Lexer, StringInput, Syntax, TsSyntax};
+use swc_ecma_visit::{Visit, VisitWith};
+
+/// Split TypeScript/JavaScript code into tokens
+pub(crate) fn tokenize(source: &str, tsx: bool) -> Vec<Range<usize>> {
+    let cm: Lrc<SourceMap> = Default::default();
+    let fm = cm.new_source_file(FileName::Anon.into(), source.to_string());
+    let lexer = Lexer::new(
+        Syntax::Typescript(TsSyntax {
+            tsx,
+            decorators: true,
+            ..Default::default()
+        }),
+        Default::default(),
+        StringInput::from(&*fm),
+        None,
+    );
+    lexer
+        .map(|token| {
+            (token.span.lo - fm.start_pos).0 as usize..(token.span.hi - fm.start_pos).0 as usize
+        })
+        .collect()
+}
+
+/// Tokenize and parse a TypeScript/JavaScript file
+pub(crate) fn syntax_index(source: &str, file_path: &str) -> PluginResult<SyntaxIndex> {
+    let (module, cm) = parse_module_with_source_map(source, file_path)?;
+    let mut collector = NodeCollector {
+        cm,
+        nodes: Vec::new(),
+    };
+    module.visit_with(&mut collector);
+    Ok(SyntaxIndex {
+        tokens: tokenize(source, file_path.ends_with(".tsx")),
+        nodes: collector.nodes,
+    })
+}
+
+struct NodeCollector {
+    cm: Lrc<SourceMap>,
+    nodes: Vec<Range<usize>>,
+}
+
+impl NodeCollector {
+    fn push(&mut self, span: Span) {
+        if span.is_dummy() || span.lo == span.hi {
+            return;
+        }
+        let start = self.cm.lookup_byte_offset(span.lo).pos.0 as usize;
+        let end = self.cm.lookup_byte_offset(span.hi).pos.0 as usize;
+        self.nodes.push(start..end);
+    }
+}
+
+impl Visit for NodeCollector {
+    fn visit_expr(&mut self, node: &Expr) {
+        self.push(node.span());
+        node.visit_children_with(self);
+    }
+
+    fn visit_stmt(&mut self, node: &Stmt) {
+        self.push(node.span());
+        node.visit_children_with(self);
+    }
+
+    fn visit_module_decl(&mut self, node: &ModuleDecl) {
+        self.push(node.span());
+        node.visit_children_with(self);
+    }
+
+    fn visit_pat(&mut self, node: &Pat) {
+        self.push(node.span());
+        node.visit_children_with(self);
+    }
+
+    fn visit_ts_type(&mut self, node: &TsType) {
+        self.push(node.span());
+        node.visit_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(source: &'a str, ranges: &[Range<usize>]) -> Vec<&'a str> {
+        ranges.iter().map(|r| &source[r.clone()]).collect()
+    }
+
+    #[test]
+    fn test_syntax_index() {
+        let source = "// note\nconst x = foo(a, `t`);\n";
+        let index = syntax_index(source, "a.ts").unwrap();
+        assert_eq!(
+            texts(source, &index.tokens),
+            vec!["const", "x", "=", "foo", "(", "a", ",", "`t`", ")", ";"]
+        );
+        let nodes = texts(source, &index.nodes);
+        assert!(nodes.contains(&"foo(a, `t`)"));
+        assert!(nodes.contains(&"const x = foo(a, `t`);"));
+    }
+}
@@ -0,0 +1,1128 @@
+//! TypeScript/JavaScript code transformations using the SWC AST
+//!
+//! Supported transform kinds:
+//! - `to_async`: make the function at the target line `async` (wrapping an explicit
+//!   return type in `Promise<...>`)
+//! - `function_to_arrow`: `function f(a) {}` to `const f = (a) => {};`
+//! - `arrow_to_function`: `const f = (a) => a;` to `function f(a) { return a; }`
+//! - `if_else_to_switch`: an `if`/`else if` chain of strict equality checks to `switch`
+//! - `callback_to_promise`: drop a trailing node-style callback parameter and return a
+//!   `Promise` settled where the callback used to be invoked
+
+use crate::refactoring::parse_module_with_source_map;
+use mill_foundation::protocol::{EditLocation, EditPlan, EditType, TextEdit};
+use mill_lang_common::{
+    offset_to_position, refactoring::edit_plan_builder::EditPlanBuilder, IndentationDetector,
+    LineExtractor,
+};
+use mill_plugin_api::{PluginApiError, PluginResult};
+use swc_common::{sync::Lrc, BytePos, SourceMap, Span, Spanned};
+use swc_ecma_ast::*;
+use swc_ecma_visit::{Visit, VisitWith};
+
+/// Transform kinds implemented by the TypeScript plugin
+pub const SUPPORTED_TRANSFORMS: &[&str] = &[
+    "to_async",
+    "function_to_arrow",
+    "arrow_to_function",
+    "if_else_to_switch",
+    "callback_to_promise",
+];
+
+/// Plan a code transformation for TypeScript/JavaScript
+pub fn plan_transform(
+    source: &str,
+    kind: &str,
+    line: u32,
+    _character: u32,
+    file_path: &str,
+) -> PluginResult<EditPlan> {
+    let (module, cm) = parse_module_with_source_map(source, file_path)?;
+    let text = SourceText { source, cm };
+
+    let (edits, intent_args) = match kind {
+        "to_async" => plan_to_async(&text, &module, line)?,
+        "function_to_arrow" => plan_function_to_arrow(&text, &module, line)?,
+        "arrow_to_function" => plan_arrow_to_function(&text, &module, line)?,
+        "if_else_to_switch" => plan_if_else_to_switch(&text, &module, line)?,
+        "callback_to_promise" => plan_callback_to_promise(&text, &module, line)?,
+        other => {
+            return Err(PluginApiError::not_supported(format!(
+                "TypeScript transform '{}' (supported: {})",
+                other,
+                SUPPORTED_TRANSFORMS.join(", ")
+            )))
+        }
+    };
+
+    let complexity = (edits.len() as u8).saturating_add(2);
+    Ok(EditPlanBuilder::new(file_path, kind)
+        .with_edits(edits)
+        .with_syntax_validation("Verify syntax is valid after transform")
+        .with_intent_args(intent_args)
+        .with_complexity(complexity)
+        .with_impact_area("code_transform")
+        .build())
+}
+
+// ============================================================================
+// Source helpers
+// ============================================================================
+
+/// Source text plus the SWC source map used to resolve spans into it
+struct SourceText<'a> {
+    source: &'a str,
+    cm: Lrc<SourceMap>,
+}
+
+impl SourceText<'_> {
+    /// Byte offset of a position within the source
+    fn offset(&self, pos: BytePos) -> usize {
+        self.cm.lookup_byte_offset(pos).pos.0 as usize
+    }
+
+    fn line(&self, pos: BytePos) -> u32 {
+        offset_to_position(self.source, self.offset(pos)).0
+    }
+
+    fn slice(&self, lo: BytePos, hi: BytePos) -> &str {
+        &self.source[self.offset(lo)..self.offset(hi)]
+    }
+
+    fn text(&self, span: Span) -> &str {
+        self.slice(span.lo, span.hi)
+    }
+
+    fn indent_of(&self, pos: BytePos) -> String {
+        LineExtractor::get_indentation_str(self.source, self.line(pos))
+    }
+
+    fn indent_unit(&self) -> String {
+        let (indent_char, indent_size) = IndentationDetector::detect(self.source);
+        IndentationDetector::indent_string(1, indent_char, indent_size)
+    }
+
+    /// Build an edit replacing the byte range `[lo, hi)` with `new_text`
+    fn edit(&self, lo: usize, hi: usize, new_text: String, description: &str) -> TextEdit {
+        let (start_line, start_column) = offset_to_position(self.source, lo);
+        let (end_line, end_column) = offset_to_position(self.source, hi);
+        TextEdit {
+            file_path: None,
+            edit_type: if lo == hi {
+                EditType::Insert
+            } else {
+                EditType::Replace
+            },
+            location: EditLocation {
+                start_line,
+                start_column,
+                end_line,
+                end_column,
+            },
+            original_text: self.source[lo..hi].to_string(),
+            new_text,
+            priority: 100,
+            description: description.to_string(),
+        }
+    }
+}
+
+/// Indent every line after the first by `unit`
+fn reindent(text: &str, unit: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, l)| {
+            if i == 0 || l.trim().is_empty() {
+                l.to_string()
+            } else {
+                format!("{}{}", unit, l)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Detects `this`/`arguments` and unlabeled `break` inside a body without
+/// descending into nested scopes that rebind them.
+#[derive(Default)]
+struct ScopeUsage {
+    uses_this: bool,
+    uses_arguments: bool,
+    unlabeled_break: bool,
+    loop_depth: usize,
+}
+
+impl Visit for ScopeUsage {
+    fn visit_this_expr(&mut self, _: &ThisExpr) {
+        self.uses_this = true;
+    }
+
+    fn visit_ident(&mut self, node: &Ident) {
+        if &*node.sym == "arguments" {
+            self.uses_arguments = true;
+        }
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+
+    fn visit_break_stmt(&mut self, node: &BreakStmt) {
+        if node.label.is_none() && self.loop_depth == 0 {
+            self.unlabeled_break = true;
+        }
+    }
+
+    fn visit_for_stmt(&mut self, node: &ForStmt) {
+        self.loop_depth += 1;
+        node.visit_children_with(self);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_for_of_stmt(&mut self, node: &ForOfStmt) {
+        self.loop_depth += 1;
+        node.visit_children_with(self);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_for_in_stmt(&mut self, node: &ForInStmt) {
+        self.loop_depth += 1;
+        node.visit_children_with(self);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_while_stmt(&mut self, node: &WhileStmt) {
+        self.loop_depth += 1;
+        node.visit_children_with(self);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_do_while_stmt(&mut self, node: &DoWhileStmt) {
+        self.loop_depth += 1;
+        node.visit_children_with(self);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_switch_stmt(&mut self, node: &SwitchStmt) {
+        self.loop_depth += 1;
+        node.visit_children_with(self);
+        self.loop_depth -= 1;
+    }
+}
+
+fn scope_usage<N: VisitWith<ScopeUsage>>(node: &N) -> ScopeUsage {
+    let mut usage = ScopeUsage::default();
+    node.visit_children_with(&mut usage);
+    usage
+}
+
+// ============================================================================
+// Function discovery (to_async, callback_to_promise)
+// ============================================================================
+
+/// A function-like construct (declaration, expression, arrow or method)
+struct FunctionSite {
+    name: String,
+    span: Span,
+    /// Where `async ` is inserted
+    async_anchor: BytePos,
+    is_async: bool,
+    is_generator: bool,
+    /// False for getters, setters and constructors
+    can_be_async: bool,
+    params: Vec<Pat>,
+    body: Option<BlockStmt>,
+    return_type: Option<Box<TsTypeAnn>>,
+}
+
+struct FunctionFinder {
+    sites: Vec<FunctionSite>,
+}
+
+impl FunctionFinder {
+    fn push_function(&mut self, name: String, span: Span, anchor: BytePos, function: &Function) {
+        self.sites.push(FunctionSite {
+            name,
+            span,
+            async_anchor: anchor,
+            is_async: function.is_async,
+            is_generator: function.is_generator,
+            can_be_async: true,
+            params: function.params.iter().map(|p| p.pat.clone()).collect(),
+            body: function.body.clone(),
+            return_type: function.return_type.clone(),
+        });
+    }
+}
+
+fn prop_name(key: &PropName) -> String {
+    match key {
+        PropName::Ident(ident) => ident.sym.to_string(),
+        PropName::Str(s) => s.value.to_string_lossy().to_string(),
+        _ => "<computed>".to_string(),
+    }
+}
+
+impl Visit for FunctionFinder {
+    fn visit_fn_decl(&mut self, node: &FnDecl) {
+        let span = node.function.span;
+        self.push_function(node.ident.sym.to_string(), span, span.lo, &node.function);
+        node.visit_children_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, node: &FnExpr) {
+        let name = node
+            .ident
+            .as_ref()
+            .map(|i| i.sym.to_string())
+            .unwrap_or_else(|| "<anonymous>".to_string());
+        let span = node.function.span;
+        self.push_function(name, span, span.lo, &node.function);
+        node.visit_children_with(self);
+    }
+
+    fn visit_class_method(&mut self, node: &ClassMethod) {
+        self.push_function(
+            prop_name(&node.key),
+            node.span,
+            node.key.span().lo,
+            &node.function,
+        );
+        if let Some(site) = self.sites.last_mut() {
+            site.can_be_async = node.kind == MethodKind::Method;
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_method_prop(&mut self, node: &MethodProp) {
+        let span = Span::new(node.key.span().lo, node.function.span.hi);
+        self.push_function(prop_name(&node.key), span, span.lo, &node.function);
+        node.visit_children_with(self);
+    }
+
+    fn visit_arrow_expr(&mut self, node: &ArrowExpr) {
+        self.sites.push(FunctionSite {
+            name: "<arrow>".to_string(),
+            span: node.span,
+            async_anchor: node.span.lo,
+            is_async: node.is_async,
+            is_generator: node.is_generator,
+            can_be_async: true,
+            params: node.params.clone(),
+            body: match node.body.as_ref() {
+                BlockStmtOrExpr::BlockStmt(block) => Some(block.clone()),
+                BlockStmtOrExpr::Expr(_) => None,
+            },
+            return_type: node.return_type.clone(),
+        });
+        node.visit_children_with(self);
+    }
+}
+
+/// Pick the function at `line`: the outermost one starting on that line, or else
+/// the innermost one spanning it.
+fn find_function(text: &SourceText, module: &Module, line: u32) -> PluginResult<FunctionSite> {
+    let mut finder = FunctionFinder { sites: Vec::new() };
+    module.visit_with(&mut finder);
+
+    let starts_on_line = finder
+        .sites
+        .iter()
+        .position(|s| text.line(s.span.lo) == line);
+    let index = starts_on_line.or_else(|| {
+        finder
+            .sites
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| text.line(s.span.lo) <= line && line <= text.line(s.span.hi))
+            .min_by_key(|(_, s)| s.span.hi.0 - s.span.lo.0)
+            .map(|(i, _)| i)
+    });
+
+    index.map(|i| finder.sites.swap_remove(i)).ok_or_else(|| {
+        PluginApiError::invalid_input(format!("No function found at line {}", line + 1))
+    })
+}
+
+/// Text of `Promise<...>` wrapping for an explicit return type, if it needs one
+fn promise_return_type(text: &SourceText, return_type: &TsTypeAnn) -> Option<(Span, String)> {
+    let type_span = return_type.type_ann.span();
+    let current = text.text(type_span);
+    if current.starts_with("Promise<") {
+        None
+    } else {
+        Some((type_span, format!("Promise<{}>", current)))
+    }
+}
+
+fn plan_to_async(
+    text: &SourceText,
+    module: &Module,
+    line: u32,
+) -> PluginResult<(Vec<TextEdit>, serde_json::Value)> {
+    let site = find_function(text, module, line)?;
+
+    if site.is_async {
+        return Err(PluginApiError::invalid_input(format!(
+            "Function '{}' is already async",
+            site.name
+        )));
+    }
+    if !site.can_be_async {
+        return Err(PluginApiError::invalid_input(format!(
+            "'{}' is a getter, setter or constructor and cannot be async",
+            site.name
+        )));
+    }
+
+    let anchor = text.offset(site.async_anchor);
+    let mut edits = vec![text.edit(
+        anchor,
+        anchor,
+        "async ".to_string(),
+        &format!("Make '{}' async", site.name),
+    )];
+
+    if let Some((span, wrapped)) = site
+        .return_type
+        .as_deref()
+        .and_then(|rt| promise_return_type(text, rt))
+    {
+        edits.push(text.edit(
+            text.offset(span.lo),
+            text.offset(span.hi),
+            wrapped,
+            "Wrap return type in Promise",
+        ));
+    }
+
+    Ok((
+        edits,
+        serde_json::json!({ "kind": "to_async", "function_name": site.name }),
+    ))
+}
+
+// ============================================================================
+// function_to_arrow
+// ============================================================================
+
+struct FnDeclFinder {
+    decls: Vec<FnDecl>,
+}
+
+impl Visit for FnDeclFinder {
+    fn visit_fn_decl(&mut self, node: &FnDecl) {
+        self.decls.push(node.clone());
+        node.visit_children_with(self);
+    }
+}
+
+fn plan_function_to_arrow(
+    text: &SourceText,
+    module: &Module,
+    line: u32,
+) -> PluginResult<(Vec<TextEdit>, serde_json::Value)> {
+    let mut finder = FnDeclFinder { decls: Vec::new() };
+    module.visit_with(&mut finder);
+
+    let decl = finder
+        .decls
+        .into_iter()
+        .filter(|d| text.line(d.function.span.lo) <= line && line <= text.line(d.function.span.hi))
+        .min_by_key(|d| d.function.span.hi.0 - d.function.span.lo.0)
+        .ok_or_else(|| {
+            PluginApiError::invalid_input(format!(
+                "No function declaration found at line {}",
+                line + 1
+            ))
+        })?;
+
+    let name = decl.ident.sym.to_string();
+    let function = &decl.function;
+    let body = function
+        .body
+        .as_ref()
+        .ok_or_else(|| PluginApiError::invalid_input(format!("Function '{}' has no body", name)))?;
+    if function.is_generator {
+        return Err(PluginApiError::invalid_input(format!(
+            "Generator function '{}' cannot be converted to an arrow function",
+            name
+        )));
+    }
+    let usage = scope_usage(body);
+    if usage.uses_this || usage.uses_arguments {
+        return Err(PluginApiError::invalid_input(format!(
+            "Function '{}' uses `this` or `arguments`, which arrow functions do not bind",
+            name
+        )));
+    }
+
+    let signature = text.slice(decl.ident.span.hi, body.span.lo).trim();
+    let async_prefix = if function.is_async { "async " } else { "" };
+    let new_text = format!(
+        "const {} = {}{} => {};",
+        name,
+        async_prefix,
+        signature,
+        text.text(body.span)
+    );
+
+    let edit = text.edit(
+        text.offset(function.span.lo),
+        text.offset(function.span.hi),
+        new_text,
+        &format!("Convert '{}' to an arrow function", name),
+    );
+
+    Ok((
+        vec![edit],
+        serde_json::json!({ "kind": "function_to_arrow", "function_name": name }),
+    ))
+}
+
+// ============================================================================
+// arrow_to_function
+// ============================================================================
+
+struct ArrowDeclFinder {
+    decls: Vec<VarDecl>,
+}
+
+impl Visit for ArrowDeclFinder {
+    fn visit_var_decl(&mut self, node: &VarDecl) {
+        let is_arrow = node.decls.len() == 1
+            && matches!(
+                node.decls[0].init.as_deref(),
+                Some(Expr::Arrow(_)) | Some(Expr::Paren(_))
+            );
+        if is_arrow {
+            self.decls.push(node.clone());
+        }
+        node.visit_children_with(self);
+    }
+}
+
+fn unwrap_arrow(expr: &Expr) -> Option<&ArrowExpr> {
+    match expr {
+        Expr::Arrow(arrow) => Some(arrow),
+        Expr::Paren(paren) => unwrap_arrow(&paren.expr),
+        _ => None,
+    }
+}
+
+fn plan_arrow_to_function(
+    text: &SourceText,
+    module: &Module,
+    line: u32,
+) -> PluginResult<(Vec<TextEdit>, serde_json::Value)> {
+    let mut finder = ArrowDeclFinder { decls: Vec::new() };
+    module.visit_with(&mut finder);
+
+    let decl = finder
+        .decls
+        .into_iter()
+        .filter(|d| text.line(d.span.lo) <= line && line <= text.line(d.span.hi))
+        .min_by_key(|d| d.span.hi.0 - d.span.lo.0)
+        .ok_or_else(|| {
+            PluginApiError::invalid_input(format!(
+                "No arrow function assigned to a variable found at line {}",
+                line + 1
+            ))
+        })?;
+
+    let declarator = &decl.decls[0];
+    let binding = match &declarator.name {
+        Pat::Ident(binding) => binding,
+        _ => {
+            return Err(PluginApiError::invalid_input(
+                "Only simple `const name = () => ...` declarations can be converted",
+            ))
+        }
+    };
+    let name = binding.id.sym.to_string();
+    if binding.type_ann.is_some() {
+        return Err(PluginApiError::invalid_input(format!(
+            "'{}' has a variable type annotation that a function declaration cannot carry",
+            name
+        )));
+    }
+
+    let arrow = declarator
+        .init
+        .as_deref()
+        .and_then(unwrap_arrow)
+        .ok_or_else(|| {
+            PluginApiError::invalid_input(format!("'{}' is not an arrow function", name))
+        })?;
+
+    let usage = scope_usage(arrow.body.as_ref());
+    if usage.uses_this || usage.uses_arguments {
+        return Err(PluginApiError::invalid_input(format!(
+            "Arrow function '{}' relies on lexical `this` or `arguments`",
+            name
+        )));
+    }
+
+    let body_span = arrow.body.span();
+    let mut signature = text.slice(arrow.span.lo, body_span.lo).trim_end();
+    signature = signature.strip_suffix("=>").unwrap_or(signature).trim();
+    if arrow.is_async {
+        signature = signature.trim_start_matches("async").trim_start();
+    }
+    // `x => x` has no parentheses around its single parameter
+    let signature = if signature.starts_with('(') || signature.starts_with('<') {
+        signature.to_string()
+    } else {
+        format!("({})", signature)
+    };
+
+    let body = match arrow.body.as_ref() {
+        BlockStmtOrExpr::BlockStmt(block) => text.text(block.span).to_string(),
+        BlockStmtOrExpr::Expr(expr) => {
+            let indent = text.indent_of(decl.span.lo);
+            format!(
+                "{{\n{}{}return {};\n{}}}",
+                indent,
+                text.indent_unit(),
+                text.text(expr.span()),
+                indent
+            )
+        }
+    };
+
+    let async_prefix = if arrow.is_async { "async " } else { "" };
+    let new_text = format!("{}function {}{} {}", async_prefix, name, signature, body);
+
+    // Swallow the declaration's trailing semicolon when the span excludes it
+    let lo = text.offset(decl.span.lo);
+    let mut hi = text.offset(decl.span.hi);
+    if text.source[hi..].starts_with(';') {
+        hi += 1;
+    }
+
+    let edit = text.edit(
+        lo,
+        hi,
+        new_text,
+        &format!("Convert '{}' to a function declaration", name),
+    );
+
+    Ok((
+        vec![edit],
+        serde_json::json!({ "kind": "arrow_to_function", "function_name": name }),
+    ))
+}
+
+// ============================================================================
+// if_else_to_switch
+// ============================================================================
+
+struct IfFinder {
+    ifs: Vec<IfStmt>,
+    chain_links: Vec<BytePos>,
+}
+
+impl Visit for IfFinder {
+    fn visit_if_stmt(&mut self, node: &IfStmt) {
+        if !self.chain_links.contains(&node.span.lo) {
+            self.ifs.push(node.clone());
+        }
+        if let Some(Stmt::If(inner)) = node.alt.as_deref() {
+            self.chain_links.push(inner.span.lo);
+        }
+        node.visit_children_with(self);
+    }
+}
+
+/// Collect `case` tests from `x === A || x === B`, checking the discriminant
+fn collect_cases(
+    text: &SourceText,
+    test: &Expr,
+    discriminant: &mut Option<String>,
+    cases: &mut Vec<String>,
+) -> PluginResult<()> {
+    match test {
+        Expr::Paren(paren) => collect_cases(text, &paren.expr, discriminant, cases),
+        Expr::Bin(bin) if bin.op == BinaryOp::LogicalOr => {
+            collect_cases(text, &bin.left, discriminant, cases)?;
+            collect_cases(text, &bin.right, discriminant, cases)
+        }
+        Expr::Bin(bin) if bin.op == BinaryOp::EqEqEq => {
+            let left = text.text(bin.left.span()).to_string();
+            let right = text.text(bin.right.span()).to_string();
+            let (subject, case) = match discriminant.as_deref() {
+                Some(d) if d == left => (left, right),
+                Some(d) if d == right => (right, left),
+                Some(_) => {
+                    return Err(PluginApiError::invalid_input(
+                        "All branches must compare the same expression to be converted to a switch",
+                    ))
+                }
+                None if matches!(bin.left.as_ref(), Expr::Lit(_)) => (right, left),
+                None => (left, right),
+            };
+            *discriminant = Some(subject);
+            cases.push(case);
+            Ok(())
+        }
+        Expr::Bin(bin) if bin.op == BinaryOp::EqEq => Err(PluginApiError::invalid_input(format!(
+            "`{}` uses loose equality; switch compares with `===` so the conversion would change behavior",
+            text.text(bin.span)
+        ))),
+        _ => Err(PluginApiError::invalid_input(format!(
+            "Condition `{}` is not a strict equality comparison",
+            text.text(test.span())
+        ))),
+    }
+}
+
+/// Render the statements of an `if` branch as the body of a `case` clause
+fn case_body(text: &SourceText, stmt: &Stmt, indent: &str, unit: &str) -> PluginResult<String> {
+    let usage = scope_usage(stmt);
+    if usage.unlabeled_break {
+        return Err(PluginApiError::invalid_input(
+            "Branch contains an unlabeled `break` that would exit the switch instead of the loop",
+        ));
+    }
+
+    let (stmts, body) = match stmt {
+        Stmt::Block(block) => {
+            let inner = text.slice(block.span.lo + BytePos(1), block.span.hi - BytePos(1));
+            let lines: Vec<String> = inner
+                .trim_matches('\n')
+                .trim_end()
+                .lines()
+                .map(|l| {
+                    if l.trim().is_empty() {
+                        String::new()
+                    } else {
+                        format!("{}{}", unit, l)
+                    }
+                })
+                .collect();
+            (block.stmts.as_slice(), lines.join("\n"))
+        }
+        other => (
+            std::slice::from_ref(other),
+            format!(
+                "{}{}{}",
+                indent,
+                unit,
+                reindent(text.text(other.span()), unit)
+            ),
+        ),
+    };
+
+    let terminates = matches!(
+        stmts.last(),
+        Some(Stmt::Return(_)) | Some(Stmt::Throw(_)) | Some(Stmt::Continue(_))
+    );
+
+    let mut out = String::new();
+    if !body.trim().is_empty() {
+        out.push_str(&body);
+        out.push('\n');
+    }
+    if !terminates {
+        out.push_str(&format!("{}{}break;\n", indent, unit));
+    }
+    Ok(out)
+}
+
+fn plan_if_else_to_switch(
+    text: &SourceText,
+    module: &Module,
+    line: u32,
+) -> PluginResult<(Vec<TextEdit>, serde_json::Value)> {
+    let mut finder = IfFinder {
+        ifs: Vec::new(),
+        chain_links: Vec::new(),
+    };
+    module.visit_with(&mut finder);
+
+    let head = finder
+        .ifs
+        .into_iter()
+        .find(|s| text.line(s.span.lo) == line)
+        .ok_or_else(|| {
+            PluginApiError::invalid_input(format!(
+                "No `if` statement starts at line {} (point at the head of the chain)",
+                line + 1
+            ))
+        })?;
+
+    let base = text.indent_of(head.span.lo);
+    let unit = text.indent_unit();
+    let case_indent = format!("{}{}", base, unit);
+
+    let mut discriminant = None;
+    let mut clauses = Vec::new();
+    let mut current = &head;
+
+    loop {
+        let mut cases = Vec::new();
+        collect_cases(text, &current.test, &mut discriminant, &mut cases)?;
+        let body = case_body(text, &current.cons, &case_indent, &unit)?;
+        let labels: Vec<String> = cases
+            .iter()
+            .map(|c| format!("{}case {}:", case_indent, c))
+            .collect();
+        clauses.push(format!(
+            "{} {{\n{}{}}}",
+            labels.join("\n"),
+            body,
+            case_indent
+        ));
+
+        match current.alt.as_deref() {
+            None => break,
+            Some(Stmt::If(inner)) => current = inner,
+            Some(other) => {
+                let body = case_body(text, other, &case_indent, &unit)?;
+                clauses.push(format!(
+                    "{}default: {{\n{}{}}}",
+                    case_indent, body, case_indent
+                ));
+                break;
+            }
+        }
+    }
+
+    let discriminant = discriminant
+        .ok_or_else(|| PluginApiError::invalid_input("Could not determine switch discriminant"))?;
+    let new_text = format!(
+        "switch ({}) {{\n{}\n{}}}",
+        discriminant,
+        clauses.join("\n"),
+        base
+    );
+
+    let edit = text.edit(
+        text.offset(head.span.lo),
+        text.offset(head.span.hi),
+        new_text,
+        "Convert if/else chain to switch",
+    );
+
+    Ok((
+        vec![edit],
+        serde_json::json!({ "kind": "if_else_to_switch", "clauses": clauses.len() }),
+    ))
+}
+
+// ============================================================================
+// callback_to_promise
+// ============================================================================
+
+/// Finds every reference to the callback parameter inside the function body
+struct CallbackUses<'a> {
+    name: &'a str,
+    calls: Vec<CallExpr>,
+    other_uses: usize,
+}
+
+impl Visit for CallbackUses<'_> {
+    fn visit_call_expr(&mut self, node: &CallExpr) {
+        if let Callee::Expr(callee) = &node.callee {
+            if let Expr::Ident(ident) = callee.as_ref() {
+                if &*ident.sym == self.name {
+                    self.calls.push(node.clone());
+                    for arg in &node.args {
+                        arg.visit_with(self);
+                    }
+                    return;
+                }
+            }
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_ident(&mut self, node: &Ident) {
+        if &*node.sym == self.name {
+            self.other_uses += 1;
+        }
+    }
+}
+
+fn is_nullish(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(Lit::Null(_)) => true,
+        Expr::Ident(ident) => &*ident.sym == "undefined",
+        _ => false,
+    }
+}
+
+/// Result type of a callback typed as `(err: E, value: T) => void`
+fn callback_value_type(text: &SourceText, binding: &BindingIdent) -> Option<String> {
+    let ann = binding.type_ann.as_ref()?;
+    if let TsType::TsFnOrConstructorType(TsFnOrConstructorType::TsFnType(fn_type)) =
+        ann.type_ann.as_ref()
+    {
+        if let Some(TsFnParam::Ident(value)) = fn_type.params.get(1) {
+            return value
+                .type_ann
+                .as_ref()
+                .map(|t| text.text(t.type_ann.span()).to_string());
+        }
+        if fn_type.params.len() == 1 {
+            return Some("void".to_string());
+        }
+    }
+    None
+}
+
+fn plan_callback_to_promise(
+    text: &SourceText,
+    module: &Module,
+    line: u32,
+) -> PluginResult<(Vec<TextEdit>, serde_json::Value)> {
+    let site = find_function(text, module, line)?;
+
+    let body = site.body.as_ref().ok_or_else(|| {
+        PluginApiError::invalid_input(format!(
+            "Function '{}' needs a block body to be converted",
+            site.name
+        ))
+    })?;
+    if site.is_generator {
+        return Err(PluginApiError::invalid_input(format!(
+            "Generator function '{}' cannot return a Promise",
+            site.name
+        )));
+    }
+    let callback = match site.params.last() {
+        Some(Pat::Ident(binding)) => binding,
+        _ => {
+            return Err(PluginApiError::invalid_input(format!(
+                "Function '{}' has no trailing callback parameter",
+                site.name
+            )))
+        }
+    };
+    let callback_name = callback.id.sym.to_string();
+
+    let mut uses = CallbackUses {
+        name: &callback_name,
+        calls: Vec::new(),
+        other_uses: 0,
+    };
+    body.visit_with(&mut uses);
+    if uses.calls.is_empty() {
+        return Err(PluginApiError::invalid_input(format!(
+            "Callback '{}' is never invoked in '{}'",
+            callback_name, site.name
+        )));
+    }
+    if uses.other_uses > 0 {
+        return Err(PluginApiError::invalid_input(format!(
+            "Callback '{}' is passed around as a value; only direct calls can be converted",
+            callback_name
+        )));
+    }
+
+    let mut edits = Vec::new();
+
+    // 1. Drop the callback parameter (and the separator before it)
+    let callback_span = site.params.last().map(|p| p.span()).unwrap_or_default();
+    let remove_from = match site.params.len() {
+        1 => text.offset(callback_span.lo),
+        n => text.offset(site.params[n - 2].span().hi),
+    };
+    edits.push(text.edit(
+        remove_from,
+        text.offset(callback_span.hi),
+        String::new(),
+        &format!("Remove callback parameter '{}'", callback_name),
+    ));
+
+    // 2. Promise return type when the function declares one
+    if let Some(return_type) = site.return_type.as_deref() {
+        let value_type =
+            callback_value_type(text, callback).unwrap_or_else(|| "unknown".to_string());
+        let type_span = return_type.type_ann.span();
+        edits.push(text.edit(
+            text.offset(type_span.lo),
+            text.offset(type_span.hi),
+            format!("Promise<{}>", value_type),
+            "Return a Promise",
+        ));
+    }
+
+    // 3. Settle the promise where the callback was called
+    let body_lo = text.offset(body.span.lo) + 1;
+    let body_hi = text.offset(body.span.hi) - 1;
+    let mut inner = text.source[body_lo..body_hi].to_string();
+    let mut calls = uses.calls;
+    calls.sort_by_key(|c| std::cmp::Reverse(c.span.lo));
+    for call in &calls {
+        let args: Vec<&str> = call.args.iter().map(|a| text.text(a.expr.span())).collect();
+        let replacement = match call.args.as_slice() {
+            [] => "resolve()".to_string(),
+            [error] if is_nullish(&error.expr) => "resolve()".to_string(),
+            [_] => format!("reject({})", args[0]),
+            [error, ..] if is_nullish(&error.expr) => format!("resolve({})", args[1]),
+            _ => format!("({} ? reject({}) : resolve({}))", args[0], args[0], args[1]),
+        };
+        let lo = text.offset(call.span.lo) - body_lo;
+        let hi = text.offset(call.span.hi) - body_lo;
+        inner.replace_range(lo..hi, &replacement);
+    }
+
+    let indent = text.indent_of(site.span.lo);
+    let unit = text.indent_unit();
+    let wrapped: Vec<String> = inner
+        .trim_matches('\n')
+        .trim_end()
+        .lines()
+        .map(|l| {
+            if l.trim().is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", unit, l)
+            }
+        })
+        .collect();
+    let new_body = format!(
+        "{{\n{i}{u}return new Promise((resolve, reject) => {{\n{body}\n{i}{u}}});\n{i}}}",
+        i = indent,
+        u = unit,
+        body = wrapped.join("\n")
+    );
+    edits.push(text.edit(
+        text.offset(body.span.lo),
+        text.offset(body.span.hi),
+        new_body,
+        "Wrap body in a Promise",
+    ));
+
+    Ok((
+        edits,
+        serde_json::json!({
+            "kind": "callback_to_promise",
+            "function_name": site.name,
+            "callback": callback_name,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply all edits of a plan, last position first
+    fn apply(source: &str, plan: &EditPlan) -> String {
+        let mut edits = plan.edits.clone();
+        edits.sort_by_key(|e| std::cmp::Reverse((e.location.start_line, e.location.start_column)));
+        let mut result = source.to_string();
+        for edit in edits {
+            let loc = &edit.location;
+            let start =
+                mill_lang_common::position_to_offset(&result, loc.start_line, loc.start_column)
+                    .unwrap();
+            let end = mill_lang_common::position_to_offset(&result, loc.end_line, loc.end_column)
+                .unwrap();
+            result.replace_range(start..end, &edit.new_text);
+        }
+        result
+    }
+
+    fn transform(source: &str, kind: &str, line: u32) -> PluginResult<String> {
+        plan_transform(source, kind, line, 0, "test.ts").map(|plan| apply(source, &plan))
+    }
+
+    #[test]
+    fn test_to_async_function_declaration() {
+        let source = "export function load(id: string): User {\n  return db.get(id);\n}\n";
+        assert_eq!(
+            transform(source, "to_async", 0).unwrap(),
+            "export async function load(id: string): Promise<User> {\n  return db.get(id);\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_async_arrow_and_method() {
+        let source = "const f = (x: number) => x;\nclass A {\n  static run(): void {}\n}\n";
+        assert_eq!(
+            transform(source, "to_async", 0).unwrap(),
+            "const f = async (x: number) => x;\nclass A {\n  static run(): void {}\n}\n"
+        );
+        assert_eq!(
+            transform(source, "to_async", 2).unwrap(),
+            "const f = (x: number) => x;\nclass A {\n  static async run(): Promise<void> {}\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_async_rejects_getter() {
+        let source = "class A {\n  get value() { return 1; }\n}\n";
+        assert!(transform(source, "to_async", 1).is_err());
+    }
+
+    #[test]
+    fn test_function_to_arrow() {
+        let source = "export async function add<T>(a: T, b: T): T {\n  return a;\n}\n";
+        assert_eq!(
+            transform(source, "function_to_arrow", 0).unwrap(),
+            "export const add = async <T>(a: T, b: T): T => {\n  return a;\n};\n"
+        );
+    }
+
+    #[test]
+    fn test_function_to_arrow_rejects_this() {
+        let source = "function f() {\n  return this.x;\n}\n";
+        assert!(transform(source, "function_to_arrow", 0).is_err());
+    }
+
+    #[test]
+    fn test_arrow_to_function_expression_body() {
+        let source = "const double = (n: number): number => n * 2;\n";
+        assert_eq!(
+            transform(source, "arrow_to_function", 0).unwrap(),
+            "function double(n: number): number {\n    return n * 2;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_arrow_to_function_async_block_body() {
+        let source = "export const load = async id => {\n  return fetch(id);\n};\n";
+        assert_eq!(
+            transform(source, "arrow_to_function", 0).unwrap(),
+            "export async function load(id) {\n  return fetch(id);\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_if_else_to_switch() {
+        let source = "function f(kind: string) {\n  if (kind === 'a' || kind === 'b') {\n    one();\n  } else if (kind === 'c') {\n    return two();\n  } else {\n    three();\n  }\n}\n";
+        let expected = "function f(kind: string) {\n  switch (kind) {\n    case 'a':\n    case 'b': {\n      one();\n      break;\n    }\n    case 'c': {\n      return two();\n    }\n    default: {\n      three();\n      break;\n    }\n  }\n}\n";
+        assert_eq!(transform(source, "if_else_to_switch", 1).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_if_else_to_switch_rejects_loose_equality() {
+        let source = "if (x == 1) {\n  a();\n}\n";
+        assert!(transform(source, "if_else_to_switch", 0).is_err());
+    }
+
+    #[test]
+    fn test_if_else_to_switch_rejects_loop_break() {
+        let source = "for (const x of xs) {\n  if (x === 1) {\n    break;\n  }\n}\n";
+        assert!(transform(source, "if_else_to_switch", 1).is_err());
+    }
+
+    #[test]
+    fn test_callback_to_promise() {
+        let source = "function read(path: string, cb: (err: Error | null, data?: string) => void): void {\n  fs.readFile(path, (err, data) => {\n    if (err) {\n      cb(err);\n      return;\n    }\n    cb(null, data);\n  });\n}\n";
+        let expected = "function read(path: string): Promise<string> {\n  return new Promise((resolve, reject) => {\n    fs.readFile(path, (err, data) => {\n      if (err) {\n        reject(err);\n        return;\n      }\n      resolve(data);\n    });\n  });\n}\n";
+        assert_eq!(
+            transform(source, "callback_to_promise", 0).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_callback_to_promise_rejects_escaping_callback() {
+        let source = "function read(path, cb) {\n  fs.readFile(path, cb);\n}\n";
+        assert!(transform(source, "callback_to_promise", 0).is_err());
+    }
+}
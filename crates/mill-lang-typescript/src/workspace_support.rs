@@ -6,6 +6,7 @@ use async_trait::async_trait;
 use mill_foundation::protocol::ConsolidationMetadata;
 use mill_plugin_api::WorkspaceSupport;
 use serde_json::{json, Value};
+use std::ops::Range;
 use std::path::Path;
 use tracing::{debug, info, warn};
 
@@ -113,6 +114,10 @@ impl WorkspaceSupport for TypeScriptWorkspaceSupport {
         }
     }
 
+    fn additional_workspace_manifests(&self) -> &'static [&'static str] {
+        &["pnpm-workspace.yaml"]
+    }
+
     /// Check if a directory is an npm package
     async fn is_package(&self, dir_path: &Path) -> bool {
         tokio::fs::try_exists(dir_path.join("package.json"))
@@ -187,73 +192,106 @@ fn detect_format(content: &str) -> WorkspaceFormat {
 // ============================================================================
 
 /// Add member to package.json workspaces
+///
+/// The manifest is edited in place so key order, indentation and line
+/// layout are kept.
 fn add_package_json_member(content: &str, member: &str) -> Result<String, String> {
-    let mut parsed: Value = serde_json::from_str(content)
+    let parsed: Value = serde_json::from_str(content)
         .map_err(|e| format!("Failed to parse package.json: {}", e))?;
-
-    // Get or create workspaces field
-    let workspaces = parsed.get_mut("workspaces");
-
-    match workspaces {
-        Some(Value::Array(arr)) => {
-            // Array format: "workspaces": ["packages/*"]
-            if !arr.iter().any(|v| v.as_str() == Some(member)) {
-                arr.push(json!(member));
-            } else {
-                debug!(member = %member, "Member already exists in workspace");
-                return Ok(content.to_string());
-            }
-        }
-        Some(Value::Object(obj)) => {
-            // Object format (Yarn v1): "workspaces": { "packages": [...] }
-            if let Some(Value::Array(packages)) = obj.get_mut("packages") {
-                if !packages.iter().any(|v| v.as_str() == Some(member)) {
-                    packages.push(json!(member));
-                } else {
-                    debug!(member = %member, "Member already exists in workspace");
-                    return Ok(content.to_string());
-                }
-            } else {
-                return Err("workspaces.packages is not an array".to_string());
-            }
-        }
-        None => {
-            // Create workspaces array
-            parsed["workspaces"] = json!([member]);
-        }
-        _ => {
-            return Err("Invalid workspaces format".to_string());
-        }
+    let encoded = serde_json::to_string(member)
+        .map_err(|e| format!("Failed to encode workspace member: {}", e))?;
+
+    let root = JsonObject::parse(content, content.len() - content.trim_start().len())?;
+    let Some(workspaces) = root.get("workspaces") else {
+        // Create workspaces array as the last key of the root object
+        let entry = format!("\"workspaces\": [{}]", encoded);
+        return Ok(insert_after_last(
+            content,
+            root.open,
+            root.close,
+            root.entries.last().map(|(_, _, value)| value.end),
+            &entry,
+        ));
+    };
+
+    if list_package_json_members(content)?
+        .iter()
+        .any(|m| m == member)
+    {
+        debug!(member = %member, "Member already exists in workspace");
+        return Ok(content.to_string());
     }
 
-    serde_json::to_string_pretty(&parsed)
-        .map_err(|e| format!("Failed to serialize package.json: {}", e))
+    let array = match parsed.get("workspaces") {
+        // Array format: "workspaces": ["packages/*"]
+        Some(Value::Array(_)) => JsonArray::parse(content, workspaces.start)?,
+        // Object format (Yarn v1): "workspaces": { "packages": [...] }
+        Some(Value::Object(obj)) if obj.get("packages").is_some_and(Value::is_array) => {
+            let object = JsonObject::parse(content, workspaces.start)?;
+            let packages = object
+                .get("packages")
+                .ok_or("workspaces.packages is not an array")?;
+            JsonArray::parse(content, packages.start)?
+        }
+        Some(Value::Object(_)) => return Err("workspaces.packages is not an array".to_string()),
+        _ => return Err("Invalid workspaces format".to_string()),
+    };
+
+    Ok(insert_after_last(
+        content,
+        array.open,
+        array.close,
+        array.elements.last().map(|element| element.end),
+        &encoded,
+    ))
 }
 
 /// Remove member from package.json workspaces
+///
+/// Only the member and one adjoining separator are removed.
 fn remove_package_json_member(content: &str, member: &str) -> Result<String, String> {
-    let mut parsed: Value = serde_json::from_str(content)
+    let parsed: Value = serde_json::from_str(content)
         .map_err(|e| format!("Failed to parse package.json: {}", e))?;
 
-    let workspaces = parsed.get_mut("workspaces");
-
-    match workspaces {
-        Some(Value::Array(arr)) => {
-            arr.retain(|v| v.as_str() != Some(member));
-        }
-        Some(Value::Object(obj)) => {
-            if let Some(Value::Array(packages)) = obj.get_mut("packages") {
-                packages.retain(|v| v.as_str() != Some(member));
+    let root = JsonObject::parse(content, content.len() - content.trim_start().len())?;
+    let array = match (parsed.get("workspaces"), root.get("workspaces")) {
+        (Some(Value::Array(_)), Some(span)) => JsonArray::parse(content, span.start)?,
+        (Some(Value::Object(_)), Some(span)) => {
+            match JsonObject::parse(content, span.start)?.get("packages") {
+                Some(packages) if content[packages.start..].starts_with('[') => {
+                    JsonArray::parse(content, packages.start)?
+                }
+                _ => {
+                    debug!(member = %member, "Member not found in workspace");
+                    return Ok(content.to_string());
+                }
             }
         }
         _ => {
             debug!(member = %member, "Member not found in workspace");
             return Ok(content.to_string());
         }
-    }
+    };
 
-    serde_json::to_string_pretty(&parsed)
-        .map_err(|e| format!("Failed to serialize package.json: {}", e))
+    let position = array.elements.iter().position(|element| {
+        serde_json::from_str::<String>(&content[element.clone()]).is_ok_and(|value| value == member)
+    });
+    let Some(index) = position else {
+        debug!(member = %member, "Member not found in workspace");
+        return Ok(content.to_string());
+    };
+
+    let removed = if array.elements.len() == 1 {
+        array.open + 1..array.close
+    } else if index + 1 < array.elements.len() {
+        array.elements[index].start..array.elements[index + 1].start
+    } else {
+        array.elements[index - 1].end..array.elements[index].end
+    };
+
+    let mut result = content.to_string();
+    result.replace_range(removed, "");
+    Ok(result)
 }
 
 /// List members from package.json workspaces
@@ -297,127 +335,413 @@ fn update_package_json_name(content: &str, new_name: &str) -> Result<String, Str
 // pnpm-workspace.yaml Operations
 // ============================================================================
 
+/// The `packages:` entry of a pnpm-workspace.yaml
+struct PnpmPackages<'a> {
+    lines: Vec<&'a str>,
+    /// Index of the `packages:` line
+    key_line: usize,
+    /// Inline sequence (`packages: ['a', 'b']`) instead of a block sequence
+    flow: Option<Vec<String>>,
+    /// Indices of the block sequence's `- item` lines
+    item_lines: Vec<usize>,
+}
+
+impl<'a> PnpmPackages<'a> {
+    fn parse(content: &'a str) -> Option<Self> {
+        let lines: Vec<&str> = content.lines().collect();
+        let key_line = lines
+            .iter()
+            .position(|line| line.trim_end().starts_with("packages:"))?;
+        let inline = strip_yaml_comment(&lines[key_line]["packages:".len()..]).trim();
+        let flow = inline
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+            .map(|items| {
+                items
+                    .split(',')
+                    .map(unquote_yaml)
+                    .filter(|item| !item.is_empty())
+                    .collect()
+            });
+
+        let mut item_lines = Vec::new();
+        if flow.is_none() {
+            for (index, line) in lines.iter().enumerate().skip(key_line + 1) {
+                let trimmed = line.trim();
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    continue;
+                }
+                if !trimmed.starts_with('-') {
+                    // Leaving the packages section
+                    break;
+                }
+                item_lines.push(index);
+            }
+        }
+
+        Some(Self {
+            lines,
+            key_line,
+            flow,
+            item_lines,
+        })
+    }
+
+    fn members(&self) -> Vec<String> {
+        match &self.flow {
+            Some(items) => items.clone(),
+            None => self
+                .item_lines
+                .iter()
+                .map(|&index| unquote_yaml(self.lines[index].trim().trim_start_matches('-')))
+                .filter(|member| !member.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Quote character used by existing members (`'` by default)
+    fn quote(&self) -> &'static str {
+        let first = match &self.flow {
+            Some(_) => self.lines[self.key_line]
+                .split_once('[')
+                .map(|(_, rest)| rest.trim_start()),
+            None => self.item_lines.first().map(|&index| {
+                self.lines[index]
+                    .trim()
+                    .trim_start_matches('-')
+                    .trim_start()
+            }),
+        };
+        match first.and_then(|item| item.chars().next()) {
+            Some('"') => "\"",
+            Some('\'') | None | Some(']') => "'",
+            Some(_) => "",
+        }
+    }
+
+    /// Rewrite the inline sequence with the given members
+    fn flow_line(&self, members: &[String]) -> String {
+        let line = self.lines[self.key_line];
+        let quote = self.quote();
+        let items: Vec<String> = members
+            .iter()
+            .map(|member| format!("{}{}{}", quote, member, quote))
+            .collect();
+        let comment = line
+            .find(" #")
+            .filter(|&i| i > line.rfind(']').unwrap_or(0))
+            .map(|i| &line[i..])
+            .unwrap_or("");
+        let indent = &line[..line.len() - line.trim_start().len()];
+        format!("{}packages: [{}]{}", indent, items.join(", "), comment)
+    }
+
+    fn render(lines: &[String], content: &str) -> String {
+        let mut result = lines.join("\n");
+        if content.ends_with('\n') || content.is_empty() {
+            result.push('\n');
+        }
+        result
+    }
+}
+
 /// Add member to pnpm-workspace.yaml
+///
+/// New block entries follow the indentation and quoting of the last entry.
 fn add_pnpm_member(content: &str, member: &str) -> Result<String, String> {
-    let members = list_pnpm_members(content)?;
+    let Some(packages) = PnpmPackages::parse(content) else {
+        let mut result = content.to_string();
+        if !result.is_empty() && !result.ends_with('\n') {
+            result.push('\n');
+        }
+        result.push_str(&format!("packages:\n  - '{}'\n", member));
+        return Ok(result);
+    };
 
+    let mut members = packages.members();
     if members.iter().any(|m| m == member) {
         debug!(member = %member, "Member already exists in pnpm workspace");
         return Ok(content.to_string());
     }
 
-    // Parse and add member
-    let mut result = String::new();
-    let mut in_packages = false;
-    let mut added = false;
+    let mut lines: Vec<String> = packages.lines.iter().map(|l| l.to_string()).collect();
+    if packages.flow.is_some() {
+        members.push(member.to_string());
+        lines[packages.key_line] = packages.flow_line(&members);
+    } else {
+        let quote = packages.quote();
+        let (index, indent) = match packages.item_lines.last() {
+            Some(&last) => {
+                let line = packages.lines[last];
+                (last + 1, &line[..line.len() - line.trim_start().len()])
+            }
+            None => (packages.key_line + 1, "  "),
+        };
+        lines.insert(index, format!("{}- {}{}{}", indent, quote, member, quote));
+    }
 
-    for line in content.lines() {
-        let trimmed = line.trim();
+    Ok(PnpmPackages::render(&lines, content))
+}
 
-        if trimmed.starts_with("packages:") {
-            in_packages = true;
-            result.push_str(line);
-            result.push('\n');
-            continue;
-        }
+/// Remove member from pnpm-workspace.yaml
+fn remove_pnpm_member(content: &str, member: &str) -> Result<String, String> {
+    let Some(packages) = PnpmPackages::parse(content) else {
+        return Ok(content.to_string());
+    };
 
-        // Check if we're leaving packages section
-        if in_packages
-            && !trimmed.is_empty()
-            && !trimmed.starts_with('-')
-            && !trimmed.starts_with('#')
-        {
-            // Add member before leaving section
-            if !added {
-                result.push_str(&format!("  - '{}'\n", member));
-                added = true;
-            }
-            in_packages = false;
-        }
+    let members = packages.members();
+    if !members.iter().any(|m| m == member) {
+        debug!(member = %member, "Member not found in pnpm workspace");
+        return Ok(content.to_string());
+    }
 
-        // Add member at end if still in packages section
-        if in_packages && line.trim().is_empty() && !added {
-            result.push_str(&format!("  - '{}'\n", member));
-            added = true;
+    let mut lines: Vec<String> = packages.lines.iter().map(|l| l.to_string()).collect();
+    if packages.flow.is_some() {
+        let remaining: Vec<String> = members.into_iter().filter(|m| m != member).collect();
+        lines[packages.key_line] = packages.flow_line(&remaining);
+    } else {
+        let removed: Vec<usize> = packages
+            .item_lines
+            .iter()
+            .copied()
+            .filter(|&index| {
+                unquote_yaml(packages.lines[index].trim().trim_start_matches('-')) == member
+            })
+            .collect();
+        for index in removed.into_iter().rev() {
+            lines.remove(index);
         }
+    }
+
+    Ok(PnpmPackages::render(&lines, content))
+}
+
+/// List members from pnpm-workspace.yaml
+fn list_pnpm_members(content: &str) -> Result<Vec<String>, String> {
+    Ok(PnpmPackages::parse(content)
+        .map(|packages| packages.members())
+        .unwrap_or_default())
+}
 
-        result.push_str(line);
-        result.push('\n');
+/// Strip a trailing ` # comment` from a YAML scalar
+fn strip_yaml_comment(value: &str) -> &str {
+    match value.find(" #") {
+        Some(index) => &value[..index],
+        None => value,
     }
+}
 
-    // If we never added it, add at the end
-    if !added {
-        if !result.ends_with('\n') {
-            result.push('\n');
+/// Unquote a YAML sequence item (`'packages/*'`, `"apps/*"` or `tools/*`)
+fn unquote_yaml(value: &str) -> String {
+    strip_yaml_comment(value)
+        .trim()
+        .trim_matches('\'')
+        .trim_matches('"')
+        .to_string()
+}
+
+// ============================================================================
+// Format-preserving JSON editing
+// ============================================================================
+
+/// Insert `item` as the last element of a JSON array or object
+///
+/// Multi-line containers get the new item on its own line with the last
+/// item's indentation; single-line containers reuse their separator.
+fn insert_after_last(
+    content: &str,
+    open: usize,
+    close: usize,
+    last_end: Option<usize>,
+    item: &str,
+) -> String {
+    let mut result = content.to_string();
+    let Some(last_end) = last_end else {
+        result.replace_range(open + 1..close, item);
+        return result;
+    };
+
+    let body = &content[open..close];
+    let insertion = if body.contains('\n') {
+        let line_start = content[..last_end].rfind('\n').map_or(0, |i| i + 1);
+        let indent: String = content[line_start..]
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        format!(",\n{}{}", indent, item)
+    } else if body.contains(", ") || !body.contains(',') {
+        format!(", {}", item)
+    } else {
+        format!(",{}", item)
+    };
+    result.insert_str(last_end, &insertion);
+    result
+}
+
+/// Byte spans of a JSON object's entries: (key, key span, value span)
+struct JsonObject {
+    open: usize,
+    close: usize,
+    entries: Vec<(String, Range<usize>, Range<usize>)>,
+}
+
+impl JsonObject {
+    /// Scan the object starting at byte `start` (which must be `{`)
+    fn parse(content: &str, start: usize) -> Result<Self, String> {
+        let mut scanner = JsonScanner::new(content, start);
+        scanner.expect(b'{')?;
+        let mut entries = Vec::new();
+        loop {
+            scanner.skip_whitespace();
+            if scanner.peek() == Some(b'}') {
+                break;
+            }
+            let key_start = scanner.pos;
+            scanner.skip_string()?;
+            let key_span = key_start..scanner.pos;
+            let key: String = serde_json::from_str(&content[key_span.clone()])
+                .map_err(|e| format!("Invalid JSON key: {}", e))?;
+            scanner.skip_whitespace();
+            scanner.expect(b':')?;
+            scanner.skip_whitespace();
+            let value_start = scanner.pos;
+            scanner.skip_value()?;
+            entries.push((key, key_span, value_start..scanner.pos));
+            scanner.skip_whitespace();
+            if scanner.peek() == Some(b',') {
+                scanner.pos += 1;
+            }
         }
-        result.push_str(&format!("  - '{}'\n", member));
+        Ok(Self {
+            open: start,
+            close: scanner.pos,
+            entries,
+        })
     }
 
-    Ok(result)
+    /// Value span of the entry with the given key
+    fn get(&self, key: &str) -> Option<Range<usize>> {
+        self.entries
+            .iter()
+            .find(|(name, _, _)| name == key)
+            .map(|(_, _, value)| value.clone())
+    }
 }
 
-/// Remove member from pnpm-workspace.yaml
-fn remove_pnpm_member(content: &str, member: &str) -> Result<String, String> {
-    let mut result = String::new();
-    let member_line = format!("- '{}'", member);
-    let member_line_alt = format!("- \"{}\"", member);
-
-    for line in content.lines() {
-        let trimmed = line.trim();
-
-        // Skip lines that match the member
-        if trimmed == member_line
-            || trimmed == member_line_alt
-            || trimmed == format!("- {}", member)
-        {
-            continue;
-        }
+/// Byte spans of a JSON array's elements
+struct JsonArray {
+    open: usize,
+    close: usize,
+    elements: Vec<Range<usize>>,
+}
 
-        result.push_str(line);
-        result.push('\n');
+impl JsonArray {
+    /// Scan the array starting at byte `start` (which must be `[`)
+    fn parse(content: &str, start: usize) -> Result<Self, String> {
+        let mut scanner = JsonScanner::new(content, start);
+        scanner.expect(b'[')?;
+        let mut elements = Vec::new();
+        loop {
+            scanner.skip_whitespace();
+            if scanner.peek() == Some(b']') {
+                break;
+            }
+            let element_start = scanner.pos;
+            scanner.skip_value()?;
+            elements.push(element_start..scanner.pos);
+            scanner.skip_whitespace();
+            if scanner.peek() == Some(b',') {
+                scanner.pos += 1;
+            }
+        }
+        Ok(Self {
+            open: start,
+            close: scanner.pos,
+            elements,
+        })
     }
+}
 
-    Ok(result)
+/// Minimal JSON scanner that tracks byte positions (input must be valid JSON)
+struct JsonScanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
 }
 
-/// List members from pnpm-workspace.yaml
-fn list_pnpm_members(content: &str) -> Result<Vec<String>, String> {
-    let mut members = Vec::new();
-    let mut in_packages = false;
+impl<'a> JsonScanner<'a> {
+    fn new(content: &'a str, pos: usize) -> Self {
+        Self {
+            bytes: content.as_bytes(),
+            pos,
+        }
+    }
 
-    for line in content.lines() {
-        let trimmed = line.trim();
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
 
-        if trimmed.starts_with("packages:") {
-            in_packages = true;
-            continue;
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected '{}' at byte {} of package.json",
+                byte as char, self.pos
+            ))
         }
+    }
 
-        // Check if we're leaving packages section
-        if in_packages
-            && !trimmed.is_empty()
-            && !trimmed.starts_with('-')
-            && !trimmed.starts_with('#')
-        {
-            break;
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
         }
+    }
 
-        // Parse member line
-        if in_packages && trimmed.starts_with('-') {
-            let member = trimmed
-                .trim_start_matches('-')
-                .trim()
-                .trim_matches('\'')
-                .trim_matches('"')
-                .to_string();
-
-            if !member.is_empty() {
-                members.push(member);
+    fn skip_string(&mut self) -> Result<(), String> {
+        self.expect(b'"')?;
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'\\' => self.pos += 1,
+                b'"' => return Ok(()),
+                _ => {}
             }
         }
+        Err("Unterminated string in package.json".to_string())
     }
 
-    Ok(members)
+    fn skip_value(&mut self) -> Result<(), String> {
+        match self.peek() {
+            Some(b'"') => self.skip_string(),
+            Some(open @ (b'{' | b'[')) => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(byte) if byte == close => {
+                            self.pos += 1;
+                            return Ok(());
+                        }
+                        Some(b',' | b':') => self.pos += 1,
+                        Some(_) => self.skip_value()?,
+                        None => return Err("Unterminated container in package.json".to_string()),
+                    }
+                }
+            }
+            Some(_) => {
+                while self
+                    .peek()
+                    .is_some_and(|b| !matches!(b, b',' | b'}' | b']') && !b.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+                Ok(())
+            }
+            None => Err("Unexpected end of package.json".to_string()),
+        }
+    }
 }
 
 // Unit tests deleted - functionality is covered by workspace_harness integration tests
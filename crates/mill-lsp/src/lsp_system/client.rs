@@ -1,5 +1,6 @@
 //! LSP client implementation for communicating with a single LSP server
 
+use super::edit_capture::{self, CapturedEdits, EditCapture};
 use crate::progress::{ProgressError, ProgressManager, ProgressParams, ProgressToken};
 use lsp_types::{Diagnostic, ServerCapabilities, Uri};
 use mill_config::LspServerConfig;
@@ -10,9 +11,9 @@ use std::process::Stdio;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
 use tokio::time::{timeout, Duration};
-use tracing::{debug, error, info, warn};
+use tracing::{debug, info, warn};
 
 // CommandExt provides pre_exec() for Unix process group management
 #[cfg(unix)]
@@ -55,6 +56,62 @@ pub(crate) type PendingRequests = Arc<Mutex<HashMap<i64, oneshot::Sender<Result<
 /// Type alias for cached diagnostics (URI -> Vec<Diagnostic>)
 pub type DiagnosticsCache = Arc<Mutex<HashMap<Uri, Vec<Diagnostic>>>>;
 
+/// Capacity of the channel announcing diagnostics updates
+const DIAGNOSTICS_UPDATES_CAPACITY: usize = 256;
+
+/// What the client answers to the server's workspace requests
+#[derive(Clone)]
+struct WorkspaceState {
+    /// Settings answered to `workspace/configuration`, with dotted keys expanded
+    settings: Arc<Mutex<Value>>,
+    /// Folders answered to `workspace/workspaceFolders`
+    folders: Value,
+    /// `workspace/applyEdit` edits captured for the running tool call
+    captured_edits: CapturedEdits,
+}
+
+/// Sends `$/cancelRequest` when a request future is dropped before its response
+struct CancelOnDrop {
+    id: i64,
+    pending_requests: PendingRequests,
+    message_tx: mpsc::Sender<LspMessage>,
+    armed: bool,
+}
+
+impl CancelOnDrop {
+    /// Called once the request has completed
+    fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let id = self.id;
+        let pending_requests = self.pending_requests.clone();
+        let message_tx = self.message_tx.clone();
+        runtime.spawn(async move {
+            // Only cancel if the response has not arrived in the meantime
+            if pending_requests.lock().await.remove(&id).is_none() {
+                return;
+            }
+            debug!(lsp_request_id = id, "Cancelling abandoned LSP request");
+            let _ = message_tx
+                .send(LspMessage::Notification {
+                    method: "$/cancelRequest".to_string(),
+                    params: json!({ "id": id }),
+                })
+                .await;
+        });
+    }
+}
+
 /// LSP client for communicating with a single LSP server process
 pub struct LspClient {
     /// Child process handle
@@ -75,6 +132,12 @@ pub struct LspClient {
     server_capabilities: Arc<Mutex<Option<ServerCapabilities>>>,
     /// Cached diagnostics from textDocument/publishDiagnostics notifications
     diagnostics_cache: DiagnosticsCache,
+    /// Announces the URI of every file whose cached diagnostics changed
+    diagnostics_updates: broadcast::Sender<Uri>,
+    /// Settings and folders reported to the server
+    workspace: WorkspaceState,
+    /// Held by the active edit capture, so captures do not overlap
+    capture_lock: Arc<Mutex<()>>,
 }
 
 /// Internal message types for LSP communication
@@ -349,6 +412,14 @@ impl LspClient {
         let initialized = Arc::new(Mutex::new(false));
         let progress_manager = ProgressManager::new();
         let diagnostics_cache: DiagnosticsCache = Arc::new(Mutex::new(HashMap::new()));
+        let (diagnostics_updates, _) = broadcast::channel(DIAGNOSTICS_UPDATES_CAPACITY);
+        let workspace = WorkspaceState {
+            settings: Arc::new(Mutex::new(expand_dotted_keys(
+                config.settings.clone().unwrap_or(Value::Null),
+            ))),
+            folders: json!([workspace_folder(root_dir)]),
+            captured_edits: Arc::new(std::sync::Mutex::new(None)),
+        };
 
         // Create message channel for both requests and notifications
         let (message_tx, mut message_rx) = mpsc::channel::<LspMessage>(CHANNEL_BUFFER_SIZE);
@@ -488,6 +559,8 @@ impl LspClient {
         let message_tx_clone = message_tx.clone();
         let progress_manager_clone = progress_manager.clone();
         let diagnostics_cache_clone = diagnostics_cache.clone();
+        let diagnostics_updates_clone = diagnostics_updates.clone();
+        let workspace_clone = workspace.clone();
         tokio::spawn(async move {
             eprintln!(
                 "🔍 LSP stdout reader task started for: {}",
@@ -549,6 +622,8 @@ impl LspClient {
                                     &message_tx_clone,
                                     &progress_manager_clone,
                                     &diagnostics_cache_clone,
+                                    &diagnostics_updates_clone,
+                                    &workspace_clone,
                                 )
                                 .await;
                             }
@@ -577,6 +652,9 @@ impl LspClient {
             progress_manager,
             server_capabilities: Arc::new(Mutex::new(None)),
             diagnostics_cache,
+            diagnostics_updates,
+            workspace,
+            capture_lock: Arc::new(Mutex::new(())),
         };
 
         // Initialize the LSP server
@@ -664,6 +742,15 @@ impl LspClient {
             )));
         }
 
+        // If the caller stops waiting (e.g. the MCP request was cancelled),
+        // tell the server to stop working on the request
+        let mut cancel_guard = CancelOnDrop {
+            id,
+            pending_requests: self.pending_requests.clone(),
+            message_tx: self.message_tx.clone(),
+            armed: true,
+        };
+
         // Wait for response with timeout
         let start_time = std::time::Instant::now();
         let result = match timeout(LSP_REQUEST_TIMEOUT, response_rx).await {
@@ -714,6 +801,7 @@ impl LspClient {
                 Err(ServerError::runtime("Request timeout"))
             }
         };
+        cancel_guard.disarm();
         result
     }
 
@@ -755,10 +843,15 @@ impl LspClient {
                     }
                 },
                 "workspace": {
+                    "applyEdit": true,
                     "workspaceEdit": {
                         "documentChanges": true
                     },
-                    "workspaceFolders": true
+                    "workspaceFolders": true,
+                    "configuration": true,
+                    "didChangeConfiguration": {
+                        "dynamicRegistration": false
+                    }
                 }
             },
             "rootUri": format!("file://{}",
@@ -766,14 +859,7 @@ impl LspClient {
                     .unwrap_or(&std::env::current_dir()
                         .expect("Failed to get current directory for LSP workspace root"))
                     .display()),
-            "workspaceFolders": [{
-                "uri": format!("file://{}",
-                    self.config.root_dir.as_deref()
-                        .unwrap_or(&std::env::current_dir()
-                            .expect("Failed to get current directory for LSP workspace folder"))
-                        .display()),
-                "name": "workspace"
-            }]
+            "workspaceFolders": self.workspace.folders.clone()
         });
 
         // Add initializationOptions if provided in the config
@@ -839,6 +925,16 @@ impl LspClient {
         // Send initialized notification
         self.send_notification("initialized", json!({})).await?;
 
+        // Push configured settings; servers that pull them with
+        // workspace/configuration treat this as a cue to do so
+        if let Some(settings) = &self.config.settings {
+            self.send_notification(
+                "workspace/didChangeConfiguration",
+                json!({ "settings": settings }),
+            )
+            .await?;
+        }
+
         // Mark as initialized
         {
             let mut initialized = self.initialized.lock().await;
@@ -872,6 +968,28 @@ impl LspClient {
         Ok(())
     }
 
+    /// Replace the server's settings and notify it with `workspace/didChangeConfiguration`
+    pub async fn update_settings(&self, settings: Option<Value>) -> ServerResult<()> {
+        let settings = settings.unwrap_or(Value::Null);
+        *self.workspace.settings.lock().await = expand_dotted_keys(settings.clone());
+        self.send_notification(
+            "workspace/didChangeConfiguration",
+            json!({ "settings": settings }),
+        )
+        .await
+    }
+
+    /// Start capturing the server's `workspace/applyEdit` requests
+    ///
+    /// While the capture is alive, edits the server asks to apply are recorded
+    /// and acknowledged instead of written to disk; the caller adds them to its
+    /// plan. Captures on one client wait for each other, so every edit belongs
+    /// to exactly one tool call. Edits arriving with no capture are refused.
+    pub async fn capture_edits(&self) -> EditCapture {
+        let exclusive = self.capture_lock.clone().lock_owned().await;
+        EditCapture::start(self.workspace.captured_edits.clone(), exclusive)
+    }
+
     /// Check if the client has been initialized
     pub async fn is_initialized(&self) -> bool {
         *self.initialized.lock().await
@@ -940,6 +1058,20 @@ impl LspClient {
         cache.clone()
     }
 
+    /// Subscribe to changes of the cached diagnostics
+    ///
+    /// Receives the URI of each file whose push-model diagnostics changed.
+    pub fn subscribe_diagnostics(&self) -> broadcast::Receiver<Uri> {
+        self.diagnostics_updates.subscribe()
+    }
+
+    /// Subscribe to `$/progress` updates from the server, such as indexing
+    pub fn subscribe_progress(
+        &self,
+    ) -> broadcast::Receiver<(ProgressToken, crate::progress::ProgressState)> {
+        self.progress_manager.subscribe()
+    }
+
     /// Clear cached diagnostics for a specific file
     pub async fn clear_cached_diagnostics(&self, uri: &Uri) {
         let mut cache: tokio::sync::MutexGuard<'_, HashMap<Uri, Vec<Diagnostic>>> =
@@ -1321,13 +1453,15 @@ impl LspClient {
         message_tx: &mpsc::Sender<LspMessage>,
         progress_manager: &ProgressManager,
         diagnostics_cache: &DiagnosticsCache,
+        diagnostics_updates: &broadcast::Sender<Uri>,
+        workspace: &WorkspaceState,
     ) {
         tracing::warn!(message = ?message, "Received message from LSP server");
 
         if message.get("method").is_some() {
             if message.get("id").is_some() {
                 // This is a server-initiated request that requires a response.
-                Self::handle_server_request(&message, message_tx).await;
+                Self::handle_server_request(&message, message_tx, workspace).await;
             } else {
                 // This is a notification from the server
                 let method = message.get("method").and_then(|m| m.as_str());
@@ -1362,7 +1496,14 @@ impl LspClient {
                                 > = diagnostics_cache.lock().await;
                                 let diagnostic_count = diag_params.diagnostics.len();
                                 let uri_str = diag_params.uri.as_str().to_string();
-                                cache.insert(diag_params.uri, diag_params.diagnostics);
+                                let changed =
+                                    cache.get(&diag_params.uri) != Some(&diag_params.diagnostics);
+                                cache.insert(diag_params.uri.clone(), diag_params.diagnostics);
+                                drop(cache);
+                                if changed {
+                                    // No receivers is fine: nobody is watching
+                                    let _ = diagnostics_updates.send(diag_params.uri);
+                                }
                                 debug!(
                                     uri = %uri_str,
                                     diagnostic_count = diagnostic_count,
@@ -1421,313 +1562,12 @@ impl LspClient {
         }
     }
 
-    /// Apply a workspace edit received from the LSP server
-    ///
-    /// This handles `workspace/applyEdit` requests, which are commonly used by
-    /// refactoring operations like "Move to new file". The edit can contain:
-    /// - `changes`: A map of file URIs to arrays of text edits
-    /// - `documentChanges`: An array of document changes (edits, creates, renames, deletes)
-    async fn apply_workspace_edit(params: Option<&Value>) -> Result<(), String> {
-        let params = params.ok_or("Missing params in workspace/applyEdit")?;
-        let edit = params
-            .get("edit")
-            .ok_or("Missing 'edit' field in workspace/applyEdit params")?;
-
-        debug!(?edit, "Applying workspace edit");
-
-        // Handle documentChanges (preferred format per LSP spec)
-        if let Some(doc_changes) = edit.get("documentChanges").and_then(|v| v.as_array()) {
-            for change in doc_changes {
-                Self::apply_document_change(change).await?;
-            }
-            return Ok(());
-        }
-
-        // Handle changes (older format)
-        if let Some(changes) = edit.get("changes").and_then(|v| v.as_object()) {
-            for (uri, edits) in changes {
-                let file_path = Self::uri_to_path(uri)?;
-                let edits = edits
-                    .as_array()
-                    .ok_or_else(|| format!("Invalid edits array for {}", uri))?;
-                Self::apply_text_edits(&file_path, edits).await?;
-            }
-            return Ok(());
-        }
-
-        Err("workspace/applyEdit contained neither 'changes' nor 'documentChanges'".to_string())
-    }
-
-    /// Apply a single document change (TextDocumentEdit, CreateFile, RenameFile, or DeleteFile)
-    async fn apply_document_change(change: &Value) -> Result<(), String> {
-        // Check for CreateFile operation
-        if change.get("kind").and_then(|k| k.as_str()) == Some("create") {
-            let uri = change
-                .get("uri")
-                .and_then(|u| u.as_str())
-                .ok_or("Missing 'uri' in CreateFile")?;
-            let file_path = Self::uri_to_path(uri)?;
-            info!(path = %file_path, "Creating file via workspace/applyEdit");
-
-            // Create parent directories if needed
-            if let Some(parent) = std::path::Path::new(&file_path).parent() {
-                tokio::fs::create_dir_all(parent).await.map_err(|e| {
-                    format!(
-                        "Failed to create parent directories for {}: {}",
-                        file_path, e
-                    )
-                })?;
-            }
-
-            // Create empty file (or with overwrite option)
-            tokio::fs::write(&file_path, "")
-                .await
-                .map_err(|e| format!("Failed to create file {}: {}", file_path, e))?;
-            return Ok(());
-        }
-
-        // Check for RenameFile operation
-        if change.get("kind").and_then(|k| k.as_str()) == Some("rename") {
-            let old_uri = change
-                .get("oldUri")
-                .and_then(|u| u.as_str())
-                .ok_or("Missing 'oldUri' in RenameFile")?;
-            let new_uri = change
-                .get("newUri")
-                .and_then(|u| u.as_str())
-                .ok_or("Missing 'newUri' in RenameFile")?;
-            let old_path = Self::uri_to_path(old_uri)?;
-            let new_path = Self::uri_to_path(new_uri)?;
-            info!(from = %old_path, to = %new_path, "Renaming file via workspace/applyEdit");
-
-            // Create parent directories if needed
-            if let Some(parent) = std::path::Path::new(&new_path).parent() {
-                tokio::fs::create_dir_all(parent).await.map_err(|e| {
-                    format!(
-                        "Failed to create parent directories for {}: {}",
-                        new_path, e
-                    )
-                })?;
-            }
-
-            tokio::fs::rename(&old_path, &new_path)
-                .await
-                .map_err(|e| format!("Failed to rename {} to {}: {}", old_path, new_path, e))?;
-            return Ok(());
-        }
-
-        // Check for DeleteFile operation
-        if change.get("kind").and_then(|k| k.as_str()) == Some("delete") {
-            let uri = change
-                .get("uri")
-                .and_then(|u| u.as_str())
-                .ok_or("Missing 'uri' in DeleteFile")?;
-            let file_path = Self::uri_to_path(uri)?;
-            info!(path = %file_path, "Deleting file via workspace/applyEdit");
-
-            tokio::fs::remove_file(&file_path)
-                .await
-                .map_err(|e| format!("Failed to delete file {}: {}", file_path, e))?;
-            return Ok(());
-        }
-
-        // Handle TextDocumentEdit (has textDocument and edits)
-        if let Some(text_doc) = change.get("textDocument") {
-            let uri = text_doc
-                .get("uri")
-                .and_then(|u| u.as_str())
-                .ok_or("Missing 'uri' in TextDocumentEdit")?;
-            let file_path = Self::uri_to_path(uri)?;
-            let edits = change
-                .get("edits")
-                .and_then(|e| e.as_array())
-                .ok_or("Missing 'edits' in TextDocumentEdit")?;
-
-            Self::apply_text_edits(&file_path, edits).await?;
-            return Ok(());
-        }
-
-        warn!(
-            ?change,
-            "Unknown document change type in workspace/applyEdit"
-        );
-        Ok(())
-    }
-
-    /// Apply text edits to a file
-    async fn apply_text_edits(file_path: &str, edits: &[Value]) -> Result<(), String> {
-        // Read current file content (or empty for new files)
-        let content = match tokio::fs::read_to_string(file_path).await {
-            Ok(c) => c,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                // File doesn't exist - create parent dirs and start with empty content
-                if let Some(parent) = std::path::Path::new(file_path).parent() {
-                    tokio::fs::create_dir_all(parent).await.map_err(|e| {
-                        format!(
-                            "Failed to create parent directories for {}: {}",
-                            file_path, e
-                        )
-                    })?;
-                }
-                String::new()
-            }
-            Err(e) => return Err(format!("Failed to read file {}: {}", file_path, e)),
-        };
-
-        // Parse and sort edits by position (reverse order for safe application)
-        let mut parsed_edits: Vec<(u32, u32, u32, u32, String)> = Vec::new();
-        for edit in edits {
-            let range = edit
-                .get("range")
-                .ok_or_else(|| format!("Missing 'range' in text edit for {}", file_path))?;
-            let start = range
-                .get("start")
-                .ok_or_else(|| format!("Missing 'start' in range for {}", file_path))?;
-            let end = range
-                .get("end")
-                .ok_or_else(|| format!("Missing 'end' in range for {}", file_path))?;
-
-            let start_line = start
-                .get("line")
-                .and_then(|l| l.as_u64())
-                .ok_or_else(|| format!("Invalid start line for {}", file_path))?
-                as u32;
-            let start_char = start
-                .get("character")
-                .and_then(|c| c.as_u64())
-                .ok_or_else(|| format!("Invalid start character for {}", file_path))?
-                as u32;
-            let end_line = end
-                .get("line")
-                .and_then(|l| l.as_u64())
-                .ok_or_else(|| format!("Invalid end line for {}", file_path))?
-                as u32;
-            let end_char = end
-                .get("character")
-                .and_then(|c| c.as_u64())
-                .ok_or_else(|| format!("Invalid end character for {}", file_path))?
-                as u32;
-
-            let new_text = edit
-                .get("newText")
-                .and_then(|t| t.as_str())
-                .unwrap_or("")
-                .to_string();
-
-            parsed_edits.push((start_line, start_char, end_line, end_char, new_text));
-        }
-
-        // Sort edits in reverse order (later positions first) for safe application
-        parsed_edits.sort_by(|a, b| match b.2.cmp(&a.2) {
-            std::cmp::Ordering::Equal => b.3.cmp(&a.3),
-            other => other,
-        });
-
-        // Convert content to a mutable string for editing
-        let mut result = content.clone();
-
-        for (start_line, start_char, end_line, end_char, new_text) in parsed_edits {
-            // Calculate byte offsets
-            let start_offset = Self::position_to_offset(&result, start_line, start_char);
-            let end_offset = Self::position_to_offset(&result, end_line, end_char);
-
-            if start_offset <= end_offset && end_offset <= result.len() {
-                result.replace_range(start_offset..end_offset, &new_text);
-            } else {
-                warn!(
-                    file = %file_path,
-                    start_line,
-                    start_char,
-                    end_line,
-                    end_char,
-                    "Invalid edit range, skipping"
-                );
-            }
-        }
-
-        // Write result back to file
-        info!(path = %file_path, "Writing workspace edit to file");
-        tokio::fs::write(file_path, &result)
-            .await
-            .map_err(|e| format!("Failed to write file {}: {}", file_path, e))?;
-
-        Ok(())
-    }
-
-    /// Convert a line/character position to a byte offset
-    fn position_to_offset(content: &str, line: u32, character: u32) -> usize {
-        let mut offset = 0;
-        for (i, line_content) in content.lines().enumerate() {
-            if i == line as usize {
-                // Found the target line, add character offset
-                // Handle UTF-16 character units (LSP uses UTF-16)
-                let char_offset = line_content
-                    .char_indices()
-                    .take(character as usize)
-                    .last()
-                    .map(|(i, c)| i + c.len_utf8())
-                    .unwrap_or(0);
-                return offset + char_offset;
-            }
-            offset += line_content.len() + 1; // +1 for newline
-        }
-        // If line is beyond content, return end of content
-        content.len()
-    }
-
-    /// Convert a file:// URI to a filesystem path
-    fn uri_to_path(uri: &str) -> Result<String, String> {
-        if !uri.starts_with("file://") {
-            return Err(format!("Unsupported URI scheme: {}", uri));
-        }
-
-        // Parse the URI and extract the path
-        let path = &uri[7..]; // Remove "file://"
-
-        // Handle Windows paths (file:///C:/...) vs Unix (file:///home/...)
-        #[cfg(windows)]
-        {
-            // On Windows, remove leading slash: /C:/path -> C:/path
-            if path.len() >= 3
-                && path.chars().nth(0) == Some('/')
-                && path.chars().nth(2) == Some(':')
-            {
-                return Ok(path[1..].to_string());
-            }
-        }
-
-        // URL decode the path (handle %20 for spaces, etc.)
-        let decoded = Self::percent_decode(path);
-        Ok(decoded)
-    }
-
-    /// Simple percent-decoding for file URIs
-    fn percent_decode(input: &str) -> String {
-        let mut result = String::with_capacity(input.len());
-        let mut chars = input.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            if c == '%' {
-                // Try to read two hex digits
-                let hex: String = chars.by_ref().take(2).collect();
-                if hex.len() == 2 {
-                    if let Ok(byte) = u8::from_str_radix(&hex, 16) {
-                        result.push(byte as char);
-                        continue;
-                    }
-                }
-                // If hex parsing failed, keep the original
-                result.push('%');
-                result.push_str(&hex);
-            } else {
-                result.push(c);
-            }
-        }
-        result
-    }
-
     /// Handle server-initiated requests
-    async fn handle_server_request(request: &Value, message_tx: &mpsc::Sender<LspMessage>) {
+    async fn handle_server_request(
+        request: &Value,
+        message_tx: &mpsc::Sender<LspMessage>,
+        workspace: &WorkspaceState,
+    ) {
         debug!(?request, "Handling server request");
         let id = request.get("id").cloned().unwrap_or(Value::Null);
         let method = request.get("method").and_then(|m| m.as_str());
@@ -1735,17 +1575,28 @@ impl LspClient {
         let response = match method {
             Some("workspace/configuration") => {
                 // The server requests configuration for a number of items. We must respond with
-                // an array of the same length. Returning `null` for each is a valid way to say
-                // "use your default".
-                let items_len = request
+                // an array of the same length, answering each item's section from the configured
+                // settings. `null` for an unknown section tells the server to use its default.
+                let settings = workspace.settings.lock().await;
+                let result: Vec<Value> = request
                     .get("params")
                     .and_then(|p| p.get("items"))
                     .and_then(|i| i.as_array())
-                    .map(|a| a.len())
-                    .unwrap_or(0);
+                    .map(|items| {
+                        items
+                            .iter()
+                            .map(|item| {
+                                configuration_section(
+                                    &settings,
+                                    item.get("section").and_then(|s| s.as_str()),
+                                )
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
                 LspMessage::Response {
                     id,
-                    result: json!(vec![Value::Null; items_len]),
+                    result: json!(result),
                 }
             }
             Some("client/registerCapability") | Some("window/workDoneProgress/create") => {
@@ -1755,21 +1606,32 @@ impl LspClient {
                     result: Value::Null,
                 }
             }
-            Some("workspace/workspaceFolders") => {
-                // Respond with an empty array as we manage the workspace.
-                LspMessage::Response {
-                    id,
-                    result: json!([]),
-                }
-            }
+            Some("workspace/workspaceFolders") => LspMessage::Response {
+                id,
+                result: workspace.folders.clone(),
+            },
             Some("workspace/applyEdit") => {
-                // Handle workspace/applyEdit requests from the LSP server
-                // This is commonly used by refactoring operations like "Move to new file"
-                info!("Received workspace/applyEdit request from LSP server");
-
-                match Self::apply_workspace_edit(request.get("params")).await {
+                // Edits from the server become part of the plan of the tool call
+                // that triggered them; mill never writes them directly
+                let edit = request
+                    .get("params")
+                    .and_then(|p| p.get("edit"))
+                    .cloned()
+                    .ok_or_else(|| "Missing edit in workspace/applyEdit".to_string())
+                    .and_then(|edit| {
+                        serde_json::from_value::<lsp_types::WorkspaceEdit>(edit)
+                            .map_err(|e| format!("Invalid WorkspaceEdit: {}", e))
+                    });
+                let outcome = edit.and_then(|edit| {
+                    if edit_capture::record_edit(&workspace.captured_edits, edit) {
+                        Ok(())
+                    } else {
+                        Err("Edits are only applied as part of a mill tool call".to_string())
+                    }
+                });
+                match outcome {
                     Ok(()) => {
-                        info!("Successfully applied workspace edit");
+                        info!("Captured workspace/applyEdit into the current plan");
                         LspMessage::Response {
                             id,
                             result: json!({
@@ -1778,7 +1640,7 @@ impl LspClient {
                         }
                     }
                     Err(e) => {
-                        error!(error = %e, "Failed to apply workspace edit");
+                        warn!(error = %e, "Refused workspace/applyEdit from LSP server");
                         LspMessage::Response {
                             id,
                             result: json!({
@@ -1808,6 +1670,69 @@ impl LspClient {
     }
 }
 
+/// Workspace folder entry for a server's root directory
+fn workspace_folder(root: &std::path::Path) -> Value {
+    let name = root
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "workspace".to_string());
+    json!({
+        "uri": format!("file://{}", root.display()),
+        "name": name
+    })
+}
+
+/// Expand dotted keys (`"cargo.features"`) into nested objects, merging
+/// them with nested keys written out in full
+fn expand_dotted_keys(settings: Value) -> Value {
+    let Value::Object(fields) = settings else {
+        return settings;
+    };
+    let mut expanded = Value::Object(serde_json::Map::new());
+    for (key, value) in fields {
+        let mut target = &mut expanded;
+        for part in key.split('.') {
+            if !target.get(part).is_some_and(Value::is_object) {
+                target[part] = json!({});
+            }
+            target = &mut target[part];
+        }
+        merge_settings(target, expand_dotted_keys(value));
+    }
+    expanded
+}
+
+/// Merge `value` into `target`, recursing into objects present in both
+fn merge_settings(target: &mut Value, value: Value) {
+    match (target, value) {
+        (Value::Object(target), Value::Object(fields)) if !target.is_empty() => {
+            for (key, value) in fields {
+                match target.get_mut(&key) {
+                    Some(existing) => merge_settings(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, value) => *target = value,
+    }
+}
+
+/// Settings for a `workspace/configuration` section path, or `null` when absent
+///
+/// A missing or empty section asks for all settings.
+fn configuration_section(settings: &Value, section: Option<&str>) -> Value {
+    let Some(section) = section.filter(|section| !section.is_empty()) else {
+        return settings.clone();
+    };
+    section
+        .split('.')
+        .try_fold(settings, |value, part| value.get(part))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
 impl Drop for LspClient {
     fn drop(&mut self) {
         // Get the PID before dropping for logging
@@ -1844,6 +1769,9 @@ mod tests {
             root_dir: None,
             restart_interval: None,
             initialization_options: None,
+            name: None,
+            roles: None,
+            settings: None,
         }
     }
 
@@ -1867,6 +1795,137 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_dropped_request_sends_cancel() {
+        let pending_requests: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+        let (message_tx, mut message_rx) = mpsc::channel(4);
+        let (response_tx, _response_rx) = oneshot::channel();
+        pending_requests.lock().await.insert(7, response_tx);
+
+        drop(CancelOnDrop {
+            id: 7,
+            pending_requests: pending_requests.clone(),
+            message_tx: message_tx.clone(),
+            armed: true,
+        });
+        match message_rx.recv().await {
+            Some(LspMessage::Notification { method, params }) => {
+                assert_eq!(method, "$/cancelRequest");
+                assert_eq!(params["id"], 7);
+            }
+            _ => panic!("Expected $/cancelRequest notification"),
+        }
+        assert!(pending_requests.lock().await.is_empty());
+
+        // A completed request is not cancelled
+        let mut guard = CancelOnDrop {
+            id: 8,
+            pending_requests,
+            message_tx,
+            armed: true,
+        };
+        guard.disarm();
+        drop(guard);
+        tokio::task::yield_now().await;
+        assert!(message_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_answers_workspace_requests_from_settings() {
+        let workspace = WorkspaceState {
+            settings: Arc::new(Mutex::new(expand_dotted_keys(json!({
+                "rust-analyzer.cargo.features": ["full"],
+                "rust-analyzer": { "checkOnSave": false },
+                "python": { "venvPath": ".venv" }
+            })))),
+            folders: json!([workspace_folder(std::path::Path::new("/work/app"))]),
+            captured_edits: Arc::new(std::sync::Mutex::new(None)),
+        };
+        let (message_tx, mut message_rx) = mpsc::channel(4);
+
+        let request = json!({
+            "id": 1,
+            "method": "workspace/configuration",
+            "params": { "items": [
+                { "section": "rust-analyzer.cargo" },
+                { "section": "rust-analyzer" },
+                { "section": "python.venvPath" },
+                { "section": "yaml" },
+                {}
+            ]}
+        });
+        LspClient::handle_server_request(&request, &message_tx, &workspace).await;
+        match message_rx.recv().await {
+            Some(LspMessage::Response { id, result }) => {
+                assert_eq!(id, 1);
+                assert_eq!(
+                    result,
+                    json!([
+                        { "features": ["full"] },
+                        { "cargo": { "features": ["full"] }, "checkOnSave": false },
+                        ".venv",
+                        null,
+                        {
+                            "rust-analyzer": { "cargo": { "features": ["full"] }, "checkOnSave": false },
+                            "python": { "venvPath": ".venv" }
+                        }
+                    ])
+                );
+            }
+            _ => panic!("Expected workspace/configuration response"),
+        }
+
+        let request = json!({ "id": 2, "method": "workspace/workspaceFolders" });
+        LspClient::handle_server_request(&request, &message_tx, &workspace).await;
+        match message_rx.recv().await {
+            Some(LspMessage::Response { result, .. }) => {
+                assert_eq!(
+                    result,
+                    json!([{ "uri": "file:///work/app", "name": "app" }])
+                );
+            }
+            _ => panic!("Expected workspace/workspaceFolders response"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_edit_is_captured_not_written() {
+        let workspace = WorkspaceState {
+            settings: Arc::new(Mutex::new(Value::Null)),
+            folders: json!([]),
+            captured_edits: Arc::new(std::sync::Mutex::new(None)),
+        };
+        let (message_tx, mut message_rx) = mpsc::channel(4);
+        let request = json!({
+            "id": 3,
+            "method": "workspace/applyEdit",
+            "params": { "edit": { "changes": { "file:///nowhere/a.ts": [{
+                "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } },
+                "newText": "x"
+            }]}}}
+        });
+
+        // Outside a tool call the edit is refused
+        LspClient::handle_server_request(&request, &message_tx, &workspace).await;
+        match message_rx.recv().await {
+            Some(LspMessage::Response { result, .. }) => assert_eq!(result["applied"], false),
+            _ => panic!("Expected workspace/applyEdit response"),
+        }
+
+        let capture = EditCapture::start(
+            workspace.captured_edits.clone(),
+            Arc::new(Mutex::new(())).lock_owned().await,
+        );
+        LspClient::handle_server_request(&request, &message_tx, &workspace).await;
+        match message_rx.recv().await {
+            Some(LspMessage::Response { result, .. }) => assert_eq!(result["applied"], true),
+            _ => panic!("Expected workspace/applyEdit response"),
+        }
+        let edit = capture.finish().unwrap().unwrap();
+        assert_eq!(edit.changes.unwrap().len(), 1);
+        assert!(!std::path::Path::new("/nowhere/a.ts").exists());
+    }
+
     #[test]
     fn test_parse_content_length() {
         assert_eq!(
@@ -0,0 +1,245 @@
+//! Capture of server-initiated workspace edits
+//!
+//! Language servers apply some refactorings (for example "move to new file")
+//! by sending `workspace/applyEdit` while a command runs. Mill never writes
+//! those edits itself: the tool call that triggered them captures them and
+//! adds them to its plan, so they go through dry-run, checksums, locking and
+//! rollback like any other edit.
+
+use lsp_types::{
+    DocumentChangeOperation, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier,
+    ResourceOp, TextDocumentEdit, Uri, WorkspaceEdit,
+};
+use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::OwnedMutexGuard;
+
+/// Edits captured for the running tool call, or `None` when no call is capturing
+pub(crate) type CapturedEdits = Arc<Mutex<Option<Vec<WorkspaceEdit>>>>;
+
+/// Record a server's edit for the capturing tool call
+///
+/// Returns `false` when no tool call is capturing, in which case the edit
+/// must be refused.
+pub(crate) fn record_edit(captured: &CapturedEdits, edit: WorkspaceEdit) -> bool {
+    match captured
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_mut()
+    {
+        Some(edits) => {
+            edits.push(edit);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Edits a server asks to apply while a tool call runs
+///
+/// Created by [`LspClient::capture_edits`](super::LspClient::capture_edits).
+/// Only one capture is active per client at a time, so every edit belongs to
+/// exactly one tool call. Capturing stops when the capture is finished or
+/// dropped.
+pub struct EditCapture {
+    captured: CapturedEdits,
+    _exclusive: OwnedMutexGuard<()>,
+}
+
+impl EditCapture {
+    pub(crate) fn start(captured: CapturedEdits, exclusive: OwnedMutexGuard<()>) -> Self {
+        *captured.lock().unwrap_or_else(PoisonError::into_inner) = Some(Vec::new());
+        Self {
+            captured,
+            _exclusive: exclusive,
+        }
+    }
+
+    /// Stop capturing and return the captured edits merged into one, if any
+    ///
+    /// Fails when the edits cannot be combined (see [`merge_workspace_edits`]).
+    pub fn finish(self) -> ServerResult<Option<WorkspaceEdit>> {
+        let edits = self
+            .captured
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .unwrap_or_default();
+        merge_workspace_edits(edits)
+    }
+}
+
+impl Drop for EditCapture {
+    fn drop(&mut self) {
+        self.captured
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+/// Combine workspace edits into one
+///
+/// A single edit is returned unchanged; several are flattened into
+/// `documentChanges` operations. Plans apply text edits to the original
+/// content of a file, so a later edit's ranges are only valid when no earlier
+/// edit changed that file: an edit touching a file an earlier edit touched is
+/// refused, unless it only edits text of a file the earlier edits created.
+/// Returns `None` when there are no edits.
+pub fn merge_workspace_edits(
+    edits: impl IntoIterator<Item = WorkspaceEdit>,
+) -> ServerResult<Option<WorkspaceEdit>> {
+    let mut edits: Vec<WorkspaceEdit> = edits.into_iter().collect();
+    if edits.len() <= 1 {
+        return Ok(edits.pop());
+    }
+
+    let mut operations = Vec::new();
+    // Files touched by earlier edits, and whether those edits only created them
+    let mut touched: HashMap<String, bool> = HashMap::new();
+    for edit in edits {
+        let edit_operations = document_operations(edit);
+
+        let mut created_only: HashMap<&str, bool> = HashMap::new();
+        for (uri, create) in edit_operations.iter().flat_map(operation_files) {
+            let refused = match touched.get(uri.as_str()) {
+                Some(&earlier_created_only) => !earlier_created_only || create,
+                None => false,
+            };
+            if refused {
+                return Err(ServerError::not_supported(format!(
+                    "Language server sent several edits of '{}'; \
+                     they cannot be combined into one plan",
+                    uri.as_str()
+                )));
+            }
+            *created_only.entry(uri.as_str()).or_insert(true) &= create;
+        }
+        for (uri, create) in created_only {
+            *touched.entry(uri.to_string()).or_insert(true) &= create;
+        }
+
+        operations.extend(edit_operations);
+    }
+
+    Ok(Some(WorkspaceEdit {
+        changes: None,
+        document_changes: Some(DocumentChanges::Operations(operations)),
+        change_annotations: None,
+    }))
+}
+
+/// A workspace edit's changes as `documentChanges` operations
+fn document_operations(edit: WorkspaceEdit) -> Vec<DocumentChangeOperation> {
+    let mut operations = Vec::new();
+    if let Some(changes) = edit.changes {
+        let mut changes: Vec<_> = changes.into_iter().collect();
+        changes.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        for (uri, text_edits) in changes {
+            operations.push(DocumentChangeOperation::Edit(TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier { uri, version: None },
+                edits: text_edits.into_iter().map(OneOf::Left).collect(),
+            }));
+        }
+    }
+    match edit.document_changes {
+        Some(DocumentChanges::Edits(document_edits)) => operations.extend(
+            document_edits
+                .into_iter()
+                .map(DocumentChangeOperation::Edit),
+        ),
+        Some(DocumentChanges::Operations(document_operations)) => {
+            operations.extend(document_operations)
+        }
+        None => {}
+    }
+    operations
+}
+
+/// The files an operation touches, and whether it creates them
+fn operation_files(operation: &DocumentChangeOperation) -> Vec<(&Uri, bool)> {
+    match operation {
+        DocumentChangeOperation::Edit(edit) => vec![(&edit.text_document.uri, false)],
+        DocumentChangeOperation::Op(ResourceOp::Create(create)) => vec![(&create.uri, true)],
+        DocumentChangeOperation::Op(ResourceOp::Rename(rename)) => {
+            vec![(&rename.old_uri, false), (&rename.new_uri, false)]
+        }
+        DocumentChangeOperation::Op(ResourceOp::Delete(delete)) => vec![(&delete.uri, false)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn edit(value: serde_json::Value) -> WorkspaceEdit {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_capture_records_until_finished() {
+        let captured: CapturedEdits = Arc::new(Mutex::new(None));
+        let exclusive = Arc::new(tokio::sync::Mutex::new(()));
+        let span =
+            json!({ "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } });
+
+        assert!(!record_edit(&captured, WorkspaceEdit::default()));
+
+        let capture = EditCapture::start(captured.clone(), exclusive.clone().lock_owned().await);
+        assert!(exclusive.try_lock().is_err());
+        assert!(record_edit(
+            &captured,
+            edit(json!({ "changes": { "file:///a.ts": [{ "range": span, "newText": "a" }] } }))
+        ));
+        assert!(record_edit(
+            &captured,
+            edit(json!({ "documentChanges": [
+                { "kind": "create", "uri": "file:///b.ts" },
+                { "textDocument": { "uri": "file:///b.ts", "version": null },
+                  "edits": [{ "range": span, "newText": "b" }] }
+            ]}))
+        ));
+
+        let merged = serde_json::to_value(capture.finish().unwrap().unwrap()).unwrap();
+        assert_eq!(
+            merged,
+            json!({ "documentChanges": [
+                { "textDocument": { "uri": "file:///a.ts", "version": null },
+                  "edits": [{ "range": span, "newText": "a" }] },
+                { "kind": "create", "uri": "file:///b.ts" },
+                { "textDocument": { "uri": "file:///b.ts", "version": null },
+                  "edits": [{ "range": span, "newText": "b" }] }
+            ]})
+        );
+        assert!(exclusive.try_lock().is_ok());
+        assert!(!record_edit(&captured, WorkspaceEdit::default()));
+    }
+
+    #[test]
+    fn test_merge_refuses_several_edits_of_one_file() {
+        let span =
+            json!({ "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 1 } });
+        let change = |text: &str| {
+            edit(json!({ "changes": { "file:///a.ts": [{ "range": span, "newText": text }] } }))
+        };
+
+        // The second edit's range was computed on text the first one changed
+        let error = merge_workspace_edits([change("x"), change("y")]).unwrap_err();
+        assert!(error.to_string().contains("file:///a.ts"));
+        let rename = edit(json!({ "documentChanges": [
+            { "kind": "rename", "oldUri": "file:///a.ts", "newUri": "file:///c.ts" }
+        ]}));
+        assert!(merge_workspace_edits([change("x"), rename]).is_err());
+
+        // Text edits of a file an earlier edit only created start from empty
+        let create = edit(json!({ "documentChanges": [
+            { "kind": "create", "uri": "file:///a.ts" }
+        ]}));
+        let merged = merge_workspace_edits([create.clone(), change("x")]).unwrap();
+        assert!(merged.is_some());
+        assert!(merge_workspace_edits([create.clone(), change("x"), change("y")]).is_err());
+        assert!(merge_workspace_edits([create.clone(), create]).is_err());
+    }
+}
@@ -1,7 +1,9 @@
 //! LSP system components
 
 pub mod client;
+pub mod edit_capture;
 pub mod zombie_reaper;
 
 pub use client::LspClient;
+pub use edit_capture::{merge_workspace_edits, EditCapture};
 pub use zombie_reaper::ZOMBIE_REAPER;
@@ -14,6 +14,7 @@
 use crate::{ModuleReference, PluginResult, ScanScope};
 use async_trait::async_trait;
 use mill_foundation::protocol::ImportGraph;
+use std::ops::Range;
 use std::path::Path;
 
 // ============================================================================
@@ -158,6 +159,39 @@ pub trait RefactoringProvider: Send + Sync {
         Err(crate::PluginApiError::not_supported("plan_inline_variable"))
     }
 
+    /// Check if inline function refactoring is supported
+    fn supports_inline_function(&self) -> bool {
+        false
+    }
+
+    /// Plan inline function refactoring
+    ///
+    /// Replaces calls with the function body, substituting arguments for
+    /// parameters. With the position on a call, only that call is inlined
+    /// unless `inline_all` is set; on the definition, every call in the file is
+    /// inlined. With `inline_all` the definition is deleted once no other
+    /// references remain. Recursive functions and bodies that capture
+    /// non-local state are refused with an error. Definitions that are kept
+    /// are explained in an optional `notes` intent argument.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Source code content
+    /// * `line` - Line of a call or of the function definition (0-based)
+    /// * `character` - Column on that line (0-based)
+    /// * `inline_all` - Inline every call in the file and delete the definition
+    /// * `file_path` - Path to the source file
+    async fn plan_inline_function(
+        &self,
+        _source: &str,
+        _line: u32,
+        _character: u32,
+        _inline_all: bool,
+        _file_path: &str,
+    ) -> PluginResult<mill_foundation::protocol::EditPlan> {
+        Err(crate::PluginApiError::not_supported("plan_inline_function"))
+    }
+
     /// Check if extract function refactoring is supported
     fn supports_extract_function(&self) -> bool {
         false
@@ -310,6 +344,79 @@ pub trait RefactoringProvider: Send + Sync {
         Err(crate::PluginApiError::not_supported("plan_symbol_delete"))
     }
 
+    /// Code transformation kinds this provider implements
+    ///
+    /// Transform kinds are snake_case identifiers such as `to_async`,
+    /// `function_to_arrow`, `arrow_to_function`, `if_else_to_match`,
+    /// `if_else_to_switch` and `callback_to_promise`.
+    fn supported_transforms(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Check if a code transformation of the given kind is supported
+    fn supports_transform(&self, kind: &str) -> bool {
+        self.supported_transforms().contains(&kind)
+    }
+
+    /// Plan a code transformation
+    ///
+    /// Analyzes the construct at the given position and generates an edit plan that
+    /// rewrites it in place (e.g. converting a function to `async`).
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Source code content
+    /// * `kind` - Transformation kind (see [`supports_transform`](Self::supports_transform))
+    /// * `line` - Line number of the construct to transform (0-based)
+    /// * `character` - Column number of the construct to transform (0-based)
+    /// * `file_path` - Path to the source file
+    async fn plan_transform(
+        &self,
+        _source: &str,
+        _kind: &str,
+        _line: u32,
+        _character: u32,
+        _file_path: &str,
+    ) -> PluginResult<mill_foundation::protocol::EditPlan> {
+        Err(crate::PluginApiError::not_supported("plan_transform"))
+    }
+
+    /// Check if reordering of the given kind is supported
+    ///
+    /// Reorder kinds are `parameters`, `fields`, `variants` and `imports`.
+    fn supports_reorder(&self, _kind: &str) -> bool {
+        false
+    }
+
+    /// Plan a reorder of the items declared at a position
+    ///
+    /// Only the declaration is rewritten. For `parameters`, the plan's intent
+    /// arguments must carry `function_name`, `name_line`/`name_character` (the
+    /// position of the function name, used to look up call sites) and
+    /// `permutation` (`permutation[i]` is the old index of the new i-th argument)
+    /// so callers can update call sites. Semantic caveats are reported as
+    /// strings in an optional `notes` intent argument.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Source code content
+    /// * `kind` - Reorder kind (see [`supports_reorder`](Self::supports_reorder))
+    /// * `line` - Line number inside the declaration (0-based)
+    /// * `character` - Column number inside the declaration (0-based)
+    /// * `new_order` - Item names in their new order (empty sorts `imports` alphabetically)
+    /// * `file_path` - Path to the source file
+    async fn plan_reorder(
+        &self,
+        _source: &str,
+        _kind: &str,
+        _line: u32,
+        _character: u32,
+        _new_order: &[String],
+        _file_path: &str,
+    ) -> PluginResult<mill_foundation::protocol::EditPlan> {
+        Err(crate::PluginApiError::not_supported("plan_reorder"))
+    }
+
     // ============================================================================
     // Legacy sync methods - DEPRECATED
     // These exist for backwards compatibility but should not be used in new code
@@ -609,6 +716,48 @@ pub trait ModuleDeclarationSupport: Send + Sync {
     }
 }
 
+// ============================================================================
+// Structural Search Capability
+// ============================================================================
+
+/// Tokens and syntax nodes of a source file, as byte ranges into the source
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyntaxIndex {
+    /// Code tokens in source order, without whitespace or comments
+    pub tokens: Vec<Range<usize>>,
+    /// Syntax nodes (expressions, statements, patterns, types, items)
+    pub nodes: Vec<Range<usize>>,
+}
+
+/// Capability for structural (syntax-aware) search
+///
+/// Exposes the language's own lexer and parser to the `structural` mode of
+/// workspace find/replace, which matches patterns token by token and only
+/// accepts matches that cover whole syntax nodes.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use mill_plugin_api::capabilities::StructuralSearchSupport;
+///
+/// if let Some(support) = plugin.structural_search_support() {
+///     let index = support.syntax_index(source, file_path).await?;
+///     // Match pattern tokens against index.tokens...
+/// }
+/// ```
+#[async_trait]
+pub trait StructuralSearchSupport: Send + Sync {
+    /// Split a code fragment into tokens without parsing it
+    ///
+    /// Used for search patterns, which need not be complete files.
+    async fn tokenize(&self, source: &str) -> PluginResult<Vec<Range<usize>>>;
+
+    /// Tokenize and parse a complete source file
+    ///
+    /// Fails with a parse error if the file is not syntactically valid.
+    async fn syntax_index(&self, source: &str, file_path: &Path) -> PluginResult<SyntaxIndex>;
+}
+
 // ============================================================================
 // File Discovery Capability
 // ============================================================================
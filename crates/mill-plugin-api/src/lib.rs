@@ -40,7 +40,7 @@ pub mod workspace_support;
 pub use capabilities::{
     ExtractParams, FileDiscovery, ImportAnalyzer, InlineParams, ManifestUpdater,
     ModuleDeclarationSupport, ModuleLocator, ModuleReferenceScanner, RefactoringProvider,
-    StandardFileDiscovery, TextEdit, WorkspaceEdit,
+    StandardFileDiscovery, StructuralSearchSupport, SyntaxIndex, TextEdit, WorkspaceEdit,
 };
 pub use import_support::{
     ImportAdvancedSupport, ImportMoveSupport, ImportMutationSupport, ImportParser,
@@ -238,6 +238,23 @@ impl SymbolKind {
             _ => None,
         }
     }
+
+    /// Convert SymbolKind to the closest LSP numeric kind
+    pub fn to_lsp_kind(self) -> u64 {
+        match self {
+            SymbolKind::Function => 12,
+            SymbolKind::Class => 5,
+            SymbolKind::Struct => 23,
+            SymbolKind::Enum => 10,
+            SymbolKind::Interface => 11,
+            SymbolKind::Variable => 13,
+            SymbolKind::Constant => 14,
+            SymbolKind::Module => 2,
+            SymbolKind::Method => 6,
+            SymbolKind::Field => 8,
+            SymbolKind::Other => 26,
+        }
+    }
 }
 
 /// Plugin capability flags
@@ -468,6 +485,14 @@ pub trait LanguagePlugin: Send + Sync {
         })
     }
 
+    /// Version of the parser behind `parse` and `analyze_detailed_imports`
+    ///
+    /// Persisted parse results are keyed by this version, so bump it whenever
+    /// a parser change alters the symbols or import graphs the plugin produces.
+    fn parser_version(&self) -> &'static str {
+        "0.0.0"
+    }
+
     /// Get import parser if available
     fn import_parser(&self) -> Option<&dyn ImportParser> {
         None
@@ -581,6 +606,13 @@ pub trait LanguagePlugin: Send + Sync {
         None
     }
 
+    /// Get structural search support if available
+    fn structural_search_support(
+        &self,
+    ) -> Option<&dyn crate::capabilities::StructuralSearchSupport> {
+        None
+    }
+
     /// Enable downcasting to concrete plugin types
     ///
     /// This allows service layers to access implementation-specific methods
@@ -86,6 +86,17 @@ pub trait WorkspaceSupport: Send + Sync {
     /// Updated manifest content with new package name
     fn update_package_name(&self, content: &str, new_name: &str) -> String;
 
+    /// Workspace manifest file names other than the package manifest
+    ///
+    /// # Returns
+    /// File names that declare workspace members on their own (e.g., "pnpm-workspace.yaml")
+    ///
+    /// # Default Implementation
+    /// Returns an empty slice: only the package manifest declares members.
+    fn additional_workspace_manifests(&self) -> &'static [&'static str] {
+        &[]
+    }
+
     // ========================================================================
     // Move/Rename Planning (Async operations for I/O)
     // ========================================================================
@@ -418,6 +418,10 @@ impl LanguagePlugin for SystemToolsPlugin {
                             "type": "boolean",
                             "description": "If true, execute the workflow in dry-run mode (preview changes without modifying files). Only applies when execute is true."
                         },
+                        "transactional": {
+                            "type": "boolean",
+                            "description": "If true, write steps only produce plans, and all plans are applied atomically after the last step (or not at all). Overrides the workflow's own setting."
+                        },
                         "workflow_id": {
                             "type": "string",
                             "description": "Optional workflow ID to resume a paused workflow. If provided, the intent parameter is ignored."
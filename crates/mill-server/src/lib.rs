@@ -81,11 +81,7 @@ pub async fn bootstrap(options: ServerOptions) -> ServerResult<ServerHandle> {
     #[cfg(feature = "mcp-proxy")]
     use mill_services::services::app_state_factory::register_mcp_proxy_if_enabled;
 
-    let cache_settings = mill_ast::CacheSettings::from_config(
-        options.config.cache.enabled,
-        options.config.cache.ttl_seconds,
-        options.config.cache.max_size_bytes,
-    );
+    let cache_settings = mill_ast::CacheSettings::from_cache_config(&options.config.cache);
 
     let plugin_manager = Arc::new(mill_plugin_system::PluginManager::new());
 
@@ -116,6 +112,9 @@ pub async fn bootstrap(options: ServerOptions) -> ServerResult<ServerHandle> {
         file_service: services.file_service,
         planner: services.planner,
         workflow_executor: services.workflow_executor,
+        symbol_index: Arc::new(mill_handlers::handlers::workspace::SymbolIndex::new(
+            project_root.clone(),
+        )),
         project_root,
         lock_manager: services.lock_manager,
         operation_queue: services.operation_queue,
@@ -123,6 +122,7 @@ pub async fn bootstrap(options: ServerOptions) -> ServerResult<ServerHandle> {
         workspace_manager,
         language_plugins: mill_handlers::LanguagePluginRegistry::from_registry(plugin_registry),
         lsp_mode: options.config.lsp.mode,
+        audit_log: services.audit_log,
     });
 
     // Create dispatcher
@@ -197,11 +197,7 @@ pub async fn create_dispatcher_with_workspace(
     #[cfg(feature = "mcp-proxy")]
     use mill_services::services::app_state_factory::register_mcp_proxy_if_enabled;
 
-    let cache_settings = mill_ast::CacheSettings::from_config(
-        config.cache.enabled,
-        config.cache.ttl_seconds,
-        config.cache.max_size_bytes,
-    );
+    let cache_settings = mill_ast::CacheSettings::from_cache_config(&config.cache);
 
     let plugin_manager = Arc::new(mill_plugin_system::PluginManager::new());
 
@@ -227,6 +223,9 @@ pub async fn create_dispatcher_with_workspace(
         file_service: services.file_service,
         planner: services.planner,
         workflow_executor: services.workflow_executor,
+        symbol_index: Arc::new(mill_handlers::handlers::workspace::SymbolIndex::new(
+            project_root.clone(),
+        )),
         project_root,
         lock_manager: services.lock_manager,
         operation_queue: services.operation_queue,
@@ -234,6 +233,7 @@ pub async fn create_dispatcher_with_workspace(
         workspace_manager,
         language_plugins: mill_handlers::LanguagePluginRegistry::from_registry(plugin_registry),
         lsp_mode: config.lsp.mode,
+        audit_log: services.audit_log,
     });
 
     // Create and return dispatcher
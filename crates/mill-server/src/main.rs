@@ -18,6 +18,8 @@ enum Commands {
     Start,
     /// Start WebSocket server (default)
     Serve,
+    /// Start MCP Streamable HTTP server
+    Http,
 }
 
 #[tokio::main]
@@ -71,6 +73,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 return Err(e);
             }
         }
+        Some(Commands::Http) => {
+            tracing::info!(
+                "Starting MCP HTTP server on {}:{}",
+                config.server.host,
+                config.server.port
+            );
+            if let Err(e) = mill_transport::start_http_server(config, dispatcher).await {
+                tracing::error!(
+                    error_category = "transport_error",
+                    error = %e,
+                    "Failed to start HTTP server"
+                );
+                return Err(e.into());
+            }
+        }
         Some(Commands::Serve) | None => {
             // Start admin server on a separate port
             let admin_port = config.server.port + 1000; // Admin on port+1000
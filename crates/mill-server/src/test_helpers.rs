@@ -179,7 +179,10 @@ pub async fn create_test_dispatcher_with_root(
     let planner = crate::services::planner::DefaultPlanner::new();
     let plugin_manager = Arc::new(PluginManager::new());
     let workflow_executor =
-        crate::services::workflow_executor::DefaultWorkflowExecutor::new(plugin_manager.clone());
+        crate::services::workflow_executor::DefaultWorkflowExecutor::new(
+            plugin_manager.clone(),
+            file_service.clone(),
+        );
     let workspace_manager = Arc::new(WorkspaceManager::new());
 
     let app_state = Arc::new(AppState {
@@ -187,6 +190,9 @@ pub async fn create_test_dispatcher_with_root(
         file_service,
         planner,
         workflow_executor,
+        symbol_index: Arc::new(mill_handlers::handlers::workspace::SymbolIndex::new(
+            project_root.clone(),
+        )),
         project_root,
         lock_manager,
         operation_queue,
@@ -194,6 +200,7 @@ pub async fn create_test_dispatcher_with_root(
         workspace_manager,
         language_plugins: mill_handlers::LanguagePluginRegistry::from_registry(plugin_registry),
         lsp_mode: mill_config::config::LspMode::Discover,
+        audit_log: None,
     });
 
     PluginDispatcher::new(app_state, plugin_manager)
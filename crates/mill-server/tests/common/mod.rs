@@ -34,6 +34,9 @@ pub async fn create_test_app_state() -> (Arc<AppState>, TempDir) {
         file_service: services.file_service,
         planner: services.planner,
         workflow_executor: services.workflow_executor,
+        symbol_index: Arc::new(mill_handlers::handlers::workspace::SymbolIndex::new(
+            project_root.clone(),
+        )),
         project_root,
         lock_manager: services.lock_manager,
         operation_queue: services.operation_queue,
@@ -41,6 +44,7 @@ pub async fn create_test_app_state() -> (Arc<AppState>, TempDir) {
         workspace_manager,
         language_plugins: mill_handlers::LanguagePluginRegistry::from_registry(plugin_registry),
         lsp_mode: mill_config::config::LspMode::Discover,
+        audit_log: services.audit_log,
     });
 
     (app_state, temp_dir)
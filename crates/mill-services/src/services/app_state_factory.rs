@@ -19,6 +19,8 @@ pub struct ServicesBundle {
     pub operation_queue: Arc<OperationQueue>,
     pub planner: Arc<dyn planner::Planner>,
     pub workflow_executor: Arc<dyn workflow_executor::WorkflowExecutor>,
+    /// Audit log of write operations, unless disabled in the config
+    pub audit_log: Option<Arc<AuditLog>>,
 }
 
 /// Create services bundle with default configuration
@@ -35,6 +37,12 @@ pub async fn create_services_bundle(
     // Plugin registry is now injected by the caller (dependency injection)
 
     let ast_cache = Arc::new(AstCache::with_settings(cache_settings));
+    if ast_cache.is_persistent() {
+        // Drop entries of parser versions no longer in use, off the startup path
+        let ast_cache = ast_cache.clone();
+        let plugins = plugin_registry.all().to_vec();
+        tokio::task::spawn_blocking(move || ast_cache.prune_persistent(&plugins));
+    }
     let ast_service = Arc::new(DefaultAstService::new(
         ast_cache.clone(),
         plugin_registry.clone(),
@@ -54,7 +62,13 @@ pub async fn create_services_bundle(
         plugin_registry,
     ));
     let planner = planner::DefaultPlanner::new();
-    let workflow_executor = workflow_executor::DefaultWorkflowExecutor::new(plugin_manager);
+    let workflow_executor =
+        workflow_executor::DefaultWorkflowExecutor::new(plugin_manager, file_service.clone());
+
+    let audit_log = config
+        .audit
+        .enabled
+        .then(|| Arc::new(AuditLog::new(project_root, &config.audit)));
 
     ServicesBundle {
         ast_service,
@@ -63,6 +77,7 @@ pub async fn create_services_bundle(
         operation_queue,
         planner,
         workflow_executor,
+        audit_log,
     }
 }
 
@@ -4,7 +4,7 @@ use async_trait::async_trait;
 use std::path::Path;
 use std::sync::Arc;
 
-use mill_ast::AstCache;
+use mill_ast::{AstCache, ContentKey};
 use mill_foundation::errors::MillError;
 use mill_foundation::protocol::{CacheStats, ImportGraph};
 use mill_plugin_api::PluginDiscovery;
@@ -56,6 +56,15 @@ impl DefaultAstService {
     pub fn maintain_cache(&self) {
         self.cache.maintenance();
     }
+
+    /// Persistent cache namespace of the parser used for `path`
+    fn parser_namespace(&self, path: &Path) -> String {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.plugin_registry.find_by_extension(ext))
+            .map(AstCache::plugin_namespace)
+            .unwrap_or_else(mill_ast::parser::fallback_namespace)
+    }
 }
 
 #[async_trait]
@@ -76,10 +85,29 @@ impl AstService for DefaultAstService {
         // Read the file content
         let content = tokio::fs::read_to_string(&file_path).await?;
 
+        // Check the persistent store, keyed by parser version and content
+        let persisted_key = self
+            .cache
+            .is_persistent()
+            .then(|| ContentKey::for_file(&self.parser_namespace(file), file, &content));
+        if let Some(key) = &persisted_key {
+            if let Some(import_graph) = self.cache.get_persisted(key).await {
+                trace!("Persistent cache hit for: {}", file_path.display());
+                if let Err(e) = self.cache.insert(file_path, import_graph.clone()).await {
+                    debug!("Failed to cache persisted import graph: {}", e);
+                }
+                return Ok(import_graph);
+            }
+        }
+
         // Use plugin-based parsing for languages with plugins
         let import_graph =
             build_import_graph_with_plugin(&content, file, self.plugin_registry.clone())?;
 
+        if let Some(key) = &persisted_key {
+            self.cache.persist(key, &import_graph).await;
+        }
+
         // Cache the result for future use
         if let Err(e) = self
             .cache
@@ -0,0 +1,295 @@
+//! Append-only audit log of write operations
+//!
+//! Every tool call that modifies files is recorded as one JSON line: who made
+//! it, the tool and its arguments, the checksums of the plan it applied, the
+//! files it changed and its outcome. When the log would grow beyond
+//! `maxSizeBytes` it is rotated: `audit.jsonl` becomes `audit.jsonl.1`, which
+//! becomes `audit.jsonl.2`, and so on up to `maxFiles` rotated logs.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use mill_config::config::AuditConfig;
+use mill_foundation::errors::MillError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+type ServerResult<T> = Result<T, MillError>;
+
+/// How an audited tool call ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditOutcome {
+    Success,
+    Error,
+}
+
+/// One audited tool call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    /// When the call finished (RFC 3339)
+    pub timestamp: String,
+    /// The caller's user ID, if the transport authenticated one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Tool name
+    pub tool: String,
+    /// Tool arguments
+    pub arguments: Value,
+    /// Checksums of the files the applied plan was computed against
+    #[serde(default)]
+    pub checksums: BTreeMap<String, String>,
+    /// Files the call changed
+    #[serde(default)]
+    pub files: Vec<String>,
+    pub outcome: AuditOutcome,
+    /// Why the call failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+impl AuditRecord {
+    /// When the call finished, or `None` if the timestamp is malformed
+    pub fn recorded_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .map(|timestamp| timestamp.with_timezone(&Utc))
+    }
+}
+
+/// Size-rotated JSON lines audit log
+pub struct AuditLog {
+    path: PathBuf,
+    max_size_bytes: u64,
+    max_files: usize,
+    /// Serializes appends and rotation
+    write_lock: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(project_root: &Path, config: &AuditConfig) -> Self {
+        Self {
+            path: project_root.join(&config.path),
+            max_size_bytes: config.max_size_bytes,
+            // At least one rotated log, so rotating never discards the log
+            max_files: config.max_files.max(1),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Path of the current audit log
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record, rotating the log first if it would grow too large
+    pub async fn record(&self, record: &AuditRecord) -> ServerResult<()> {
+        let mut line = serde_json::to_vec(record)
+            .map_err(|e| MillError::serialization(format!("Invalid audit record: {}", e)))?;
+        line.push(b'\n');
+
+        let _guard = self.write_lock.lock().await;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| self.io_error(e))?;
+        }
+        let size = fs::metadata(&self.path).await.map_or(0, |m| m.len());
+        if size > 0 && size + line.len() as u64 > self.max_size_bytes {
+            self.rotate().await?;
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| self.io_error(e))?;
+        file.write_all(&line).await.map_err(|e| self.io_error(e))?;
+        file.flush().await.map_err(|e| self.io_error(e))
+    }
+
+    /// Records finished at or after `since`, oldest first
+    ///
+    /// Reads the rotated logs too. Lines that are not valid records are skipped.
+    pub async fn read(&self, since: Option<DateTime<Utc>>) -> ServerResult<Vec<AuditRecord>> {
+        let mut records = Vec::new();
+        let oldest_first = (1..=self.max_files)
+            .rev()
+            .map(|n| self.rotated(n))
+            .chain([self.path.clone()]);
+        for path in oldest_first {
+            let content = match fs::read_to_string(&path).await {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(self.io_error(e)),
+            };
+            for (index, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<AuditRecord>(line) {
+                    Ok(record) => {
+                        let recent = since.is_none_or(|since| {
+                            record.recorded_at().is_some_and(|time| time >= since)
+                        });
+                        if recent {
+                            records.push(record);
+                        }
+                    }
+                    Err(e) => warn!(
+                        path = %path.display(),
+                        line = index + 1,
+                        error = %e,
+                        "Skipping malformed audit record"
+                    ),
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    /// Shift every log one rotation older, dropping the oldest
+    async fn rotate(&self) -> ServerResult<()> {
+        if let Err(e) = fs::remove_file(self.rotated(self.max_files)).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(self.io_error(e));
+            }
+        }
+        for n in (1..self.max_files).rev() {
+            let from = self.rotated(n);
+            if fs::try_exists(&from).await.unwrap_or(false) {
+                fs::rename(&from, self.rotated(n + 1))
+                    .await
+                    .map_err(|e| self.io_error(e))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))
+            .await
+            .map_err(|e| self.io_error(e))
+    }
+
+    /// Path of the `n`th most recent rotated log
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+
+    fn io_error(&self, error: std::io::Error) -> MillError {
+        MillError::io(format!("Audit log {}: {}", self.path.display(), error))
+    }
+}
+
+/// Parse a `--since` value relative to `now`
+///
+/// Accepts an RFC 3339 timestamp, a `YYYY-MM-DD` date (midnight UTC), or an
+/// age such as `30m`, `12h`, `7d` or `2w`.
+pub fn parse_since(value: &str, now: DateTime<Utc>) -> ServerResult<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+
+    let invalid = || {
+        MillError::invalid_request(format!(
+            "Invalid --since value '{}': expected a timestamp, a date (YYYY-MM-DD) or an age like 12h or 7d",
+            value
+        ))
+    };
+    let unit_start = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let amount: i64 = value[..unit_start].parse().map_err(|_| invalid())?;
+    let age = match &value[unit_start..] {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+    Ok(now - age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(timestamp: &str, tool: &str) -> AuditRecord {
+        AuditRecord {
+            timestamp: timestamp.to_string(),
+            user_id: Some("alice".to_string()),
+            tool: tool.to_string(),
+            arguments: json!({ "options": { "dryRun": false } }),
+            checksums: BTreeMap::from([("src/a.ts".to_string(), "abc".to_string())]),
+            files: vec!["src/a.ts".to_string()],
+            outcome: AuditOutcome::Success,
+            error: None,
+            duration_ms: 12,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_rotate_by_size_and_read_back_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let line_len = serde_json::to_vec(&record("2026-10-01T10:00:00Z", "prune"))
+            .unwrap()
+            .len() as u64
+            + 1;
+        let log = AuditLog::new(
+            dir.path(),
+            &AuditConfig {
+                enabled: true,
+                path: PathBuf::from(".typemill/audit.jsonl"),
+                // Two records per file
+                max_size_bytes: 2 * line_len,
+                max_files: 2,
+            },
+        );
+
+        for hour in 10..17 {
+            let timestamp = format!("2026-10-01T{}:00:00Z", hour);
+            log.record(&record(&timestamp, "prune")).await.unwrap();
+        }
+
+        // The oldest rotated log, holding the first two records, was dropped
+        let records = log.read(None).await.unwrap();
+        let hours: Vec<&str> = records.iter().map(|r| &r.timestamp[11..13]).collect();
+        assert_eq!(hours, ["12", "13", "14", "15", "16"]);
+        assert!(dir.path().join(".typemill/audit.jsonl.2").exists());
+        assert!(!dir.path().join(".typemill/audit.jsonl.3").exists());
+        assert_eq!(records[0], record("2026-10-01T12:00:00Z", "prune"));
+
+        let since = parse_since("2026-10-01T14:30:00Z", Utc::now()).unwrap();
+        let recent = log.read(Some(since)).await.unwrap();
+        assert_eq!(recent.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = DateTime::parse_from_rfc3339("2026-10-17T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let at = |value: &str| parse_since(value, now).unwrap().to_rfc3339();
+
+        assert_eq!(at("2026-10-01T08:30:00+02:00"), "2026-10-01T06:30:00+00:00");
+        assert_eq!(at("2026-10-01"), "2026-10-01T00:00:00+00:00");
+        assert_eq!(at("90m"), "2026-10-17T10:30:00+00:00");
+        assert_eq!(at("7d"), "2026-10-10T12:00:00+00:00");
+        assert!(parse_since("yesterday", now).is_err());
+        assert!(parse_since("7y", now).is_err());
+        assert!(parse_since("h", now).is_err());
+    }
+}
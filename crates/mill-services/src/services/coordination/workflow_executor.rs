@@ -1,16 +1,32 @@
 //! The WorkflowExecutor service for executing multi-step workflows.
 
+use crate::services::{ExecutionOptions, FileService, PlanExecutor};
 use dashmap::DashMap;
-use mill_foundation::core::model::workflow::Workflow;
+use mill_foundation::core::model::workflow::{OnError, Step, Workflow};
+use mill_foundation::core::tool_effects::{self, PLANNING_TOOLS};
 use mill_foundation::errors::{MillError as ServerError, MillResult as ServerResult};
+use mill_foundation::protocol::RefactorPlan;
 use mill_plugin_system::{PluginManager, PluginRequest};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+/// Progress of a workflow execution, carried across a confirmation pause
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WorkflowRun {
+    /// Results from previously executed steps
+    pub step_results: HashMap<usize, Value>,
+    /// Execution log up to this point
+    pub log: Vec<String>,
+    /// Number of steps executed (skipped steps are not counted)
+    pub steps_executed: usize,
+    /// Plans produced by the write steps of a transactional workflow
+    pub plans: Vec<RefactorPlan>,
+}
+
 /// State of a paused workflow waiting for user confirmation (internal to mill-services).
 #[derive(Debug, Clone)]
 pub(crate) struct PausedWorkflowState {
@@ -18,10 +34,8 @@ pub(crate) struct PausedWorkflowState {
     pub workflow: Workflow,
     /// The index of the step that requires confirmation
     pub step_index: usize,
-    /// Results from previously executed steps
-    pub step_results: HashMap<usize, Value>,
-    /// Execution log up to this point
-    pub log: Vec<String>,
+    /// Progress up to this point
+    pub run: WorkflowRun,
     /// Whether this is a dry-run execution
     pub dry_run: bool,
 }
@@ -42,14 +56,17 @@ pub trait WorkflowExecutor: Send + Sync {
 pub struct DefaultWorkflowExecutor {
     /// Plugin manager for executing tool calls
     plugin_manager: Arc<PluginManager>,
+    /// Applies the collected plans of transactional workflows
+    plan_executor: PlanExecutor,
     /// Cache of paused workflows waiting for confirmation
     paused_workflows: DashMap<String, PausedWorkflowState>,
 }
 
 impl DefaultWorkflowExecutor {
-    pub fn new(plugin_manager: Arc<PluginManager>) -> Arc<Self> {
+    pub fn new(plugin_manager: Arc<PluginManager>, file_service: Arc<FileService>) -> Arc<Self> {
         Arc::new(Self {
             plugin_manager,
+            plan_executor: PlanExecutor::new(file_service),
             paused_workflows: DashMap::new(),
         })
     }
@@ -131,19 +148,300 @@ impl DefaultWorkflowExecutor {
         }
     }
 
+    /// Replace `$item` and `$item.{path}` placeholders with the current `forEach` element.
+    fn resolve_item_params(params: &Value, item: &Value) -> ServerResult<Value> {
+        match params {
+            Value::Object(map) => {
+                let mut resolved = serde_json::Map::new();
+                for (key, value) in map {
+                    resolved.insert(key.clone(), Self::resolve_item_params(value, item)?);
+                }
+                Ok(Value::Object(resolved))
+            }
+            Value::Array(arr) => arr
+                .iter()
+                .map(|value| Self::resolve_item_params(value, item))
+                .collect::<ServerResult<Vec<_>>>()
+                .map(Value::Array),
+            Value::String(s) if s == "$item" || s.starts_with("$item.") => {
+                Self::lookup_item(s, item)
+            }
+            other => Ok(other.clone()),
+        }
+    }
+
+    /// Look up an `$item` placeholder in the current `forEach` element.
+    fn lookup_item(placeholder: &str, item: &Value) -> ServerResult<Value> {
+        let mut current = item;
+        if let Some(path) = placeholder.strip_prefix("$item.") {
+            for part in path.split('.') {
+                current = current.get(part).ok_or_else(|| {
+                    ServerError::runtime(format!(
+                        "Failed to resolve placeholder '{}': field '{}' not found in the current item",
+                        placeholder, part
+                    ))
+                })?;
+            }
+        }
+        Ok(current.clone())
+    }
+
+    /// Resolve an operand of a `when` condition; unresolvable placeholders yield `None`.
+    fn condition_operand(
+        operand: &Value,
+        step_results: &HashMap<usize, Value>,
+        item: Option<&Value>,
+    ) -> Option<Value> {
+        match (operand.as_str(), item) {
+            (Some(s), Some(item)) if s == "$item" || s.starts_with("$item.") => {
+                Self::lookup_item(s, item).ok()
+            }
+            (Some(s), _) if s.starts_with("$steps.") => {
+                Self::resolve_step_params(operand, step_results).ok()
+            }
+            _ => Some(operand.clone()),
+        }
+    }
+
+    /// Evaluate a step's `when` condition.
+    fn condition_holds(
+        condition: &Value,
+        step_results: &HashMap<usize, Value>,
+        item: Option<&Value>,
+    ) -> ServerResult<bool> {
+        match condition {
+            Value::Bool(b) => Ok(*b),
+            Value::String(s) => {
+                let (negated, operand) = match s.strip_prefix('!') {
+                    Some(rest) => (true, Value::String(rest.to_string())),
+                    None => (false, condition.clone()),
+                };
+                let truthy = Self::condition_operand(&operand, step_results, item)
+                    .is_some_and(|value| is_truthy(&value));
+                Ok(truthy != negated)
+            }
+            Value::Object(map) if map.contains_key("value") => {
+                let value = Self::condition_operand(&map["value"], step_results, item);
+                let compare = |expected: &Value| {
+                    value.is_some()
+                        && value == Self::condition_operand(expected, step_results, item)
+                };
+                match (map.get("equals"), map.get("notEquals")) {
+                    (Some(expected), None) => Ok(compare(expected)),
+                    (None, Some(expected)) => Ok(!compare(expected)),
+                    _ => Err(ServerError::runtime(
+                        "Invalid 'when' condition: expected exactly one of 'equals' or 'notEquals'",
+                    )),
+                }
+            }
+            other => Err(ServerError::runtime(format!(
+                "Invalid 'when' condition: {}",
+                other
+            ))),
+        }
+    }
+
+    /// Extract the refactoring plan from a write step's preview result, if any.
+    ///
+    /// Write tools return their plan in the `changes` field of a preview.
+    fn plan_from_result(result: &Value) -> Option<RefactorPlan> {
+        [result.get("changes"), Some(result)]
+            .into_iter()
+            .flatten()
+            .find(|value| value.get("planType").is_some())
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Whether a step with these (resolved) parameters may modify files
+    fn step_writes(tool: &str, params: &Value) -> bool {
+        !tool_effects::is_read_only(tool, params)
+    }
+
+    /// Run the steps of a workflow from `start`, pausing at steps that need confirmation.
+    ///
+    /// `confirmed_step` is the step the user just confirmed when resuming.
+    async fn run_steps(
+        &self,
+        workflow: &Workflow,
+        start: usize,
+        confirmed_step: Option<usize>,
+        mut run: WorkflowRun,
+        dry_run: bool,
+    ) -> ServerResult<Value> {
+        let total_steps = workflow.steps.len();
+        let mut final_result = json!({});
+
+        for (step_index, step) in workflow.steps.iter().enumerate().skip(start) {
+            // Check if this step requires user confirmation
+            if step.requires_confirmation == Some(true) && confirmed_step != Some(step_index) {
+                info!(
+                    step_index = step_index,
+                    workflow_name = %workflow.name,
+                    "Step requires confirmation - pausing workflow"
+                );
+
+                // Generate a unique workflow ID
+                let workflow_id = Uuid::new_v4().to_string();
+
+                run.log.push(format!(
+                    "[Step {}/{}] PAUSED: {} - {}. Awaiting user confirmation.",
+                    step_index + 1,
+                    total_steps,
+                    step.tool,
+                    step.description
+                ));
+                let log = run.log.clone();
+
+                // Store the paused workflow state
+                self.paused_workflows.insert(
+                    workflow_id.clone(),
+                    PausedWorkflowState {
+                        workflow: workflow.clone(),
+                        step_index,
+                        run,
+                        dry_run,
+                    },
+                );
+
+                return Ok(json!({
+                    "status": "awaiting_confirmation",
+                    "workflow_id": workflow_id,
+                    "workflow": workflow.name,
+                    "step_index": step_index,
+                    "step_description": step.description,
+                    "log": log
+                }));
+            }
+
+            let step_result = match &step.for_each {
+                Some(for_each) => {
+                    let items = Self::resolve_step_params(
+                        &Value::String(for_each.clone()),
+                        &run.step_results,
+                    )?;
+                    let items = items.as_array().ok_or_else(|| {
+                        ServerError::runtime(format!(
+                            "Workflow '{}' step {}: forEach '{}' does not resolve to an array",
+                            workflow.name,
+                            step_index + 1,
+                            for_each
+                        ))
+                    })?;
+
+                    let mut results = Vec::new();
+                    for (item_index, item) in items.iter().enumerate() {
+                        if let Some(condition) = &step.when {
+                            if !Self::condition_holds(condition, &run.step_results, Some(item))? {
+                                continue;
+                            }
+                        }
+                        results.push(
+                            self.execute_workflow_step(
+                                step,
+                                step_index,
+                                Some((item_index, item)),
+                                workflow,
+                                &mut run,
+                                dry_run,
+                            )
+                            .await?,
+                        );
+                    }
+                    Value::Array(results)
+                }
+                None => {
+                    if let Some(condition) = &step.when {
+                        if !Self::condition_holds(condition, &run.step_results, None)? {
+                            debug!(step_index = step_index, "Step condition not met - skipping");
+                            run.log.push(format!(
+                                "[Step {}/{}] SKIPPED: {} - {}. Condition not met.",
+                                step_index + 1,
+                                total_steps,
+                                step.tool,
+                                step.description
+                            ));
+                            continue;
+                        }
+                    }
+                    self.execute_workflow_step(step, step_index, None, workflow, &mut run, dry_run)
+                        .await?
+                }
+            };
+
+            run.step_results.insert(step_index, step_result.clone());
+            run.steps_executed += 1;
+            final_result = step_result;
+        }
+
+        let mut response = json!({
+            "success": true,
+            "workflow": workflow.name,
+            "steps_executed": run.steps_executed,
+            "dryRun": dry_run,
+            "result": final_result
+        });
+
+        if workflow.transactional {
+            let plan_count = run.plans.len();
+            if dry_run {
+                run.log.push(format!(
+                    "[TRANSACTION] {} plan(s) collected; nothing applied in dry-run mode",
+                    plan_count
+                ));
+                response["plans"] = json!(run.plans);
+            } else {
+                let result = self
+                    .plan_executor
+                    .execute_plans(run.plans, ExecutionOptions::default())
+                    .await
+                    .map_err(|e| {
+                        error!(
+                            workflow = %workflow.name,
+                            error = %e,
+                            "Transactional workflow failed to apply its plans"
+                        );
+                        ServerError::runtime(format!(
+                            "Workflow '{}' could not apply its {} plan(s); no changes were made: {}",
+                            workflow.name, plan_count, e
+                        ))
+                    })?;
+                run.log.push(format!(
+                    "[TRANSACTION] Applied {} plan(s) atomically to {} file(s)",
+                    plan_count,
+                    result.applied_files.len()
+                ));
+                response["transaction"] = json!(result);
+            }
+        }
+
+        info!(
+            workflow_name = %workflow.name,
+            dry_run = dry_run,
+            "Workflow execution completed successfully"
+        );
+
+        run.log.push(format!(
+            "[COMPLETE] Workflow '{}' finished successfully ({} steps executed)",
+            workflow.name, run.steps_executed
+        ));
+        response["log"] = json!(run.log);
+
+        Ok(response)
+    }
+
     /// Execute a single workflow step
     ///
     /// This method handles parameter resolution, dry-run injection,
-    /// plugin request creation, and execution with logging.
-    #[allow(clippy::too_many_arguments)]
+    /// plugin request creation, the step's `onError` policy, and execution
+    /// with logging. In a transactional workflow the step only previews its
+    /// changes and the resulting plan is collected for the final apply.
     async fn execute_workflow_step(
         &self,
-        step: &mill_foundation::core::model::workflow::Step,
+        step: &Step,
         step_index: usize,
-        workflow_name: &str,
-        total_steps: usize,
-        step_results: &mut HashMap<usize, Value>,
-        log: &mut Vec<String>,
+        item: Option<(usize, &Value)>,
+        workflow: &Workflow,
+        run: &mut WorkflowRun,
         dry_run: bool,
     ) -> ServerResult<Value> {
         debug!(
@@ -153,17 +451,50 @@ impl DefaultWorkflowExecutor {
             "Executing workflow step"
         );
 
+        let label = match item {
+            Some((item_index, _)) => format!(
+                "[Step {}/{}, item {}]",
+                step_index + 1,
+                workflow.steps.len(),
+                item_index + 1
+            ),
+            None => format!("[Step {}/{}]", step_index + 1, workflow.steps.len()),
+        };
+
         // Resolve parameters using generic placeholder substitution
-        let mut resolved_params = Self::resolve_step_params(&step.params, step_results)?;
+        let mut resolved_params = Self::resolve_step_params(&step.params, &run.step_results)?;
+        if let Some((_, item)) = item {
+            resolved_params = Self::resolve_item_params(&resolved_params, item)?;
+        }
 
-        // If dry_run is enabled, add it to the parameters for all tools
-        if dry_run {
+        // A transactional workflow can only defer changes that come as plans
+        let collects_plan =
+            workflow.transactional && Self::step_writes(&step.tool, &resolved_params);
+        if collects_plan && !tool_effects::is_planning_tool(&step.tool) {
+            return Err(ServerError::runtime(format!(
+                "Workflow '{}' step {} ({}) cannot run in a transactional workflow: \
+                 only {} can preview their changes as a plan",
+                workflow.name,
+                step_index + 1,
+                step.tool,
+                PLANNING_TOOLS.join(", ")
+            )));
+        }
+
+        // Preview only when dry_run is enabled or the plans are applied at the end.
+        // Tools read `options.dryRun`, which overrides whatever the step set.
+        let preview = dry_run || workflow.transactional;
+        if preview {
             if let Value::Object(ref mut map) = resolved_params {
                 map.insert("dryRun".to_string(), Value::Bool(true));
+                let options = map.entry("options").or_insert_with(|| json!({}));
+                if let Value::Object(options) = options {
+                    options.insert("dryRun".to_string(), Value::Bool(true));
+                }
             }
         }
 
-        debug!(params = ?resolved_params, dry_run = dry_run, "Resolved step parameters");
+        debug!(params = ?resolved_params, dry_run = preview, "Resolved step parameters");
 
         // Create plugin request
         let file_path = resolved_params
@@ -181,62 +512,120 @@ impl DefaultWorkflowExecutor {
             request_id: None,
         };
 
-        // Execute the step
-        match self.plugin_manager.handle_request(plugin_request).await {
-            Ok(response) => {
-                let step_result = response.data.unwrap_or(json!({}));
-                debug!(
-                    step_index = step_index,
-                    result = ?step_result,
-                    "Step completed successfully"
-                );
-
-                // Log successful step completion
-                log.push(format!(
-                    "[Step {}/{}] SUCCESS: {} - {}",
-                    step_index + 1,
-                    total_steps,
-                    step.tool,
-                    step.description
-                ));
+        let mut attempt = 0;
+        loop {
+            // Execute the step
+            match self
+                .plugin_manager
+                .handle_request(plugin_request.clone())
+                .await
+            {
+                Ok(response) => {
+                    let step_result = response.data.unwrap_or(json!({}));
+                    debug!(
+                        step_index = step_index,
+                        result = ?step_result,
+                        "Step completed successfully"
+                    );
+
+                    if collects_plan {
+                        let plan = Self::plan_from_result(&step_result).ok_or_else(|| {
+                            run.log.push(format!(
+                                "{} FAILED: {} - {}. No plan returned",
+                                label, step.tool, step.description
+                            ));
+                            ServerError::runtime(format!(
+                                "Workflow '{}' step {}/{} ({}) returned no plan, so its \
+                                 changes cannot be applied with the rest of the transaction",
+                                workflow.name,
+                                step_index + 1,
+                                workflow.steps.len(),
+                                step.tool
+                            ))
+                        })?;
+                        run.plans.push(plan);
+                    }
 
-                step_results.insert(step_index, step_result.clone());
-                Ok(step_result)
-            }
-            Err(e) => {
-                error!(
-                    step_index = step_index,
-                    step_description = %step.description,
-                    tool = %step.tool,
-                    workflow = %workflow_name,
-                    error = %e,
-                    "Step execution failed - halting workflow"
-                );
+                    // Log successful step completion
+                    run.log.push(format!(
+                        "{} SUCCESS: {} - {}",
+                        label, step.tool, step.description
+                    ));
 
-                // Log the failure
-                log.push(format!(
-                    "[Step {}/{}] FAILED: {} - {}. Error: {}",
-                    step_index + 1,
-                    total_steps,
-                    step.tool,
-                    step.description,
-                    e
-                ));
+                    return Ok(step_result);
+                }
+                Err(e) => match step.on_error {
+                    OnError::Retry(retries) if attempt < retries => {
+                        attempt += 1;
+                        warn!(
+                            step_index = step_index,
+                            tool = %step.tool,
+                            attempt = attempt,
+                            error = %e,
+                            "Step execution failed - retrying"
+                        );
+                        run.log.push(format!(
+                            "{} RETRY {}/{}: {} - {}. Error: {}",
+                            label, attempt, retries, step.tool, step.description, e
+                        ));
+                    }
+                    OnError::Continue => {
+                        warn!(
+                            step_index = step_index,
+                            tool = %step.tool,
+                            error = %e,
+                            "Step execution failed - continuing workflow"
+                        );
+                        run.log.push(format!(
+                            "{} FAILED (continuing): {} - {}. Error: {}",
+                            label, step.tool, step.description, e
+                        ));
+                        return Ok(json!({ "error": e.to_string() }));
+                    }
+                    OnError::Fail | OnError::Retry(_) => {
+                        error!(
+                            step_index = step_index,
+                            step_description = %step.description,
+                            tool = %step.tool,
+                            workflow = %workflow.name,
+                            error = %e,
+                            "Step execution failed - halting workflow"
+                        );
+
+                        // Log the failure
+                        run.log.push(format!(
+                            "{} FAILED: {} - {}. Error: {}",
+                            label, step.tool, step.description, e
+                        ));
 
-                Err(ServerError::runtime(format!(
-                    "Workflow '{}' failed at step {}/{} ({}): {}. Error: {}",
-                    workflow_name,
-                    step_index + 1,
-                    total_steps,
-                    step.tool,
-                    step.description,
-                    e
-                )))
+                        return Err(ServerError::runtime(format!(
+                            "Workflow '{}' failed at step {}/{} ({}): {}. Error: {}",
+                            workflow.name,
+                            step_index + 1,
+                            workflow.steps.len(),
+                            step.tool,
+                            step.description,
+                            e
+                        )));
+                    }
+                },
             }
         }
     }
 }
 
+/// Whether a `when` operand counts as true: not `null`, `false`, `0` or empty.
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(arr) => !arr.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
 #[async_trait::async_trait]
 impl WorkflowExecutor for DefaultWorkflowExecutor {
     async fn execute_workflow(&self, workflow: &Workflow, dry_run: bool) -> ServerResult<Value> {
@@ -244,97 +633,25 @@ impl WorkflowExecutor for DefaultWorkflowExecutor {
             workflow_name = %workflow.name,
             steps_count = workflow.steps.len(),
             dry_run = dry_run,
+            transactional = workflow.transactional,
             "Starting workflow execution"
         );
 
-        let mut step_results: HashMap<usize, Value> = HashMap::new();
-        let mut final_result = json!({});
-        let mut log: Vec<String> = Vec::new();
+        let mut run = WorkflowRun::default();
 
         if dry_run {
-            log.push(format!(
+            run.log.push(format!(
                 "[DRY RUN MODE] Executing workflow '{}' without modifying files",
                 workflow.name
             ));
+        } else if workflow.transactional {
+            run.log.push(format!(
+                "[TRANSACTION] Collecting plans for workflow '{}'; changes are applied after the last step",
+                workflow.name
+            ));
         }
 
-        for (step_index, step) in workflow.steps.iter().enumerate() {
-            // Check if this step requires user confirmation
-            if step.requires_confirmation == Some(true) {
-                info!(
-                    step_index = step_index,
-                    workflow_name = %workflow.name,
-                    "Step requires confirmation - pausing workflow"
-                );
-
-                // Generate a unique workflow ID
-                let workflow_id = Uuid::new_v4().to_string();
-
-                // Store the paused workflow state
-                let paused_state = PausedWorkflowState {
-                    workflow: workflow.clone(),
-                    step_index,
-                    step_results: step_results.clone(),
-                    log: log.clone(),
-                    dry_run,
-                };
-
-                self.paused_workflows
-                    .insert(workflow_id.clone(), paused_state);
-
-                log.push(format!(
-                    "[Step {}/{}] PAUSED: {} - {}. Awaiting user confirmation.",
-                    step_index + 1,
-                    workflow.steps.len(),
-                    step.tool,
-                    step.description
-                ));
-
-                return Ok(json!({
-                    "status": "awaiting_confirmation",
-                    "workflow_id": workflow_id,
-                    "workflow": workflow.name,
-                    "step_index": step_index,
-                    "step_description": step.description,
-                    "log": log
-                }));
-            }
-
-            let step_result = self
-                .execute_workflow_step(
-                    step,
-                    step_index,
-                    &workflow.name,
-                    workflow.steps.len(),
-                    &mut step_results,
-                    &mut log,
-                    dry_run,
-                )
-                .await?;
-
-            final_result = step_result;
-        }
-
-        info!(
-            workflow_name = %workflow.name,
-            dry_run = dry_run,
-            "Workflow execution completed successfully"
-        );
-
-        log.push(format!(
-            "[COMPLETE] Workflow '{}' finished successfully ({} steps executed)",
-            workflow.name,
-            workflow.steps.len()
-        ));
-
-        Ok(json!({
-            "success": true,
-            "workflow": workflow.name,
-            "steps_executed": workflow.steps.len(),
-            "dryRun": dry_run,
-            "log": log,
-            "result": final_result
-        }))
+        self.run_steps(workflow, 0, None, run, dry_run).await
     }
 
     async fn resume_workflow(
@@ -356,60 +673,22 @@ impl WorkflowExecutor for DefaultWorkflowExecutor {
             })?
             .1;
 
-        let workflow = paused_state.workflow;
-        let mut step_results = paused_state.step_results;
-        let mut log = paused_state.log;
-        let dry_run = paused_state.dry_run;
-        let mut final_result = json!({});
-
-        log.push(format!(
+        let mut run = paused_state.run;
+        run.log.push(format!(
             "[RESUMED] Continuing workflow '{}' from step {}",
-            workflow.name,
+            paused_state.workflow.name,
             paused_state.step_index + 1
         ));
 
         // Continue execution from the paused step
-        for (step_index, step) in workflow
-            .steps
-            .iter()
-            .enumerate()
-            .skip(paused_state.step_index)
-        {
-            let step_result = self
-                .execute_workflow_step(
-                    step,
-                    step_index,
-                    &workflow.name,
-                    workflow.steps.len(),
-                    &mut step_results,
-                    &mut log,
-                    dry_run,
-                )
-                .await?;
-
-            final_result = step_result;
-        }
-
-        info!(
-            workflow_name = %workflow.name,
-            dry_run = dry_run,
-            "Resumed workflow execution completed successfully"
-        );
-
-        log.push(format!(
-            "[COMPLETE] Workflow '{}' finished successfully ({} steps executed)",
-            workflow.name,
-            workflow.steps.len()
-        ));
-
-        Ok(json!({
-            "success": true,
-            "workflow": workflow.name,
-            "steps_executed": workflow.steps.len(),
-            "dryRun": dry_run,
-            "log": log,
-            "result": final_result
-        }))
+        self.run_steps(
+            &paused_state.workflow,
+            paused_state.step_index,
+            Some(paused_state.step_index),
+            run,
+            paused_state.dry_run,
+        )
+        .await
     }
 
     fn get_paused_workflow_count(&self) -> usize {
@@ -592,4 +871,300 @@ mod tests {
         assert_eq!(files[0].as_str().unwrap(), "test.ts");
         assert_eq!(files[1].as_str().unwrap(), "other.ts");
     }
+
+    #[test]
+    fn test_resolve_item_placeholders() {
+        let item = json!({ "name": "oldName", "location": { "line": 3 } });
+        let params = json!({
+            "target": "$item",
+            "line": "$item.location.line",
+            "names": ["$item.name", "other"]
+        });
+
+        let resolved = DefaultWorkflowExecutor::resolve_item_params(&params, &item).unwrap();
+        assert_eq!(resolved["target"], item);
+        assert_eq!(resolved["line"], 3);
+        assert_eq!(resolved["names"], json!(["oldName", "other"]));
+
+        let missing = json!({ "kind": "$item.kind" });
+        assert!(DefaultWorkflowExecutor::resolve_item_params(&missing, &item).is_err());
+    }
+
+    #[test]
+    fn test_when_conditions() {
+        let mut step_results = HashMap::new();
+        step_results.insert(0, json!({ "symbols": [{ "name": "a" }], "count": 0 }));
+        let item = json!({ "kind": "function" });
+        let holds = |condition: Value| {
+            DefaultWorkflowExecutor::condition_holds(&condition, &step_results, Some(&item))
+                .unwrap()
+        };
+
+        assert!(holds(json!("$steps.0.symbols")));
+        assert!(!holds(json!("$steps.0.count")));
+        assert!(holds(json!("!$steps.0.count")));
+        // Missing fields and skipped steps count as false
+        assert!(!holds(json!("$steps.0.missing")));
+        assert!(!holds(json!("$steps.1.symbols")));
+        assert!(holds(
+            json!({ "value": "$item.kind", "equals": "function" })
+        ));
+        assert!(holds(
+            json!({ "value": "$item.kind", "notEquals": "class" })
+        ));
+        assert!(!holds(json!({ "value": "$item.missing", "equals": null })));
+
+        let invalid = json!({ "value": "$item.kind" });
+        assert!(
+            DefaultWorkflowExecutor::condition_holds(&invalid, &step_results, Some(&item)).is_err()
+        );
+    }
+
+    #[test]
+    fn test_plan_from_preview_result() {
+        let plan = json!({
+            "planType": "renamePlan",
+            "edits": { "changes": {} },
+            "summary": { "affectedFiles": 1, "createdFiles": 0, "deletedFiles": 0 },
+            "warnings": [],
+            "metadata": {
+                "planVersion": "1.0",
+                "kind": "rename",
+                "language": "typescript",
+                "estimatedImpact": "low",
+                "createdAt": "2024-01-01T00:00:00Z"
+            },
+            "fileChecksums": {}
+        });
+
+        let preview = json!({ "status": "preview", "changes": plan });
+        assert!(matches!(
+            DefaultWorkflowExecutor::plan_from_result(&preview),
+            Some(RefactorPlan::RenamePlan(_))
+        ));
+        assert!(DefaultWorkflowExecutor::plan_from_result(&plan).is_some());
+        assert!(DefaultWorkflowExecutor::plan_from_result(&json!({ "symbols": [] })).is_none());
+    }
+
+    /// Stands in for the write tools: `rename_all` previews a plan replacing
+    /// the start of `a.txt`, or writes `a.txt` when `options.dryRun` is false;
+    /// `prune` returns no plan,
+    /// `notify_file_opened` succeeds and `refactor` fails
+    struct WriteToolsPlugin {
+        root: PathBuf,
+    }
+
+    #[async_trait::async_trait]
+    impl mill_plugin_system::LanguagePlugin for WriteToolsPlugin {
+        fn metadata(&self) -> mill_plugin_system::PluginMetadata {
+            mill_plugin_system::PluginMetadata::new("write-tools", "1.0.0", "test")
+        }
+
+        fn supported_extensions(&self) -> Vec<String> {
+            vec!["txt".to_string()]
+        }
+
+        fn tool_definitions(&self) -> Vec<Value> {
+            vec![]
+        }
+
+        fn capabilities(&self) -> mill_plugin_system::Capabilities {
+            let mut capabilities = mill_plugin_system::Capabilities::default();
+            for tool in ["rename_all", "prune", "refactor", "notify_file_opened"] {
+                capabilities.custom.insert(tool.to_string(), json!(true));
+            }
+            capabilities
+        }
+
+        async fn handle_request(
+            &self,
+            request: PluginRequest,
+        ) -> mill_plugin_system::PluginResult<mill_plugin_system::PluginResponse> {
+            let dry_run = request.params.pointer("/options/dryRun") == Some(&json!(true));
+            let data = match request.method.as_str() {
+                "rename_all" if dry_run => {
+                    json!({ "status": "preview", "changes": rename_plan(&self.root) })
+                }
+                "rename_all" => {
+                    std::fs::write(self.root.join("a.txt"), "renamed").unwrap();
+                    json!({ "status": "success" })
+                }
+                "prune" => json!({ "status": "preview" }),
+                "notify_file_opened" => json!({ "status": "success" }),
+                _ => {
+                    return Err(mill_plugin_system::PluginSystemError::request_failed(
+                        "write-tools",
+                        "step failed",
+                    ))
+                }
+            };
+            Ok(mill_plugin_system::PluginResponse::success(
+                data,
+                "write-tools",
+            ))
+        }
+
+        fn configure(&self, _config: Value) -> mill_plugin_system::PluginResult<()> {
+            Ok(())
+        }
+    }
+
+    fn rename_plan(root: &std::path::Path) -> Value {
+        let uri = url::Url::from_file_path(root.join("a.txt")).unwrap();
+        json!({
+            "planType": "renamePlan",
+            "edits": {
+                "changes": {
+                    uri.as_str(): [{
+                        "range": {
+                            "start": { "line": 0, "character": 0 },
+                            "end": { "line": 0, "character": 8 }
+                        },
+                        "newText": "renamed"
+                    }]
+                }
+            },
+            "summary": { "affectedFiles": 0, "createdFiles": 0, "deletedFiles": 0 },
+            "warnings": [],
+            "metadata": {
+                "planVersion": "1.0",
+                "kind": "rename",
+                "language": "typescript",
+                "estimatedImpact": "low",
+                "createdAt": "2024-01-01T00:00:00Z"
+            },
+            "fileChecksums": {}
+        })
+    }
+
+    /// An executor whose tools are served by [`WriteToolsPlugin`] in `root`
+    async fn write_tools_executor(root: &std::path::Path) -> Arc<DefaultWorkflowExecutor> {
+        let plugin_manager = Arc::new(PluginManager::new());
+        let plugin = Arc::new(WriteToolsPlugin {
+            root: root.to_path_buf(),
+        });
+        plugin_manager
+            .register_plugin("write-tools", plugin)
+            .await
+            .unwrap();
+        let lock_manager = Arc::new(crate::services::LockManager::new());
+        let file_service = Arc::new(FileService::new(
+            root,
+            Arc::new(mill_ast::AstCache::new()),
+            lock_manager.clone(),
+            Arc::new(crate::services::OperationQueue::new(lock_manager)),
+            &mill_config::AppConfig::default(),
+            crate::services::build_language_plugin_registry(vec![]),
+        ));
+        DefaultWorkflowExecutor::new(plugin_manager, file_service)
+    }
+
+    /// A transactional workflow running each tool on `a.txt`
+    fn transaction(tools: &[&str]) -> Workflow {
+        let steps: Vec<Value> = tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "tool": tool,
+                    "params": { "filePath": "a.txt", "options": { "dryRun": false } },
+                    "description": format!("Run {}", tool)
+                })
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "name": "transaction",
+            "steps": steps,
+            "metadata": { "complexity": 2 },
+            "transactional": true
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_failed_transaction_changes_no_files() {
+        let project = tempfile::TempDir::new().unwrap();
+        std::fs::write(project.path().join("a.txt"), "original").unwrap();
+        let executor = write_tools_executor(project.path()).await;
+
+        // The first step's template asks to write; it must only preview
+        let failed = executor
+            .execute_workflow(&transaction(&["rename_all", "refactor"]), false)
+            .await;
+        assert!(failed.is_err());
+        let content = std::fs::read_to_string(project.path().join("a.txt")).unwrap();
+        assert_eq!(content, "original");
+
+        let error = executor
+            .execute_workflow(&transaction(&["prune"]), false)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("returned no plan"));
+    }
+
+    #[tokio::test]
+    async fn test_transaction_refuses_steps_editing_the_same_text() {
+        let project = tempfile::TempDir::new().unwrap();
+        std::fs::write(project.path().join("a.txt"), "original").unwrap();
+        let executor = write_tools_executor(project.path()).await;
+
+        let error = executor
+            .execute_workflow(&transaction(&["rename_all", "rename_all"]), false)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("overlapping"));
+        let content = std::fs::read_to_string(project.path().join("a.txt")).unwrap();
+        assert_eq!(content, "original");
+
+        executor
+            .execute_workflow(&transaction(&["rename_all"]), false)
+            .await
+            .unwrap();
+        let content = std::fs::read_to_string(project.path().join("a.txt")).unwrap();
+        assert_eq!(content, "renamed");
+    }
+
+    #[tokio::test]
+    async fn test_transaction_runs_notification_steps_as_reads() {
+        let project = tempfile::TempDir::new().unwrap();
+        std::fs::write(project.path().join("a.txt"), "original").unwrap();
+        let executor = write_tools_executor(project.path()).await;
+
+        for tool in [
+            "notify_file_opened",
+            "notify_file_saved",
+            "notify_file_closed",
+        ] {
+            assert!(!DefaultWorkflowExecutor::step_writes(tool, &json!({})));
+        }
+        // A write step would have to preview a plan, which notifications cannot
+        executor
+            .execute_workflow(&transaction(&["notify_file_opened", "rename_all"]), false)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_step_control_fields_deserialize() {
+        let step: Step = serde_json::from_value(json!({
+            "tool": "rename_all",
+            "params": {},
+            "description": "Rename each symbol",
+            "forEach": "$steps.0.symbols",
+            "when": "$item.name",
+            "onError": { "retry": 2 }
+        }))
+        .unwrap();
+        assert_eq!(step.for_each.as_deref(), Some("$steps.0.symbols"));
+        assert_eq!(step.on_error, OnError::Retry(2));
+
+        let step: Step = serde_json::from_value(json!({
+            "tool": "inspect_code",
+            "params": {},
+            "description": "Inspect",
+            "onError": "continue"
+        }))
+        .unwrap();
+        assert_eq!(step.on_error, OnError::Continue);
+        assert!(step.when.is_none());
+    }
 }
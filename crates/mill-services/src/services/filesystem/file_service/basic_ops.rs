@@ -22,6 +22,7 @@ impl FileService {
         dry_run: bool,
     ) -> ServerResult<DryRunnable<Value>> {
         let abs_path = self.to_absolute_path_checked(path)?;
+        self.check_not_reserved(&abs_path, "create")?;
         let content = content.unwrap_or("").to_string();
 
         let exists = fs::try_exists(&abs_path).await.unwrap_or(false);
@@ -112,6 +113,7 @@ impl FileService {
         dry_run: bool,
     ) -> ServerResult<DryRunnable<Value>> {
         let abs_path = self.to_absolute_path_checked(path)?;
+        self.check_not_reserved(&abs_path, "delete")?;
         let exists = fs::try_exists(&abs_path).await.unwrap_or(false);
 
         if dry_run {
@@ -265,6 +267,7 @@ impl FileService {
         dry_run: bool,
     ) -> ServerResult<DryRunnable<Value>> {
         let abs_path = self.to_absolute_path_checked(path)?;
+        self.check_not_reserved(&abs_path, "write")?;
         let content = content.to_string();
 
         let exists = fs::try_exists(&abs_path).await.unwrap_or(false);
@@ -1,4 +1,5 @@
 use super::FileService;
+use mill_foundation::core::{cancellation, progress, write_scope};
 use mill_foundation::errors::MillError as ServerError;
 use mill_foundation::protocol::{
     DependencyUpdate, EditPlan, EditPlanMetadata, EditPlanResult, TextEdit,
@@ -16,6 +17,19 @@ use mill_ast::transformer;
 impl FileService {
     /// Apply an edit plan to the filesystem atomically
     pub async fn apply_edit_plan(&self, plan: &EditPlan) -> ServerResult<EditPlanResult> {
+        self.apply_plan(plan, true).await
+    }
+
+    /// Apply an edit plan, recording it in the operation journal if `journal`
+    /// is set
+    ///
+    /// Undo and redo apply their plans without journaling them, as they
+    /// update the journal themselves.
+    pub(super) async fn apply_plan(
+        &self,
+        plan: &EditPlan,
+        journal: bool,
+    ) -> ServerResult<EditPlanResult> {
         info!(source_file = %plan.source_file, "Applying edit plan");
         debug!(
             edits_count = plan.edits.len(),
@@ -38,9 +52,93 @@ impl FileService {
             );
         }
 
+        self.check_plan_targets(plan)?;
+        // Once writing starts, the request runs to completion even if cancelled
+        if !cancellation::begin_apply() {
+            return Err(ServerError::runtime(
+                "Request was cancelled before its changes were applied",
+            ));
+        }
+
+        // Capture pre-images for the operation journal once pending writes
+        // have landed
+        self.operation_queue.wait_until_idle().await;
+        let before = if journal {
+            self.capture_for_journal(plan).await
+        } else {
+            None
+        };
+
         // For simplicity, we'll apply edits sequentially with individual locks
         // In a production system, you might want more sophisticated coordination
-        self.apply_edits_with_coordination(plan).await
+        let result = self.apply_edits_with_coordination(plan).await?;
+
+        if let Some(before) = before {
+            self.record_in_journal(plan, before).await;
+        }
+        Ok(result)
+    }
+
+    /// Refuse a plan that would write TypeMill's own state or outside the
+    /// current write scope
+    ///
+    /// Runs before anything is written, so a refused plan changes nothing.
+    fn check_plan_targets(&self, plan: &EditPlan) -> ServerResult<()> {
+        use mill_foundation::protocol::EditType;
+
+        let moved_to = plan
+            .edits
+            .iter()
+            .filter(|edit| edit.edit_type == EditType::Move)
+            .map(|edit| edit.new_text.as_str());
+        let files = std::iter::once(plan.source_file.as_str())
+            .chain(
+                plan.edits
+                    .iter()
+                    .filter_map(|edit| edit.file_path.as_deref()),
+            )
+            .chain(moved_to)
+            .chain(
+                plan.dependency_updates
+                    .iter()
+                    .map(|u| u.target_file.as_str()),
+            )
+            .filter(|file| !file.is_empty());
+        for file in files {
+            let path = self.to_absolute_path_checked(Path::new(file))?;
+            if self.is_reserved(&path) {
+                return Err(ServerError::invalid_request(format!(
+                    "Plan would change '{}', which holds TypeMill's own state",
+                    file
+                )));
+            }
+            if !write_scope::permits(&path) {
+                return Err(ServerError::PermissionDenied {
+                    operation: format!(
+                        "plan would change '{}', outside the token's path scopes",
+                        file
+                    ),
+                    required_permission: Some(format!("path:{}", file)),
+                });
+            }
+        }
+
+        // Moving or deleting a directory takes everything inside along
+        let removed = plan
+            .edits
+            .iter()
+            .filter(|edit| matches!(edit.edit_type, EditType::Move | EditType::Delete))
+            .filter_map(|edit| edit.file_path.as_deref());
+        for file in removed {
+            let path = self.to_absolute_path_checked(Path::new(file))?;
+            if self.holds_reserved(&path) {
+                return Err(ServerError::invalid_request(format!(
+                    "Plan would move or delete '{}', which contains TypeMill's own state",
+                    file
+                )));
+            }
+        }
+        Ok(())
     }
 
     /// Apply edits with file coordination and atomic rollback on failure
@@ -397,12 +495,18 @@ impl FileService {
             "Step 4: Applying text edits"
         );
 
-        for (file_path, edits) in edits_by_file {
+        let files_total = edits_by_file.len() as u64;
+        for (files_done, (file_path, edits)) in edits_by_file.into_iter().enumerate() {
             debug!(
                 file_path = %file_path,
                 edits_count = edits.len(),
                 "Processing file edits"
             );
+            progress::report(
+                files_done as u64,
+                Some(files_total),
+                format!("Applying edits to {}", file_path),
+            );
 
             let abs_file_path = self.to_absolute_path_checked(Path::new(&file_path))?;
             let file_lock = self.lock_manager.get_lock(&abs_file_path).await;
@@ -541,6 +645,8 @@ impl FileService {
             // Guard is dropped here after each file
         }
 
+        progress::report(files_total, Some(files_total), "Applied edits");
+
         // Step 6: Invalidate AST cache for all modified files
         for file_path in &modified_files {
             let abs_path = self.to_absolute_path_checked(Path::new(file_path))?;
@@ -0,0 +1,261 @@
+//! Operation history: journaling applied edit plans, undo and redo
+
+use super::FileService;
+use crate::services::filesystem::journal::{
+    FileImages, JournalEntry, JournalOutcome, Replay, JOURNAL_DIR,
+};
+use mill_foundation::errors::MillError as ServerError;
+use mill_foundation::protocol::{EditLocation, EditPlan, EditPlanMetadata, EditType, TextEdit};
+use std::collections::BTreeSet;
+use std::path::Path;
+use tracing::warn;
+
+type ServerResult<T> = Result<T, ServerError>;
+
+impl FileService {
+    /// Capture the files an edit plan may touch, before it is applied
+    ///
+    /// Returns `None` (with a warning) if the plan cannot be journaled; the
+    /// plan is still applied, it just cannot be undone.
+    pub(super) async fn capture_for_journal(&self, plan: &EditPlan) -> Option<FileImages> {
+        if !self.journal.is_enabled() {
+            return None;
+        }
+        let paths = match self.journal_paths(plan) {
+            Ok(paths) => paths,
+            Err(e) => {
+                warn!(error = %e, "Cannot determine files to journal, operation will not be undoable");
+                return None;
+            }
+        };
+        self.journal.capture(&paths).await
+    }
+
+    /// Record an applied edit plan in the journal
+    pub(super) async fn record_in_journal(&self, plan: &EditPlan, before: FileImages) {
+        if let Err(e) = self
+            .journal
+            .record(&plan.metadata.intent_name, before)
+            .await
+        {
+            warn!(error = %e, "Failed to record operation in journal");
+        }
+    }
+
+    /// Project-relative paths of every file `plan` may create, change or remove
+    fn journal_paths(&self, plan: &EditPlan) -> ServerResult<BTreeSet<String>> {
+        let mut paths = BTreeSet::new();
+        if !plan.source_file.is_empty() {
+            self.add_journal_path(&mut paths, &plan.source_file)?;
+        }
+        for edit in &plan.edits {
+            if let Some(file_path) = &edit.file_path {
+                self.add_journal_path(&mut paths, file_path)?;
+            }
+            if edit.edit_type == EditType::Move {
+                self.add_journal_path(&mut paths, &edit.new_text)?;
+                // Files moved into the destination directory land at the
+                // same relative paths as in the source
+                if let Some(file_path) = &edit.file_path {
+                    let source = self.to_absolute_path_checked(Path::new(file_path))?;
+                    let destination = self.to_absolute_path_checked(Path::new(&edit.new_text))?;
+                    if source.is_dir() {
+                        for file in walk_files(&source) {
+                            if let Ok(relative) = file.strip_prefix(&source) {
+                                self.add_journal_path(
+                                    &mut paths,
+                                    &destination.join(relative).to_string_lossy(),
+                                )?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for dep_update in &plan.dependency_updates {
+            self.add_journal_path(&mut paths, &dep_update.target_file)?;
+        }
+        Ok(paths)
+    }
+
+    /// Add `path`, or every file under it if it is a directory
+    fn add_journal_path(&self, paths: &mut BTreeSet<String>, path: &str) -> ServerResult<()> {
+        let abs_path = self.to_absolute_path_checked(Path::new(path))?;
+        let files = if abs_path.is_dir() {
+            walk_files(&abs_path)
+        } else {
+            vec![abs_path]
+        };
+        for file in files {
+            let relative = file
+                .strip_prefix(&self.canonical_project_root)
+                .map_err(|_| {
+                    ServerError::invalid_request(format!(
+                        "Path {} is outside the project root",
+                        file.display()
+                    ))
+                })?
+                .to_string_lossy()
+                .replace('\\', "/");
+            if !relative.starts_with(JOURNAL_DIR) {
+                paths.insert(relative);
+            }
+        }
+        Ok(())
+    }
+
+    /// Operations recorded in the journal, newest first
+    pub async fn list_operations(&self) -> ServerResult<Vec<JournalEntry>> {
+        self.journal.list().await
+    }
+
+    /// Undo a journaled operation, the most recent one if `id` is `None`
+    ///
+    /// Refuses if a file changed since the operation, unless `merge` is set
+    /// and the later changes can be merged.
+    pub async fn undo_operation(
+        &self,
+        id: Option<u64>,
+        merge: bool,
+        dry_run: bool,
+    ) -> ServerResult<JournalOutcome> {
+        self.operation_queue.wait_until_idle().await;
+        let replay = self.journal.undo(id, merge).await?;
+        self.apply_replay(replay, "undo", dry_run).await
+    }
+
+    /// Redo an undone operation, the earliest undone one if `id` is `None`
+    pub async fn redo_operation(
+        &self,
+        id: Option<u64>,
+        merge: bool,
+        dry_run: bool,
+    ) -> ServerResult<JournalOutcome> {
+        self.operation_queue.wait_until_idle().await;
+        let replay = self.journal.redo(id, merge).await?;
+        self.apply_replay(replay, "redo", dry_run).await
+    }
+
+    /// Write an undo or redo as an edit plan, then record it in the journal
+    async fn apply_replay(
+        &self,
+        replay: Replay<'_>,
+        intent: &str,
+        dry_run: bool,
+    ) -> ServerResult<JournalOutcome> {
+        if dry_run {
+            return Ok(replay.outcome);
+        }
+        let plan = self.replay_plan(&replay, intent).await?;
+        self.apply_plan(&plan, false).await?;
+        for path in &replay.outcome.removed_files {
+            let abs_path = self.canonical_project_root.join(path);
+            self.ast_cache.invalidate(&abs_path);
+            self.remove_empty_parents(&abs_path).await;
+        }
+        self.journal.complete(replay).await
+    }
+
+    /// Edit plan writing the files of `replay`
+    ///
+    /// Existing files are replaced in full, missing ones created and files
+    /// absent from the restored state deleted.
+    async fn replay_plan(&self, replay: &Replay<'_>, intent: &str) -> ServerResult<EditPlan> {
+        let mut edits = Vec::new();
+        for (path, content) in &replay.writes {
+            let abs_path = self.canonical_project_root.join(path);
+            let file_path = Some(abs_path.to_string_lossy().into_owned());
+            let Some(content) = content else {
+                edits.push(TextEdit {
+                    file_path,
+                    edit_type: EditType::Delete,
+                    location: EditLocation {
+                        start_line: 0,
+                        start_column: 0,
+                        end_line: 0,
+                        end_column: 0,
+                    },
+                    original_text: String::new(),
+                    new_text: String::new(),
+                    priority: 0,
+                    description: format!("Remove {}", path),
+                });
+                continue;
+            };
+            let new_text = String::from_utf8(content.clone()).map_err(|_| {
+                ServerError::invalid_request(format!("Cannot restore binary file {}", path))
+            })?;
+            let (edit_type, location, original_text) =
+                match tokio::fs::read_to_string(&abs_path).await {
+                    Ok(current) => (
+                        EditType::Replace,
+                        EditLocation::whole_file(&current),
+                        current,
+                    ),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => (
+                        EditType::Create,
+                        EditLocation {
+                            start_line: 0,
+                            start_column: 0,
+                            end_line: 0,
+                            end_column: 0,
+                        },
+                        String::new(),
+                    ),
+                    Err(e) => {
+                        return Err(ServerError::internal(format!(
+                            "Failed to read {}: {}",
+                            path, e
+                        )))
+                    }
+                };
+            edits.push(TextEdit {
+                file_path,
+                edit_type,
+                location,
+                original_text,
+                new_text,
+                priority: 0,
+                description: format!("Restore {}", path),
+            });
+        }
+
+        Ok(EditPlan {
+            source_file: String::new(),
+            edits,
+            dependency_updates: Vec::new(),
+            validations: Vec::new(),
+            metadata: EditPlanMetadata {
+                intent_name: intent.to_string(),
+                intent_arguments: serde_json::json!({
+                    "operationId": replay.outcome.entry.id
+                }),
+                created_at: chrono::Utc::now(),
+                complexity: 1,
+                impact_areas: Vec::new(),
+                consolidation: None,
+            },
+        })
+    }
+
+    /// Remove directories left empty by deleting `path`, up to the project root
+    async fn remove_empty_parents(&self, path: &Path) {
+        let mut dir = path.parent();
+        while let Some(parent) = dir.filter(|d| *d != self.canonical_project_root) {
+            if tokio::fs::remove_dir(parent).await.is_err() {
+                break;
+            }
+            dir = parent.parent();
+        }
+    }
+}
+
+/// Files under `dir`, skipping those the project ignores
+fn walk_files(dir: &Path) -> Vec<std::path::PathBuf> {
+    ignore::WalkBuilder::new(dir)
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
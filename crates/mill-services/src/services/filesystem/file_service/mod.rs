@@ -3,6 +3,7 @@
 // Module declarations
 mod basic_ops;
 mod edit_plan;
+mod history;
 mod rename;
 mod utils;
 
@@ -22,6 +23,7 @@ pub use mill_foundation::protocol::EditPlanResult;
 use crate::services::coordination::lock_manager::LockManager;
 use crate::services::coordination::operation_queue::OperationQueue;
 use crate::services::filesystem::git_service::GitService;
+use crate::services::filesystem::journal::{OperationJournal, JOURNAL_DIR};
 use crate::services::move_service::MoveService;
 use crate::services::reference_updater::ReferenceUpdater;
 use mill_ast::AstCache;
@@ -54,6 +56,11 @@ pub struct FileService {
     pub(super) use_git: bool,
     /// Validation configuration
     pub(super) validation_config: ValidationConfig,
+    /// Journal of applied edit plans, for undo and redo
+    pub(super) journal: OperationJournal,
+    /// TypeMill's own state (the journal and the audit log), which no
+    /// operation may change
+    pub(super) reserved_paths: Vec<PathBuf>,
 }
 
 impl FileService {
@@ -94,6 +101,12 @@ impl FileService {
             "Initializing FileService with git support and injected plugin registry"
         );
 
+        let journal = OperationJournal::new(&canonical_project_root, &config.journal);
+        let reserved_paths = vec![
+            canonical_project_root.join(JOURNAL_DIR),
+            canonical_project_root.join(&config.audit.path),
+        ];
+
         Self {
             reference_updater: ReferenceUpdater::new(&project_root),
             plugin_registry,
@@ -105,9 +118,16 @@ impl FileService {
             git_service: GitService::new(),
             use_git,
             validation_config: config.validation.clone(),
+            journal,
+            reserved_paths,
         }
     }
 
+    /// AST cache shared with the AST service
+    pub fn ast_cache(&self) -> &Arc<AstCache> {
+        &self.ast_cache
+    }
+
     /// Create a MoveService for unified move/rename planning
     ///
     /// The MoveService provides the single source of truth for all move and rename operations.
@@ -88,6 +88,8 @@ impl FileService {
 
         let old_abs = self.to_absolute_path_checked(old_path)?;
         let new_abs = self.to_absolute_path_checked(new_path)?;
+        self.check_not_reserved(&old_abs, "rename")?;
+        self.check_not_reserved(&new_abs, "overwrite")?;
 
         if dry_run {
             return self
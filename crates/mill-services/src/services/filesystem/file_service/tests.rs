@@ -513,6 +513,224 @@ pub(super) mod tests {
             "Third dependency file should remain unchanged"
         );
     }
+    #[tokio::test]
+    async fn test_edit_plan_refused_outside_write_scope() {
+        use mill_foundation::core::write_scope::WriteScope;
+        use mill_foundation::protocol::{DependencyUpdateType, EditLocation, EditType};
+
+        let temp_dir = TempDir::new().unwrap();
+        let (service, _queue) = create_test_service(&temp_dir);
+
+        let main_file = "src/main.ts";
+        let dep_file = "lib/dependency.ts";
+        let main_original = "import { foo } from '../lib/old';\nconst x = 1;";
+        let dep_original = "import './old';\nconst y = 2;";
+        for (file, content) in [(main_file, main_original), (dep_file, dep_original)] {
+            service
+                .create_file(Path::new(file), Some(content), false, false)
+                .await
+                .unwrap();
+        }
+
+        let plan = EditPlan {
+            source_file: main_file.to_string(),
+            edits: vec![TextEdit {
+                file_path: None,
+                edit_type: EditType::Replace,
+                location: EditLocation {
+                    start_line: 1,
+                    start_column: 0,
+                    end_line: 1,
+                    end_column: 12,
+                },
+                original_text: "const x = 1;".to_string(),
+                new_text: "const x = 2;".to_string(),
+                priority: 1,
+                description: "Update value".to_string(),
+            }],
+            dependency_updates: vec![DependencyUpdate {
+                target_file: dep_file.to_string(),
+                update_type: DependencyUpdateType::ImportPath,
+                old_reference: "./old".to_string(),
+                new_reference: "./new".to_string(),
+            }],
+            validations: vec![],
+            metadata: EditPlanMetadata {
+                intent_name: "test".to_string(),
+                intent_arguments: serde_json::json!({}),
+                created_at: chrono::Utc::now(),
+                complexity: 1,
+                impact_areas: vec!["test".to_string()],
+                consolidation: None,
+            },
+        };
+
+        // The dependency lies outside the scope, so nothing is written
+        let src = temp_dir.path().canonicalize().unwrap().join("src");
+        let scope = WriteScope::new(move |path| path.starts_with(&src));
+        let result = scope.scope(service.apply_edit_plan(&plan)).await;
+        assert!(matches!(result, Err(MillError::PermissionDenied { .. })));
+
+        let main_content = service.read_file(Path::new(main_file)).await.unwrap();
+        assert_eq!(main_content, main_original);
+        let dep_content = service.read_file(Path::new(dep_file)).await.unwrap();
+        assert_eq!(dep_content, dep_original);
+    }
+
+    #[tokio::test]
+    async fn test_edit_plan_refused_for_typemill_state() {
+        use mill_foundation::protocol::{EditLocation, EditType};
+
+        let temp_dir = TempDir::new().unwrap();
+        let (service, _queue) = create_test_service(&temp_dir);
+        let root = temp_dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join(".typemill")).unwrap();
+        std::fs::write(root.join(".typemill/audit.jsonl"), "{}\n").unwrap();
+
+        let plan = |edit_type, file: &Path, new_text: &str| EditPlan {
+            source_file: String::new(),
+            edits: vec![TextEdit {
+                file_path: Some(file.to_string_lossy().into_owned()),
+                edit_type,
+                location: EditLocation {
+                    start_line: 0,
+                    start_column: 0,
+                    end_line: 0,
+                    end_column: 0,
+                },
+                original_text: String::new(),
+                new_text: new_text.to_string(),
+                priority: 1,
+                description: "test".to_string(),
+            }],
+            dependency_updates: vec![],
+            validations: vec![],
+            metadata: EditPlanMetadata {
+                intent_name: "test".to_string(),
+                intent_arguments: serde_json::json!({}),
+                created_at: chrono::Utc::now(),
+                complexity: 1,
+                impact_areas: vec![],
+                consolidation: None,
+            },
+        };
+
+        let audit_log = root.join(".typemill/audit.jsonl");
+        let result = service
+            .apply_edit_plan(&plan(EditType::Create, &audit_log, ""))
+            .await;
+        assert!(matches!(result, Err(MillError::InvalidRequest { .. })));
+        let result = service
+            .apply_edit_plan(&plan(EditType::Delete, &root, ""))
+            .await;
+        assert!(matches!(result, Err(MillError::InvalidRequest { .. })));
+        let rotated = root.join(".typemill/audit.jsonl.1");
+        let result = service
+            .apply_edit_plan(&plan(EditType::Create, &rotated, ""))
+            .await;
+        assert!(matches!(result, Err(MillError::InvalidRequest { .. })));
+        assert!(!rotated.exists());
+
+        let result = service.write_file(&audit_log, "", false).await;
+        assert!(matches!(result, Err(MillError::InvalidRequest { .. })));
+        let result = service.delete_file(&audit_log, true, false).await;
+        assert!(matches!(result, Err(MillError::InvalidRequest { .. })));
+        let result = service
+            .create_file(
+                &root.join(".typemill/journal/x.json"),
+                Some(""),
+                true,
+                false,
+            )
+            .await;
+        assert!(matches!(result, Err(MillError::InvalidRequest { .. })));
+        assert_eq!(std::fs::read_to_string(&audit_log).unwrap(), "{}\n");
+
+        let result = service
+            .apply_edit_plan(&plan(EditType::Create, &root.join("notes.txt"), "ok"))
+            .await;
+        assert!(result.is_ok());
+        let config = root.join(".typemill/config.toml");
+        let result = service.write_file(&config, "[server]\n", false).await;
+        assert!(result.is_ok());
+        assert_eq!(std::fs::read_to_string(&config).unwrap(), "[server]\n");
+    }
+
+    #[tokio::test]
+    async fn test_undo_and_redo_apply_edit_plans() {
+        use crate::services::filesystem::journal::OperationStatus;
+        use mill_foundation::core::write_scope::WriteScope;
+        use mill_foundation::protocol::{EditLocation, EditType};
+
+        let temp_dir = TempDir::new().unwrap();
+        let (service, _queue) = create_test_service(&temp_dir);
+        let root = temp_dir.path().canonicalize().unwrap();
+        let original = "one\ntwo\n";
+        std::fs::write(root.join("a.txt"), original).unwrap();
+        let created = root.join("gen/new.txt");
+
+        let edit = |edit_type, file: &Path, line, text: &str| TextEdit {
+            file_path: Some(file.to_string_lossy().into_owned()),
+            edit_type,
+            location: EditLocation {
+                start_line: line,
+                start_column: 0,
+                end_line: line,
+                end_column: 3,
+            },
+            original_text: String::new(),
+            new_text: text.to_string(),
+            priority: 1,
+            description: "test".to_string(),
+        };
+        let plan = EditPlan {
+            source_file: String::new(),
+            edits: vec![
+                edit(EditType::Replace, &root.join("a.txt"), 1, "2"),
+                edit(EditType::Create, &created, 0, "new\n"),
+            ],
+            dependency_updates: vec![],
+            validations: vec![],
+            metadata: EditPlanMetadata {
+                intent_name: "test".to_string(),
+                intent_arguments: serde_json::json!({}),
+                created_at: chrono::Utc::now(),
+                complexity: 1,
+                impact_areas: vec![],
+                consolidation: None,
+            },
+        };
+        service.apply_edit_plan(&plan).await.unwrap();
+        let changed = std::fs::read_to_string(root.join("a.txt")).unwrap();
+        assert_eq!(changed, "one\n2\n");
+
+        // Undo writes through the edit plan path, so scopes apply to it
+        let gen = root.join("gen");
+        let scope = WriteScope::new(move |path| path.starts_with(&gen));
+        let result = scope.scope(service.undo_operation(None, false, false)).await;
+        assert!(matches!(result, Err(MillError::PermissionDenied { .. })));
+        assert!(created.exists());
+
+        let outcome = service.undo_operation(None, false, false).await.unwrap();
+        assert_eq!(outcome.removed_files, vec!["gen/new.txt"]);
+        assert_eq!(
+            std::fs::read_to_string(root.join("a.txt")).unwrap(),
+            original
+        );
+        assert!(!root.join("gen").exists());
+
+        // Replays are not journaled as operations of their own
+        let outcome = service.redo_operation(None, false, false).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(root.join("a.txt")).unwrap(),
+            changed
+        );
+        assert_eq!(std::fs::read_to_string(&created).unwrap(), "new\n");
+        let operations = service.list_operations().await.unwrap();
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].id, outcome.entry.id);
+        assert_eq!(operations[0].status, OperationStatus::Applied);
+    }
 }
 
 #[cfg(test)]
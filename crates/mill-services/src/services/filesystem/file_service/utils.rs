@@ -193,6 +193,44 @@ impl FileService {
         }
     }
 
+    /// Whether an absolute path is part of TypeMill's own state: the journal,
+    /// or the audit log and its rotations (`audit.jsonl.1`, ...)
+    pub(super) fn is_reserved(&self, path: &Path) -> bool {
+        self.reserved_paths.iter().any(|reserved| {
+            let rotation = path.parent() == reserved.parent()
+                && path
+                    .file_name()
+                    .zip(reserved.file_name())
+                    .and_then(|(name, reserved)| {
+                        name.to_str()?
+                            .strip_prefix(reserved.to_str()?)?
+                            .strip_prefix('.')
+                            .map(|n| n.parse::<u32>().is_ok())
+                    })
+                    .unwrap_or(false);
+            path.starts_with(reserved) || rotation
+        })
+    }
+
+    /// Whether moving or deleting an absolute path takes TypeMill's own state along
+    pub(super) fn holds_reserved(&self, path: &Path) -> bool {
+        self.reserved_paths
+            .iter()
+            .any(|reserved| reserved.starts_with(path))
+    }
+
+    /// Refuse to create, overwrite or remove TypeMill's own state
+    pub(super) fn check_not_reserved(&self, path: &Path, operation: &str) -> ServerResult<()> {
+        if self.is_reserved(path) || self.holds_reserved(path) {
+            return Err(ServerError::invalid_request(format!(
+                "Cannot {} '{}', which holds TypeMill's own state",
+                operation,
+                path.display()
+            )));
+        }
+        Ok(())
+    }
+
     /// Convert path to absolute and verify it's within project root
     ///
     /// This performs canonicalization and containment checking to prevent
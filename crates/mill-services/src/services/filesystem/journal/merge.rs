@@ -0,0 +1,157 @@
+//! Line-based three-way merge
+//!
+//! Used when undoing or redoing an operation on a file that was edited after
+//! the operation: the journal image the file was expected to match is the
+//! base, the current content is "ours" and the image being restored is
+//! "theirs".
+
+/// A replacement of `base[start..end]` by `lines`
+#[derive(Debug)]
+struct Change<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a str>,
+}
+
+/// Merge the changes from `base` to `ours` and from `base` to `theirs`
+///
+/// Returns `None` when both sides change the same lines differently.
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> Option<String> {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+    let ours_changes = changes(&base_lines, &ours_lines);
+    let theirs_changes = changes(&base_lines, &theirs_lines);
+
+    let mut merged = String::with_capacity(ours.len().max(theirs.len()));
+    let mut pos = 0;
+    let (mut i, mut j) = (0, 0);
+    while i < ours_changes.len() || j < theirs_changes.len() {
+        // A region starts at the earliest pending change and grows while
+        // changes from either side overlap it
+        let start = match (ours_changes.get(i), theirs_changes.get(j)) {
+            (Some(a), Some(b)) => a.start.min(b.start),
+            (Some(a), None) => a.start,
+            (None, Some(b)) => b.start,
+            (None, None) => break,
+        };
+        let mut end = start;
+        let (first_ours, first_theirs) = (i, j);
+        loop {
+            let mut grew = false;
+            while let Some(change) = ours_changes.get(i).filter(|c| overlaps(c, start, end)) {
+                end = end.max(change.end);
+                i += 1;
+                grew = true;
+            }
+            while let Some(change) = theirs_changes.get(j).filter(|c| overlaps(c, start, end)) {
+                end = end.max(change.end);
+                j += 1;
+                grew = true;
+            }
+            if !grew {
+                break;
+            }
+        }
+
+        merged.extend(base_lines[pos..start].iter().copied());
+        let ours_region = &ours_changes[first_ours..i];
+        let theirs_region = &theirs_changes[first_theirs..j];
+        let region = if theirs_region.is_empty() {
+            apply(&base_lines, start, end, ours_region)
+        } else if ours_region.is_empty() {
+            apply(&base_lines, start, end, theirs_region)
+        } else {
+            let ours_text = apply(&base_lines, start, end, ours_region);
+            let theirs_text = apply(&base_lines, start, end, theirs_region);
+            if ours_text != theirs_text {
+                return None;
+            }
+            ours_text
+        };
+        merged.push_str(&region);
+        pos = end;
+    }
+    merged.extend(base_lines[pos..].iter().copied());
+    Some(merged)
+}
+
+fn overlaps(change: &Change<'_>, start: usize, end: usize) -> bool {
+    change.start < end || change.start == start
+}
+
+/// The edits turning `base` into `other`, in order
+fn changes<'a>(base: &[&'a str], other: &[&'a str]) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    let mut current: Option<Change<'a>> = None;
+    let mut pos = 0;
+    for result in diff::slice(base, other) {
+        match result {
+            diff::Result::Both(..) => {
+                changes.extend(current.take());
+                pos += 1;
+            }
+            diff::Result::Left(_) => {
+                pos += 1;
+                current
+                    .get_or_insert(Change {
+                        start: pos - 1,
+                        end: pos - 1,
+                        lines: Vec::new(),
+                    })
+                    .end = pos;
+            }
+            diff::Result::Right(line) => {
+                current
+                    .get_or_insert(Change {
+                        start: pos,
+                        end: pos,
+                        lines: Vec::new(),
+                    })
+                    .lines
+                    .push(line);
+            }
+        }
+    }
+    changes.extend(current);
+    changes
+}
+
+/// `base[start..end]` with `changes` applied
+fn apply(base: &[&str], start: usize, end: usize, changes: &[Change<'_>]) -> String {
+    let mut text = String::new();
+    let mut pos = start;
+    for change in changes {
+        text.extend(base[pos..change.start].iter().copied());
+        text.extend(change.lines.iter().copied());
+        pos = change.end;
+    }
+    text.extend(base[pos..end].iter().copied());
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merges_changes_to_different_lines() {
+        let base = "a\nb\nc\nd\n";
+        let ours = "a\nB\nc\nd\n";
+        let theirs = "a\nb\nc\nD\ne\n";
+        assert_eq!(
+            merge3(base, ours, theirs).as_deref(),
+            Some("a\nB\nc\nD\ne\n")
+        );
+    }
+
+    #[test]
+    fn test_identical_changes_merge_and_conflicts_do_not() {
+        let base = "a\nb\nc\n";
+        assert_eq!(
+            merge3(base, "a\nx\nc\n", "a\nx\nc\n").as_deref(),
+            Some("a\nx\nc\n")
+        );
+        assert_eq!(merge3(base, "a\nx\nc\n", "a\ny\nc\n"), None);
+    }
+}
@@ -0,0 +1,621 @@
+//! Persistent journal of applied edit plans
+//!
+//! Every edit plan applied by [`FileService`](super::file_service::FileService)
+//! is recorded under `.typemill/journal/` with the content of each file it
+//! touched before and after, stored once per checksum in `blobs/`. Undo
+//! restores the before-images and redo the after-images, but only while the
+//! files still match the images they are expected to have; otherwise the
+//! operation is refused, or with `merge` text files are merged three ways.
+//!
+//! The journal only works out what an undo or redo must write; the file
+//! service applies it as an edit plan, like any other change.
+//!
+//! Undone operations can be redone until a new operation is recorded, which
+//! discards them, as in an editor.
+
+mod merge;
+
+pub use merge::merge3;
+
+use crate::services::validation::checksum::ChecksumValidator;
+use mill_config::config::JournalConfig;
+use mill_foundation::errors::MillError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::{debug, info, warn};
+
+type ServerResult<T> = Result<T, MillError>;
+
+/// Journal directory, relative to the project root
+pub const JOURNAL_DIR: &str = ".typemill/journal";
+
+/// Number of operations kept; older ones are pruned
+const MAX_ENTRIES: usize = 100;
+
+/// Operations touching a larger file are not journaled
+const MAX_FILE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Whether an operation is currently in effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OperationStatus {
+    Applied,
+    Undone,
+}
+
+/// One file touched by an operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChange {
+    /// Project-relative path, with `/` separators
+    pub path: String,
+    /// Checksum of the content before the operation, `None` if it did not exist
+    pub before: Option<String>,
+    /// Checksum of the content after the operation, `None` if it was deleted
+    pub after: Option<String>,
+}
+
+/// A recorded operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    /// Sequence number, increasing with each operation
+    pub id: u64,
+    /// Intent that produced the edit plan (e.g. `rename_all`)
+    pub intent: String,
+    /// When the operation was applied (RFC 3339)
+    pub applied_at: String,
+    pub status: OperationStatus,
+    pub files: Vec<FileChange>,
+}
+
+/// Result of undoing or redoing an operation
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalOutcome {
+    pub entry: JournalEntry,
+    /// Files restored to the journal image
+    pub restored_files: Vec<String>,
+    /// Files that had changed since and were merged with the journal image
+    pub merged_files: Vec<String>,
+    /// Files removed because they did not exist in the restored state
+    pub removed_files: Vec<String>,
+}
+
+/// Contents of a set of project files, `None` for missing files
+#[derive(Debug, Default)]
+pub struct FileImages(BTreeMap<String, Option<Vec<u8>>>);
+
+/// An undo or redo worked out but not yet applied
+///
+/// Holds the journal's lock until it is passed to
+/// [`OperationJournal::complete`] or dropped.
+pub struct Replay<'a> {
+    pub outcome: JournalOutcome,
+    /// New content of each file to change, `None` to remove it
+    pub writes: Vec<(String, Option<Vec<u8>>)>,
+    _guard: MutexGuard<'a, ()>,
+}
+
+/// Which way an operation is being replayed
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Undo,
+    Redo,
+}
+
+/// The on-disk operation journal of a project
+pub struct OperationJournal {
+    project_root: PathBuf,
+    dir: PathBuf,
+    config: JournalConfig,
+    /// Serializes journal updates within this process
+    lock: Mutex<()>,
+}
+
+impl OperationJournal {
+    pub fn new(project_root: impl AsRef<Path>, config: &JournalConfig) -> Self {
+        let project_root = project_root.as_ref().to_path_buf();
+        Self {
+            dir: project_root.join(JOURNAL_DIR),
+            project_root,
+            config: config.clone(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Whether applied operations are recorded
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Read the current content of `paths` (project-relative)
+    ///
+    /// Returns `None` if the files are too large or not text, since
+    /// undo restores them through text edits.
+    pub async fn capture(&self, paths: &BTreeSet<String>) -> Option<FileImages> {
+        let mut images = BTreeMap::new();
+        let mut total_bytes = 0u64;
+        for path in paths {
+            if path.starts_with(JOURNAL_DIR) {
+                continue;
+            }
+            let content = read_optional(&self.project_root.join(path)).await;
+            if let Some(content) = &content {
+                if content.len() > MAX_FILE_BYTES {
+                    warn!(path = %path, "File too large to journal, operation will not be undoable");
+                    return None;
+                }
+                if std::str::from_utf8(content).is_err() {
+                    warn!(path = %path, "Cannot journal binary file, operation will not be undoable");
+                    return None;
+                }
+                total_bytes += content.len() as u64;
+                if total_bytes > self.config.max_operation_bytes {
+                    warn!(
+                        files = paths.len(),
+                        max_operation_bytes = self.config.max_operation_bytes,
+                        "Operation too large to journal, it will not be undoable"
+                    );
+                    return None;
+                }
+            }
+            images.insert(path.clone(), content);
+        }
+        Some(FileImages(images))
+    }
+
+    /// Record an operation whose files were captured in `before`
+    ///
+    /// Returns `None` if the operation changed nothing.
+    pub async fn record(
+        &self,
+        intent: &str,
+        before: FileImages,
+    ) -> ServerResult<Option<JournalEntry>> {
+        let _guard = self.lock.lock().await;
+
+        let mut files = Vec::new();
+        for (path, before) in before.0 {
+            let after = read_optional(&self.project_root.join(&path)).await;
+            if before == after {
+                continue;
+            }
+            files.push(FileChange {
+                path,
+                before: self.store_blob(before.as_deref()).await?,
+                after: self.store_blob(after.as_deref()).await?,
+            });
+        }
+        if files.is_empty() {
+            return Ok(None);
+        }
+
+        // A new operation ends the redo history
+        let mut entries = self.read_entries().await?;
+        for entry in entries
+            .iter()
+            .filter(|e| e.status == OperationStatus::Undone)
+        {
+            self.remove_entry(entry.id).await;
+        }
+        entries.retain(|e| e.status == OperationStatus::Applied);
+
+        let entry = JournalEntry {
+            id: entries.iter().map(|e| e.id).max().unwrap_or(0) + 1,
+            intent: intent.to_string(),
+            applied_at: chrono::Utc::now().to_rfc3339(),
+            status: OperationStatus::Applied,
+            files,
+        };
+        self.write_entry(&entry).await?;
+        info!(
+            operation_id = entry.id,
+            intent = %entry.intent,
+            files = entry.files.len(),
+            "Recorded operation in journal"
+        );
+
+        entries.push(entry.clone());
+        self.prune(entries).await;
+        Ok(Some(entry))
+    }
+
+    /// Recorded operations, newest first
+    pub async fn list(&self) -> ServerResult<Vec<JournalEntry>> {
+        let mut entries = self.read_entries().await?;
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Work out how to undo operation `id`, or the most recent applied
+    /// operation
+    pub async fn undo(&self, id: Option<u64>, merge: bool) -> ServerResult<Replay<'_>> {
+        self.replay(Direction::Undo, id, merge).await
+    }
+
+    /// Work out how to redo operation `id`, or the most recently undone
+    /// operation
+    pub async fn redo(&self, id: Option<u64>, merge: bool) -> ServerResult<Replay<'_>> {
+        self.replay(Direction::Redo, id, merge).await
+    }
+
+    /// Record that the writes of `replay` have been applied
+    pub async fn complete(&self, replay: Replay<'_>) -> ServerResult<JournalOutcome> {
+        let entry = &replay.outcome.entry;
+        self.write_entry(entry).await?;
+        info!(operation_id = entry.id, status = ?entry.status, "Replayed journaled operation");
+        Ok(replay.outcome)
+    }
+
+    async fn replay(
+        &self,
+        direction: Direction,
+        id: Option<u64>,
+        merge: bool,
+    ) -> ServerResult<Replay<'_>> {
+        let guard = self.lock.lock().await;
+
+        let (from, to) = match direction {
+            Direction::Undo => (OperationStatus::Applied, OperationStatus::Undone),
+            Direction::Redo => (OperationStatus::Undone, OperationStatus::Applied),
+        };
+        let entries = self.read_entries().await?;
+        let mut entry = match id {
+            Some(id) => entries
+                .into_iter()
+                .find(|e| e.id == id)
+                .ok_or_else(|| MillError::not_found(format!("Operation {}", id)))?,
+            None => {
+                let candidates = entries.into_iter().filter(|e| e.status == from);
+                match direction {
+                    Direction::Undo => candidates.max_by_key(|e| e.id),
+                    Direction::Redo => candidates.min_by_key(|e| e.id),
+                }
+                .ok_or_else(|| {
+                    MillError::invalid_request(match direction {
+                        Direction::Undo => "No operation to undo",
+                        Direction::Redo => "No undone operation to redo",
+                    })
+                })?
+            }
+        };
+        if entry.status != from {
+            return Err(MillError::invalid_request(format!(
+                "Operation {} is already {}",
+                entry.id,
+                match to {
+                    OperationStatus::Applied => "applied",
+                    OperationStatus::Undone => "undone",
+                }
+            )));
+        }
+
+        // Work out every file's new content before touching any of them
+        let mut writes: Vec<(String, Option<Vec<u8>>)> = Vec::new();
+        let mut outcome = JournalOutcome {
+            entry: entry.clone(),
+            restored_files: Vec::new(),
+            merged_files: Vec::new(),
+            removed_files: Vec::new(),
+        };
+        let mut conflicts = Vec::new();
+        for file in &entry.files {
+            let (expected, target) = match direction {
+                Direction::Undo => (&file.after, &file.before),
+                Direction::Redo => (&file.before, &file.after),
+            };
+            let current = read_optional(&self.project_root.join(&file.path)).await;
+            let current_checksum = current
+                .as_deref()
+                .map(ChecksumValidator::calculate_bytes_checksum);
+
+            if &current_checksum == target {
+                continue;
+            }
+            let target_content = self.load_blob(target.as_deref()).await?;
+            if &current_checksum == expected {
+                writes.push((file.path.clone(), target_content));
+                continue;
+            }
+
+            // Changed since the operation: merge text files if asked to
+            let base = self.load_blob(expected.as_deref()).await?;
+            let merged = match (merge, &base, &current, &target_content) {
+                (true, Some(base), Some(current), Some(target)) => {
+                    match (
+                        std::str::from_utf8(base),
+                        std::str::from_utf8(current),
+                        std::str::from_utf8(target),
+                    ) {
+                        (Ok(base), Ok(current), Ok(target)) => merge3(base, current, target),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            match merged {
+                Some(merged) => {
+                    outcome.merged_files.push(file.path.clone());
+                    writes.push((file.path.clone(), Some(merged.into_bytes())));
+                }
+                None => conflicts.push(file.path.clone()),
+            }
+        }
+
+        if !conflicts.is_empty() {
+            let hint = if merge {
+                "The changes since overlap the operation's own edits"
+            } else {
+                "Retry with merge to combine them with the operation's edits"
+            };
+            return Err(MillError::invalid_request(format!(
+                "Cannot {} operation {}: files changed since it was {}: {}. {}.",
+                match direction {
+                    Direction::Undo => "undo",
+                    Direction::Redo => "redo",
+                },
+                entry.id,
+                match direction {
+                    Direction::Undo => "applied",
+                    Direction::Redo => "undone",
+                },
+                conflicts.join(", "),
+                hint
+            )));
+        }
+
+        for (path, content) in &writes {
+            match content {
+                Some(_) if outcome.merged_files.contains(path) => {}
+                Some(_) => outcome.restored_files.push(path.clone()),
+                None => outcome.removed_files.push(path.clone()),
+            }
+        }
+
+        entry.status = to;
+        outcome.entry = entry;
+        Ok(Replay {
+            outcome,
+            writes,
+            _guard: guard,
+        })
+    }
+
+    /// Store `content` under its checksum, returning the checksum
+    async fn store_blob(&self, content: Option<&[u8]>) -> ServerResult<Option<String>> {
+        let Some(content) = content else {
+            return Ok(None);
+        };
+        let checksum = ChecksumValidator::calculate_bytes_checksum(content);
+        let path = self.dir.join("blobs").join(&checksum);
+        if !path.exists() {
+            write_atomically(&path, content).await?;
+        }
+        Ok(Some(checksum))
+    }
+
+    async fn load_blob(&self, checksum: Option<&str>) -> ServerResult<Option<Vec<u8>>> {
+        let Some(checksum) = checksum else {
+            return Ok(None);
+        };
+        fs::read(self.dir.join("blobs").join(checksum))
+            .await
+            .map(Some)
+            .map_err(|e| {
+                MillError::internal(format!("Journal content {} is missing: {}", checksum, e))
+            })
+    }
+
+    /// All entries, oldest first
+    async fn read_entries(&self) -> ServerResult<Vec<JournalEntry>> {
+        let mut dir = match fs::read_dir(&self.dir).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(MillError::internal(format!(
+                    "Failed to read journal directory: {}",
+                    e
+                )))
+            }
+        };
+        let mut entries = Vec::new();
+        while let Ok(Some(item)) = dir.next_entry().await {
+            let path = item.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match fs::read(&path)
+                .await
+                .map(|bytes| serde_json::from_slice(&bytes))
+            {
+                Ok(Ok(entry)) => entries.push(entry),
+                Ok(Err(e)) => {
+                    warn!(path = %path.display(), error = %e, "Skipping unreadable journal entry")
+                }
+                Err(e) => warn!(path = %path.display(), error = %e, "Failed to read journal entry"),
+            }
+        }
+        entries.sort_by_key(|e: &JournalEntry| e.id);
+        Ok(entries)
+    }
+
+    fn entry_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{:06}.json", id))
+    }
+
+    async fn write_entry(&self, entry: &JournalEntry) -> ServerResult<()> {
+        let json = serde_json::to_vec_pretty(entry)?;
+        write_atomically(&self.entry_path(entry.id), &json).await
+    }
+
+    async fn remove_entry(&self, id: u64) {
+        if let Err(e) = fs::remove_file(self.entry_path(id)).await {
+            warn!(operation_id = id, error = %e, "Failed to remove journal entry");
+        }
+    }
+
+    /// Drop the oldest entries beyond `MAX_ENTRIES` and unreferenced blobs
+    async fn prune(&self, mut entries: Vec<JournalEntry>) {
+        if entries.len() > MAX_ENTRIES {
+            let excess = entries.len() - MAX_ENTRIES;
+            for entry in entries.drain(..excess) {
+                self.remove_entry(entry.id).await;
+            }
+        }
+
+        let referenced: HashSet<&str> = entries
+            .iter()
+            .flat_map(|e| &e.files)
+            .flat_map(|f| [f.before.as_deref(), f.after.as_deref()])
+            .flatten()
+            .collect();
+        let Ok(mut blobs) = fs::read_dir(self.dir.join("blobs")).await else {
+            return;
+        };
+        while let Ok(Some(blob)) = blobs.next_entry().await {
+            let name = blob.file_name();
+            if !referenced.contains(name.to_string_lossy().as_ref()) {
+                debug!(blob = ?name, "Removing unreferenced journal content");
+                let _ = fs::remove_file(blob.path()).await;
+            }
+        }
+    }
+}
+
+async fn read_optional(path: &Path) -> Option<Vec<u8>> {
+    fs::read(path).await.ok()
+}
+
+async fn write_atomically(path: &Path, content: &[u8]) -> ServerResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content).await?;
+    fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn record_edit(journal: &OperationJournal, root: &Path, path: &str, content: &str) {
+        let paths = BTreeSet::from([path.to_string()]);
+        let before = journal.capture(&paths).await.unwrap();
+        std::fs::write(root.join(path), content).unwrap();
+        journal.record("test", before).await.unwrap().unwrap();
+    }
+
+    /// Write the files of a replay, as the file service would
+    async fn apply(
+        journal: &OperationJournal,
+        replay: ServerResult<Replay<'_>>,
+    ) -> ServerResult<JournalOutcome> {
+        let replay = replay?;
+        for (path, content) in &replay.writes {
+            let path = journal.project_root.join(path);
+            match content {
+                Some(content) => std::fs::write(path, content).unwrap(),
+                None => std::fs::remove_file(path).unwrap(),
+            }
+        }
+        journal.complete(replay).await
+    }
+
+    #[tokio::test]
+    async fn test_undo_and_redo_restore_images() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("a.txt"), "one\n").unwrap();
+        let journal = OperationJournal::new(root, &JournalConfig::default());
+
+        record_edit(&journal, root, "a.txt", "two\n").await;
+        let paths = BTreeSet::from(["new.txt".to_string()]);
+        let before = journal.capture(&paths).await.unwrap();
+        std::fs::write(root.join("new.txt"), "created\n").unwrap();
+        journal.record("create", before).await.unwrap();
+
+        let outcome = apply(&journal, journal.undo(None, false).await)
+            .await
+            .unwrap();
+        assert_eq!(outcome.entry.id, 2);
+        assert_eq!(outcome.removed_files, vec!["new.txt"]);
+        assert!(!root.join("new.txt").exists());
+
+        apply(&journal, journal.undo(None, false).await)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(root.join("a.txt")).unwrap(),
+            "one\n"
+        );
+
+        let outcome = apply(&journal, journal.redo(None, false).await)
+            .await
+            .unwrap();
+        assert_eq!(outcome.entry.id, 1);
+        assert_eq!(
+            std::fs::read_to_string(root.join("a.txt")).unwrap(),
+            "two\n"
+        );
+
+        // A new operation discards what is left to redo
+        record_edit(&journal, root, "a.txt", "three\n").await;
+        let ids: Vec<u64> = journal.list().await.unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_undo_refuses_or_merges_later_changes() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("a.txt"), "a\nb\nc\nd\n").unwrap();
+        let journal = OperationJournal::new(root, &JournalConfig::default());
+        record_edit(&journal, root, "a.txt", "a\nB\nc\nd\n").await;
+
+        // Edited after the operation, elsewhere in the file
+        std::fs::write(root.join("a.txt"), "a\nB\nc\nD\n").unwrap();
+        let err = apply(&journal, journal.undo(None, false).await)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("a.txt"));
+        assert_eq!(
+            std::fs::read_to_string(root.join("a.txt")).unwrap(),
+            "a\nB\nc\nD\n"
+        );
+
+        let outcome = apply(&journal, journal.undo(None, true).await)
+            .await
+            .unwrap();
+        assert_eq!(outcome.merged_files, vec!["a.txt"]);
+        assert_eq!(
+            std::fs::read_to_string(root.join("a.txt")).unwrap(),
+            "a\nb\nc\nD\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_large_or_binary_operations_are_not_journaled() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("a.txt"), "a".repeat(600)).unwrap();
+        std::fs::write(root.join("b.txt"), "b".repeat(600)).unwrap();
+        std::fs::write(root.join("image.bin"), [0xff, 0xfe, 0x00]).unwrap();
+        let config = JournalConfig {
+            max_operation_bytes: 1000,
+            ..JournalConfig::default()
+        };
+        let journal = OperationJournal::new(root, &config);
+
+        let paths = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        assert!(journal.capture(&paths(&["a.txt"])).await.is_some());
+        assert!(journal.capture(&paths(&["a.txt", "b.txt"])).await.is_none());
+        assert!(journal.capture(&paths(&["image.bin"])).await.is_none());
+    }
+}
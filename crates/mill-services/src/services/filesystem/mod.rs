@@ -2,3 +2,4 @@
 
 pub mod file_service;
 pub mod git_service;
+pub mod journal;
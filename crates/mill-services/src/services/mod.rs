@@ -19,6 +19,7 @@ pub mod validation;
 // --- Other Service Modules ---
 
 pub mod app_state_factory;
+pub mod audit;
 pub mod move_service;
 pub mod reference_updater;
 pub mod registry_builder;
@@ -41,7 +42,9 @@ pub use self::coordination::operation_queue::{
 pub use self::coordination::workflow_executor::{self, WorkflowExecutor};
 pub use self::filesystem::file_service::{self, FileService};
 pub use self::filesystem::git_service::{self, GitService};
+pub use self::filesystem::journal::{self, OperationJournal};
 pub use self::planning::converter::{self, PlanConverter};
+pub use self::planning::diff::{self, plan_from_patch, render_plan_diff};
 pub use self::planning::executor::{self, ExecutionOptions, ExecutionResult, PlanExecutor};
 pub use self::planning::planner::{self, Planner};
 pub use self::validation::checksum::{self, ChecksumValidator};
@@ -49,5 +52,6 @@ pub use self::validation::dry_run::{self, DryRunGenerator, DryRunResult};
 pub use self::validation::post_apply::{self, PostApplyValidator};
 
 // Re-export items from modules that were not moved.
+pub use self::audit::{AuditLog, AuditOutcome, AuditRecord};
 pub use self::move_service::MoveService;
 pub use self::registry_builder::build_language_plugin_registry;
@@ -26,6 +26,46 @@ impl PlanConverter {
         Self
     }
 
+    /// Convert a refactoring plan to the internal EditPlan format
+    ///
+    /// Converts the plan's WorkspaceEdit and, for a DeletePlan, adds a delete
+    /// operation for each of its deletions.
+    pub fn convert_plan(&self, plan: &RefactorPlan) -> ServerResult<EditPlan> {
+        let mut edit_plan = self.convert_to_edit_plan(plan.workspace_edit().clone(), plan)?;
+
+        // DeletePlan lists its deletions outside the WorkspaceEdit
+        if let RefactorPlan::DeletePlan(delete_plan) = plan {
+            debug!(
+                deletion_count = delete_plan.deletions.len(),
+                "Adding delete operations from DeletePlan"
+            );
+
+            for target in &delete_plan.deletions {
+                debug!(
+                    path = %target.path,
+                    kind = %target.kind,
+                    "Adding delete operation"
+                );
+                edit_plan.edits.push(TextEdit {
+                    file_path: Some(target.path.clone()),
+                    edit_type: EditType::Delete,
+                    location: mill_foundation::protocol::EditLocation {
+                        start_line: 0,
+                        start_column: 0,
+                        end_line: 0,
+                        end_column: 0,
+                    },
+                    original_text: String::new(),
+                    new_text: String::new(),
+                    priority: 0,
+                    description: format!("Delete {}: {}", target.kind, target.path),
+                });
+            }
+        }
+
+        Ok(edit_plan)
+    }
+
     /// Convert LSP WorkspaceEdit to internal EditPlan format
     ///
     /// Extracts all edits from the WorkspaceEdit and converts them to our
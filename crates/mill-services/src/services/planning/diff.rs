@@ -0,0 +1,951 @@
+//! Unified diff previews of refactoring plans
+//!
+//! [`render_plan_diff`] shows what a plan would change as a git-style unified
+//! diff: edited files get hunks, and created, deleted and moved files get
+//! `new file`, `deleted file` and `rename from`/`rename to` headers.
+//! [`plan_from_patch`] goes the other way and turns such a patch into a plan
+//! that is applied like any other, checksums included.
+
+use crate::services::planning::converter::PlanConverter;
+use crate::services::validation::checksum::ChecksumValidator;
+use crate::services::FileService;
+use diffy::{DiffOptions, Patch};
+use lsp_types::{
+    CreateFile, DeleteFile, DocumentChangeOperation, DocumentChanges, OneOf,
+    OptionalVersionedTextDocumentIdentifier, Position, Range, RenameFile, ResourceOp,
+    TextDocumentEdit, Uri, WorkspaceEdit,
+};
+use mill_ast::transformer;
+use mill_foundation::errors::MillError;
+use mill_foundation::protocol::{
+    EditLocation, EditPlan, EditPlanMetadata, EditType, PlanMetadata, PlanSummary, RefactorPlan,
+    TextEdit, TransformPlan,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+type ServerResult<T> = Result<T, MillError>;
+
+const DEV_NULL: &str = "/dev/null";
+
+/// Render the changes a refactoring plan would make as a unified diff
+pub async fn render_plan_diff(plan: &RefactorPlan, project_root: &Path) -> ServerResult<String> {
+    let edit_plan = PlanConverter::new().convert_plan(plan)?;
+    render_edit_plan_diff(&edit_plan, project_root).await
+}
+
+/// Render the changes an edit plan would make as a unified diff
+///
+/// Paths are shown relative to `project_root`. Files are read from disk but
+/// never written.
+pub async fn render_edit_plan_diff(plan: &EditPlan, project_root: &Path) -> ServerResult<String> {
+    let changes = collect_changes(plan, project_root).await;
+
+    let mut diff = String::new();
+    for (path, change) in &changes {
+        let from = match &change.origin {
+            Origin::Existing(from) => Some(from.as_path()),
+            Origin::Created(_) => None,
+        };
+        let to = (!change.deleted).then_some(path.as_path());
+        let moved = from.is_some_and(|from| from != path);
+
+        // Pure renames are shown without reading the file. The content before
+        // and after the plan is `None` when the file is not text.
+        let contents = if change.deleted || !change.edits.is_empty() || from.is_none() {
+            let before = match &change.origin {
+                Origin::Existing(from) => read_text(from).await?,
+                Origin::Created(_) => Some(String::new()),
+            };
+            let after = match (&change.origin, &before) {
+                _ if change.deleted => Some(String::new()),
+                (Origin::Created(content), _) | (Origin::Existing(_), Some(content)) => {
+                    Some(apply_text_edits(content, &change.edits, path)?)
+                }
+                (Origin::Existing(from), None) => {
+                    return Err(MillError::invalid_request(format!(
+                        "Cannot preview edits to binary file {}",
+                        from.display()
+                    )))
+                }
+            };
+            Some((before, after))
+        } else {
+            None
+        };
+        let edited = matches!(&contents, Some((before, after)) if before != after);
+        if !(moved || edited || change.deleted || from.is_none()) {
+            continue;
+        }
+
+        let old_name = from.map(|from| display_path(from, project_root));
+        let new_name = to.map(|to| display_path(to, project_root));
+        let header_old = old_name
+            .as_deref()
+            .or(new_name.as_deref())
+            .unwrap_or_default();
+        let header_new = new_name
+            .as_deref()
+            .or(old_name.as_deref())
+            .unwrap_or_default();
+        diff.push_str(&format!("diff --git a/{} b/{}\n", header_old, header_new));
+        if from.is_none() {
+            diff.push_str("new file mode 100644\n");
+        }
+        if change.deleted {
+            diff.push_str("deleted file mode 100644\n");
+        }
+        if moved && !change.deleted {
+            diff.push_str(&format!(
+                "rename from {}\nrename to {}\n",
+                header_old, header_new
+            ));
+        }
+
+        let original = old_name.map_or_else(|| DEV_NULL.to_string(), |name| format!("a/{}", name));
+        let modified = new_name.map_or_else(|| DEV_NULL.to_string(), |name| format!("b/{}", name));
+        match contents {
+            Some((Some(before), Some(after))) if before != after => {
+                let patch = DiffOptions::new()
+                    .set_original_filename(original)
+                    .set_modified_filename(modified)
+                    .create_patch(&before, &after);
+                diff.push_str(&patch.to_string());
+            }
+            Some((None, _)) => {
+                diff.push_str(&format!(
+                    "Binary files {} and {} differ\n",
+                    original, modified
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(diff)
+}
+
+/// Where a file's content before the plan comes from
+enum Origin {
+    /// An existing file at this path
+    Existing(PathBuf),
+    /// A file the plan creates, with its initial content
+    Created(String),
+}
+
+/// A file the plan touches
+struct FileChange {
+    origin: Origin,
+    deleted: bool,
+    /// Text edits, against the file's path after moves
+    edits: Vec<TextEdit>,
+}
+
+impl FileChange {
+    fn existing(path: PathBuf) -> Self {
+        Self {
+            origin: Origin::Existing(path),
+            deleted: false,
+            edits: Vec::new(),
+        }
+    }
+}
+
+/// Group a plan's operations by the path each file ends up at
+///
+/// File operations are applied first, in order, and text edits then refer to
+/// the files' new paths, as when the plan is executed.
+async fn collect_changes(plan: &EditPlan, project_root: &Path) -> BTreeMap<PathBuf, FileChange> {
+    let mut changes: BTreeMap<PathBuf, FileChange> = BTreeMap::new();
+
+    for edit in &plan.edits {
+        let Some(file_path) = &edit.file_path else {
+            continue;
+        };
+        let path = resolve(project_root, file_path);
+        match edit.edit_type {
+            EditType::Move => {
+                let destination = resolve(project_root, &edit.new_text);
+                for (from, to) in expand_move(&path, &destination, &changes).await {
+                    let change = changes
+                        .remove(&from)
+                        .unwrap_or_else(|| FileChange::existing(from));
+                    changes.insert(to, change);
+                }
+            }
+            EditType::Create => {
+                changes.insert(
+                    path,
+                    FileChange {
+                        origin: Origin::Created(edit.new_text.clone()),
+                        deleted: false,
+                        edits: Vec::new(),
+                    },
+                );
+            }
+            EditType::Delete => {
+                for file in expand_delete(&path, &changes).await {
+                    let change = changes
+                        .entry(file.clone())
+                        .or_insert_with(|| FileChange::existing(file.clone()));
+                    if matches!(change.origin, Origin::Created(_)) {
+                        changes.remove(&file);
+                    } else {
+                        change.deleted = true;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for edit in &plan.edits {
+        if matches!(
+            edit.edit_type,
+            EditType::Move | EditType::Create | EditType::Delete
+        ) {
+            continue;
+        }
+        let file_path = edit.file_path.as_deref().unwrap_or(&plan.source_file);
+        let path = resolve(project_root, file_path);
+        changes
+            .entry(path.clone())
+            .or_insert_with(|| FileChange::existing(path))
+            .edits
+            .push(edit.clone());
+    }
+
+    changes
+}
+
+/// The files a move renames: one pair, or every file in a moved directory
+async fn expand_move(
+    from: &Path,
+    to: &Path,
+    changes: &BTreeMap<PathBuf, FileChange>,
+) -> Vec<(PathBuf, PathBuf)> {
+    if !is_dir(from, changes).await {
+        return vec![(from.to_path_buf(), to.to_path_buf())];
+    }
+    files_under(from, changes)
+        .into_iter()
+        .filter_map(|file| {
+            let relative = file.strip_prefix(from).ok()?.to_path_buf();
+            Some((file, to.join(relative)))
+        })
+        .collect()
+}
+
+/// The files a deletion removes: one file, or every file in a directory
+async fn expand_delete(path: &Path, changes: &BTreeMap<PathBuf, FileChange>) -> Vec<PathBuf> {
+    if is_dir(path, changes).await {
+        files_under(path, changes)
+    } else {
+        vec![path.to_path_buf()]
+    }
+}
+
+/// Whether `path` is a directory, on disk or after earlier operations
+async fn is_dir(path: &Path, changes: &BTreeMap<PathBuf, FileChange>) -> bool {
+    if changes.contains_key(path) {
+        return false;
+    }
+    fs::metadata(path).await.is_ok_and(|m| m.is_dir())
+        || changes.keys().any(|file| file.starts_with(path))
+}
+
+/// Files inside `dir` after earlier operations
+fn files_under(dir: &Path, changes: &BTreeMap<PathBuf, FileChange>) -> Vec<PathBuf> {
+    // Files already moved away from or deleted in `dir` are tracked in `changes`
+    let moved_away: Vec<&Path> = changes
+        .values()
+        .filter_map(|change| match &change.origin {
+            Origin::Existing(from) => Some(from.as_path()),
+            Origin::Created(_) => None,
+        })
+        .collect();
+    let mut files: Vec<PathBuf> = ignore::WalkBuilder::new(dir)
+        .hidden(false)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|file| !moved_away.contains(&file.as_path()) && !changes.contains_key(file))
+        .collect();
+    files.extend(
+        changes
+            .iter()
+            .filter(|(file, change)| file.starts_with(dir) && !change.deleted)
+            .map(|(file, _)| file.clone()),
+    );
+    files.sort();
+    files
+}
+
+/// Read a file as text, or `None` if it is not UTF-8
+async fn read_text(path: &Path) -> ServerResult<Option<String>> {
+    let bytes = fs::read(path)
+        .await
+        .map_err(|e| MillError::io(format!("Cannot read {}: {}", path.display(), e)))?;
+    Ok(String::from_utf8(bytes).ok())
+}
+
+/// Apply text edits to file content without touching the disk
+fn apply_text_edits(content: &str, edits: &[TextEdit], path: &Path) -> ServerResult<String> {
+    if edits.is_empty() {
+        return Ok(content.to_string());
+    }
+    let plan = EditPlan {
+        source_file: String::new(),
+        edits: edits.to_vec(),
+        dependency_updates: Vec::new(),
+        validations: Vec::new(),
+        metadata: EditPlanMetadata {
+            intent_name: "preview".to_string(),
+            intent_arguments: serde_json::json!({}),
+            created_at: chrono::Utc::now(),
+            complexity: 0,
+            impact_areas: Vec::new(),
+            consolidation: None,
+        },
+    };
+    let result = transformer::apply_edit_plan(content, &plan).map_err(|e| {
+        MillError::internal(format!(
+            "Failed to preview edits to {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    if let Some(skipped) = result.skipped_edits.first() {
+        return Err(MillError::internal(format!(
+            "Failed to preview edits to {}: {}",
+            path.display(),
+            skipped.reason
+        )));
+    }
+    Ok(result.transformed_source)
+}
+
+/// Turn a unified diff into a plan that makes its changes
+///
+/// Accepts `git diff` output, including new, deleted and renamed files, and
+/// plain `diff -u` output. Every hunk must apply to the current files, whose
+/// checksums are recorded in the plan so it cannot be applied once they change.
+/// The patch is rejected before any file is read if a path escapes the project.
+pub async fn plan_from_patch(
+    patch: &str,
+    file_service: &FileService,
+) -> ServerResult<RefactorPlan> {
+    let sections = split_patch(patch)?;
+    if sections.is_empty() {
+        return Err(MillError::invalid_request("Patch contains no file changes"));
+    }
+    let paths = sections
+        .iter()
+        .map(|section| {
+            let checked = |path: &str| file_service.to_absolute_path_checked(Path::new(path));
+            Ok((
+                section.old_path().map(checked).transpose()?,
+                section.new_path().map(checked).transpose()?,
+            ))
+        })
+        .collect::<ServerResult<Vec<_>>>()?;
+
+    let mut operations = Vec::new();
+    let mut checksums = HashMap::new();
+    let mut summary = PlanSummary {
+        affected_files: 0,
+        created_files: 0,
+        deleted_files: 0,
+    };
+    for (section, (old_path, new_path)) in sections.iter().zip(paths) {
+        let body = &patch[section.body.clone()];
+        let hunks = if body.is_empty() {
+            None
+        } else {
+            Some(Patch::from_str(body).map_err(|e| {
+                MillError::invalid_request(format!("Invalid patch for {}: {}", section.name(), e))
+            })?)
+        };
+        let apply = |content: &str| match &hunks {
+            Some(hunks) => diffy::apply(content, hunks).map_err(|e| {
+                MillError::invalid_request(format!(
+                    "Patch does not apply to {}: {}",
+                    section.name(),
+                    e
+                ))
+            }),
+            None => Ok(content.to_string()),
+        };
+
+        match (old_path, new_path) {
+            (None, Some(new_path)) => {
+                let uri = file_uri(&new_path)?;
+                let content = apply("")?;
+                operations.push(DocumentChangeOperation::Op(ResourceOp::Create(
+                    CreateFile {
+                        uri: uri.clone(),
+                        options: None,
+                        annotation_id: None,
+                    },
+                )));
+                if !content.is_empty() {
+                    operations.push(replace_document(uri, "", content));
+                }
+                summary.created_files += 1;
+            }
+            (Some(old_path), new_path) => {
+                let current = fs::read_to_string(&old_path).await.map_err(|e| {
+                    MillError::invalid_request(format!(
+                        "Patch does not apply to {}: {}",
+                        section.name(),
+                        e
+                    ))
+                })?;
+                let content = apply(&current)?;
+                checksums.insert(
+                    old_path.to_string_lossy().into_owned(),
+                    ChecksumValidator::calculate_checksum(&current),
+                );
+                let old_uri = file_uri(&old_path)?;
+
+                let Some(new_path) = new_path else {
+                    operations.push(DocumentChangeOperation::Op(ResourceOp::Delete(
+                        DeleteFile {
+                            uri: old_uri,
+                            options: None,
+                        },
+                    )));
+                    summary.deleted_files += 1;
+                    continue;
+                };
+                let new_uri = file_uri(&new_path)?;
+                if new_path != old_path {
+                    operations.push(DocumentChangeOperation::Op(ResourceOp::Rename(
+                        RenameFile {
+                            old_uri,
+                            new_uri: new_uri.clone(),
+                            options: None,
+                            annotation_id: None,
+                        },
+                    )));
+                }
+                if content != current {
+                    operations.push(replace_document(new_uri, &current, content));
+                }
+                summary.affected_files += 1;
+            }
+            (None, None) => {
+                return Err(MillError::invalid_request(format!(
+                    "Patch for {} names no file",
+                    section.name()
+                )))
+            }
+        }
+    }
+
+    let impact = match sections.len() {
+        0..=3 => "low",
+        4..=10 => "medium",
+        _ => "high",
+    };
+    Ok(RefactorPlan::TransformPlan(TransformPlan {
+        edits: WorkspaceEdit {
+            changes: None,
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            change_annotations: None,
+        },
+        summary,
+        warnings: Vec::new(),
+        metadata: PlanMetadata {
+            plan_version: "1.0".to_string(),
+            kind: "patch".to_string(),
+            language: "unknown".to_string(),
+            estimated_impact: impact.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        },
+        file_checksums: checksums,
+    }))
+}
+
+/// One file's part of a patch
+#[derive(Debug, Default)]
+struct FileSection {
+    /// Paths from the `diff --git a/... b/...` line
+    git_paths: Option<(String, String)>,
+    /// Path from the `---` line, `None` for `/dev/null`
+    minus: Option<Option<String>>,
+    /// Path from the `+++` line, `None` for `/dev/null`
+    plus: Option<Option<String>>,
+    rename_from: Option<String>,
+    rename_to: Option<String>,
+    new_file: bool,
+    deleted_file: bool,
+    /// Byte range of the `---`/`+++` lines and hunks
+    body: std::ops::Range<usize>,
+}
+
+impl FileSection {
+    fn old_path(&self) -> Option<&str> {
+        if self.new_file {
+            return None;
+        }
+        match &self.minus {
+            Some(minus) => minus.as_deref(),
+            None => self
+                .rename_from
+                .as_deref()
+                .or(self.git_paths.as_ref().map(|(old, _)| old.as_str())),
+        }
+    }
+
+    fn new_path(&self) -> Option<&str> {
+        if self.deleted_file {
+            return None;
+        }
+        match &self.plus {
+            Some(plus) => plus.as_deref(),
+            None => self
+                .rename_to
+                .as_deref()
+                .or(self.git_paths.as_ref().map(|(_, new)| new.as_str())),
+        }
+    }
+
+    /// A name for error messages
+    fn name(&self) -> &str {
+        self.new_path()
+            .or(self.old_path())
+            .unwrap_or("an unnamed file")
+    }
+}
+
+/// Split a patch into per-file sections
+///
+/// A section starts at a `diff --git` line, or at a `---` line outside a
+/// hunk. Text before the first section (such as a commit message) and after
+/// the last hunk of a section is ignored. Fails on a malformed hunk header.
+fn split_patch(patch: &str) -> ServerResult<Vec<FileSection>> {
+    let mut sections: Vec<FileSection> = Vec::new();
+    // Old and new lines left in the current hunk
+    let mut remaining = (0usize, 0usize);
+    let mut offset = 0;
+
+    for raw in patch.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+
+        if remaining != (0, 0) {
+            match line.chars().next() {
+                Some('-') => remaining.0 = remaining.0.saturating_sub(1),
+                Some('+') => remaining.1 = remaining.1.saturating_sub(1),
+                Some('\\') => {}
+                _ => {
+                    remaining.0 = remaining.0.saturating_sub(1);
+                    remaining.1 = remaining.1.saturating_sub(1);
+                }
+            }
+            if let Some(section) = sections.last_mut() {
+                section.body.end = offset;
+            }
+            continue;
+        }
+
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            sections.push(FileSection {
+                git_paths: parse_git_paths(paths),
+                body: offset..offset,
+                ..FileSection::default()
+            });
+            continue;
+        }
+        let Some(section) = sections.last_mut() else {
+            if let Some(path) = line.strip_prefix("--- ") {
+                sections.push(FileSection {
+                    minus: Some(parse_header_path(path, "a/")),
+                    body: start..offset,
+                    ..FileSection::default()
+                });
+            }
+            continue;
+        };
+        let in_body = !section.body.is_empty();
+
+        if let Some(path) = line.strip_prefix("--- ") {
+            if section.minus.is_some() {
+                // A plain unified diff for the next file
+                sections.push(FileSection {
+                    minus: Some(parse_header_path(path, "a/")),
+                    body: start..offset,
+                    ..FileSection::default()
+                });
+            } else {
+                section.minus = Some(parse_header_path(path, "a/"));
+                section.body = start..offset;
+            }
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            section.plus = Some(parse_header_path(path, "b/"));
+            section.body.end = offset;
+        } else if line.starts_with("@@ ") {
+            remaining = parse_hunk_lengths(line).ok_or_else(|| {
+                MillError::invalid_request(format!("Malformed hunk header: {}", line))
+            })?;
+            if !in_body {
+                section.body.start = start;
+            }
+            section.body.end = offset;
+        } else if !in_body {
+            if line.starts_with("new file mode") {
+                section.new_file = true;
+            } else if line.starts_with("deleted file mode") {
+                section.deleted_file = true;
+            } else if let Some(path) = line.strip_prefix("rename from ") {
+                section.rename_from = Some(path.to_string());
+            } else if let Some(path) = line.strip_prefix("rename to ") {
+                section.rename_to = Some(path.to_string());
+            }
+        }
+    }
+
+    Ok(sections)
+}
+
+/// Paths from `a/<old> b/<new>`
+fn parse_git_paths(paths: &str) -> Option<(String, String)> {
+    let paths = paths.strip_prefix("a/")?;
+    let (old, new) = paths.split_once(" b/")?;
+    Some((old.to_string(), new.to_string()))
+}
+
+/// Path from a `---` or `+++` line, without its git prefix and timestamp
+fn parse_header_path(value: &str, prefix: &str) -> Option<String> {
+    let path = value.split('\t').next().unwrap_or(value).trim_end();
+    if path == DEV_NULL {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+/// Old and new line counts from a `@@ -a,b +c,d @@` line
+///
+/// Returns `None` when the line is not a well-formed hunk header.
+fn parse_hunk_lengths(line: &str) -> Option<(usize, usize)> {
+    let length = |range: &str| -> Option<usize> {
+        match range.split_once(',') {
+            Some((start, len)) => {
+                start.parse::<usize>().ok()?;
+                len.parse().ok()
+            }
+            None => range.parse::<usize>().ok().map(|_| 1),
+        }
+    };
+    let mut ranges = line.split(' ').skip(1);
+    let old = length(ranges.next()?.strip_prefix('-')?)?;
+    let new = length(ranges.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+/// A text edit replacing the whole of `current` with `content`
+fn replace_document(uri: Uri, current: &str, content: String) -> DocumentChangeOperation {
+    let location = EditLocation::whole_file(current);
+    DocumentChangeOperation::Edit(TextDocumentEdit {
+        text_document: OptionalVersionedTextDocumentIdentifier { uri, version: None },
+        edits: vec![OneOf::Left(lsp_types::TextEdit {
+            range: Range {
+                start: Position::new(location.start_line, location.start_column),
+                end: Position::new(location.end_line, location.end_column),
+            },
+            new_text: content,
+        })],
+    })
+}
+
+fn file_uri(path: &Path) -> ServerResult<Uri> {
+    let url = url::Url::from_file_path(path).map_err(|_| {
+        MillError::invalid_request(format!("Invalid file path: {}", path.display()))
+    })?;
+    // fluent-uri rejects unencoded brackets
+    let uri = url.as_str().replace('[', "%5B").replace(']', "%5D");
+    uri.parse::<Uri>()
+        .map_err(|e| MillError::internal(format!("Failed to parse URI '{}': {}", uri, e)))
+}
+
+/// Resolve a plan or patch path against the project root
+fn resolve(project_root: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        project_root.join(path)
+    }
+}
+
+/// A path as shown in diff headers: relative to the project root, with `/`
+fn display_path(path: &Path, project_root: &Path) -> String {
+    path.strip_prefix(project_root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{LockManager, OperationQueue};
+    use mill_ast::AstCache;
+    use mill_config::config::AppConfig;
+    use mill_plugin_api::PluginDiscovery;
+    use std::sync::Arc;
+
+    fn file_service(project_root: &Path) -> FileService {
+        let lock_manager = Arc::new(LockManager::new());
+        FileService::new(
+            project_root,
+            Arc::new(AstCache::new()),
+            lock_manager.clone(),
+            Arc::new(OperationQueue::new(lock_manager)),
+            &AppConfig::default(),
+            Arc::new(PluginDiscovery::new()),
+        )
+    }
+
+    fn edit(
+        edit_type: EditType,
+        file_path: &Path,
+        location: (u32, u32, u32, u32),
+        new_text: &str,
+    ) -> TextEdit {
+        TextEdit {
+            file_path: Some(file_path.to_string_lossy().into_owned()),
+            edit_type,
+            location: EditLocation {
+                start_line: location.0,
+                start_column: location.1,
+                end_line: location.2,
+                end_column: location.3,
+            },
+            original_text: String::new(),
+            new_text: new_text.to_string(),
+            priority: 0,
+            description: String::new(),
+        }
+    }
+
+    fn edit_plan(edits: Vec<TextEdit>) -> EditPlan {
+        EditPlan {
+            source_file: String::new(),
+            edits,
+            dependency_updates: Vec::new(),
+            validations: Vec::new(),
+            metadata: EditPlanMetadata {
+                intent_name: "test".to_string(),
+                intent_arguments: serde_json::json!({}),
+                created_at: chrono::Utc::now(),
+                complexity: 1,
+                impact_areas: Vec::new(),
+                consolidation: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_edits_creates_deletes_and_moves() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/util")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "mod util;\nuse util::helper;\n").unwrap();
+        std::fs::write(root.join("src/util/mod.rs"), "pub fn helper() {}\n").unwrap();
+        std::fs::write(root.join("src/gone.rs"), "// unused\n").unwrap();
+
+        let plan = edit_plan(vec![
+            edit(
+                EditType::Move,
+                &root.join("src/util"),
+                (0, 0, 0, 0),
+                "src/tools",
+            ),
+            edit(EditType::Create, &root.join("src/new.rs"), (0, 0, 0, 0), ""),
+            edit(
+                EditType::Delete,
+                &root.join("src/gone.rs"),
+                (0, 0, 0, 0),
+                "",
+            ),
+            edit(
+                EditType::Replace,
+                &root.join("src/lib.rs"),
+                (0, 4, 0, 8),
+                "tools",
+            ),
+            edit(
+                EditType::Replace,
+                &root.join("src/lib.rs"),
+                (1, 4, 1, 8),
+                "tools",
+            ),
+            edit(
+                EditType::Replace,
+                &root.join("src/tools/mod.rs"),
+                (0, 7, 0, 13),
+                "helper2",
+            ),
+            edit(
+                EditType::Replace,
+                &root.join("src/new.rs"),
+                (0, 0, 0, 0),
+                "// new\n",
+            ),
+        ]);
+
+        let diff = render_edit_plan_diff(&plan, root).await.unwrap();
+        assert_eq!(
+            diff,
+            "\
+diff --git a/src/gone.rs b/src/gone.rs
+deleted file mode 100644
+--- a/src/gone.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-// unused
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,2 +1,2 @@
+-mod util;
+-use util::helper;
++mod tools;
++use tools::helper;
+diff --git a/src/new.rs b/src/new.rs
+new file mode 100644
+--- /dev/null
++++ b/src/new.rs
+@@ -0,0 +1 @@
++// new
+diff --git a/src/util/mod.rs b/src/tools/mod.rs
+rename from src/util/mod.rs
+rename to src/tools/mod.rs
+--- a/src/util/mod.rs
++++ b/src/tools/mod.rs
+@@ -1 +1 @@
+-pub fn helper() {}
++pub fn helper2() {}
+"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_patch_round_trips_through_a_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/a.ts"),
+            "export const a = 1;\nexport const b = 2;\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/old.ts"), "export {};\n").unwrap();
+        std::fs::write(root.join("src/gone.ts"), "").unwrap();
+
+        let patch = "\
+From 1234 Mon Sep 17 00:00:00 2001
+Subject: [PATCH] Tidy up
+
+diff --git a/src/a.ts b/src/a.ts
+index 1111111..2222222 100644
+--- a/src/a.ts
++++ b/src/a.ts
+@@ -1,2 +1,2 @@
+-export const a = 1;
++export const a = 10;
+ export const b = 2;
+diff --git a/src/gone.ts b/src/gone.ts
+deleted file mode 100644
+diff --git a/src/new.ts b/src/new.ts
+new file mode 100644
+--- /dev/null
++++ b/src/new.ts
+@@ -0,0 +1,2 @@
++--- not a header
++export {};
+diff --git a/src/old.ts b/src/renamed.ts
+similarity index 100%
+rename from src/old.ts
+rename to src/renamed.ts
+";
+        let plan = plan_from_patch(patch, &file_service(root)).await.unwrap();
+        let RefactorPlan::TransformPlan(transform) = &plan else {
+            panic!("expected a transform plan");
+        };
+        assert_eq!(transform.summary.affected_files, 2);
+        assert_eq!(transform.summary.created_files, 1);
+        assert_eq!(transform.summary.deleted_files, 1);
+        assert_eq!(transform.file_checksums.len(), 3);
+
+        let diff = render_plan_diff(&plan, root).await.unwrap();
+        let expected: String = patch
+            .lines()
+            .skip(3)
+            .filter(|line| !line.starts_with("index ") && !line.starts_with("similarity "))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        assert_eq!(diff, expected);
+    }
+
+    #[tokio::test]
+    async fn test_stale_patch_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "changed\n").unwrap();
+
+        let patch = "--- a.txt\n+++ a.txt\n@@ -1 +1 @@\n-original\n+patched\n";
+        let error = plan_from_patch(patch, &file_service(dir.path()))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Patch does not apply to a.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_patch_escaping_the_project_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "original\n").unwrap();
+
+        for (old, new) in [
+            ("../secret.txt", "../secret.txt"),
+            ("a.txt", "../moved.txt"),
+        ] {
+            let patch = format!(
+                "--- {}\n+++ {}\n@@ -1 +1 @@\n-original\n+patched\n",
+                old, new
+            );
+            let error = plan_from_patch(&patch, &file_service(&root))
+                .await
+                .unwrap_err();
+            assert!(
+                error.to_string().contains("escapes project root"),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_malformed_hunk_header_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "original\n").unwrap();
+
+        for header in ["@@ ", "@@ é @@", "@@ -1 @@", "@@ -x +1 @@"] {
+            let patch = format!("--- a.txt\n+++ a.txt\n{}\n-original\n+patched\n", header);
+            let error = plan_from_patch(&patch, &file_service(dir.path()))
+                .await
+                .unwrap_err();
+            assert!(
+                error.to_string().contains("Malformed hunk header"),
+                "{}",
+                header
+            );
+        }
+    }
+}
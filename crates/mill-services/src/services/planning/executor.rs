@@ -6,11 +6,13 @@
 use crate::services::filesystem::file_service::EditPlanResult;
 use crate::{ChecksumValidator, PlanConverter, PostApplyValidator};
 use mill_foundation::errors::MillError;
-use mill_foundation::protocol::{EditPlan, EditType, RefactorPlan, RefactorPlanExt};
+use mill_foundation::protocol::{EditPlan, EditType, RefactorPlan, RefactorPlanExt, TextEdit};
 
 type ServerResult<T> = Result<T, MillError>;
 use mill_foundation::validation::{ValidationConfig, ValidationResult};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::{debug, error, info};
 
@@ -51,6 +53,8 @@ pub struct ExecutionResult {
     pub warnings: Vec<String>,
     pub validation: Option<ValidationResult>,
     pub rollback_available: bool,
+    /// Checksums of the files the applied plans were computed against
+    pub checksums: BTreeMap<String, String>,
 }
 
 /// Service for executing refactoring plans
@@ -89,56 +93,23 @@ impl PlanExecutor {
             self.checksum_validator.validate_checksums(&plan).await?;
         }
 
-        // Step 2: Extract WorkspaceEdit from the discriminated union
-        let workspace_edit = plan.workspace_edit();
-
-        // Step 3: Convert LSP WorkspaceEdit to internal EditPlan format
-        let mut edit_plan = self
-            .plan_converter
-            .convert_to_edit_plan(workspace_edit.clone(), &plan)?;
-
-        // Step 4: Handle DeletePlan explicitly by reading from the deletions field
-        if let RefactorPlan::DeletePlan(delete_plan) = &plan {
-            debug!(
-                deletion_count = delete_plan.deletions.len(),
-                "Adding delete operations from DeletePlan"
-            );
-
-            for target in &delete_plan.deletions {
-                debug!(
-                    path = %target.path,
-                    kind = %target.kind,
-                    "Adding delete operation"
-                );
-                edit_plan.edits.push(mill_foundation::protocol::TextEdit {
-                    file_path: Some(target.path.clone()),
-                    edit_type: EditType::Delete,
-                    location: mill_foundation::protocol::EditLocation {
-                        start_line: 0,
-                        start_column: 0,
-                        end_line: 0,
-                        end_column: 0,
-                    },
-                    original_text: String::new(),
-                    new_text: String::new(),
-                    priority: 0,
-                    description: format!("Delete {}: {}", target.kind, target.path),
-                });
-            }
-        }
+        // Steps 2-4: Convert the plan to internal EditPlan format
+        let edit_plan = self.plan_converter.convert_plan(&plan)?;
+        let warnings = plan.warnings().iter().map(|w| w.message.clone()).collect();
+        let checksums = plan.checksums().clone().into_iter().collect();
 
         // Step 5: Apply edits atomically with automatic backup for rollback
         let apply_result = self.file_service.apply_edit_plan(&edit_plan).await;
 
-        match apply_result {
+        let result = match apply_result {
             Ok(result) => {
                 // Step 6: Run post-apply validation if specified
                 if let Some(validation_config) = options.validation {
-                    self.handle_validation(validation_config, result, &edit_plan, &plan)
+                    self.handle_validation(validation_config, result, &edit_plan, warnings)
                         .await
                 } else {
                     // No validation - return success immediately
-                    Ok(self.create_success_result(result, &edit_plan, &plan, None))
+                    Ok(self.create_success_result(result, &edit_plan, warnings, None))
                 }
             }
             Err(e) => {
@@ -146,7 +117,90 @@ impl PlanExecutor {
                 error!(error = %e, "Edit plan application failed");
                 Err(e)
             }
+        };
+        result.map(|result| ExecutionResult {
+            checksums,
+            ..result
+        })
+    }
+
+    /// Execute several refactoring plans as one atomic change
+    ///
+    /// All checksums are validated before anything is written, and the plans'
+    /// edits are applied together: either every plan is applied or none is.
+    /// The plans must have been computed against the same state of the files,
+    /// so plans whose edits overlap are refused (see [`check_plan_conflicts`]).
+    pub async fn execute_plans(
+        &self,
+        plans: Vec<RefactorPlan>,
+        options: ExecutionOptions,
+    ) -> ServerResult<ExecutionResult> {
+        info!(
+            plan_count = plans.len(),
+            validate_checksums = options.validate_checksums,
+            "Executing refactoring plans as one transaction"
+        );
+
+        if options.validate_checksums {
+            debug!("Validating file checksums");
+            for plan in &plans {
+                self.checksum_validator.validate_checksums(plan).await?;
+            }
+        }
+
+        let edit_plans = plans
+            .iter()
+            .map(|plan| self.plan_converter.convert_plan(plan))
+            .collect::<ServerResult<Vec<_>>>()?;
+        check_plan_conflicts(&edit_plans)?;
+
+        let mut merged: Option<EditPlan> = None;
+        let mut warnings = Vec::new();
+        let mut checksums = BTreeMap::new();
+        for (plan, edit_plan) in plans.iter().zip(edit_plans) {
+            warnings.extend(plan.warnings().iter().map(|w| w.message.clone()));
+            checksums.extend(plan.checksums().clone());
+            match merged.as_mut() {
+                Some(merged) => {
+                    merged.edits.extend(edit_plan.edits);
+                    merged
+                        .dependency_updates
+                        .extend(edit_plan.dependency_updates);
+                    merged.validations.extend(edit_plan.validations);
+                }
+                None => merged = Some(edit_plan),
+            }
         }
+        let Some(edit_plan) = merged else {
+            return Ok(ExecutionResult {
+                success: true,
+                applied_files: Vec::new(),
+                created_files: Vec::new(),
+                deleted_files: Vec::new(),
+                warnings,
+                validation: None,
+                rollback_available: false,
+                checksums,
+            });
+        };
+
+        // Apply all edits in one FileService call so a failure rolls back every plan
+        let result = self
+            .file_service
+            .apply_edit_plan(&edit_plan)
+            .await
+            .inspect_err(|e| error!(error = %e, "Combined edit plan application failed"))?;
+
+        let result = if let Some(validation_config) = options.validation {
+            self.handle_validation(validation_config, result, &edit_plan, warnings)
+                .await
+        } else {
+            Ok(self.create_success_result(result, &edit_plan, warnings, None))
+        };
+        result.map(|result| ExecutionResult {
+            checksums,
+            ..result
+        })
     }
 
     /// Handle post-apply validation workflow
@@ -155,7 +209,7 @@ impl PlanExecutor {
         validation_config: ValidationConfig,
         result: EditPlanResult,
         edit_plan: &EditPlan,
-        plan: &RefactorPlan,
+        warnings: Vec<String>,
     ) -> ServerResult<ExecutionResult> {
         info!(command = %validation_config.command, "Running post-apply validation");
 
@@ -173,14 +227,12 @@ impl PlanExecutor {
                         "Post-apply validation passed"
                     );
 
-                    Ok(
-                        self.create_success_result(
-                            result,
-                            edit_plan,
-                            plan,
-                            Some(validation_result),
-                        ),
-                    )
+                    Ok(self.create_success_result(
+                        result,
+                        edit_plan,
+                        warnings,
+                        Some(validation_result),
+                    ))
                 } else {
                     // Validation failed - return error with details
                     error!(
@@ -209,7 +261,7 @@ impl PlanExecutor {
         &self,
         result: EditPlanResult,
         edit_plan: &EditPlan,
-        plan: &RefactorPlan,
+        warnings: Vec<String>,
         validation: Option<ValidationResult>,
     ) -> ExecutionResult {
         let rollback_available = validation.is_none(); // Save before moving validation
@@ -219,9 +271,10 @@ impl PlanExecutor {
             applied_files: result.modified_files.clone(),
             created_files: PlanConverter::extract_created_files(edit_plan),
             deleted_files: PlanConverter::extract_deleted_files(edit_plan),
-            warnings: plan.warnings().iter().map(|w| w.message.clone()).collect(),
+            warnings,
             validation,
             rollback_available, // Validation consumes backup
+            checksums: BTreeMap::new(),
         }
     }
 
@@ -242,3 +295,127 @@ impl PlanExecutor {
         ))
     }
 }
+
+/// Refuse plans whose edits would conflict when applied together
+///
+/// Each plan was computed against the same state of the files, so their edits
+/// can only be merged when no two plans change the same text of a file, and
+/// no plan creates, moves or deletes a file that another plan changes.
+fn check_plan_conflicts(plans: &[EditPlan]) -> ServerResult<()> {
+    // Edits by the file they change, with the index of their plan
+    let mut by_file: HashMap<PathBuf, Vec<(usize, &TextEdit)>> = HashMap::new();
+    for (index, plan) in plans.iter().enumerate() {
+        for edit in &plan.edits {
+            let file = edit.file_path.as_deref().unwrap_or(&plan.source_file);
+            by_file
+                .entry(PathBuf::from(file))
+                .or_default()
+                .push((index, edit));
+            // A move also takes its destination
+            if edit.edit_type == EditType::Move {
+                by_file
+                    .entry(PathBuf::from(&edit.new_text))
+                    .or_default()
+                    .push((index, edit));
+            }
+        }
+    }
+
+    for (file, edits) in &by_file {
+        for (i, (plan_a, a)) in edits.iter().enumerate() {
+            for (plan_b, b) in &edits[i + 1..] {
+                if plan_a != plan_b && edits_conflict(a, b) {
+                    return Err(MillError::invalid_request(format!(
+                        "Plans {} and {} both change '{}' in overlapping places; \
+                         apply them separately",
+                        plan_a + 1,
+                        plan_b + 1,
+                        file.display()
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether two edits of the same file cannot both be applied
+fn edits_conflict(a: &TextEdit, b: &TextEdit) -> bool {
+    let whole_file = |edit: &TextEdit| {
+        matches!(
+            edit.edit_type,
+            EditType::Create | EditType::Move | EditType::Delete
+        )
+    };
+    if whole_file(a) || whole_file(b) {
+        return true;
+    }
+    let start = |edit: &TextEdit| (edit.location.start_line, edit.location.start_column);
+    let end = |edit: &TextEdit| (edit.location.end_line, edit.location.end_column);
+    // Insertions at the same place have no defined order
+    start(a) == start(b) || (start(a) < end(b) && start(b) < end(a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mill_foundation::protocol::{EditLocation, EditPlanMetadata};
+
+    fn edit(file: &str, edit_type: EditType, range: (u32, u32, u32, u32)) -> TextEdit {
+        let (start_line, start_column, end_line, end_column) = range;
+        TextEdit {
+            file_path: Some(file.to_string()),
+            edit_type,
+            location: EditLocation {
+                start_line,
+                start_column,
+                end_line,
+                end_column,
+            },
+            original_text: String::new(),
+            new_text: String::new(),
+            priority: 0,
+            description: "test".to_string(),
+        }
+    }
+
+    fn plan(edits: Vec<TextEdit>) -> EditPlan {
+        EditPlan {
+            source_file: String::new(),
+            edits,
+            dependency_updates: Vec::new(),
+            validations: Vec::new(),
+            metadata: EditPlanMetadata {
+                intent_name: "test".to_string(),
+                intent_arguments: serde_json::json!({}),
+                created_at: chrono::Utc::now(),
+                complexity: 1,
+                impact_areas: Vec::new(),
+                consolidation: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_plan_conflicts() {
+        let first = plan(vec![edit("a.rs", EditType::Replace, (1, 0, 1, 5))]);
+
+        // Disjoint edits of the same file merge
+        let disjoint = plan(vec![edit("a.rs", EditType::Replace, (1, 5, 2, 0))]);
+        assert!(check_plan_conflicts(&[first.clone(), disjoint]).is_ok());
+
+        let overlapping = plan(vec![edit("a.rs", EditType::Replace, (1, 3, 1, 8))]);
+        assert!(check_plan_conflicts(&[first.clone(), overlapping]).is_err());
+        let same_insert = plan(vec![edit("a.rs", EditType::Insert, (1, 0, 1, 0))]);
+        assert!(check_plan_conflicts(&[first.clone(), same_insert]).is_err());
+        let deleted = plan(vec![edit("a.rs", EditType::Delete, (0, 0, 0, 0))]);
+        assert!(check_plan_conflicts(&[first.clone(), deleted]).is_err());
+
+        // A plan's own edits are its own business
+        let own = plan(vec![
+            edit("a.rs", EditType::Replace, (1, 0, 1, 5)),
+            edit("a.rs", EditType::Replace, (1, 3, 1, 8)),
+        ]);
+        assert!(check_plan_conflicts(&[own]).is_ok());
+    }
+}
@@ -1,5 +1,6 @@
 //! Services for plan generation, conversion, and execution.
 
 pub mod converter;
+pub mod diff;
 pub mod executor;
 pub mod planner;
@@ -16,6 +16,8 @@ struct WorkflowTemplate {
     metadata: WorkflowMetadata,
     steps: Vec<Step>,
     required_params: Vec<String>,
+    #[serde(default)]
+    transactional: bool,
 }
 
 /// Root structure for workflows.json configuration file
@@ -178,6 +180,12 @@ impl Planner for DefaultPlanner {
                     params,
                     description,
                     requires_confirmation: step_template.requires_confirmation,
+                    when: step_template
+                        .when
+                        .as_ref()
+                        .map(|when| Self::replace_placeholders_in_value(when, &intent.params)),
+                    for_each: step_template.for_each.clone(),
+                    on_error: step_template.on_error,
                 }
             })
             .collect();
@@ -186,6 +194,7 @@ impl Planner for DefaultPlanner {
             name: workflow_name,
             metadata: template.metadata.clone(),
             steps,
+            transactional: template.transactional,
         })
     }
 }
@@ -12,6 +12,7 @@ pub use cache::{load_filelist_cache, save_filelist_cache, FileImportInfo, Import
 pub use helpers::{compute_line_info, create_full_file_edit, create_import_update_edit, create_path_reference_edit};
 
 use async_trait::async_trait;
+use mill_foundation::core::progress::ProgressReporter;
 use mill_foundation::errors::MillError as ServerError;
 use mill_plugin_api::LanguagePlugin;
 use mill_foundation::protocol::{DependencyUpdate, EditPlan, EditPlanMetadata};
@@ -19,6 +20,7 @@ use mill_foundation::protocol::{DependencyUpdate, EditPlan, EditPlanMetadata};
 type ServerResult<T> = Result<T, ServerError>;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::task::JoinSet;
 use std::time::Duration;
@@ -542,7 +544,14 @@ impl ReferenceUpdater {
         let rewrite_concurrency = rewrite_concurrency_limit();
         let rewrite_semaphore = Arc::new(tokio::sync::Semaphore::new(rewrite_concurrency));
 
+        // Tasks report their own completion; the reporter is task-local
+        let progress_reporter = ProgressReporter::current();
+        let scan_total = affected_files.len() as u64;
+        let scanned = Arc::new(AtomicU64::new(0));
+
         for file_path in affected_files {
+            let progress_reporter = progress_reporter.clone();
+            let scanned = scanned.clone();
             let plugin_map = plugin_map.clone();
             let project_root = project_root.clone();
             let old_path = old_path.clone();
@@ -561,6 +570,14 @@ impl ReferenceUpdater {
                     file_path = %file_path.display(),
                     "Processing affected file"
                 );
+                if let Some(reporter) = &progress_reporter {
+                    let done = scanned.fetch_add(1, Ordering::Relaxed) + 1;
+                    reporter.report(
+                        done,
+                        Some(scan_total),
+                        format!("Scanning {}", file_path.display()),
+                    );
+                }
 
                 let ext_str = file_path
                     .extension()
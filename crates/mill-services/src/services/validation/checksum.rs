@@ -87,8 +87,15 @@ impl ChecksumValidator {
     ///
     /// Returns a hex-encoded SHA-256 checksum string.
     pub fn calculate_checksum(content: &str) -> String {
+        Self::calculate_bytes_checksum(content.as_bytes())
+    }
+
+    /// Calculate SHA-256 checksum of raw file bytes
+    ///
+    /// Matches [`Self::calculate_checksum`] for UTF-8 content.
+    pub fn calculate_bytes_checksum(content: &[u8]) -> String {
         let mut hasher = Sha256::new();
-        hasher.update(content.as_bytes());
+        hasher.update(content);
         format!("{:x}", hasher.finalize())
     }
 }
@@ -27,9 +27,10 @@ pub enum WorkspaceOperation {
 pub enum WorkspaceExpectedBehavior {
     IsWorkspace(bool),
     MembersList(Vec<String>),
-    Added,               // Verify member was added by checking list contains it
-    Removed,             // Verify member was removed by checking list doesn't contain it
-    NameUpdated(String), // Verify name matches expected
+    Added,                        // Verify member was added by checking list contains it
+    Removed,                      // Verify member was removed by checking list doesn't contain it
+    NameUpdated(String),          // Verify name matches expected
+    ManifestEquals(&'static str), // Verify the edited manifest text exactly (layout preserved)
     NotSupported,
 }
 
@@ -66,6 +67,11 @@ impl WorkspaceTestCase {
         }
         self
     }
+
+    pub fn with_fixture(mut self, fixture: WorkspaceFixture) -> Self {
+        self.fixtures.push(fixture);
+        self
+    }
 }
 
 /// Predefined workspace test scenarios
@@ -285,6 +291,113 @@ impl WorkspaceScenarios {
             }
         })
     }
+
+    /// Add a member to a hand-formatted manifest (indentation and comments kept)
+    pub fn add_workspace_member_preserves_layout() -> WorkspaceTestCase {
+        WorkspaceTestCase::new("add_workspace_member_preserves_layout").with_all_languages(
+            |lang| {
+                let (manifest, member, expected) = match lang {
+                    Language::TypeScript => (
+                        "{\n  \"name\": \"root\",\n  \"workspaces\": [\n    \"packages/a\"\n  ],\n  \"private\": true\n}\n",
+                        "packages/b",
+                        "{\n  \"name\": \"root\",\n  \"workspaces\": [\n    \"packages/a\",\n    \"packages/b\"\n  ],\n  \"private\": true\n}\n",
+                    ),
+                    Language::Rust => (
+                        "[workspace]\n# Core crates\nmembers = [\n    \"crates/a\", # first\n]\nresolver = \"2\"\n",
+                        "crates/b",
+                        "[workspace]\n# Core crates\nmembers = [\n    \"crates/a\", # first\n    \"crates/b\",\n]\nresolver = \"2\"\n",
+                    ),
+                    Language::Python => (
+                        "[tool.pdm.workspace]\n# Local packages\nmembers = [\n    \"packages/a\",\n]\n",
+                        "packages/b",
+                        "[tool.pdm.workspace]\n# Local packages\nmembers = [\n    \"packages/a\",\n    \"packages/b\",\n]\n",
+                    ),
+                };
+
+                WorkspaceFixture {
+                    language: lang,
+                    manifest_content: manifest,
+                    operation: WorkspaceOperation::AddWorkspaceMember {
+                        member: member.to_string(),
+                    },
+                    expected: WorkspaceExpectedBehavior::ManifestEquals(expected),
+                }
+            },
+        )
+    }
+
+    /// Remove a member from a hand-formatted manifest (indentation and comments kept)
+    pub fn remove_workspace_member_preserves_layout() -> WorkspaceTestCase {
+        WorkspaceTestCase::new("remove_workspace_member_preserves_layout").with_all_languages(
+            |lang| {
+                let (manifest, member, expected) = match lang {
+                    Language::TypeScript => (
+                        "{\n  \"workspaces\": [\n    \"packages/a\",\n    \"packages/b\",\n    \"packages/c\"\n  ]\n}\n",
+                        "packages/b",
+                        "{\n  \"workspaces\": [\n    \"packages/a\",\n    \"packages/c\"\n  ]\n}\n",
+                    ),
+                    Language::Rust => (
+                        "[workspace]\nmembers = [\n    \"crates/a\",\n    \"crates/b\",\n    # Tools\n    \"crates/c\",\n]\n",
+                        "crates/b",
+                        "[workspace]\nmembers = [\n    \"crates/a\",\n    # Tools\n    \"crates/c\",\n]\n",
+                    ),
+                    Language::Python => (
+                        "[tool.uv.workspace]\nmembers = [\n    \"packages/a\",\n    \"packages/b\",\n    \"packages/c\",\n]\n",
+                        "packages/b",
+                        "[tool.uv.workspace]\nmembers = [\n    \"packages/a\",\n    \"packages/c\",\n]\n",
+                    ),
+                };
+
+                WorkspaceFixture {
+                    language: lang,
+                    manifest_content: manifest,
+                    operation: WorkspaceOperation::RemoveWorkspaceMember {
+                        member: member.to_string(),
+                    },
+                    expected: WorkspaceExpectedBehavior::ManifestEquals(expected),
+                }
+            },
+        )
+    }
+
+    /// Add members to the other workspace formats of each ecosystem
+    /// (pnpm-workspace.yaml, Yarn `workspaces.packages`, uv, Poetry packages)
+    pub fn add_workspace_member_formats() -> WorkspaceTestCase {
+        let add = |language, manifest_content, member: &str, expected| WorkspaceFixture {
+            language,
+            manifest_content,
+            operation: WorkspaceOperation::AddWorkspaceMember {
+                member: member.to_string(),
+            },
+            expected: WorkspaceExpectedBehavior::ManifestEquals(expected),
+        };
+
+        WorkspaceTestCase::new("add_workspace_member_formats")
+            .with_fixture(add(
+                Language::TypeScript,
+                "packages:\n  # Applications\n  - 'apps/*'\n  - 'packages/a'\n",
+                "packages/b",
+                "packages:\n  # Applications\n  - 'apps/*'\n  - 'packages/a'\n  - 'packages/b'\n",
+            ))
+            .with_fixture(add(
+                Language::TypeScript,
+                "{\n  \"workspaces\": {\n    \"packages\": [\"packages/*\"],\n    \"nohoist\": []\n  }\n}\n",
+                "tools/cli",
+                "{\n  \"workspaces\": {\n    \"packages\": [\"packages/*\", \"tools/cli\"],\n    \"nohoist\": []\n  }\n}\n",
+            ))
+            .with_fixture(add(
+                Language::Python,
+                "[project]\nname = \"root\"\n\n[tool.uv.workspace]\nmembers = [\"packages/*\"]  # all packages\nexclude = [\"packages/legacy\"]\n",
+                "tools/cli",
+                "[project]\nname = \"root\"\n\n[tool.uv.workspace]\nmembers = [\"packages/*\", \"tools/cli\"]  # all packages\nexclude = [\"packages/legacy\"]\n",
+            ))
+            .with_fixture(add(
+                Language::Python,
+                "[tool.poetry]\nname = \"root\"\npackages = [\n    { include = \"a\", from = \"packages/a\" },\n]\n",
+                "packages/b",
+                "[tool.poetry]\nname = \"root\"\npackages = [\n    { include = \"a\", from = \"packages/a\" },\n    { include = \"b\", from = \"packages/b\" },\n]\n",
+            ))
+    }
 }
 
 #[cfg(test)]
@@ -303,9 +416,16 @@ mod tests {
             WorkspaceScenarios::update_package_name(),
             WorkspaceScenarios::list_workspace_members_empty(),
             WorkspaceScenarios::remove_nonexistent_member(),
+            WorkspaceScenarios::add_workspace_member_preserves_layout(),
+            WorkspaceScenarios::remove_workspace_member_preserves_layout(),
+            WorkspaceScenarios::add_workspace_member_formats(),
         ];
 
-        assert_eq!(scenarios.len(), 9, "Should have 9 core workspace scenarios");
+        assert_eq!(
+            scenarios.len(),
+            12,
+            "Should have 12 core workspace scenarios"
+        );
     }
 
     #[test]
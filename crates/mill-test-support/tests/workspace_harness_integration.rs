@@ -317,4 +317,58 @@ mod workspace_harness_tests {
             }
         }
     }
+
+    /// Apply the fixture's add/remove operation and compare the edited manifest text
+    fn assert_manifest_edits(scenario: mill_test_support::harness::WorkspaceTestCase) {
+        let registry = get_test_registry();
+
+        for fixture in scenario.fixtures {
+            let plugin = registry
+                .find_by_extension(fixture.language.file_extension())
+                .unwrap_or_else(|| panic!("Plugin not found for {:?}", fixture.language));
+
+            let workspace_support = plugin
+                .workspace_support()
+                .unwrap_or_else(|| panic!("{:?} should have workspace support", fixture.language));
+
+            let result = match &fixture.operation {
+                WorkspaceOperation::AddWorkspaceMember { member } => {
+                    workspace_support.add_workspace_member(fixture.manifest_content, member)
+                }
+                WorkspaceOperation::RemoveWorkspaceMember { member } => {
+                    workspace_support.remove_workspace_member(fixture.manifest_content, member)
+                }
+                _ => panic!("Wrong operation for {} test", scenario.scenario_name),
+            };
+
+            match &fixture.expected {
+                WorkspaceExpectedBehavior::ManifestEquals(expected) => {
+                    assert_eq!(
+                        result, *expected,
+                        "{} failed for {:?}\nManifest:\n{}",
+                        scenario.scenario_name, fixture.language, fixture.manifest_content
+                    );
+                }
+                _ => panic!(
+                    "Wrong expected behavior for {} test",
+                    scenario.scenario_name
+                ),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_workspace_member_preserves_layout_all_languages() {
+        assert_manifest_edits(WorkspaceScenarios::add_workspace_member_preserves_layout());
+    }
+
+    #[tokio::test]
+    async fn test_remove_workspace_member_preserves_layout_all_languages() {
+        assert_manifest_edits(WorkspaceScenarios::remove_workspace_member_preserves_layout());
+    }
+
+    #[tokio::test]
+    async fn test_add_workspace_member_formats() {
+        assert_manifest_edits(WorkspaceScenarios::add_workspace_member_formats());
+    }
 }
@@ -7,10 +7,7 @@ use axum::{
     routing::{get, post},
     Router,
 };
-use mill_auth::{
-    generate_token,
-    jwt::{decode, Claims, DecodingKey, Validation},
-};
+use mill_auth::{generate_token, verify_token, TokenScopes};
 use mill_config::config::AppConfig;
 use mill_foundation::MillResult;
 use mill_workspaces::{Workspace, WorkspaceManager};
@@ -95,6 +92,8 @@ pub struct GenerateTokenRequest {
     pub user_id: Option<String>,
     /// Optional custom expiry in seconds (defaults to config value)
     pub expiry_seconds: Option<u64>,
+    /// Optional restrictions on what the token may do (unrestricted if omitted)
+    pub scopes: Option<TokenScopes>,
 }
 
 /// Generate token response
@@ -249,25 +248,11 @@ fn extract_user_id_from_jwt(
         )
     })?;
 
-    let key = DecodingKey::from_secret(auth_config.jwt_secret.as_ref());
-    let mut validation = Validation::default();
-
-    // Use config to determine if audience validation is enabled
-    validation.validate_aud = auth_config.validate_audience;
-
-    if auth_config.validate_audience {
-        let audience = auth_config
-            .jwt_audience_override
-            .as_ref()
-            .unwrap_or(&auth_config.jwt_audience);
-        validation.set_audience(&[audience]);
-    }
-
-    let token_data = decode::<Claims>(token, &key, &validation)
+    let claims = verify_token(token, auth_config)
         .map_err(|e| (StatusCode::UNAUTHORIZED, format!("Invalid token: {}", e)))?;
 
     // 4. Extract user_id claim
-    token_data.claims.user_id.ok_or_else(|| {
+    claims.user_id.ok_or_else(|| {
         (
             StatusCode::UNAUTHORIZED,
             "Token missing user_id claim".to_string(),
@@ -411,6 +396,14 @@ async fn generate_auth_token(
         )
     })?;
 
+    // Tokens verified with the issuer's public keys must come from that issuer
+    if auth_config.jwt_public_key_path.is_some() || auth_config.jwks_path.is_some() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Tokens for this server are issued by its external issuer".to_string(),
+        ));
+    }
+
     // Use custom expiry or default from config
     let expiry_seconds = request
         .expiry_seconds
@@ -424,6 +417,7 @@ async fn generate_auth_token(
         &auth_config.jwt_audience,
         request.project_id,
         request.user_id.clone(),
+        request.scopes.clone(),
     )
     .map_err(|e| {
         error!(error = %e, "Failed to generate token");
@@ -441,6 +435,7 @@ async fn generate_auth_token(
     info!(
         expiry_seconds = expiry_seconds,
         user_id = ?request.user_id,
+        scopes = ?request.scopes,
         "Generated authentication token"
     );
 
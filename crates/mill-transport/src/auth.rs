@@ -0,0 +1,58 @@
+//! Bearer token authentication shared by the network transports
+
+use mill_auth::{verify_token, TokenScopes};
+use mill_config::config::AuthConfig;
+
+/// Why a connection was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum AuthRejection {
+    MissingHeader,
+    MalformedHeader,
+    InvalidToken(String),
+}
+
+impl std::fmt::Display for AuthRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthRejection::MissingHeader => write!(f, "missing Authorization header"),
+            AuthRejection::MalformedHeader => write!(f, "malformed Authorization header"),
+            AuthRejection::InvalidToken(e) => write!(f, "token validation failed: {}", e),
+        }
+    }
+}
+
+/// Who a bearer token identifies and what it may do
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Identity {
+    /// The token's `user_id` claim
+    pub user_id: Option<String>,
+    /// The token's `scopes` claim; `None` when unrestricted
+    pub scopes: Option<TokenScopes>,
+}
+
+/// Validate an `Authorization: Bearer <jwt>` header value
+pub(crate) fn authenticate_bearer(
+    auth_config: &AuthConfig,
+    auth_header: Option<&str>,
+) -> Result<Identity, AuthRejection> {
+    let auth_value = auth_header.ok_or(AuthRejection::MissingHeader)?;
+    let token = auth_value
+        .strip_prefix("Bearer ")
+        .ok_or(AuthRejection::MalformedHeader)?;
+
+    // Decode token to validate and extract user_id
+    let claims =
+        verify_token(token, auth_config).map_err(|e| AuthRejection::InvalidToken(e.to_string()))?;
+
+    // Warn if project_id is missing (deprecation path)
+    if claims.project_id.is_none() {
+        tracing::warn!(
+            "Connection authenticated but token missing project_id claim - this will be required in future versions"
+        );
+    }
+
+    Ok(Identity {
+        user_id: claims.user_id,
+        scopes: claims.scopes,
+    })
+}
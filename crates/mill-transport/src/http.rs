@@ -0,0 +1,1064 @@
+//! MCP Streamable HTTP transport
+//!
+//! Serves the MCP "Streamable HTTP" transport on a single `/mcp` endpoint:
+//!
+//! - `POST` carries one JSON-RPC message. Notifications and responses are
+//!   acknowledged with `202 Accepted`. A request is answered with JSON or, when
+//!   the client accepts `text/event-stream`, with an SSE stream carrying the
+//!   request's notifications (such as progress) followed by its response.
+//! - `GET` opens an SSE stream for server-initiated messages that belong to no
+//!   request, such as resource updates.
+//! - `DELETE` ends the session.
+//!
+//! A session starts with `initialize`, whose response carries the
+//! `Mcp-Session-Id` header that later requests must send back. Authentication
+//! and TLS follow `AppConfig.server`, as for the WebSocket transport.
+
+use crate::auth::{authenticate_bearer, Identity};
+use crate::stdio::{cancellation_target, dispatch_message};
+use crate::{McpDispatcher, NotificationSender, SessionInfo};
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use futures_util::stream;
+use mill_auth::TokenScopes;
+use mill_config::config::{ServerConfig, TlsConfig};
+use mill_config::AppConfig;
+use mill_foundation::core::cancellation::Cancellation;
+use mill_foundation::core::model::mcp::{McpError, McpMessage, McpNotification, McpResponse};
+use mill_foundation::errors::{MillError, MillResult};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, watch};
+use tokio::task::{AbortHandle, JoinSet};
+use tokio_rustls::rustls;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+/// Path of the MCP endpoint
+pub const MCP_ENDPOINT: &str = "/mcp";
+
+/// Header carrying the session id
+const SESSION_HEADER: HeaderName = HeaderName::from_static("mcp-session-id");
+
+/// JSON-RPC error code for a request cancelled by the client
+const REQUEST_CANCELLED: i32 = -32800;
+
+/// Time allowed for a client to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time after which a session with no open stream or request is ended
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// How often idle sessions are looked for
+const SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Limit on concurrent sessions when `server.max_clients` is not set
+const MAX_SESSIONS: usize = 1000;
+
+/// Notifications buffered for a client that is not reading them; later ones
+/// are dropped
+const NOTIFICATION_QUEUE_CAPACITY: usize = 1000;
+
+/// One client session
+struct HttpSession {
+    /// The `Mcp-Session-Id` naming the session
+    id: String,
+    /// The authenticated user who created the session
+    user_id: Option<String>,
+    /// Server-initiated messages, queued for the GET stream
+    notifications: NotificationSender,
+    /// The queue behind `notifications`, lent to the GET stream while it is open
+    receiver: Mutex<Option<mpsc::Receiver<McpNotification>>>,
+    /// In-flight requests by JSON-encoded id, for `notifications/cancelled`
+    in_flight: Mutex<HashMap<String, (AbortHandle, Cancellation)>>,
+    /// Set when the session is deleted or expires, ending its GET stream
+    closed: watch::Sender<bool>,
+    /// When the client last used the session
+    last_seen: Mutex<Instant>,
+}
+
+impl HttpSession {
+    fn new(id: String, user_id: Option<String>) -> Self {
+        let (notifications, mut incoming) = mpsc::unbounded_channel::<McpNotification>();
+        let (queue, receiver) = mpsc::channel(NOTIFICATION_QUEUE_CAPACITY);
+        let closed = watch::Sender::new(false);
+
+        // The dispatcher sends without waiting, so a client that stops reading
+        // loses notifications rather than growing the queue without limit
+        let mut session_closed = closed.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let notification = tokio::select! {
+                    Some(notification) = incoming.recv() => notification,
+                    _ = session_closed.wait_for(|closed| *closed) => break,
+                    else => break,
+                };
+                match queue.try_send(notification) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(notification)) => {
+                        tracing::warn!(
+                            method = %notification.method,
+                            "Session notification queue full, dropping notification"
+                        );
+                    }
+                    Err(TrySendError::Closed(_)) => break,
+                }
+            }
+        });
+
+        Self {
+            id,
+            user_id,
+            notifications,
+            receiver: Mutex::new(Some(receiver)),
+            in_flight: Mutex::new(HashMap::new()),
+            closed,
+            last_seen: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Whether the session has gone unused since `cutoff`
+    ///
+    /// A session with an open stream or a running request is in use.
+    fn idle_since(&self, cutoff: Instant) -> bool {
+        *lock(&self.last_seen) < cutoff
+            && lock(&self.receiver).is_some()
+            && lock(&self.in_flight).is_empty()
+    }
+}
+
+#[derive(Clone)]
+struct HttpState {
+    config: Arc<AppConfig>,
+    dispatcher: Arc<dyn McpDispatcher>,
+    sessions: Arc<Mutex<HashMap<String, Arc<HttpSession>>>>,
+}
+
+/// Start the Streamable HTTP server
+pub async fn start_http_server(
+    config: Arc<AppConfig>,
+    dispatcher: Arc<dyn McpDispatcher>,
+) -> MillResult<()> {
+    // Enforce TLS for non-loopback hosts
+    let acceptor = match &config.server.tls {
+        Some(tls) => Some(tls_acceptor(tls)?),
+        None if !config.server.is_loopback_host() => {
+            return Err(MillError::bootstrap(format!(
+                "TLS is required when binding to non-loopback address '{}'. \
+                     Configure server.tls or bind to 127.0.0.1",
+                config.server.host
+            )));
+        }
+        None => {
+            tracing::warn!(
+                host = %config.server.host,
+                "HTTP server running without TLS on loopback. \
+                 Enable TLS in production environments."
+            );
+            None
+        }
+    };
+
+    let addr = format!("{}:{}", config.server.host, config.server.port);
+    let listener = TcpListener::bind(&addr)
+        .await
+        .map_err(|e| MillError::bootstrap(format!("Failed to bind to {}: {}", addr, e)))?;
+
+    let app = router(config, dispatcher);
+    let served = match acceptor {
+        Some(acceptor) => {
+            tracing::info!(
+                "MCP HTTP server listening on https://{}{}",
+                addr,
+                MCP_ENDPOINT
+            );
+            axum::serve(TlsListener::new(listener, acceptor), app).await
+        }
+        None => {
+            tracing::info!(
+                "MCP HTTP server listening on http://{}{}",
+                addr,
+                MCP_ENDPOINT
+            );
+            axum::serve(listener, app).await
+        }
+    };
+    served.map_err(|e| MillError::transport(format!("HTTP server error: {}", e)))
+}
+
+/// Router serving the MCP endpoint
+///
+/// Must be called within a Tokio runtime, which runs the idle session sweep.
+pub fn router(config: Arc<AppConfig>, dispatcher: Arc<dyn McpDispatcher>) -> Router {
+    let sessions = Arc::new(Mutex::new(HashMap::new()));
+    tokio::spawn(sweep_idle_sessions(Arc::downgrade(&sessions)));
+    Router::new()
+        .route(
+            MCP_ENDPOINT,
+            post(handle_post).get(handle_get).delete(handle_delete),
+        )
+        .with_state(HttpState {
+            config,
+            dispatcher,
+            sessions,
+        })
+}
+
+/// End idle sessions until the router is dropped
+async fn sweep_idle_sessions(sessions: Weak<Mutex<HashMap<String, Arc<HttpSession>>>>) {
+    let mut interval = tokio::time::interval(SESSION_SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(sessions) = sessions.upgrade() else {
+            return;
+        };
+        evict_idle_sessions(&mut lock(&sessions), Instant::now());
+    }
+}
+
+/// End the sessions idle for longer than [`SESSION_IDLE_TIMEOUT`] at `now`
+fn evict_idle_sessions(sessions: &mut HashMap<String, Arc<HttpSession>>, now: Instant) {
+    let Some(cutoff) = now.checked_sub(SESSION_IDLE_TIMEOUT) else {
+        return;
+    };
+    sessions.retain(|session_id, session| {
+        if !session.idle_since(cutoff) {
+            return true;
+        }
+        session.closed.send_replace(true);
+        tracing::info!(session_id = %session_id, "MCP HTTP session expired");
+        false
+    });
+}
+
+/// Handle one JSON-RPC message from the client
+async fn handle_post(State(state): State<HttpState>, headers: HeaderMap, body: Bytes) -> Response {
+    let identity = match admit(&state, &headers) {
+        Ok(identity) => identity,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    let message: McpMessage = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to parse MCP message");
+            let response = error_response(None, -32700, format!("Parse error: {}", e));
+            return (StatusCode::BAD_REQUEST, Json(response)).into_response();
+        }
+    };
+
+    let request_id = match &message {
+        McpMessage::Request(request) => request.id.clone(),
+        _ => None,
+    };
+    let initialize = matches!(
+        &message,
+        McpMessage::Request(request) if request.method == "initialize" && request.id.is_some()
+    );
+
+    let found = if initialize {
+        create_session(&state, identity.user_id.clone())
+    } else {
+        find_session(&state, &headers, &identity.user_id)
+    };
+    let (session_id, session) = match found {
+        Ok(found) => found,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    // Cancellation is handled here rather than by the dispatcher
+    if let Some(target) = cancellation_target(&message) {
+        let mut in_flight = lock(&session.in_flight);
+        let cancelled = target.and_then(|key| {
+            let (_, cancellation) = in_flight.get(&key)?;
+            Some((cancellation.cancel(), key))
+        });
+        match cancelled {
+            // A request that has begun writing files finishes instead
+            Some((true, key)) => {
+                if let Some((handle, _)) = in_flight.remove(&key) {
+                    tracing::info!("Cancelling in-flight request");
+                    handle.abort();
+                }
+            }
+            Some((false, _)) => tracing::info!("Request is applying changes, letting it finish"),
+            None => {}
+        }
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    // Notifications and responses are only acknowledged
+    let Some(id) = request_id else {
+        let session_info = SessionInfo {
+            user_id: session.user_id.clone(),
+            session_id: Some(session.id.clone()),
+            scopes: identity.scopes,
+            notifications: Some(session.notifications.clone()),
+        };
+        dispatch_message(state.dispatcher.as_ref(), message, &session_info, None).await;
+        return StatusCode::ACCEPTED.into_response();
+    };
+
+    let mut events = spawn_request(
+        &session,
+        identity.scopes,
+        state.dispatcher.clone(),
+        message,
+        id.clone(),
+    );
+
+    let mut response = if accepts_event_stream(&headers) {
+        let stream = stream::unfold(events, |mut events| async move {
+            let message = events.recv().await?;
+            Some((Ok::<_, Infallible>(sse_event(&message)), events))
+        });
+        Sse::new(stream).into_response()
+    } else {
+        // Notifications raised while the request runs go to the session's stream
+        let mut result = None;
+        while let Some(message) = events.recv().await {
+            match message {
+                McpMessage::Notification(notification) => {
+                    let _ = session.notifications.send(notification);
+                }
+                message => result = Some(message),
+            }
+        }
+        let result = result.unwrap_or_else(|| {
+            McpMessage::Response(error_response(
+                Some(id),
+                REQUEST_CANCELLED,
+                "Request cancelled".to_string(),
+            ))
+        });
+        Json(result).into_response()
+    };
+
+    if initialize {
+        if let Ok(value) = HeaderValue::from_str(&session_id) {
+            response.headers_mut().insert(SESSION_HEADER, value);
+        }
+    }
+    response
+}
+
+/// Open the session's stream of server-initiated messages
+async fn handle_get(State(state): State<HttpState>, headers: HeaderMap) -> Response {
+    let identity = match admit(&state, &headers) {
+        Ok(identity) => identity,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let session = match find_session(&state, &headers, &identity.user_id) {
+        Ok((_, session)) => session,
+        Err(rejection) => return rejection.into_response(),
+    };
+    if !accepts_event_stream(&headers) {
+        return (
+            StatusCode::NOT_ACCEPTABLE,
+            "Accept must include text/event-stream",
+        )
+            .into_response();
+    }
+
+    let Some(receiver) = lock(&session.receiver).take() else {
+        return (StatusCode::CONFLICT, "Session already has an open stream").into_response();
+    };
+    let lease = StreamLease {
+        closed: session.closed.subscribe(),
+        session,
+        receiver: Some(receiver),
+    };
+    let stream = stream::unfold(lease, |mut lease| async move {
+        let receiver = lease.receiver.as_mut()?;
+        let notification = tokio::select! {
+            notification = receiver.recv() => notification?,
+            _ = lease.closed.wait_for(|closed| *closed) => return None,
+        };
+        let event = sse_event(&McpMessage::Notification(notification));
+        Some((Ok::<_, Infallible>(event), lease))
+    });
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// End a session
+async fn handle_delete(State(state): State<HttpState>, headers: HeaderMap) -> Response {
+    let identity = match admit(&state, &headers) {
+        Ok(identity) => identity,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let (session_id, session) = match find_session(&state, &headers, &identity.user_id) {
+        Ok(found) => found,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    lock(&state.sessions).remove(&session_id);
+    session.closed.send_replace(true);
+    for (_, (handle, cancellation)) in lock(&session.in_flight).drain() {
+        if cancellation.cancel() {
+            handle.abort();
+        }
+    }
+    tracing::info!(session_id = %session_id, "MCP HTTP session ended");
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Run a request on its own task
+///
+/// The returned channel yields the notifications the request raises, then its
+/// response; it closes without a response if the request is cancelled. Once
+/// the response is sent, later notifications (e.g. for resource subscriptions
+/// made by the request) are forwarded to the session's stream. Closing the
+/// channel early cancels the request, unless it has begun applying changes,
+/// in which case it runs to completion. The request runs with the `scopes` of
+/// the token that sent it.
+fn spawn_request(
+    session: &Arc<HttpSession>,
+    scopes: Option<TokenScopes>,
+    dispatcher: Arc<dyn McpDispatcher>,
+    message: McpMessage,
+    id: Value,
+) -> mpsc::Receiver<McpMessage> {
+    let (events_tx, events_rx) = mpsc::channel(NOTIFICATION_QUEUE_CAPACITY);
+    let (notification_tx, mut notification_rx) = mpsc::unbounded_channel();
+    let session_info = SessionInfo {
+        user_id: session.user_id.clone(),
+        session_id: Some(session.id.clone()),
+        scopes,
+        notifications: Some(notification_tx),
+    };
+    let key = id.to_string();
+    let task_session = session.clone();
+    let task_key = key.clone();
+    let cancellation = Cancellation::new();
+    let task_cancellation = cancellation.clone();
+
+    // Registered under the lock so the task cannot finish before it is tracked
+    let mut in_flight = lock(&session.in_flight);
+    let handle = tokio::spawn(async move {
+        let response = {
+            let request = task_cancellation.clone().scope(dispatch_message(
+                dispatcher.as_ref(),
+                message,
+                &session_info,
+                Some(id),
+            ));
+            let mut request = std::pin::pin!(request);
+            let mut client_gone = false;
+            loop {
+                tokio::select! {
+                    response = &mut request => break response,
+                    Some(notification) = notification_rx.recv() => {
+                        send_notification(&events_tx, notification);
+                    }
+                    _ = events_tx.closed(), if !client_gone => {
+                        if task_cancellation.cancel() {
+                            tracing::debug!("Client went away, cancelling request");
+                            finish_request(&task_session, &task_key);
+                            return;
+                        }
+                        tracing::debug!(
+                            "Client went away, finishing request that is applying changes"
+                        );
+                        client_gone = true;
+                    }
+                }
+            }
+        };
+        drop(session_info);
+        finish_request(&task_session, &task_key);
+
+        while let Ok(notification) = notification_rx.try_recv() {
+            send_notification(&events_tx, notification);
+        }
+        let _ = events_tx.send(response).await;
+        drop(events_tx);
+
+        let session_notifications = task_session.notifications.clone();
+        drop(task_session);
+        while let Some(notification) = notification_rx.recv().await {
+            if session_notifications.send(notification).is_err() {
+                break;
+            }
+        }
+    });
+    if in_flight
+        .insert(key, (handle.abort_handle(), cancellation))
+        .is_some()
+    {
+        tracing::warn!("Request id reused while in flight; only the latest can be cancelled");
+    }
+
+    events_rx
+}
+
+/// Queue a request's notification for its client, dropping it if the client
+/// is not keeping up
+fn send_notification(events: &mpsc::Sender<McpMessage>, notification: McpNotification) {
+    if let Err(TrySendError::Full(_)) = events.try_send(McpMessage::Notification(notification)) {
+        tracing::warn!("Request event queue full, dropping notification");
+    }
+}
+
+/// Stop tracking the current task's request, unless the id now belongs to a
+/// later request
+fn finish_request(session: &HttpSession, key: &str) {
+    let mut in_flight = lock(&session.in_flight);
+    if in_flight
+        .get(key)
+        .is_some_and(|(handle, _)| handle.id() == tokio::task::id())
+    {
+        in_flight.remove(key);
+    }
+    *lock(&session.last_seen) = Instant::now();
+}
+
+/// Origin and authentication checks for every request
+///
+/// Returns the authenticated identity, which is empty when authentication
+/// is not configured.
+fn admit(state: &HttpState, headers: &HeaderMap) -> Result<Identity, Rejection> {
+    if !host_allowed(&state.config.server, headers) {
+        tracing::warn!("HTTP request rejected: host not allowed");
+        return Err(Rejection(StatusCode::FORBIDDEN, "Host not allowed"));
+    }
+    if !origin_allowed(&state.config.server.allowed_origins, headers) {
+        tracing::warn!("HTTP request rejected: origin not allowed");
+        return Err(Rejection(StatusCode::FORBIDDEN, "Origin not allowed"));
+    }
+
+    let Some(auth_config) = &state.config.server.auth else {
+        return Ok(Identity::default());
+    };
+    let auth_header = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok());
+    authenticate_bearer(auth_config, auth_header).map_err(|rejection| {
+        tracing::warn!(reason = %rejection, "HTTP request rejected");
+        Rejection(StatusCode::UNAUTHORIZED, "Unauthorized")
+    })
+}
+
+/// Whether a host name refers to the local machine
+fn is_loopback_name(host: &str) -> bool {
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+/// Whether the `Host` header may address the server
+///
+/// A server bound to loopback is only reachable under a loopback name. Any
+/// other name means a page rebound its own domain to a local address (DNS
+/// rebinding), so its Origin would match its Host.
+fn host_allowed(server: &ServerConfig, headers: &HeaderMap) -> bool {
+    if !server.is_loopback_host() {
+        return true;
+    }
+    let Some(host) = headers.get(header::HOST) else {
+        // Browsers always send a Host
+        return true;
+    };
+    host.to_str()
+        .ok()
+        .and_then(|host| url::Url::parse(&format!("http://{}", host)).ok())
+        .is_some_and(|url| url.host_str().is_some_and(is_loopback_name))
+}
+
+/// Whether a browser `Origin` may call the server
+///
+/// Only loopback origins and those in `server.allowedOrigins` are accepted;
+/// comparing the Origin with the Host header would not stop DNS rebinding.
+/// Non-browser clients send no `Origin`.
+fn origin_allowed(allowed_origins: &[String], headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN) else {
+        return true;
+    };
+    let Some(origin) = origin
+        .to_str()
+        .ok()
+        .and_then(|origin| url::Url::parse(origin).ok())
+    else {
+        return false;
+    };
+    let Some(origin_host) = origin.host_str() else {
+        return false;
+    };
+    if is_loopback_name(origin_host) {
+        return true;
+    }
+
+    allowed_origins.iter().any(|allowed| {
+        url::Url::parse(allowed).is_ok_and(|allowed| allowed.origin() == origin.origin())
+    })
+}
+
+/// Start a session for `initialize`
+fn create_session(
+    state: &HttpState,
+    user_id: Option<String>,
+) -> Result<(String, Arc<HttpSession>), Rejection> {
+    let mut sessions = lock(&state.sessions);
+    evict_idle_sessions(&mut sessions, Instant::now());
+    let max_clients = state.config.server.max_clients.unwrap_or(MAX_SESSIONS);
+    if sessions.len() >= max_clients {
+        tracing::warn!(
+            current_sessions = sessions.len(),
+            max_clients = max_clients,
+            "Max clients limit reached, rejecting session"
+        );
+        return Err(Rejection(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Too many sessions",
+        ));
+    }
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let session = Arc::new(HttpSession::new(session_id.clone(), user_id));
+    sessions.insert(session_id.clone(), session.clone());
+    tracing::info!(session_id = %session_id, "MCP HTTP session started");
+    Ok((session_id, session))
+}
+
+/// The session named by the `Mcp-Session-Id` header
+fn find_session(
+    state: &HttpState,
+    headers: &HeaderMap,
+    user_id: &Option<String>,
+) -> Result<(String, Arc<HttpSession>), Rejection> {
+    let Some(session_id) = headers.get(SESSION_HEADER).and_then(|h| h.to_str().ok()) else {
+        return Err(Rejection(
+            StatusCode::BAD_REQUEST,
+            "Missing Mcp-Session-Id header",
+        ));
+    };
+    // A session is only visible to the user who started it
+    match lock(&state.sessions).get(session_id) {
+        Some(session) if &session.user_id == user_id => {
+            *lock(&session.last_seen) = Instant::now();
+            Ok((session_id.to_string(), session.clone()))
+        }
+        _ => Err(Rejection(StatusCode::NOT_FOUND, "Unknown session")),
+    }
+}
+
+/// A request refused before reaching the dispatcher
+struct Rejection(StatusCode, &'static str);
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        let Rejection(status, message) = self;
+        let mut response = (status, message).into_response();
+        if status == StatusCode::UNAUTHORIZED {
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static("Bearer realm=\"MCP\""),
+            );
+        }
+        response
+    }
+}
+
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.contains("text/event-stream"))
+}
+
+fn sse_event(message: &McpMessage) -> Event {
+    match serde_json::to_string(message) {
+        Ok(json) => Event::default().event("message").data(json),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to serialize message");
+            Event::default().comment("unserializable message")
+        }
+    }
+}
+
+fn error_response(id: Option<Value>, code: i32, message: String) -> McpResponse {
+    McpResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result: None,
+        error: Some(McpError {
+            code,
+            message,
+            data: None,
+        }),
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Lends a session's notification receiver to its GET stream, returning it when
+/// the stream ends so the client can reconnect
+struct StreamLease {
+    session: Arc<HttpSession>,
+    receiver: Option<mpsc::Receiver<McpNotification>>,
+    closed: watch::Receiver<bool>,
+}
+
+impl Drop for StreamLease {
+    fn drop(&mut self) {
+        if let Some(receiver) = self.receiver.take() {
+            *lock(&self.session.receiver) = Some(receiver);
+        }
+    }
+}
+
+/// Load the certificate chain and private key from `AppConfig.server.tls`
+fn tls_acceptor(tls: &TlsConfig) -> MillResult<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(&tls.cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| {
+            MillError::bootstrap(format!(
+                "Failed to read TLS certificate {}: {}",
+                tls.cert_path.display(),
+                e
+            ))
+        })?;
+    let key = PrivateKeyDer::from_pem_file(&tls.key_path).map_err(|e| {
+        MillError::bootstrap(format!(
+            "Failed to read TLS private key {}: {}",
+            tls.key_path.display(),
+            e
+        ))
+    })?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut server_config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| MillError::bootstrap(format!("Invalid TLS configuration: {}", e)))?;
+    server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// Accepts TCP connections and completes their TLS handshakes concurrently
+struct TlsListener {
+    tcp: TcpListener,
+    acceptor: TlsAcceptor,
+    handshakes: JoinSet<Option<(TlsStream<TcpStream>, SocketAddr)>>,
+}
+
+impl TlsListener {
+    fn new(tcp: TcpListener, acceptor: TlsAcceptor) -> Self {
+        Self {
+            tcp,
+            acceptor,
+            handshakes: JoinSet::new(),
+        }
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            tokio::select! {
+                accepted = self.tcp.accept() => match accepted {
+                    Ok((stream, addr)) => {
+                        let acceptor = self.acceptor.clone();
+                        self.handshakes.spawn(async move {
+                            match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                                Ok(Ok(stream)) => Some((stream, addr)),
+                                Ok(Err(e)) => {
+                                    tracing::debug!(client_addr = %addr, error = %e, "TLS handshake failed");
+                                    None
+                                }
+                                Err(_) => {
+                                    tracing::debug!(client_addr = %addr, "TLS handshake timed out");
+                                    None
+                                }
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Failed to accept connection");
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                },
+                Some(Ok(Some(connection))) = self.handshakes.join_next() => return connection,
+            }
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        self.tcp.local_addr()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use mill_config::config::{CacheConfig, LoggingConfig, LspConfig, ServerConfig};
+    use serde_json::json;
+
+    /// Echoes the method, first sending a notification for `notify`
+    struct EchoDispatcher;
+
+    #[async_trait]
+    impl McpDispatcher for EchoDispatcher {
+        async fn dispatch(
+            &self,
+            message: McpMessage,
+            session_info: &SessionInfo,
+        ) -> MillResult<McpMessage> {
+            let McpMessage::Request(request) = message else {
+                return Ok(message);
+            };
+            if request.method == "notify" {
+                if let Some(notifications) = &session_info.notifications {
+                    let _ = notifications.send(McpNotification {
+                        jsonrpc: "2.0".to_string(),
+                        method: "notifications/progress".to_string(),
+                        params: Some(json!({ "progressToken": 1, "progress": 1 })),
+                    });
+                }
+            }
+            Ok(McpMessage::Response(McpResponse {
+                jsonrpc: "2.0".to_string(),
+                id: request.id,
+                result: Some(json!({ "method": request.method })),
+                error: None,
+            }))
+        }
+    }
+
+    fn test_config() -> AppConfig {
+        AppConfig {
+            server: ServerConfig {
+                host: "127.0.0.1".to_string(),
+                port: 0,
+                max_clients: Some(10),
+                timeout_ms: 30000,
+                tls: None,
+                auth: None,
+                allowed_origins: Vec::new(),
+            },
+            lsp: LspConfig::default(),
+            fuse: None,
+            logging: LoggingConfig::default(),
+            cache: CacheConfig::default(),
+            plugin_selection: Default::default(),
+            git: Default::default(),
+            audit: Default::default(),
+            journal: Default::default(),
+            validation: Default::default(),
+            language_plugins: Default::default(),
+            #[cfg(feature = "mcp-proxy")]
+            external_mcp: None,
+        }
+    }
+
+    async fn serve_test_router() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = router(Arc::new(test_config()), Arc::new(EchoDispatcher));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{}{}", addr, MCP_ENDPOINT)
+    }
+
+    fn request(id: u64, method: &str) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method })
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let url = serve_test_router().await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(&url)
+            .json(&request(1, "initialize"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let session_id = response.headers()[SESSION_HEADER.as_str()]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["result"]["method"], "initialize");
+
+        // Requests outside a session are refused
+        let response = client
+            .post(&url)
+            .json(&request(2, "tools/list"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = client
+            .post(&url)
+            .header(SESSION_HEADER.as_str(), "no-such-session")
+            .json(&request(2, "tools/list"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Notifications get no response body
+        let response = client
+            .post(&url)
+            .header(SESSION_HEADER.as_str(), &session_id)
+            .json(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert!(response.text().await.unwrap().is_empty());
+
+        let response = client
+            .delete(&url)
+            .header(SESSION_HEADER.as_str(), &session_id)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = client
+            .post(&url)
+            .header(SESSION_HEADER.as_str(), &session_id)
+            .json(&request(3, "tools/list"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_carries_notifications_before_response() {
+        let url = serve_test_router().await;
+        let client = reqwest::Client::new();
+        let response = client
+            .post(&url)
+            .json(&request(1, "initialize"))
+            .send()
+            .await
+            .unwrap();
+        let session_id = response.headers()[SESSION_HEADER.as_str()].clone();
+
+        let response = client
+            .post(&url)
+            .header(SESSION_HEADER.as_str(), session_id)
+            .header(header::ACCEPT, "application/json, text/event-stream")
+            .json(&request(2, "notify"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE.as_str()],
+            "text/event-stream"
+        );
+        let body = response.text().await.unwrap();
+        let messages: Vec<Value> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["method"], "notifications/progress");
+        assert_eq!(messages[1]["id"], 2);
+        assert_eq!(messages[1]["result"]["method"], "notify");
+    }
+
+    #[tokio::test]
+    async fn test_idle_sessions_expire() {
+        let idle = Arc::new(HttpSession::new("idle".to_string(), None));
+        let streaming = Arc::new(HttpSession::new("streaming".to_string(), None));
+        let _stream = lock(&streaming.receiver).take();
+        let mut sessions = HashMap::from([
+            ("idle".to_string(), idle.clone()),
+            ("streaming".to_string(), streaming.clone()),
+        ]);
+
+        evict_idle_sessions(&mut sessions, Instant::now());
+        assert_eq!(sessions.len(), 2);
+
+        let later = Instant::now() + SESSION_IDLE_TIMEOUT + Duration::from_secs(1);
+        evict_idle_sessions(&mut sessions, later);
+        assert!(!sessions.contains_key("idle"));
+        assert!(*idle.closed.borrow());
+        assert!(sessions.contains_key("streaming"));
+        assert!(!*streaming.closed.borrow());
+    }
+
+    #[tokio::test]
+    async fn test_unread_notifications_are_dropped() {
+        let session = HttpSession::new("lossy".to_string(), None);
+        for progress in 0..NOTIFICATION_QUEUE_CAPACITY + 10 {
+            let _ = session.notifications.send(McpNotification {
+                jsonrpc: "2.0".to_string(),
+                method: "notifications/progress".to_string(),
+                params: Some(json!({ "progressToken": 1, "progress": progress })),
+            });
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut receiver = lock(&session.receiver).take().unwrap();
+        let mut queued = 0;
+        while receiver.try_recv().is_ok() {
+            queued += 1;
+        }
+        assert_eq!(queued, NOTIFICATION_QUEUE_CAPACITY);
+    }
+
+    #[test]
+    fn test_origin_allowed() {
+        let headers = |origin: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::HOST,
+                HeaderValue::from_static("mill.example.com:3040"),
+            );
+            headers.insert(header::ORIGIN, HeaderValue::from_str(origin).unwrap());
+            headers
+        };
+        let allowed = vec!["https://app.example.com".to_string()];
+        assert!(origin_allowed(&allowed, &HeaderMap::new()));
+        assert!(origin_allowed(&allowed, &headers("http://localhost:5173")));
+        assert!(origin_allowed(&allowed, &headers("https://app.example.com")));
+        assert!(!origin_allowed(&allowed, &headers("http://app.example.com")));
+        // Matching the Host header is not enough: that is what DNS rebinding does
+        assert!(!origin_allowed(&allowed, &headers("https://mill.example.com")));
+        assert!(!origin_allowed(&allowed, &headers("https://attacker.example")));
+        assert!(!origin_allowed(&allowed, &headers("null")));
+    }
+
+    #[test]
+    fn test_host_allowed() {
+        let headers = |host: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::HOST, HeaderValue::from_static(host));
+            headers
+        };
+        let mut server = test_config().server;
+        assert!(host_allowed(&server, &headers("127.0.0.1:3040")));
+        assert!(host_allowed(&server, &headers("localhost:3040")));
+        assert!(host_allowed(&server, &headers("[::1]:3040")));
+        assert!(!host_allowed(&server, &headers("evil.example:3040")));
+
+        server.host = "0.0.0.0".to_string();
+        assert!(host_allowed(&server, &headers("mill.example.com:3040")));
+    }
+}
@@ -1,14 +1,16 @@
 //! Transport layer implementations for WebSocket and stdio communication
 //!
 //! This crate provides transport mechanisms for the mill server,
-//! enabling communication via WebSocket (for production) and stdio
-//! (for MCP clients like Claude Code).
+//! enabling communication via WebSocket (for production), MCP Streamable
+//! HTTP (for remote MCP clients) and stdio (for local MCP clients).
 
 use async_trait::async_trait;
 use mill_foundation::core::model::mcp::McpMessage;
 use mill_foundation::errors::MillResult;
 
 pub mod admin;
+mod auth;
+pub mod http;
 pub mod session;
 pub mod stdio;
 #[cfg(unix)]
@@ -16,7 +18,8 @@ pub mod unix_socket;
 pub mod ws;
 
 pub use admin::start_admin_server;
-pub use session::SessionInfo;
+pub use http::start_http_server;
+pub use session::{NotificationSender, SessionInfo};
 pub use stdio::start_stdio_server;
 #[cfg(unix)]
 pub use unix_socket::{default_socket_path, is_daemon_running, UnixSocketClient, UnixSocketServer};
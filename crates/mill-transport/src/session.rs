@@ -1,7 +1,20 @@
 //! Session information for transport layer
 
+use mill_foundation::core::model::mcp::McpNotification;
+use tokio::sync::mpsc;
+
+/// Channel for pushing server-initiated notifications to a connected client
+pub type NotificationSender = mpsc::UnboundedSender<McpNotification>;
+
 #[derive(Debug, Clone, Default)]
 pub struct SessionInfo {
     /// The ID of the user making the request, for multi-tenancy.
     pub user_id: Option<String>,
+    /// The client session the request belongs to, if the transport has one.
+    pub session_id: Option<String>,
+    /// What the caller's token may do; `None` when unrestricted.
+    pub scopes: Option<mill_auth::TokenScopes>,
+    /// Sender for notifications to this session's client, if the transport
+    /// supports server-initiated messages.
+    pub notifications: Option<NotificationSender>,
 }
@@ -1,63 +1,200 @@
 //! Stdio transport implementation for MCP
 
 use crate::McpDispatcher;
+use mill_foundation::core::cancellation::Cancellation;
 use mill_foundation::core::model::mcp::{McpError, McpMessage, McpResponse};
 use mill_foundation::errors::ErrorResponse;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::task::{AbortHandle, JoinSet};
+use tracing::Instrument;
 use uuid::Uuid;
 
 /// Frame delimiter used to separate JSON messages
 /// Using a multi-character delimiter prevents confusion with newlines in error messages
 const FRAME_DELIMITER: &[u8] = b"\n---FRAME---\n";
 
+/// Environment variable selecting the framing: `auto` (default), `ndjson` or `frame`
+pub const FRAMING_ENV_VAR: &str = "TYPEMILL_STDIO_FRAMING";
+
+/// How long auto-detection waits for a frame delimiter after a one-line first message
+const DETECT_WINDOW: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// How messages are delimited on the stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Decide from the first message the client sends
+    Auto,
+    /// One JSON message per line, as the MCP specification requires
+    Ndjson,
+    /// Messages followed by `\n---FRAME---\n` (the original TypeMill framing)
+    Delimited,
+}
+
+impl Framing {
+    /// Framing from `TYPEMILL_STDIO_FRAMING`, defaulting to `Auto`
+    pub fn from_env() -> Self {
+        match std::env::var(FRAMING_ENV_VAR).as_deref() {
+            Ok("ndjson") => Framing::Ndjson,
+            Ok("frame") => Framing::Delimited,
+            Ok("auto") | Err(_) => Framing::Auto,
+            Ok(other) => {
+                tracing::warn!(value = other, "Unknown stdio framing, using auto-detection");
+                Framing::Auto
+            }
+        }
+    }
+}
+
 /// Stdio transport with message framing for reliable JSON parsing
+///
+/// In `Auto` mode, a first message spanning several lines, or followed by the
+/// frame delimiter, selects `Delimited`; otherwise `Ndjson` is used.
 pub struct StdioTransport<R, W> {
     reader: BufReader<R>,
     writer: W,
+    framing: Framing,
+    /// Bytes of the frame being read, kept across cancelled reads
+    buffer: Vec<u8>,
+    /// Bytes read past the first message during auto-detection
+    lookahead: Vec<u8>,
 }
 
 impl<R: tokio::io::AsyncRead + Unpin, W: tokio::io::AsyncWrite + Unpin> StdioTransport<R, W> {
-    /// Create a new StdioTransport
+    /// Create a new StdioTransport that detects the client's framing
     pub fn new(reader: R, writer: W) -> Self {
+        Self::with_framing(reader, writer, Framing::Auto)
+    }
+
+    /// Create a new StdioTransport with the given framing
+    pub fn with_framing(reader: R, writer: W, framing: Framing) -> Self {
         Self {
             reader: BufReader::new(reader),
             writer,
+            framing,
+            buffer: Vec::new(),
+            lookahead: Vec::new(),
         }
     }
 
+    /// The framing in use; `Auto` until the first message has been read
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
     /// Read a single framed message from the input
     /// Returns None if EOF is reached
+    ///
+    /// Cancel safe: a partially read frame is kept for the next call.
     pub async fn read_message(&mut self) -> Result<Option<String>, std::io::Error> {
-        let mut buffer = Vec::new();
+        match self.framing {
+            Framing::Ndjson => self.read_line_message().await,
+            Framing::Delimited => self.read_delimited_message().await,
+            Framing::Auto => self.detect_and_read_message().await,
+        }
+    }
+
+    async fn read_delimited_message(&mut self) -> Result<Option<String>, std::io::Error> {
         let delimiter = FRAME_DELIMITER;
 
         loop {
-            let bytes_read = self.reader.read_until(b'\n', &mut buffer).await?;
+            // Check if we've reached the delimiter
+            if self.buffer.ends_with(delimiter) {
+                let mut buffer = std::mem::take(&mut self.buffer);
+                // Remove the delimiter
+                buffer.truncate(buffer.len() - delimiter.len());
+                let message = String::from_utf8_lossy(&buffer).trim().to_string();
+                return Ok(Some(message));
+            }
+
+            let bytes_read = self.reader.read_until(b'\n', &mut self.buffer).await?;
 
             if bytes_read == 0 {
                 // EOF reached
-                if buffer.is_empty() {
+                if self.buffer.is_empty() {
                     return Ok(None);
                 }
                 // Return whatever we have buffered
+                let buffer = std::mem::take(&mut self.buffer);
                 return Ok(Some(String::from_utf8_lossy(&buffer).to_string()));
             }
+        }
+    }
 
-            // Check if we've reached the delimiter
-            if buffer.ends_with(delimiter) {
-                // Remove the delimiter
-                buffer.truncate(buffer.len() - delimiter.len());
-                let message = String::from_utf8_lossy(&buffer).trim().to_string();
+    async fn read_line_message(&mut self) -> Result<Option<String>, std::io::Error> {
+        loop {
+            if !self.buffer.ends_with(b"\n")
+                && self.reader.read_until(b'\n', &mut self.buffer).await? == 0
+                && self.buffer.is_empty()
+            {
+                return Ok(None);
+            }
+            let line = std::mem::take(&mut self.buffer);
+            let message = String::from_utf8_lossy(&line).trim().to_string();
+            if !message.is_empty() {
                 return Ok(Some(message));
             }
         }
     }
 
+    async fn detect_and_read_message(&mut self) -> Result<Option<String>, std::io::Error> {
+        // Read the first non-blank line
+        loop {
+            if !self.buffer.ends_with(b"\n") {
+                let bytes_read = self.reader.read_until(b'\n', &mut self.buffer).await?;
+                if bytes_read == 0 && self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                if bytes_read == 0 {
+                    break;
+                }
+            }
+            if !self.buffer.iter().all(u8::is_ascii_whitespace) {
+                break;
+            }
+            self.buffer.clear();
+        }
+
+        // A first line that is not a complete message means a multi-line frame
+        if serde_json::from_slice::<serde_json::Value>(&self.buffer).is_err() {
+            tracing::debug!("Detected delimited stdio framing");
+            self.framing = Framing::Delimited;
+            return self.read_delimited_message().await;
+        }
+
+        // A delimited client sends the delimiter right after the message
+        let lookahead = tokio::time::timeout(
+            DETECT_WINDOW,
+            self.reader.read_until(b'\n', &mut self.lookahead),
+        )
+        .await;
+        if let Ok(result) = lookahead {
+            result?;
+        }
+        if self.lookahead.as_slice() == &FRAME_DELIMITER[1..] {
+            tracing::debug!("Detected delimited stdio framing");
+            self.framing = Framing::Delimited;
+            self.buffer.pop();
+            self.buffer.extend_from_slice(FRAME_DELIMITER);
+            self.lookahead.clear();
+            return self.read_delimited_message().await;
+        }
+
+        tracing::debug!("Detected newline-delimited stdio framing");
+        self.framing = Framing::Ndjson;
+        let message = String::from_utf8_lossy(&self.buffer).trim().to_string();
+        self.buffer = std::mem::take(&mut self.lookahead);
+        Ok(Some(message))
+    }
+
     /// Write a framed message to the output
     pub async fn write_message(&mut self, message: &str) -> Result<(), std::io::Error> {
         self.writer.write_all(message.as_bytes()).await?;
-        self.writer.write_all(FRAME_DELIMITER).await?;
+        match self.framing {
+            Framing::Delimited => self.writer.write_all(FRAME_DELIMITER).await?,
+            Framing::Ndjson | Framing::Auto => self.writer.write_all(b"\n").await?,
+        }
         self.writer.flush().await?;
         Ok(())
     }
@@ -65,7 +202,16 @@ impl<R: tokio::io::AsyncRead + Unpin, W: tokio::io::AsyncWrite + Unpin> StdioTra
 
 use crate::SessionInfo;
 
+/// MCP notification asking the server to abandon an in-flight request
+const CANCELLED_NOTIFICATION: &str = "notifications/cancelled";
+
 /// Start the stdio MCP server
+///
+/// Each request is dispatched on its own task, so a slow request does not hold
+/// up the others; responses are written as they complete, matched to their
+/// request by id. `notifications/cancelled` aborts the matching task.
+///
+/// The framing is auto-detected unless `TYPEMILL_STDIO_FRAMING` selects one.
 pub async fn start_stdio_server(
     dispatcher: Arc<dyn McpDispatcher>,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -73,15 +219,70 @@ pub async fn start_stdio_server(
 
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
-    let mut transport = StdioTransport::new(stdin, stdout);
+    let transport = StdioTransport::with_framing(stdin, stdout, Framing::from_env());
 
     tracing::info!("TypeMill Server running on stdio");
 
-    // For stdio, there is no user context, so we use a default SessionInfo.
-    let session_info = SessionInfo::default();
+    serve(transport, dispatcher).await?;
+
+    tracing::info!("Stdio server stopped");
+    Ok(())
+}
+
+/// Serve MCP messages on a transport until its input ends
+async fn serve<R, W>(
+    mut transport: StdioTransport<R, W>,
+    dispatcher: Arc<dyn McpDispatcher>,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    // Server-initiated notifications are written between responses
+    let (notification_tx, mut notification_rx) = tokio::sync::mpsc::unbounded_channel();
+    // Responses from request tasks, in completion order
+    let (response_tx, mut response_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+
+    // For stdio, there is no user context
+    let session_info = SessionInfo {
+        session_id: Some(uuid::Uuid::new_v4().to_string()),
+        notifications: Some(notification_tx),
+        ..SessionInfo::default()
+    };
+
+    let mut tasks = JoinSet::new();
+    // In-flight requests by JSON-encoded id, for cancellation
+    let mut in_flight: HashMap<String, (AbortHandle, Cancellation)> = HashMap::new();
+    let mut task_ids: HashMap<tokio::task::Id, String> = HashMap::new();
 
     loop {
-        let message = match transport.read_message().await {
+        let next = tokio::select! {
+            next = transport.read_message() => next,
+            Some(notification) = notification_rx.recv() => {
+                let notification_json =
+                    serde_json::to_string(&McpMessage::Notification(notification))?;
+                transport.write_message(&notification_json).await?;
+                continue;
+            }
+            Some(response_json) = response_rx.recv() => {
+                transport.write_message(&response_json).await?;
+                continue;
+            }
+            Some(finished) = tasks.join_next_with_id() => {
+                let task_id = match finished {
+                    Ok((task_id, ())) => task_id,
+                    Err(e) => e.id(),
+                };
+                // The id may have been reused by a later request
+                if let Some(key) = task_ids.remove(&task_id) {
+                    if in_flight.get(&key).is_some_and(|(handle, _)| handle.id() == task_id) {
+                        in_flight.remove(&key);
+                    }
+                }
+                continue;
+            }
+        };
+        let message = match next {
             Ok(Some(msg)) => msg,
             Ok(None) => {
                 // EOF reached
@@ -105,15 +306,15 @@ pub async fn start_stdio_server(
 
         // Create request span for automatic context propagation
         let span = mill_config::logging::request_span(&request_id.to_string(), "stdio");
-        let _enter = span.enter();
 
-        tracing::debug!(message_length = message.len(), "Received framed message");
+        tracing::debug!(parent: &span, message_length = message.len(), "Received framed message");
 
         // Parse the JSON-RPC message
         let mcp_message: McpMessage = match serde_json::from_str(&message) {
             Ok(msg) => msg,
             Err(e) => {
                 tracing::error!(
+                    parent: &span,
                     request_id = %request_id,
                     error = %e,
                     message_preview = &message[..message.len().min(100)],
@@ -136,47 +337,306 @@ pub async fn start_stdio_server(
             }
         };
 
+        // Cancellation is handled here rather than by the dispatcher, and gets no response
+        if let Some(target) = cancellation_target(&mcp_message) {
+            match target.and_then(|key| in_flight.get(&key)) {
+                // A request that has begun writing files finishes instead
+                Some((handle, cancellation)) if cancellation.cancel() => {
+                    tracing::info!(parent: &span, "Cancelling in-flight request");
+                    handle.abort();
+                }
+                Some(_) => {
+                    tracing::info!(parent: &span, "Request is applying changes, letting it finish")
+                }
+                None => {
+                    tracing::debug!(parent: &span, "Cancellation for unknown or finished request")
+                }
+            }
+            continue;
+        }
+
         // Extract the ID from the original message for error responses
         let message_id = match &mcp_message {
             McpMessage::Request(req) => req.id.clone(),
             _ => None,
         };
 
-        // Handle the message
-        let response = match dispatcher.dispatch(mcp_message, &session_info).await {
-            Ok(response) => response,
-            Err(e) => {
-                // Convert to structured API error
-                let api_error: ErrorResponse = e.into();
+        // Newline-delimited clients follow the spec: only requests with an id get a reply
+        let reply = transport.framing() != Framing::Ndjson || message_id.is_some();
 
-                tracing::error!(
-                    request_id = %request_id,
-                    error_code = %api_error.code,
-                    error = %api_error.message,
-                    "Failed to handle message"
+        let dispatcher = dispatcher.clone();
+        let session_info = session_info.clone();
+        let response_tx = response_tx.clone();
+        let task_message_id = message_id.clone();
+        let cancellation = Cancellation::new();
+        let task_cancellation = cancellation.clone();
+        let handle = tasks.spawn(
+            async move {
+                let response = task_cancellation
+                    .scope(dispatch_message(
+                        dispatcher.as_ref(),
+                        mcp_message,
+                        &session_info,
+                        task_message_id,
+                    ))
+                    .await;
+                if !reply {
+                    return;
+                }
+                match serde_json::to_string(&response) {
+                    Ok(response_json) => {
+                        let _ = response_tx.send(response_json);
+                    }
+                    Err(e) => tracing::error!(error = %e, "Failed to serialize response"),
+                }
+            }
+            .instrument(span),
+        );
+        if let Some(id) = message_id {
+            let key = id.to_string();
+            task_ids.insert(handle.id(), key.clone());
+            if in_flight.insert(key, (handle, cancellation)).is_some() {
+                tracing::warn!(
+                    request_id = %id,
+                    "Request id reused while in flight; only the latest can be cancelled"
                 );
+            }
+        }
+    }
 
-                // Serialize the structured error to JSON for the data field
-                let error_data = serde_json::to_value(&api_error).ok();
+    // Finish the requests already received before shutting down
+    while tasks.join_next().await.is_some() {}
+    drop(response_tx);
+    while let Some(response_json) = response_rx.recv().await {
+        transport.write_message(&response_json).await?;
+    }
 
-                McpMessage::Response(McpResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: message_id,
-                    result: None,
-                    error: Some(McpError {
-                        code: -1,
-                        message: api_error.message.clone(),
-                        data: error_data,
-                    }),
-                })
+    Ok(())
+}
+
+/// For a `notifications/cancelled` message, the JSON-encoded id of the request to cancel
+///
+/// Notifications usually deserialize as id-less requests, so both shapes are checked.
+pub(crate) fn cancellation_target(message: &McpMessage) -> Option<Option<String>> {
+    let params = match message {
+        McpMessage::Request(request)
+            if request.id.is_none() && request.method == CANCELLED_NOTIFICATION =>
+        {
+            &request.params
+        }
+        McpMessage::Notification(notification) if notification.method == CANCELLED_NOTIFICATION => {
+            &notification.params
+        }
+        _ => return None,
+    };
+    Some(
+        params
+            .as_ref()
+            .and_then(|params| params.get("requestId"))
+            .map(|id| id.to_string()),
+    )
+}
+
+/// Dispatch one message, turning dispatcher errors into JSON-RPC error responses
+pub(crate) async fn dispatch_message(
+    dispatcher: &dyn McpDispatcher,
+    message: McpMessage,
+    session_info: &SessionInfo,
+    message_id: Option<serde_json::Value>,
+) -> McpMessage {
+    match dispatcher.dispatch(message, session_info).await {
+        Ok(response) => response,
+        Err(e) => {
+            // Convert to structured API error
+            let api_error: ErrorResponse = e.into();
+
+            tracing::error!(
+                error_code = %api_error.code,
+                error = %api_error.message,
+                "Failed to handle message"
+            );
+
+            // Serialize the structured error to JSON for the data field
+            let error_data = serde_json::to_value(&api_error).ok();
+
+            McpMessage::Response(McpResponse {
+                jsonrpc: "2.0".to_string(),
+                id: message_id,
+                result: None,
+                error: Some(McpError {
+                    code: -1,
+                    message: api_error.message.clone(),
+                    data: error_data,
+                }),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use mill_foundation::errors::MillResult;
+    use serde_json::json;
+    use tokio::io::AsyncReadExt;
+
+    /// Answers `slow` after a long delay, `apply` after applying changes for a
+    /// moment, and everything else immediately
+    struct DelayDispatcher;
+
+    #[async_trait]
+    impl McpDispatcher for DelayDispatcher {
+        async fn dispatch(
+            &self,
+            message: McpMessage,
+            _session_info: &SessionInfo,
+        ) -> MillResult<McpMessage> {
+            let McpMessage::Request(request) = message else {
+                return Ok(message);
+            };
+            if request.method == "slow" {
+                tokio::time::sleep(std::time::Duration::from_secs(30)).await;
             }
+            if request.method == "apply" {
+                assert!(mill_foundation::core::cancellation::begin_apply());
+                tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+            }
+            Ok(McpMessage::Response(McpResponse {
+                jsonrpc: "2.0".to_string(),
+                id: request.id,
+                result: Some(json!({ "method": request.method })),
+                error: None,
+            }))
+        }
+    }
+
+    fn frame(message: serde_json::Value) -> Vec<u8> {
+        let mut bytes = message.to_string().into_bytes();
+        bytes.extend_from_slice(FRAME_DELIMITER);
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_requests_run_concurrently_and_can_be_cancelled() {
+        let mut input = Vec::new();
+        input.extend(frame(
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "slow" }),
+        ));
+        input.extend(frame(
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "fast" }),
+        ));
+        input.extend(frame(json!({
+            "jsonrpc": "2.0",
+            "method": CANCELLED_NOTIFICATION,
+            "params": { "requestId": 1, "reason": "user cancelled" }
+        })));
+
+        let (output_writer, mut output_reader) = tokio::io::duplex(64 * 1024);
+        let transport = StdioTransport::new(input.as_slice(), output_writer);
+
+        // Returns once the cancelled request is gone, long before `slow` would finish
+        tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            serve(transport, Arc::new(DelayDispatcher)),
+        )
+        .await
+        .expect("cancelled request kept the server busy")
+        .unwrap();
+
+        let mut output = String::new();
+        output_reader.read_to_string(&mut output).await.unwrap();
+        let responses: Vec<serde_json::Value> = output
+            .split("\n---FRAME---\n")
+            .filter(|frame| !frame.is_empty())
+            .map(|frame| serde_json::from_str(frame).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0]["id"], 2);
+        assert_eq!(responses[0]["result"]["method"], "fast");
+    }
+
+    #[tokio::test]
+    async fn test_requests_applying_changes_finish_despite_cancellation() {
+        let (mut input_writer, input_reader) = tokio::io::duplex(64 * 1024);
+        let (output_writer, mut output_reader) = tokio::io::duplex(64 * 1024);
+        let server = serve(
+            StdioTransport::new(input_reader, output_writer),
+            Arc::new(DelayDispatcher),
+        );
+
+        // Cancel once the request has begun applying its changes
+        let client = async move {
+            input_writer
+                .write_all(&frame(
+                    json!({ "jsonrpc": "2.0", "id": 1, "method": "apply" }),
+                ))
+                .await
+                .unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            input_writer
+                .write_all(&frame(json!({
+                    "jsonrpc": "2.0",
+                    "method": CANCELLED_NOTIFICATION,
+                    "params": { "requestId": 1 }
+                })))
+                .await
+                .unwrap();
         };
+        let (served, ()) = tokio::join!(server, client);
+        served.unwrap();
 
-        // Send response with framing
-        let response_json = serde_json::to_string(&response)?;
-        transport.write_message(&response_json).await?;
+        let mut output = String::new();
+        output_reader.read_to_string(&mut output).await.unwrap();
+        let response: serde_json::Value =
+            serde_json::from_str(output.trim_end_matches("\n---FRAME---\n")).unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["method"], "apply");
     }
 
-    tracing::info!("Stdio server stopped");
-    Ok(())
+    #[tokio::test]
+    async fn test_newline_delimited_input_is_detected_and_notifications_get_no_reply() {
+        let input = [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" }),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+        ]
+        .iter()
+        .map(|message| format!("{}\n", message))
+        .collect::<String>();
+
+        let (output_writer, mut output_reader) = tokio::io::duplex(64 * 1024);
+        let transport = StdioTransport::new(input.as_bytes(), output_writer);
+        serve(transport, Arc::new(DelayDispatcher)).await.unwrap();
+
+        let mut output = String::new();
+        output_reader.read_to_string(&mut output).await.unwrap();
+        assert!(!output.contains("---FRAME---"));
+        let ids: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["id"].clone())
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&json!(1)) && ids.contains(&json!(2)));
+    }
+
+    #[tokio::test]
+    async fn test_single_line_frames_are_detected_as_delimited() {
+        let mut input = frame(json!({ "jsonrpc": "2.0", "id": 1, "method": "a" }));
+        input.extend(frame(json!({ "jsonrpc": "2.0", "id": 2, "method": "b" })));
+
+        let mut transport = StdioTransport::new(input.as_slice(), Vec::new());
+        let first = transport.read_message().await.unwrap().unwrap();
+        assert_eq!(transport.framing(), Framing::Delimited);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&first).unwrap()["id"],
+            1
+        );
+        let second = transport.read_message().await.unwrap().unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&second).unwrap()["id"],
+            2
+        );
+        assert_eq!(transport.read_message().await.unwrap(), None);
+    }
 }
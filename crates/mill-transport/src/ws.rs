@@ -1,10 +1,13 @@
 //! WebSocket transport implementation
 
+use crate::auth::{authenticate_bearer, Identity};
 use crate::{McpDispatcher, SessionInfo};
-use futures_util::{SinkExt, StreamExt};
-use mill_auth::jwt::{decode, Claims, DecodingKey, Validation};
+use futures_util::{Sink, SinkExt, StreamExt};
+use mill_auth::TokenScopes;
 use mill_config::AppConfig;
-use mill_foundation::core::model::mcp::{McpError, McpMessage, McpRequest, McpResponse};
+use mill_foundation::core::model::mcp::{
+    McpError, McpMessage, McpNotification, McpRequest, McpResponse,
+};
 use mill_foundation::errors::{ErrorResponse, MillError, MillResult};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -59,6 +62,8 @@ pub struct Session {
     pub initialized: bool,
     /// The ID of the user for this session.
     pub user_id: Option<String>,
+    /// What the session's token may do; `None` when unrestricted.
+    pub scopes: Option<TokenScopes>,
 }
 
 impl Session {
@@ -69,6 +74,7 @@ impl Session {
             project_root: None,
             initialized: false,
             user_id: None,
+            scopes: None,
         }
     }
 }
@@ -174,64 +180,32 @@ async fn handle_connection(
         .peer_addr()
         .unwrap_or_else(|_| "unknown".parse().unwrap());
 
-    let mut user_id_from_token: Option<String> = None;
+    let mut identity_from_token = Identity::default();
     let config_clone = config.clone();
 
     // Perform WebSocket handshake with authorization header validation
     let ws_stream = match accept_hdr_async(stream, |req: &Request, response: Response| {
         // Check if authentication is required
         if let Some(auth_config) = &config_clone.server.auth {
-            // Extract Authorization header
             let auth_header = req
                 .headers()
                 .get("Authorization")
                 .and_then(|h| h.to_str().ok());
 
-            if let Some(auth_value) = auth_header {
-                // Check for Bearer token
-                if let Some(token) = auth_value.strip_prefix("Bearer ") {
-                    // Decode token to validate and extract user_id
-                    let key = DecodingKey::from_secret(auth_config.jwt_secret.as_ref());
-                    let mut validation = Validation::default();
-
-                    // Use config to determine if audience validation is enabled
-                    validation.validate_aud = auth_config.validate_audience;
-
-                    if auth_config.validate_audience {
-                        let audience = auth_config
-                            .jwt_audience_override
-                            .as_ref()
-                            .unwrap_or(&auth_config.jwt_audience);
-                        validation.set_audience(&[audience]);
-                    }
-
-                    match decode::<Claims>(token, &key, &validation) {
-                        Ok(token_data) => {
-                            tracing::debug!("WebSocket connection authenticated");
-
-                            // Warn if project_id is missing (deprecation path)
-                            if token_data.claims.project_id.is_none() {
-                                tracing::warn!(
-                                    "WebSocket connection authenticated but token missing project_id claim - this will be required in future versions"
-                                );
-                            }
-
-                            user_id_from_token = token_data.claims.user_id;
-                            return Ok(response);
-                        }
-                        Err(e) => {
-                            tracing::warn!(error = %e, "WebSocket connection rejected: token validation failed");
-                        }
-                    }
-                } else {
-                    tracing::warn!("WebSocket connection rejected: malformed Authorization header");
+            match authenticate_bearer(auth_config, auth_header) {
+                Ok(identity) => {
+                    tracing::debug!("WebSocket connection authenticated");
+                    identity_from_token = identity;
+                    return Ok(response);
+                }
+                Err(rejection) => {
+                    tracing::warn!(reason = %rejection, "WebSocket connection rejected");
                 }
-            } else {
-                tracing::warn!("WebSocket connection rejected: missing Authorization header");
             }
 
             // Reject with 401 Unauthorized
-            let mut error_response: HttpResponse<Option<String>> = HttpResponse::new(Some("Unauthorized".to_string()));
+            let mut error_response: HttpResponse<Option<String>> =
+                HttpResponse::new(Some("Unauthorized".to_string()));
             *error_response.status_mut() = StatusCode::UNAUTHORIZED;
             error_response.headers_mut().insert(
                 "WWW-Authenticate",
@@ -259,12 +233,25 @@ async fn handle_connection(
     tracing::info!("WebSocket connection established");
     let (mut write, mut read) = ws_stream.split();
     let mut session = Session::new();
-    session.user_id = user_id_from_token;
+    session.user_id = identity_from_token.user_id;
+    session.scopes = identity_from_token.scopes;
+
+    // Server-initiated notifications, written as soon as they are raised
+    let (notification_tx, mut notification_rx) = tokio::sync::mpsc::unbounded_channel();
 
     // Message processing loop with idle timeout
     const IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300); // 5 minutes
     loop {
-        let msg = match tokio::time::timeout(IDLE_TIMEOUT, read.next()).await {
+        let next = tokio::select! {
+            next = tokio::time::timeout(IDLE_TIMEOUT, read.next()) => next,
+            Some(notification) = notification_rx.recv() => {
+                if !send_notification(&mut write, notification).await {
+                    break;
+                }
+                continue;
+            }
+        };
+        let msg = match next {
             Ok(Some(msg)) => msg,
             Ok(None) => {
                 tracing::info!("WebSocket connection closed by client");
@@ -318,18 +305,36 @@ async fn handle_connection(
                 // Create session info for this request
                 let session_info = SessionInfo {
                     user_id: session.user_id.clone(),
+                    session_id: Some(session.id.clone()),
+                    scopes: session.scopes.clone(),
+                    notifications: Some(notification_tx.clone()),
                 };
 
-                // Handle the message
-                let response = match handle_message(
+                // Handle the message, writing its notifications while it runs so
+                // that they reach the client before the response
+                let handling = handle_message(
                     &mut session,
                     mcp_message,
                     &config,
                     dispatcher.as_ref(),
                     &session_info,
-                )
-                .await
-                {
+                );
+                tokio::pin!(handling);
+                let result = loop {
+                    tokio::select! {
+                        biased;
+                        Some(notification) = notification_rx.recv() => {
+                            if !send_notification(&mut write, notification).await {
+                                break None;
+                            }
+                        }
+                        result = &mut handling => break Some(result),
+                    }
+                };
+                let Some(result) = result else {
+                    break;
+                };
+                let response = match result {
                     Ok(response) => response,
                     Err(e) => {
                         // Convert to structured API error
@@ -400,6 +405,26 @@ async fn handle_connection(
     tracing::info!("WebSocket connection closed");
 }
 
+/// Write a server-initiated notification, returning `false` if the connection failed
+async fn send_notification<S>(write: &mut S, notification: McpNotification) -> bool
+where
+    S: Sink<Message> + Unpin,
+    S::Error: std::fmt::Display,
+{
+    let text = match serde_json::to_string(&McpMessage::Notification(notification)) {
+        Ok(text) => text,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to serialize notification");
+            return true;
+        }
+    };
+    if let Err(e) = write.send(Message::Text(text.into())).await {
+        tracing::error!(error = %e, "Failed to send notification");
+        return false;
+    }
+    true
+}
+
 /// Handle a single MCP message
 async fn handle_message(
     session: &mut Session,
@@ -510,6 +535,7 @@ mod tests {
                 max_clients: Some(10),
                 timeout_ms: 30000,
                 tls: None,
+                allowed_origins: Vec::new(),
                 auth: if with_auth {
                     Some(AuthConfig {
                         jwt_secret: "test_secret".to_string(),
@@ -518,6 +544,9 @@ mod tests {
                         jwt_audience: "test".to_string(),
                         validate_audience: false,
                         jwt_audience_override: None,
+                        jwt_public_key_path: None,
+                        jwt_algorithm: None,
+                        jwks_path: None,
                     })
                 } else {
                     None
@@ -529,6 +558,8 @@ mod tests {
             cache: CacheConfig::default(),
             plugin_selection: Default::default(),
             git: Default::default(),
+            audit: Default::default(),
+            journal: Default::default(),
             validation: Default::default(),
             language_plugins: Default::default(),
             #[cfg(feature = "mcp-proxy")]
@@ -565,6 +596,73 @@ mod tests {
         }
     }
 
+    /// Sends a progress notification before answering each request
+    struct ProgressDispatcher;
+
+    #[async_trait::async_trait]
+    impl McpDispatcher for ProgressDispatcher {
+        async fn dispatch(
+            &self,
+            message: McpMessage,
+            session_info: &SessionInfo,
+        ) -> MillResult<McpMessage> {
+            let McpMessage::Request(request) = message else {
+                return Ok(message);
+            };
+            if let Some(notifications) = &session_info.notifications {
+                let _ = notifications.send(McpNotification {
+                    jsonrpc: "2.0".to_string(),
+                    method: "notifications/progress".to_string(),
+                    params: Some(json!({ "progressToken": 1, "progress": 1 })),
+                });
+            }
+            // Give the connection a chance to write the response first
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            Ok(McpMessage::Response(McpResponse {
+                jsonrpc: "2.0".to_string(),
+                id: request.id,
+                result: Some(json!({ "method": request.method })),
+                error: None,
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notifications_arrive_before_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            handle_connection(
+                stream,
+                Arc::new(create_test_config(false)),
+                Arc::new(ProgressDispatcher),
+            )
+            .await;
+        });
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .unwrap();
+
+        let request = |id: u64, method: &str| {
+            let request = json!({ "jsonrpc": "2.0", "id": id, "method": method });
+            Message::Text(request.to_string().into())
+        };
+        socket.send(request(1, "initialize")).await.unwrap();
+        socket.send(request(2, "tools/call")).await.unwrap();
+
+        let mut messages = Vec::new();
+        while messages.len() < 3 {
+            if let Message::Text(text) = socket.next().await.unwrap().unwrap() {
+                messages.push(serde_json::from_str::<serde_json::Value>(&text).unwrap());
+            }
+        }
+        assert_eq!(messages[0]["id"], 1);
+        assert_eq!(messages[1]["method"], "notifications/progress");
+        assert_eq!(messages[2]["id"], 2);
+        assert_eq!(messages[2]["result"]["method"], "tools/call");
+    }
+
     #[test]
     fn test_connection_guard_increments_on_creation() {
         let counter = Arc::new(std::sync::atomic::AtomicUsize::new(0));
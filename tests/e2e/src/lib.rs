@@ -95,6 +95,10 @@ pub mod test_workspace_update_members;
 #[cfg(test)]
 pub mod test_workspace_find_replace;
 
+// Workspace project verification tests
+#[cfg(test)]
+pub mod test_workspace_verify_project;
+
 // Tool coverage tests
 #[cfg(test)]
 pub mod test_tools_coverage;
@@ -112,6 +112,51 @@ async fn test_search_code_basics() {
     }
 }
 
+#[tokio::test]
+async fn test_search_code_without_lsp() {
+    let workspace = TestWorkspace::new();
+    workspace.create_file(".typemill/config.toml", "[lsp]\nmode = \"off\"\n");
+    workspace.create_file(
+        "src/config.rs",
+        "pub struct ConfigLoader;\n\npub fn load_config() {}\n",
+    );
+    workspace.create_file(
+        "web/settings.ts",
+        "export function loadConfigFile() { return 1; }\n",
+    );
+    let mut client = TestClient::new(workspace.path());
+
+    let result = client
+        .call_tool("search_code", json!({ "query": "config" }))
+        .await
+        .expect("search_code should succeed without LSP");
+    let names: Vec<&str> = result["result"]["results"]
+        .as_array()
+        .expect("results array")
+        .iter()
+        .filter_map(|s| s["name"].as_str())
+        .collect();
+    assert_eq!(names, vec!["ConfigLoader", "loadConfigFile", "load_config"]);
+
+    let result = client
+        .call_tool(
+            "search_code",
+            json!({ "query": "lcf", "kind": "function", "path": "web" }),
+        )
+        .await
+        .expect("search_code should succeed without LSP");
+    let results = result["result"]["results"]
+        .as_array()
+        .expect("results array");
+    assert_eq!(results.len(), 1, "Unexpected results: {:?}", results);
+    assert_eq!(results[0]["name"], "loadConfigFile");
+    assert_eq!(results[0]["kind"], 12);
+    assert!(results[0]["location"]["uri"]
+        .as_str()
+        .unwrap()
+        .ends_with("web/settings.ts"));
+}
+
 #[tokio::test]
 async fn test_rename_all_basics() {
     let workspace = TestWorkspace::new();
@@ -884,3 +884,84 @@ let z = user*2;
     assert!(modified.contains("account[0]"));
     assert!(modified.contains("account*2"));
 }
+
+// =====================================================================
+// 8. Structural Mode Tests
+// =====================================================================
+
+#[tokio::test]
+async fn test_structural_metavariables() {
+    let workspace = TestWorkspace::new();
+    let mut client = TestClient::new(workspace.path());
+
+    workspace.create_file(
+        "test.rs",
+        r#"fn load() -> Result<(), Error> {
+    let config = read_config(path).unwrap();
+    let value = config
+        .get("key")
+        .unwrap();
+    // comment mentioning x.unwrap()
+    let text = "s.unwrap()";
+    Ok(())
+}
+"#,
+    );
+
+    let result = client
+        .call_tool(
+            "workspace",
+            json!({
+                "action": "find_replace",
+                "params": {
+                    "pattern": "$EXPR.unwrap()",
+                    "replacement": "$EXPR?",
+                    "mode": "structural"
+                },
+                "options": {
+                    "dryRun": false
+                }
+            }),
+        )
+        .await
+        .expect("find_replace should succeed");
+
+    let content = assert_m7_success(&result);
+    assert_eq!(get_matches_replaced(content), 2);
+
+    let modified = workspace.read_file("test.rs");
+    assert!(modified.contains("let config = read_config(path)?;"));
+    assert!(modified.contains("let value = config\n        .get(\"key\")?;"));
+    assert!(modified.contains("// comment mentioning x.unwrap()"));
+    assert!(modified.contains("\"s.unwrap()\""));
+}
+
+#[tokio::test]
+async fn test_structural_unbound_replacement_metavariable() {
+    let workspace = TestWorkspace::new();
+    let mut client = TestClient::new(workspace.path());
+
+    workspace.create_file("test.rs", "fn f() { a.unwrap(); }\n");
+
+    let result = client
+        .call_tool(
+            "workspace",
+            json!({
+                "action": "find_replace",
+                "params": {
+                    "pattern": "$EXPR.unwrap()",
+                    "replacement": "$OTHER?",
+                    "mode": "structural"
+                },
+                "options": {
+                    "dryRun": false
+                }
+            }),
+        )
+        .await;
+
+    assert!(
+        result.is_err(),
+        "Replacement with an unbound metavariable should return error"
+    );
+}
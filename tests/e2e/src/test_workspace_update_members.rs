@@ -587,3 +587,143 @@ async fn test_path_normalization() {
     assert!(cargo_toml.contains("crates/my-crate"));
     assert!(!cargo_toml.contains("crates\\my-crate"));
 }
+
+/// Helper: Call update_members and return the response content
+async fn update_members(
+    client: &mut TestClient,
+    manifest_path: &std::path::Path,
+    action: &str,
+    members: &[&str],
+) -> serde_json::Value {
+    let result = client
+        .call_tool(
+            "workspace",
+            json!({
+                "action": "update_members",
+                "params": {
+                    "action": action,
+                    "workspaceManifest": manifest_path.to_string_lossy(),
+                    "members": members,
+                },
+                "options": {
+                    "dryRun": false
+                }
+            }),
+        )
+        .await
+        .expect("workspace.update_members should succeed");
+
+    result.get("result").expect("Result should exist").clone()
+}
+
+#[tokio::test]
+async fn test_package_json_workspaces() {
+    let workspace = TestWorkspace::new();
+    let mut client = TestClient::new(workspace.path());
+
+    workspace.create_file(
+        "package.json",
+        "{\n  \"name\": \"root\",\n  \"private\": true,\n  \"workspaces\": [\n    \"packages/a\",\n    \"packages/b\"\n  ]\n}\n",
+    );
+    let manifest_path = workspace.absolute_path("package.json");
+
+    let content = update_members(&mut client, &manifest_path, "add", &["tools/cli"]).await;
+    assert_eq!(content["result"]["changesMade"], 1);
+
+    let content = update_members(&mut client, &manifest_path, "remove", &["packages/a"]).await;
+    assert_eq!(content["result"]["changesMade"], 1);
+    assert_eq!(
+        content["result"]["membersAfter"],
+        json!(["packages/b", "tools/cli"])
+    );
+
+    assert_eq!(
+        workspace.read_file("package.json"),
+        "{\n  \"name\": \"root\",\n  \"private\": true,\n  \"workspaces\": [\n    \"packages/b\",\n    \"tools/cli\"\n  ]\n}\n"
+    );
+}
+
+#[tokio::test]
+async fn test_pnpm_workspace_yaml() {
+    let workspace = TestWorkspace::new();
+    let mut client = TestClient::new(workspace.path());
+
+    workspace.create_file("package.json", r#"{"name": "root", "private": true}"#);
+    workspace.create_file(
+        "pnpm-workspace.yaml",
+        "packages:\n  # Libraries\n  - \"packages/*\"\n",
+    );
+    let manifest_path = workspace.absolute_path("pnpm-workspace.yaml");
+
+    let content = update_members(&mut client, &manifest_path, "add", &["apps/web"]).await;
+    assert_eq!(content["result"]["changesMade"], 1);
+    assert_eq!(
+        workspace.read_file("pnpm-workspace.yaml"),
+        "packages:\n  # Libraries\n  - \"packages/*\"\n  - \"apps/web\"\n"
+    );
+
+    let content = update_members(&mut client, &manifest_path, "list", &[]).await;
+    assert_eq!(
+        content["result"]["membersAfter"],
+        json!(["packages/*", "apps/web"])
+    );
+}
+
+#[tokio::test]
+async fn test_uv_workspace_pyproject() {
+    let workspace = TestWorkspace::new();
+    let mut client = TestClient::new(workspace.path());
+
+    workspace.create_file(
+        "pyproject.toml",
+        "[project]\nname = \"root\"\n\n[tool.uv.workspace]\nmembers = [\n    \"libs/core\",  # shared code\n]\n",
+    );
+    let manifest_path = workspace.absolute_path("pyproject.toml");
+
+    let content = update_members(&mut client, &manifest_path, "add", &["libs/api"]).await;
+    assert_eq!(content["result"]["changesMade"], 1);
+    assert_eq!(
+        workspace.read_file("pyproject.toml"),
+        "[project]\nname = \"root\"\n\n[tool.uv.workspace]\nmembers = [\n    \"libs/core\",  # shared code\n    \"libs/api\",\n]\n"
+    );
+}
+
+#[tokio::test]
+async fn test_glob_members() {
+    let workspace = TestWorkspace::new();
+    let mut client = TestClient::new(workspace.path());
+
+    let manifest_path = setup_workspace(&workspace, &["crates/*"]);
+    for name in ["alpha", "beta"] {
+        workspace.create_file(
+            &format!("crates/{}/Cargo.toml", name),
+            &format!(
+                "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+                name
+            ),
+        );
+    }
+    let original = workspace.read_file("Cargo.toml");
+
+    // Already covered by the glob: nothing to add
+    let content = update_members(&mut client, &manifest_path, "add", &["crates/alpha"]).await;
+    assert_eq!(content["result"]["changesMade"], 0);
+    assert_eq!(content["diagnostics"][0]["severity"], "info");
+
+    // Only covered by the glob: cannot be removed by editing the list
+    let content = update_members(&mut client, &manifest_path, "remove", &["crates/beta"]).await;
+    assert_eq!(content["result"]["changesMade"], 0);
+    assert_eq!(content["diagnostics"][0]["severity"], "warning");
+    assert_eq!(workspace.read_file("Cargo.toml"), original);
+
+    let content = update_members(&mut client, &manifest_path, "list", &[]).await;
+    assert_eq!(
+        content["result"]["resolvedMembers"],
+        json!(["crates/alpha", "crates/beta"])
+    );
+
+    // The pattern itself can be removed
+    let content = update_members(&mut client, &manifest_path, "remove", &["crates/*"]).await;
+    assert_eq!(content["result"]["changesMade"], 1);
+    assert_eq!(content["result"]["membersAfter"], json!([]));
+}
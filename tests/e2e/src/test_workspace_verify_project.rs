@@ -0,0 +1,150 @@
+//! workspace verify_project tests
+//!
+//! Tests the project health check: unresolved imports, workspace members,
+//! path dependencies and import cycles.
+
+use crate::harness::{TestClient, TestWorkspace};
+use serde_json::json;
+
+async fn verify(workspace: &TestWorkspace) -> serde_json::Value {
+    let mut client = TestClient::new(workspace.path());
+    let result = client
+        .call_tool("workspace", json!({ "action": "verify_project" }))
+        .await
+        .expect("verify_project should succeed");
+    result.get("result").expect("Should have result").clone()
+}
+
+fn check_count(content: &serde_json::Value, name: &str) -> u64 {
+    content["changes"]["checks"][name]
+        .as_u64()
+        .unwrap_or_else(|| panic!("Missing check counter '{}': {:?}", name, content))
+}
+
+fn messages(content: &serde_json::Value) -> Vec<String> {
+    content["diagnostics"]
+        .as_array()
+        .map(|diagnostics| {
+            diagnostics
+                .iter()
+                .filter_map(|d| d["message"].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn test_verify_clean_project() {
+    let workspace = TestWorkspace::new();
+    workspace.create_file("src/index.ts", "import { format } from './format';\n");
+    workspace.create_file(
+        "src/format.ts",
+        "export function format(s: string) { return s; }\n",
+    );
+
+    let content = verify(&workspace).await;
+
+    assert_eq!(content["status"], "success", "{:?}", content);
+    assert_eq!(check_count(&content, "filesChecked"), 2);
+    assert_eq!(check_count(&content, "unresolvedImports"), 0);
+    assert_eq!(check_count(&content, "importCycles"), 0);
+}
+
+#[tokio::test]
+async fn test_verify_reports_broken_imports_and_cycles() {
+    let workspace = TestWorkspace::new();
+    workspace.create_file(
+        "src/index.ts",
+        "import { a } from './a';\nimport { missing } from './missing';\n",
+    );
+    workspace.create_file(
+        "src/a.ts",
+        "import { b } from './b';\nexport const a = 1;\n",
+    );
+    workspace.create_file(
+        "src/b.ts",
+        "import { a } from './a';\nexport const b = 2;\n",
+    );
+
+    let content = verify(&workspace).await;
+
+    assert_eq!(content["status"], "error", "{:?}", content);
+    assert_eq!(check_count(&content, "unresolvedImports"), 1);
+    assert_eq!(check_count(&content, "importCycles"), 1);
+
+    let diagnostics = content["diagnostics"].as_array().unwrap();
+    let unresolved = diagnostics
+        .iter()
+        .find(|d| d["message"] == "Unresolved import './missing'")
+        .unwrap_or_else(|| panic!("Missing unresolved import: {:?}", diagnostics));
+    assert_eq!(unresolved["severity"], "error");
+    assert_eq!(unresolved["filePath"], "src/index.ts");
+    assert_eq!(unresolved["line"], 2);
+
+    assert!(
+        messages(&content)
+            .iter()
+            .any(|m| m.starts_with("Import cycle:")
+                && m.contains("src/a.ts")
+                && m.contains("src/b.ts")),
+        "{:?}",
+        diagnostics
+    );
+}
+
+#[tokio::test]
+async fn test_verify_reports_cargo_workspace_problems() {
+    let workspace = TestWorkspace::new();
+    workspace.create_file(
+        "Cargo.toml",
+        r#"[workspace]
+members = ["crates/app", "crates/gone"]
+resolver = "2"
+"#,
+    );
+    workspace.create_file(
+        "crates/app/Cargo.toml",
+        r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+helper = { path = "../helper" }
+"#,
+    );
+    workspace.create_file("crates/app/src/lib.rs", "pub fn app() {}\n");
+    workspace.create_file(
+        "crates/stray/Cargo.toml",
+        r#"[package]
+name = "stray"
+version = "0.1.0"
+edition = "2021"
+"#,
+    );
+    workspace.create_file("crates/stray/src/lib.rs", "pub fn stray() {}\n");
+
+    let content = verify(&workspace).await;
+
+    assert_eq!(content["status"], "error", "{:?}", content);
+    assert_eq!(check_count(&content, "missingMembers"), 1);
+    assert_eq!(check_count(&content, "undeclaredMembers"), 1);
+    assert_eq!(check_count(&content, "brokenPathDependencies"), 1);
+
+    let messages = messages(&content);
+    assert!(
+        messages.iter().any(|m| m.contains("'crates/gone'")),
+        "{:?}",
+        messages
+    );
+    assert!(
+        messages.iter().any(|m| m.contains("'crates/stray'")),
+        "{:?}",
+        messages
+    );
+    assert!(
+        messages.iter().any(|m| m.contains("helper")),
+        "{:?}",
+        messages
+    );
+}